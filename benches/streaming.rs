@@ -1,8 +1,12 @@
 use criterion::{criterion_group, criterion_main, Criterion, black_box};
 
+use rktri::streaming::MemoryBudget;
 use rktri::voxel::streaming::{
     PrefetchPredictor,
     FeedbackBuffer,
+    CameraPath,
+    ReplayConfig,
+    StreamingReplay,
 };
 use rktri::voxel::brick_handle::BrickId;
 use rktri::voxel::chunk::ChunkCoord;
@@ -191,6 +195,24 @@ fn bench_feedback_raw_feedback_processing(c: &mut Criterion) {
     });
 }
 
+fn bench_streaming_replay(c: &mut Criterion) {
+    // 2 s flyover at 20 m/s with a budget too small for the view range
+    let path = CameraPath::flyover(Vec3::new(0.0, 6.0, 0.0), Vec3::new(20.0, 0.0, 0.0), 120, 1.0 / 60.0);
+    let config = ReplayConfig { view_distance: 64.0, ..Default::default() };
+    let run = || StreamingReplay::new(config.clone(), MemoryBudget::new(1024, 64)).run(&path);
+
+    // Regression numbers: the replay is deterministic, so these only change with the code
+    let s = run().summary;
+    eprintln!(
+        "streaming_replay: {} loaded, time to resident mean {:.2} / p95 {} frames, {} pop-ins, {} evictions, {} budget violations",
+        s.chunks_loaded, s.mean_time_to_resident, s.p95_time_to_resident, s.pop_ins, s.evictions, s.budget_violations,
+    );
+
+    c.bench_function("streaming_replay_flyover", |b| {
+        b.iter(|| black_box(run()));
+    });
+}
+
 criterion_group!(
     benches,
     bench_octree_build_32,
@@ -204,5 +226,6 @@ criterion_group!(
     bench_feedback_priority_sort,
     bench_feedback_pack_unpack_chunk,
     bench_feedback_raw_feedback_processing,
+    bench_streaming_replay,
 );
 criterion_main!(benches);
@@ -95,7 +95,8 @@ struct GrassParams {
     wind_direction: vec3<f32>,
     wind_speed: f32,
     profile_count: u32,
-    _pad0: f32,
+    // Material ID of grass hits (the registry's "grass_blade")
+    material_id: u32,
     _pad1: f32,
     _pad2: f32,
 }
@@ -136,6 +137,31 @@ struct GrassMaskNode {
 @group(0) @binding(2) var<uniform> grass: GrassParams;
 @group(0) @binding(3) var<storage, read> grass_profiles: array<GrassProfileGpu>;
 
+// Material table indexed by voxel material ID (see voxel::material::GpuMaterial)
+struct GpuMaterial {
+    albedo: vec3<f32>,
+    roughness: f32,
+    emissive: vec3<f32>,
+    metallic: f32,
+    transparency: f32,
+    flags: u32,
+    _pad0: u32,
+    _pad1: u32,
+}
+
+// Must match MaterialFlag::bit
+const MAT_FLAG_TERRAIN: u32 = 1u;
+const MAT_FLAG_BARK: u32 = 2u;
+const MAT_FLAG_FOLIAGE: u32 = 4u;
+const MAT_FLAG_SDF_NORMAL: u32 = 8u;
+const MAT_FLAG_GRASS_BLADE: u32 = 32u;
+
+@group(0) @binding(4) var<storage, read> materials: array<GpuMaterial, 256>;
+
+fn has_material_flag(material_id: u32, flag: u32) -> bool {
+    return (materials[material_id & 0xFFu].flags & flag) != 0u;
+}
+
 @group(1) @binding(0) var<storage, read> nodes: array<OctreeNode>;
 @group(1) @binding(1) var<storage, read> bricks: array<VoxelBrick>;
 
@@ -271,28 +297,8 @@ fn coverage_normal(filled_mask: u32) -> vec3<f32> {
 // Material PBR properties lookup
 // Returns vec3(roughness, metallic, translucency)
 fn get_material_properties(material_id: u32) -> vec3<f32> {
-    switch material_id {
-        // Core material types (non-terrain objects like trees)
-        case 1u: { return vec3<f32>(0.9, 0.0, 0.0); }   // Sand: very rough
-        case 2u: { return vec3<f32>(0.85, 0.0, 0.0); }  // Bark: rough, matte
-        case 3u: { return vec3<f32>(0.55, 0.0, 0.8); }  // Leaves: slight sheen, translucent
-        case 4u: { return vec3<f32>(0.95, 0.0, 0.0); }  // Stone: very rough
-        case 5u: { return vec3<f32>(0.8, 0.1, 0.0); }   // Snow: rough, slight metallic
-        case 6u: { return vec3<f32>(0.9, 0.0, 0.0); }   // Dirt: very rough
-        // Biome terrain surfaces (inherit PBR from base type)
-        case 7u:  { return vec3<f32>(0.9, 0.0, 0.0); }  // Beach sand
-        case 8u:  { return vec3<f32>(0.9, 0.0, 0.0); }  // Desert sand
-        case 9u:  { return vec3<f32>(0.85, 0.0, 0.0); } // Grassland
-        case 10u: { return vec3<f32>(0.85, 0.0, 0.0); } // Forest
-        case 11u: { return vec3<f32>(0.85, 0.0, 0.0); } // Taiga
-        case 12u: { return vec3<f32>(0.9, 0.0, 0.0); }  // Tundra
-        case 13u: { return vec3<f32>(0.95, 0.0, 0.0); } // Mountains
-        case 14u: { return vec3<f32>(0.8, 0.1, 0.0); }  // Snow terrain
-        case 15u: { return vec3<f32>(0.95, 0.0, 0.0); } // Ocean floor
-        case 16u: { return vec3<f32>(0.9, 0.0, 0.3); }  // Grass blade: rough, slight translucency
-        case 20u: { return vec3<f32>(0.95, 0.0, 0.0); }  // Rock: very rough, non-metallic
-        default: { return vec3<f32>(0.5, 0.0, 0.0); }   // Default
-    }
+    let mat = materials[material_id & 0xFFu];
+    return vec3<f32>(mat.roughness, mat.metallic, mat.transparency);
 }
 
 // Get voxel flags (sub-voxel height fraction for terrain)
@@ -344,23 +350,10 @@ fn terrain_color_variation(world_pos: vec3<f32>, material_id: u32) -> vec3<f32>
     return clamp(base * brightness + warm, vec3<f32>(0.0), vec3<f32>(1.0));
 }
 
-// Base color for terrain biome surfaces (IDs 4, 7-15)
-// Color field stores gradient, so visual color comes from this LUT
+// Base color for terrain surfaces
+// Color field stores gradient, so visual color comes from the material albedo
 fn terrain_base_color(material_id: u32) -> vec3<f32> {
-    switch material_id {
-        case 4u:  { return vec3<f32>(0.35, 0.33, 0.32); } // Stone - dark grey-brown
-        case 20u: { return vec3<f32>(0.35, 0.33, 0.32); } // Rock - same as stone for now
-        case 7u:  { return vec3<f32>(0.93, 0.84, 0.69); } // Beach sand
-        case 8u:  { return vec3<f32>(0.93, 0.79, 0.69); } // Desert sand
-        case 9u:  { return vec3<f32>(0.39, 0.71, 0.31); } // Grassland
-        case 10u: { return vec3<f32>(0.20, 0.47, 0.16); } // Forest
-        case 11u: { return vec3<f32>(0.31, 0.39, 0.24); } // Taiga
-        case 12u: { return vec3<f32>(0.63, 0.71, 0.67); } // Tundra
-        case 13u: { return vec3<f32>(0.47, 0.47, 0.47); } // Mountains
-        case 14u: { return vec3<f32>(0.94, 0.97, 1.00); } // Snow
-        case 15u: { return vec3<f32>(0.12, 0.31, 0.59); } // Ocean floor
-        default:  { return vec3<f32>(0.50, 0.50, 0.50); }
-    }
+    return materials[material_id & 0xFFu].albedo;
 }
 
 // Procedural bark color variation — vertical streaks + moss patches
@@ -423,14 +416,14 @@ fn tree_wind_displacement(world_pos: vec3<f32>, material_id: u32, chunk_min_y: f
 
     // Branch oscillation (medium freq, bark only)
     var branch = vec2<f32>(0.0);
-    if (material_id == 2u) {
+    if (has_material_flag(material_id, MAT_FLAG_BARK)) {
         let bp = grass.time * 2.5 + hash31(world_pos * 0.5) * 6.28;
         branch = vec2<f32>(sin(bp), cos(bp * 0.8)) * 0.015 * hfrac;
     }
 
     // Leaf flutter (fast, leaves only)
     var flutter = vec2<f32>(0.0);
-    if (material_id == 3u) {
+    if (has_material_flag(material_id, MAT_FLAG_FOLIAGE)) {
         let fp = grass.time * 6.0 + hash31(world_pos * 2.0) * 6.28;
         flutter = vec2<f32>(sin(fp), cos(fp * 1.3)) * 0.025 * hfrac;
     }
@@ -551,7 +544,9 @@ fn trace_brick(
                 var voxel_min = brick_min + voxel_offset;
                 var voxel_max = voxel_min + vec3<f32>(voxel_size);
 
-                let is_tree = material_id == 2u || material_id == 3u;
+                let is_bark = has_material_flag(material_id, MAT_FLAG_BARK);
+                let is_foliage = has_material_flag(material_id, MAT_FLAG_FOLIAGE);
+                let is_tree = is_bark || is_foliage;
 
                 // Wind displacement for tree materials
                 if (is_tree) {
@@ -610,10 +605,10 @@ fn trace_brick(
                         // Leaves: spherical normal (cloud center → voxel)
                         // Both encoded in the color field by the generator.
 
-                        if (material_id == 2u && flags > 0u) {
+                        if (is_bark && flags > 0u) {
                             // Bark: precise radial normal from generator
                             final_normal = decode_bark_normal(voxel);
-                        } else if (material_id == 3u) {
+                        } else if (is_foliage) {
                             // Leaves: soft edge culling + precise spherical normal
                             if (flags > 0u) {
                                 let sdf_frac = f32(flags) / 255.0;
@@ -625,7 +620,7 @@ fn trace_brick(
                                 // Decode cloud-center-to-point normal from color field
                                 final_normal = decode_bark_normal(voxel);
                             }
-                        } else if (material_id == 20u && flags > 0u) {
+                        } else if (has_material_flag(material_id, MAT_FLAG_SDF_NORMAL) && flags > 0u) {
                             // Rock: precise gradient normal from generator (SDF-based)
                             final_normal = decode_rock_normal(voxel);
                         } else {
@@ -656,13 +651,13 @@ fn trace_brick(
                         // Color: terrain procedural, bark procedural, leaf procedural, else voxel data
                         if (is_terrain) {
                             result.color = terrain_color_variation(world_hit, material_id);
-                        } else if (material_id == 2u) {
-                            // Bark color field stores encoded normal, not color — use fixed base
-                            let bark_base = vec3<f32>(0.35, 0.25, 0.15);
+                        } else if (is_bark) {
+                            // Bark color field stores encoded normal, not color — use material albedo
+                            let bark_base = materials[material_id & 0xFFu].albedo;
                             result.color = bark_color_variation(world_hit, bark_base);
-                        } else if (material_id == 3u) {
-                            // Leaf color field stores encoded normal, not color — use fixed base
-                            let leaf_base = vec3<f32>(0.20, 0.35, 0.08);
+                        } else if (is_foliage) {
+                            // Leaf color field stores encoded normal, not color — use material albedo
+                            let leaf_base = materials[material_id & 0xFFu].albedo;
                             result.color = leaf_color_variation(world_hit, leaf_base);
                         } else {
                             result.color = unpack_voxel_color(voxel);
@@ -905,8 +900,8 @@ fn trace_chunk_octree(
                         }
                         result.material_id = lod_mat;
 
-                        // Terrain biome surfaces (7-15): procedural color + up normal
-                        let is_lod_terrain = lod_mat >= 7u && lod_mat <= 15u;
+                        // Terrain biome surfaces: procedural color + up normal
+                        let is_lod_terrain = has_material_flag(lod_mat, MAT_FLAG_TERRAIN);
                         if (is_lod_terrain) {
                             let world_hit = ray_origin + ray_dir * t;
                             result.color = terrain_color_variation(world_hit, lod_mat);
@@ -1205,7 +1200,7 @@ fn trace_grass_volumetric(
         let understory = terrain_color * 0.15;  // dark shadow beneath grass canopy
         result.color = accum_color + understory * (1.0 - accum_opacity);
         result.normal = normalize(mix(gn, first_grass_normal, min(accum_opacity * 2.0, 1.0)));
-        result.material_id = 16u;
+        result.material_id = grass.material_id;
         result.roughness = 0.85;
         result.translucency = 0.3 * accum_opacity;
     }
@@ -1426,7 +1421,7 @@ fn test_grass_blade_curved(
         result.t = best_t;
         result.color = mix(terrain_color, best_color, best_coverage);
         result.normal = normalize(mix(gn, best_normal, best_coverage));
-        result.material_id = 16u;
+        result.material_id = grass.material_id;
         result.roughness = 0.9;
         result.translucency = 0.3;
     }
@@ -1606,7 +1601,7 @@ fn test_grass_blade(
             nz * face_sign + tip_dz * height_frac * 0.3
         ));
 
-        result.material_id = 16u;
+        result.material_id = grass.material_id;
         result.roughness = 0.9;
         result.translucency = 0.3;
     }
@@ -1892,8 +1887,9 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
             let profile_idx = grass_cell & 0xFFu;
             let mask_density = f32((grass_cell >> 8u) & 0xFFu) / 255.0;
 
-            // Skip grass on rocks (material 20)
-            if (profile_idx > 0u && profile_idx < grass.profile_count && mask_density > 0.01 && hit.material_id != 20u) {
+            // Skip grass on rocks
+            let on_rock = has_material_flag(hit.material_id, MAT_FLAG_SDF_NORMAL);
+            if (profile_idx > 0u && profile_idx < grass.profile_count && mask_density > 0.01 && !on_rock) {
                 let slope_factor = smoothstep(0.15, 0.5, abs(hit.normal.y));
                 let profile = grass_profiles[profile_idx];
 
@@ -1918,8 +1914,9 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
                 // Ground tint: only for terrain pixels (not volumetric grass, not rocks).
                 // Tints bare terrain in grassy areas for smooth distance transition.
-                // Exclude rocks (material 20) - grass shouldn't grow on rocks.
-                if (hit.material_id != 16u && hit.material_id != 20u) {
+                // Exclude rocks - grass shouldn't grow on rocks.
+                let is_grass = has_material_flag(hit.material_id, MAT_FLAG_GRASS_BLADE);
+                if (!is_grass && !has_material_flag(hit.material_id, MAT_FLAG_SDF_NORMAL)) {
                     let tint_fade = 1.0 - smoothstep(grass.fade_start, grass_tint_range, hit.t);
                     let tint_strength = slope_factor * mask_density * tint_fade * 0.4;
                     if (tint_strength > 0.01) {
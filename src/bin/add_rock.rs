@@ -13,6 +13,7 @@ use rktri::voxel::rock_library::{RockGenerator, RockParams};
 use rktri::voxel::svo::adaptive::AdaptiveOctreeBuilder;
 use rktri::voxel::tree_merge::{TreeInstance, MultiTreeClassifier};
 use rktri::streaming::disk_io;
+use rktri::streaming::RegionStore;
 
 use std::path::PathBuf;
 
@@ -93,6 +94,7 @@ fn main() {
         biome_mask_depth: 3,
         grass_mask_depth: 5,
         clutter_mask_depth: 4,
+        ..Default::default()
     };
     let pipeline = GenerationPipeline::new(&config);
 
@@ -168,6 +170,7 @@ fn main() {
         min_cx, max_cx, min_cy, max_cy, min_cz, max_cz);
 
     let terrain_dir = world_dir.join("terrain");
+    let terrain_store = RegionStore::new(&terrain_dir);
     let mut chunks_written = 0;
     let mut new_chunks = Vec::new();
 
@@ -226,10 +229,9 @@ fn main() {
                 let compressed = disk_io::compress_chunk(&disk_chunk)
                     .expect("Failed to compress chunk");
 
-                let chunk_file = terrain_dir.join(format!("chunk_{}_{}_{}.rkc", cx, cy, cz));
-                let is_new = !chunk_file.exists();
-                std::fs::write(&chunk_file, &compressed)
-                    .expect("Failed to write chunk file");
+                let is_new = !disk_io::stored_chunk_exists(&terrain_store, disk_coord, disk_io::CHUNK_EXTENSION);
+                disk_io::write_stored_chunk(&terrain_store, disk_coord, disk_io::CHUNK_EXTENSION, &compressed)
+                    .expect("Failed to write chunk");
 
                 if is_new {
                     new_chunks.push((cx, cy, cz));
@@ -0,0 +1,165 @@
+//! Bake an edit log into a world's chunk files.
+//!
+//! Usage: cargo run --release --bin bake_edits -- --world <name> [OPTIONS]
+//!
+//! Options:
+//!   --world <NAME>    World directory under assets/worlds (required)
+//!   --log <PATH>      Edit log to bake (default: assets/worlds/<name>/edits.rked)
+//!   --layer <NAME>    Layer the edits apply to (default: "terrain")
+//!
+//! Applied edits are removed from the log and the layer's chunk list in
+//! manifest.json is updated for chunks the edits created or emptied. Layers
+//! generated with baked occlusion get it rebaked around the edited chunks, and
+//! the far-field proxies of super chunks built from the layer are rebuilt.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde_json::{json, Value};
+
+use rktri::streaming::disk_io;
+use rktri::streaming::region::RegionStore;
+use rktri::voxel::chunk::ChunkCoord;
+use rktri::voxel::edit::{bake_edits, rebake_occlusion, ChunkInvalidator, EditLog};
+use rktri::voxel::super_chunk::{ProxyBuilder, SuperChunkCoord};
+use rktri::voxel::svo::{OcclusionBaker, SvdagBuilder};
+
+fn main() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info"),
+    )
+    .format_timestamp_millis()
+    .init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let world_name = parse_str_arg(&args, "--world")
+        .expect("Usage: bake_edits --world <name> [--log <path>] [--layer <name>]");
+    let layer_name = parse_str_arg(&args, "--layer").unwrap_or_else(|| "terrain".to_string());
+
+    let world_dir = PathBuf::from(format!("assets/worlds/{}", world_name));
+    let log_path = parse_str_arg(&args, "--log")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| world_dir.join("edits.rked"));
+    let manifest_path = world_dir.join("manifest.json");
+
+    let mut manifest: Value = serde_json::from_str(
+        &std::fs::read_to_string(&manifest_path).expect("Failed to read manifest")
+    ).expect("Failed to parse manifest");
+
+    let layer = manifest["layers"].as_array()
+        .and_then(|layers| layers.iter().find(|l| l["name"].as_str() == Some(layer_name.as_str())))
+        .unwrap_or_else(|| panic!("Layer '{}' not found in manifest", layer_name));
+    let layer_dir = world_dir.join(layer["directory"].as_str().unwrap_or(&layer_name));
+    let occlusion = layer["occlusion"].as_bool().unwrap_or(false);
+
+    if !log_path.exists() {
+        println!("No edit log at {}, nothing to bake", log_path.display());
+        return;
+    }
+    let mut edit_log = EditLog::load(&log_path).expect("Failed to read edit log");
+
+    println!("=== Baking edits into '{}' ===", world_name);
+    println!("Log:   {} ({} edits)", log_path.display(), edit_log.edit_count());
+    println!("Layer: {} ({})", layer_name, layer_dir.display());
+
+    let start = Instant::now();
+    let report = bake_edits(&mut edit_log, &layer_dir).expect("Failed to bake edits");
+
+    println!();
+    println!("Baked {} edits in {:.1}s", report.edits_applied, start.elapsed().as_secs_f64());
+    println!("  {} chunks updated, {} created, {} removed",
+        report.updated.len(), report.created.len(), report.removed.len());
+    if edit_log.edit_count() > 0 {
+        println!("  {} edits left in the log (they touch chunks that failed to load)", edit_log.edit_count());
+    }
+
+    if occlusion {
+        let mut invalidator = ChunkInvalidator::new();
+        for &coord in report.updated.iter().chain(&report.created).chain(&report.removed) {
+            invalidator.mark_chunk_dirty(coord);
+        }
+        let start = Instant::now();
+        let rebaked = rebake_occlusion(&invalidator, &layer_dir, &OcclusionBaker::new())
+            .expect("Failed to rebake occlusion");
+        println!("  {} chunks rebaked for occlusion in {:.1}s", rebaked.len(), start.elapsed().as_secs_f64());
+    }
+
+    let proxies = &manifest["proxies"];
+    if proxies["source_layer"].as_str() == Some(layer_name.as_str()) {
+        let touched: BTreeSet<(i32, i32, i32)> = report.updated.iter()
+            .chain(&report.created)
+            .chain(&report.removed)
+            .map(|&c| {
+                let coord = SuperChunkCoord::from_chunk(c);
+                (coord.x, coord.y, coord.z)
+            })
+            .collect();
+        let proxy_store = RegionStore::new(world_dir.join(proxies["directory"].as_str().unwrap_or("proxies")));
+        let start = Instant::now();
+        rebuild_proxies(&layer_dir, &proxy_store, &touched);
+        println!("  {} super chunk proxies rebuilt in {:.1}s", touched.len(), start.elapsed().as_secs_f64());
+    }
+
+    if report.created.is_empty() && report.removed.is_empty() {
+        return;
+    }
+
+    // Keep the manifest's chunk list in sync with the layer directory
+    let layer = manifest["layers"].as_array_mut().unwrap()
+        .iter_mut()
+        .find(|l| l["name"].as_str() == Some(layer_name.as_str()))
+        .unwrap();
+    let mut chunks: Vec<ChunkCoord> = layer["chunks"].as_array()
+        .map(|list| disk_io::parse_manifest_chunk_list(list))
+        .unwrap_or_default();
+    chunks.retain(|c| !report.removed.contains(c));
+    chunks.extend(report.created.iter().copied());
+
+    layer["chunk_count"] = json!(chunks.len());
+    layer["chunks"] = Value::Array(disk_io::manifest_chunk_list(&chunks));
+
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest).unwrap())
+        .expect("Failed to write manifest");
+    println!("Updated manifest: {}", manifest_path.display());
+}
+
+/// Rebuild the far-field proxies of super chunks from the layer's chunks.
+/// Super chunks left without solid voxels lose their proxy.
+fn rebuild_proxies(layer_dir: &Path, proxy_store: &RegionStore, touched: &BTreeSet<(i32, i32, i32)>) {
+    let store = RegionStore::new(layer_dir);
+    let dictionary = disk_io::load_layer_dictionary(&store).expect("Failed to read shared dictionary");
+    let chunks = disk_io::stored_chunk_coords(&store, disk_io::CHUNK_EXTENSION)
+        .expect("Failed to list layer chunks");
+
+    for &(x, y, z) in touched {
+        let coord = SuperChunkCoord::new(x, y, z);
+        let mut builder = ProxyBuilder::new(coord);
+        for &chunk in &chunks {
+            if SuperChunkCoord::from_chunk(chunk) != coord {
+                continue;
+            }
+            let stored = disk_io::load_stored_chunk_resolved(&store, chunk, dictionary.as_ref())
+                .expect("Failed to read chunk");
+            if let Some(stored) = stored {
+                builder.add_chunk(chunk, stored.octree());
+            }
+        }
+
+        match builder.build() {
+            Some(proxy) => {
+                let proxy = SvdagBuilder::new().build_full(&proxy);
+                disk_io::save_super_chunk_proxy(proxy_store, coord, &proxy).expect("Failed to write proxy");
+            }
+            None => {
+                disk_io::remove_super_chunk_proxy(proxy_store, coord).expect("Failed to remove proxy");
+            }
+        }
+    }
+}
+
+fn parse_str_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
@@ -0,0 +1,304 @@
+//! Export voxel chunks as a triangle mesh (OBJ or binary glTF).
+//!
+//! Usage:
+//!   cargo run --release --bin export_mesh -- --chunk <FILE.rkc> --out rock.obj
+//!   cargo run --release --bin export_mesh -- --world <NAME> [--region X,Y,Z] --out area.glb
+//!
+//! Options:
+//!   --chunk <FILE>      Single chunk file, meshed in its local space
+//!   --world <NAME>      World directory under assets/worlds
+//!   --layer <NAME>      Layer to export (default: "terrain")
+//!   --region <X,Y,Z>    Only chunks in this region file (default: whole layer)
+//!   --mode <MODE>       greedy (default) or nets (surface nets)
+//!   --out <FILE>        Output path; the extension picks the format:
+//!                       .obj (plus a .mtl alongside) or .glb
+//!
+//! World chunks are meshed one at a time in world space, each reading its
+//! neighbours' border voxels so the pieces join without seams or duplicate
+//! faces. OBJ output is streamed chunk by chunk; GLB is written at the end.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use glam::{UVec3, Vec3};
+use serde_json::Value;
+
+use rktri::math::Aabb;
+use rktri::streaming::disk_io::{self, AnyChunk, ChunkCoord};
+use rktri::streaming::region::region_of;
+use rktri::streaming::RegionStore;
+use rktri::voxel::chunk::CHUNK_SIZE;
+use rktri::voxel::material::{self, MaterialRegistry};
+use rktri::voxel::mesh::{self, Mesh, MeshMode, ObjWriter};
+use rktri::voxel::svo::classifier::{RegionClassifier, RegionHint};
+use rktri::voxel::svo::{Octree, SubtreeDictionary};
+use rktri::voxel::super_chunk::SuperChunkCoord;
+use rktri::voxel::voxel::Voxel;
+
+const USAGE: &str = "Usage: export_mesh (--chunk <file.rkc> | --world <name> [--layer <name>] [--region x,y,z]) \
+                     [--mode greedy|nets] --out <file.obj|file.glb>";
+
+/// Where meshes go: streamed OBJ or one accumulated GLB
+enum Output {
+    Obj { writer: ObjWriter<BufWriter<File>>, mtl_path: PathBuf },
+    Glb { mesh: Mesh, path: PathBuf },
+}
+
+impl Output {
+    fn create(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("obj") => {
+                let mtl_path = path.with_extension("mtl");
+                let mtl_name = mtl_path.file_name().unwrap().to_string_lossy().into_owned();
+                let file = File::create(path).expect("Failed to create output file");
+                let writer = ObjWriter::new(BufWriter::new(file), Some(&mtl_name))
+                    .expect("Failed to write OBJ header");
+                Output::Obj { writer, mtl_path }
+            }
+            Some("glb") => Output::Glb { mesh: Mesh::default(), path: path.to_path_buf() },
+            _ => panic!("Output must end in .obj or .glb\n{}", USAGE),
+        }
+    }
+
+    fn add(&mut self, name: &str, mesh: &Mesh) {
+        match self {
+            Output::Obj { writer, .. } => writer.write_mesh(name, mesh).expect("Failed to write OBJ"),
+            Output::Glb { mesh: all, .. } => all.append(mesh),
+        }
+    }
+
+    fn finish(self) {
+        match self {
+            Output::Obj { writer, mtl_path } => {
+                let (_, materials) = writer.finish().expect("Failed to write OBJ");
+                let mtl = File::create(&mtl_path).expect("Failed to create MTL file");
+                mesh::write_mtl(BufWriter::new(mtl), &materials).expect("Failed to write MTL");
+                println!("Materials: {} ({})", mtl_path.display(), materials.len());
+            }
+            Output::Glb { mesh, path } => {
+                let file = File::create(&path).expect("Failed to create output file");
+                mesh::write_glb(&mesh, BufWriter::new(file)).expect("Failed to write GLB");
+            }
+        }
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info"),
+    )
+    .format_timestamp_millis()
+    .init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let out_path = PathBuf::from(parse_str_arg(&args, "--out").expect(USAGE));
+    let mode = parse_str_arg(&args, "--mode")
+        .map(|m| MeshMode::from_name(&m).unwrap_or_else(|| panic!("Unknown mode '{}'\n{}", m, USAGE)))
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let mut output = Output::create(&out_path);
+    let (chunks, triangles) = if let Some(chunk_path) = parse_str_arg(&args, "--chunk") {
+        export_chunk_file(Path::new(&chunk_path), mode, &mut output)
+    } else if let Some(world_name) = parse_str_arg(&args, "--world") {
+        let layer_name = parse_str_arg(&args, "--layer").unwrap_or_else(|| "terrain".to_string());
+        let region = parse_str_arg(&args, "--region").map(|r| parse_region(&r));
+        export_world(&world_name, &layer_name, region, mode, &mut output)
+    } else {
+        panic!("{}", USAGE);
+    };
+    output.finish();
+
+    println!(
+        "Exported {} chunks, {} triangles ({:?}) to {} in {:.1}s",
+        chunks, triangles, mode, out_path.display(), start.elapsed().as_secs_f32()
+    );
+}
+
+/// Mesh a single chunk file in the octree's local space
+fn export_chunk_file(path: &Path, mode: MeshMode, output: &mut Output) -> (usize, usize) {
+    let data = std::fs::read(path).expect("Failed to read chunk file");
+    let octree = match disk_io::decode_any_chunk(&data).expect("Failed to decode chunk") {
+        AnyChunk::Octree(c) | AnyChunk::Svdag(c) | AnyChunk::Attributed(c) => c.octree,
+        AnyChunk::GrassMask(..) => panic!("{} is a grass mask, not a voxel chunk", path.display()),
+    };
+
+    let mesh = mesh::mesh_octree(&octree, mode);
+    let name = path.file_stem().map_or("chunk".into(), |s| s.to_string_lossy());
+    output.add(&name, &mesh);
+    (1, mesh.triangle_count())
+}
+
+/// Mesh every chunk of a world layer, optionally limited to one region
+fn export_world(
+    world_name: &str,
+    layer_name: &str,
+    region: Option<SuperChunkCoord>,
+    mode: MeshMode,
+    output: &mut Output,
+) -> (usize, usize) {
+    let world_dir = PathBuf::from(format!("assets/worlds/{}", world_name));
+    let manifest: Value = serde_json::from_str(
+        &std::fs::read_to_string(world_dir.join("manifest.json")).expect("Failed to read manifest")
+    ).expect("Failed to parse manifest");
+    let layer_dir = manifest["layers"].as_array()
+        .and_then(|layers| layers.iter().find(|l| l["name"].as_str() == Some(layer_name)))
+        .map(|l| world_dir.join(l["directory"].as_str().unwrap_or(layer_name)))
+        .unwrap_or_else(|| panic!("Layer '{}' not found in manifest", layer_name));
+
+    // Colors of terrain, bark and rock voxels come from the world's materials
+    let materials_path = world_dir.join("materials.json");
+    if materials_path.exists() {
+        match MaterialRegistry::load(&materials_path) {
+            Ok(registry) => {
+                material::install(registry)
+                    .unwrap_or_else(|e| panic!("Failed to install materials from {}: {}", materials_path.display(), e));
+            }
+            Err(e) => log::warn!("Failed to load {}: {} (using built-in materials)", materials_path.display(), e),
+        }
+    }
+
+    let store = RegionStore::new(&layer_dir);
+    let coords: Vec<ChunkCoord> = disk_io::stored_chunk_coords(&store, disk_io::CHUNK_EXTENSION)
+        .expect("Failed to list chunks")
+        .into_iter()
+        .filter(|c| region.is_none_or(|r| region_of(*c) == r))
+        .collect();
+    println!("=== Exporting {} chunks from '{}' / {} ===", coords.len(), world_name, layer_name);
+
+    let dictionary = disk_io::load_layer_dictionary(&store).expect("Failed to load shared dictionary");
+    let mut cache = ChunkCache { store, dictionary, octrees: HashMap::new() };
+    let mut triangles = 0;
+    for (i, &coord) in coords.iter().enumerate() {
+        // Load the chunk and its neighbours, then drop what no later chunk needs
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    cache.load(ChunkCoord::new(coord.x + dx, coord.y + dy, coord.z + dz));
+                }
+            }
+        }
+        let Some(octree) = cache.octrees.get(&coord).and_then(Option::as_ref) else { continue };
+        if octree.is_empty() {
+            continue;
+        }
+
+        let resolution = 1u32 << octree.max_depth();
+        let mut mesh = Mesh::default();
+        mesh::mesh_tiled(
+            &cache,
+            chunk_origin(coord),
+            UVec3::splat(resolution),
+            CHUNK_SIZE as f32 / resolution as f32,
+            mode,
+            |tile| mesh.append(&tile),
+        );
+        triangles += mesh.triangle_count();
+        output.add(&format!("chunk_{}_{}_{}", coord.x, coord.y, coord.z), &mesh);
+
+        // Chunks are listed in (x, y, z) order, so anything more than one
+        // x step behind is never a neighbour again
+        cache.octrees.retain(|c, _| c.x >= coord.x - 1);
+        if (i + 1) % 100 == 0 {
+            log::info!("{}/{} chunks, {} triangles", i + 1, coords.len(), triangles);
+        }
+    }
+    (coords.len(), triangles)
+}
+
+/// Decoded chunks around the one being meshed
+struct ChunkCache {
+    store: RegionStore,
+    /// The layer's shared subtree dictionary, resolved into each loaded chunk
+    dictionary: Option<SubtreeDictionary>,
+    /// `None` for coordinates with no stored chunk
+    octrees: HashMap<ChunkCoord, Option<Octree>>,
+}
+
+impl ChunkCache {
+    fn load(&mut self, coord: ChunkCoord) {
+        let (store, dictionary) = (&self.store, &self.dictionary);
+        self.octrees.entry(coord).or_insert_with(|| {
+            match disk_io::load_any_chunk(store, coord) {
+                Ok(Some(AnyChunk::Octree(c) | AnyChunk::Svdag(c) | AnyChunk::Attributed(c))) => Some(match dictionary {
+                    Some(dictionary) => dictionary.resolve(&c.octree),
+                    None => c.octree,
+                }),
+                Ok(_) => None,
+                Err(e) => {
+                    log::warn!("Skipping chunk ({}, {}, {}): {}", coord.x, coord.y, coord.z, e);
+                    None
+                }
+            }
+        });
+    }
+
+}
+
+/// World-space view of the loaded chunks; missing chunks read as empty
+impl RegionClassifier for ChunkCache {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        let min = (aabb.min / CHUNK_SIZE as f32).floor().as_ivec3();
+        let max = (aabb.max / CHUNK_SIZE as f32).floor().as_ivec3();
+        let mut combined = None;
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let coord = ChunkCoord::new(x, y, z);
+                    let center = chunk_origin(coord) + Vec3::splat(CHUNK_SIZE as f32 / 2.0);
+                    let hint = match self.octrees.get(&coord) {
+                        Some(Some(octree)) => {
+                            let local = Aabb::new(
+                                aabb.min.max(chunk_origin(coord)) - center,
+                                aabb.max.min(chunk_origin(coord) + Vec3::splat(CHUNK_SIZE as f32)) - center,
+                            );
+                            octree.region_hint(&local)
+                        }
+                        Some(None) => RegionHint::Empty,
+                        None => RegionHint::Unknown,
+                    };
+                    match combined {
+                        None => combined = Some(hint),
+                        Some(previous) if previous == hint => {}
+                        Some(_) => return RegionHint::Mixed,
+                    }
+                }
+            }
+        }
+        combined.unwrap_or(RegionHint::Empty)
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        let cell = (pos / CHUNK_SIZE as f32).floor().as_ivec3();
+        let coord = ChunkCoord::new(cell.x, cell.y, cell.z);
+        match self.octrees.get(&coord) {
+            Some(Some(octree)) => {
+                octree.sample_voxel(pos - chunk_origin(coord) - Vec3::splat(CHUNK_SIZE as f32 / 2.0))
+            }
+            _ => Voxel::EMPTY,
+        }
+    }
+}
+
+fn chunk_origin(coord: ChunkCoord) -> Vec3 {
+    Vec3::new(coord.x as f32, coord.y as f32, coord.z as f32) * CHUNK_SIZE as f32
+}
+
+fn parse_region(value: &str) -> SuperChunkCoord {
+    let parts: Vec<i32> = value.split(',')
+        .map(|p| p.trim().parse().expect("--region expects X,Y,Z"))
+        .collect();
+    match parts[..] {
+        [x, y, z] => SuperChunkCoord::new(x, y, z),
+        _ => panic!("--region expects X,Y,Z"),
+    }
+}
+
+fn parse_str_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
@@ -5,7 +5,7 @@
 use std::path::PathBuf;
 use std::time::Instant;
 
-use rktri::voxel::rock_library::{RockGenerator, RockParams, MATERIAL_ROCK};
+use rktri::voxel::rock_library::{RockGenerator, RockParams};
 
 const DEFAULT_OUTPUT_DIR: &str = "assets/rocks";
 const DEFAULT_COUNT: usize = 30;
@@ -9,30 +9,51 @@
 //!   --scale <SCALE>   Terrain noise scale (default: 150.0)
 //!   --height <H>      Terrain height scale (default: 80.0)
 //!   --jobs <N>         Max parallel chunk builds (default: 4)
+//!   --caves           Generate 3D density terrain (caves, caverns, overhangs)
+//!   --crust <METERS>  Solid ground depth below the surface with --caves (default: 24)
+//!   --materials <FILE> Material registry JSON (default: built-in materials)
+//!   --attributes      Store per-voxel normal, albedo and wetness with terrain
+//!                     bricks (larger chunks; not shared by --shared-dict)
+//!   --bake-ao         Bake ambient occlusion and sky visibility into terrain
+//!                     chunks, tracing into neighbouring chunks (slow; not
+//!                     shared by --shared-dict)
+//!   --shared-dict     Move subtrees common to many terrain chunks into one
+//!                     shared dictionary referenced by the chunks
+//!   --shared-dict-min <N> Chunks a subtree must appear in to be shared (default: 2)
 //!
 //! Output structure:
 //!   assets/worlds/<name>/
 //!     manifest.json           # World metadata + per-layer chunk lists
-//!     terrain/                # Terrain layer chunks
-//!       chunk_0_10_0.rkc
-//!       ...
-//!     grass/                  # Grass mask layer
-//!       chunk_0_10_0.rkm
+//!     materials.json          # Material registry the chunks were built with
+//!     terrain/                # Terrain layer chunks (SVDAG: shared subtrees and bricks)
+//!       dictionary.rkd        # Subtrees shared across chunks (--shared-dict)
+//!       r_0_0_0.rkr           # Region file: all chunks of one 16^3 super chunk
 //!       ...
+//!     grass/                  # Grass mask layer (region files)
+//!     rocks/                  # Rocks layer (region files)
+//!     vegetation/             # Vegetation layer (region files)
+//!     proxies/                # Far-field proxy per 64m super chunk (1m voxels),
+//!                             # region files keyed by super chunk coordinate
 
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::Instant;
 
 use glam::Vec3;
 use rayon::prelude::*;
 use serde_json::json;
 
-use rktri::generation::{GenerationConfig, GenerationPipeline};
+use rktri::generation::{CaveParams, GenerationConfig, GenerationPipeline, TerrainMode};
 use rktri::streaming::disk_io;
+use rktri::streaming::region::{RegionStore, REGION_EXTENSION};
 use rktri::terrain::generator::TerrainParams;
 use rktri::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use rktri::voxel::hierarchy::PROXY_VOXELS_PER_SUPER_CHUNK;
+use rktri::voxel::material::{self, MaterialRegistry};
+use rktri::voxel::super_chunk::{ProxyBuilder, SuperChunkCoord};
+use rktri::voxel::svo::{DictionaryBuilder, OcclusionBaker, Octree, SvdagBuilder, SvdagStats};
 
 fn main() {
     env_logger::Builder::from_env(
@@ -48,6 +69,18 @@ fn main() {
     let scale = parse_f32_arg(&args, "--scale").unwrap_or(150.0);
     let height_scale = parse_f32_arg(&args, "--height").unwrap_or(80.0);
     let jobs = parse_usize_arg(&args, "--jobs").unwrap_or(4);
+    let caves = args.iter().any(|a| a == "--caves");
+    let crust_depth = parse_f32_arg(&args, "--crust").unwrap_or(CaveParams::default().crust_depth);
+    let attributes = args.iter().any(|a| a == "--attributes");
+    let bake_ao = args.iter().any(|a| a == "--bake-ao");
+    let shared_dict = args.iter().any(|a| a == "--shared-dict");
+    let shared_dict_min = parse_u32_arg(&args, "--shared-dict-min").unwrap_or(2);
+    if let Some(path) = parse_str_arg(&args, "--materials") {
+        let registry = MaterialRegistry::load(std::path::Path::new(&path))
+            .unwrap_or_else(|e| panic!("Failed to load materials from {}: {}", path, e));
+        material::install(registry)
+            .unwrap_or_else(|e| panic!("Failed to install materials from {}: {}", path, e));
+    }
 
     // Limit rayon's thread pool to cap peak memory usage
     rayon::ThreadPoolBuilder::new()
@@ -63,6 +96,18 @@ fn main() {
     println!("Seed:  {}", seed);
     println!("Scale: {}, Height: {}", scale, height_scale);
     println!("Jobs:  {} parallel", jobs);
+    if caves {
+        println!("Caves: on ({}m crust)", crust_depth);
+    }
+    if attributes {
+        println!("Attributes: terrain normal, albedo and wetness");
+    }
+    if bake_ao {
+        println!("Occlusion: baked AO and sky visibility");
+    }
+    if shared_dict {
+        println!("Shared dictionary: subtrees in {}+ chunks", shared_dict_min);
+    }
     println!("Output: {}", output_dir.display());
     println!();
 
@@ -82,6 +127,12 @@ fn main() {
         biome_mask_depth: 3,
         grass_mask_depth: 5,
         clutter_mask_depth: 4,
+        terrain_mode: if caves {
+            TerrainMode::Caves(CaveParams { crust_depth, ..Default::default() })
+        } else {
+            TerrainMode::Heightfield
+        },
+        terrain_attributes: attributes,
     };
     let pipeline = GenerationPipeline::new(&config);
 
@@ -97,10 +148,7 @@ fn main() {
         for dz in -chunk_radius..=chunk_radius {
             let cx = (center_coord.x + dx) as f32 * chunk_f + chunk_f * 0.5;
             let cz = (center_coord.z + dz) as f32 * chunk_f + chunk_f * 0.5;
-            let h = pipeline.height_at(cx, cz);
-
-            let min_y = ((h - chunk_f) / chunk_f).floor().max(0.0) as i32;
-            let max_y = ((h + chunk_f) / chunk_f).ceil() as i32;
+            let (min_y, max_y) = pipeline.chunk_y_range(cx, cz);
 
             for dy in min_y..=max_y {
                 coords.push(ChunkCoord::new(center_coord.x + dx, dy, center_coord.z + dz));
@@ -118,10 +166,21 @@ fn main() {
     let grass_dir = output_dir.join("grass");
     let rocks_dir = output_dir.join("rocks");
     let vegetation_dir = output_dir.join("vegetation");
+    let proxy_dir = output_dir.join("proxies");
     std::fs::create_dir_all(&terrain_dir).expect("Failed to create terrain directory");
     std::fs::create_dir_all(&grass_dir).expect("Failed to create grass directory");
     std::fs::create_dir_all(&rocks_dir).expect("Failed to create rocks directory");
     std::fs::create_dir_all(&vegetation_dir).expect("Failed to create vegetation directory");
+    std::fs::create_dir_all(&proxy_dir).expect("Failed to create proxy directory");
+    for dir in [&terrain_dir, &grass_dir, &rocks_dir, &vegetation_dir, &proxy_dir] {
+        clear_region_files(dir);
+    }
+    let terrain_store = RegionStore::new(&terrain_dir);
+    let grass_store = RegionStore::new(&grass_dir);
+    let rocks_store = RegionStore::new(&rocks_dir);
+    let vegetation_store = RegionStore::new(&vegetation_dir);
+    let proxy_store = RegionStore::new(&proxy_dir);
+    remove_dictionary(&terrain_store);
 
     let start = Instant::now();
     let generated = AtomicUsize::new(0);
@@ -132,6 +191,7 @@ fn main() {
     let grass_chunk_count = AtomicUsize::new(0);
     let rocks_chunk_count = AtomicUsize::new(0);
     let vegetation_chunk_count = AtomicUsize::new(0);
+    let dag_stats = Mutex::new(SvdagStats::default());
 
     let terrain_chunks: Vec<(i32, i32, i32)> = coords
         .par_iter()
@@ -152,50 +212,37 @@ fn main() {
             }
 
             // Write terrain chunk
-            let disk_coord = disk_io::ChunkCoord::new(coord.x, coord.y, coord.z);
-            let disk_chunk = disk_io::Chunk::from_octree(disk_coord, result.chunk.octree);
-            let compressed = disk_io::compress_chunk(&disk_chunk)
-                .expect("Failed to compress chunk");
+            let compressed = compress_dag_chunk(coord, &result.chunk.octree, &dag_stats);
 
-            let chunk_file = terrain_dir.join(format!("chunk_{}_{}_{}.rkc", coord.x, coord.y, coord.z));
             total_terrain_bytes.fetch_add(compressed.len(), Ordering::Relaxed);
-            std::fs::write(&chunk_file, &compressed)
-                .expect("Failed to write chunk file");
+            disk_io::write_stored_chunk(&terrain_store, coord, disk_io::CHUNK_EXTENSION, &compressed)
+                .expect("Failed to write chunk");
 
             // Write grass mask (only if non-empty)
             if !result.grass_mask.is_empty() {
-                let grass_compressed = disk_io::compress_grass_mask(disk_coord, &result.grass_mask)
+                let grass_compressed = disk_io::compress_grass_mask(coord, &result.grass_mask)
                     .expect("Failed to compress grass mask");
-                let grass_file = grass_dir.join(format!("chunk_{}_{}_{}.rkm", coord.x, coord.y, coord.z));
                 total_grass_bytes.fetch_add(grass_compressed.len(), Ordering::Relaxed);
-                std::fs::write(&grass_file, &grass_compressed)
-                    .expect("Failed to write grass mask file");
+                disk_io::write_stored_chunk(&grass_store, coord, disk_io::GRASS_MASK_EXTENSION, &grass_compressed)
+                    .expect("Failed to write grass mask");
                 grass_chunk_count.fetch_add(1, Ordering::Relaxed);
             }
 
             // Write rocks layer (layer_id = 2)
             if result.layer_octrees.rocks_octree.brick_count() > 0 {
-                let rocks_octree = result.layer_octrees.rocks_octree;
-                let rocks_disk_chunk = disk_io::Chunk::from_octree(disk_coord, rocks_octree);
-                let rocks_compressed = disk_io::compress_chunk(&rocks_disk_chunk)
-                    .expect("Failed to compress rocks chunk");
-                let rocks_file = rocks_dir.join(format!("chunk_{}_{}_{}.rkc", coord.x, coord.y, coord.z));
+                let rocks_compressed = compress_dag_chunk(coord, &result.layer_octrees.rocks_octree, &dag_stats);
                 total_rocks_bytes.fetch_add(rocks_compressed.len(), Ordering::Relaxed);
-                std::fs::write(&rocks_file, &rocks_compressed)
-                    .expect("Failed to write rocks chunk file");
+                disk_io::write_stored_chunk(&rocks_store, coord, disk_io::CHUNK_EXTENSION, &rocks_compressed)
+                    .expect("Failed to write rocks chunk");
                 rocks_chunk_count.fetch_add(1, Ordering::Relaxed);
             }
 
             // Write vegetation layer (layer_id = 3)
             if result.layer_octrees.vegetation_octree.brick_count() > 0 {
-                let veg_octree = result.layer_octrees.vegetation_octree;
-                let veg_disk_chunk = disk_io::Chunk::from_octree(disk_coord, veg_octree);
-                let veg_compressed = disk_io::compress_chunk(&veg_disk_chunk)
-                    .expect("Failed to compress vegetation chunk");
-                let veg_file = vegetation_dir.join(format!("chunk_{}_{}_{}.rkc", coord.x, coord.y, coord.z));
+                let veg_compressed = compress_dag_chunk(coord, &result.layer_octrees.vegetation_octree, &dag_stats);
                 total_vegetation_bytes.fetch_add(veg_compressed.len(), Ordering::Relaxed);
-                std::fs::write(&veg_file, &veg_compressed)
-                    .expect("Failed to write vegetation chunk file");
+                disk_io::write_stored_chunk(&vegetation_store, coord, disk_io::CHUNK_EXTENSION, &veg_compressed)
+                    .expect("Failed to write vegetation chunk");
                 vegetation_chunk_count.fetch_add(1, Ordering::Relaxed);
             }
 
@@ -204,13 +251,14 @@ fn main() {
         .collect();
 
     let elapsed = start.elapsed();
-    let terrain_bytes = total_terrain_bytes.load(Ordering::Relaxed);
+    let mut terrain_bytes = total_terrain_bytes.load(Ordering::Relaxed);
     let grass_bytes = total_grass_bytes.load(Ordering::Relaxed);
     let rocks_bytes = total_rocks_bytes.load(Ordering::Relaxed);
     let vegetation_bytes = total_vegetation_bytes.load(Ordering::Relaxed);
     let grass_count = grass_chunk_count.load(Ordering::Relaxed);
     let rocks_count = rocks_chunk_count.load(Ordering::Relaxed);
     let vegetation_count = vegetation_chunk_count.load(Ordering::Relaxed);
+    let dag_stats = dag_stats.into_inner().unwrap();
 
     println!();
     println!("Terrain: {} chunks in {:.1}s ({:.0} chunks/sec, {:.1} MB)",
@@ -223,6 +271,42 @@ fn main() {
         rocks_count, rocks_bytes as f64 / 1024.0);
     println!("Vegetation: {} chunks ({:.1} KB)",
         vegetation_count, vegetation_bytes as f64 / 1024.0);
+    println!("SVDAG:   {} -> {} nodes ({:.1}x), {} -> {} bricks ({:.1}x)",
+        dag_stats.nodes_before, dag_stats.nodes_after, dag_stats.node_ratio(),
+        dag_stats.bricks_before, dag_stats.bricks_after, dag_stats.brick_ratio());
+    println!("         {} nodes reused, deepest shared subtree {} levels, {:.1} MB saved before LZ4",
+        dag_stats.reused_nodes, dag_stats.deepest_shared_level,
+        dag_stats.bytes_saved() as f64 / (1024.0 * 1024.0));
+
+    // Phase 2a: Bake ambient occlusion and sky visibility across chunk borders
+    if bake_ao {
+        println!();
+        let chunk_bytes = bake_terrain_occlusion(&terrain_store, &terrain_chunks);
+        println!("Terrain: {:.1} MB -> {:.1} MB with occlusion",
+            terrain_bytes as f64 / (1024.0 * 1024.0),
+            chunk_bytes as f64 / (1024.0 * 1024.0));
+        terrain_bytes = chunk_bytes;
+    }
+
+    // Phase 2b: Downsample terrain into far-field proxies, one per super chunk
+    println!();
+    let (proxy_count, proxy_bytes) = build_terrain_proxies(&terrain_store, &proxy_store, &terrain_chunks);
+
+    // Phase 2c: Move subtrees common to many terrain chunks into a shared dictionary
+    let dictionary_info = if shared_dict {
+        println!();
+        let (chunk_bytes, info) = share_terrain_subtrees(&terrain_store, &terrain_chunks, shared_dict_min);
+        let dictionary_bytes = info["bytes"].as_u64().unwrap_or(0) as usize;
+        println!("Terrain: {:.1} MB per-chunk -> {:.1} MB chunks + {:.1} KB dictionary ({:.0}% of per-chunk)",
+            terrain_bytes as f64 / (1024.0 * 1024.0),
+            chunk_bytes as f64 / (1024.0 * 1024.0),
+            dictionary_bytes as f64 / 1024.0,
+            (chunk_bytes + dictionary_bytes) as f64 * 100.0 / terrain_bytes.max(1) as f64);
+        terrain_bytes = chunk_bytes;
+        info
+    } else {
+        serde_json::Value::Null
+    };
 
     // Phase 3: Write manifest with per-layer structure
     let mut y_groups: BTreeMap<i32, usize> = BTreeMap::new();
@@ -244,6 +328,8 @@ fn main() {
             "lacunarity": 2.0,
             "sea_level": 20.0,
         },
+        "terrain_mode": if caves { "caves" } else { "heightfield" },
+        "crust_depth": crust_depth,
         "layers": [
             {
                 "name": "terrain",
@@ -251,6 +337,9 @@ fn main() {
                 "directory": "terrain",
                 "chunk_count": terrain_chunks.len(),
                 "total_bytes": terrain_bytes,
+                "attributes": attributes,
+                "occlusion": bake_ao,
+                "dictionary": dictionary_info,
                 "y_levels": y_groups,
                 "chunks": terrain_chunks.iter().map(|(x, y, z)| {
                     json!({"x": x, "y": y, "z": z})
@@ -269,7 +358,7 @@ fn main() {
                 "directory": "rocks",
                 "chunk_count": rocks_count,
                 "total_bytes": rocks_bytes,
-                "chunks": read_chunk_coords(&rocks_dir),
+                "chunks": read_chunk_coords(&rocks_store),
             },
             {
                 "name": "vegetation",
@@ -277,14 +366,21 @@ fn main() {
                 "directory": "vegetation",
                 "chunk_count": vegetation_count,
                 "total_bytes": vegetation_bytes,
-                "chunks": read_chunk_coords(&vegetation_dir),
+                "chunks": read_chunk_coords(&vegetation_store),
             }
         ],
+        "proxies": {
+            "directory": "proxies",
+            "source_layer": "terrain",
+            "resolution": PROXY_VOXELS_PER_SUPER_CHUNK,
+        },
     });
 
     let manifest_path = output_dir.join("manifest.json");
     std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest).unwrap())
         .expect("Failed to write manifest");
+    material::registry().save(&output_dir.join("materials.json"))
+        .expect("Failed to write materials");
 
     println!();
     println!("=== Generation Complete ===");
@@ -293,43 +389,182 @@ fn main() {
     println!("Grass:  {} chunks with masks", grass_count);
     println!("Rocks:  {} chunks with rocks", rocks_count);
     println!("Vegetation: {} chunks", vegetation_count);
+    println!("Proxies: {} super chunks ({:.1} KB)", proxy_count, proxy_bytes as f64 / 1024.0);
     println!("Size:   {:.1} MB terrain + {:.1} KB grass + {:.1} KB rocks + {:.1} KB vegetation",
         terrain_bytes as f64 / (1024.0 * 1024.0),
         grass_bytes as f64 / 1024.0,
         rocks_bytes as f64 / 1024.0,
         vegetation_bytes as f64 / 1024.0);
+    println!("SVDAG:  {:.1} MB of nodes and bricks saved by deduplication",
+        dag_stats.bytes_saved() as f64 / (1024.0 * 1024.0));
+    if let Some(bytes) = dictionary_info["bytes"].as_u64() {
+        println!("Dict:   {:.1} KB of subtrees shared by terrain chunks", bytes as f64 / 1024.0);
+    }
     println!("Output: {}", output_dir.display());
     println!();
     println!("To load this world:");
     println!("  cargo run --release --bin rktri -- --world {}", name);
 }
 
-/// Read chunk coordinates from a directory (e.g., rocks/, vegetation/)
-fn read_chunk_coords(dir: &std::path::Path) -> Vec<serde_json::Value> {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return vec![],
-    };
-    entries
-        .filter_map(|e| {
-            let e = e.ok()?;
-            let filename = e.file_name();
-            let name = filename.to_str()?;
-            if name.starts_with("chunk_") && name.ends_with(".rkc") {
-                let coords: Vec<_> = name.strip_prefix("chunk_")
-                    .and_then(|s| s.strip_suffix(".rkc"))
-                    .map(|s| s.split('_').collect::<Vec<_>>())?;
-                if coords.len() == 3 {
-                    return Some(json!({
-                        "x": coords[0].parse::<i32>().unwrap_or(0),
-                        "y": coords[1].parse::<i32>().unwrap_or(0),
-                        "z": coords[2].parse::<i32>().unwrap_or(0),
-                    }));
+/// Deduplicate an octree's subtrees and bricks (full SVDAG) and compress it
+/// for storage, adding what was shared to `stats`
+fn compress_dag_chunk(coord: ChunkCoord, octree: &Octree, stats: &Mutex<SvdagStats>) -> Vec<u8> {
+    let (dag, chunk_stats) = SvdagBuilder::new().build_full_with_stats(octree);
+    stats.lock().unwrap().merge(&chunk_stats);
+    disk_io::compress_svdag_chunk(&disk_io::Chunk::from_octree(coord, dag))
+        .expect("Failed to compress chunk")
+}
+
+/// Rebuild every terrain chunk with ambient occlusion and sky visibility,
+/// traced into the chunks around it. Returns the new total chunk bytes.
+fn bake_terrain_occlusion(store: &RegionStore, coords: &[(i32, i32, i32)]) -> usize {
+    let start = Instant::now();
+    let baker = OcclusionBaker::new();
+    let baked = AtomicUsize::new(0);
+    let chunk_bytes = AtomicUsize::new(0);
+
+    // Neighbours rewritten meanwhile keep their voxels, so either version traces the same
+    coords.par_iter().for_each(|&(x, y, z)| {
+        let coord = ChunkCoord::new(x, y, z);
+        let neighbourhood = disk_io::load_chunk_neighbourhood(store, coord, None)
+            .expect("Failed to read terrain chunks");
+        let octree = baker.bake_chunk(coord, &neighbourhood)
+            .expect("Terrain chunk missing");
+        let dag = SvdagBuilder::new().build_full(&octree);
+        let compressed = disk_io::compress_svdag_chunk(&disk_io::Chunk::from_octree(coord, dag))
+            .expect("Failed to compress chunk");
+        chunk_bytes.fetch_add(compressed.len(), Ordering::Relaxed);
+        disk_io::write_stored_chunk(store, coord, disk_io::CHUNK_EXTENSION, &compressed)
+            .expect("Failed to write chunk");
+
+        let done = baked.fetch_add(1, Ordering::Relaxed) + 1;
+        if done % 1000 == 0 || done == coords.len() {
+            let elapsed = start.elapsed().as_secs_f64();
+            let remaining = (coords.len() - done) as f64 * elapsed / done as f64;
+            eprintln!("  [{}/{}] occlusion, ~{:.0}s remaining", done, coords.len(), remaining);
+        }
+    });
+
+    println!("Occlusion: {} chunks baked in {:.1}s ({} cones, {:.1}m reach)",
+        coords.len(), start.elapsed().as_secs_f64(), baker.cone_count(), baker.max_distance());
+    chunk_bytes.into_inner()
+}
+
+/// Downsample the terrain chunks of each super chunk into its far-field proxy
+/// and save it. Must run before chunks reference a shared dictionary.
+///
+/// Returns the number of super chunks that got a proxy and their total bytes.
+fn build_terrain_proxies(
+    terrain: &RegionStore,
+    proxies: &RegionStore,
+    coords: &[(i32, i32, i32)],
+) -> (usize, usize) {
+    let start = Instant::now();
+    let mut groups: BTreeMap<(i32, i32, i32), Vec<ChunkCoord>> = BTreeMap::new();
+    for &(x, y, z) in coords {
+        let chunk = ChunkCoord::new(x, y, z);
+        let coord = SuperChunkCoord::from_chunk(chunk);
+        groups.entry((coord.x, coord.y, coord.z)).or_default().push(chunk);
+    }
+
+    let proxy_bytes = AtomicUsize::new(0);
+    let built = groups
+        .par_iter()
+        .filter_map(|(&(sx, sy, sz), chunks)| {
+            let coord = SuperChunkCoord::new(sx, sy, sz);
+            let mut builder = ProxyBuilder::new(coord);
+            for &chunk in chunks {
+                // No dictionary yet, so nothing to resolve
+                if let Some(stored) = disk_io::load_stored_chunk_resolved(terrain, chunk, None)
+                    .expect("Failed to read terrain chunk")
+                {
+                    builder.add_chunk(chunk, stored.octree());
                 }
             }
-            None
+
+            let proxy = SvdagBuilder::new().build_full(&builder.build()?);
+            let bytes = disk_io::save_super_chunk_proxy(proxies, coord, &proxy)
+                .expect("Failed to write proxy");
+            proxy_bytes.fetch_add(bytes, Ordering::Relaxed);
+            Some(coord)
         })
-        .collect()
+        .count();
+
+    println!("Proxies: {} super chunks ({}^3 voxels) in {:.1}s ({:.1} KB)",
+        built, PROXY_VOXELS_PER_SUPER_CHUNK, start.elapsed().as_secs_f64(),
+        proxy_bytes.load(Ordering::Relaxed) as f64 / 1024.0);
+    (built, proxy_bytes.into_inner())
+}
+
+/// Rewrite a layer's chunks to reference a dictionary of the subtrees and
+/// bricks found in at least `min_chunks` of them, and save the dictionary.
+///
+/// Returns the new total chunk bytes and the manifest entry for the dictionary.
+fn share_terrain_subtrees(store: &RegionStore, coords: &[(i32, i32, i32)], min_chunks: u32) -> (usize, serde_json::Value) {
+    let start = Instant::now();
+    let load = |&(x, y, z): &(i32, i32, i32)| {
+        let coord = ChunkCoord::new(x, y, z);
+        disk_io::load_stored_chunk(store, coord)
+            .expect("Failed to read terrain chunk")
+            .expect("Terrain chunk missing")
+    };
+
+    let mut builder = DictionaryBuilder::new().with_min_chunks(min_chunks);
+    for coord in coords {
+        builder.count_chunk(&load(coord).octree);
+    }
+
+    let mut chunk_bytes = 0;
+    for coord in coords {
+        let chunk = load(coord);
+        let shared = disk_io::Chunk::from_octree(chunk.coord, builder.share_chunk(&chunk.octree));
+        let compressed = disk_io::compress_svdag_chunk(&shared).expect("Failed to compress chunk");
+        chunk_bytes += compressed.len();
+        disk_io::write_stored_chunk(store, chunk.coord, disk_io::CHUNK_EXTENSION, &compressed)
+            .expect("Failed to write chunk");
+    }
+
+    let dictionary = builder.finish();
+    let bytes = disk_io::save_layer_dictionary(store, &dictionary).expect("Failed to write shared dictionary");
+    println!("Shared dictionary: {} nodes, {} bricks ({:.1} KB uncompressed) in {:.1}s",
+        dictionary.node_count(), dictionary.brick_count(),
+        dictionary.memory_usage() as f64 / 1024.0, start.elapsed().as_secs_f64());
+
+    let info = json!({
+        "file": disk_io::DICTIONARY_FILE,
+        "bytes": bytes,
+        "nodes": dictionary.node_count(),
+        "bricks": dictionary.brick_count(),
+        "min_chunks": min_chunks,
+    });
+    (chunk_bytes, info)
+}
+
+/// Remove a shared dictionary left over from a previous run
+fn remove_dictionary(store: &RegionStore) {
+    match std::fs::remove_file(disk_io::dictionary_path(store)) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => panic!("Failed to remove old shared dictionary: {}", e),
+    }
+}
+
+/// Manifest chunk list of the chunks stored in a layer (e.g., rocks/, vegetation/)
+fn read_chunk_coords(store: &RegionStore) -> Vec<serde_json::Value> {
+    disk_io::manifest_chunk_list(
+        &disk_io::stored_chunk_coords(store, disk_io::CHUNK_EXTENSION).unwrap_or_default()
+    )
+}
+
+/// Remove region files left over from a previous run so stale chunks don't survive
+fn clear_region_files(dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) == Some(REGION_EXTENSION) {
+            std::fs::remove_file(&path).expect("Failed to remove old region file");
+        }
+    }
 }
 
 fn parse_f32_arg(args: &[String], flag: &str) -> Option<f32> {
@@ -0,0 +1,166 @@
+//! Import artist-authored voxel models into the clutter or tree library.
+//!
+//! Usage:
+//!   cargo run --release --bin import_voxels -- --input crate.vox --name crate
+//!   cargo run --release --bin import_voxels -- --input oak.vox --as tree --style oak
+//!   cargo run --release --bin import_voxels -- --input bunny.binvox --material sand
+//!
+//! Options:
+//!   --input <FILE>              .vox (MagicaVoxel), .binvox or .raw
+//!   --dims <WxHxD>              Grid size of a .raw file (one palette byte
+//!                               per voxel, x fastest, y up)
+//!   --model <N>                 Import only model N of a .vox file instead of
+//!                               the whole scene
+//!   --voxel-size <M>            Voxel edge length in meters (default: 1/128)
+//!   --material <NAME>           Material for every palette index (default: stone)
+//!   --palette-material <I=NAME> Material for palette index I; repeatable
+//!   --color <R,G,B>             Color of .binvox voxels (default: 128,128,128)
+//!   --as <clutter|tree>         Library to add to (default: clutter)
+//!   --style <STYLE>             Tree style: oak, willow, elm, winter_oak,
+//!                               winter_willow (default: oak)
+//!   --name <NAME>               Clutter name (default: input file stem)
+//!   --output-dir <DIR>          Library directory (default: assets/clutter
+//!                               or assets/trees)
+//!
+//! Clutter metadata goes in the clutter library index; the octree is written
+//! beside it as a `.rkt` file with the same stem.
+
+use std::path::{Path, PathBuf};
+
+use glam::UVec3;
+
+use rktri::clutter::ClutterLibrary;
+use rktri::voxel::chunk::VOXEL_SIZE;
+use rktri::voxel::import::{self, IndexedGrid, Palette, VoxFile};
+use rktri::voxel::material;
+use rktri::voxel::procgen::TreeStyle;
+use rktri::voxel::tree_library::TreeLibrary;
+
+const USAGE: &str = "Usage: import_voxels --input <file.vox|file.binvox|file.raw> [--dims WxHxD] [--model N] \
+                     [--voxel-size M] [--material NAME] [--palette-material I=NAME]... [--color R,G,B] \
+                     [--as clutter|tree] [--style STYLE] [--name NAME] [--output-dir DIR]";
+
+fn main() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info"),
+    )
+    .format_timestamp_millis()
+    .init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let input = PathBuf::from(parse_str_arg(&args, "--input").expect(USAGE));
+    let voxel_size = parse_str_arg(&args, "--voxel-size")
+        .map(|v| v.parse::<f32>().expect("--voxel-size must be a number"))
+        .unwrap_or(VOXEL_SIZE);
+    let registry = material::registry();
+    let material_id = |name: &str| registry.id(name)
+        .unwrap_or_else(|| panic!("Unknown material '{}'", name));
+    let default_material = material_id(&parse_str_arg(&args, "--material").unwrap_or_else(|| "stone".to_string()));
+
+    let data = std::fs::read(&input)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", input.display(), e));
+    let extension = input.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let (grid, palette) = match extension.as_str() {
+        "vox" => {
+            let file = VoxFile::parse(&data).expect("Failed to parse .vox file");
+            println!("{} models, {} visible instances", file.models.len(), file.instances.len());
+            let grid = match parse_str_arg(&args, "--model") {
+                Some(n) => {
+                    let n: usize = n.parse().expect("--model must be an index");
+                    assert!(n < file.models.len(), "--model {} out of range", n);
+                    file.model_grid(n)
+                }
+                None => file.scene_grid(),
+            }.expect("Failed to build voxel grid");
+            (grid, file.palette(default_material))
+        }
+        "binvox" | "raw" => {
+            let color = parse_str_arg(&args, "--color").map_or([128, 128, 128], |c| parse_color(&c));
+            let grid = if extension == "binvox" {
+                import::read_binvox(&data).expect("Failed to parse .binvox file")
+            } else {
+                let dims = parse_str_arg(&args, "--dims").expect("--dims is required for .raw input");
+                import::read_raw(&data, parse_dims(&dims)).expect("Failed to read raw grid")
+            };
+            (grid, Palette::uniform(color, default_material))
+        }
+        _ => panic!("Unknown input format '{}'\n{}", extension, USAGE),
+    };
+
+    let palette = palette_materials(&args)
+        .into_iter()
+        .fold(palette, |palette, (index, name)| palette.with_material(index, material_id(&name)));
+    import_grid(&args, &input, &grid, &palette, voxel_size);
+}
+
+fn import_grid(args: &[String], input: &Path, grid: &IndexedGrid, palette: &Palette, voxel_size: f32) {
+    let model = grid.to_model(palette, voxel_size).expect("Failed to build octree");
+    println!(
+        "Imported {}x{}x{} grid: {} voxels, {} nodes, {} bricks, {:.2}m root",
+        grid.size.x, grid.size.y, grid.size.z, model.voxel_count,
+        model.octree.node_count(), model.octree.brick_count(), model.octree.root_size()
+    );
+
+    let kind = parse_str_arg(args, "--as").unwrap_or_else(|| "clutter".to_string());
+    match kind.as_str() {
+        "clutter" => {
+            let dir = parse_str_arg(args, "--output-dir").unwrap_or_else(|| "assets/clutter".to_string());
+            let name = parse_str_arg(args, "--name").unwrap_or_else(|| {
+                input.file_stem().map_or("imported".into(), |s| s.to_string_lossy().into_owned())
+            });
+            let mut library = ClutterLibrary::open_sync(PathBuf::from(&dir)).expect("Failed to open clutter library");
+            let (id, octree_path) = model.save_clutter(&mut library, &name).expect("Failed to add clutter");
+            println!("Added clutter '{}' as id {} ({})", name, id, octree_path.display());
+        }
+        "tree" => {
+            let dir = parse_str_arg(args, "--output-dir").unwrap_or_else(|| "assets/trees".to_string());
+            let style = parse_str_arg(args, "--style").map_or(TreeStyle::Oak, |s| parse_style(&s));
+            let mut library = TreeLibrary::open_sync(PathBuf::from(&dir)).expect("Failed to open tree library");
+            let id = library.add_tree_sync(&model.tree_data(style, 0)).expect("Failed to add tree");
+            println!("Added {:?} tree as id {} in {}", style, id, dir);
+        }
+        other => panic!("Unknown library '{}'\n{}", other, USAGE),
+    }
+}
+
+/// Every `--palette-material I=NAME` pair
+fn palette_materials(args: &[String]) -> Vec<(u8, String)> {
+    args.windows(2)
+        .filter(|w| w[0] == "--palette-material")
+        .map(|w| {
+            let (index, name) = w[1].split_once('=')
+                .unwrap_or_else(|| panic!("--palette-material expects I=NAME, got '{}'", w[1]));
+            let index = index.parse().unwrap_or_else(|_| panic!("Bad palette index '{}'", index));
+            (index, name.to_string())
+        })
+        .collect()
+}
+
+fn parse_style(s: &str) -> TreeStyle {
+    match s.to_lowercase().as_str() {
+        "oak" => TreeStyle::Oak,
+        "willow" => TreeStyle::Willow,
+        "elm" => TreeStyle::Elm,
+        "winter_oak" => TreeStyle::WinterOak,
+        "winter_willow" => TreeStyle::WinterWillow,
+        other => panic!("Unknown style '{}'\n{}", other, USAGE),
+    }
+}
+
+fn parse_dims(s: &str) -> UVec3 {
+    let parts: Vec<u32> = s.split('x').map(|p| p.trim().parse().expect("Bad --dims")).collect();
+    assert!(parts.len() == 3, "--dims expects WxHxD, got '{}'", s);
+    UVec3::new(parts[0], parts[1], parts[2])
+}
+
+fn parse_color(s: &str) -> [u8; 3] {
+    let parts: Vec<u8> = s.split(',').map(|p| p.trim().parse().expect("Bad --color")).collect();
+    assert!(parts.len() == 3, "--color expects R,G,B, got '{}'", s);
+    [parts[0], parts[1], parts[2]]
+}
+
+fn parse_str_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
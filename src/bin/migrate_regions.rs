@@ -0,0 +1,137 @@
+//! Convert a world's per-chunk files into region files.
+//!
+//! Usage: cargo run --release --bin migrate_regions -- --world <name> [OPTIONS]
+//!
+//! Options:
+//!   --world <NAME>    World directory under assets/worlds (required)
+//!   --keep            Keep the original chunk files after copying them
+//!
+//! Every layer directory listed in manifest.json is migrated. Both flat
+//! (`chunk_x_y_z.rkc`) and `y_N/` layouts are picked up, as are grass masks
+//! (`.rkm`). Region slots are keyed by chunk only, so a directory holding
+//! both chunks and masks is refused and left untouched. Worlds keep loading
+//! during a partial migration since readers fall back to per-chunk files.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde_json::Value;
+
+use rktri::streaming::disk_io;
+use rktri::streaming::RegionStore;
+
+fn main() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("info"),
+    )
+    .format_timestamp_millis()
+    .init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let world_name = args.iter()
+        .position(|a| a == "--world")
+        .and_then(|i| args.get(i + 1))
+        .expect("Usage: migrate_regions --world <name> [--keep]");
+    let keep = args.iter().any(|a| a == "--keep");
+
+    let world_dir = PathBuf::from(format!("assets/worlds/{}", world_name));
+    let manifest_path = world_dir.join("manifest.json");
+    let manifest: Value = serde_json::from_str(
+        &std::fs::read_to_string(&manifest_path).expect("Failed to read manifest")
+    ).expect("Failed to parse manifest");
+
+    let layer_dirs: Vec<String> = manifest["layers"].as_array()
+        .expect("Manifest missing 'layers' array")
+        .iter()
+        .filter_map(|l| l["directory"].as_str().or(l["name"].as_str()).map(String::from))
+        .collect();
+
+    println!("=== Migrating '{}' to region files ===", world_name);
+    let start = Instant::now();
+    let mut total_chunks = 0;
+    let mut refused = 0;
+
+    for layer in &layer_dirs {
+        let dir = world_dir.join(layer);
+        match migrate_dir(&dir, keep) {
+            Some((chunks, bytes)) => {
+                total_chunks += chunks;
+                println!("  {:<12} {} chunks ({:.1} KB)", layer, chunks, bytes as f64 / 1024.0);
+            }
+            None => {
+                refused += 1;
+                println!("  {:<12} skipped: holds both .{} chunks and .{} grass masks",
+                    layer, disk_io::CHUNK_EXTENSION, disk_io::GRASS_MASK_EXTENSION);
+            }
+        }
+    }
+
+    println!();
+    println!("Migrated {} chunks in {:.1}s", total_chunks, start.elapsed().as_secs_f64());
+    if refused > 0 {
+        println!("{} layers were left as per-chunk files; split them into separate directories first", refused);
+        std::process::exit(1);
+    }
+}
+
+/// Move every per-chunk file in `dir` into region files.
+/// Returns (chunks migrated, bytes migrated), or None without touching
+/// anything if `dir` mixes chunk files and grass masks.
+fn migrate_dir(dir: &Path, keep: bool) -> Option<(usize, usize)> {
+    let listed: Vec<(&str, Vec<PathBuf>)> = [disk_io::CHUNK_EXTENSION, disk_io::GRASS_MASK_EXTENSION]
+        .into_iter()
+        .map(|extension| {
+            let files = disk_io::legacy_chunk_files(dir, extension)
+                .unwrap_or_else(|e| panic!("Failed to list {}: {}", dir.display(), e));
+            (extension, files)
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+    if listed.len() > 1 {
+        return None;
+    }
+
+    let store = RegionStore::new(dir);
+    let mut chunks = 0;
+    let mut bytes = 0;
+
+    for (extension, files) in listed {
+        for path in files {
+            let coord = path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| disk_io::parse_chunk_file_name(n, extension))
+                .expect("legacy_chunk_files returns chunk files");
+            let data = std::fs::read(&path)
+                .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+
+            if keep {
+                store.write(coord, &data)
+            } else {
+                disk_io::write_stored_chunk(&store, coord, extension, &data)
+            }
+            .unwrap_or_else(|e| panic!("Failed to store chunk {:?}: {}", coord, e));
+
+            chunks += 1;
+            bytes += data.len();
+        }
+    }
+
+    if !keep {
+        remove_empty_y_dirs(dir);
+    }
+    Some((chunks, bytes))
+}
+
+/// Remove `y_N/` subdirectories left empty by the migration
+fn remove_empty_y_dirs(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_y_dir = path.is_dir()
+            && entry.file_name().to_str().is_some_and(|n| n.starts_with("y_"));
+        if is_y_dir {
+            // Fails (and is skipped) when the directory still has other files
+            let _ = std::fs::remove_dir(&path);
+        }
+    }
+}
@@ -217,7 +217,8 @@ impl ViewerState {
             wind_direction: [1.0, 0.0, 0.0],
             wind_speed: 1.0,
             profile_count: 0,
-            _pad: [0.0; 3],
+            material_id: 0,
+            _pad: [0.0; 2],
         };
         svo_pipeline.update_grass_params(queue, &grass_params);
 
@@ -11,6 +11,7 @@ use rktri::generation::{GenerationConfig, GenerationPipeline};
 use rktri::terrain::generator::TerrainParams;
 use rktri::voxel::chunk::ChunkCoord;
 use rktri::streaming::disk_io;
+use rktri::streaming::RegionStore;
 
 fn main() {
     env_logger::Builder::from_env(
@@ -52,6 +53,7 @@ fn main() {
         biome_mask_depth: 3,
         grass_mask_depth: 5,
         clutter_mask_depth: 4,
+        ..Default::default()
     };
     let pipeline = GenerationPipeline::new(&config);
 
@@ -75,6 +77,7 @@ fn main() {
 
     let grass_dir = world_dir.join("grass");
     std::fs::create_dir_all(&grass_dir).expect("Failed to create grass directory");
+    let grass_store = RegionStore::new(&grass_dir);
 
     let start = Instant::now();
     let generated = AtomicUsize::new(0);
@@ -93,11 +96,9 @@ fn main() {
         }
 
         if !result.grass_mask.is_empty() {
-            let disk_coord = disk_io::ChunkCoord::new(coord.x, coord.y, coord.z);
-            let compressed = disk_io::compress_grass_mask(disk_coord, &result.grass_mask)
+            let compressed = disk_io::compress_grass_mask(coord, &result.grass_mask)
                 .expect("Failed to compress grass mask");
-            let grass_file = grass_dir.join(format!("chunk_{}_{}_{}.rkm", coord.x, coord.y, coord.z));
-            std::fs::write(&grass_file, &compressed)
+            disk_io::write_stored_chunk(&grass_store, coord, disk_io::GRASS_MASK_EXTENSION, &compressed)
                 .expect("Failed to write grass mask");
             grass_count.fetch_add(1, Ordering::Relaxed);
         }
@@ -0,0 +1,264 @@
+//! Validate a generated world and optionally repair its manifest.
+//!
+//! Usage: cargo run --release --bin verify_world -- --world <name> [--fix]
+//!
+//! Options:
+//!   --world <NAME>    World directory under assets/worlds (required)
+//!   --fix             Rewrite manifest.json from the chunks actually on disk
+//!
+//! For every layer in manifest.json this decodes each stored chunk, checks
+//! octree invariants (masks, child/brick indices, orphan nodes), compares
+//! the chunks on disk with the manifest's chunk list and reports per-layer
+//! sizes. Exits with status 1 if problems remain.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use rktri::streaming::disk_io::{self, AnyChunk, ChunkCoord};
+use rktri::streaming::RegionStore;
+
+/// Verification results for one layer
+#[derive(Default)]
+struct LayerReport {
+    chunks: usize,
+    bytes: usize,
+    /// Chunks that failed to decode (header, checksum or payload)
+    corrupt: Vec<ChunkCoord>,
+    /// Chunks that decoded but break octree invariants
+    invalid: Vec<(ChunkCoord, Vec<String>)>,
+    /// Chunks on disk whose payload names a different coordinate
+    misplaced: Vec<ChunkCoord>,
+    /// Listed in the manifest but not on disk
+    missing: Vec<ChunkCoord>,
+    /// On disk but not listed in the manifest
+    extra: Vec<ChunkCoord>,
+    /// Manifest chunk_count / total_bytes disagree with the good chunks on disk
+    count_mismatch: Option<(u64, usize)>,
+    bytes_mismatch: Option<(u64, usize)>,
+    /// Decodable chunks on disk, used by --fix
+    good: Vec<ChunkCoord>,
+    /// Stored size of the good chunks
+    good_bytes: usize,
+}
+
+impl LayerReport {
+    fn problem_count(&self) -> usize {
+        self.corrupt.len()
+            + self.invalid.len()
+            + self.misplaced.len()
+            + self.missing.len()
+            + self.extra.len()
+            + self.count_mismatch.is_some() as usize
+            + self.bytes_mismatch.is_some() as usize
+    }
+}
+
+fn main() {
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("warn"),
+    )
+    .format_timestamp_millis()
+    .init();
+
+    let args: Vec<String> = std::env::args().collect();
+    let world_name = parse_str_arg(&args, "--world")
+        .expect("Usage: verify_world --world <name> [--fix]");
+    let fix = args.iter().any(|a| a == "--fix");
+
+    let world_dir = PathBuf::from(format!("assets/worlds/{}", world_name));
+    let manifest_path = world_dir.join("manifest.json");
+    let mut manifest: Value = serde_json::from_str(
+        &std::fs::read_to_string(&manifest_path).expect("Failed to read manifest")
+    ).expect("Failed to parse manifest");
+
+    println!("=== Verifying world '{}' ===", world_name);
+    let version = manifest["version"].as_u64().unwrap_or(0);
+    if version != 3 {
+        println!("  manifest version {} (expected 3)", version);
+    }
+
+    let layers = manifest["layers"].as_array().cloned()
+        .expect("Manifest missing 'layers' array");
+
+    let mut reports = Vec::new();
+    let mut total_problems = 0;
+    for layer in &layers {
+        let name = layer["name"].as_str().unwrap_or("unknown").to_string();
+        let directory = layer["directory"].as_str().unwrap_or(&name).to_string();
+        let report = verify_layer(&RegionStore::new(world_dir.join(&directory)), layer);
+        print_report(&name, &report);
+        total_problems += report.problem_count();
+        reports.push(report);
+    }
+
+    println!();
+    if total_problems == 0 {
+        println!("World is consistent");
+        return;
+    }
+    println!("{} problems found", total_problems);
+
+    if !fix {
+        println!("Run with --fix to rebuild manifest.json from the chunks on disk");
+        std::process::exit(1);
+    }
+
+    for (layer, report) in manifest["layers"].as_array_mut().unwrap().iter_mut().zip(&reports) {
+        fix_layer(layer, report);
+    }
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest).unwrap())
+        .expect("Failed to write manifest");
+    println!("Rebuilt manifest: {}", manifest_path.display());
+
+    let unfixable: usize = reports.iter().map(|r| r.corrupt.len() + r.invalid.len() + r.misplaced.len()).sum();
+    if unfixable > 0 {
+        println!("{} damaged chunks were dropped from the manifest but left on disk", unfixable);
+        std::process::exit(1);
+    }
+}
+
+/// Decode and check every chunk stored for one layer
+fn verify_layer(store: &RegionStore, layer: &Value) -> LayerReport {
+    let mut report = LayerReport::default();
+
+    // Chunks referencing a shared dictionary are checked with it resolved;
+    // without a readable dictionary their references show up as invalid
+    let dictionary = disk_io::load_layer_dictionary(store).unwrap_or_else(|e| {
+        println!("Unreadable shared dictionary in {}: {}", store.dir().display(), e);
+        None
+    });
+
+    let stored: BTreeSet<(i32, i32, i32)> = [disk_io::CHUNK_EXTENSION, disk_io::GRASS_MASK_EXTENSION]
+        .iter()
+        .flat_map(|ext| disk_io::stored_chunk_coords(store, ext).unwrap_or_default())
+        .map(|c| (c.x, c.y, c.z))
+        .collect();
+
+    for &(x, y, z) in &stored {
+        let coord = ChunkCoord::new(x, y, z);
+        let data = match disk_io::read_stored_chunk(store, coord, disk_io::CHUNK_EXTENSION) {
+            Ok(Some(data)) => Ok(data),
+            Ok(None) => disk_io::read_stored_chunk(store, coord, disk_io::GRASS_MASK_EXTENSION)
+                .map(|data| data.unwrap_or_default()),
+            Err(e) => Err(e),
+        };
+        let data = match data {
+            Ok(data) => data,
+            Err(_) => {
+                report.corrupt.push(coord);
+                continue;
+            }
+        };
+        report.chunks += 1;
+        report.bytes += data.len();
+        let size = data.len();
+
+        let chunk = match disk_io::decode_any_chunk(&data) {
+            Ok(chunk) => chunk,
+            Err(_) => {
+                report.corrupt.push(coord);
+                continue;
+            }
+        };
+        if chunk.coord() != coord {
+            report.misplaced.push(coord);
+            continue;
+        }
+        if let AnyChunk::Octree(c) | AnyChunk::Svdag(c) | AnyChunk::Attributed(c) = &chunk {
+            let issues = match &dictionary {
+                Some(dictionary) => dictionary.resolve(&c.octree).validate(),
+                None => c.octree.validate(),
+            };
+            if !issues.is_empty() {
+                report.invalid.push((coord, issues));
+                continue;
+            }
+        }
+        report.good.push(coord);
+        report.good_bytes += size;
+    }
+
+    // Compare with the manifest's chunk list (layers like grass have none).
+    // Damaged chunks are already reported above, so only good ones count as extra
+    let good: BTreeSet<(i32, i32, i32)> = report.good.iter().map(|c| (c.x, c.y, c.z)).collect();
+    if let Some(list) = layer["chunks"].as_array() {
+        let listed: BTreeSet<(i32, i32, i32)> = disk_io::parse_manifest_chunk_list(list)
+            .iter()
+            .map(|c| (c.x, c.y, c.z))
+            .collect();
+        report.missing = listed.difference(&stored).map(|&(x, y, z)| ChunkCoord::new(x, y, z)).collect();
+        report.extra = good.difference(&listed).map(|&(x, y, z)| ChunkCoord::new(x, y, z)).collect();
+    }
+
+    // chunk_count and total_bytes both describe the good chunks, as --fix writes them
+    let count = layer["chunk_count"].as_u64().unwrap_or(0);
+    if count != report.good.len() as u64 {
+        report.count_mismatch = Some((count, report.good.len()));
+    }
+    let bytes = layer["total_bytes"].as_u64().unwrap_or(0);
+    if bytes != report.good_bytes as u64 {
+        report.bytes_mismatch = Some((bytes, report.good_bytes));
+    }
+
+    report
+}
+
+fn print_report(name: &str, report: &LayerReport) {
+    println!();
+    println!("Layer '{}': {} chunks, {:.1} KB", name, report.chunks, report.bytes as f64 / 1024.0);
+
+    print_coords("corrupt", &report.corrupt);
+    for (coord, issues) in report.invalid.iter().take(10) {
+        println!("  invalid octree ({},{},{}): {}", coord.x, coord.y, coord.z, issues.join("; "));
+    }
+    if report.invalid.len() > 10 {
+        println!("  ... {} more invalid octrees", report.invalid.len() - 10);
+    }
+    print_coords("payload coordinate mismatch", &report.misplaced);
+    print_coords("listed in manifest but missing", &report.missing);
+    print_coords("on disk but not in manifest", &report.extra);
+    if let Some((manifest, disk)) = report.count_mismatch {
+        println!("  chunk_count: manifest says {}, disk has {} good", manifest, disk);
+    }
+    if let Some((manifest, disk)) = report.bytes_mismatch {
+        println!("  total_bytes: manifest says {}, disk has {} in good chunks", manifest, disk);
+    }
+    if report.problem_count() == 0 {
+        println!("  OK");
+    }
+}
+
+fn print_coords(label: &str, coords: &[ChunkCoord]) {
+    if coords.is_empty() {
+        return;
+    }
+    let shown: Vec<String> = coords.iter().take(8)
+        .map(|c| format!("({},{},{})", c.x, c.y, c.z))
+        .collect();
+    let more = if coords.len() > 8 { format!(" ... +{}", coords.len() - 8) } else { String::new() };
+    println!("  {} {}: {}{}", coords.len(), label, shown.join(" "), more);
+}
+
+/// Rewrite a layer's manifest entry from the decodable chunks on disk
+fn fix_layer(layer: &mut Value, report: &LayerReport) {
+    layer["chunk_count"] = json!(report.good.len());
+    layer["total_bytes"] = json!(report.good_bytes);
+    if layer.get("chunks").is_some() {
+        layer["chunks"] = Value::Array(disk_io::manifest_chunk_list(&report.good));
+    }
+    if layer.get("y_levels").is_some() {
+        let mut y_levels: BTreeMap<i32, usize> = BTreeMap::new();
+        for c in &report.good {
+            *y_levels.entry(c.y).or_insert(0) += 1;
+        }
+        layer["y_levels"] = json!(y_levels);
+    }
+}
+
+fn parse_str_arg(args: &[String], flag: &str) -> Option<String> {
+    args.iter().position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
@@ -0,0 +1,217 @@
+//! Mesh voxelization utility.
+//!
+//! Converts OBJ and glTF props into voxel octrees and adds them to the
+//! clutter library.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use rktri::clutter::ClutterLibrary;
+use rktri::voxel::chunk::VOXELS_PER_METER;
+use rktri::voxel::import::{self, obj, gltf, FillRule, VoxelizeOptions};
+use rktri::voxel::material;
+
+const DEFAULT_OUTPUT_DIR: &str = "assets/clutter";
+
+#[derive(Debug)]
+struct Args {
+    inputs: Vec<PathBuf>,
+    output_dir: PathBuf,
+    name: Option<String>,
+    resolution: u32,
+    scale: f32,
+    fill: FillRule,
+    material: String,
+    mesh_materials: Vec<(String, String)>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+
+    let mut inputs = Vec::new();
+    let mut output_dir = PathBuf::from(DEFAULT_OUTPUT_DIR);
+    let mut name: Option<String> = None;
+    let mut resolution = VOXELS_PER_METER;
+    let mut scale = 1.0f32;
+    let mut fill = FillRule::default();
+    let mut material = "stone".to_string();
+    let mut mesh_materials = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output-dir" => {
+                if let Some(v) = args.next() {
+                    output_dir = PathBuf::from(v);
+                }
+            }
+            "-n" | "--name" => {
+                name = args.next();
+            }
+            "-r" | "--resolution" => {
+                if let Some(v) = args.next() {
+                    resolution = v.parse().map_err(|_| format!("Invalid resolution: {}", v))?;
+                }
+            }
+            "-s" | "--scale" => {
+                if let Some(v) = args.next() {
+                    scale = v.parse().map_err(|_| format!("Invalid scale: {}", v))?;
+                }
+            }
+            "-f" | "--fill" => {
+                if let Some(v) = args.next() {
+                    fill = FillRule::from_name(&v)
+                        .ok_or_else(|| format!("Unknown fill rule: {}. Valid rules: winding, parity, surface", v))?;
+                }
+            }
+            "-m" | "--material" => {
+                if let Some(v) = args.next() {
+                    material = v;
+                }
+            }
+            "--mesh-material" => {
+                let v = args.next().ok_or("Missing value for --mesh-material")?;
+                let (mesh_material, voxel_material) = v.split_once('=')
+                    .ok_or_else(|| format!("--mesh-material expects MESH=MATERIAL, got {}", v))?;
+                mesh_materials.push((mesh_material.to_string(), voxel_material.to_string()));
+            }
+            "-h" | "--help" | "help" => {
+                return Err("show_help".to_string());
+            }
+            other if !other.starts_with('-') => inputs.push(PathBuf::from(other)),
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err("No input meshes given".to_string());
+    }
+    if name.is_some() && inputs.len() > 1 {
+        return Err("--name needs a single input mesh".to_string());
+    }
+    if resolution == 0 {
+        return Err("Resolution must be at least 1 voxel per meter".to_string());
+    }
+
+    Ok(Args {
+        inputs,
+        output_dir,
+        name,
+        resolution,
+        scale,
+        fill,
+        material,
+        mesh_materials,
+    })
+}
+
+fn print_help() {
+    println!("Mesh Voxelization Utility");
+    println!("=========================");
+    println!();
+    println!("Usage: voxelize_mesh [OPTIONS] <MESH>...");
+    println!();
+    println!("Meshes: .obj (with .mtl materials), .gltf or .glb");
+    println!();
+    println!("Options:");
+    println!("  -o, --output-dir <DIR>        Clutter library directory (default: assets/clutter)");
+    println!("  -n, --name <NAME>             Clutter name (default: mesh file stem)");
+    println!("  -r, --resolution <N>          Voxels per meter (default: {})", VOXELS_PER_METER);
+    println!("  -s, --scale <N>               Mesh units to meters (default: 1.0)");
+    println!("  -f, --fill <RULE>             Interior fill: winding, parity or surface (default: winding)");
+    println!("  -m, --material <NAME>         Voxel material (default: stone)");
+    println!("  --mesh-material <MESH=NAME>   Voxel material for a mesh material; repeatable");
+    println!();
+    println!("Examples:");
+    println!("  voxelize_mesh props/barrel.glb");
+    println!("  voxelize_mesh --scale 0.01 --fill parity props/crate.obj");
+    println!("  voxelize_mesh --mesh-material Bark=bark --mesh-material Leaves=leaves stump.gltf");
+}
+
+fn voxelize_file(path: &Path, name: &str, options: &VoxelizeOptions, library: &mut ClutterLibrary) -> Result<(), String> {
+    let start = Instant::now();
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let mesh = match extension.as_str() {
+        "obj" => obj::load_obj(path),
+        "gltf" | "glb" => gltf::load_gltf(path),
+        other => return Err(format!("Unknown mesh format: .{}", other)),
+    }
+    .map_err(|e| e.to_string())?;
+
+    let model = import::voxelize(&mesh, options).map_err(|e| e.to_string())?;
+    let (id, octree_path) = model.save_clutter(library, name).map_err(|e| e.to_string())?;
+
+    let size = model.bounds_max - model.bounds_min;
+    println!("  {} -> '{}' (id {})", path.display(), name, id);
+    println!("    {} triangles, {} textures", mesh.triangles.len(), mesh.textures.len());
+    println!("    {:.2} x {:.2} x {:.2} m, {} voxels, {} nodes, {} bricks",
+             size.x, size.y, size.z, model.voxel_count,
+             model.octree.node_count(), model.octree.brick_count());
+    println!("    Octree: {} ({:.2}s)", octree_path.display(), start.elapsed().as_secs_f64());
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            if e == "show_help" {
+                print_help();
+                return;
+            }
+            eprintln!("Error: {}", e);
+            print_help();
+            std::process::exit(1);
+        }
+    };
+
+    println!("Mesh Voxelization Utility");
+    println!("=========================");
+    println!("Output directory: {}", args.output_dir.display());
+    println!("Resolution: {} voxels/m", args.resolution);
+    println!("Scale: {}", args.scale);
+    println!("Fill: {:?}", args.fill);
+    println!();
+
+    let registry = material::registry();
+    let material_id = |name: &str| {
+        registry.id(name).unwrap_or_else(|| {
+            eprintln!("Error: unknown material '{}'", name);
+            std::process::exit(1);
+        })
+    };
+    let mut options = VoxelizeOptions::default()
+        .with_resolution(args.resolution)
+        .with_scale(args.scale)
+        .with_fill(args.fill)
+        .with_material(material_id(&args.material));
+    for (mesh_material, voxel_material) in &args.mesh_materials {
+        options = options.with_mesh_material(mesh_material.clone(), material_id(voxel_material));
+    }
+
+    let mut library = match ClutterLibrary::open_sync(args.output_dir.clone()) {
+        Ok(library) => library,
+        Err(e) => {
+            eprintln!("Error: failed to open clutter library: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let start = Instant::now();
+    let mut failed = 0;
+    for input in &args.inputs {
+        let name = args.name.clone().unwrap_or_else(|| {
+            input.file_stem().map_or("mesh".to_string(), |s| s.to_string_lossy().into_owned())
+        });
+        if let Err(e) = voxelize_file(input, &name, &options, &mut library) {
+            eprintln!("  {}: {}", input.display(), e);
+            failed += 1;
+        }
+    }
+
+    println!();
+    println!("Voxelized {} of {} meshes in {:.2}s ({} clutter objects in library)",
+             args.inputs.len() - failed, args.inputs.len(), start.elapsed().as_secs_f64(), library.len());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
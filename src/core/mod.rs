@@ -7,6 +7,7 @@ pub mod time;
 pub mod camera;
 pub mod input;
 pub mod camera_controller;
+pub mod scheduler;
 // pub mod transform;
 
 pub use types::*;
@@ -0,0 +1,413 @@
+//! Frame-time-budgeted main-thread work scheduler
+//!
+//! Chunk uploads, brick re-bakes, octree buffer compactions and mask rebuilds
+//! are queued here instead of running as soon as they are reached. Each frame the scheduler runs queued
+//! work by priority class until its millisecond budget is spent, estimating
+//! each job's cost from the durations it measured for earlier jobs of the
+//! same kind. Work that waited too long runs regardless of the budget, one
+//! job per priority class per frame.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Kind of main-thread work, each with its own learned cost estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkKind {
+    /// GPU upload of a chunk octree (`OctreeBuffer::upload_chunk_incremental`)
+    ChunkUpload,
+    /// Re-baking the bricks of edited chunks
+    BrickRebake,
+    /// Rebuilding chunk masks (grass, clutter, biome) and the chunk tables
+    /// they are indexed by
+    MaskRebuild,
+    /// Compacting the GPU octree buffer to make room for uploads, kept apart
+    /// so its cost doesn't skew the upload estimate
+    Compaction,
+}
+
+impl WorkKind {
+    const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Priority class of queued work, highest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WorkPriority {
+    /// Needed by what is on screen now
+    Visible,
+    /// Predicted to be needed soon
+    Prefetch,
+    /// Everything else
+    Background,
+}
+
+impl WorkPriority {
+    const COUNT: usize = 3;
+    const ALL: [WorkPriority; Self::COUNT] = [Self::Visible, Self::Prefetch, Self::Background];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// What the scheduler ran and deferred in one frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SchedulerStats {
+    /// Frame budget (ms)
+    pub budget_ms: f32,
+    /// Time spent running work (ms)
+    pub used_ms: f32,
+    /// Jobs run
+    pub ran: u32,
+    /// Jobs run past the budget because they waited too long (at most one
+    /// per priority class)
+    pub forced: u32,
+    /// Jobs left queued, per priority class (visible, prefetch, background)
+    pub deferred: [u32; WorkPriority::COUNT],
+}
+
+impl SchedulerStats {
+    /// Total jobs left queued
+    pub fn deferred_total(&self) -> u32 {
+        self.deferred.iter().sum()
+    }
+}
+
+/// Queued job
+struct Job<T> {
+    kind: WorkKind,
+    units: u32,
+    queued_frame: u64,
+    work: T,
+}
+
+/// Runs queued main-thread work within a per-frame time budget
+///
+/// `T` is the caller's description of a job; `run_frame` hands due jobs back
+/// to the caller's executor and times them.
+pub struct FrameScheduler<T> {
+    /// Per-frame budget (ms)
+    budget_ms: f32,
+    /// Frames a job may wait before it runs regardless of the budget
+    max_wait_frames: u64,
+    /// Weight of the newest measurement in the cost estimates (0..1)
+    smoothing: f32,
+    /// Learned cost per unit of work (ms) per kind; None until measured
+    estimates: [Option<f32>; WorkKind::COUNT],
+    /// Queued jobs per priority class, oldest first
+    queues: [VecDeque<Job<T>>; WorkPriority::COUNT],
+    frame: u64,
+    last_stats: SchedulerStats,
+    /// Time since a fixed point, used to measure jobs
+    clock: Box<dyn Fn() -> Duration + Send>,
+}
+
+impl<T> FrameScheduler<T> {
+    /// Create a scheduler with a per-frame budget in milliseconds
+    pub fn new(budget_ms: f32) -> Self {
+        let start = Instant::now();
+        Self {
+            budget_ms,
+            max_wait_frames: 30,
+            smoothing: 0.2,
+            estimates: [None; WorkKind::COUNT],
+            queues: std::array::from_fn(|_| VecDeque::new()),
+            frame: 0,
+            last_stats: SchedulerStats::default(),
+            clock: Box::new(move || start.elapsed()),
+        }
+    }
+
+    /// Frames a job may wait before it runs regardless of the budget
+    pub fn with_max_wait_frames(mut self, frames: u64) -> Self {
+        self.max_wait_frames = frames;
+        self
+    }
+
+    /// Seed the cost estimate of a kind (ms per unit), e.g. from a previous run
+    pub fn with_estimate(mut self, kind: WorkKind, ms_per_unit: f32) -> Self {
+        self.estimates[kind.index()] = Some(ms_per_unit);
+        self
+    }
+
+    /// Measure jobs with another clock (time since a fixed point), e.g. a
+    /// fake one in tests
+    pub fn with_clock(mut self, clock: impl Fn() -> Duration + Send + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Change the per-frame budget
+    pub fn set_budget_ms(&mut self, budget_ms: f32) {
+        self.budget_ms = budget_ms;
+    }
+
+    /// Queue a job. `units` scales its cost estimate (e.g. bricks to re-bake).
+    pub fn schedule(&mut self, kind: WorkKind, priority: WorkPriority, units: u32, work: T) {
+        self.queues[priority.index()].push_back(Job {
+            kind,
+            units: units.max(1),
+            queued_frame: self.frame,
+            work,
+        });
+    }
+
+    /// Run this frame's work through `execute`.
+    ///
+    /// The oldest job of each priority class runs first regardless of the
+    /// budget if it waited `max_wait_frames`; further starved jobs keep
+    /// waiting and are forced on later frames, so a backlog never stalls a
+    /// single frame. Then jobs run by priority class, oldest first within a
+    /// class, while their estimated cost fits the remaining budget. At least
+    /// one job runs per frame, and a kind with no estimate yet runs one job
+    /// per frame until it has one.
+    pub fn run_frame(&mut self, mut execute: impl FnMut(T)) -> SchedulerStats {
+        self.frame += 1;
+        let mut stats = SchedulerStats { budget_ms: self.budget_ms, ..Default::default() };
+        let mut probed = [false; WorkKind::COUNT];
+
+        // Starvation protection
+        for priority in WorkPriority::ALL {
+            let queue = priority.index();
+            let starved = self.queues[queue].front()
+                .is_some_and(|job| self.frame - job.queued_frame >= self.max_wait_frames);
+            if starved {
+                let job = self.queues[queue].pop_front().expect("starved job is queued");
+                stats.used_ms += self.execute(job, &mut execute);
+                stats.ran += 1;
+                stats.forced += 1;
+            }
+        }
+
+        'classes: for priority in WorkPriority::ALL {
+            let queue = priority.index();
+            while let Some(job) = self.queues[queue].front() {
+                let kind = job.kind.index();
+                let fits = match self.estimates[kind] {
+                    Some(per_unit) => stats.used_ms + per_unit * job.units as f32 <= self.budget_ms,
+                    None => !probed[kind] && stats.used_ms < self.budget_ms,
+                };
+                if !fits && stats.ran > 0 {
+                    break 'classes;
+                }
+                probed[kind] = true;
+                let job = self.queues[queue].pop_front().expect("front job is queued");
+                stats.used_ms += self.execute(job, &mut execute);
+                stats.ran += 1;
+            }
+        }
+
+        self.finish_frame(stats)
+    }
+
+    /// Run every queued job regardless of the budget, by priority class
+    /// (e.g. while loading, before the first frame)
+    pub fn run_all(&mut self, mut execute: impl FnMut(T)) -> SchedulerStats {
+        self.frame += 1;
+        let mut stats = SchedulerStats { budget_ms: self.budget_ms, ..Default::default() };
+        for priority in WorkPriority::ALL {
+            while let Some(job) = self.queues[priority.index()].pop_front() {
+                stats.used_ms += self.execute(job, &mut execute);
+                stats.ran += 1;
+            }
+        }
+        self.finish_frame(stats)
+    }
+
+    /// Number of queued jobs
+    pub fn queued(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Number of queued jobs in a priority class
+    pub fn queued_in(&self, priority: WorkPriority) -> usize {
+        self.queues[priority.index()].len()
+    }
+
+    /// Learned cost per unit of a kind (ms), if measured
+    pub fn estimate(&self, kind: WorkKind) -> Option<f32> {
+        self.estimates[kind.index()]
+    }
+
+    /// Stats of the last `run_frame`
+    pub fn last_stats(&self) -> SchedulerStats {
+        self.last_stats
+    }
+
+    /// Record what is left queued and keep the frame's stats
+    fn finish_frame(&mut self, mut stats: SchedulerStats) -> SchedulerStats {
+        for priority in WorkPriority::ALL {
+            stats.deferred[priority.index()] = self.queues[priority.index()].len() as u32;
+        }
+        self.last_stats = stats;
+        stats
+    }
+
+    /// Run a job, learn from its duration and return it (ms)
+    fn execute(&mut self, job: Job<T>, execute: &mut impl FnMut(T)) -> f32 {
+        let start = (self.clock)();
+        execute(job.work);
+        let ms = ((self.clock)() - start).as_secs_f32() * 1000.0;
+
+        let per_unit = ms / job.units as f32;
+        let estimate = &mut self.estimates[job.kind.index()];
+        *estimate = Some(match *estimate {
+            Some(old) => old + (per_unit - old) * self.smoothing,
+            None => per_unit,
+        });
+        ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// Clock that only moves when jobs advance it
+    #[derive(Clone, Default)]
+    struct FakeClock(Arc<AtomicU64>);
+
+    impl FakeClock {
+        fn advance_ms(&self, ms: u64) {
+            self.0.fetch_add(ms * 1000, Ordering::Relaxed);
+        }
+
+        fn source(&self) -> impl Fn() -> Duration + Send + 'static {
+            let micros = self.0.clone();
+            move || Duration::from_micros(micros.load(Ordering::Relaxed))
+        }
+    }
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_budget_limits_work_per_frame() {
+        let clock = FakeClock::default();
+        let mut scheduler = FrameScheduler::new(10.0)
+            .with_clock(clock.source())
+            .with_estimate(WorkKind::ChunkUpload, 3.0);
+        for i in 0..10 {
+            scheduler.schedule(WorkKind::ChunkUpload, WorkPriority::Visible, 1, i);
+        }
+
+        let mut done = Vec::new();
+        let mut execute = |i| {
+            clock.advance_ms(3);
+            done.push(i);
+        };
+        // 3 ms jobs: three fit in 10 ms
+        let stats = scheduler.run_frame(&mut execute);
+        assert_eq!(stats.ran, 3);
+        assert_eq!(stats.deferred, [7, 0, 0]);
+        assert!(approx(stats.used_ms, 9.0), "used {}", stats.used_ms);
+
+        let next = scheduler.run_frame(&mut execute);
+        assert_eq!(next.ran, 3);
+        assert_eq!(done, (0..6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_priority_order_and_learning() {
+        let clock = FakeClock::default();
+        let mut scheduler = FrameScheduler::new(100.0).with_clock(clock.source());
+        scheduler.schedule(WorkKind::MaskRebuild, WorkPriority::Background, 1, "background");
+        scheduler.schedule(WorkKind::MaskRebuild, WorkPriority::Prefetch, 1, "prefetch");
+        scheduler.schedule(WorkKind::MaskRebuild, WorkPriority::Visible, 2, "visible");
+
+        let mut order = Vec::new();
+        scheduler.run_frame(|name| {
+            clock.advance_ms(2);
+            order.push(name);
+        });
+        assert_eq!(order, vec!["visible", "prefetch", "background"]);
+
+        // 1 ms per unit from the 2-unit job, then two 2 ms jobs smoothed in
+        let estimate = scheduler.estimate(WorkKind::MaskRebuild).unwrap();
+        assert!(approx(estimate, 1.36), "estimate {}", estimate);
+        assert_eq!(scheduler.estimate(WorkKind::BrickRebake), None);
+    }
+
+    #[test]
+    fn test_starved_work_runs_past_budget() {
+        let clock = FakeClock::default();
+        let mut scheduler = FrameScheduler::new(1.0)
+            .with_clock(clock.source())
+            .with_max_wait_frames(3)
+            .with_estimate(WorkKind::BrickRebake, 5.0);
+        scheduler.schedule(WorkKind::BrickRebake, WorkPriority::Background, 1, "background");
+
+        let mut background_frame = None;
+        for frame in 1..=5 {
+            // Visible work over the budget arrives every frame
+            scheduler.schedule(WorkKind::BrickRebake, WorkPriority::Visible, 1, "visible");
+            let stats = scheduler.run_frame(|name| {
+                clock.advance_ms(5);
+                if name == "background" {
+                    background_frame = Some(frame);
+                }
+            });
+            if background_frame == Some(frame) {
+                assert_eq!(stats.forced, 1);
+            }
+        }
+        assert_eq!(background_frame, Some(3));
+        assert_eq!(scheduler.queued_in(WorkPriority::Background), 0);
+    }
+
+    #[test]
+    fn test_starved_backlog_spread_over_frames() {
+        let clock = FakeClock::default();
+        let mut scheduler = FrameScheduler::new(1.0)
+            .with_clock(clock.source())
+            .with_max_wait_frames(2)
+            .with_estimate(WorkKind::ChunkUpload, 5.0);
+        for i in 0..3 {
+            scheduler.schedule(WorkKind::ChunkUpload, WorkPriority::Prefetch, 1, i);
+            scheduler.schedule(WorkKind::ChunkUpload, WorkPriority::Background, 1, i);
+        }
+        let mut execute = |_| clock.advance_ms(5);
+
+        // Nothing starved yet: one job over the budget
+        let stats = scheduler.run_frame(&mut execute);
+        assert_eq!((stats.ran, stats.forced), (1, 0));
+
+        // Every job is starved from now on, but each frame forces only the
+        // oldest of each class
+        let stats = scheduler.run_frame(&mut execute);
+        assert_eq!((stats.ran, stats.forced), (2, 2));
+        assert_eq!(stats.deferred, [0, 1, 2]);
+        let stats = scheduler.run_frame(&mut execute);
+        assert_eq!((stats.ran, stats.forced), (2, 2));
+        let stats = scheduler.run_frame(&mut execute);
+        assert_eq!((stats.ran, stats.forced), (1, 1));
+        assert_eq!(scheduler.queued(), 0);
+    }
+
+    #[test]
+    fn test_run_all_ignores_budget() {
+        let clock = FakeClock::default();
+        let mut scheduler = FrameScheduler::new(1.0)
+            .with_clock(clock.source())
+            .with_estimate(WorkKind::ChunkUpload, 5.0);
+        for i in 0..4 {
+            scheduler.schedule(WorkKind::ChunkUpload, WorkPriority::Background, 1, i);
+        }
+        scheduler.schedule(WorkKind::MaskRebuild, WorkPriority::Visible, 1, 4);
+
+        let mut done = Vec::new();
+        let stats = scheduler.run_all(|i| {
+            clock.advance_ms(5);
+            done.push(i);
+        });
+        assert_eq!(stats.ran, 5);
+        assert_eq!(done, vec![4, 0, 1, 2, 3]);
+        assert_eq!(scheduler.queued(), 0);
+    }
+}
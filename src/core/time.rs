@@ -3,6 +3,8 @@
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+use super::scheduler::SchedulerStats;
+
 /// FPS statistics for a time window
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct FpsWindow {
@@ -19,6 +21,8 @@ pub struct FpsStats {
     pub fifteen_sec: FpsWindow,
     pub current_fps: f32,
     pub frame_count: u64,
+    /// What the frame scheduler ran and deferred last frame
+    pub scheduler: SchedulerStats,
 }
 
 /// Tracks frame timing and calculates FPS
@@ -31,6 +35,8 @@ pub struct FrameTimer {
     fps_frame_count: u32,
     /// Ring buffer of (timestamp, frame_time_secs) for rolling stats
     frame_history: VecDeque<(Instant, f32)>,
+    /// Last frame's scheduler stats
+    scheduler: SchedulerStats,
 }
 
 impl FrameTimer {
@@ -45,6 +51,7 @@ impl FrameTimer {
             fps: 0.0,
             fps_frame_count: 0,
             frame_history: VecDeque::new(),
+            scheduler: SchedulerStats::default(),
         }
     }
 
@@ -99,6 +106,16 @@ impl FrameTimer {
         self.frame_count
     }
 
+    /// Record what the frame scheduler ran and deferred this frame
+    pub fn record_scheduler(&mut self, stats: SchedulerStats) {
+        self.scheduler = stats;
+    }
+
+    /// Get the last recorded scheduler stats
+    pub fn scheduler_stats(&self) -> SchedulerStats {
+        self.scheduler
+    }
+
     /// Get rolling FPS statistics over 1s, 5s, and 15s windows
     pub fn fps_stats(&self) -> FpsStats {
         let now = Instant::now();
@@ -113,6 +130,7 @@ impl FrameTimer {
             fifteen_sec,
             current_fps: self.fps,
             frame_count: self.frame_count,
+            scheduler: self.scheduler,
         }
     }
 
@@ -0,0 +1,549 @@
+//! 3D density terrain — caves, caverns and overhangs.
+//!
+//! The heightfield from `TerrainGenerator` is turned into a volume:
+//! - a solid crust `crust_depth` meters thick below the surface,
+//! - the surface displaced vertically by 3D noise (overhangs where the
+//!   displacement changes faster than the terrain rises),
+//! - worm tunnels where two independent noise fields are both near zero,
+//! - cavern chambers where a low-frequency noise exceeds a threshold.
+//!
+//! `classify_region` bounds every term with Lipschitz estimates around the
+//! region center, so `AdaptiveOctreeBuilder` can skip empty air, empty space
+//! below the crust, and solid rock that no cave can reach.
+
+use glam::Vec3;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use crate::math::Aabb;
+use crate::mask::{BiomeId, MaskOctree};
+use crate::terrain::biome::Biome;
+use crate::terrain::generator::{TerrainGenerator, TerrainParams};
+use crate::voxel::attributes::VoxelAttributes;
+use crate::voxel::sdf::{encode_gradient, GRADIENT_RANGE};
+use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
+use crate::voxel::voxel::Voxel;
+
+/// Upper bound on the gradient magnitude of a single Perlin octave
+/// (noise units per input unit). Deliberately generous.
+const PERLIN_LIPSCHITZ: f32 = 3.0;
+
+/// Upper bound on the absolute value of Perlin/FBM noise output.
+const NOISE_BOUND: f32 = 1.1;
+
+/// Voxels less than this far inside the displaced surface use the biome
+/// surface material; deeper voxels use the underground material.
+const SURFACE_LAYER: f32 = 1.0;
+
+/// Parameters for 3D density terrain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaveParams {
+    /// Thickness of solid ground below the surface (m); deeper space stays empty.
+    pub crust_depth: f32,
+    /// Caves never come closer to the heightfield surface than this (m).
+    pub cave_min_depth: f32,
+    /// Depth range below `cave_min_depth` over which caves grow to full size (m).
+    pub cave_fade: f32,
+    /// Frequency of the worm tunnel noise (1/m).
+    pub worm_frequency: f32,
+    /// Tunnel half-width in noise units (larger = wider, more connected tunnels).
+    pub worm_width: f32,
+    /// Frequency of the cavern chamber noise (1/m).
+    pub cavern_frequency: f32,
+    /// Noise value above which caverns are carved.
+    pub cavern_threshold: f32,
+    /// Frequency of the surface displacement noise (1/m).
+    pub overhang_frequency: f32,
+    /// Surface displacement amplitude (m); 0 disables overhangs.
+    pub overhang_amplitude: f32,
+}
+
+impl Default for CaveParams {
+    fn default() -> Self {
+        Self {
+            crust_depth: 24.0,
+            cave_min_depth: 4.0,
+            cave_fade: 4.0,
+            worm_frequency: 1.0 / 24.0,
+            worm_width: 0.08,
+            cavern_frequency: 1.0 / 40.0,
+            cavern_threshold: 0.5,
+            overhang_frequency: 1.0 / 10.0,
+            overhang_amplitude: 4.0,
+        }
+    }
+}
+
+/// Noise fields and bounds for density terrain (shared across chunks).
+pub struct DensityField {
+    params: CaveParams,
+    worm_a: Perlin,
+    worm_b: Perlin,
+    cavern: Perlin,
+    overhang: Fbm<Perlin>,
+    /// Bound on |dh/dx|, |dh/dz| of the heightfield
+    height_lipschitz: f32,
+    /// Bound on the gradient of the overhang FBM (noise units per input unit)
+    overhang_lipschitz: f32,
+}
+
+/// Lipschitz bound of an FBM built from Perlin octaves, matching `noise::Fbm`
+/// (octave i has amplitude persistence^(i+1) and frequency lacunarity^i,
+/// normalized by the sum of amplitudes).
+fn fbm_lipschitz(octaves: u32, persistence: f32, lacunarity: f32) -> f32 {
+    let mut weighted = 0.0;
+    let mut total = 0.0;
+    let mut amplitude = persistence;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        weighted += amplitude * frequency;
+        total += amplitude;
+        amplitude *= persistence;
+        frequency *= lacunarity;
+    }
+    if total > 0.0 {
+        PERLIN_LIPSCHITZ * weighted / total
+    } else {
+        0.0
+    }
+}
+
+/// Range of a noise function over a sphere of `radius` input units around a
+/// sample with value `center`.
+fn noise_range(center: f32, lipschitz: f32, radius: f32) -> (f32, f32) {
+    let spread = lipschitz * radius;
+    ((center - spread).max(-NOISE_BOUND), (center + spread).min(NOISE_BOUND))
+}
+
+impl DensityField {
+    const OVERHANG_OCTAVES: u32 = 2;
+
+    /// Create noise fields for the given terrain.
+    pub fn new(params: CaveParams, terrain_params: &TerrainParams) -> Self {
+        let seed = terrain_params.seed;
+        let overhang = Fbm::<Perlin>::new(seed.wrapping_add(404))
+            .set_octaves(Self::OVERHANG_OCTAVES as usize)
+            .set_persistence(0.5)
+            .set_lacunarity(2.0);
+
+        let height_lipschitz = 0.5 * terrain_params.height_scale / terrain_params.scale
+            * fbm_lipschitz(terrain_params.octaves, terrain_params.persistence, terrain_params.lacunarity);
+
+        Self {
+            params,
+            worm_a: Perlin::new(seed.wrapping_add(101)),
+            worm_b: Perlin::new(seed.wrapping_add(202)),
+            cavern: Perlin::new(seed.wrapping_add(303)),
+            overhang,
+            height_lipschitz,
+            overhang_lipschitz: fbm_lipschitz(Self::OVERHANG_OCTAVES, 0.5, 2.0),
+        }
+    }
+
+    /// Get cave parameters.
+    pub fn params(&self) -> &CaveParams {
+        &self.params
+    }
+
+    /// How far solid ground can extend (below, above) the heightfield surface.
+    ///
+    /// Used to pick which chunk Y levels to generate per column.
+    pub fn vertical_extent(&self) -> (f32, f32) {
+        (self.params.crust_depth, self.params.overhang_amplitude * NOISE_BOUND)
+    }
+
+    fn sample(noise: &impl NoiseFn<f64, 3>, p: Vec3, frequency: f32) -> f32 {
+        let q = p * frequency;
+        noise.get([q.x as f64, q.y as f64, q.z as f64]) as f32
+    }
+
+    /// Vertical surface displacement at `pos` (m).
+    fn overhang_at(&self, pos: Vec3) -> f32 {
+        if self.params.overhang_amplitude == 0.0 {
+            return 0.0;
+        }
+        self.params.overhang_amplitude * Self::sample(&self.overhang, pos, self.params.overhang_frequency)
+    }
+
+    /// Signed distance-like value to the displaced surface: positive below it.
+    pub fn surface_density(&self, terrain: &TerrainGenerator, pos: Vec3) -> f32 {
+        terrain.height_at(pos.x, pos.z) - pos.y + self.overhang_at(pos)
+    }
+
+    /// Cave growth factor (0 = no caves, 1 = full size) at a heightfield depth.
+    fn cave_gate(&self, depth: f32) -> f32 {
+        let p = &self.params;
+        if p.cave_fade <= 0.0 {
+            return if depth >= p.cave_min_depth { 1.0 } else { 0.0 };
+        }
+        ((depth - p.cave_min_depth) / p.cave_fade).clamp(0.0, 1.0)
+    }
+
+    /// Check if a cave is carved at `pos`, given the heightfield depth there.
+    pub fn is_cave(&self, pos: Vec3, depth: f32) -> bool {
+        let gate = self.cave_gate(depth);
+        if gate <= 0.0 {
+            return false;
+        }
+        let p = &self.params;
+
+        let width = p.worm_width * gate;
+        let a = Self::sample(&self.worm_a, pos, p.worm_frequency);
+        if a.abs() < width && Self::sample(&self.worm_b, pos, p.worm_frequency).abs() < width {
+            return true;
+        }
+
+        // Threshold rises to the noise bound as the gate closes
+        let threshold = p.cavern_threshold + (1.0 - gate) * (NOISE_BOUND - p.cavern_threshold).max(0.0);
+        Self::sample(&self.cavern, pos, p.cavern_frequency) > threshold
+    }
+
+    /// Check if `pos` is solid.
+    pub fn is_solid(&self, terrain: &TerrainGenerator, pos: Vec3) -> bool {
+        let depth = terrain.height_at(pos.x, pos.z) - pos.y;
+        if depth > self.params.crust_depth {
+            return false;
+        }
+        if depth + self.overhang_at(pos) <= 0.0 {
+            return false;
+        }
+        !self.is_cave(pos, depth)
+    }
+}
+
+/// Density terrain classifier — the 3D counterpart of `MaskDrivenTerrainClassifier`.
+///
+/// Biome materials come from the same biome mask; surface voxels facing up get
+/// the heightfield gradient encoding so they shade like regular terrain.
+pub struct DensityTerrainClassifier<'a> {
+    terrain: &'a TerrainGenerator,
+    field: &'a DensityField,
+    biome_mask: &'a MaskOctree<BiomeId>,
+    /// World-space origin of the chunk (for mask lookups).
+    chunk_origin: Vec3,
+    voxel_size: f32,
+}
+
+impl<'a> DensityTerrainClassifier<'a> {
+    pub fn new(
+        terrain: &'a TerrainGenerator,
+        field: &'a DensityField,
+        biome_mask: &'a MaskOctree<BiomeId>,
+        chunk_origin: Vec3,
+        voxel_size: f32,
+    ) -> Self {
+        Self {
+            terrain,
+            field,
+            biome_mask,
+            chunk_origin,
+            voxel_size,
+        }
+    }
+
+    /// Gradient of the displaced surface density (central differences)
+    fn surface_gradient(&self, pos: Vec3) -> Vec3 {
+        let eps = self.voxel_size;
+        let density = |p: Vec3| self.field.surface_density(self.terrain, p);
+        Vec3::new(
+            density(pos + Vec3::X * eps) - density(pos - Vec3::X * eps),
+            density(pos + Vec3::Y * eps) - density(pos - Vec3::Y * eps),
+            density(pos + Vec3::Z * eps) - density(pos - Vec3::Z * eps),
+        ) / (2.0 * eps)
+    }
+
+    /// Check whether any cave could be carved inside the region.
+    fn cave_possible(&self, center: Vec3, radius: f32, max_depth: f32) -> bool {
+        let field = self.field;
+        let p = &field.params;
+        if max_depth <= p.cave_min_depth {
+            return false;
+        }
+
+        let worm_lipschitz = PERLIN_LIPSCHITZ * p.worm_frequency;
+        let (a_lo, a_hi) = noise_range(DensityField::sample(&field.worm_a, center, p.worm_frequency), worm_lipschitz, radius);
+        let (b_lo, b_hi) = noise_range(DensityField::sample(&field.worm_b, center, p.worm_frequency), worm_lipschitz, radius);
+        let min_abs = |lo: f32, hi: f32| if lo <= 0.0 && hi >= 0.0 { 0.0 } else { lo.abs().min(hi.abs()) };
+        if min_abs(a_lo, a_hi) < p.worm_width && min_abs(b_lo, b_hi) < p.worm_width {
+            return true;
+        }
+
+        let cavern_lipschitz = PERLIN_LIPSCHITZ * p.cavern_frequency;
+        let (_, c_hi) = noise_range(DensityField::sample(&field.cavern, center, p.cavern_frequency), cavern_lipschitz, radius);
+        c_hi > p.cavern_threshold
+    }
+}
+
+impl<'a> RegionClassifier for DensityTerrainClassifier<'a> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        let field = self.field;
+        let p = &field.params;
+
+        let center = aabb.center();
+        let half = aabb.half_extent();
+        let radius = half.length();
+        let radius_xz = Vec3::new(half.x, 0.0, half.z).length();
+
+        // Heightfield range over the region's XZ footprint
+        let h = self.terrain.height_at(center.x, center.z);
+        let h_lo = h - field.height_lipschitz * radius_xz;
+        let h_hi = h + field.height_lipschitz * radius_xz;
+
+        // Overhang displacement range
+        let (o_lo, o_hi) = if p.overhang_amplitude == 0.0 {
+            (0.0, 0.0)
+        } else {
+            let (lo, hi) = noise_range(
+                DensityField::sample(&field.overhang, center, p.overhang_frequency),
+                field.overhang_lipschitz * p.overhang_frequency,
+                radius,
+            );
+            (lo * p.overhang_amplitude, hi * p.overhang_amplitude)
+        };
+
+        // Entirely above the displaced surface
+        if h_hi - aabb.min.y + o_hi <= 0.0 {
+            return RegionHint::Empty;
+        }
+
+        // Entirely below the crust
+        if h_lo - aabb.max.y > p.crust_depth {
+            return RegionHint::Empty;
+        }
+
+        // Solid only if the whole region is below the surface layer, above the
+        // crust bottom, and out of reach of every cave
+        let surface_lo = h_lo - aabb.max.y + o_lo;
+        let depth_hi = h_hi - aabb.min.y;
+        if surface_lo <= SURFACE_LAYER || depth_hi > p.crust_depth {
+            return RegionHint::Mixed;
+        }
+        if self.cave_possible(center, radius, depth_hi) {
+            return RegionHint::Mixed;
+        }
+
+        match self.biome_mask.classify_region(self.chunk_origin, aabb) {
+            Some(biome_id) => {
+                let under = Biome::from_id(biome_id).underground_color();
+                RegionHint::Solid { material: under.material_id, color: under.color }
+            }
+            None => RegionHint::Mixed,
+        }
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        if !self.field.is_solid(self.terrain, pos) {
+            return Voxel::EMPTY;
+        }
+
+        let biome = Biome::from_id(self.biome_mask.sample(self.chunk_origin, pos));
+        let surface = self.field.surface_density(self.terrain, pos);
+        if surface >= SURFACE_LAYER {
+            // Interior and cave walls: plain box voxels
+            let under = biome.underground_color();
+            return Voxel::from_rgb565(under.color, under.material_id);
+        }
+
+        // Surface layer: gradient of the displaced surface for shading
+        let eps = self.voxel_size;
+        let grad = self.surface_gradient(pos);
+        let normal = (-grad).normalize_or_zero();
+        let material_id = biome.surface_color().material_id;
+
+        if normal.y <= 0.25 {
+            // Walls and undersides of overhangs: no sub-voxel height plane
+            return Voxel {
+                color: encode_gradient(0.0, 0.0),
+                material_id,
+                flags: 0,
+            };
+        }
+
+        // Upward-facing: same encoding as heightfield terrain
+        let dh_dx = (-normal.x / normal.y).clamp(-GRADIENT_RANGE, GRADIENT_RANGE);
+        let dh_dz = (-normal.z / normal.y).clamp(-GRADIENT_RANGE, GRADIENT_RANGE);
+
+        // Surface crossing within this voxel column, from the local density slope
+        let dy = -grad.y;
+        let surface_y = if dy > 1e-4 { pos.y + surface / dy } else { pos.y + eps };
+        let voxel_bottom = pos.y - self.voxel_size * 0.5;
+        let h_frac = ((surface_y - voxel_bottom) / self.voxel_size).clamp(0.0, 1.0);
+        let flags = ((h_frac * 254.0) as u8).max(1);
+
+        Voxel {
+            color: encode_gradient(dh_dx, dh_dz),
+            material_id,
+            flags,
+        }
+    }
+
+    /// Biome RGB888 color, plus the full 3D surface normal on the surface
+    /// layer (walls and overhang undersides included)
+    fn evaluate_attributes(&self, pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+        let biome = Biome::from_id(self.biome_mask.sample(self.chunk_origin, pos));
+        if self.field.surface_density(self.terrain, pos) >= SURFACE_LAYER {
+            let (r, g, b) = biome.underground_rgb();
+            return VoxelAttributes::NONE.with_albedo(r, g, b);
+        }
+        let (r, g, b) = biome.surface_rgb();
+        let attributes = VoxelAttributes::NONE.with_albedo(r, g, b);
+        match (-self.surface_gradient(pos)).try_normalize() {
+            Some(normal) => attributes.with_normal(normal),
+            None => attributes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generation::biome_gen::BiomeNoiseGenerator;
+    use crate::mask::MaskBuilder;
+    use crate::terrain::biome::BiomeMap;
+
+    fn terrain_params() -> TerrainParams {
+        TerrainParams {
+            scale: 150.0,
+            height_scale: 80.0,
+            octaves: 5,
+            sea_level: 20.0,
+            ..Default::default()
+        }
+    }
+
+    fn biome_mask(terrain: &TerrainGenerator, origin: Vec3) -> MaskOctree<BiomeId> {
+        let biome_map = BiomeMap::new(12345);
+        let biome_gen = BiomeNoiseGenerator::new(&biome_map, terrain, 20.0);
+        MaskBuilder::new(3).build(&biome_gen, origin, 4.0)
+    }
+
+    #[test]
+    fn test_classify_region_is_conservative() {
+        let params = terrain_params();
+        let terrain = TerrainGenerator::new(params.clone());
+        let field = DensityField::new(CaveParams::default(), &params);
+
+        let surface = terrain.height_at(2.0, 2.0);
+        let mut checked_empty = 0;
+        let mut checked_solid = 0;
+
+        // Chunks from well above the surface down past the crust
+        for level in -9..3 {
+            let origin = Vec3::new(0.0, (surface / 4.0).floor() * 4.0 + level as f32 * 4.0, 0.0);
+            let mask = biome_mask(&terrain, origin);
+            let classifier = DensityTerrainClassifier::new(&terrain, &field, &mask, origin, 4.0 / 128.0);
+
+            for size in [4.0f32, 1.0, 0.25] {
+                let steps = (4.0 / size) as i32;
+                for ix in 0..steps {
+                    for iy in 0..steps {
+                        for iz in 0..steps {
+                            let min = origin + Vec3::new(ix as f32, iy as f32, iz as f32) * size;
+                            let aabb = Aabb::new(min, min + Vec3::splat(size));
+                            let hint = classifier.classify_region(&aabb);
+                            if !hint.is_terminal() {
+                                continue;
+                            }
+
+                            // Dense samples must agree with the hint
+                            for s in 0..27 {
+                                let f = Vec3::new((s % 3) as f32, ((s / 3) % 3) as f32, (s / 9) as f32) / 2.0;
+                                let p = aabb.min + (aabb.max - aabb.min) * (f * 0.98 + Vec3::splat(0.01));
+                                let voxel = classifier.evaluate(p);
+                                match hint {
+                                    RegionHint::Empty => assert!(voxel.is_empty(), "Empty region {:?} has solid at {:?}", aabb, p),
+                                    RegionHint::Solid { material, color } => {
+                                        assert_eq!(voxel, Voxel::from_rgb565(color, material), "at {:?}", p);
+                                    }
+                                    _ => unreachable!(),
+                                }
+                            }
+                            match hint {
+                                RegionHint::Empty => checked_empty += 1,
+                                _ => checked_solid += 1,
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // The bounds must still allow early-outs in both directions
+        assert!(checked_empty > 0);
+        assert!(checked_solid > 0);
+    }
+
+    #[test]
+    fn test_caves_below_surface() {
+        let params = terrain_params();
+        let terrain = TerrainGenerator::new(params.clone());
+        let cave_params = CaveParams::default();
+        let field = DensityField::new(cave_params.clone(), &params);
+
+        let mut cave_voxels = 0;
+        let mut near_surface_caves = 0;
+        for ix in 0..64 {
+            for iz in 0..64 {
+                let (x, z) = (ix as f32 * 2.0, iz as f32 * 2.0);
+                let h = terrain.height_at(x, z);
+                for iy in 0..24 {
+                    let depth = iy as f32 + 0.5;
+                    let pos = Vec3::new(x, h - depth, z);
+                    if field.is_cave(pos, depth) {
+                        cave_voxels += 1;
+                        if depth < cave_params.cave_min_depth {
+                            near_surface_caves += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        assert!(cave_voxels > 0, "expected some caves");
+        assert_eq!(near_surface_caves, 0);
+    }
+
+    #[test]
+    fn test_overhangs() {
+        let params = terrain_params();
+        let terrain = TerrainGenerator::new(params.clone());
+        let field = DensityField::new(
+            CaveParams {
+                overhang_amplitude: 8.0,
+                overhang_frequency: 1.0 / 6.0,
+                ..Default::default()
+            },
+            &params,
+        );
+
+        // Look for air directly below solid ground near the surface (not a cave)
+        let mut found = false;
+        'search: for ix in 0..48 {
+            for iz in 0..48 {
+                let (x, z) = (ix as f32, iz as f32);
+                let h = terrain.height_at(x, z);
+                let mut above_solid = false;
+                for iy in 0..40 {
+                    let y = h + 10.0 - iy as f32 * 0.5;
+                    let solid = field.is_solid(&terrain, Vec3::new(x, y, z));
+                    if above_solid && !solid && h - y < field.params().cave_min_depth {
+                        found = true;
+                        break 'search;
+                    }
+                    above_solid |= solid;
+                }
+            }
+        }
+        assert!(found, "expected at least one overhang");
+    }
+
+    #[test]
+    fn test_crust_depth() {
+        let params = terrain_params();
+        let terrain = TerrainGenerator::new(params.clone());
+        let field = DensityField::new(CaveParams::default(), &params);
+
+        let h = terrain.height_at(10.0, 10.0);
+        assert!(!field.is_solid(&terrain, Vec3::new(10.0, h - 30.0, 10.0)));
+        assert!(!field.is_solid(&terrain, Vec3::new(10.0, h + 10.0, 10.0)));
+    }
+}
@@ -1,6 +1,17 @@
 //! Generation configuration extracted from SceneConfig.
 
 use crate::terrain::generator::TerrainParams;
+use super::cave_gen::CaveParams;
+
+/// Shape of the generated terrain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TerrainMode {
+    /// 2D heightfield with a thin surface shell (classic terrain).
+    #[default]
+    Heightfield,
+    /// 3D density field: solid crust with worm caves, caverns and overhangs.
+    Caves(CaveParams),
+}
 
 /// Configuration for the terrain generation pipeline.
 #[derive(Clone, Debug)]
@@ -15,6 +26,10 @@ pub struct GenerationConfig {
     pub grass_mask_depth: u8,
     /// Max depth for clutter mask octree (depth 4 = 16 cells/side = 0.25m resolution).
     pub clutter_mask_depth: u8,
+    /// Heightfield or 3D density terrain.
+    pub terrain_mode: TerrainMode,
+    /// Store per-voxel attributes (normal, albedo, wetness) with terrain bricks.
+    pub terrain_attributes: bool,
 }
 
 impl Default for GenerationConfig {
@@ -25,6 +40,8 @@ impl Default for GenerationConfig {
             biome_mask_depth: 3,
             grass_mask_depth: 5,
             clutter_mask_depth: 4,
+            terrain_mode: TerrainMode::Heightfield,
+            terrain_attributes: false,
         }
     }
 }
@@ -38,6 +55,8 @@ impl GenerationConfig {
             biome_mask_depth: 3,
             grass_mask_depth: 5,
             clutter_mask_depth: 4,
+            terrain_mode: TerrainMode::Heightfield,
+            terrain_attributes: false,
         }
     }
 }
@@ -2,20 +2,23 @@
 //!
 //! The pipeline orchestrates:
 //! 1. Biome mask construction (MaskOctree<BiomeId>)
-//! 2. Terrain octree building (via MaskDrivenTerrainClassifier)
+//! 2. Terrain octree building (via MaskDrivenTerrainClassifier, or
+//!    DensityTerrainClassifier for cave terrain)
 //! 3. Grass mask construction (MaskOctree<GrassCell>)
 //! 4. Clutter mask construction (MaskOctree<ClutterCell>)
 
 pub mod config;
 pub mod biome_gen;
 pub mod terrain_gen;
+pub mod cave_gen;
 pub mod grass_gen;
 pub mod clutter_gen;
 pub mod clutter_to_layer;
 
-pub use config::GenerationConfig;
+pub use config::{GenerationConfig, TerrainMode};
 pub use biome_gen::BiomeNoiseGenerator;
 pub use terrain_gen::MaskDrivenTerrainClassifier;
+pub use cave_gen::{CaveParams, DensityField, DensityTerrainClassifier};
 pub use grass_gen::GrassNoiseGenerator;
 pub use clutter_gen::ClutterNoiseGenerator;
 pub use clutter_to_layer::{ClutterLayerConfig, ClutterToLayerConverter, LayerOctrees};
@@ -43,6 +46,10 @@ pub struct GeneratedChunk {
 /// Orchestrates chunk generation: biome mask → terrain octree → grass mask → clutter mask → layers.
 pub struct GenerationPipeline {
     terrain: TerrainGenerator,
+    /// 3D density field when generating cave terrain
+    density: Option<DensityField>,
+    /// Build terrain octrees with brick attributes
+    terrain_attributes: bool,
     biome_map: BiomeMap,
     biome_mask_depth: u8,
     grass_mask_depth: u8,
@@ -58,6 +65,10 @@ impl GenerationPipeline {
     /// Create a new pipeline from configuration.
     pub fn new(config: &GenerationConfig) -> Self {
         let terrain = TerrainGenerator::new(config.terrain_params.clone());
+        let density = match &config.terrain_mode {
+            TerrainMode::Heightfield => None,
+            TerrainMode::Caves(params) => Some(DensityField::new(params.clone(), &config.terrain_params)),
+        };
         let biome_map = BiomeMap::new(config.seed);
         let clutter_converter = ClutterToLayerConverter::new(
             crate::generation::clutter_to_layer::ClutterLayerConfig::default(),
@@ -66,6 +77,8 @@ impl GenerationPipeline {
 
         Self {
             terrain,
+            density,
+            terrain_attributes: config.terrain_attributes,
             biome_map,
             biome_mask_depth: config.biome_mask_depth,
             grass_mask_depth: config.grass_mask_depth,
@@ -98,14 +111,28 @@ impl GenerationPipeline {
         // 2. Build terrain octree reading biome from mask
         const TERRAIN_VOXELS: u32 = 128;
         let voxel_size = chunk_size / TERRAIN_VOXELS as f32;
-        let classifier = MaskDrivenTerrainClassifier::new(
-            &self.terrain,
-            &biome_mask,
-            origin,
-            voxel_size,
-        );
-        let builder = AdaptiveOctreeBuilder::new(TERRAIN_VOXELS);
-        let octree = builder.build(&classifier, origin, chunk_size);
+        let builder = AdaptiveOctreeBuilder::new(TERRAIN_VOXELS).with_attributes(self.terrain_attributes);
+        let octree = match &self.density {
+            Some(field) => {
+                let classifier = DensityTerrainClassifier::new(
+                    &self.terrain,
+                    field,
+                    &biome_mask,
+                    origin,
+                    voxel_size,
+                );
+                builder.build(&classifier, origin, chunk_size)
+            }
+            None => {
+                let classifier = MaskDrivenTerrainClassifier::new(
+                    &self.terrain,
+                    &biome_mask,
+                    origin,
+                    voxel_size,
+                );
+                builder.build(&classifier, origin, chunk_size)
+            }
+        };
 
         // 3. Build grass mask from biome mask + slope
         let grass_gen = GrassNoiseGenerator::new(
@@ -145,6 +172,26 @@ impl GenerationPipeline {
         &self.terrain
     }
 
+    /// Get the 3D density field (cave terrain only).
+    pub fn density_field(&self) -> Option<&DensityField> {
+        self.density.as_ref()
+    }
+
+    /// Range of chunk Y levels (inclusive) that can contain terrain in the
+    /// column at world (x, z).
+    ///
+    /// Heightfield terrain spans one chunk around the surface; cave terrain
+    /// also covers the crust below and overhangs above.
+    pub fn chunk_y_range(&self, x: f32, z: f32) -> (i32, i32) {
+        let chunk_f = CHUNK_SIZE as f32;
+        let h = self.terrain.height_at(x, z);
+        let (below, above) = self.density.as_ref().map_or((0.0, 0.0), |f| f.vertical_extent());
+
+        let min_y = ((h - below - chunk_f) / chunk_f).floor().max(0.0) as i32;
+        let max_y = ((h + above + chunk_f) / chunk_f).ceil() as i32;
+        (min_y, max_y)
+    }
+
     /// Get a reference to the biome map.
     pub fn biome_map(&self) -> &BiomeMap {
         &self.biome_map
@@ -183,10 +230,7 @@ impl GenerationPipeline {
             for dz in -chunk_radius..=chunk_radius {
                 let cx = (center_coord.x + dx) as f32 * chunk_f + chunk_f * 0.5;
                 let cz = (center_coord.z + dz) as f32 * chunk_f + chunk_f * 0.5;
-                let h = self.terrain.height_at(cx, cz);
-
-                let min_y = ((h - chunk_f) / chunk_f).floor().max(0.0) as i32;
-                let max_y = ((h + chunk_f) / chunk_f).ceil() as i32;
+                let (min_y, max_y) = self.chunk_y_range(cx, cz);
 
                 for dy in min_y..=max_y {
                     let coord = ChunkCoord::new(
@@ -244,10 +288,7 @@ impl GenerationPipeline {
             for dz in -chunk_radius..=chunk_radius {
                 let cx = (center_coord.x + dx) as f32 * chunk_f + chunk_f * 0.5;
                 let cz = (center_coord.z + dz) as f32 * chunk_f + chunk_f * 0.5;
-                let h = self.terrain.height_at(cx, cz);
-
-                let min_y = ((h - chunk_f) / chunk_f).floor().max(0.0) as i32;
-                let max_y = ((h + chunk_f) / chunk_f).ceil() as i32;
+                let (min_y, max_y) = self.chunk_y_range(cx, cz);
 
                 for dy in min_y..=max_y {
                     let coord = ChunkCoord::new(
@@ -305,6 +346,8 @@ mod tests {
             biome_mask_depth: 3,
             grass_mask_depth: 5,
             clutter_mask_depth: 4,
+            terrain_mode: TerrainMode::Heightfield,
+            terrain_attributes: false,
         }
     }
 
@@ -383,6 +426,8 @@ mod tests {
             biome_mask_depth: 3,
             grass_mask_depth: 5,
             clutter_mask_depth: 4,
+            terrain_mode: TerrainMode::Heightfield,
+            terrain_attributes: false,
         };
         let pipeline = GenerationPipeline::new(&config);
 
@@ -399,4 +444,48 @@ mod tests {
                 "Chunk {:?} should have geometry", coord);
         }
     }
+
+    #[test]
+    fn test_pipeline_cave_terrain() {
+        let config = GenerationConfig {
+            terrain_mode: TerrainMode::Caves(CaveParams::default()),
+            ..test_config()
+        };
+        let pipeline = GenerationPipeline::new(&config);
+
+        // Cave terrain covers the crust below the surface
+        let (min_y, max_y) = pipeline.chunk_y_range(2.0, 2.0);
+        let h = pipeline.height_at(2.0, 2.0);
+        assert!(min_y <= ((h - 20.0) / CHUNK_SIZE as f32).floor().max(0.0) as i32);
+        assert!(max_y > (h / CHUNK_SIZE as f32) as i32);
+
+        // A chunk well below the surface is solid crust, not empty like a shell
+        let y_level = ((h - 10.0) / CHUNK_SIZE as f32).floor() as i32;
+        let chunk = pipeline.generate_chunk(ChunkCoord::new(0, y_level, 0));
+        assert!(chunk.octree.brick_count() > 0);
+
+        let heightfield = GenerationPipeline::new(&test_config());
+        assert!(heightfield.density_field().is_none());
+        assert_eq!(heightfield.generate_chunk(ChunkCoord::new(0, y_level, 0)).octree.brick_count(), 0);
+    }
+
+    #[test]
+    fn test_pipeline_terrain_attributes() {
+        let config = GenerationConfig { terrain_attributes: true, ..test_config() };
+        let pipeline = GenerationPipeline::new(&config);
+        let h = pipeline.height_at(2.0, 2.0);
+        let coord = ChunkCoord::new(0, (h / CHUNK_SIZE as f32).floor() as i32, 0);
+
+        let chunk = pipeline.generate_chunk(coord);
+        let attributes = chunk.octree.attributes_slice().expect("terrain attributes enabled");
+        assert_eq!(attributes.len(), chunk.octree.brick_count());
+        assert!(attributes.iter()
+            .flat_map(|brick| brick.voxels)
+            .any(|voxel| voxel.normal().is_some_and(|n| n.y > 0.0) && voxel.albedo().is_some()));
+
+        // Same voxels as without attributes
+        let plain = GenerationPipeline::new(&test_config()).generate_chunk(coord);
+        assert!(!plain.octree.has_attributes());
+        assert_eq!(plain.octree.bricks_slice().len(), chunk.octree.bricks_slice().len());
+    }
 }
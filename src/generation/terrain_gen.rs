@@ -6,10 +6,14 @@ use crate::math::Aabb;
 use crate::mask::{BiomeId, MaskOctree};
 use crate::terrain::biome::Biome;
 use crate::terrain::generator::TerrainGenerator;
+use crate::voxel::attributes::VoxelAttributes;
 use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
 use crate::voxel::sdf::{encode_gradient, GRADIENT_RANGE};
 use crate::voxel::voxel::Voxel;
 
+/// Height above sea level over which terrain dries out (meters)
+const SHORE_WET_HEIGHT: f32 = 1.0;
+
 /// Terrain classifier that reads biome data from a pre-built mask octree.
 ///
 /// Replaces `BiomeTerrainClassifier` — same height-based shell logic,
@@ -36,6 +40,16 @@ impl<'a> MaskDrivenTerrainClassifier<'a> {
             voxel_size,
         }
     }
+
+    /// Terrain slope (dh/dx, dh/dz) via finite differences
+    fn gradient(&self, pos: Vec3) -> (f32, f32) {
+        let eps = self.voxel_size;
+        let dh_dx = (self.terrain.height_at(pos.x + eps, pos.z)
+                   - self.terrain.height_at(pos.x - eps, pos.z)) / (2.0 * eps);
+        let dh_dz = (self.terrain.height_at(pos.x, pos.z + eps)
+                   - self.terrain.height_at(pos.x, pos.z - eps)) / (2.0 * eps);
+        (dh_dx, dh_dz)
+    }
 }
 
 impl<'a> RegionClassifier for MaskDrivenTerrainClassifier<'a> {
@@ -81,11 +95,7 @@ impl<'a> RegionClassifier for MaskDrivenTerrainClassifier<'a> {
 
         // Compute gradient via finite differences for smooth shading.
         // Uses the SDF module's gradient range constant.
-        let eps = self.voxel_size;
-        let dh_dx = (self.terrain.height_at(pos.x + eps, pos.z)
-                   - self.terrain.height_at(pos.x - eps, pos.z)) / (2.0 * eps);
-        let dh_dz = (self.terrain.height_at(pos.x, pos.z + eps)
-                   - self.terrain.height_at(pos.x, pos.z - eps)) / (2.0 * eps);
+        let (dh_dx, dh_dz) = self.gradient(pos);
 
         // Clamp gradient to valid range for encoding
         let dh_dx_clamped = dh_dx.clamp(-GRADIENT_RANGE, GRADIENT_RANGE);
@@ -105,6 +115,18 @@ impl<'a> RegionClassifier for MaskDrivenTerrainClassifier<'a> {
             flags,
         }
     }
+
+    /// Unclamped surface normal, the biome's RGB888 color, and wetness near the sea
+    fn evaluate_attributes(&self, pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+        let (dh_dx, dh_dz) = self.gradient(pos);
+        let biome = Biome::from_id(self.biome_mask.sample(self.chunk_origin, pos));
+        let (r, g, b) = biome.surface_rgb();
+        let above_sea = pos.y - self.terrain.params().sea_level;
+        VoxelAttributes::NONE
+            .with_normal(Vec3::new(-dh_dx, 1.0, -dh_dz).normalize())
+            .with_albedo(r, g, b)
+            .with_wetness(1.0 - above_sea / SHORE_WET_HEIGHT)
+    }
 }
 
 #[cfg(test)]
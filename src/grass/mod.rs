@@ -13,6 +13,7 @@ pub use params::GrassParams;
 pub use profile::{GrassProfile, GrassCell, GpuGrassProfile, GrassProfileDef, GrassProfileTable};
 
 use crate::atmosphere::state::WindState;
+use crate::voxel::material;
 
 /// Manages grass configuration and builds per-frame GPU params.
 pub struct GrassSystem {
@@ -54,7 +55,8 @@ impl GrassSystem {
             wind_direction: wind.direction,
             wind_speed: wind.speed,
             profile_count: self.profile_table.len() as u32,
-            _pad: [0.0; 3],
+            material_id: u32::from(material::material_id("grass_blade")),
+            _pad: [0.0; 2],
         }
     }
 
@@ -89,6 +91,7 @@ mod tests {
         assert_eq!(params.wind_speed, 3.0);
         assert_eq!(params.time, 1.5);
         assert!(params.profile_count >= 6);
+        assert_eq!(params.material_id, u32::from(material::material_id("grass_blade")));
     }
 
     #[test]
@@ -18,7 +18,9 @@ pub struct GrassParams {
     pub wind_speed: f32,
     // -- 16 bytes --
     pub profile_count: u32,
-    pub _pad: [f32; 3],
+    /// Material ID written for grass hits (the registry's "grass_blade")
+    pub material_id: u32,
+    pub _pad: [f32; 2],
     // -- 16 bytes --
     // Total: 48 bytes
 }
@@ -15,3 +15,4 @@ pub mod grass;
 pub mod mask;
 pub mod generation;
 pub mod clutter;
+pub mod physics;
@@ -15,11 +15,13 @@ use rktri::core::{
     camera_controller::FpsCameraController,
     input::InputState,
     logging,
+    scheduler::{FrameScheduler, SchedulerStats, WorkKind, WorkPriority},
     time::FrameTimer,
 };
 use rktri::render::{
     context::GpuContext,
     buffer::{OctreeBuffer, CameraBuffer},
+    buffer::octree_buffer::{GpuChunkInfo, MAX_CHUNKS},
     pipeline::{SvoTracePipeline, DisplayPipeline, TraceParams, LightingPipeline, LightingUniforms, ShadowPipeline, ShadowParams, SkyParams, DebugParams, GodRaysPipeline, GodRaysParams, TonemapPipeline, TonemapParams, CloudPipeline, CloudParams},
     texture::GBuffer,
     culling::ChunkCuller,
@@ -30,7 +32,9 @@ use rktri::grass::GrassCell;
 use rktri::mask::MaskOctree;
 use rktri::scene::SceneConfig;
 use rktri::voxel::StreamingManager;
+use rktri::voxel::material::{self, MaterialRegistry};
 use rktri::streaming::disk_io;
+use rktri::streaming::{ChunkStreamer, MemoryBudget, RegionStore, StreamerConfig, StreamEvent, StreamLayer};
 use std::path::PathBuf;
 
 #[cfg(feature = "dlss")]
@@ -101,7 +105,7 @@ struct RenderResources {
     chunk_bounds: Vec<[f32; 4]>, // [world_min_x, world_min_y, world_min_z, root_size]
     #[allow(dead_code)]
     chunk_culler: ChunkCuller,
-    // Chunk grid acceleration params (uploaded once, used per frame)
+    // Chunk grid acceleration params (rebuilt when the resident chunks change)
     grid_min: [i32; 3],
     grid_size: [u32; 3],
     #[allow(dead_code)]
@@ -109,147 +113,159 @@ struct RenderResources {
     // Debug: loaded chunk info for GetChunkInfo command
     loaded_chunks: std::collections::HashMap<(i32, i32, i32), ChunkDebugInfo>,
     loaded_chunks_grass: std::collections::HashMap<(i32, i32, i32), GrassDebugInfo>,
+    // Chunk streaming around the camera. Uploaded chunk layers and SuperChunk
+    // proxies are kept on the CPU so the octree buffer can be compacted, and
+    // grass masks are re-packed whenever the resident set changes.
+    chunk_streamer: ChunkStreamer,
+    resident_chunks: std::collections::BTreeMap<((i32, i32, i32), u32), ResidentChunk>,
+    resident_proxies: std::collections::BTreeMap<(i32, i32, i32), ResidentChunk>,
+    // Layer id proxies are drawn with (the layer they were built from)
+    proxy_layer_id: u32,
+    grass_masks: std::collections::HashMap<(i32, i32, i32), MaskOctree<GrassCell>>,
+    // Bumped whenever the chunk tables are rebuilt (debug state refresh)
+    chunk_tables_version: u64,
+    // Uploads, re-bakes, compactions and chunk table rebuilds run within a
+    // per-frame time budget. Queued uploads carry a ticket; ones superseded
+    // by a later upload or an unload before they ran are dropped.
+    frame_scheduler: FrameScheduler<FrameWork>,
+    upload_tickets: std::collections::HashMap<((i32, i32, i32), u32), u64>,
+    proxy_tickets: std::collections::HashMap<(i32, i32, i32), u64>,
+    next_ticket: u64,
+    // Jobs queued by the jobs that just ran, scheduled once they are done
+    follow_up_work: Vec<(WorkKind, WorkPriority, u32, FrameWork)>,
+    compaction_queued: bool,
+    // Edits not baked into the chunk files yet, applied as chunks arrive
+    unbaked_edits: Option<UnbakedEdits>,
+    // The resident set changed since the chunk tables were last rebuilt
+    tables_dirty: bool,
+    tables_rebuild_queued: bool,
+    // The octree buffer was compacted: the chunk tables must be rebuilt
+    // before the next frame is drawn
+    buffer_compacted: bool,
 }
 
-impl RenderResources {
-    fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_format: wgpu::TextureFormat, width: u32, height: u32, render_scale: f32, world_path: &PathBuf, view_distance: f32) -> Self {
-        use rktri::voxel::chunk::CHUNK_SIZE;
+/// A chunk layer or proxy uploaded to the octree buffer
+struct ResidentChunk {
+    octree: rktri::voxel::svo::Octree,
+    info: GpuChunkInfo,
+}
 
-        // Only path: load pre-generated v3 world from disk (SVDAG pre-compressed)
-        struct CompressedEntry {
-            coord: (i32, i32, i32),
-            world_min: [f32; 3],
-            root_size: f32,
-            layer_id: u32,
-            octree: rktri::voxel::svo::Octree,
-        }
+/// Edits from the world's edit log that `bake_edits` hasn't written into the
+/// chunk files of their layer yet
+struct UnbakedEdits {
+    log: rktri::voxel::edit::EditLog,
+    layer_id: u32,
+    /// Shared dictionary of the layer, resolved before edits are applied
+    dictionary: Option<rktri::voxel::svo::SubtreeDictionary>,
+}
+
+/// Main-thread streaming work run through the frame scheduler
+enum FrameWork {
+    /// Upload a chunk layer
+    Upload {
+        key: ((i32, i32, i32), u32),
+        ticket: u64,
+        octree: rktri::voxel::svo::Octree,
+        world_min: glam::Vec3,
+        priority: WorkPriority,
+    },
+    /// Apply unbaked edits to a chunk layer, then upload it
+    Rebake {
+        key: ((i32, i32, i32), u32),
+        ticket: u64,
+        octree: rktri::voxel::svo::Octree,
+        world_min: glam::Vec3,
+        priority: WorkPriority,
+    },
+    /// Upload a SuperChunk proxy
+    ProxyUpload {
+        key: (i32, i32, i32),
+        ticket: u64,
+        octree: rktri::voxel::svo::Octree,
+        world_min: glam::Vec3,
+        priority: WorkPriority,
+    },
+    /// Compact the octree buffer so waiting uploads fit
+    Compact,
+    /// Rebuild the chunk tables and re-pack the grass masks
+    RebuildTables,
+}
+
+/// Main-thread time per frame for streaming uploads and table rebuilds (ms)
+const FRAME_WORK_BUDGET_MS: f32 = 4.0;
 
+impl RenderResources {
+    fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_format: wgpu::TextureFormat, width: u32, height: u32, render_scale: f32, world_path: &PathBuf, view_distance: f32, generate_missing: bool) -> Self {
         log::info!("Loading pre-generated v3 world from: {}", world_path.display());
-        let chunks = Self::load_chunks_from_disk(world_path, view_distance);
-
-        // Keep layers separate - each chunk is stored with its actual layer_id
-        // The raycaster will check all layer indices at each grid cell
-        let mut merged_entries: Vec<CompressedEntry> = Vec::with_capacity(chunks.len());
-
-        for (coord, octree, layer_id) in &chunks {
-            let world_min = [
-                coord.x as f32 * CHUNK_SIZE as f32,
-                coord.y as f32 * CHUNK_SIZE as f32,
-                coord.z as f32 * CHUNK_SIZE as f32,
-            ];
-            let root_size = octree.root_size();
-
-            merged_entries.push(CompressedEntry {
-                coord: (coord.x, coord.y, coord.z),
-                world_min,
-                root_size,
-                layer_id: *layer_id,
-                octree: octree.clone(),
-            });
-        }
+        let (mut chunk_streamer, world_center, proxy_layer_id) = Self::create_chunk_streamer(world_path, view_distance, generate_missing);
 
-        let compressed_entries = merged_entries;
+        // Stream the view range around the world center before the first frame
+        let start = std::time::Instant::now();
+        let preload_events = chunk_streamer.preload(world_center);
+        log::info!("Preloaded {} chunks in {:.1}s", chunk_streamer.resident_count(), start.elapsed().as_secs_f64());
 
         // Sum up total nodes/bricks for buffer allocation
         let mut total_nodes = 0usize;
         let mut total_bricks = 0usize;
-        let mut world_max = glam::Vec3::ZERO;
-        for entry in &compressed_entries {
-            total_nodes += entry.octree.node_count();
-            total_bricks += entry.octree.brick_count();
-            let entry_max = glam::Vec3::from(entry.world_min) + glam::Vec3::splat(entry.root_size);
-            world_max = world_max.max(entry_max);
+        // Proxies are re-sent as chunks cut into them; only the last one stays
+        let mut proxy_sizes = std::collections::HashMap::new();
+        for event in &preload_events {
+            match event {
+                StreamEvent::Upload { octree, .. } => {
+                    total_nodes += octree.node_count();
+                    total_bricks += octree.brick_count();
+                }
+                StreamEvent::ProxyUpload { coord, octree } => {
+                    proxy_sizes.insert((coord.x, coord.y, coord.z), (octree.node_count(), octree.brick_count()));
+                }
+                _ => {}
+            }
+        }
+        for (nodes, bricks) in proxy_sizes.into_values() {
+            total_nodes += nodes;
+            total_bricks += bricks;
+        }
+
+        // Terrain chunks written with `generate_world --shared-dict` reference
+        // a dictionary of common subtrees, uploaded once ahead of the chunks
+        let terrain_store = RegionStore::new(world_path.join("terrain"));
+        let dictionary = match disk_io::load_layer_dictionary(&terrain_store) {
+            Ok(dictionary) => dictionary,
+            Err(e) => {
+                log::error!("Failed to load shared dictionary from {}: {}", terrain_store.dir().display(), e);
+                std::process::exit(1);
+            }
+        };
+
+        // Chunks streamed in later are appended behind the preloaded ones until
+        // the buffer is full and gets compacted, so leave room for the view
+        // range to turn over once
+        let mut max_nodes = total_nodes * 2;
+        let mut max_bricks = total_bricks * 2;
+        if let Some(dictionary) = &dictionary {
+            max_nodes += dictionary.node_count();
+            max_bricks += dictionary.brick_count();
         }
-        let chunk_count = compressed_entries.len() as u32;
-        let world_extent = world_max.x.max(world_max.y).max(world_max.z);
 
-        log::info!("World: {} chunks, {} total nodes, {} total bricks, extent={:.1}m",
-            chunk_count, total_nodes, total_bricks, world_extent);
+        log::info!("Preloaded {} total nodes, {} total bricks; octree buffer holds {} nodes, {} bricks",
+            total_nodes, total_bricks, max_nodes, max_bricks);
 
         // Create buffers
         let camera_buffer = CameraBuffer::new(device);
         let mut octree_buffer = OctreeBuffer::new(device,
-            total_nodes.max(1) as u32,
-            total_bricks.max(1) as u32);
-
-        // Incrementally upload each chunk to GPU
-        let mut all_chunk_infos: Vec<rktri::render::buffer::octree_buffer::GpuChunkInfo> = Vec::with_capacity(compressed_entries.len());
-        let mut loaded_chunks: std::collections::HashMap<(i32, i32, i32), ChunkDebugInfo> = std::collections::HashMap::new();
-        for entry in &compressed_entries {
-            let info = octree_buffer.upload_chunk_incremental(
-                queue,
-                &entry.octree,
-                entry.world_min,
-                entry.root_size,
-                entry.layer_id,
-                0,
-            );
-            loaded_chunks.insert(entry.coord, ChunkDebugInfo {
-                node_count: entry.octree.node_count() as u32,
-                brick_count: entry.octree.brick_count() as u32,
-                world_min: entry.world_min,
-            });
-            all_chunk_infos.push(info);
+            max_nodes.max(1) as u32,
+            max_bricks.max(1) as u32);
+        if let Some(dictionary) = &dictionary {
+            octree_buffer.upload_dictionary(queue, dictionary);
         }
+        let unbaked_edits = Self::load_unbaked_edits(world_path, dictionary);
 
         // Initialize feedback header
         let feedback_header: [u32; 4] = [0, rktri::voxel::streaming::feedback::MAX_FEEDBACK_REQUESTS, 0, 0];
         queue.write_buffer(octree_buffer.feedback_header_buffer(), 0, bytemuck::cast_slice(&feedback_header));
 
-        // Write ALL chunk infos to buffer once (grid-based ray marching uses static indices)
-        octree_buffer.update_chunk_infos(queue, &all_chunk_infos);
-
-        // Build 3D chunk grid: maps chunk coordinates → LayerDescriptor for DDA ray marching
-        let chunk_size_f = rktri::voxel::chunk::CHUNK_SIZE as f32;
-        let (grid_min, grid_size, grid_data, layer_data) = Self::build_chunk_grid(&all_chunk_infos, chunk_size_f);
-        octree_buffer.upload_chunk_grid(device, queue, &grid_data, &layer_data);
-
-        // Debug: count cells with multiple layers
-        let multi_layer_cells = grid_data.iter().filter(|d| d.layer_count > 1).count();
-        let max_layers = grid_data.iter().map(|d| d.layer_count).max().unwrap_or(0);
-        log::info!("Chunk grid: min={:?}, size={:?}, cells={}, layer_indices={}, multi_layer_cells={}, max_layers_per_cell={}",
-            grid_min, grid_size,
-            grid_size[0] as usize * grid_size[1] as usize * grid_size[2] as usize,
-            layer_data.len(), multi_layer_cells, max_layers);
-
-        // Load and upload grass masks
-        let grass_masks_map = Self::load_grass_masks_from_disk(world_path);
-        let mut loaded_chunks_grass: std::collections::HashMap<(i32, i32, i32), GrassDebugInfo> = std::collections::HashMap::new();
-        if !grass_masks_map.is_empty() {
-            // Build per-chunk mask array matching compressed_entries order
-            let chunk_size_i = rktri::voxel::chunk::CHUNK_SIZE as f32;
-            let masks_ordered: Vec<Option<&MaskOctree<GrassCell>>> = compressed_entries.iter()
-                .map(|entry| {
-                    let cx = (entry.world_min[0] / chunk_size_i).round() as i32;
-                    let cy = (entry.world_min[1] / chunk_size_i).round() as i32;
-                    let cz = (entry.world_min[2] / chunk_size_i).round() as i32;
-                    grass_masks_map.get(&(cx, cy, cz))
-                })
-                .collect();
-            let matched = masks_ordered.iter().filter(|m| m.is_some()).count();
-            log::info!("Grass masks: {} loaded, {} matched to chunks", grass_masks_map.len(), matched);
-
-            // Pack masks to GPU format and upload
-            let (infos, nodes, values) = rktri::render::buffer::pack_grass_masks(&masks_ordered);
-
-            // Track grass info per chunk
-            for (i, entry) in compressed_entries.iter().enumerate() {
-                if let Some(mask) = masks_ordered[i] {
-                    loaded_chunks_grass.insert(entry.coord, GrassDebugInfo {
-                        node_count: mask.node_count() as u32,
-                    });
-                }
-            }
-
-            octree_buffer.upload_grass_masks(device, queue, &infos, &nodes, &values);
-        }
-
-        // Store chunk bounds for terrain height queries
-        let chunk_bounds: Vec<[f32; 4]> = all_chunk_infos.iter()
-            .map(|c| [c.world_min[0], c.world_min[1], c.world_min[2], c.root_size])
-            .collect();
-
-        let chunk_culler = ChunkCuller::new(all_chunk_infos);
+        // Grass masks, matched to resident chunks when the chunk tables are built
+        let grass_masks = Self::load_grass_masks_from_disk(world_path);
 
         // Create streaming manager (simplified - uses first chunk for now)
         let pool_size = (total_bricks as u32).min(1_000_000);
@@ -259,6 +275,7 @@ impl RenderResources {
 
         // Create pipelines
         let svo_pipeline = SvoTracePipeline::new(device, &camera_buffer, &octree_buffer);
+        svo_pipeline.update_material_table(queue, &material::registry().gpu_table());
         let shadow_pipeline = ShadowPipeline::new(device, &camera_buffer, &octree_buffer);
         let godrays_pipeline = GodRaysPipeline::new(device, &camera_buffer);
         let cloud_pipeline = CloudPipeline::new(device, &camera_buffer);
@@ -340,7 +357,7 @@ impl RenderResources {
         // Display reads from post_texture (tonemapped LDR) instead of lit_texture (HDR)
         let display_bind_group = display_pipeline.create_bind_group(device, &post_texture_view);
 
-        Self {
+        let mut resources = Self {
             camera_buffer,
             octree_buffer,
             svo_pipeline,
@@ -393,29 +410,451 @@ impl RenderResources {
             dlss_norm_depth_pipeline: None,
             #[cfg(feature = "dlss")]
             dlss_norm_depth_bind_group: None,
-            chunk_count,
-            world_extent,
+            chunk_count: 0,
+            world_extent: 0.0,
             world_offset,
-            chunk_bounds,
-            chunk_culler,
-            grid_min,
-            grid_size,
+            chunk_bounds: Vec::new(),
+            chunk_culler: ChunkCuller::new(Vec::new()),
+            grid_min: [0; 3],
+            grid_size: [1, 1, 1],
             streaming,
-            loaded_chunks,
-            loaded_chunks_grass,
+            loaded_chunks: std::collections::HashMap::new(),
+            loaded_chunks_grass: std::collections::HashMap::new(),
+            chunk_streamer,
+            resident_chunks: std::collections::BTreeMap::new(),
+            resident_proxies: std::collections::BTreeMap::new(),
+            proxy_layer_id,
+            grass_masks,
+            chunk_tables_version: 0,
+            frame_scheduler: FrameScheduler::new(FRAME_WORK_BUDGET_MS),
+            upload_tickets: std::collections::HashMap::new(),
+            proxy_tickets: std::collections::HashMap::new(),
+            next_ticket: 0,
+            follow_up_work: Vec::new(),
+            compaction_queued: false,
+            unbaked_edits,
+            tables_dirty: false,
+            tables_rebuild_queued: false,
+            buffer_compacted: false,
+        };
+
+        // Upload the preloaded chunks and build the chunk grid
+        resources.queue_stream_events(preload_events);
+        resources.run_frame_work(device, queue, true);
+        log::info!("World: {} chunks, extent={:.1}m, grid min={:?} size={:?}",
+            resources.chunk_count, resources.world_extent, resources.grid_min, resources.grid_size);
+        resources
+    }
+
+    /// Stream chunks around the camera for this frame. Returns what the
+    /// frame scheduler ran and deferred.
+    fn update_streaming(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, camera: &Camera, dt: f32) -> SchedulerStats {
+        let events = self.chunk_streamer.update(camera.position, camera.forward(), dt);
+        self.queue_stream_events(events);
+        self.run_frame_work(device, queue, false)
+    }
+
+    /// Queue uploads of arriving chunk layers and proxies and drop unloaded
+    /// ones. Chunks stay drawn until the chunk tables are rebuilt.
+    ///
+    /// Chunks in view upload first, then chunks prefetched ahead of the
+    /// camera and new proxies, then proxies replacing drawn ones (those only
+    /// trim what resident chunks now cover). Chunks with unbaked edits are
+    /// re-baked before they upload.
+    fn queue_stream_events(&mut self, events: Vec<StreamEvent>) {
+        for event in events {
+            match event {
+                StreamEvent::Upload { coord, layer_id, octree } => {
+                    let key = ((coord.x, coord.y, coord.z), layer_id);
+                    let ticket = self.next_ticket();
+                    self.upload_tickets.insert(key, ticket);
+                    let priority = if self.chunk_streamer.in_view(coord) {
+                        WorkPriority::Visible
+                    } else {
+                        WorkPriority::Prefetch
+                    };
+                    let units = octree.brick_count() as u32;
+                    let world_min = coord.world_origin();
+                    let edited = self.unbaked_edits.as_ref().is_some_and(|edits| {
+                        edits.layer_id == layer_id && !edits.log.edits_for_chunk(coord).is_empty()
+                    });
+                    if edited {
+                        self.frame_scheduler.schedule(WorkKind::BrickRebake, priority, units, FrameWork::Rebake {
+                            key,
+                            ticket,
+                            octree,
+                            world_min,
+                            priority,
+                        });
+                    } else {
+                        self.frame_scheduler.schedule(WorkKind::ChunkUpload, priority, units, FrameWork::Upload {
+                            key,
+                            ticket,
+                            octree,
+                            world_min,
+                            priority,
+                        });
+                    }
+                }
+                StreamEvent::Unload(coord) => {
+                    let key = (coord.x, coord.y, coord.z);
+                    self.upload_tickets.retain(|(c, _), _| *c != key);
+                    let before = self.resident_chunks.len();
+                    self.resident_chunks.retain(|(c, _), _| *c != key);
+                    self.tables_dirty |= self.resident_chunks.len() != before;
+                }
+                StreamEvent::ProxyUpload { coord, octree } => {
+                    let key = (coord.x, coord.y, coord.z);
+                    let ticket = self.next_ticket();
+                    self.proxy_tickets.insert(key, ticket);
+                    let priority = if self.resident_proxies.contains_key(&key) {
+                        WorkPriority::Background
+                    } else {
+                        WorkPriority::Prefetch
+                    };
+                    let units = octree.brick_count() as u32;
+                    self.frame_scheduler.schedule(WorkKind::ChunkUpload, priority, units, FrameWork::ProxyUpload {
+                        key,
+                        ticket,
+                        octree,
+                        world_min: coord.world_origin(),
+                        priority,
+                    });
+                }
+                StreamEvent::ProxyUnload(coord) => {
+                    let key = (coord.x, coord.y, coord.z);
+                    self.proxy_tickets.remove(&key);
+                    self.tables_dirty |= self.resident_proxies.remove(&key).is_some();
+                }
+                StreamEvent::Load(_) => {}
+            }
+        }
+    }
+
+    fn next_ticket(&mut self) -> u64 {
+        self.next_ticket += 1;
+        self.next_ticket
+    }
+
+    /// Run queued uploads within the frame budget, or all of them if
+    /// `run_all`, then make sure the chunk tables match the octree buffer.
+    fn run_frame_work(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, run_all: bool) -> SchedulerStats {
+        let stats = self.run_scheduled(device, queue, run_all);
+        if run_all {
+            // Until re-baked chunks and uploads waiting for a compaction are done
+            while self.frame_scheduler.queued() > 0 {
+                self.run_scheduled(device, queue, true);
+            }
+        }
+
+        if self.buffer_compacted || (run_all && self.tables_dirty) {
+            // Chunk infos moved (or everything must be drawn right away)
+            self.rebuild_chunk_tables(device, queue);
+        } else if self.tables_dirty && !self.tables_rebuild_queued {
+            let units = (self.resident_chunks.len() + self.resident_proxies.len()) as u32;
+            self.frame_scheduler.schedule(WorkKind::MaskRebuild, WorkPriority::Visible, units, FrameWork::RebuildTables);
+            self.tables_rebuild_queued = true;
+        }
+        stats
+    }
+
+    /// Run this frame's jobs through the frame scheduler, then queue the
+    /// work they left to follow
+    fn run_scheduled(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, run_all: bool) -> SchedulerStats {
+        // Jobs run on `self`, so the scheduler is taken out while it runs them
+        let mut scheduler = std::mem::replace(&mut self.frame_scheduler, FrameScheduler::new(FRAME_WORK_BUDGET_MS));
+        let execute = |work: FrameWork| self.run_frame_job(device, queue, work);
+        let stats = if run_all { scheduler.run_all(execute) } else { scheduler.run_frame(execute) };
+        self.frame_scheduler = scheduler;
+
+        for (kind, priority, units, work) in std::mem::take(&mut self.follow_up_work) {
+            self.frame_scheduler.schedule(kind, priority, units, work);
+        }
+        stats
+    }
+
+    /// Run a job handed back by the frame scheduler
+    fn run_frame_job(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, work: FrameWork) {
+        match work {
+            FrameWork::Upload { key, ticket, octree, world_min, priority } => {
+                if self.upload_tickets.get(&key) != Some(&ticket) {
+                    return;
+                }
+                if self.wait_for_compaction(&octree) {
+                    let units = octree.brick_count() as u32;
+                    let work = FrameWork::Upload { key, ticket, octree, world_min, priority };
+                    self.follow_up_work.push((WorkKind::ChunkUpload, priority, units, work));
+                    return;
+                }
+                self.upload_tickets.remove(&key);
+                let (coord, layer_id) = key;
+                log::debug!("Loaded chunk ({},{},{}) layer={}: {} nodes, {} bricks",
+                    coord.0, coord.1, coord.2, layer_id, octree.node_count(), octree.brick_count());
+                // The slots of a replaced chunk are reclaimed by the next compaction
+                self.resident_chunks.remove(&key);
+                if let Some(chunk) = self.upload_octree(queue, octree, world_min, layer_id) {
+                    self.resident_chunks.insert(key, chunk);
+                }
+                self.tables_dirty = true;
+            }
+            FrameWork::Rebake { key, ticket, octree, world_min, priority } => {
+                if self.upload_tickets.get(&key) != Some(&ticket) {
+                    return;
+                }
+                let Some(edits) = &self.unbaked_edits else { return };
+                let coord = rktri::voxel::chunk::ChunkCoord::new(key.0.0, key.0.1, key.0.2);
+                let base = match &edits.dictionary {
+                    Some(dictionary) => dictionary.resolve(&octree),
+                    None => octree,
+                };
+                let octree = rktri::voxel::edit::bake_octree(&base, coord, &edits.log.edits_for_chunk(coord));
+                let units = octree.brick_count() as u32;
+                let work = FrameWork::Upload { key, ticket, octree, world_min, priority };
+                self.follow_up_work.push((WorkKind::ChunkUpload, priority, units, work));
+            }
+            FrameWork::ProxyUpload { key, ticket, octree, world_min, priority } => {
+                if self.proxy_tickets.get(&key) != Some(&ticket) {
+                    return;
+                }
+                if self.wait_for_compaction(&octree) {
+                    let units = octree.brick_count() as u32;
+                    let work = FrameWork::ProxyUpload { key, ticket, octree, world_min, priority };
+                    self.follow_up_work.push((WorkKind::ChunkUpload, priority, units, work));
+                    return;
+                }
+                self.proxy_tickets.remove(&key);
+                self.resident_proxies.remove(&key);
+                if let Some(proxy) = self.upload_octree(queue, octree, world_min, self.proxy_layer_id) {
+                    self.resident_proxies.insert(key, proxy);
+                }
+                self.tables_dirty = true;
+            }
+            FrameWork::Compact => {
+                self.compaction_queued = false;
+                self.compact_octree_buffer(queue);
+            }
+            FrameWork::RebuildTables => {
+                self.tables_rebuild_queued = false;
+                if self.tables_dirty {
+                    self.rebuild_chunk_tables(device, queue);
+                }
+            }
+        }
+    }
+
+    /// Upload an octree behind the octree buffer's current contents. Returns
+    /// None if it doesn't fit or the chunk table is full.
+    fn upload_octree(
+        &mut self,
+        queue: &wgpu::Queue,
+        octree: rktri::voxel::svo::Octree,
+        world_min: glam::Vec3,
+        layer_id: u32,
+    ) -> Option<ResidentChunk> {
+        if self.resident_chunks.len() + self.resident_proxies.len() >= MAX_CHUNKS as usize {
+            log::warn!("Chunk table full ({} chunks), skipping octree at {:?} layer={}",
+                MAX_CHUNKS, world_min, layer_id);
+            return None;
         }
+        if !self.octree_buffer_fits(&octree) {
+            log::warn!("Octree buffer full ({}/{} nodes, {}/{} bricks), skipping octree at {:?} layer={}",
+                self.octree_buffer.used_nodes(), self.octree_buffer.max_nodes(),
+                self.octree_buffer.used_bricks(), self.octree_buffer.max_bricks(),
+                world_min, layer_id);
+            return None;
+        }
+
+        let info = self.octree_buffer.upload_chunk_incremental(
+            queue,
+            &octree,
+            world_min.to_array(),
+            octree.root_size(),
+            layer_id,
+            0,
+        );
+        Some(ResidentChunk { octree, info })
+    }
+
+    /// Check if an octree fits behind the octree buffer's current contents
+    fn octree_buffer_fits(&self, octree: &rktri::voxel::svo::Octree) -> bool {
+        let buffer = &self.octree_buffer;
+        buffer.used_nodes() as usize + octree.nodes_slice().len() <= buffer.max_nodes() as usize
+            && buffer.used_bricks() as usize + octree.bricks_slice().len() <= buffer.max_bricks() as usize
+    }
+
+    /// Check if an upload has to wait for a compaction of the octree buffer
+    /// to fit, and queue one if none is. Octrees that won't fit even after a
+    /// compaction are left to `upload_octree` to skip.
+    fn wait_for_compaction(&mut self, octree: &rktri::voxel::svo::Octree) -> bool {
+        if self.octree_buffer_fits(octree) || !self.fits_after_compaction(octree) {
+            return false;
+        }
+        if !self.compaction_queued {
+            // Visible: uploads of every class wait for it
+            let units = (self.resident_chunks.len() + self.resident_proxies.len()) as u32;
+            self.follow_up_work.push((WorkKind::Compaction, WorkPriority::Visible, units, FrameWork::Compact));
+            self.compaction_queued = true;
+        }
+        true
+    }
+
+    /// Check if an octree would fit behind the resident chunks and proxies
+    /// once the octree buffer is compacted
+    fn fits_after_compaction(&self, octree: &rktri::voxel::svo::Octree) -> bool {
+        let buffer = &self.octree_buffer;
+        let (nodes, bricks) = self.resident_chunks.values()
+            .chain(self.resident_proxies.values())
+            .fold((buffer.dictionary_nodes() as usize, buffer.dictionary_bricks() as usize), |(nodes, bricks), chunk| {
+                (nodes + chunk.octree.nodes_slice().len(), bricks + chunk.octree.bricks_slice().len())
+            });
+        nodes + octree.nodes_slice().len() <= buffer.max_nodes() as usize
+            && bricks + octree.bricks_slice().len() <= buffer.max_bricks() as usize
+    }
+
+    /// Re-upload every resident chunk and proxy from the start of the octree
+    /// buffer, reclaiming the slots of unloaded and replaced ones. Chunk infos
+    /// move, so the chunk tables must be rebuilt afterwards.
+    fn compact_octree_buffer(&mut self, queue: &wgpu::Queue) {
+        self.octree_buffer.reset_usage();
+        for chunk in self.resident_chunks.values_mut().chain(self.resident_proxies.values_mut()) {
+            let info = chunk.info;
+            chunk.info = self.octree_buffer.upload_chunk_incremental(
+                queue,
+                &chunk.octree,
+                info.world_min,
+                info.root_size,
+                info.layer_id,
+                info.flags,
+            );
+        }
+        self.buffer_compacted = true;
+        log::info!("Compacted octree buffer: {} chunks, {} proxies, {} nodes, {} bricks",
+            self.resident_chunks.len(), self.resident_proxies.len(),
+            self.octree_buffer.used_nodes(), self.octree_buffer.used_bricks());
+    }
+
+    /// Rebuild everything derived from the resident chunks and proxies: chunk
+    /// infos, the chunk grid, grass masks (indexed like the chunk infos),
+    /// bounds and the debug info.
+    fn rebuild_chunk_tables(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        use rktri::voxel::chunk::CHUNK_SIZE;
+        use rktri::voxel::hierarchy::CHUNKS_PER_SUPER_CHUNK;
+        use rktri::voxel::svo::RegionHint;
+
+        // Chunks first, then proxies
+        let chunk_infos: Vec<GpuChunkInfo> = self.resident_chunks.values()
+            .chain(self.resident_proxies.values())
+            .map(|c| c.info)
+            .collect();
+
+        // Grid-based ray marching indexes chunk infos by their position in this list
+        self.octree_buffer.update_chunk_infos(queue, &chunk_infos);
+
+        // A chunk sits in its own grid cell. A proxy spans the cells of its
+        // SuperChunk and is registered in those it has content in and no
+        // resident chunk covers.
+        let mut cells: Vec<((i32, i32, i32), u32)> = self.resident_chunks.keys()
+            .enumerate()
+            .map(|(index, (coord, _))| (*coord, index as u32))
+            .collect();
+        let covered: std::collections::HashSet<(i32, i32, i32)> = cells.iter().map(|(cell, _)| *cell).collect();
+        let n = CHUNKS_PER_SUPER_CHUNK as i32;
+        let chunk_size = CHUNK_SIZE as f32;
+        for (i, (coord, proxy)) in self.resident_proxies.iter().enumerate() {
+            let index = (self.resident_chunks.len() + i) as u32;
+            let half = proxy.info.root_size / 2.0;
+            for z in 0..n {
+                for y in 0..n {
+                    for x in 0..n {
+                        let cell = (coord.0 * n + x, coord.1 * n + y, coord.2 * n + z);
+                        if covered.contains(&cell) {
+                            continue;
+                        }
+                        let min = glam::Vec3::new(x as f32, y as f32, z as f32) * chunk_size - half;
+                        let region = rktri::math::Aabb::new(min, min + glam::Vec3::splat(chunk_size));
+                        if proxy.octree.region_hint(&region) != RegionHint::Empty {
+                            cells.push((cell, index));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Build 3D chunk grid: maps chunk coordinates → LayerDescriptor for DDA ray marching
+        let (grid_min, grid_size, grid_data, layer_data) = Self::build_chunk_grid(&cells);
+        self.octree_buffer.upload_chunk_grid(device, queue, &grid_data, &layer_data);
+        self.grid_min = grid_min;
+        self.grid_size = grid_size;
+
+        // Debug: count cells with multiple layers
+        let multi_layer_cells = grid_data.iter().filter(|d| d.layer_count > 1).count();
+        let max_layers = grid_data.iter().map(|d| d.layer_count).max().unwrap_or(0);
+        log::debug!("Chunk grid: min={:?}, size={:?}, cells={}, layer_indices={}, multi_layer_cells={}, max_layers_per_cell={}",
+            grid_min, grid_size,
+            grid_size[0] as usize * grid_size[1] as usize * grid_size[2] as usize,
+            layer_data.len(), multi_layer_cells, max_layers);
+
+        // Per-chunk mask array matching the chunk info order (proxies have none)
+        self.loaded_chunks_grass.clear();
+        if !self.grass_masks.is_empty() {
+            let masks_ordered: Vec<Option<&MaskOctree<GrassCell>>> = self.resident_chunks.keys()
+                .map(|(coord, _)| self.grass_masks.get(coord))
+                .chain(std::iter::repeat_n(None, self.resident_proxies.len()))
+                .collect();
+            for ((coord, _), mask) in self.resident_chunks.keys().zip(&masks_ordered) {
+                if let Some(mask) = mask {
+                    self.loaded_chunks_grass.insert(*coord, GrassDebugInfo {
+                        node_count: mask.node_count() as u32,
+                    });
+                }
+            }
+
+            // Pack masks to GPU format and upload
+            let (infos, nodes, values) = rktri::render::buffer::pack_grass_masks(&masks_ordered);
+            self.octree_buffer.upload_grass_masks(device, queue, &infos, &nodes, &values);
+        }
+
+        self.loaded_chunks.clear();
+        for ((coord, _), chunk) in &self.resident_chunks {
+            let entry = self.loaded_chunks.entry(*coord).or_insert(ChunkDebugInfo {
+                node_count: 0,
+                brick_count: 0,
+                world_min: chunk.info.world_min,
+            });
+            entry.node_count += chunk.octree.node_count() as u32;
+            entry.brick_count += chunk.octree.brick_count() as u32;
+        }
+
+        // Bounds and extent of the full-resolution chunks only
+        let resident_infos = &chunk_infos[..self.resident_chunks.len()];
+        let mut world_max = glam::Vec3::ZERO;
+        for info in resident_infos {
+            world_max = world_max.max(glam::Vec3::from(info.world_min) + glam::Vec3::splat(info.root_size));
+        }
+        self.world_extent = world_max.x.max(world_max.y).max(world_max.z);
+        self.chunk_count = chunk_infos.len() as u32;
+
+        // Store chunk bounds for terrain height queries
+        self.chunk_bounds = resident_infos.iter()
+            .map(|c| [c.world_min[0], c.world_min[1], c.world_min[2], c.root_size])
+            .collect();
+        self.chunk_culler = ChunkCuller::new(chunk_infos);
+        self.chunk_tables_version += 1;
+        self.tables_dirty = false;
+        self.buffer_compacted = false;
     }
 
     /// Build a 3D grid mapping chunk coordinates to LayerDescriptor + layer_data.
+    /// `cells` lists the chunk info indices registered in each grid cell; an
+    /// index may appear in several cells.
     /// Returns (grid_min, grid_size, grid_data, layer_data).
     /// Each grid cell stores a descriptor pointing into layer_data where octree indices are stored.
     fn build_chunk_grid(
-        chunk_infos: &[rktri::render::buffer::octree_buffer::GpuChunkInfo],
-        chunk_size: f32,
+        cells: &[((i32, i32, i32), u32)],
     ) -> ([i32; 3], [u32; 3], Vec<rktri::render::buffer::LayerDescriptor>, Vec<u32>) {
         use rktri::render::buffer::LayerDescriptor;
 
-        if chunk_infos.is_empty() {
+        if cells.is_empty() {
             return ([0; 3], [1, 1, 1], vec![LayerDescriptor { base_index: 0, layer_count: 0 }], vec![]);
         }
 
@@ -423,11 +862,7 @@ impl RenderResources {
         let mut min_coord = [i32::MAX; 3];
         let mut max_coord = [i32::MIN; 3];
 
-        for info in chunk_infos {
-            let cx = (info.world_min[0] / chunk_size).floor() as i32;
-            let cy = (info.world_min[1] / chunk_size).floor() as i32;
-            let cz = (info.world_min[2] / chunk_size).floor() as i32;
-
+        for &((cx, cy, cz), _) in cells {
             min_coord[0] = min_coord[0].min(cx);
             min_coord[1] = min_coord[1].min(cy);
             min_coord[2] = min_coord[2].min(cz);
@@ -441,19 +876,17 @@ impl RenderResources {
         let size_z = (max_coord[2] - min_coord[2] + 1) as u32;
         let total_cells = size_x as usize * size_y as usize * size_z as usize;
 
+        let flat_index = |(cx, cy, cz): (i32, i32, i32)| {
+            let gx = (cx - min_coord[0]) as usize;
+            let gy = (cy - min_coord[1]) as usize;
+            let gz = (cz - min_coord[2]) as usize;
+            gx + gy * size_x as usize + gz * size_x as usize * size_y as usize
+        };
+
         // First pass: count layers per cell
         let mut layer_counts = vec![0u32; total_cells];
-        for info in chunk_infos {
-            let cx = (info.world_min[0] / chunk_size).floor() as i32;
-            let cy = (info.world_min[1] / chunk_size).floor() as i32;
-            let cz = (info.world_min[2] / chunk_size).floor() as i32;
-
-            let gx = (cx - min_coord[0]) as u32;
-            let gy = (cy - min_coord[1]) as u32;
-            let gz = (cz - min_coord[2]) as u32;
-
-            let flat_idx = gx + gy * size_x + gz * size_x * size_y;
-            layer_counts[flat_idx as usize] += 1;
+        for &(cell, _) in cells {
+            layer_counts[flat_index(cell)] += 1;
         }
 
         // Build descriptors with base indices
@@ -469,19 +902,11 @@ impl RenderResources {
         let mut layer_data = vec![0u32; current_base as usize];
         let mut cell_indices = vec![0u32; total_cells]; // tracks position within each cell's data
 
-        for (idx, info) in chunk_infos.iter().enumerate() {
-            let cx = (info.world_min[0] / chunk_size).floor() as i32;
-            let cy = (info.world_min[1] / chunk_size).floor() as i32;
-            let cz = (info.world_min[2] / chunk_size).floor() as i32;
-
-            let gx = (cx - min_coord[0]) as u32;
-            let gy = (cy - min_coord[1]) as u32;
-            let gz = (cz - min_coord[2]) as u32;
-
-            let flat_idx = gx + gy * size_x + gz * size_x * size_y;
-            let offset = descriptors[flat_idx as usize].base_index + cell_indices[flat_idx as usize];
-            layer_data[offset as usize] = idx as u32;
-            cell_indices[flat_idx as usize] += 1;
+        for &(cell, idx) in cells {
+            let flat_idx = flat_index(cell);
+            let offset = descriptors[flat_idx].base_index + cell_indices[flat_idx];
+            layer_data[offset as usize] = idx;
+            cell_indices[flat_idx] += 1;
         }
 
         (min_coord, [size_x, size_y, size_z], descriptors, layer_data)
@@ -894,12 +1319,17 @@ impl RenderResources {
         }
     }
 
-    /// Load individual chunk octrees from disk (v3 format only - SVDAG pre-compressed)
-    /// Only loads chunks within view_distance of center (0 = load all)
-    /// Returns (coord, octree, layer_id)
-    fn load_chunks_from_disk(world_path: &PathBuf, view_distance: f32) -> Vec<(disk_io::ChunkCoord, rktri::voxel::svo::Octree, u32)> {
+    /// Create the streamer for the world's chunk layers (v3 format only - SVDAG pre-compressed)
+    /// and its SuperChunk proxies, if it has them
+    /// Keeps chunks within view_distance of the camera resident (0 = load all)
+    /// With `generate_missing`, terrain, rock and vegetation chunks beyond the
+    /// pre-generated world are generated as the camera reaches them and
+    /// written back to the layers' region files
+    /// Returns the streamer, the world center to preload around and the layer id of the proxies
+    fn create_chunk_streamer(world_path: &PathBuf, view_distance: f32, generate_missing: bool) -> (ChunkStreamer, glam::Vec3, u32) {
         use serde_json::Value;
-        use rktri::voxel::chunk::CHUNK_SIZE;
+        use rktri::voxel::chunk::ChunkCoord;
+        use rktri::streaming::GeneratedLayer;
 
         let manifest_path = world_path.join("manifest.json");
         if !manifest_path.exists() {
@@ -921,16 +1351,22 @@ impl RenderResources {
         }
         log::info!("Manifest version: {}", version);
 
-        let mut result = Vec::new();
-
-        // V3: layer-based format — chunks stored under <layer_dir>/chunk_x_y_z.rkc
+        // V3: layer-based format — chunks stored in region files under <layer_dir>/
         let layers = manifest["layers"].as_array()
             .expect("V3 manifest missing 'layers' array");
 
+        let generation = if !generate_missing {
+            None
+        } else if view_distance <= 0.0 {
+            log::warn!("--generate-missing needs a view distance to stream within; ignored");
+            None
+        } else {
+            Some(Self::chunk_generation(&manifest))
+        };
+
+        let mut listed_layers = Vec::new();
         for layer in layers {
             let layer_name = layer["name"].as_str().unwrap_or("unknown");
-            let layer_dir = layer["directory"].as_str().unwrap_or(layer_name);
-            let layer_id = layer["id"].as_u64().unwrap_or(0) as u32;
 
             // Skip non-terrain layers (e.g. grass masks are loaded separately)
             let chunk_list = match layer["chunks"].as_array() {
@@ -940,73 +1376,143 @@ impl RenderResources {
                     continue;
                 }
             };
+            let chunks: Vec<ChunkCoord> = chunk_list.iter().map(|c| ChunkCoord::new(
+                c["x"].as_i64().unwrap() as i32,
+                c["y"].as_i64().unwrap() as i32,
+                c["z"].as_i64().unwrap() as i32,
+            )).collect();
+            listed_layers.push((layer, chunks));
+        }
+
+        // Every chunk the world was generated for has a terrain chunk or
+        // content in another layer; the layers without it are empty there
+        let generated: std::collections::HashSet<ChunkCoord> = listed_layers.iter()
+            .flat_map(|(_, chunks)| chunks.iter().copied())
+            .collect();
 
-            log::info!("Loading layer '{}' (id={}): {} chunks", layer_name, layer_id, chunk_list.len());
-            eprintln!("DEBUG: Loading layer '{}' id={}", layer_name, layer_id);
-
-            // Debug: print first few chunk coordinates
-            for (i, chunk_info) in chunk_list.iter().take(3).enumerate() {
-                let x = chunk_info["x"].as_i64().unwrap() as i32;
-                let y = chunk_info["y"].as_i64().unwrap() as i32;
-                let z = chunk_info["z"].as_i64().unwrap() as i32;
-                log::debug!("Layer {} chunk[{}]: ({},{},{})", layer_name, i, x, y, z);
-            }
-
-            // Get world center from manifest (approximate)
-            let world_center_x = manifest["size"].as_f64().unwrap_or(0.0) as f32 / 2.0;
-            let world_center_z = manifest["size"].as_f64().unwrap_or(0.0) as f32 / 2.0;
-            let chunk_size = CHUNK_SIZE as f32;
-
-            for chunk_info in chunk_list {
-                let x = chunk_info["x"].as_i64().unwrap() as i32;
-                let y = chunk_info["y"].as_i64().unwrap() as i32;
-                let z = chunk_info["z"].as_i64().unwrap() as i32;
-
-                // Filter by view distance if specified
-                if view_distance > 0.0 {
-                    let chunk_world_x = x as f32 * chunk_size;
-                    let chunk_world_z = z as f32 * chunk_size;
-                    let dx = chunk_world_x - world_center_x;
-                    let dz = chunk_world_z - world_center_z;
-                    let dist = (dx * dx + dz * dz).sqrt();
-                    if dist > view_distance {
-                        continue;
-                    }
-                }
+        let mut stream_layers = Vec::new();
+        for (layer, chunks) in listed_layers {
+            let layer_name = layer["name"].as_str().unwrap_or("unknown");
+            let layer_dir = layer["directory"].as_str().unwrap_or(layer_name);
+            let layer_id = layer["id"].as_u64().unwrap_or(0) as u32;
 
-                let chunk_file = world_path
-                    .join(layer_dir)
-                    .join(format!("chunk_{}_{}_{}.rkc", x, y, z));
+            log::info!("Streaming layer '{}' (id={}): {} chunks", layer_name, layer_id, chunks.len());
+            let mut stream_layer = StreamLayer::new(layer_id, world_path.join(layer_dir)).with_chunks(chunks);
 
-                if let Some((coord, octree)) = Self::load_chunk_file(&chunk_file, x, y, z, layer_id) {
-                    result.push((coord, octree, layer_id));
+            let generated_layer = match layer_name {
+                "terrain" => Some(GeneratedLayer::Terrain),
+                "rocks" => Some(GeneratedLayer::Rocks),
+                "vegetation" => Some(GeneratedLayer::Vegetation),
+                _ => None,
+            };
+            if let (Some(generation), Some(generated_layer)) = (&generation, generated_layer) {
+                let mut layer_generation = generation.for_layer(generated_layer);
+                // Like `generate_world --bake-ao`, which bakes terrain only
+                if generated_layer == GeneratedLayer::Terrain && layer["occlusion"].as_bool().unwrap_or(false) {
+                    layer_generation = layer_generation.with_occlusion(rktri::voxel::svo::OcclusionBaker::new());
                 }
+                log::info!("Generating missing chunks of layer '{}'", layer_name);
+                stream_layer = stream_layer
+                    .with_generation(layer_generation)
+                    .with_generated(generated.iter().copied());
             }
+            stream_layers.push(stream_layer);
         }
 
-        log::info!("Loaded {} chunks total", result.len());
-        result
-    }
+        // Whole columns around the camera. Generating layers are unbounded,
+        // so they are streamed within the default height around the camera.
+        let world_center = manifest["size"].as_f64().unwrap_or(0.0) as f32 / 2.0;
+        let config = StreamerConfig {
+            view_distance,
+            view_height: if generation.is_some() { StreamerConfig::default().view_height } else { f32::INFINITY },
+            ..Default::default()
+        };
+        let mut streamer = ChunkStreamer::new(stream_layers, MemoryBudget::default(), config);
 
-    fn load_chunk_file(path: &std::path::Path, x: i32, y: i32, z: i32, layer_id: u32)
-        -> Option<(disk_io::ChunkCoord, rktri::voxel::svo::Octree)>
-    {
-        if !path.exists() {
-            log::warn!("Chunk file not found: {}", path.display());
-            return None;
+        // Far-field proxies, drawn with the id of the layer they were built from
+        let proxies = &manifest["proxies"];
+        let mut proxy_layer_id = 0;
+        if let Some(proxy_dir) = proxies["directory"].as_str() {
+            proxy_layer_id = layers.iter()
+                .find(|l| l["name"].as_str() == proxies["source_layer"].as_str())
+                .and_then(|l| l["id"].as_u64())
+                .unwrap_or(0) as u32;
+            log::info!("Streaming SuperChunk proxies from '{}' (layer id={})", proxy_dir, proxy_layer_id);
+            streamer = streamer.with_proxies(world_path.join(proxy_dir));
         }
 
-        let compressed = std::fs::read(path)
-            .expect(&format!("Failed to read chunk file: {}", path.display()));
+        (streamer, glam::Vec3::new(world_center, 0.0, world_center), proxy_layer_id)
+    }
+
+    /// Generation with the pipeline settings the world was generated with,
+    /// writing generated chunks back
+    fn chunk_generation(manifest: &serde_json::Value) -> rktri::streaming::ChunkGeneration {
+        use rktri::generation::{CaveParams, GenerationConfig, GenerationPipeline, TerrainMode};
+        use rktri::streaming::{ChunkGeneration, GeneratedLayer};
+        use rktri::terrain::generator::TerrainParams;
+
+        let seed = manifest["seed"].as_u64().unwrap_or(12345) as u32;
+        let tp = &manifest["terrain_params"];
+        let terrain_params = TerrainParams {
+            seed,
+            scale: tp["scale"].as_f64().unwrap_or(150.0) as f32,
+            height_scale: tp["height_scale"].as_f64().unwrap_or(80.0) as f32,
+            octaves: tp["octaves"].as_u64().unwrap_or(5) as u32,
+            persistence: tp["persistence"].as_f64().unwrap_or(0.5) as f32,
+            lacunarity: tp["lacunarity"].as_f64().unwrap_or(2.0) as f32,
+            sea_level: tp["sea_level"].as_f64().unwrap_or(20.0) as f32,
+        };
+        let terrain_mode = if manifest["terrain_mode"].as_str() == Some("caves") {
+            let defaults = CaveParams::default();
+            let crust_depth = manifest["crust_depth"].as_f64().map_or(defaults.crust_depth, |d| d as f32);
+            TerrainMode::Caves(CaveParams { crust_depth, ..defaults })
+        } else {
+            TerrainMode::Heightfield
+        };
+        let attributes = manifest["layers"].as_array()
+            .and_then(|layers| layers.iter().find(|l| l["name"].as_str() == Some("terrain")))
+            .and_then(|l| l["attributes"].as_bool())
+            .unwrap_or(false);
+
+        let config = GenerationConfig {
+            seed,
+            terrain_params,
+            terrain_mode,
+            terrain_attributes: attributes,
+            ..Default::default()
+        };
+        ChunkGeneration::new(Arc::new(GenerationPipeline::new(&config)), GeneratedLayer::Terrain)
+            .with_write_back(true)
+    }
+
+    /// Load the edits of the world's edit log not baked into the terrain
+    /// layer yet. `dictionary` is the terrain layer's shared dictionary.
+    fn load_unbaked_edits(world_path: &PathBuf, dictionary: Option<rktri::voxel::svo::SubtreeDictionary>) -> Option<UnbakedEdits> {
+        use rktri::voxel::edit::EditLog;
 
-        // v3: SVDAG pre-compressed on disk
-        let chunk = disk_io::decompress_svdag_chunk(&compressed)
-            .expect(&format!("Failed to decompress SVDAG chunk: {}", path.display()));
+        let log_path = world_path.join("edits.rked");
+        if !log_path.exists() {
+            return None;
+        }
+        let log = match EditLog::load(&log_path) {
+            Ok(log) if log.edit_count() > 0 => log,
+            Ok(_) => return None,
+            Err(e) => {
+                log::warn!("Ignoring unreadable edit log {}: {}", log_path.display(), e);
+                return None;
+            }
+        };
 
-        log::info!("Loaded chunk ({},{},{}) layer={}: {} nodes, {} bricks",
-            x, y, z, layer_id, chunk.octree.node_count(), chunk.octree.brick_count());
+        let manifest: serde_json::Value = std::fs::read_to_string(world_path.join("manifest.json")).ok()
+            .and_then(|data| serde_json::from_str(&data).ok())?;
+        let layer_id = manifest["layers"].as_array()?
+            .iter()
+            .find(|l| l["name"].as_str() == Some("terrain"))
+            .and_then(|l| l["id"].as_u64())? as u32;
 
-        Some((disk_io::ChunkCoord::new(x, y, z), chunk.octree))
+        log::info!("Applying {} unbaked edits from {} to terrain chunks as they stream in",
+            log.edit_count(), log_path.display());
+        Some(UnbakedEdits { log, layer_id, dictionary })
     }
 
     fn load_grass_masks_from_disk(world_path: &PathBuf) -> std::collections::HashMap<(i32, i32, i32), MaskOctree<GrassCell>> {
@@ -1018,25 +1524,22 @@ impl RenderResources {
             return result;
         }
 
-        // Read all .rkm files from grass directory
-        let entries = match std::fs::read_dir(&grass_dir) {
-            Ok(entries) => entries,
+        // Read every stored grass mask (region files, plus legacy .rkm files)
+        let store = RegionStore::new(&grass_dir);
+        let coords = match disk_io::stored_chunk_coords(&store, disk_io::GRASS_MASK_EXTENSION) {
+            Ok(coords) => coords,
             Err(e) => {
                 log::warn!("Failed to read grass directory: {}", e);
                 return result;
             }
         };
 
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("rkm") {
-                continue;
-            }
-
-            let data = match std::fs::read(&path) {
-                Ok(d) => d,
+        for coord in coords {
+            let data = match disk_io::read_stored_chunk(&store, coord, disk_io::GRASS_MASK_EXTENSION) {
+                Ok(Some(d)) => d,
+                Ok(None) => continue,
                 Err(e) => {
-                    log::warn!("Failed to read grass mask {}: {}", path.display(), e);
+                    log::warn!("Failed to read grass mask {:?}: {}", coord, e);
                     continue;
                 }
             };
@@ -1046,7 +1549,7 @@ impl RenderResources {
                     result.insert((coord.x, coord.y, coord.z), mask);
                 }
                 Err(e) => {
-                    log::warn!("Failed to decompress grass mask {}: {}", path.display(), e);
+                    log::warn!("Failed to decompress grass mask {:?}: {}", coord, e);
                 }
             }
         }
@@ -1175,6 +1678,8 @@ struct SharedDebugState {
     // Chunk info for GetChunkInfo (updated by render loop)
     loaded_chunks: std::collections::HashMap<(i32, i32, i32), ChunkDebugInfo>,
     loaded_chunks_grass: std::collections::HashMap<(i32, i32, i32), GrassDebugInfo>,
+    // Version of the render loop's chunk tables the above were copied from
+    chunk_tables_version: u64,
 
     // Current grass state (read-only, updated by render loop)
     current_grass_enabled: bool,
@@ -1225,6 +1730,7 @@ impl Default for SharedDebugState {
             chunk_bounds: Vec::new(),
             loaded_chunks: std::collections::HashMap::new(),
             loaded_chunks_grass: std::collections::HashMap::new(),
+            chunk_tables_version: 0,
             chunk_count: 0,
             world_extent: 0.0,
             current_fps: 0.0,
@@ -1755,6 +2261,7 @@ struct App {
     atmosphere: AtmosphereSystem,
     debug_mode: u32,  // 0=normal, 1=albedo, 2=normal, 3=depth, 4=material
     world_path: Option<PathBuf>,  // Path to pre-generated world
+    generate_missing: bool,  // Generate chunks beyond the pre-generated world
     debug_state: Arc<StdMutex<SharedDebugState>>,
     render_scale: f32,
     // Persistent god rays overrides (applied from debug state)
@@ -1770,7 +2277,7 @@ struct App {
 }
 
 impl App {
-    fn new(world_path: Option<PathBuf>, world_size: Option<f32>, debug_state: Arc<StdMutex<SharedDebugState>>) -> Self {
+    fn new(world_path: Option<PathBuf>, world_size: Option<f32>, generate_missing: bool, debug_state: Arc<StdMutex<SharedDebugState>>) -> Self {
         let mut config = SceneConfig::default();
         if let Some(size) = world_size {
             config.view_distance = size / 2.0; // size is diameter, view_distance is radius
@@ -1799,6 +2306,7 @@ impl App {
             },
             debug_mode: 0,
             world_path,
+            generate_missing,
             debug_state,
             render_scale: 1.0,
             godrays_density: 0.3,
@@ -2154,10 +2662,9 @@ impl App {
         if let Some(res) = &self.resources {
             state.chunk_count = res.chunk_count;
             state.world_extent = res.world_extent;
-            if state.chunk_bounds.is_empty() && !res.chunk_bounds.is_empty() {
+            if state.chunk_tables_version != res.chunk_tables_version {
+                state.chunk_tables_version = res.chunk_tables_version;
                 state.chunk_bounds = res.chunk_bounds.clone();
-            }
-            if state.loaded_chunks.is_empty() && !res.loaded_chunks.is_empty() {
                 state.loaded_chunks = res.loaded_chunks.clone();
                 state.loaded_chunks_grass = res.loaded_chunks_grass.clone();
             }
@@ -2216,7 +2723,8 @@ impl App {
         #[cfg(not(feature = "dlss"))]
         resources.camera_buffer.update_with_offset(&gpu.queue, &self.camera, resources.world_offset);
 
-        // Grid-based ray marching: chunk_info_buffer is static, no per-frame rewrite needed.
+        // Grid-based ray marching: chunk_info_buffer and the grid only change when
+        // streaming changes the resident chunks, not per frame.
         // The DDA in the shader uses the grid to find chunks along each ray.
 
         // Update trace params with grid acceleration
@@ -2734,7 +3242,7 @@ impl ApplicationHandler for App {
 
         // Create render resources - requires pre-generated v3 world
         let world_path = self.world_path.clone().expect("No world specified. Generate one with: cargo run --release --bin generate_world -- --size <N> --name <name>");
-        let resources = RenderResources::new(&gpu.device, &gpu.queue, gpu.format(), size.width, size.height, self.render_scale, &world_path, self.view_distance);
+        let resources = RenderResources::new(&gpu.device, &gpu.queue, gpu.format(), size.width, size.height, self.render_scale, &world_path, self.view_distance, self.generate_missing);
 
         #[cfg(feature = "dlss")]
         {
@@ -2928,6 +3436,12 @@ impl ApplicationHandler for App {
                 // Update camera
                 self.controller.update(&mut self.camera, &self.input, dt);
 
+                // Stream chunks around the camera
+                if let (Some(gpu), Some(resources)) = (&self.gpu, &mut self.resources) {
+                    let stats = resources.update_streaming(&gpu.device, &gpu.queue, &self.camera, dt);
+                    self.timer.record_scheduler(stats);
+                }
+
                 // Update FPS stats in debug state
                 {
                     let stats = self.timer.fps_stats();
@@ -3002,13 +3516,32 @@ fn main() {
     let args: Vec<String> = std::env::args().collect();
     let world_path = parse_world_arg(&args);
     let world_size = parse_size_arg(&args);
+    let generate_missing = args.iter().any(|a| a == "--generate-missing");
 
     if let Some(ref path) = world_path {
         log::info!("Loading world from: {}", path.display());
+
+        // Worlds carry the material registry their chunks were generated with
+        let materials_path = path.join("materials.json");
+        if materials_path.exists() {
+            match MaterialRegistry::load(&materials_path) {
+                Ok(registry) => {
+                    log::info!("Loaded {} materials", registry.len());
+                    if let Err(e) = material::install(registry) {
+                        log::error!("Failed to install materials from {}: {}", materials_path.display(), e);
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => log::warn!("Failed to load {}: {} (using built-in materials)", materials_path.display(), e),
+            }
+        }
     }
     if let Some(size) = world_size {
         log::info!("World size: {}m x {}m", size, size);
     }
+    if generate_missing {
+        log::info!("Generating chunks missing from the world as the camera reaches them");
+    }
 
     // Create shared debug state
     let debug_state = Arc::new(StdMutex::new(SharedDebugState::default()));
@@ -3039,7 +3572,7 @@ fn main() {
     });
 
     let event_loop = EventLoop::new().expect("Failed to create event loop");
-    let mut app = App::new(world_path, world_size, debug_state);
+    let mut app = App::new(world_path, world_size, generate_missing, debug_state);
 
     event_loop.run_app(&mut app).expect("Event loop error");
 }
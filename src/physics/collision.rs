@@ -0,0 +1,610 @@
+//! Voxel collision queries: swept AABBs and capsules against octree data
+//!
+//! Everything is built on `VoxelCollider::solid_boxes`, which gathers the
+//! axis-aligned boxes of solid voxels near a query region. Octree traversal
+//! skips empty subtrees and emits uniform bricks as a single box, so broad
+//! empty regions cost almost nothing.
+
+use glam::Vec3;
+
+use crate::math::Aabb;
+use crate::voxel::chunk::ChunkCoord;
+use crate::voxel::svo::Octree;
+use crate::voxel::world::World;
+use crate::voxel::world_index::WorldIndex;
+
+/// Tolerance for treating touching boxes as blocking in `move_aabb`
+const SKIN: f32 = 1e-4;
+
+/// Source of solid voxel geometry for collision queries
+pub trait VoxelCollider {
+    /// Append boxes of solid voxels that overlap `region`.
+    ///
+    /// Boxes may be coarser than single voxels (uniform bricks) but never
+    /// cover empty space.
+    fn solid_boxes(&self, region: &Aabb, out: &mut Vec<Aabb>);
+
+    /// Check if any solid voxel overlaps `region` (touching does not count)
+    fn overlaps_solid(&self, region: &Aabb) -> bool {
+        let mut boxes = Vec::new();
+        self.solid_boxes(region, &mut boxes);
+        boxes.iter().any(|b| overlaps(b, region))
+    }
+}
+
+/// Strict overlap test: boxes that only share a face do not overlap
+fn overlaps(a: &Aabb, b: &Aabb) -> bool {
+    a.min.x < b.max.x && a.max.x > b.min.x &&
+    a.min.y < b.max.y && a.max.y > b.min.y &&
+    a.min.z < b.max.z && a.max.z > b.min.z
+}
+
+fn translated(aabb: &Aabb, offset: Vec3) -> Aabb {
+    Aabb::new(aabb.min + offset, aabb.max + offset)
+}
+
+impl VoxelCollider for Octree {
+    /// `region` and the returned boxes are in octree-local space
+    fn solid_boxes(&self, region: &Aabb, out: &mut Vec<Aabb>) {
+        if self.root().is_empty() {
+            return;
+        }
+        let half = self.root_size() / 2.0;
+        let bounds = Aabb::new(Vec3::splat(-half), Vec3::splat(half));
+        collect_node(self, 0, &bounds, region, out);
+    }
+}
+
+fn collect_node(octree: &Octree, node_idx: u32, bounds: &Aabb, region: &Aabb, out: &mut Vec<Aabb>) {
+    let node = octree.node(node_idx);
+    if node.is_empty() || !overlaps(bounds, region) {
+        return;
+    }
+
+    if node.is_terminal_leaf() {
+        collect_brick(octree, node.brick_offset, bounds, region, out);
+        return;
+    }
+
+    let valid_mask = node.child_valid_mask();
+    let leaf_mask = node.child_leaf_mask();
+    for child_idx in 0..8u8 {
+        if valid_mask & (1 << child_idx) == 0 {
+            continue;
+        }
+        let child_bounds = bounds.child_octant(child_idx);
+        if !overlaps(&child_bounds, region) {
+            continue;
+        }
+        if leaf_mask & (1 << child_idx) != 0 {
+            let brick_idx = octree.leaf_brick_index(node, child_idx);
+            if (brick_idx as usize) < octree.brick_count() {
+                collect_brick(octree, brick_idx, &child_bounds, region, out);
+            }
+        } else {
+            let child_node_idx = octree.child_node_index(node, child_idx);
+            collect_node(octree, child_node_idx, &child_bounds, region, out);
+        }
+    }
+}
+
+fn collect_brick(octree: &Octree, brick_idx: u32, bounds: &Aabb, region: &Aabb, out: &mut Vec<Aabb>) {
+    let brick = octree.brick(brick_idx);
+    if let Some(voxel) = brick.is_uniform() {
+        if !voxel.is_empty() {
+            out.push(*bounds);
+        }
+        return;
+    }
+
+    // Brick voxel index uses the same bit layout as child_octant
+    for (i, voxel) in brick.voxels.iter().enumerate() {
+        if voxel.is_empty() {
+            continue;
+        }
+        let voxel_bounds = bounds.child_octant(i as u8);
+        if overlaps(&voxel_bounds, region) {
+            out.push(voxel_bounds);
+        }
+    }
+}
+
+/// Append solid boxes of a chunk octree, converting between world and octree-local space
+fn chunk_solid_boxes(octree: &Octree, coord: ChunkCoord, region: &Aabb, out: &mut Vec<Aabb>) {
+    let center = coord.world_origin() + Vec3::splat(octree.root_size() / 2.0);
+    let start = out.len();
+    octree.solid_boxes(&translated(region, -center), out);
+    for b in &mut out[start..] {
+        *b = translated(b, center);
+    }
+}
+
+/// Call `visit` for every chunk coordinate overlapped by a world-space region
+fn for_each_chunk(region: &Aabb, mut visit: impl FnMut(ChunkCoord)) {
+    let min = ChunkCoord::from_world_pos(region.min);
+    let max = ChunkCoord::from_world_pos(region.max);
+    for x in min.x..=max.x {
+        for y in min.y..=max.y {
+            for z in min.z..=max.z {
+                visit(ChunkCoord::new(x, y, z));
+            }
+        }
+    }
+}
+
+impl VoxelCollider for World {
+    /// `region` and the returned boxes are in world space
+    fn solid_boxes(&self, region: &Aabb, out: &mut Vec<Aabb>) {
+        for_each_chunk(region, |coord| {
+            if let Some(chunk) = self.get_chunk(coord) {
+                chunk_solid_boxes(&chunk.octree, coord, region, out);
+            }
+        });
+    }
+}
+
+impl VoxelCollider for WorldIndex {
+    /// `region` and the returned boxes are in world space.
+    /// Chunks that are not loaded are treated as empty.
+    fn solid_boxes(&self, region: &Aabb, out: &mut Vec<Aabb>) {
+        for_each_chunk(region, |coord| {
+            if let Some(handle) = self.get_chunk(coord) {
+                let state = handle.read_state();
+                if let Some(chunk) = state.get_chunk() {
+                    chunk_solid_boxes(&chunk.octree, coord, region, out);
+                }
+            }
+        });
+    }
+}
+
+/// First contact of a moving box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepHit {
+    /// Fraction of the motion (0..=1) travelled before contact
+    pub toi: f32,
+    /// Outward normal of the surface that was hit
+    pub normal: Vec3,
+}
+
+/// Sweep an AABB along `motion` and return the first solid voxel it touches.
+///
+/// Boxes the AABB already overlaps at the start are ignored, so a body that
+/// ended up embedded can still move out.
+pub fn sweep_aabb(collider: &dyn VoxelCollider, aabb: &Aabb, motion: Vec3) -> Option<SweepHit> {
+    let swept = aabb.merged(&translated(aabb, motion));
+    let mut boxes = Vec::new();
+    collider.solid_boxes(&swept, &mut boxes);
+
+    let center = aabb.center();
+    let half = aabb.half_extent();
+    let mut best: Option<SweepHit> = None;
+
+    for b in &boxes {
+        if overlaps(aabb, b) {
+            continue;
+        }
+
+        // Minkowski sum: sweep the center point against the expanded box
+        let expanded = Aabb::new(b.min - half, b.max + half);
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+        let mut hit_axis = None;
+        let mut missed = false;
+
+        for axis in 0..3 {
+            let o = center[axis];
+            let d = motion[axis];
+            if d == 0.0 {
+                if o <= expanded.min[axis] || o >= expanded.max[axis] {
+                    missed = true;
+                    break;
+                }
+                continue;
+            }
+            let t0 = (expanded.min[axis] - o) / d;
+            let t1 = (expanded.max[axis] - o) / d;
+            let (t0, t1) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > t_enter {
+                t_enter = t0;
+                hit_axis = Some(axis);
+            }
+            t_exit = t_exit.min(t1);
+        }
+
+        let Some(axis) = hit_axis else { continue };
+        if missed || t_enter >= t_exit || !(0.0..=1.0).contains(&t_enter) {
+            continue;
+        }
+        if best.is_none_or(|hit| t_enter < hit.toi) {
+            let mut normal = Vec3::ZERO;
+            normal[axis] = -motion[axis].signum();
+            best = Some(SweepHit { toi: t_enter, normal });
+        }
+    }
+
+    best
+}
+
+/// Outcome of `move_aabb`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveResult {
+    /// Motion actually applied
+    pub offset: Vec3,
+    /// Per-axis flag: motion along this axis was cut short by a voxel
+    pub blocked: [bool; 3],
+}
+
+/// Move an AABB as far as possible along `motion`, sliding along surfaces.
+///
+/// Axes are resolved one at a time (Y first, then X, then Z), clipping the
+/// motion against every solid box in the swept region. This is the usual
+/// voxel-game approach: cheap, exact on grid-aligned geometry and free of
+/// tunnelling regardless of speed.
+pub fn move_aabb(collider: &dyn VoxelCollider, aabb: &Aabb, motion: Vec3) -> MoveResult {
+    let mut current = *aabb;
+    let mut offset = Vec3::ZERO;
+    let mut blocked = [false; 3];
+
+    for axis in [1, 0, 2] {
+        let distance = motion[axis];
+        if distance == 0.0 {
+            continue;
+        }
+        let allowed = clip_axis(collider, &current, axis, distance);
+        if allowed.abs() < distance.abs() {
+            blocked[axis] = true;
+        }
+        let mut delta = Vec3::ZERO;
+        delta[axis] = allowed;
+        current = translated(&current, delta);
+        offset += delta;
+    }
+
+    MoveResult { offset, blocked }
+}
+
+/// Largest movement along one axis (same sign as `distance`) before touching a voxel
+fn clip_axis(collider: &dyn VoxelCollider, aabb: &Aabb, axis: usize, distance: f32) -> f32 {
+    let mut swept = *aabb;
+    if distance > 0.0 {
+        swept.max[axis] += distance;
+    } else {
+        swept.min[axis] += distance;
+    }
+
+    let mut boxes = Vec::new();
+    collider.solid_boxes(&swept, &mut boxes);
+
+    let mut allowed = distance;
+    for b in &boxes {
+        // Only boxes overlapping the other two axes can block this one
+        let in_path = (0..3)
+            .filter(|&a| a != axis)
+            .all(|a| b.min[a] < aabb.max[a] && b.max[a] > aabb.min[a]);
+        if !in_path {
+            continue;
+        }
+        if distance > 0.0 && b.min[axis] >= aabb.max[axis] - SKIN {
+            allowed = allowed.min((b.min[axis] - aabb.max[axis]).max(0.0));
+        } else if distance < 0.0 && b.max[axis] <= aabb.min[axis] + SKIN {
+            allowed = allowed.max((b.max[axis] - aabb.min[axis]).min(0.0));
+        }
+    }
+    allowed
+}
+
+/// Capsule: a line segment swept by a sphere
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule {
+    /// Segment start (sphere center)
+    pub a: Vec3,
+    /// Segment end (sphere center)
+    pub b: Vec3,
+    /// Sphere radius
+    pub radius: f32,
+}
+
+impl Capsule {
+    /// Create a capsule from its segment endpoints and radius
+    pub fn new(a: Vec3, b: Vec3, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    /// Upright capsule standing on `feet` with total `height` (including caps)
+    pub fn upright(feet: Vec3, height: f32, radius: f32) -> Self {
+        let cap = radius.min(height * 0.5);
+        Self::new(feet + Vec3::Y * cap, feet + Vec3::Y * (height - cap), radius)
+    }
+
+    /// Bounding box of the capsule
+    pub fn bounds(&self) -> Aabb {
+        let r = Vec3::splat(self.radius);
+        Aabb::new(self.a.min(self.b) - r, self.a.max(self.b) + r)
+    }
+
+    /// Capsule moved by `offset`
+    pub fn translated(&self, offset: Vec3) -> Self {
+        Self::new(self.a + offset, self.b + offset, self.radius)
+    }
+}
+
+/// Closest points between a segment and a box: (distance, segment point, box point)
+fn segment_box_closest(a: Vec3, b: Vec3, aabb: &Aabb) -> (f32, Vec3, Vec3) {
+    let point_at = |t: f32| a + (b - a) * t;
+    let dist_at = |t: f32| {
+        let p = point_at(t);
+        p.distance(p.clamp(aabb.min, aabb.max))
+    };
+
+    // Distance from a point moving along a line to a convex set is convex,
+    // so a ternary search finds the minimum.
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    for _ in 0..32 {
+        let m1 = lo + (hi - lo) / 3.0;
+        let m2 = hi - (hi - lo) / 3.0;
+        if dist_at(m1) <= dist_at(m2) {
+            hi = m2;
+        } else {
+            lo = m1;
+        }
+    }
+
+    let p = point_at((lo + hi) * 0.5);
+    let q = p.clamp(aabb.min, aabb.max);
+    (p.distance(q), p, q)
+}
+
+/// Check if a capsule overlaps any solid voxel
+pub fn capsule_overlaps(collider: &dyn VoxelCollider, capsule: &Capsule) -> bool {
+    let mut boxes = Vec::new();
+    collider.solid_boxes(&capsule.bounds(), &mut boxes);
+    boxes
+        .iter()
+        .any(|b| segment_box_closest(capsule.a, capsule.b, b).0 < capsule.radius)
+}
+
+/// Sweep a capsule along `motion` and return the first solid voxel it touches.
+///
+/// Like `sweep_aabb`, boxes the capsule already overlaps at the start are
+/// ignored. The normal points from the voxel towards the capsule at contact.
+pub fn sweep_capsule(collider: &dyn VoxelCollider, capsule: &Capsule, motion: Vec3) -> Option<SweepHit> {
+    if motion == Vec3::ZERO {
+        return None;
+    }
+    let swept = capsule.bounds().merged(&capsule.translated(motion).bounds());
+    let mut boxes = Vec::new();
+    collider.solid_boxes(&swept, &mut boxes);
+
+    let mut best: Option<SweepHit> = None;
+    for b in &boxes {
+        if segment_box_closest(capsule.a, capsule.b, b).0 < capsule.radius {
+            continue;
+        }
+        if let Some(hit) = sweep_capsule_box(capsule, motion, b)
+            && best.is_none_or(|best| hit.toi < best.toi)
+        {
+            best = Some(hit);
+        }
+    }
+    best
+}
+
+/// First contact of a moving capsule with one box it starts outside of
+fn sweep_capsule_box(capsule: &Capsule, motion: Vec3, aabb: &Aabb) -> Option<SweepHit> {
+    const MAX_ITERATIONS: usize = 32;
+    const TOLERANCE: f32 = 1e-4;
+
+    // The distance between a translating capsule and a box is convex in
+    // time, so Newton steps from t = 0 approach the first contact from
+    // below and never step through the box.
+    let mut t = 0.0;
+    let mut normal = -motion.normalize();
+    for _ in 0..MAX_ITERATIONS {
+        let offset = motion * t;
+        let (dist, p, q) = segment_box_closest(capsule.a + offset, capsule.b + offset, aabb);
+        if dist > 1e-6 {
+            normal = (p - q) / dist;
+        }
+        let gap = dist - capsule.radius;
+        if gap <= TOLERANCE {
+            return Some(SweepHit { toi: t, normal });
+        }
+        let closing = -motion.dot(normal);
+        if closing <= 0.0 {
+            return None;
+        }
+        t += gap / closing;
+        if t > 1.0 {
+            return None;
+        }
+    }
+    // Still closing in after every iteration: stop here rather than risk tunnelling
+    Some(SweepHit { toi: t, normal })
+}
+
+/// Compute the translation that pushes a capsule out of solid voxels.
+///
+/// Resolves the deepest penetration first and repeats a few times to settle
+/// corners. Returns `Vec3::ZERO` if the capsule is already free.
+pub fn resolve_capsule(collider: &dyn VoxelCollider, capsule: &Capsule) -> Vec3 {
+    const MAX_ITERATIONS: usize = 4;
+
+    let mut total = Vec3::ZERO;
+    let mut boxes = Vec::new();
+
+    for _ in 0..MAX_ITERATIONS {
+        let current = capsule.translated(total);
+        boxes.clear();
+        collider.solid_boxes(&current.bounds(), &mut boxes);
+
+        let mut deepest: Option<(f32, Vec3)> = None;
+        for b in &boxes {
+            let (dist, p, q) = segment_box_closest(current.a, current.b, b);
+            if dist >= current.radius {
+                continue;
+            }
+
+            let push = if dist > 1e-6 {
+                (p - q) / dist * (current.radius - dist)
+            } else {
+                // Segment passes through the box: exit via the nearest face
+                let to_min = p - b.min;
+                let to_max = b.max - p;
+                let mut best_push = Vec3::ZERO;
+                let mut best_depth = f32::INFINITY;
+                for axis in 0..3 {
+                    if to_min[axis] < best_depth {
+                        best_depth = to_min[axis];
+                        best_push = Vec3::ZERO;
+                        best_push[axis] = -(to_min[axis] + current.radius);
+                    }
+                    if to_max[axis] < best_depth {
+                        best_depth = to_max[axis];
+                        best_push = Vec3::ZERO;
+                        best_push[axis] = to_max[axis] + current.radius;
+                    }
+                }
+                best_push
+            };
+
+            let depth = push.length();
+            if deepest.is_none_or(|(d, _)| depth > d) {
+                deepest = Some((depth, push));
+            }
+        }
+
+        match deepest {
+            Some((_, push)) => total += push,
+            None => break,
+        }
+    }
+
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::chunk::{Chunk, CHUNK_SIZE};
+    use crate::voxel::svo::AdaptiveOctreeBuilder;
+    use crate::voxel::voxel::Voxel;
+
+    /// World with a single chunk filled where `solid(world_pos)` is true
+    fn world_with(coord: ChunkCoord, solid: impl Fn(Vec3) -> bool) -> World {
+        let builder = AdaptiveOctreeBuilder::new(32);
+        let octree = builder.build_simple(
+            &|pos: Vec3| if solid(pos) { Voxel::new(120, 120, 120, 1) } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        );
+        let mut world = World::new();
+        world.insert_chunk(Chunk::from_octree(coord, octree));
+        world
+    }
+
+    fn unit_box(center: Vec3) -> Aabb {
+        Aabb::from_center_half_extent(center, Vec3::splat(0.25))
+    }
+
+    #[test]
+    fn test_solid_boxes_skip_empty() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        let mut boxes = Vec::new();
+        world.solid_boxes(&Aabb::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(4.0, 4.0, 4.0)), &mut boxes);
+        assert!(boxes.is_empty());
+
+        world.solid_boxes(&Aabb::new(Vec3::new(1.0, 0.5, 1.0), Vec3::new(1.5, 1.5, 1.5)), &mut boxes);
+        assert!(!boxes.is_empty());
+        assert!(boxes.iter().all(|b| b.max.y <= 1.0 + 1e-5));
+    }
+
+    #[test]
+    fn test_overlaps_solid() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        assert!(world.overlaps_solid(&unit_box(Vec3::new(2.0, 1.0, 2.0))));
+        // Resting exactly on the surface is not an overlap
+        assert!(!world.overlaps_solid(&unit_box(Vec3::new(2.0, 1.25, 2.0))));
+    }
+
+    #[test]
+    fn test_sweep_aabb_hits_floor() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        let aabb = unit_box(Vec3::new(2.0, 3.25, 2.0));
+        let hit = sweep_aabb(&world, &aabb, Vec3::new(0.0, -4.0, 0.0)).expect("should hit floor");
+        assert!((hit.toi - 0.5).abs() < 1e-4);
+        assert_eq!(hit.normal, Vec3::Y);
+
+        // Moving away from the floor never hits
+        assert!(sweep_aabb(&world, &aabb, Vec3::new(0.0, 0.5, 0.0)).is_none());
+    }
+
+    #[test]
+    fn test_move_aabb_slides_along_floor() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        let aabb = unit_box(Vec3::new(1.0, 1.5, 1.0));
+        let result = move_aabb(&world, &aabb, Vec3::new(1.0, -2.0, 0.5));
+        assert!((result.offset.y + 0.25).abs() < 1e-4);
+        assert!((result.offset.x - 1.0).abs() < 1e-5);
+        assert!((result.offset.z - 0.5).abs() < 1e-5);
+        assert_eq!(result.blocked, [false, true, false]);
+    }
+
+    #[test]
+    fn test_move_aabb_no_tunnelling() {
+        // Thin wall at x in [2.0, 2.125)
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.x >= 2.0 && p.x < 2.125);
+        let aabb = unit_box(Vec3::new(1.0, 2.0, 2.0));
+        let result = move_aabb(&world, &aabb, Vec3::new(100.0, 0.0, 0.0));
+        assert!((result.offset.x - 0.75).abs() < 1e-4);
+        assert!(result.blocked[0]);
+    }
+
+    #[test]
+    fn test_capsule_overlap_and_resolve() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        let capsule = Capsule::upright(Vec3::new(2.0, 0.8, 2.0), 1.8, 0.3);
+        assert!(capsule_overlaps(&world, &capsule));
+
+        let push = resolve_capsule(&world, &capsule);
+        assert!((push.y - 0.2).abs() < 1e-3, "push = {:?}", push);
+        assert!(!capsule_overlaps(&world, &capsule.translated(push + Vec3::Y * 1e-3)));
+
+        let free = Capsule::upright(Vec3::new(2.0, 1.5, 2.0), 1.8, 0.3);
+        assert!(!capsule_overlaps(&world, &free));
+        assert_eq!(resolve_capsule(&world, &free), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_sweep_capsule_hits_floor_and_wall() {
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.y < 1.0);
+        let capsule = Capsule::upright(Vec3::new(2.0, 3.0, 2.0), 1.0, 0.25);
+        let hit = sweep_capsule(&world, &capsule, Vec3::new(0.0, -4.0, 0.0)).expect("should hit floor");
+        assert!((hit.toi - 0.5).abs() < 1e-3, "toi = {}", hit.toi);
+        assert!(hit.normal.abs_diff_eq(Vec3::Y, 1e-3), "normal = {:?}", hit.normal);
+        assert!(sweep_capsule(&world, &capsule, Vec3::new(0.0, 0.5, 0.0)).is_none());
+        // Gliding parallel to the floor never touches it
+        assert!(sweep_capsule(&world, &capsule, Vec3::new(1.0, 0.0, 0.5)).is_none());
+
+        // Thin wall at x in [2.0, 2.125) is not tunnelled through
+        let world = world_with(ChunkCoord::new(0, 0, 0), |p| p.x >= 2.0 && p.x < 2.125);
+        let capsule = Capsule::upright(Vec3::new(1.0, 1.0, 2.0), 1.0, 0.25);
+        let hit = sweep_capsule(&world, &capsule, Vec3::new(100.0, 0.0, 0.0)).expect("should hit wall");
+        assert!((hit.toi * 100.0 - 0.75).abs() < 1e-3, "toi = {}", hit.toi);
+        assert!(hit.normal.abs_diff_eq(-Vec3::X, 1e-3), "normal = {:?}", hit.normal);
+    }
+
+    #[test]
+    fn test_octree_local_space() {
+        let builder = AdaptiveOctreeBuilder::new(16);
+        let octree = builder.build_simple(
+            &|pos: Vec3| if pos.y < 0.0 { Voxel::new(1, 1, 1, 1) } else { Voxel::EMPTY },
+            Vec3::splat(-2.0),
+            4.0,
+        );
+        let mut boxes = Vec::new();
+        octree.solid_boxes(&Aabb::new(Vec3::splat(-2.0), Vec3::splat(2.0)), &mut boxes);
+        assert!(!boxes.is_empty());
+        assert!(boxes.iter().all(|b| b.min.y >= -2.0 - 1e-5 && b.max.y <= 1e-5));
+    }
+}
@@ -0,0 +1,352 @@
+//! Walking character controller against voxel terrain
+//!
+//! The character is an upright AABB moved with `move_aabb`. On top of the
+//! raw collision it adds gravity and jumping, stepping up small ledges,
+//! snapping down when walking off small drops, and a slope limit based on a
+//! ground normal estimated from the surrounding column heights.
+
+use glam::Vec3;
+
+use super::collision::{move_aabb, VoxelCollider};
+use crate::math::Aabb;
+
+/// Tunables for `CharacterController`
+#[derive(Clone, Debug)]
+pub struct CharacterConfig {
+    /// Half-width of the collision box on X and Z
+    pub radius: f32,
+    /// Height of the collision box
+    pub height: f32,
+    /// Camera height above the feet
+    pub eye_height: f32,
+    /// Tallest ledge the character walks up without jumping
+    pub step_height: f32,
+    /// Steepest walkable slope in degrees
+    pub max_slope_degrees: f32,
+    /// Downward acceleration in units per second squared
+    pub gravity: f32,
+    /// Upward velocity applied when jumping
+    pub jump_speed: f32,
+    /// Maximum falling speed
+    pub terminal_velocity: f32,
+    /// Horizontal speed when sliding down slopes steeper than the limit
+    pub slide_speed: f32,
+}
+
+impl Default for CharacterConfig {
+    fn default() -> Self {
+        Self {
+            radius: 0.3,
+            height: 1.8,
+            eye_height: 1.6,
+            step_height: 0.35,
+            max_slope_degrees: 50.0,
+            gravity: 20.0,
+            jump_speed: 6.0,
+            terminal_velocity: 50.0,
+            slide_speed: 4.0,
+        }
+    }
+}
+
+/// Walking-mode counterpart to `FpsCameraController`'s free-fly movement
+pub struct CharacterController {
+    /// Controller tunables
+    pub config: CharacterConfig,
+    /// Position of the feet (bottom center of the collision box)
+    pub position: Vec3,
+    /// Current velocity; only the vertical component persists between updates
+    pub velocity: Vec3,
+    grounded: bool,
+    ground_normal: Vec3,
+}
+
+impl CharacterController {
+    /// Create a controller with its feet at `position`
+    pub fn new(config: CharacterConfig, position: Vec3) -> Self {
+        Self {
+            config,
+            position,
+            velocity: Vec3::ZERO,
+            grounded: false,
+            ground_normal: Vec3::Y,
+        }
+    }
+
+    /// Check if standing on walkable ground
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Estimated normal of the ground below the feet (`Vec3::Y` when airborne)
+    pub fn ground_normal(&self) -> Vec3 {
+        self.ground_normal
+    }
+
+    /// Camera position for this character
+    pub fn eye_position(&self) -> Vec3 {
+        self.position + Vec3::Y * self.config.eye_height
+    }
+
+    /// Collision box at the current position
+    pub fn bounds(&self) -> Aabb {
+        self.bounds_at(self.position)
+    }
+
+    fn bounds_at(&self, feet: Vec3) -> Aabb {
+        let r = self.config.radius;
+        Aabb::new(
+            feet - Vec3::new(r, 0.0, r),
+            feet + Vec3::new(r, self.config.height, r),
+        )
+    }
+
+    /// Advance the character by `dt` seconds.
+    ///
+    /// `wish_velocity` is the desired horizontal velocity (its Y component is
+    /// ignored). `jump` only has an effect while grounded.
+    pub fn update(&mut self, collider: &dyn VoxelCollider, wish_velocity: Vec3, jump: bool, dt: f32) {
+        if dt <= 0.0 {
+            return;
+        }
+
+        if jump && self.grounded {
+            self.velocity.y = self.config.jump_speed;
+            self.grounded = false;
+        }
+
+        self.velocity.y = (self.velocity.y - self.config.gravity * dt).max(-self.config.terminal_velocity);
+
+        // Horizontal velocity comes from input, plus sliding on steep ground
+        let mut horizontal = Vec3::new(wish_velocity.x, 0.0, wish_velocity.z);
+        let steep = !self.is_walkable(self.ground_normal);
+        if steep {
+            let downhill = Vec3::new(self.ground_normal.x, 0.0, self.ground_normal.z).normalize_or_zero();
+            // Cannot walk uphill on a steep slope
+            let uphill_speed = -horizontal.dot(downhill);
+            if uphill_speed > 0.0 {
+                horizontal += downhill * uphill_speed;
+            }
+            horizontal += downhill * self.config.slide_speed;
+        }
+
+        // Vertical first, so landing is resolved before walking
+        let vertical = move_aabb(collider, &self.bounds(), Vec3::new(0.0, self.velocity.y * dt, 0.0));
+        self.position += vertical.offset;
+        let landed = vertical.blocked[1] && self.velocity.y < 0.0;
+        if vertical.blocked[1] {
+            self.velocity.y = 0.0;
+        }
+
+        let on_ground = landed || (self.grounded && self.velocity.y <= 0.0);
+        let motion = horizontal * dt;
+        if motion != Vec3::ZERO {
+            self.move_horizontal(collider, motion, on_ground && !steep);
+        }
+
+        self.update_ground(collider);
+        self.velocity.x = horizontal.x;
+        self.velocity.z = horizontal.z;
+    }
+
+    /// Horizontal move with step-up and snap-down while on walkable ground
+    fn move_horizontal(&mut self, collider: &dyn VoxelCollider, motion: Vec3, can_step: bool) {
+        let start = self.position;
+        let direct = move_aabb(collider, &self.bounds_at(start), motion);
+        let mut end = start + direct.offset;
+
+        let blocked = direct.blocked[0] || direct.blocked[2];
+        if can_step && blocked && self.config.step_height > 0.0 {
+            // Step up: rise, walk, then settle back down onto the ledge
+            let up = move_aabb(collider, &self.bounds_at(start), Vec3::Y * self.config.step_height);
+            let raised = start + up.offset;
+            let across = move_aabb(collider, &self.bounds_at(raised), motion);
+            let moved = raised + across.offset;
+            let down = move_aabb(collider, &self.bounds_at(moved), -Vec3::Y * up.offset.y);
+            let stepped = moved + down.offset;
+
+            let horizontal_progress = |p: Vec3| Vec3::new(p.x - start.x, 0.0, p.z - start.z).length_squared();
+            if down.blocked[1] && horizontal_progress(stepped) > horizontal_progress(end) + 1e-8 {
+                end = stepped;
+            }
+        }
+
+        if can_step {
+            // Follow the ground down small drops instead of launching off them
+            let snap = move_aabb(collider, &self.bounds_at(end), -Vec3::Y * self.config.step_height);
+            if snap.blocked[1] {
+                end += snap.offset;
+            }
+        }
+
+        self.position = end;
+    }
+
+    /// Refresh grounded state and ground normal from the terrain below the feet
+    fn update_ground(&mut self, collider: &dyn VoxelCollider) {
+        let probe = Vec3::new(0.0, -0.01, 0.0);
+        let touching = collider.overlaps_solid(&self.bounds_at(self.position + probe));
+        if !touching || self.velocity.y > 0.0 {
+            self.grounded = false;
+            self.ground_normal = Vec3::Y;
+            return;
+        }
+
+        self.ground_normal = self.estimate_ground_normal(collider);
+        self.grounded = self.is_walkable(self.ground_normal);
+    }
+
+    fn is_walkable(&self, normal: Vec3) -> bool {
+        normal.y >= self.config.max_slope_degrees.to_radians().cos() - 1e-4
+    }
+
+    /// Estimate the ground normal from column heights around the feet
+    fn estimate_ground_normal(&self, collider: &dyn VoxelCollider) -> Vec3 {
+        let d = self.config.radius;
+        // Deep enough to see the downhill side of slopes well past the limit
+        let top = self.position.y + self.config.step_height + 4.0 * d;
+        let bottom = self.position.y - self.config.step_height - 4.0 * d;
+        let center = self.position.y;
+
+        let height = |dx: f32, dz: f32| {
+            column_height(collider, self.position.x + dx, self.position.z + dz, bottom, top)
+                .unwrap_or(center)
+        };
+
+        let dh_dx = (height(d, 0.0) - height(-d, 0.0)) / (2.0 * d);
+        let dh_dz = (height(0.0, d) - height(0.0, -d)) / (2.0 * d);
+        Vec3::new(-dh_dx, 1.0, -dh_dz).normalize()
+    }
+}
+
+/// Highest solid surface in a thin vertical column between `bottom` and `top`
+fn column_height(collider: &dyn VoxelCollider, x: f32, z: f32, bottom: f32, top: f32) -> Option<f32> {
+    const HALF_WIDTH: f32 = 1e-3;
+    let column = Aabb::new(
+        Vec3::new(x - HALF_WIDTH, bottom, z - HALF_WIDTH),
+        Vec3::new(x + HALF_WIDTH, top, z + HALF_WIDTH),
+    );
+    let mut boxes = Vec::new();
+    collider.solid_boxes(&column, &mut boxes);
+    boxes
+        .iter()
+        .map(|b| b.max.y.min(top))
+        .reduce(f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
+    use crate::voxel::svo::AdaptiveOctreeBuilder;
+    use crate::voxel::voxel::Voxel;
+    use crate::voxel::world::World;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    /// Single-chunk world at the origin, solid where `solid(world_pos)` is true
+    fn world_with(solid: impl Fn(Vec3) -> bool) -> World {
+        let coord = ChunkCoord::new(0, 0, 0);
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if solid(pos) { Voxel::new(120, 120, 120, 1) } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        );
+        let mut world = World::new();
+        world.insert_chunk(Chunk::from_octree(coord, octree));
+        world
+    }
+
+    fn small_config() -> CharacterConfig {
+        CharacterConfig {
+            radius: 0.2,
+            height: 1.0,
+            eye_height: 0.9,
+            ..Default::default()
+        }
+    }
+
+    fn run(controller: &mut CharacterController, world: &World, wish: Vec3, frames: usize) {
+        for _ in 0..frames {
+            controller.update(world, wish, false, DT);
+        }
+    }
+
+    #[test]
+    fn test_falls_and_lands() {
+        let world = world_with(|p| p.y < 1.0);
+        let mut controller = CharacterController::new(small_config(), Vec3::new(2.0, 3.0, 2.0));
+        assert!(!controller.is_grounded());
+
+        run(&mut controller, &world, Vec3::ZERO, 120);
+        assert!(controller.is_grounded());
+        assert!((controller.position.y - 1.0).abs() < 1e-3, "feet at {}", controller.position.y);
+        assert_eq!(controller.velocity.y, 0.0);
+        assert!((controller.ground_normal().y - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_jump() {
+        let world = world_with(|p| p.y < 1.0);
+        let mut controller = CharacterController::new(small_config(), Vec3::new(2.0, 1.0, 2.0));
+        run(&mut controller, &world, Vec3::ZERO, 5);
+        assert!(controller.is_grounded());
+
+        controller.update(&world, Vec3::ZERO, true, DT);
+        assert!(!controller.is_grounded());
+        assert!(controller.position.y > 1.0);
+
+        run(&mut controller, &world, Vec3::ZERO, 120);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn test_steps_up_ledge() {
+        // 0.25 ledge at x >= 2
+        let world = world_with(|p| p.y < if p.x >= 2.0 { 1.25 } else { 1.0 });
+        let mut controller = CharacterController::new(small_config(), Vec3::new(1.0, 1.0, 2.0));
+        run(&mut controller, &world, Vec3::ZERO, 5);
+
+        run(&mut controller, &world, Vec3::new(2.0, 0.0, 0.0), 60);
+        assert!(controller.position.x > 2.5, "stuck at x = {}", controller.position.x);
+        assert!((controller.position.y - 1.25).abs() < 1e-3);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn test_blocked_by_wall() {
+        // Wall taller than the step height at x >= 2
+        let world = world_with(|p| p.y < 1.0 || (p.x >= 2.0 && p.y < 2.5));
+        let mut controller = CharacterController::new(small_config(), Vec3::new(1.0, 1.0, 2.0));
+        run(&mut controller, &world, Vec3::new(2.0, 0.0, 0.0), 60);
+
+        assert!((controller.position.x - 1.8).abs() < 1e-3, "x = {}", controller.position.x);
+        assert!((controller.position.y - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_walks_up_gentle_slope() {
+        // ~17 degree ramp rising along +X
+        let world = world_with(|p| p.y < 0.5 + 0.3 * p.x);
+        let mut controller = CharacterController::new(small_config(), Vec3::new(0.8, 2.0, 2.0));
+        run(&mut controller, &world, Vec3::ZERO, 60);
+        let start_x = controller.position.x;
+
+        run(&mut controller, &world, Vec3::new(1.5, 0.0, 0.0), 60);
+        assert!(controller.position.x > start_x + 1.0, "x = {}", controller.position.x);
+        assert!(controller.is_grounded());
+    }
+
+    #[test]
+    fn test_slides_down_steep_slope() {
+        // ~63 degree ramp: each voxel column rises less than the step height,
+        // so only the slope limit keeps the character from climbing it
+        let world = world_with(|p| p.y < 0.2 + 2.0 * (p.x - 1.0).max(0.0));
+        let mut controller = CharacterController::new(small_config(), Vec3::new(2.0, 3.0, 2.0));
+        run(&mut controller, &world, Vec3::ZERO, 30);
+        let start_x = controller.position.x;
+
+        run(&mut controller, &world, Vec3::new(1.5, 0.0, 0.0), 30);
+        assert!(controller.position.x < start_x, "x = {} (started {})", controller.position.x, start_x);
+    }
+}
@@ -0,0 +1,14 @@
+//! Physics: voxel collision and character movement
+//!
+//! Collision queries run directly against octree data (`Octree`, `World`,
+//! `WorldIndex`) so gameplay code can keep bodies out of terrain without
+//! building separate collision meshes.
+
+pub mod collision;
+pub mod controller;
+
+pub use collision::{
+    capsule_overlaps, move_aabb, resolve_capsule, sweep_aabb, sweep_capsule, Capsule, MoveResult,
+    SweepHit, VoxelCollider,
+};
+pub use controller::{CharacterConfig, CharacterController};
@@ -6,7 +6,7 @@ use crate::grass::profile::GrassCell;
 use crate::mask::octree::MaskOctree;
 use crate::streaming::disk_io::ChunkCoord;
 use crate::voxel::chunk::CHUNK_SIZE;
-use crate::voxel::svo::{Octree, OctreeNode};
+use crate::voxel::svo::{Octree, OctreeNode, SubtreeDictionary, DICTIONARY_REF};
 use crate::voxel::brick::VoxelBrick;
 use crate::voxel::streaming::feedback::MAX_FEEDBACK_REQUESTS;
 
@@ -107,6 +107,10 @@ pub struct OctreeBuffer {
     used_nodes: u32,
     /// Next free brick slot (tracks how many bricks have been uploaded)
     used_bricks: u32,
+    /// Nodes of the shared subtree dictionary at the start of the node buffer
+    dictionary_nodes: u32,
+    /// Bricks of the shared subtree dictionary at the start of the brick buffer
+    dictionary_bricks: u32,
 }
 
 impl OctreeBuffer {
@@ -383,6 +387,8 @@ impl OctreeBuffer {
             max_bricks,
             used_nodes: 0,
             used_bricks: 0,
+            dictionary_nodes: 0,
+            dictionary_bricks: 0,
         }
     }
 
@@ -403,30 +409,11 @@ impl OctreeBuffer {
         let mut chunk_infos: Vec<GpuChunkInfo> = Vec::new();
 
         for (coord, octree) in chunks {
-            let node_base = all_nodes.len() as u32;
-            let brick_base = all_bricks.len() as u32;
+            let node_base = self.dictionary_nodes + all_nodes.len() as u32;
+            let brick_base = self.dictionary_bricks + all_bricks.len() as u32;
 
             // Copy nodes with adjusted offsets
-            let src_nodes = octree.nodes_slice();
-            for node in src_nodes {
-                let mut adjusted = *node;
-                let valid = node.child_valid_mask();
-                let leaf = node.child_leaf_mask();
-
-                // Offset child_offset if this node has any internal (non-leaf) children
-                let has_internal = valid & !leaf;
-                if has_internal != 0 {
-                    adjusted.child_offset += node_base;
-                }
-
-                // Offset brick_offset if this node has any leaf children OR is a terminal leaf
-                let has_leaves = valid & leaf;
-                if has_leaves != 0 || node.is_terminal_leaf() {
-                    adjusted.brick_offset += brick_base;
-                }
-
-                all_nodes.push(adjusted);
-            }
+            all_nodes.extend(octree.nodes_slice().iter().map(|node| rebase_node(node, node_base, brick_base)));
 
             // Copy bricks as-is (no offsets needed)
             all_bricks.extend_from_slice(octree.bricks_slice());
@@ -448,16 +435,17 @@ impl OctreeBuffer {
             });
         }
 
-        assert!(all_nodes.len() <= self.max_nodes as usize,
-            "Too many total nodes: {} (max {})", all_nodes.len(), self.max_nodes);
-        assert!(all_bricks.len() <= self.max_bricks as usize,
-            "Too many total bricks: {} (max {})", all_bricks.len(), self.max_bricks);
+        assert!(self.dictionary_nodes as usize + all_nodes.len() <= self.max_nodes as usize,
+            "Too many total nodes: {} + {} shared (max {})", all_nodes.len(), self.dictionary_nodes, self.max_nodes);
+        assert!(self.dictionary_bricks as usize + all_bricks.len() <= self.max_bricks as usize,
+            "Too many total bricks: {} + {} shared (max {})", all_bricks.len(), self.dictionary_bricks, self.max_bricks);
 
         log::info!("Uploading {} chunks: {} nodes, {} bricks to GPU",
             chunks.len(), all_nodes.len(), all_bricks.len());
 
-        queue.write_buffer(&self.node_buffer, 0, bytemuck::cast_slice(&all_nodes));
-        queue.write_buffer(&self.brick_buffer, 0, bytemuck::cast_slice(&all_bricks));
+        // Chunks go after the shared dictionary, which stays in place
+        queue.write_buffer(&self.node_buffer, self.dictionary_node_bytes(), bytemuck::cast_slice(&all_nodes));
+        queue.write_buffer(&self.brick_buffer, self.dictionary_brick_bytes(), bytemuck::cast_slice(&all_bricks));
         queue.write_buffer(&self.chunk_info_buffer, 0, bytemuck::cast_slice(&chunk_infos));
 
         // Initialize feedback header
@@ -465,8 +453,8 @@ impl OctreeBuffer {
         queue.write_buffer(&self.feedback_header_buffer, 0, bytemuck::cast_slice(&feedback_header));
 
         // Update usage counters
-        self.used_nodes = all_nodes.len() as u32;
-        self.used_bricks = all_bricks.len() as u32;
+        self.used_nodes = self.dictionary_nodes + all_nodes.len() as u32;
+        self.used_bricks = self.dictionary_bricks + all_bricks.len() as u32;
 
         (chunks.len() as u32, chunk_infos)
     }
@@ -492,30 +480,13 @@ impl OctreeBuffer {
         let mut chunk_infos: Vec<GpuChunkInfo> = Vec::new();
 
         for entry in entries {
-            let node_base = all_nodes.len() as u32;
-            let brick_base = all_bricks.len() as u32;
+            let node_base = self.dictionary_nodes + all_nodes.len() as u32;
+            let brick_base = self.dictionary_bricks + all_bricks.len() as u32;
 
             let octree = &entry.octree;
 
             // Copy nodes with adjusted offsets
-            let src_nodes = octree.nodes_slice();
-            for node in src_nodes {
-                let mut adjusted = *node;
-                let valid = node.child_valid_mask();
-                let leaf = node.child_leaf_mask();
-
-                let has_internal = valid & !leaf;
-                if has_internal != 0 {
-                    adjusted.child_offset += node_base;
-                }
-
-                let has_leaves = valid & leaf;
-                if has_leaves != 0 || node.is_terminal_leaf() {
-                    adjusted.brick_offset += brick_base;
-                }
-
-                all_nodes.push(adjusted);
-            }
+            all_nodes.extend(octree.nodes_slice().iter().map(|node| rebase_node(node, node_base, brick_base)));
 
             all_bricks.extend_from_slice(octree.bricks_slice());
 
@@ -529,16 +500,17 @@ impl OctreeBuffer {
             });
         }
 
-        assert!(all_nodes.len() <= self.max_nodes as usize,
-            "Too many total nodes: {} (max {})", all_nodes.len(), self.max_nodes);
-        assert!(all_bricks.len() <= self.max_bricks as usize,
-            "Too many total bricks: {} (max {})", all_bricks.len(), self.max_bricks);
+        assert!(self.dictionary_nodes as usize + all_nodes.len() <= self.max_nodes as usize,
+            "Too many total nodes: {} + {} shared (max {})", all_nodes.len(), self.dictionary_nodes, self.max_nodes);
+        assert!(self.dictionary_bricks as usize + all_bricks.len() <= self.max_bricks as usize,
+            "Too many total bricks: {} + {} shared (max {})", all_bricks.len(), self.dictionary_bricks, self.max_bricks);
 
         log::info!("Uploading {} flat entries: {} nodes, {} bricks to GPU",
             entries.len(), all_nodes.len(), all_bricks.len());
 
-        queue.write_buffer(&self.node_buffer, 0, bytemuck::cast_slice(&all_nodes));
-        queue.write_buffer(&self.brick_buffer, 0, bytemuck::cast_slice(&all_bricks));
+        // Chunks go after the shared dictionary, which stays in place
+        queue.write_buffer(&self.node_buffer, self.dictionary_node_bytes(), bytemuck::cast_slice(&all_nodes));
+        queue.write_buffer(&self.brick_buffer, self.dictionary_brick_bytes(), bytemuck::cast_slice(&all_bricks));
         queue.write_buffer(&self.chunk_info_buffer, 0, bytemuck::cast_slice(&chunk_infos));
 
         // Initialize feedback header
@@ -546,8 +518,8 @@ impl OctreeBuffer {
         queue.write_buffer(&self.feedback_header_buffer, 0, bytemuck::cast_slice(&feedback_header));
 
         // Update usage counters
-        self.used_nodes = all_nodes.len() as u32;
-        self.used_bricks = all_bricks.len() as u32;
+        self.used_nodes = self.dictionary_nodes + all_nodes.len() as u32;
+        self.used_bricks = self.dictionary_bricks + all_bricks.len() as u32;
 
         (entries.len() as u32, chunk_infos)
     }
@@ -587,24 +559,9 @@ impl OctreeBuffer {
         );
 
         // Rebase child_offset/brick_offset by current used counts
-        let mut adjusted_nodes: Vec<OctreeNode> = Vec::with_capacity(src_nodes.len());
-        for node in src_nodes {
-            let mut adjusted = *node;
-            let valid = node.child_valid_mask();
-            let leaf = node.child_leaf_mask();
-
-            let has_internal = valid & !leaf;
-            if has_internal != 0 {
-                adjusted.child_offset += node_base;
-            }
-
-            let has_leaves = valid & leaf;
-            if has_leaves != 0 || node.is_terminal_leaf() {
-                adjusted.brick_offset += brick_base;
-            }
-
-            adjusted_nodes.push(adjusted);
-        }
+        let adjusted_nodes: Vec<OctreeNode> = src_nodes.iter()
+            .map(|node| rebase_node(node, node_base, brick_base))
+            .collect();
 
         // Write nodes at byte offset for the current used_nodes position
         let node_byte_offset = (node_base as u64) * (std::mem::size_of::<OctreeNode>() as u64);
@@ -633,10 +590,57 @@ impl OctreeBuffer {
         }
     }
 
-    /// Reset usage counters (for full reload scenarios).
+    /// Upload a layer's shared subtree dictionary to the start of the node
+    /// and brick buffers.
+    ///
+    /// Must come before any chunk upload. Chunk nodes with `DICTIONARY_REF`
+    /// offsets uploaded afterwards point into it, so data shared by many
+    /// chunks is on the GPU once.
+    pub fn upload_dictionary(&mut self, queue: &wgpu::Queue, dictionary: &SubtreeDictionary) {
+        assert!(
+            self.used_nodes == self.dictionary_nodes && self.used_bricks == self.dictionary_bricks,
+            "Shared dictionary must be uploaded before any chunk"
+        );
+        assert!(
+            dictionary.node_count() <= self.max_nodes as usize && dictionary.brick_count() <= self.max_bricks as usize,
+            "Shared dictionary exceeds buffer capacity: {} nodes, {} bricks",
+            dictionary.node_count(), dictionary.brick_count()
+        );
+
+        queue.write_buffer(&self.node_buffer, 0, bytemuck::cast_slice(dictionary.nodes_slice()));
+        queue.write_buffer(&self.brick_buffer, 0, bytemuck::cast_slice(dictionary.bricks_slice()));
+        self.dictionary_nodes = dictionary.node_count() as u32;
+        self.dictionary_bricks = dictionary.brick_count() as u32;
+        self.used_nodes = self.dictionary_nodes;
+        self.used_bricks = self.dictionary_bricks;
+
+        log::info!("Uploaded shared dictionary: {} nodes, {} bricks ({:.1} KB)",
+            self.dictionary_nodes, self.dictionary_bricks, dictionary.memory_usage() as f64 / 1024.0);
+    }
+
+    /// Reset usage counters (for full reload scenarios). The shared
+    /// dictionary stays uploaded.
     pub fn reset_usage(&mut self) {
-        self.used_nodes = 0;
-        self.used_bricks = 0;
+        self.used_nodes = self.dictionary_nodes;
+        self.used_bricks = self.dictionary_bricks;
+    }
+
+    /// Number of node slots held by the shared dictionary.
+    pub fn dictionary_nodes(&self) -> u32 {
+        self.dictionary_nodes
+    }
+
+    /// Number of brick slots held by the shared dictionary.
+    pub fn dictionary_bricks(&self) -> u32 {
+        self.dictionary_bricks
+    }
+
+    fn dictionary_node_bytes(&self) -> u64 {
+        (self.dictionary_nodes as u64) * (std::mem::size_of::<OctreeNode>() as u64)
+    }
+
+    fn dictionary_brick_bytes(&self) -> u64 {
+        (self.dictionary_bricks as u64) * (std::mem::size_of::<VoxelBrick>() as u64)
     }
 
     /// Number of node slots currently in use.
@@ -863,6 +867,30 @@ impl OctreeBuffer {
     }
 }
 
+/// Make a chunk node's offsets absolute in the shared buffers.
+///
+/// Chunk-local offsets move by the chunk's base; `DICTIONARY_REF` offsets
+/// point into the shared dictionary at the start of the buffers.
+fn rebase_node(node: &OctreeNode, node_base: u32, brick_base: u32) -> OctreeNode {
+    let rebase = |offset: u32, base: u32| {
+        if offset & DICTIONARY_REF != 0 { offset & !DICTIONARY_REF } else { offset + base }
+    };
+    let mut adjusted = *node;
+    let valid = node.child_valid_mask();
+    let leaf = node.child_leaf_mask();
+
+    // Offset child_offset if this node has any internal (non-leaf) children
+    if valid & !leaf != 0 {
+        adjusted.child_offset = rebase(node.child_offset, node_base);
+    }
+
+    // Offset brick_offset if this node has any leaf children OR is a terminal leaf
+    if valid & leaf != 0 || node.is_terminal_leaf() {
+        adjusted.brick_offset = rebase(node.brick_offset, brick_base);
+    }
+    adjusted
+}
+
 /// Pack grass mask octrees into GPU-ready data.
 ///
 /// `masks` is indexed by chunk index (matching terrain upload order).
@@ -3,6 +3,7 @@
 use bytemuck::{Pod, Zeroable};
 use crate::render::buffer::{OctreeBuffer, CameraBuffer};
 use crate::grass::{GrassParams, GpuGrassProfile};
+use crate::voxel::material::{GpuMaterial, MAX_MATERIALS};
 
 /// Maximum number of grass profiles supported on GPU.
 pub const MAX_GRASS_PROFILES: u32 = 16;
@@ -35,6 +36,7 @@ pub struct SvoTracePipeline {
     params_buffer: wgpu::Buffer,
     grass_buffer: wgpu::Buffer,
     profile_table_buffer: wgpu::Buffer,
+    material_buffer: wgpu::Buffer,
     #[allow(dead_code)]
     params_bind_group_layout: wgpu::BindGroupLayout,
     params_bind_group: wgpu::BindGroup,
@@ -77,7 +79,15 @@ impl SvoTracePipeline {
             mapped_at_creation: false,
         });
 
-        // Bind group 0: Camera + Params + Grass + Profile Table
+        // Material table (storage buffer, one entry per material ID)
+        let material_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("material_table"),
+            size: (MAX_MATERIALS as u64) * (std::mem::size_of::<GpuMaterial>() as u64),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Bind group 0: Camera + Params + Grass + Profile Table + Materials
         let params_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("trace_params_layout"),
             entries: &[
@@ -122,6 +132,17 @@ impl SvoTracePipeline {
                     },
                     count: None,
                 },
+                // binding 4: material table (storage)
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -145,6 +166,10 @@ impl SvoTracePipeline {
                     binding: 3,
                     resource: profile_table_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: material_buffer.as_entire_binding(),
+                },
             ],
         });
 
@@ -236,6 +261,7 @@ impl SvoTracePipeline {
             params_buffer,
             grass_buffer,
             profile_table_buffer,
+            material_buffer,
             params_bind_group_layout,
             params_bind_group,
             output_bind_group_layout,
@@ -307,6 +333,17 @@ impl SvoTracePipeline {
         }
     }
 
+    /// Upload the material table (see `MaterialRegistry::gpu_table`)
+    pub fn update_material_table(&self, queue: &wgpu::Queue, materials: &[GpuMaterial]) {
+        assert!(
+            materials.len() <= MAX_MATERIALS,
+            "Too many materials: {} (max {})",
+            materials.len(),
+            MAX_MATERIALS
+        );
+        queue.write_buffer(&self.material_buffer, 0, bytemuck::cast_slice(materials));
+    }
+
     /// Dispatch compute shader
     pub fn dispatch(
         &self,
@@ -6,7 +6,9 @@
 /// Memory budget manager
 ///
 /// Tracks CPU and GPU memory usage and provides pressure metrics to guide
-/// chunk loading and eviction decisions.
+/// chunk loading and eviction decisions. This is the one budget shared by
+/// `ChunkStreamer` and the `StreamingOrchestrator` it drives.
+#[derive(Clone, Debug)]
 pub struct MemoryBudget {
     /// Maximum CPU memory allowed (bytes)
     cpu_budget_bytes: usize,
@@ -69,6 +71,16 @@ impl MemoryBudget {
 
     // --- Query methods ---
 
+    /// Get the CPU memory budget in bytes
+    pub fn cpu_budget(&self) -> usize {
+        self.cpu_budget_bytes
+    }
+
+    /// Get the GPU memory budget in bytes
+    pub fn gpu_budget(&self) -> usize {
+        self.gpu_budget_bytes
+    }
+
     /// Get current CPU memory usage in bytes
     pub fn cpu_used(&self) -> usize {
         self.cpu_used_bytes
@@ -133,6 +145,13 @@ impl MemoryBudget {
     }
 }
 
+impl Default for MemoryBudget {
+    /// 1 GB of cached chunks, 2.5 GB of GPU nodes and bricks
+    fn default() -> Self {
+        Self::new(1024, 2560)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,9 +1,20 @@
 //! Async chunk loading system with priority-based concurrent loading
-
-use crate::streaming::disk_io::{Chunk, ChunkCoord, load_chunk};
-use std::collections::HashSet;
+//!
+//! Chunks missing on disk can fall back to procedural generation, which
+//! runs on the rayon pool so the I/O tasks are never blocked by it.
+
+use crate::generation::GenerationPipeline;
+use crate::streaming::disk_io::{
+    Chunk, ChunkCoord, load_chunk_neighbourhood, load_layer_dictionary, load_stored_chunk, save_stored_chunk,
+};
+use crate::streaming::region::RegionStore;
+use crate::voxel::chunk::CHUNK_SIZE;
+use crate::voxel::svo::{OcclusionBaker, Octree, SubtreeDictionary, SvdagBuilder};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{mpsc, oneshot};
 use tokio::runtime::Runtime;
 
 /// Request to load a chunk with priority
@@ -22,10 +33,243 @@ pub enum LoadResult {
     Generated(Chunk),
     /// Chunk file not found on disk
     NotFound(ChunkCoord),
+    /// Chunk data on disk is damaged or unreadable (bad header, checksum
+    /// or payload); the caller should regenerate the chunk
+    Corrupt(ChunkCoord, String),
     /// Error during loading
     Error(ChunkCoord, String),
 }
 
+/// Where a streamed layer's chunks come from
+///
+/// `ChunkLoader` reads them from disk; replays and tests can answer with
+/// mock chunks instead.
+pub trait ChunkSource: Send {
+    /// Queue a chunk load. Returns `false` if the chunk is already pending.
+    fn request(&mut self, coord: ChunkCoord, priority: f32) -> bool;
+
+    /// Take the loads that finished since the last call (non-blocking)
+    fn poll_results(&mut self) -> Vec<LoadResult>;
+}
+
+/// Which octree of a generated chunk a loader produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratedLayer {
+    Terrain,
+    Rocks,
+    Vegetation,
+}
+
+impl GeneratedLayer {
+    const COUNT: usize = 3;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn bit(self) -> u8 {
+        1 << self.index()
+    }
+}
+
+/// Most pipeline runs kept for layers that have not taken their octree yet.
+/// Past it, finished runs are dropped and regenerated if a layer asks again.
+const MAX_SHARED_RUNS: usize = 256;
+
+/// One pipeline run for a chunk, shared by the layers generating it
+struct GenerationRun {
+    /// Octree of every layer, set once the pipeline finished
+    octrees: OnceLock<[Octree; GeneratedLayer::COUNT]>,
+    /// Layers that took their octree (bit per `GeneratedLayer`)
+    taken: AtomicU8,
+}
+
+/// Pipeline runs shared by the `ChunkGeneration`s of one pipeline
+struct SharedRuns {
+    /// Layers generating from these runs (bit per `GeneratedLayer`)
+    layers: AtomicU8,
+    /// Runs some layer has not taken its octree from yet
+    runs: Mutex<HashMap<ChunkCoord, Arc<GenerationRun>>>,
+    /// Pipeline runs started
+    started: AtomicUsize,
+}
+
+impl SharedRuns {
+    /// Get the run for a chunk, starting one if there is none
+    fn run(&self, coord: ChunkCoord) -> Arc<GenerationRun> {
+        let mut runs = self.runs.lock().unwrap();
+        if let Some(run) = runs.get(&coord) {
+            return run.clone();
+        }
+        if runs.len() >= MAX_SHARED_RUNS {
+            runs.retain(|_, run| run.octrees.get().is_none());
+        }
+        let run = Arc::new(GenerationRun { octrees: OnceLock::new(), taken: AtomicU8::new(0) });
+        runs.insert(coord, run.clone());
+        run
+    }
+
+    /// Record a layer taking its octree; the run is dropped once every layer did
+    fn take(&self, coord: ChunkCoord, run: &Arc<GenerationRun>, layer: GeneratedLayer) {
+        let taken = run.taken.fetch_or(layer.bit(), Ordering::AcqRel) | layer.bit();
+        let layers = self.layers.load(Ordering::Acquire);
+        if taken & layers == layers {
+            let mut runs = self.runs.lock().unwrap();
+            if runs.get(&coord).is_some_and(|r| Arc::ptr_eq(r, run)) {
+                runs.remove(&coord);
+            }
+        }
+    }
+}
+
+/// Procedural fallback for chunks that are not on disk
+///
+/// Generations for the layers of one pipeline should come from one another
+/// through `for_layer`: they share pipeline runs, so a chunk missing in
+/// several layers is generated once and each layer takes its octree.
+///
+/// Written-back chunks are what the pipeline produces, SVDAG-compressed.
+/// `generate_world`'s post-passes are not applied: occlusion is only baked
+/// if enabled with `with_occlusion`, chunks don't reference the layer's
+/// shared dictionary, and the far-field proxy of their super chunk is not
+/// rebuilt. Brick attributes follow the pipeline's
+/// `GenerationConfig::terrain_attributes`.
+#[derive(Clone)]
+pub struct ChunkGeneration {
+    pipeline: Arc<GenerationPipeline>,
+    layer: GeneratedLayer,
+    write_back: bool,
+    occlusion: Option<Arc<OcclusionBaker>>,
+    /// Shared dictionary of the layer, loaded on the first occlusion bake
+    dictionary: Arc<OnceLock<Option<SubtreeDictionary>>>,
+    shared: Arc<SharedRuns>,
+}
+
+impl ChunkGeneration {
+    /// Generate `layer` with `pipeline`, without writing results to disk
+    pub fn new(pipeline: Arc<GenerationPipeline>, layer: GeneratedLayer) -> Self {
+        let shared = Arc::new(SharedRuns {
+            layers: AtomicU8::new(layer.bit()),
+            runs: Mutex::new(HashMap::new()),
+            started: AtomicUsize::new(0),
+        });
+        Self {
+            pipeline,
+            layer,
+            write_back: false,
+            occlusion: None,
+            dictionary: Arc::new(OnceLock::new()),
+            shared,
+        }
+    }
+
+    /// Generate another layer from the same pipeline runs, with the same
+    /// write-back and occlusion settings
+    pub fn for_layer(&self, layer: GeneratedLayer) -> Self {
+        self.shared.layers.fetch_or(layer.bit(), Ordering::AcqRel);
+        Self { layer, dictionary: Arc::new(OnceLock::new()), ..self.clone() }
+    }
+
+    /// Store generated chunks in the loader's region files, so they are
+    /// loaded from disk next time
+    pub fn with_write_back(mut self, write_back: bool) -> Self {
+        self.write_back = write_back;
+        self
+    }
+
+    /// Bake ambient occlusion and sky visibility into generated chunks on
+    /// the loader's worker, like `generate_world --bake-ao`.
+    ///
+    /// The bake traces against the neighbours already in the loader's region
+    /// files; missing ones count as open air. Neighbours generated later
+    /// don't rebake this chunk, so its borders toward them stay lighter
+    /// than a full-world bake.
+    pub fn with_occlusion(mut self, baker: OcclusionBaker) -> Self {
+        self.occlusion = Some(Arc::new(baker));
+        self
+    }
+
+    /// Get the generated layer
+    pub fn layer(&self) -> GeneratedLayer {
+        self.layer
+    }
+
+    /// Number of chunks the pipeline ran for, across the layers sharing runs
+    pub fn pipeline_runs(&self) -> usize {
+        self.shared.started.load(Ordering::Relaxed)
+    }
+
+    /// Check if the pipeline can produce anything at this chunk (it lies
+    /// within the terrain's vertical extent for its column)
+    pub fn may_generate(&self, coord: ChunkCoord) -> bool {
+        let center = coord.world_origin() + glam::Vec3::splat(CHUNK_SIZE as f32 * 0.5);
+        let (min_y, max_y) = self.pipeline.chunk_y_range(center.x, center.z);
+        (min_y..=max_y).contains(&coord.y)
+    }
+
+    /// Generate a chunk SVDAG-compressed, like chunks stored on disk.
+    /// Returns None if the layer is empty there.
+    ///
+    /// Runs the pipeline unless a layer sharing runs with this one already
+    /// did for the chunk (or is doing so, in which case this waits for it).
+    pub fn generate(&self, coord: ChunkCoord) -> Option<Octree> {
+        if !self.may_generate(coord) {
+            return None;
+        }
+        let run = self.shared.run(coord);
+        let octrees = run.octrees.get_or_init(|| {
+            self.shared.started.fetch_add(1, Ordering::Relaxed);
+            let generated = self.pipeline.generate_chunk_with_grass(coord);
+            [
+                generated.chunk.octree,
+                generated.layer_octrees.rocks_octree,
+                generated.layer_octrees.vegetation_octree,
+            ]
+        });
+        let octree = &octrees[self.layer.index()];
+        let result = (octree.brick_count() > 0).then(|| SvdagBuilder::new().build_full(octree));
+        self.shared.take(coord, &run, self.layer);
+        result
+    }
+
+    /// Generate a chunk, bake its occlusion and write it back if enabled
+    fn generate_into(&self, store: &RegionStore, coord: ChunkCoord) -> LoadResult {
+        let Some(mut octree) = self.generate(coord) else {
+            return LoadResult::NotFound(coord);
+        };
+        if let Some(baker) = &self.occlusion {
+            octree = self.bake_occlusion(store, coord, octree, baker);
+        }
+        let chunk = Chunk::from_octree(coord, octree);
+        if self.write_back && let Err(e) = save_stored_chunk(store, &chunk) {
+            log::warn!("Failed to store generated chunk {:?}: {}", coord, e);
+        }
+        LoadResult::Generated(chunk)
+    }
+
+    /// Bake occlusion into a generated chunk against its neighbours on disk.
+    /// Returns the chunk unbaked if the neighbours can't be read.
+    fn bake_occlusion(&self, store: &RegionStore, coord: ChunkCoord, octree: Octree, baker: &OcclusionBaker) -> Octree {
+        let dictionary = self.dictionary.get_or_init(|| {
+            load_layer_dictionary(store).unwrap_or_else(|e| {
+                log::warn!("Unreadable shared dictionary in {}: {}", store.dir().display(), e);
+                None
+            })
+        });
+        let mut neighbourhood = match load_chunk_neighbourhood(store, coord, dictionary.as_ref()) {
+            Ok(neighbourhood) => neighbourhood,
+            Err(e) => {
+                log::warn!("Skipping occlusion bake of generated chunk {:?}: {}", coord, e);
+                return octree;
+            }
+        };
+        // Replaces the corrupt copy on disk, if that is why it was generated
+        neighbourhood.insert(coord, octree);
+        let baked = baker.bake_chunk(coord, &neighbourhood).expect("chunk is in its own neighbourhood");
+        SvdagBuilder::new().build_full(&baked)
+    }
+}
+
 /// Concurrent chunk loader with async I/O
 pub struct ChunkLoader {
     /// Channel for sending load requests to worker tasks
@@ -36,6 +280,10 @@ pub struct ChunkLoader {
     pending: HashSet<ChunkCoord>,
     /// Base directory for chunk storage
     base_dir: PathBuf,
+    /// Region files under `base_dir`, shared with the worker tasks
+    store: Arc<RegionStore>,
+    /// Fallback for chunks missing on disk
+    generation: Option<ChunkGeneration>,
     /// Tokio runtime handle (optional - if None, uses current runtime)
     #[allow(dead_code)]
     runtime: Option<Runtime>,
@@ -45,20 +293,36 @@ impl ChunkLoader {
     /// Create a new chunk loader
     ///
     /// # Arguments
-    /// * `base_dir` - Directory where chunks are stored
+    /// * `base_dir` - Directory where chunks are stored (region files, with
+    ///   legacy per-chunk files as a fallback)
     /// * `max_concurrent` - Maximum number of concurrent load operations
     pub fn new(base_dir: PathBuf, max_concurrent: usize) -> Self {
+        Self::spawn(base_dir, max_concurrent, None)
+    }
+
+    /// Create a chunk loader that generates chunks missing on disk
+    ///
+    /// Missing chunks come back as `LoadResult::Generated`, or `NotFound`
+    /// if the generated layer is empty there. Corrupt chunks are
+    /// regenerated too.
+    pub fn new_generating(base_dir: PathBuf, max_concurrent: usize, generation: ChunkGeneration) -> Self {
+        Self::spawn(base_dir, max_concurrent, Some(generation))
+    }
+
+    fn spawn(base_dir: PathBuf, max_concurrent: usize, generation: Option<ChunkGeneration>) -> Self {
         let (request_tx, mut request_rx) = mpsc::unbounded_channel::<LoadRequest>();
         let (result_tx, result_rx) = mpsc::unbounded_channel::<LoadResult>();
 
         // Create a dedicated runtime for async operations
         let runtime = Runtime::new().expect("Failed to create tokio runtime");
 
-        let base_dir_clone = base_dir.clone();
+        let store = Arc::new(RegionStore::new(base_dir.clone()));
+        let store_clone = store.clone();
+        let generation_clone = generation.clone();
 
         // Spawn the worker task on the runtime
         runtime.spawn(async move {
-            Self::worker_loop(base_dir_clone, max_concurrent, &mut request_rx, result_tx).await;
+            Self::worker_loop(store_clone, generation_clone, max_concurrent, &mut request_rx, result_tx).await;
         });
 
         Self {
@@ -66,6 +330,8 @@ impl ChunkLoader {
             result_rx,
             pending: HashSet::new(),
             base_dir,
+            store,
+            generation,
             runtime: Some(runtime),
         }
     }
@@ -75,14 +341,32 @@ impl ChunkLoader {
     /// This is useful when the caller already has a tokio runtime active.
     /// Panics if called outside a tokio runtime context.
     pub fn new_with_current_runtime(base_dir: PathBuf, max_concurrent: usize) -> Self {
+        Self::spawn_on_current(base_dir, max_concurrent, None)
+    }
+
+    /// Create a chunk loader that generates chunks missing on disk, using
+    /// the current tokio runtime (see `new_generating`)
+    ///
+    /// Panics if called outside a tokio runtime context.
+    pub fn new_generating_with_current_runtime(
+        base_dir: PathBuf,
+        max_concurrent: usize,
+        generation: ChunkGeneration,
+    ) -> Self {
+        Self::spawn_on_current(base_dir, max_concurrent, Some(generation))
+    }
+
+    fn spawn_on_current(base_dir: PathBuf, max_concurrent: usize, generation: Option<ChunkGeneration>) -> Self {
         let (request_tx, mut request_rx) = mpsc::unbounded_channel::<LoadRequest>();
         let (result_tx, result_rx) = mpsc::unbounded_channel::<LoadResult>();
 
-        let base_dir_clone = base_dir.clone();
+        let store = Arc::new(RegionStore::new(base_dir.clone()));
+        let store_clone = store.clone();
+        let generation_clone = generation.clone();
 
         // Spawn on the current runtime
         tokio::spawn(async move {
-            Self::worker_loop(base_dir_clone, max_concurrent, &mut request_rx, result_tx).await;
+            Self::worker_loop(store_clone, generation_clone, max_concurrent, &mut request_rx, result_tx).await;
         });
 
         Self {
@@ -90,13 +374,16 @@ impl ChunkLoader {
             result_rx,
             pending: HashSet::new(),
             base_dir,
+            store,
+            generation,
             runtime: None,
         }
     }
 
     /// Worker loop that processes load requests with concurrency control
     async fn worker_loop(
-        base_dir: PathBuf,
+        store: Arc<RegionStore>,
+        generation: Option<ChunkGeneration>,
         max_concurrent: usize,
         request_rx: &mut mpsc::UnboundedReceiver<LoadRequest>,
         result_tx: mpsc::UnboundedSender<LoadResult>,
@@ -141,21 +428,45 @@ impl ChunkLoader {
                 pending_requests.sort_by(|a, b| b.priority.partial_cmp(&a.priority).unwrap_or(std::cmp::Ordering::Equal));
                 let request = pending_requests.remove(0);
 
-                let base_dir_clone = base_dir.clone();
+                let store = store.clone();
+                let generation = generation.clone();
                 active_tasks.spawn(async move {
-                    Self::load_chunk_task(base_dir_clone, request.coord).await
+                    Self::load_chunk_task(store, generation, request.coord).await
                 });
             }
         }
     }
 
-    /// Task that loads a single chunk
-    async fn load_chunk_task(base_dir: PathBuf, coord: ChunkCoord) -> LoadResult {
-        match load_chunk(&base_dir, coord).await {
-            Ok(Some(chunk)) => LoadResult::Loaded(chunk),
-            Ok(None) => LoadResult::NotFound(coord),
+    /// Task that loads a single chunk, generating it if missing or corrupt
+    async fn load_chunk_task(store: Arc<RegionStore>, generation: Option<ChunkGeneration>, coord: ChunkCoord) -> LoadResult {
+        let disk_store = store.clone();
+        // Loaded chunks go to the GPU, which resolves dictionary references itself
+        let loaded = tokio::task::spawn_blocking(move || load_stored_chunk(&disk_store, coord)).await;
+        let result = match loaded {
+            Ok(Ok(Some(chunk))) => LoadResult::Loaded(chunk),
+            Ok(Ok(None)) => LoadResult::NotFound(coord),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::InvalidData => {
+                LoadResult::Corrupt(coord, e.to_string())
+            }
+            Ok(Err(e)) => LoadResult::Error(coord, e.to_string()),
             Err(e) => LoadResult::Error(coord, e.to_string()),
+        };
+
+        let Some(generation) = generation else {
+            return result;
+        };
+        match result {
+            LoadResult::NotFound(_) => {}
+            LoadResult::Corrupt(_, ref e) => log::warn!("Regenerating corrupt chunk {:?}: {}", coord, e),
+            _ => return result,
         }
+
+        // Generation is CPU-bound: run it on the rayon pool
+        let (tx, rx) = oneshot::channel();
+        rayon::spawn(move || {
+            let _ = tx.send(generation.generate_into(&store, coord));
+        });
+        rx.await.unwrap_or_else(|e| LoadResult::Error(coord, e.to_string()))
     }
 
     /// Request a chunk to be loaded
@@ -187,6 +498,7 @@ impl ChunkLoader {
                 LoadResult::Loaded(chunk) => chunk.coord,
                 LoadResult::Generated(chunk) => chunk.coord,
                 LoadResult::NotFound(coord) => *coord,
+                LoadResult::Corrupt(coord, _) => *coord,
                 LoadResult::Error(coord, _) => *coord,
             };
             self.pending.remove(&coord);
@@ -220,6 +532,26 @@ impl ChunkLoader {
     pub fn base_dir(&self) -> &PathBuf {
         &self.base_dir
     }
+
+    /// Get the region store chunks are read from
+    pub fn store(&self) -> &Arc<RegionStore> {
+        &self.store
+    }
+
+    /// Get the fallback for chunks missing on disk, if any
+    pub fn generation(&self) -> Option<&ChunkGeneration> {
+        self.generation.as_ref()
+    }
+}
+
+impl ChunkSource for ChunkLoader {
+    fn request(&mut self, coord: ChunkCoord, priority: f32) -> bool {
+        ChunkLoader::request(self, coord, priority)
+    }
+
+    fn poll_results(&mut self) -> Vec<LoadResult> {
+        ChunkLoader::poll_results(self)
+    }
 }
 
 impl Drop for ChunkLoader {
@@ -235,6 +567,8 @@ impl Drop for ChunkLoader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::voxel::svo::DICTIONARY_REF;
+    use crate::voxel::svo::occlusion::has_baked_occlusion;
 
     #[test]
     fn test_load_request_creation() {
@@ -287,6 +621,126 @@ mod tests {
         assert!(!loader.is_pending(coord));
     }
 
+    fn poll_one(loader: &mut ChunkLoader) -> LoadResult {
+        for _ in 0..6000 {
+            if let Some(result) = loader.poll_results().pop() {
+                return result;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("Chunk load timed out");
+    }
+
+    #[test]
+    fn test_generate_on_miss_writes_back() {
+        use crate::generation::GenerationConfig;
+
+        let temp_dir = std::env::temp_dir().join("rktri_loader_generate_test");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let pipeline = Arc::new(GenerationPipeline::new(&GenerationConfig::default()));
+        let generation = ChunkGeneration::new(pipeline.clone(), GeneratedLayer::Terrain)
+            .with_write_back(true);
+
+        // The surface chunk of a column far outside any pre-generated world
+        let (x, z) = (4000, -3000);
+        let center = CHUNK_SIZE as f32 * 0.5;
+        let height = pipeline.height_at(x as f32 * CHUNK_SIZE as f32 + center, z as f32 * CHUNK_SIZE as f32 + center);
+        let coord = ChunkCoord::new(x, (height / CHUNK_SIZE as f32).floor() as i32, z);
+        let sky = ChunkCoord::new(x, coord.y + 100, z);
+        assert!(generation.may_generate(coord));
+        assert!(!generation.may_generate(sky));
+
+        let mut loader = ChunkLoader::new_generating(temp_dir.clone(), 2, generation);
+        loader.request(coord, 1.0);
+        let generated = match poll_one(&mut loader) {
+            LoadResult::Generated(chunk) => chunk,
+            other => panic!("Expected Generated, got {:?}", other),
+        };
+        assert_eq!(generated.coord, coord);
+        assert!(generated.octree.brick_count() > 0);
+
+        loader.request(sky, 1.0);
+        assert!(matches!(poll_one(&mut loader), LoadResult::NotFound(c) if c == sky));
+
+        // Written back: a plain loader now finds it on disk
+        let mut plain = ChunkLoader::new(temp_dir.clone(), 2);
+        plain.request(coord, 1.0);
+        match poll_one(&mut plain) {
+            LoadResult::Loaded(chunk) => {
+                assert_eq!(chunk.octree.brick_count(), generated.octree.brick_count());
+                // generate_world's post-passes are not applied to written-back chunks
+                assert!(!has_baked_occlusion(&chunk.octree));
+                assert!(chunk.octree.nodes_slice().iter().all(|n| {
+                    n.child_offset & DICTIONARY_REF == 0 && n.brick_offset & DICTIONARY_REF == 0
+                }));
+            }
+            other => panic!("Expected Loaded, got {:?}", other),
+        }
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_generate_with_occlusion() {
+        use crate::generation::GenerationConfig;
+
+        let temp_dir = std::env::temp_dir().join("rktri_loader_occlusion_test");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let pipeline = Arc::new(GenerationPipeline::new(&GenerationConfig::default()));
+        let generation = ChunkGeneration::new(pipeline.clone(), GeneratedLayer::Terrain)
+            .with_write_back(true)
+            .with_occlusion(OcclusionBaker::new().with_cones(4));
+        assert!(generation.for_layer(GeneratedLayer::Rocks).occlusion.is_some());
+
+        let (x, z) = (-3700, -4100);
+        let center = CHUNK_SIZE as f32 * 0.5;
+        let height = pipeline.height_at(x as f32 * CHUNK_SIZE as f32 + center, z as f32 * CHUNK_SIZE as f32 + center);
+        let coord = ChunkCoord::new(x, (height / CHUNK_SIZE as f32).floor() as i32, z);
+
+        let mut loader = ChunkLoader::new_generating(temp_dir.clone(), 2, generation);
+        loader.request(coord, 1.0);
+        match poll_one(&mut loader) {
+            LoadResult::Generated(chunk) => assert!(has_baked_occlusion(&chunk.octree)),
+            other => panic!("Expected Generated, got {:?}", other),
+        }
+
+        // The written-back copy keeps the bake
+        let stored = load_stored_chunk(loader.store(), coord).unwrap().unwrap();
+        assert!(has_baked_occlusion(&stored.octree));
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_layers_share_pipeline_runs() {
+        use crate::generation::GenerationConfig;
+
+        let pipeline = Arc::new(GenerationPipeline::new(&GenerationConfig::default()));
+        let terrain = ChunkGeneration::new(pipeline.clone(), GeneratedLayer::Terrain);
+        let rocks = terrain.for_layer(GeneratedLayer::Rocks);
+        let vegetation = terrain.for_layer(GeneratedLayer::Vegetation);
+
+        let (x, z) = (-2500, 3100);
+        let center = CHUNK_SIZE as f32 * 0.5;
+        let height = pipeline.height_at(x as f32 * CHUNK_SIZE as f32 + center, z as f32 * CHUNK_SIZE as f32 + center);
+        let coord = ChunkCoord::new(x, (height / CHUNK_SIZE as f32).floor() as i32, z);
+
+        let handles: Vec<_> = [terrain.clone(), rocks, vegetation]
+            .into_iter()
+            .map(|generation| std::thread::spawn(move || generation.generate(coord)))
+            .collect();
+        let octrees: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert!(octrees[0].is_some());
+        assert_eq!(terrain.pipeline_runs(), 1);
+        // Every layer took its octree, so the run is gone
+        assert!(terrain.shared.runs.lock().unwrap().is_empty());
+
+        // Asking again runs the pipeline again
+        terrain.generate(coord);
+        assert_eq!(terrain.pipeline_runs(), 2);
+    }
+
     // Async tests require tokio test support
     // #[tokio::test]
     // async fn test_load_nonexistent_chunk() {
@@ -2,25 +2,17 @@
 
 use crate::grass::profile::GrassCell;
 use crate::mask::{MaskOctree, MaskNode};
-use crate::voxel::svo::{Octree, node::OctreeNode};
+use crate::streaming::region::RegionStore;
+use crate::voxel::svo::{ChunkNeighbourhood, Octree, SubtreeDictionary, node::OctreeNode};
+use crate::voxel::attributes::BrickAttributes;
 use crate::voxel::brick::VoxelBrick;
+use crate::voxel::super_chunk::SuperChunkCoord;
 use rkyv::{Archive, Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::io;
 
-/// Chunk coordinate in world space
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Archive, Deserialize, Serialize)]
-pub struct ChunkCoord {
-    pub x: i32,
-    pub y: i32,
-    pub z: i32,
-}
-
-impl ChunkCoord {
-    pub fn new(x: i32, y: i32, z: i32) -> Self {
-        Self { x, y, z }
-    }
-}
+/// Chunk coordinate in world space (the same type as `voxel::chunk::ChunkCoord`)
+pub use crate::voxel::chunk::ChunkCoord;
 
 /// Serializable chunk data (v2 - raw, uncompressed)
 ///
@@ -56,6 +48,36 @@ pub struct SvdagChunkData {
     pub bricks: Vec<VoxelBrick>,
 }
 
+/// Serializable chunk data with a brick attribute channel (v4)
+///
+/// Only written for octrees that carry attributes, so plain chunks keep
+/// the v2/v3 layouts and their 4-byte voxels.
+#[derive(Archive, Deserialize, Serialize)]
+pub struct AttributedChunkData {
+    pub coord_x: i32,
+    pub coord_y: i32,
+    pub coord_z: i32,
+    pub root_size: f32,
+    pub max_depth: u8,
+    /// OctreeNode data (SVDAG-compressed when written by `compress_svdag_chunk`)
+    pub nodes: Vec<OctreeNode>,
+    /// VoxelBrick data
+    pub bricks: Vec<VoxelBrick>,
+    /// One BrickAttributes per brick
+    pub attributes: Vec<BrickAttributes>,
+}
+
+/// Serializable shared subtree dictionary of a world layer
+///
+/// Chunks of the layer reference these runs with `DICTIONARY_REF` offsets.
+#[derive(Archive, Deserialize, Serialize)]
+pub struct DictionaryData {
+    /// Dictionary nodes (offsets are dictionary-local)
+    pub nodes: Vec<OctreeNode>,
+    /// Dictionary bricks (brick 0 is padding)
+    pub bricks: Vec<VoxelBrick>,
+}
+
 /// Runtime chunk containing octree and metadata
 pub struct Chunk {
     pub coord: ChunkCoord,
@@ -164,10 +186,59 @@ pub fn deserialize_svdag_chunk(data: &[u8]) -> Result<Chunk, io::Error> {
     Ok(Chunk::from_octree(coord, octree))
 }
 
-/// Compress a serialized chunk using LZ4
+/// Serialize a chunk whose octree carries brick attributes (v4)
+pub fn serialize_attributed_chunk(chunk: &Chunk) -> Result<Vec<u8>, io::Error> {
+    let attributes = chunk.octree.attributes_slice()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "octree has no brick attributes"))?;
+    let data = AttributedChunkData {
+        coord_x: chunk.coord.x,
+        coord_y: chunk.coord.y,
+        coord_z: chunk.coord.z,
+        root_size: chunk.octree.root_size(),
+        max_depth: chunk.octree.max_depth(),
+        nodes: chunk.octree.nodes_slice().to_vec(),
+        bricks: chunk.octree.bricks_slice().to_vec(),
+        attributes: attributes.to_vec(),
+    };
+
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&data)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    Ok(bytes.to_vec())
+}
+
+/// Deserialize a chunk with brick attributes from bytes (v4)
+pub fn deserialize_attributed_chunk(data: &[u8]) -> Result<Chunk, io::Error> {
+    let archived = rkyv::access::<ArchivedAttributedChunkData, rkyv::rancor::Error>(data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+    let chunk_data: AttributedChunkData = rkyv::deserialize::<AttributedChunkData, rkyv::rancor::Error>(archived)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+    if chunk_data.attributes.len() != chunk_data.bricks.len() {
+        return Err(corrupt("brick attribute count mismatch"));
+    }
+    let mut octree = Octree::from_serialized(
+        chunk_data.root_size,
+        chunk_data.max_depth,
+        chunk_data.nodes,
+        chunk_data.bricks,
+    );
+    octree.set_attributes(chunk_data.attributes);
+
+    let coord = ChunkCoord::new(chunk_data.coord_x, chunk_data.coord_y, chunk_data.coord_z);
+
+    Ok(Chunk::from_octree(coord, octree))
+}
+
+/// Compress a serialized chunk using LZ4 (with a `ChunkHeader`)
+///
+/// Octrees with brick attributes are written as v4 payloads.
 pub fn compress_chunk(chunk: &Chunk) -> Result<Vec<u8>, io::Error> {
+    if chunk.octree.has_attributes() {
+        return Ok(pack_payload(PayloadKind::Attributed, &serialize_attributed_chunk(chunk)?));
+    }
     let serialized = serialize_chunk(chunk)?;
-    Ok(lz4_flex::compress_prepend_size(&serialized))
+    Ok(pack_payload(PayloadKind::Octree, &serialized))
 }
 
 /// Serialize an SVDAG-compressed chunk (for v3 world format)
@@ -187,21 +258,251 @@ pub fn serialize_svdag_chunk(chunk: &Chunk) -> Result<Vec<u8>, io::Error> {
     Ok(bytes.to_vec())
 }
 
+/// Compress a serialized SVDAG chunk using LZ4 (v3 format, with a `ChunkHeader`)
+///
+/// Octrees with brick attributes are written as v4 payloads.
+pub fn compress_svdag_chunk(chunk: &Chunk) -> Result<Vec<u8>, io::Error> {
+    if chunk.octree.has_attributes() {
+        return Ok(pack_payload(PayloadKind::Attributed, &serialize_attributed_chunk(chunk)?));
+    }
+    let serialized = serialize_svdag_chunk(chunk)?;
+    Ok(pack_payload(PayloadKind::Svdag, &serialized))
+}
+
 /// Decompress and deserialize a chunk (v2 format)
+///
+/// Accepts headered and legacy headerless data. Corrupt data is reported
+/// as `io::ErrorKind::InvalidData`.
 pub fn decompress_chunk(data: &[u8]) -> Result<Chunk, io::Error> {
-    let decompressed = lz4_flex::decompress_size_prepended(data)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("LZ4 decompression failed: {}", e)))?;
-    deserialize_chunk(&decompressed)
+    match unpack_payload(data, &[PayloadKind::Octree, PayloadKind::Svdag, PayloadKind::Attributed])? {
+        (Some(PayloadKind::Attributed), decompressed) => deserialize_attributed_chunk(&decompressed),
+        (_, decompressed) => deserialize_chunk(&decompressed),
+    }
 }
 
 /// Decompress and deserialize an SVDAG-compressed chunk (v3 format)
+///
+/// Accepts headered and legacy headerless data. Corrupt data is reported
+/// as `io::ErrorKind::InvalidData`.
 pub fn decompress_svdag_chunk(data: &[u8]) -> Result<Chunk, io::Error> {
-    let decompressed = lz4_flex::decompress_size_prepended(data)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("LZ4 decompression failed: {}", e)))?;
-    deserialize_svdag_chunk(&decompressed)
+    match unpack_payload(data, &[PayloadKind::Svdag, PayloadKind::Octree, PayloadKind::Attributed])? {
+        (Some(PayloadKind::Attributed), decompressed) => deserialize_attributed_chunk(&decompressed),
+        (_, decompressed) => deserialize_svdag_chunk(&decompressed),
+    }
+}
+
+// --- Chunk file header ---
+
+/// Magic bytes at the start of every headered chunk file
+pub const CHUNK_MAGIC: [u8; 4] = *b"RKCF";
+/// Current chunk header version
+pub const CHUNK_HEADER_VERSION: u16 = 1;
+/// Size of an encoded `ChunkHeader` in bytes
+pub const CHUNK_HEADER_SIZE: usize = 16;
+
+/// What a chunk file's payload contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PayloadKind {
+    /// Octree chunk (v2, `ChunkData`)
+    Octree = 1,
+    /// SVDAG-compressed octree chunk (v3, `SvdagChunkData`)
+    Svdag = 2,
+    /// Grass mask (`GrassMaskData`)
+    GrassMask = 3,
+    /// Shared subtree dictionary of a layer (`DictionaryData`)
+    Dictionary = 4,
+    /// Octree chunk with brick attributes (v4, `AttributedChunkData`)
+    Attributed = 5,
+}
+
+impl PayloadKind {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Octree),
+            2 => Some(Self::Svdag),
+            3 => Some(Self::GrassMask),
+            4 => Some(Self::Dictionary),
+            5 => Some(Self::Attributed),
+            _ => None,
+        }
+    }
+}
+
+/// Self-describing header in front of a chunk file's LZ4 payload.
+///
+/// Layout (little endian): magic `RKCF`, version u16, payload kind u8,
+/// reserved u8, uncompressed size u32, CRC-32 of the compressed payload u32.
+/// Files written before the header existed start directly with the LZ4
+/// size prefix and are still readable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkHeader {
+    pub version: u16,
+    pub kind: PayloadKind,
+    pub uncompressed_size: u32,
+    pub crc: u32,
+}
+
+impl ChunkHeader {
+    /// Encode the header to bytes
+    pub fn to_bytes(&self) -> [u8; CHUNK_HEADER_SIZE] {
+        let mut bytes = [0u8; CHUNK_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(&CHUNK_MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6] = self.kind as u8;
+        bytes[8..12].copy_from_slice(&self.uncompressed_size.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.crc.to_le_bytes());
+        bytes
+    }
+
+    /// Parse the header at the start of `data`.
+    ///
+    /// Returns `Ok(None)` for legacy data without a header, and
+    /// `InvalidData` for a header that can't be understood.
+    pub fn parse(data: &[u8]) -> Result<Option<Self>, io::Error> {
+        if data.len() < 4 || data[0..4] != CHUNK_MAGIC {
+            return Ok(None);
+        }
+        if data.len() < CHUNK_HEADER_SIZE {
+            return Err(corrupt("truncated chunk header"));
+        }
+
+        let version = u16::from_le_bytes([data[4], data[5]]);
+        if version > CHUNK_HEADER_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported chunk header version: {}", version),
+            ));
+        }
+        let kind = PayloadKind::from_u8(data[6])
+            .ok_or_else(|| corrupt(&format!("unknown payload kind {}", data[6])))?;
+
+        Ok(Some(Self {
+            version,
+            kind,
+            uncompressed_size: u32::from_le_bytes(data[8..12].try_into().unwrap()),
+            crc: u32::from_le_bytes(data[12..16].try_into().unwrap()),
+        }))
+    }
+}
+
+fn corrupt(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Corrupt chunk: {}", msg))
+}
+
+/// Compress `serialized` and prepend a `ChunkHeader`
+fn pack_payload(kind: PayloadKind, serialized: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::compress(serialized);
+    let header = ChunkHeader {
+        version: CHUNK_HEADER_VERSION,
+        kind,
+        uncompressed_size: serialized.len() as u32,
+        crc: crc32fast::hash(&compressed),
+    };
+
+    let mut data = Vec::with_capacity(CHUNK_HEADER_SIZE + compressed.len());
+    data.extend_from_slice(&header.to_bytes());
+    data.extend_from_slice(&compressed);
+    data
+}
+
+/// Verify and decompress chunk file data.
+///
+/// Headered data must have one of the `expected` payload kinds and a
+/// matching checksum. Legacy headerless data is returned as kind `None`.
+fn unpack_payload(data: &[u8], expected: &[PayloadKind]) -> Result<(Option<PayloadKind>, Vec<u8>), io::Error> {
+    let Some(header) = ChunkHeader::parse(data)? else {
+        let decompressed = lz4_flex::decompress_size_prepended(data)
+            .map_err(|e| corrupt(&format!("LZ4 decompression failed: {}", e)))?;
+        return Ok((None, decompressed));
+    };
+
+    if !expected.contains(&header.kind) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected chunk payload {:?} (expected {:?})", header.kind, expected),
+        ));
+    }
+
+    let payload = &data[CHUNK_HEADER_SIZE..];
+    if crc32fast::hash(payload) != header.crc {
+        return Err(corrupt("checksum mismatch"));
+    }
+    let decompressed = lz4_flex::decompress(payload, header.uncompressed_size as usize)
+        .map_err(|e| corrupt(&format!("LZ4 decompression failed: {}", e)))?;
+    if decompressed.len() != header.uncompressed_size as usize {
+        return Err(corrupt("size mismatch"));
+    }
+    Ok((Some(header.kind), decompressed))
+}
+
+/// A decoded chunk file of any payload kind
+pub enum AnyChunk {
+    /// Octree chunk (v2)
+    Octree(Chunk),
+    /// SVDAG-compressed octree chunk (v3)
+    Svdag(Chunk),
+    /// Octree chunk with brick attributes (v4)
+    Attributed(Chunk),
+    /// Grass mask
+    GrassMask(ChunkCoord, MaskOctree<GrassCell>),
+}
+
+impl AnyChunk {
+    /// Payload kind of this chunk
+    pub fn kind(&self) -> PayloadKind {
+        match self {
+            AnyChunk::Octree(_) => PayloadKind::Octree,
+            AnyChunk::Svdag(_) => PayloadKind::Svdag,
+            AnyChunk::Attributed(_) => PayloadKind::Attributed,
+            AnyChunk::GrassMask(..) => PayloadKind::GrassMask,
+        }
+    }
+
+    /// Chunk coordinate stored in the payload
+    pub fn coord(&self) -> ChunkCoord {
+        match self {
+            AnyChunk::Octree(chunk) | AnyChunk::Svdag(chunk) | AnyChunk::Attributed(chunk) => chunk.coord,
+            AnyChunk::GrassMask(coord, _) => *coord,
+        }
+    }
+
+    /// The octree chunk, if this is one
+    pub fn into_chunk(self) -> Option<Chunk> {
+        match self {
+            AnyChunk::Octree(chunk) | AnyChunk::Svdag(chunk) | AnyChunk::Attributed(chunk) => Some(chunk),
+            AnyChunk::GrassMask(..) => None,
+        }
+    }
+}
+
+/// Decode chunk file data of any kind, dispatching on its header.
+///
+/// Legacy headerless data is tried as an octree chunk, then as a grass mask.
+pub fn decode_any_chunk(data: &[u8]) -> Result<AnyChunk, io::Error> {
+    let all = [PayloadKind::Octree, PayloadKind::Svdag, PayloadKind::Attributed, PayloadKind::GrassMask];
+    match unpack_payload(data, &all)? {
+        (Some(PayloadKind::Octree), bytes) => Ok(AnyChunk::Octree(deserialize_chunk(&bytes)?)),
+        (Some(PayloadKind::Svdag), bytes) => Ok(AnyChunk::Svdag(deserialize_svdag_chunk(&bytes)?)),
+        (Some(PayloadKind::Attributed), bytes) => Ok(AnyChunk::Attributed(deserialize_attributed_chunk(&bytes)?)),
+        (Some(PayloadKind::GrassMask), bytes) => {
+            let (coord, mask) = deserialize_grass_mask(&bytes)?;
+            Ok(AnyChunk::GrassMask(coord, mask))
+        }
+        (Some(PayloadKind::Dictionary), _) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "dictionary payload is not a chunk",
+        )),
+        (None, bytes) => deserialize_svdag_chunk(&bytes)
+            .map(AnyChunk::Svdag)
+            .or_else(|_| deserialize_grass_mask(&bytes).map(|(coord, mask)| AnyChunk::GrassMask(coord, mask))),
+    }
 }
 
-/// Get the file path for a chunk
+/// Get the legacy per-chunk file path for a chunk
+///
+/// Chunks are now stored in region files (see `streaming::region`); this
+/// layout is still read as a fallback for unmigrated directories.
 pub fn chunk_path(base_dir: &Path, coord: ChunkCoord) -> PathBuf {
     // Organize chunks in subdirectories by Y coordinate to avoid too many files in one dir
     // Format: base_dir/y_{coord.y}/chunk_{x}_{y}_{z}.rkc
@@ -210,66 +511,316 @@ pub fn chunk_path(base_dir: &Path, coord: ChunkCoord) -> PathBuf {
         .join(format!("chunk_{}_{}_{}.rkc", coord.x, coord.y, coord.z))
 }
 
-/// Save a chunk to disk (compressed, v2 format)
-pub async fn save_chunk(base_dir: &Path, chunk: &Chunk) -> Result<(), io::Error> {
-    let path = chunk_path(base_dir, chunk.coord);
+/// Get the legacy per-chunk file path for a chunk inside a world layer directory
+///
+/// World layers used to keep their chunks flat:
+/// layer_dir/chunk_{x}_{y}_{z}.rkc
+pub fn layer_chunk_path(layer_dir: &Path, coord: ChunkCoord) -> PathBuf {
+    layer_dir.join(format!("chunk_{}_{}_{}.rkc", coord.x, coord.y, coord.z))
+}
 
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = path.parent() {
-        tokio::fs::create_dir_all(parent).await?;
+/// File extension of per-chunk octree files
+pub const CHUNK_EXTENSION: &str = "rkc";
+/// File extension of per-chunk grass mask files
+pub const GRASS_MASK_EXTENSION: &str = "rkm";
+/// File name of a layer's shared subtree dictionary
+pub const DICTIONARY_FILE: &str = "dictionary.rkd";
+
+/// Legacy per-chunk file locations in a directory: flat, then `y_N/`
+fn legacy_chunk_paths(dir: &Path, coord: ChunkCoord, extension: &str) -> [PathBuf; 2] {
+    let name = format!("chunk_{}_{}_{}.{}", coord.x, coord.y, coord.z, extension);
+    [dir.join(&name), dir.join(format!("y_{}", coord.y)).join(name)]
+}
+
+/// Parse a legacy per-chunk file name (`chunk_{x}_{y}_{z}.{extension}`)
+pub fn parse_chunk_file_name(name: &str, extension: &str) -> Option<ChunkCoord> {
+    let parts: Vec<i32> = name
+        .strip_prefix("chunk_")?
+        .strip_suffix(&format!(".{}", extension))?
+        .split('_')
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [x, y, z] => Some(ChunkCoord::new(x, y, z)),
+        _ => None,
     }
+}
 
-    let compressed = compress_chunk(chunk)?;
-    tokio::fs::write(&path, compressed).await?;
+/// Read a chunk's stored bytes from a chunk directory.
+///
+/// Region files are checked first, then legacy per-chunk files (flat or in
+/// `y_N/` subdirectories), so unmigrated worlds keep loading.
+pub fn read_stored_chunk(store: &RegionStore, coord: ChunkCoord, extension: &str) -> Result<Option<Vec<u8>>, io::Error> {
+    if let Some(data) = store.read(coord)? {
+        return Ok(Some(data));
+    }
+    for path in legacy_chunk_paths(store.dir(), coord, extension) {
+        match std::fs::read(&path) {
+            Ok(data) => return Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
 
+/// Write a chunk's bytes into the directory's region files.
+/// Legacy per-chunk files for the same chunk are removed.
+pub fn write_stored_chunk(store: &RegionStore, coord: ChunkCoord, extension: &str, data: &[u8]) -> Result<(), io::Error> {
+    store.write(coord, data)?;
+    for path in legacy_chunk_paths(store.dir(), coord, extension) {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+    }
     Ok(())
 }
 
-/// Save an SVDAG-precompressed chunk to disk (v3 format)
-pub async fn save_svdag_chunk(base_dir: &Path, chunk: &Chunk) -> Result<(), io::Error> {
-    let path = chunk_path(base_dir, chunk.coord);
+/// Remove a chunk from region files and legacy files.
+/// Returns true if anything was removed.
+pub fn remove_stored_chunk(store: &RegionStore, coord: ChunkCoord, extension: &str) -> Result<bool, io::Error> {
+    let mut removed = store.remove(coord)?;
+    for path in legacy_chunk_paths(store.dir(), coord, extension) {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+            removed = true;
+        }
+    }
+    Ok(removed)
+}
+
+/// Check if a chunk is stored in a chunk directory (region or legacy file)
+pub fn stored_chunk_exists(store: &RegionStore, coord: ChunkCoord, extension: &str) -> bool {
+    store.contains(coord)
+        || legacy_chunk_paths(store.dir(), coord, extension).iter().any(|p| p.exists())
+}
 
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = path.parent() {
-        tokio::fs::create_dir_all(parent).await?;
+/// List every chunk stored in a chunk directory, sorted by (x, y, z).
+pub fn stored_chunk_coords(store: &RegionStore, extension: &str) -> Result<Vec<ChunkCoord>, io::Error> {
+    let mut coords = store.chunk_coords()?;
+    for path in legacy_chunk_files(store.dir(), extension)? {
+        if let Some(coord) = path.file_name().and_then(|n| n.to_str())
+            .and_then(|n| parse_chunk_file_name(n, extension))
+        {
+            coords.push(coord);
+        }
     }
+    coords.sort_by_key(|c| (c.x, c.y, c.z));
+    coords.dedup();
+    Ok(coords)
+}
 
-    let serialized = serialize_svdag_chunk(chunk)?;
-    let compressed = lz4_flex::compress_prepend_size(&serialized);
-    tokio::fs::write(&path, compressed).await?;
+/// Manifest chunk list (`[{"x", "y", "z"}, ...]`) for a set of chunk coordinates
+pub fn manifest_chunk_list(coords: &[ChunkCoord]) -> Vec<serde_json::Value> {
+    coords.iter()
+        .map(|c| serde_json::json!({"x": c.x, "y": c.y, "z": c.z}))
+        .collect()
+}
 
-    Ok(())
+/// Chunk coordinates of a manifest chunk list. Malformed entries are skipped.
+pub fn parse_manifest_chunk_list(list: &[serde_json::Value]) -> Vec<ChunkCoord> {
+    list.iter()
+        .filter_map(|c| Some(ChunkCoord::new(
+            c["x"].as_i64()? as i32,
+            c["y"].as_i64()? as i32,
+            c["z"].as_i64()? as i32,
+        )))
+        .collect()
 }
 
-/// Load a chunk from disk (if it exists)
-pub async fn load_chunk(base_dir: &Path, coord: ChunkCoord) -> Result<Option<Chunk>, io::Error> {
-    let path = chunk_path(base_dir, coord);
+/// Legacy per-chunk files in a directory (flat and in `y_N/` subdirectories)
+pub fn legacy_chunk_files(dir: &Path, extension: &str) -> Result<Vec<PathBuf>, io::Error> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
 
-    // Check if file exists
-    if !path.exists() {
-        return Ok(None);
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else { continue };
+        if path.is_dir() && name.starts_with("y_") {
+            files.extend(legacy_chunk_files(&path, extension)?);
+        } else if parse_chunk_file_name(name, extension).is_some() {
+            files.push(path);
+        }
     }
+    Ok(files)
+}
 
-    let compressed = tokio::fs::read(&path).await?;
-    let chunk = decompress_chunk(&compressed)?;
+/// Load a chunk of any payload kind from a chunk directory
+/// (region file, or legacy `.rkc` / `.rkm` file).
+pub fn load_any_chunk(store: &RegionStore, coord: ChunkCoord) -> Result<Option<AnyChunk>, io::Error> {
+    let data = match read_stored_chunk(store, coord, CHUNK_EXTENSION)? {
+        Some(data) => Some(data),
+        None => read_stored_chunk(store, coord, GRASS_MASK_EXTENSION)?,
+    };
+    data.map(|data| decode_any_chunk(&data)).transpose()
+}
 
-    Ok(Some(chunk))
+/// Load an SVDAG chunk from a chunk directory (region or legacy file).
+///
+/// Chunks of layers with a shared dictionary keep their `DICTIONARY_REF`
+/// nodes, as the GPU upload expects. Anything reading voxels on the CPU
+/// should use `load_stored_chunk_resolved`.
+pub fn load_stored_chunk(store: &RegionStore, coord: ChunkCoord) -> Result<Option<Chunk>, io::Error> {
+    read_stored_chunk(store, coord, CHUNK_EXTENSION)?
+        .map(|data| decompress_svdag_chunk(&data))
+        .transpose()
 }
 
-/// Delete a chunk from disk
-pub async fn delete_chunk(base_dir: &Path, coord: ChunkCoord) -> Result<(), io::Error> {
-    let path = chunk_path(base_dir, coord);
+/// Load an SVDAG chunk with its references into the layer's shared
+/// `dictionary` (see `load_layer_dictionary`) resolved, so the octree
+/// stands alone. Pass `None` for layers without a dictionary.
+pub fn load_stored_chunk_resolved(
+    store: &RegionStore,
+    coord: ChunkCoord,
+    dictionary: Option<&SubtreeDictionary>,
+) -> Result<Option<Chunk>, io::Error> {
+    let mut chunk = load_stored_chunk(store, coord)?;
+    if let (Some(chunk), Some(dictionary)) = (&mut chunk, dictionary) {
+        chunk.octree = dictionary.resolve(&chunk.octree);
+    }
+    Ok(chunk)
+}
 
-    if path.exists() {
-        tokio::fs::remove_file(&path).await?;
+/// Load a chunk and the 26 chunks around it from a chunk directory, for
+/// passes that look past chunk borders. Dictionary references are resolved.
+pub fn load_chunk_neighbourhood(
+    store: &RegionStore,
+    coord: ChunkCoord,
+    dictionary: Option<&SubtreeDictionary>,
+) -> Result<ChunkNeighbourhood, io::Error> {
+    let mut neighbourhood = ChunkNeighbourhood::new();
+    for neighbour in ChunkNeighbourhood::coords_around(coord) {
+        if let Some(chunk) = load_stored_chunk_resolved(store, neighbour, dictionary)? {
+            neighbourhood.insert(neighbour, chunk.octree);
+        }
     }
+    Ok(neighbourhood)
+}
 
-    Ok(())
+/// Save a chunk SVDAG-compressed into a chunk directory's region files.
+/// Returns the number of bytes stored.
+pub fn save_stored_chunk(store: &RegionStore, chunk: &Chunk) -> Result<usize, io::Error> {
+    let data = compress_svdag_chunk(chunk)?;
+    write_stored_chunk(store, chunk.coord, CHUNK_EXTENSION, &data)?;
+    Ok(data.len())
+}
+
+/// Serialize and compress a layer's shared subtree dictionary
+pub fn compress_dictionary(dictionary: &SubtreeDictionary) -> Result<Vec<u8>, io::Error> {
+    let data = DictionaryData {
+        nodes: dictionary.nodes_slice().to_vec(),
+        bricks: dictionary.bricks_slice().to_vec(),
+    };
+    let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&data)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    Ok(pack_payload(PayloadKind::Dictionary, &bytes))
+}
+
+/// Decompress and deserialize a layer's shared subtree dictionary
+pub fn decompress_dictionary(data: &[u8]) -> Result<SubtreeDictionary, io::Error> {
+    let (kind, decompressed) = unpack_payload(data, &[PayloadKind::Dictionary])?;
+    if kind != Some(PayloadKind::Dictionary) {
+        return Err(corrupt("dictionary file without a header"));
+    }
+    let archived = rkyv::access::<ArchivedDictionaryData, rkyv::rancor::Error>(&decompressed)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    let data: DictionaryData = rkyv::deserialize::<DictionaryData, rkyv::rancor::Error>(archived)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    Ok(SubtreeDictionary::from_parts(data.nodes, data.bricks))
+}
+
+/// Path of the shared subtree dictionary in a layer directory
+pub fn dictionary_path(store: &RegionStore) -> PathBuf {
+    store.dir().join(DICTIONARY_FILE)
+}
+
+/// Save a layer's shared subtree dictionary. Returns the number of bytes stored.
+pub fn save_layer_dictionary(store: &RegionStore, dictionary: &SubtreeDictionary) -> Result<usize, io::Error> {
+    let data = compress_dictionary(dictionary)?;
+    std::fs::write(dictionary_path(store), &data)?;
+    Ok(data.len())
+}
+
+/// Load a layer's shared subtree dictionary, if its chunks use one
+pub fn load_layer_dictionary(store: &RegionStore) -> Result<Option<SubtreeDictionary>, io::Error> {
+    match std::fs::read(dictionary_path(store)) {
+        Ok(data) => decompress_dictionary(&data).map(Some),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Save a SuperChunk's far-field proxy into a proxy directory's region files.
+///
+/// Proxies are stored like SVDAG chunks keyed by SuperChunk coordinate, so
+/// one region file holds the proxies of 16^3 SuperChunks.
+/// Returns the number of bytes stored.
+pub fn save_super_chunk_proxy(store: &RegionStore, coord: SuperChunkCoord, proxy: &Octree) -> Result<usize, io::Error> {
+    let key = ChunkCoord::new(coord.x, coord.y, coord.z);
+    let data = compress_svdag_chunk(&Chunk::from_octree(key, proxy.clone()))?;
+    store.write(key, &data)?;
+    Ok(data.len())
+}
+
+/// Load a SuperChunk's far-field proxy from a proxy directory, if it has one
+pub fn load_super_chunk_proxy(store: &RegionStore, coord: SuperChunkCoord) -> Result<Option<Octree>, io::Error> {
+    let key = ChunkCoord::new(coord.x, coord.y, coord.z);
+    store.read(key)?
+        .map(|data| decompress_svdag_chunk(&data).map(|chunk| chunk.octree))
+        .transpose()
+}
+
+/// Remove a SuperChunk's far-field proxy. Returns true if it had one.
+pub fn remove_super_chunk_proxy(store: &RegionStore, coord: SuperChunkCoord) -> Result<bool, io::Error> {
+    store.remove(ChunkCoord::new(coord.x, coord.y, coord.z))
+}
+
+/// Run blocking region I/O for `base_dir` off the async executor
+async fn with_store<T, F>(base_dir: &Path, f: F) -> Result<T, io::Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&RegionStore) -> Result<T, io::Error> + Send + 'static,
+{
+    let store = RegionStore::new(base_dir);
+    tokio::task::spawn_blocking(move || f(&store))
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Save a chunk to disk (compressed, v2 format)
+pub async fn save_chunk(base_dir: &Path, chunk: &Chunk) -> Result<(), io::Error> {
+    let coord = chunk.coord;
+    let compressed = compress_chunk(chunk)?;
+    with_store(base_dir, move |store| write_stored_chunk(store, coord, CHUNK_EXTENSION, &compressed)).await
+}
+
+/// Save an SVDAG-precompressed chunk to disk (v3 format)
+pub async fn save_svdag_chunk(base_dir: &Path, chunk: &Chunk) -> Result<(), io::Error> {
+    let coord = chunk.coord;
+    let compressed = compress_svdag_chunk(chunk)?;
+    with_store(base_dir, move |store| write_stored_chunk(store, coord, CHUNK_EXTENSION, &compressed)).await
+}
+
+/// Load a chunk from disk (if it exists)
+pub async fn load_chunk(base_dir: &Path, coord: ChunkCoord) -> Result<Option<Chunk>, io::Error> {
+    with_store(base_dir, move |store| load_stored_chunk(store, coord)).await
+}
+
+/// Delete a chunk from disk
+pub async fn delete_chunk(base_dir: &Path, coord: ChunkCoord) -> Result<(), io::Error> {
+    with_store(base_dir, move |store| remove_stored_chunk(store, coord, CHUNK_EXTENSION).map(|_| ())).await
 }
 
 /// Check if a chunk exists on disk
 pub async fn chunk_exists(base_dir: &Path, coord: ChunkCoord) -> bool {
-    chunk_path(base_dir, coord).exists()
+    with_store(base_dir, move |store| Ok(stored_chunk_exists(store, coord, CHUNK_EXTENSION)))
+        .await
+        .unwrap_or(false)
 }
 
 // --- Grass mask serialization ---
@@ -380,16 +931,15 @@ pub fn deserialize_grass_mask(data: &[u8]) -> Result<(ChunkCoord, MaskOctree<Gra
     Ok((coord, mask))
 }
 
-/// Compress a serialized grass mask using LZ4.
+/// Compress a serialized grass mask using LZ4 (with a `ChunkHeader`).
 pub fn compress_grass_mask(coord: ChunkCoord, mask: &MaskOctree<GrassCell>) -> Result<Vec<u8>, io::Error> {
     let serialized = serialize_grass_mask(coord, mask)?;
-    Ok(lz4_flex::compress_prepend_size(&serialized))
+    Ok(pack_payload(PayloadKind::GrassMask, &serialized))
 }
 
-/// Decompress and deserialize a grass mask.
+/// Decompress and deserialize a grass mask (headered or legacy data).
 pub fn decompress_grass_mask(data: &[u8]) -> Result<(ChunkCoord, MaskOctree<GrassCell>), io::Error> {
-    let decompressed = lz4_flex::decompress_size_prepended(data)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("LZ4 decompression failed: {}", e)))?;
+    let (_, decompressed) = unpack_payload(data, &[PayloadKind::GrassMask])?;
     deserialize_grass_mask(&decompressed)
 }
 
@@ -470,6 +1020,219 @@ mod tests {
         assert!(compressed.len() <= uncompressed.len());
     }
 
+    #[test]
+    fn test_chunk_header_roundtrip() {
+        let chunk = Chunk::new(ChunkCoord::new(4, -1, 7));
+        let compressed = compress_svdag_chunk(&chunk).unwrap();
+
+        let header = ChunkHeader::parse(&compressed).unwrap().unwrap();
+        assert_eq!(header.version, CHUNK_HEADER_VERSION);
+        assert_eq!(header.kind, PayloadKind::Svdag);
+        assert_eq!(header.uncompressed_size as usize, serialize_svdag_chunk(&chunk).unwrap().len());
+
+        match decode_any_chunk(&compressed).unwrap() {
+            AnyChunk::Svdag(loaded) => assert_eq!(loaded.coord, ChunkCoord::new(4, -1, 7)),
+            other => panic!("Expected SVDAG chunk, got {:?}", other.kind()),
+        }
+        assert_eq!(decode_any_chunk(&compress_chunk(&chunk).unwrap()).unwrap().kind(), PayloadKind::Octree);
+    }
+
+    #[test]
+    fn test_attributed_chunk_roundtrip() {
+        use crate::voxel::attributes::VoxelAttributes;
+        use crate::voxel::voxel::Voxel;
+
+        // Solid terminal leaf after the brick 0 padding
+        let mut octree = Octree::new(4.0, 2);
+        octree.enable_attributes();
+        octree.add_brick(VoxelBrick::EMPTY);
+        let wet = VoxelAttributes::NONE.with_wetness(0.75).with_albedo(90, 60, 30);
+        octree.root_mut().brick_offset = octree.add_brick_with_attributes(
+            VoxelBrick::new([Voxel::new(90, 60, 30, 1); 8]),
+            BrickAttributes { voxels: [wet; 8] },
+        );
+        let chunk = Chunk::from_octree(ChunkCoord::new(2, 0, -3), octree);
+
+        let compressed = compress_svdag_chunk(&chunk).unwrap();
+        assert_eq!(ChunkHeader::parse(&compressed).unwrap().unwrap().kind, PayloadKind::Attributed);
+
+        let loaded = decompress_svdag_chunk(&compressed).unwrap();
+        assert_eq!(loaded.coord, chunk.coord);
+        assert_eq!(loaded.octree.attributes_slice(), chunk.octree.attributes_slice());
+        assert_eq!(decode_any_chunk(&compress_chunk(&chunk).unwrap()).unwrap().kind(), PayloadKind::Attributed);
+
+        // Plain octrees keep the attribute-free payloads
+        assert!(!decompress_chunk(&compress_chunk(&Chunk::new(chunk.coord)).unwrap()).unwrap().octree.has_attributes());
+    }
+
+    #[test]
+    fn test_legacy_headerless_chunk_still_loads() {
+        let chunk = Chunk::new(ChunkCoord::new(1, 2, 3));
+        let legacy = lz4_flex::compress_prepend_size(&serialize_svdag_chunk(&chunk).unwrap());
+
+        assert!(ChunkHeader::parse(&legacy).unwrap().is_none());
+        assert_eq!(decompress_svdag_chunk(&legacy).unwrap().coord, chunk.coord);
+        assert_eq!(decode_any_chunk(&legacy).unwrap().coord(), chunk.coord);
+    }
+
+    #[test]
+    fn test_corrupt_chunk_is_invalid_data() {
+        let chunk = Chunk::new(ChunkCoord::new(0, 0, 0));
+        let compressed = compress_svdag_chunk(&chunk).unwrap();
+
+        // Flipped payload byte fails the checksum
+        let mut damaged = compressed.clone();
+        let last = damaged.len() - 1;
+        damaged[last] ^= 0xFF;
+        let err = decompress_svdag_chunk(&damaged).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Truncated header
+        let err = decompress_svdag_chunk(&compressed[..10]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Wrong payload kind for the decoder
+        let err = decompress_grass_mask(&compressed).map(|_| ()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_stored_chunks_fall_back_to_legacy_files() {
+        let dir = std::env::temp_dir().join("rktri_test_stored_chunks");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = RegionStore::new(&dir);
+
+        // Legacy files in both layouts
+        let flat = ChunkCoord::new(1, 0, 2);
+        let nested = ChunkCoord::new(-3, 4, 5);
+        std::fs::create_dir_all(dir.join("y_4")).unwrap();
+        std::fs::write(layer_chunk_path(&dir, flat), b"flat").unwrap();
+        std::fs::write(chunk_path(&dir, nested), b"nested").unwrap();
+
+        let in_region = ChunkCoord::new(0, 0, 0);
+        write_stored_chunk(&store, in_region, CHUNK_EXTENSION, b"region").unwrap();
+
+        assert_eq!(read_stored_chunk(&store, flat, CHUNK_EXTENSION).unwrap().unwrap(), b"flat");
+        assert_eq!(read_stored_chunk(&store, nested, CHUNK_EXTENSION).unwrap().unwrap(), b"nested");
+        assert_eq!(
+            stored_chunk_coords(&store, CHUNK_EXTENSION).unwrap(),
+            vec![nested, in_region, flat]
+        );
+
+        // Rewriting a legacy chunk moves it into the region file
+        write_stored_chunk(&store, flat, CHUNK_EXTENSION, b"moved").unwrap();
+        assert!(!layer_chunk_path(&dir, flat).exists());
+        assert_eq!(read_stored_chunk(&store, flat, CHUNK_EXTENSION).unwrap().unwrap(), b"moved");
+
+        assert!(remove_stored_chunk(&store, nested, CHUNK_EXTENSION).unwrap());
+        assert!(!stored_chunk_exists(&store, nested, CHUNK_EXTENSION));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_manifest_chunk_list_roundtrip() {
+        let coords = vec![ChunkCoord::new(1, -2, 3), ChunkCoord::new(0, 0, 0)];
+        let mut list = manifest_chunk_list(&coords);
+        list.push(serde_json::json!({"x": 1, "y": "bad"}));
+        assert_eq!(parse_manifest_chunk_list(&list), coords);
+    }
+
+    #[test]
+    fn test_layer_dictionary_roundtrip() {
+        use crate::voxel::svo::{DictionaryBuilder, OctreeBuilder, create_test_sphere};
+
+        let dir = std::env::temp_dir().join("rktri_test_layer_dictionary");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = RegionStore::new(&dir);
+        assert!(load_layer_dictionary(&store).unwrap().is_none());
+
+        let octree = OctreeBuilder::new(16).build(&create_test_sphere(16, 6.0), 4.0);
+        let mut builder = DictionaryBuilder::new().with_min_chunks(1);
+        builder.count_chunk(&octree);
+        let shared = builder.share_chunk(&octree);
+        let dictionary = builder.finish();
+        assert!(save_layer_dictionary(&store, &dictionary).unwrap() > 0);
+
+        let loaded = load_layer_dictionary(&store).unwrap().unwrap();
+        assert_eq!(loaded.node_count(), dictionary.node_count());
+        assert_eq!(loaded.brick_count(), dictionary.brick_count());
+        let resolved = loaded.resolve(&shared);
+        assert!(resolved.validate().is_empty(), "{:?}", resolved.validate());
+
+        // A dictionary is not a chunk, and a chunk is not a dictionary
+        let data = std::fs::read(dictionary_path(&store)).unwrap();
+        assert!(decompress_chunk(&data).is_err());
+        let err = decode_any_chunk(&data).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let chunk = compress_chunk(&Chunk::from_octree(ChunkCoord::new(0, 0, 0), octree)).unwrap();
+        assert!(decompress_dictionary(&chunk).is_err());
+
+        // Stored chunks keep their references unless loaded resolved
+        let coord = ChunkCoord::new(1, 0, 0);
+        save_stored_chunk(&store, &Chunk::from_octree(coord, shared)).unwrap();
+        let raw = load_stored_chunk(&store, coord).unwrap().unwrap();
+        assert!(!raw.octree.validate().is_empty());
+        let resolved = load_stored_chunk_resolved(&store, coord, Some(&loaded)).unwrap().unwrap();
+        assert!(resolved.octree.validate().is_empty(), "{:?}", resolved.octree.validate());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_super_chunk_proxy_roundtrip() {
+        use crate::voxel::svo::{OctreeBuilder, create_test_sphere};
+
+        let dir = std::env::temp_dir().join("rktri_test_super_chunk_proxy");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = RegionStore::new(&dir);
+        let coord = SuperChunkCoord::new(-2, 0, 7);
+        assert!(load_super_chunk_proxy(&store, coord).unwrap().is_none());
+
+        let proxy = OctreeBuilder::new(64).build(&create_test_sphere(64, 20.0), 64.0);
+        assert!(save_super_chunk_proxy(&store, coord, &proxy).unwrap() > 0);
+        let loaded = load_super_chunk_proxy(&store, coord).unwrap().unwrap();
+        assert_eq!(loaded.root_size(), 64.0);
+        assert!(!loaded.sample_voxel(glam::Vec3::ZERO).is_empty());
+        assert!(load_super_chunk_proxy(&store, SuperChunkCoord::new(-2, 0, 6)).unwrap().is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_async_saves_into_one_region() {
+        use crate::voxel::svo::{OctreeBuilder, create_test_sphere};
+
+        let dir = std::env::temp_dir().join("rktri_test_concurrent_saves");
+        let _ = std::fs::remove_dir_all(&dir);
+        let octree = OctreeBuilder::new(16).build(&create_test_sphere(16, 6.0), 4.0);
+        let coords: Vec<ChunkCoord> = (0..16).map(|i| ChunkCoord::new(i, 0, 0)).collect();
+
+        // Every save opens its own store on the same region file
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let saves = coords.iter().map(|&coord| {
+                let dir = dir.clone();
+                let chunk = Chunk::from_octree(coord, octree.clone());
+                tokio::spawn(async move { save_svdag_chunk(&dir, &chunk).await })
+            });
+            for save in saves.collect::<Vec<_>>() {
+                save.await.unwrap().unwrap();
+            }
+        });
+
+        let store = RegionStore::new(&dir);
+        for &coord in &coords {
+            let loaded = load_stored_chunk(&store, coord).unwrap().unwrap();
+            assert_eq!(loaded.coord, coord);
+            assert_eq!(loaded.octree.brick_count(), octree.brick_count());
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     // TODO: Enable tokio test support in Cargo.toml
     // #[tokio::test]
     // async fn test_save_and_load_chunk() {
@@ -1,11 +1,13 @@
 //! Dynamic chunk loading and LOD management
 
 pub mod disk_io;
+pub mod region;
 pub mod priority;
 pub mod chunk_loader;
 pub mod cache;
 pub mod budget;
 pub mod lod;
+pub mod streamer;
 
 pub use disk_io::{
     Chunk, ChunkCoord, ChunkData,
@@ -13,11 +15,14 @@ pub use disk_io::{
     serialize_chunk, deserialize_chunk,
     save_chunk, load_chunk, delete_chunk, chunk_exists,
     chunk_path,
+    load_stored_chunk, load_stored_chunk_resolved, save_stored_chunk, stored_chunk_coords,
 };
+pub use region::{RegionFile, RegionStore};
 pub use priority::{ChunkPriority, ChunkPriorityQueue};
-pub use chunk_loader::{ChunkLoader, LoadRequest, LoadResult};
+pub use chunk_loader::{ChunkGeneration, ChunkLoader, ChunkSource, GeneratedLayer, LoadRequest, LoadResult};
 pub use cache::ChunkCache;
 pub use budget::MemoryBudget;
+pub use streamer::{ChunkStreamer, StreamerConfig, StreamEvent, StreamLayer};
 pub use lod::{
     LodConfig, lod_from_distance, traversal_depth_for_lod,
     voxel_size_at_lod, lod_blend_factor, LOD_DISTANCES, MAX_LOD,
@@ -0,0 +1,583 @@
+//! Region files: many chunks per file, aligned to SuperChunks
+//!
+//! One region file holds the 16x16x16 chunks of a `SuperChunkCoord`, so a
+//! world directory has a few hundred files instead of tens of thousands.
+//!
+//! File layout (little endian):
+//! - Header: magic `RKRG`, version u32, then 4096 entries of
+//!   (first sector u32, sector count u32, byte length u32), one per chunk slot.
+//! - Data: chunk payloads stored in runs of 512-byte sectors.
+//!
+//! Rewriting a chunk puts the new payload in the first free run large
+//! enough and only then points the slot at it, so an interrupted write
+//! leaves the old payload readable. The old run is freed afterwards and
+//! reused by later writes, so updated worlds don't grow without bound.
+//!
+//! Sector allocation lives in memory, so every `RegionStore` over the same
+//! directory in a process shares one set of open region files. Separate
+//! processes must not write to one directory at the same time.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+use crate::streaming::disk_io::ChunkCoord;
+use crate::voxel::hierarchy::CHUNKS_PER_SUPER_CHUNK;
+use crate::voxel::super_chunk::SuperChunkCoord;
+
+const MAGIC: &[u8; 4] = b"RKRG";
+const VERSION: u32 = 1;
+
+/// Chunks per region along each axis
+const REGION_CHUNKS: i32 = CHUNKS_PER_SUPER_CHUNK as i32;
+/// Chunk slots per region file
+const SLOT_COUNT: usize = (CHUNKS_PER_SUPER_CHUNK * CHUNKS_PER_SUPER_CHUNK * CHUNKS_PER_SUPER_CHUNK) as usize;
+/// Allocation unit for chunk payloads
+const SECTOR_SIZE: u64 = 512;
+const ENTRY_SIZE: u64 = 12;
+const HEADER_SIZE: u64 = 8 + SLOT_COUNT as u64 * ENTRY_SIZE;
+const HEADER_SECTORS: u32 = HEADER_SIZE.div_ceil(SECTOR_SIZE) as u32;
+
+/// Maximum region files a `RegionStore` keeps open
+const MAX_OPEN_REGIONS: usize = 64;
+
+/// File extension of region files
+pub const REGION_EXTENSION: &str = "rkr";
+
+/// Region (SuperChunk) containing a chunk
+pub fn region_of(coord: ChunkCoord) -> SuperChunkCoord {
+    SuperChunkCoord::new(
+        coord.x.div_euclid(REGION_CHUNKS),
+        coord.y.div_euclid(REGION_CHUNKS),
+        coord.z.div_euclid(REGION_CHUNKS),
+    )
+}
+
+/// Slot index of a chunk within its region
+fn slot_of(coord: ChunkCoord) -> usize {
+    let x = coord.x.rem_euclid(REGION_CHUNKS) as usize;
+    let y = coord.y.rem_euclid(REGION_CHUNKS) as usize;
+    let z = coord.z.rem_euclid(REGION_CHUNKS) as usize;
+    let n = REGION_CHUNKS as usize;
+    x + y * n + z * n * n
+}
+
+/// Path of the region file for `region` inside `dir`
+pub fn region_path(dir: &Path, region: SuperChunkCoord) -> PathBuf {
+    dir.join(format!("r_{}_{}_{}.{}", region.x, region.y, region.z, REGION_EXTENSION))
+}
+
+/// Parse a region file name produced by `region_path`
+fn parse_region_name(name: &str) -> Option<SuperChunkCoord> {
+    let parts: Vec<i32> = name
+        .strip_prefix("r_")?
+        .strip_suffix(&format!(".{}", REGION_EXTENSION))?
+        .split('_')
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [x, y, z] => Some(SuperChunkCoord::new(x, y, z)),
+        _ => None,
+    }
+}
+
+/// Location of one chunk payload in a region file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct RegionEntry {
+    /// First sector (0 = slot is empty)
+    sector: u32,
+    /// Sectors reserved for the payload
+    sectors: u32,
+    /// Payload length in bytes
+    length: u32,
+}
+
+impl RegionEntry {
+    fn is_empty(&self) -> bool {
+        self.sector == 0
+    }
+}
+
+/// A single open region file
+pub struct RegionFile {
+    file: File,
+    region: SuperChunkCoord,
+    entries: Vec<RegionEntry>,
+    /// Per-sector allocation map (header sectors are always used)
+    used: Vec<bool>,
+}
+
+impl RegionFile {
+    /// Open a region file, creating an empty one if it doesn't exist.
+    pub fn open(path: &Path, region: SuperChunkCoord) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut entries = vec![RegionEntry::default(); SLOT_COUNT];
+        if file.metadata()?.len() == 0 {
+            let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+            header.extend_from_slice(MAGIC);
+            header.extend_from_slice(&VERSION.to_le_bytes());
+            header.resize(HEADER_SIZE as usize, 0);
+            file.write_all(&header)?;
+        } else {
+            let mut header = vec![0u8; HEADER_SIZE as usize];
+            file.read_exact(&mut header)?;
+            if &header[0..4] != MAGIC {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid region magic bytes"));
+            }
+            let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+            if version != VERSION {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unsupported region version: {}", version),
+                ));
+            }
+            for (i, entry) in entries.iter_mut().enumerate() {
+                let at = 8 + i * ENTRY_SIZE as usize;
+                let word = |k: usize| u32::from_le_bytes(header[at + k * 4..at + k * 4 + 4].try_into().unwrap());
+                *entry = RegionEntry { sector: word(0), sectors: word(1), length: word(2) };
+            }
+        }
+
+        let mut used = vec![true; HEADER_SECTORS as usize];
+        for entry in entries.iter().filter(|e| !e.is_empty()) {
+            let end = (entry.sector + entry.sectors) as usize;
+            if entry.sector < HEADER_SECTORS || entry.length as u64 > entry.sectors as u64 * SECTOR_SIZE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Corrupt region entry"));
+            }
+            if used.len() < end {
+                used.resize(end, false);
+            }
+            used[entry.sector as usize..end].fill(true);
+        }
+
+        Ok(Self { file, region, entries, used })
+    }
+
+    /// Region this file covers
+    pub fn region(&self) -> SuperChunkCoord {
+        self.region
+    }
+
+    fn entry_index(&self, coord: ChunkCoord) -> io::Result<usize> {
+        if region_of(coord) != self.region {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Chunk {:?} is not in region {:?}", coord, self.region),
+            ));
+        }
+        Ok(slot_of(coord))
+    }
+
+    /// Check if a chunk is stored in this region
+    pub fn contains(&self, coord: ChunkCoord) -> bool {
+        region_of(coord) == self.region && !self.entries[slot_of(coord)].is_empty()
+    }
+
+    /// Read a chunk's payload, or None if the slot is empty
+    pub fn read(&mut self, coord: ChunkCoord) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.entries[self.entry_index(coord)?];
+        if entry.is_empty() {
+            return Ok(None);
+        }
+        let mut data = vec![0u8; entry.length as usize];
+        self.file.seek(SeekFrom::Start(entry.sector as u64 * SECTOR_SIZE))?;
+        self.file.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    /// Store a chunk's payload, replacing any previous one
+    pub fn write(&mut self, coord: ChunkCoord, data: &[u8]) -> io::Result<()> {
+        let slot = self.entry_index(coord)?;
+        let old = self.entries[slot];
+        let needed = (data.len() as u64).div_ceil(SECTOR_SIZE).max(1) as u32;
+
+        // Never overwrite the old run: it stays valid until the entry moves
+        let sector = self.allocate(needed);
+        let entry = RegionEntry { sector, sectors: needed, length: data.len() as u32 };
+        let written = self.file.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))
+            .and_then(|_| self.file.write_all(data))
+            .and_then(|_| self.write_entry(slot, entry));
+        if let Err(e) = written {
+            self.release(sector, needed);
+            return Err(e);
+        }
+
+        if !old.is_empty() {
+            self.release(old.sector, old.sectors);
+        }
+        Ok(())
+    }
+
+    /// Remove a chunk, freeing its sectors. Returns false if it wasn't stored.
+    pub fn remove(&mut self, coord: ChunkCoord) -> io::Result<bool> {
+        let slot = self.entry_index(coord)?;
+        let old = self.entries[slot];
+        if old.is_empty() {
+            return Ok(false);
+        }
+        self.release(old.sector, old.sectors);
+        self.write_entry(slot, RegionEntry::default())?;
+        Ok(true)
+    }
+
+    /// Coordinates of all chunks stored in this region
+    pub fn chunk_coords(&self) -> Vec<ChunkCoord> {
+        let n = REGION_CHUNKS as usize;
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_empty())
+            .map(|(slot, _)| ChunkCoord::new(
+                self.region.x * REGION_CHUNKS + (slot % n) as i32,
+                self.region.y * REGION_CHUNKS + (slot / n % n) as i32,
+                self.region.z * REGION_CHUNKS + (slot / (n * n)) as i32,
+            ))
+            .collect()
+    }
+
+    /// Number of unused sectors between the header and the end of the file
+    pub fn free_sectors(&self) -> usize {
+        self.used.iter().filter(|&&u| !u).count()
+    }
+
+    /// Total sectors spanned by the file (header included)
+    pub fn total_sectors(&self) -> usize {
+        self.used.len()
+    }
+
+    fn release(&mut self, sector: u32, count: u32) {
+        let start = sector as usize;
+        let end = (start + count as usize).min(self.used.len());
+        if start < end {
+            self.used[start..end].fill(false);
+        }
+        // Trailing free sectors are just end-of-file
+        while self.used.last() == Some(&false) {
+            self.used.pop();
+        }
+    }
+
+    /// First-fit allocation of `count` consecutive sectors
+    fn allocate(&mut self, count: u32) -> u32 {
+        let count = count as usize;
+        let mut run_start = 0;
+        let mut run_len = 0;
+        for (i, &used) in self.used.iter().enumerate() {
+            if used {
+                run_len = 0;
+                continue;
+            }
+            if run_len == 0 {
+                run_start = i;
+            }
+            run_len += 1;
+            if run_len == count {
+                self.used[run_start..run_start + count].fill(true);
+                return run_start as u32;
+            }
+        }
+
+        // Extend the file, reusing a free run that touches the end
+        let start = if run_len > 0 { run_start } else { self.used.len() };
+        self.used.resize(start + count, true);
+        self.used[start..].fill(true);
+        start as u32
+    }
+
+    fn write_entry(&mut self, slot: usize, entry: RegionEntry) -> io::Result<()> {
+        let mut bytes = [0u8; ENTRY_SIZE as usize];
+        bytes[0..4].copy_from_slice(&entry.sector.to_le_bytes());
+        bytes[4..8].copy_from_slice(&entry.sectors.to_le_bytes());
+        bytes[8..12].copy_from_slice(&entry.length.to_le_bytes());
+        self.file.seek(SeekFrom::Start(8 + slot as u64 * ENTRY_SIZE))?;
+        self.file.write_all(&bytes)?;
+        self.entries[slot] = entry;
+        Ok(())
+    }
+}
+
+/// Open region files of one directory, shared by its `RegionStore`s
+#[derive(Default)]
+struct SharedRegions {
+    regions: Mutex<HashMap<SuperChunkCoord, Arc<Mutex<RegionFile>>>>,
+}
+
+/// Shared region files of every directory with a live `RegionStore`, keyed
+/// by canonical path
+fn shared_regions(dir: &Path) -> Arc<SharedRegions> {
+    static STORES: OnceLock<Mutex<HashMap<PathBuf, Weak<SharedRegions>>>> = OnceLock::new();
+
+    let key = canonical_dir(dir);
+    let mut stores = STORES.get_or_init(Default::default).lock().unwrap();
+    if let Some(shared) = stores.get(&key).and_then(Weak::upgrade) {
+        return shared;
+    }
+    stores.retain(|_, shared| shared.strong_count() > 0);
+    let shared = Arc::new(SharedRegions::default());
+    stores.insert(key, Arc::downgrade(&shared));
+    shared
+}
+
+/// Canonical form of a directory that may not exist yet: the deepest
+/// existing ancestor is canonicalized and the rest appended.
+fn canonical_dir(dir: &Path) -> PathBuf {
+    let absolute = std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut existing = absolute.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.iter().rev().fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return absolute,
+        }
+    }
+}
+
+/// Region-file storage for one chunk directory (e.g. a world layer).
+///
+/// Thread-safe: region files are opened on demand, cached, and each is
+/// locked independently so parallel writers only contend within a region.
+/// Stores created for the same directory share their open region files, so
+/// independent stores never allocate the same sectors.
+#[derive(Clone)]
+pub struct RegionStore {
+    dir: PathBuf,
+    shared: Arc<SharedRegions>,
+}
+
+impl RegionStore {
+    /// Create a store over `dir`. Nothing is touched on disk until a write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let shared = shared_regions(&dir);
+        Self { dir, shared }
+    }
+
+    /// Directory holding the region files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Check if `dir` contains any region files
+    pub fn has_regions(dir: &Path) -> bool {
+        std::fs::read_dir(dir)
+            .map(|entries| entries.flatten().any(|e| {
+                e.file_name().to_str().and_then(parse_region_name).is_some()
+            }))
+            .unwrap_or(false)
+    }
+
+    fn region(&self, region: SuperChunkCoord, create: bool) -> io::Result<Option<Arc<Mutex<RegionFile>>>> {
+        let mut regions = self.shared.regions.lock().unwrap();
+        if let Some(file) = regions.get(&region) {
+            return Ok(Some(file.clone()));
+        }
+
+        let path = region_path(&self.dir, region);
+        if !create && !path.exists() {
+            return Ok(None);
+        }
+
+        if regions.len() >= MAX_OPEN_REGIONS {
+            // Close regions nobody is using right now
+            regions.retain(|_, file| Arc::strong_count(file) > 1);
+        }
+        let file = Arc::new(Mutex::new(RegionFile::open(&path, region)?));
+        regions.insert(region, file.clone());
+        Ok(Some(file))
+    }
+
+    /// Read a chunk's payload, or None if it isn't stored
+    pub fn read(&self, coord: ChunkCoord) -> io::Result<Option<Vec<u8>>> {
+        match self.region(region_of(coord), false)? {
+            Some(file) => file.lock().unwrap().read(coord),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a chunk's payload, creating its region file if needed
+    pub fn write(&self, coord: ChunkCoord, data: &[u8]) -> io::Result<()> {
+        let file = self.region(region_of(coord), true)?.expect("region created");
+        let mut file = file.lock().unwrap();
+        file.write(coord, data)
+    }
+
+    /// Remove a chunk. Returns false if it wasn't stored.
+    pub fn remove(&self, coord: ChunkCoord) -> io::Result<bool> {
+        match self.region(region_of(coord), false)? {
+            Some(file) => file.lock().unwrap().remove(coord),
+            None => Ok(false),
+        }
+    }
+
+    /// Check if a chunk is stored
+    pub fn contains(&self, coord: ChunkCoord) -> bool {
+        match self.region(region_of(coord), false) {
+            Ok(Some(file)) => file.lock().unwrap().contains(coord),
+            _ => false,
+        }
+    }
+
+    /// Coordinates of every chunk stored in this directory's region files
+    pub fn chunk_coords(&self) -> io::Result<Vec<ChunkCoord>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut regions: Vec<SuperChunkCoord> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str().and_then(parse_region_name))
+            .collect();
+        regions.sort_by_key(|r| (r.x, r.y, r.z));
+
+        let mut coords = Vec::new();
+        for region in regions {
+            if let Some(file) = self.region(region, false)? {
+                coords.extend(file.lock().unwrap().chunk_coords());
+            }
+        }
+        Ok(coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rktri_test_region_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_region_of_negative_coords() {
+        assert_eq!(region_of(ChunkCoord::new(0, 0, 0)), SuperChunkCoord::new(0, 0, 0));
+        assert_eq!(region_of(ChunkCoord::new(15, 16, -1)), SuperChunkCoord::new(0, 1, -1));
+        assert_eq!(slot_of(ChunkCoord::new(-1, 0, 0)), 15);
+    }
+
+    #[test]
+    fn test_write_read_roundtrip() {
+        let dir = temp_dir("roundtrip");
+        let store = RegionStore::new(&dir);
+        let a = ChunkCoord::new(1, 2, 3);
+        let b = ChunkCoord::new(-5, 20, 7);
+
+        store.write(a, b"hello").unwrap();
+        store.write(b, &vec![7u8; 3000]).unwrap();
+        assert_eq!(store.read(a).unwrap().unwrap(), b"hello");
+        assert_eq!(store.read(b).unwrap().unwrap().len(), 3000);
+        assert!(store.read(ChunkCoord::new(1, 2, 4)).unwrap().is_none());
+        assert!(store.read(ChunkCoord::new(100, 0, 0)).unwrap().is_none());
+
+        // Reopen from disk
+        let store = RegionStore::new(&dir);
+        assert!(RegionStore::has_regions(&dir));
+        assert_eq!(store.read(a).unwrap().unwrap(), b"hello");
+        let mut coords = store.chunk_coords().unwrap();
+        coords.sort_by_key(|c| (c.x, c.y, c.z));
+        assert_eq!(coords, vec![b, a]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rewrite_reuses_space() {
+        let dir = temp_dir("reuse");
+        let path = region_path(&dir, SuperChunkCoord::new(0, 0, 0));
+        let mut region = RegionFile::open(&path, SuperChunkCoord::new(0, 0, 0)).unwrap();
+        let a = ChunkCoord::new(0, 0, 0);
+        let b = ChunkCoord::new(1, 0, 0);
+
+        region.write(a, &vec![1u8; 2048]).unwrap();
+        region.write(b, &vec![2u8; 512]).unwrap();
+        let size = region.total_sectors();
+
+        // A rewrite goes to fresh sectors and frees the old run afterwards
+        region.write(a, &vec![3u8; 600]).unwrap();
+        assert_eq!(region.total_sectors(), size + 2);
+        assert_eq!(region.free_sectors(), 4);
+        assert_eq!(region.read(a).unwrap().unwrap(), vec![3u8; 600]);
+
+        // A payload that fits the hole goes there instead of the end
+        let c = ChunkCoord::new(2, 0, 0);
+        region.write(c, &vec![4u8; 1000]).unwrap();
+        assert_eq!(region.total_sectors(), size + 2);
+        assert_eq!(region.free_sectors(), 2);
+
+        // Growing moves the chunk; removing frees its sectors
+        region.write(b, &vec![5u8; 4096]).unwrap();
+        assert!(region.remove(a).unwrap());
+        assert!(!region.remove(a).unwrap());
+        assert_eq!(region.read(b).unwrap().unwrap(), vec![5u8; 4096]);
+        assert_eq!(region.read(c).unwrap().unwrap(), vec![4u8; 1000]);
+
+        drop(region);
+        let mut region = RegionFile::open(&path, SuperChunkCoord::new(0, 0, 0)).unwrap();
+        assert!(region.read(a).unwrap().is_none());
+        assert_eq!(region.read(c).unwrap().unwrap(), vec![4u8; 1000]);
+        assert_eq!(region.free_sectors(), 5);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_concurrent_stores_share_a_region() {
+        let dir = temp_dir("concurrent");
+        std::fs::create_dir_all(&dir).unwrap();
+        // Two independent stores, one through a different spelling of the path
+        let stores = [RegionStore::new(&dir), RegionStore::new(dir.join("."))];
+
+        std::thread::scope(|scope| {
+            for (t, store) in stores.iter().enumerate() {
+                scope.spawn(move || {
+                    for i in 0..64 {
+                        let coord = ChunkCoord::new(i % 16, t as i32, i / 16);
+                        let payload = vec![(t * 64 + i as usize) as u8; 700 + i as usize * 13];
+                        store.write(coord, &payload).unwrap();
+                    }
+                });
+            }
+        });
+
+        drop(stores);
+        let store = RegionStore::new(&dir);
+        assert_eq!(store.chunk_coords().unwrap().len(), 128);
+        for t in 0..2 {
+            for i in 0..64 {
+                let data = store.read(ChunkCoord::new(i % 16, t, i / 16)).unwrap().unwrap();
+                assert_eq!(data, vec![(t as usize * 64 + i as usize) as u8; 700 + i as usize * 13]);
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejects_foreign_chunk() {
+        let dir = temp_dir("foreign");
+        let path = region_path(&dir, SuperChunkCoord::new(0, 0, 0));
+        let mut region = RegionFile::open(&path, SuperChunkCoord::new(0, 0, 0)).unwrap();
+        assert!(region.write(ChunkCoord::new(16, 0, 0), b"x").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,853 @@
+//! Chunk streaming facade
+//!
+//! `ChunkStreamer` drives the streaming pieces together: a `ChunkLoader` per
+//! world layer for disk I/O, the `StreamingOrchestrator` for queueing and
+//! residency, the `PrefetchPredictor` for chunks the camera is heading
+//! towards, and one `MemoryBudget` for eviction. It takes the camera state
+//! each frame and reports what the renderer should do as `StreamEvent`s.
+//!
+//! Worlds with far-field proxies also get the SuperChunk proxies around the
+//! camera streamed, ahead of the chunks, with the regions of resident chunks
+//! cut out of them. Cut-outs are built on the rayon pool, at most one per
+//! SuperChunk at a time, so a SuperChunk whose chunks keep arriving is
+//! rebuilt and re-sent once per finished build rather than once per chunk.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use glam::Vec3;
+
+use crate::streaming::budget::MemoryBudget;
+use crate::streaming::chunk_loader::{ChunkGeneration, ChunkLoader, ChunkSource, LoadResult};
+use crate::streaming::disk_io;
+use crate::streaming::region::RegionStore;
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use crate::voxel::streaming::{LoadPriority, PrefetchPredictor, StreamingOrchestrator};
+use crate::voxel::super_chunk::{SuperChunk, SuperChunkCoord};
+use crate::voxel::svo::Octree;
+
+/// A world layer streamed from its own chunk directory.
+pub struct StreamLayer {
+    /// Layer id passed through to uploads (the manifest's `id`)
+    pub id: u32,
+    /// Directory holding the layer's region files
+    pub dir: PathBuf,
+    /// Chunks the layer has on disk, if known. Other chunks are never
+    /// requested from this layer unless it generates them.
+    pub chunks: Option<HashSet<ChunkCoord>>,
+    /// Procedural fallback for chunks that are not on disk
+    pub generation: Option<ChunkGeneration>,
+    /// Chunks the world was already generated for. A generating layer with
+    /// none of these in `chunks` is empty there, so they aren't generated
+    /// again (sparse layers only store chunks that have content).
+    pub generated: Option<HashSet<ChunkCoord>>,
+}
+
+impl StreamLayer {
+    /// Create a layer whose chunks are discovered by reading the directory.
+    pub fn new(id: u32, dir: impl Into<PathBuf>) -> Self {
+        Self { id, dir: dir.into(), chunks: None, generation: None, generated: None }
+    }
+
+    /// Restrict the layer to a known list of chunks (e.g. from the manifest).
+    pub fn with_chunks(mut self, chunks: impl IntoIterator<Item = ChunkCoord>) -> Self {
+        self.chunks = Some(chunks.into_iter().collect());
+        self
+    }
+
+    /// Generate chunks missing on disk, which makes the layer unbounded.
+    pub fn with_generation(mut self, generation: ChunkGeneration) -> Self {
+        self.generation = Some(generation);
+        self
+    }
+
+    /// Chunks the world was already generated for (e.g. every chunk listed
+    /// in the manifest). Those the layer doesn't list are known to be empty
+    /// in it and are not generated.
+    pub fn with_generated(mut self, chunks: impl IntoIterator<Item = ChunkCoord>) -> Self {
+        self.generated = Some(chunks.into_iter().collect());
+        self
+    }
+
+    /// Check if the layer may have a chunk, on disk or generated
+    fn may_have(&self, coord: ChunkCoord) -> bool {
+        let listed = self.chunks.as_ref().is_none_or(|chunks| chunks.contains(&coord));
+        if let Some(generation) = &self.generation && generation.may_generate(coord) {
+            return listed || !self.generated.as_ref().is_some_and(|generated| generated.contains(&coord));
+        }
+        listed
+    }
+
+    fn loader(&self, io_concurrency: usize) -> Box<dyn ChunkSource> {
+        Box::new(match &self.generation {
+            Some(generation) => ChunkLoader::new_generating(self.dir.clone(), io_concurrency, generation.clone()),
+            None => ChunkLoader::new(self.dir.clone(), io_concurrency),
+        })
+    }
+}
+
+/// Streaming ranges and limits.
+#[derive(Clone, Debug)]
+pub struct StreamerConfig {
+    /// Horizontal distance around the camera kept resident (meters).
+    /// 0 streams every known chunk and never unloads by distance.
+    pub view_distance: f32,
+    /// Vertical distance around the camera kept resident (meters). Must be
+    /// finite if any layer has no chunk list or generates chunks.
+    pub view_height: f32,
+    /// Extra distance before resident chunks unload, so chunks at the edge
+    /// of the range don't reload every time the camera crosses a chunk
+    pub unload_margin: f32,
+    /// Chunks loading at once (the layers of a chunk count once)
+    pub max_concurrent_loads: usize,
+    /// Disk reads in flight per layer
+    pub io_concurrency: usize,
+    /// Raise the priority of chunks ahead of the camera's predicted motion
+    pub prefetch: bool,
+    /// Horizontal distance around the camera within which SuperChunk proxies
+    /// are streamed (meters), if the streamer has a proxy directory
+    pub proxy_distance: f32,
+}
+
+impl Default for StreamerConfig {
+    fn default() -> Self {
+        Self {
+            view_distance: 256.0,
+            view_height: 64.0,
+            unload_margin: 16.0,
+            max_concurrent_loads: 32,
+            io_concurrency: 8,
+            prefetch: true,
+            proxy_distance: 512.0,
+        }
+    }
+}
+
+/// What the renderer has to do after a streaming update.
+#[derive(Debug)]
+pub enum StreamEvent {
+    /// A chunk was handed to the layer loaders
+    Load(ChunkCoord),
+    /// A layer of a chunk arrived; upload its octree
+    Upload {
+        coord: ChunkCoord,
+        layer_id: u32,
+        octree: Octree,
+    },
+    /// A chunk left the resident set; release its data in every layer
+    Unload(ChunkCoord),
+    /// The part of a SuperChunk's proxy not covered by resident chunks
+    /// changed; replace what is drawn for it
+    ProxyUpload {
+        coord: SuperChunkCoord,
+        octree: Octree,
+    },
+    /// Stop drawing a SuperChunk's proxy (unloaded, or nothing of it is left
+    /// next to the resident chunks)
+    ProxyUnload(SuperChunkCoord),
+}
+
+/// Far-field proxies of a world, loaded on the rayon pool
+struct ProxyStream {
+    /// Directory holding the proxy region files
+    store: RegionStore,
+    /// SuperChunks with a proxy on disk, listed when streaming started
+    known: HashSet<SuperChunkCoord>,
+    result_tx: mpsc::Sender<(SuperChunkCoord, io::Result<Option<Octree>>)>,
+    result_rx: mpsc::Receiver<(SuperChunkCoord, io::Result<Option<Octree>>)>,
+    /// Proxies being read
+    loading: HashSet<SuperChunkCoord>,
+    /// Loaded SuperChunks, without a proxy where it was missing on disk
+    loaded: HashMap<SuperChunkCoord, Arc<SuperChunk>>,
+    /// Proxies the renderer is drawing
+    drawn: HashSet<SuperChunkCoord>,
+    cut_tx: mpsc::Sender<(SuperChunkCoord, u64, Option<Octree>)>,
+    cut_rx: mpsc::Receiver<(SuperChunkCoord, u64, Option<Octree>)>,
+    /// Cut-outs being built, by the build number their result carries
+    cutting: HashMap<SuperChunkCoord, u64>,
+    /// SuperChunks whose coverage changed again while a cut-out was built
+    recut: HashSet<SuperChunkCoord>,
+    next_cut: u64,
+    /// Camera SuperChunk the proxy sweeps last ran for
+    swept: Option<SuperChunkCoord>,
+}
+
+/// One entry point for chunk streaming: camera state in, events out.
+pub struct ChunkStreamer {
+    /// Streamed layers, in the same order as `loaders`
+    layers: Vec<StreamLayer>,
+    /// Disk loader (or other chunk source) per layer
+    loaders: Vec<Box<dyn ChunkSource>>,
+    /// Queueing, residency and budget enforcement
+    orchestrator: StreamingOrchestrator,
+    /// Camera motion prediction for prefetching
+    predictor: PrefetchPredictor,
+    /// Ranges and limits
+    config: StreamerConfig,
+    /// Layers still to answer per chunk being loaded
+    outstanding: HashMap<ChunkCoord, usize>,
+    /// Bytes uploaded per resident chunk (all layers)
+    resident_bytes: HashMap<ChunkCoord, usize>,
+    /// Camera chunk the range sweeps last ran for
+    swept_chunk: Option<ChunkCoord>,
+    /// Far-field proxy streaming, if enabled
+    proxies: Option<ProxyStream>,
+}
+
+impl ChunkStreamer {
+    /// Create a streamer over the given layers.
+    pub fn new(layers: Vec<StreamLayer>, budget: MemoryBudget, config: StreamerConfig) -> Self {
+        let sources = layers
+            .iter()
+            .map(|layer| layer.loader(config.io_concurrency))
+            .collect();
+        Self::with_sources(layers, sources, budget, config)
+    }
+
+    /// Create a streamer whose layers load from the given sources instead of
+    /// their directories, one source per layer. The layers' chunk lists and
+    /// generation still decide which chunks are requested.
+    pub fn with_sources(
+        layers: Vec<StreamLayer>,
+        sources: Vec<Box<dyn ChunkSource>>,
+        budget: MemoryBudget,
+        config: StreamerConfig,
+    ) -> Self {
+        assert_eq!(layers.len(), sources.len(), "one chunk source per layer");
+        let orchestrator = StreamingOrchestrator::new(budget)
+            .with_max_concurrent_loads(config.max_concurrent_loads);
+
+        Self {
+            layers,
+            loaders: sources,
+            orchestrator,
+            predictor: PrefetchPredictor::new(),
+            config,
+            outstanding: HashMap::new(),
+            resident_bytes: HashMap::new(),
+            swept_chunk: None,
+            proxies: None,
+        }
+    }
+
+    /// Stream the SuperChunk proxies stored in `dir` within `proxy_distance`
+    /// of the camera. Proxies load ahead of chunks.
+    pub fn with_proxies(mut self, dir: impl Into<PathBuf>) -> Self {
+        let store = RegionStore::new(dir.into());
+        let known = match store.chunk_coords() {
+            Ok(coords) => coords.into_iter().map(|c| SuperChunkCoord::new(c.x, c.y, c.z)).collect(),
+            Err(e) => {
+                log::error!("Failed to list proxies in {}: {}", store.dir().display(), e);
+                HashSet::new()
+            }
+        };
+        let (result_tx, result_rx) = mpsc::channel();
+        let (cut_tx, cut_rx) = mpsc::channel();
+        self.proxies = Some(ProxyStream {
+            store,
+            known,
+            result_tx,
+            result_rx,
+            loading: HashSet::new(),
+            loaded: HashMap::new(),
+            drawn: HashSet::new(),
+            cut_tx,
+            cut_rx,
+            cutting: HashMap::new(),
+            recut: HashSet::new(),
+            next_cut: 0,
+            swept: None,
+        });
+        self
+    }
+
+    /// Use a custom prefetch predictor.
+    pub fn with_predictor(mut self, predictor: PrefetchPredictor) -> Self {
+        self.predictor = predictor;
+        self
+    }
+
+    /// Advance streaming by one frame.
+    ///
+    /// Requests chunks entering the view range, unloads those that left it,
+    /// hands queued chunks to the loaders, collects finished loads and
+    /// evicts if the budget is exceeded.
+    pub fn update(&mut self, camera_pos: Vec3, forward: Vec3, delta_time: f32) -> Vec<StreamEvent> {
+        self.step(camera_pos, Some(forward), delta_time)
+    }
+
+    /// Stream everything in range of `camera_pos` and wait until it is
+    /// resident. Meant for startup, before the first frame.
+    pub fn preload(&mut self, camera_pos: Vec3) -> Vec<StreamEvent> {
+        let mut events = self.step(camera_pos, None, 0.0);
+        while !self.is_idle() {
+            std::thread::sleep(Duration::from_millis(1));
+            events.extend(self.step(camera_pos, None, 0.0));
+        }
+        events
+    }
+
+    /// Check if nothing is queued, loading or being cut out of a proxy.
+    pub fn is_idle(&self) -> bool {
+        self.orchestrator.queue_length() == 0
+            && self.outstanding.is_empty()
+            && self.orchestrator.proxy_queue_length() == 0
+            && self.proxies.as_ref().is_none_or(|p| p.loading.is_empty() && p.cutting.is_empty())
+    }
+
+    /// Check if a chunk is resident.
+    pub fn is_resident(&self, coord: ChunkCoord) -> bool {
+        self.orchestrator.is_resident(coord)
+    }
+
+    /// Number of resident chunks.
+    pub fn resident_count(&self) -> usize {
+        self.orchestrator.resident_count()
+    }
+
+    /// Number of chunks queued or loading.
+    pub fn pending_count(&self) -> usize {
+        self.orchestrator.queue_length() + self.outstanding.len()
+    }
+
+    /// Check if a SuperChunk's proxy was loaded (or found missing).
+    pub fn is_proxy_resident(&self, coord: SuperChunkCoord) -> bool {
+        self.proxies.as_ref().is_some_and(|p| p.loaded.contains_key(&coord))
+    }
+
+    /// Get the memory budget.
+    pub fn budget(&self) -> &MemoryBudget {
+        self.orchestrator.budget()
+    }
+
+    /// Get the orchestrator, e.g. for its statistics.
+    pub fn orchestrator(&self) -> &StreamingOrchestrator {
+        &self.orchestrator
+    }
+
+    /// Get the streaming configuration.
+    pub fn config(&self) -> &StreamerConfig {
+        &self.config
+    }
+
+    fn step(&mut self, camera_pos: Vec3, forward: Option<Vec3>, delta_time: f32) -> Vec<StreamEvent> {
+        let mut events = Vec::new();
+        self.orchestrator.begin_frame(camera_pos);
+        self.predictor.update(camera_pos, delta_time);
+
+        let camera_chunk = ChunkCoord::from_world_pos(camera_pos);
+        if self.swept_chunk != Some(camera_chunk) {
+            self.swept_chunk = Some(camera_chunk);
+            self.unload_out_of_range(camera_pos, &mut events);
+            self.request_in_range(camera_pos);
+        }
+        if self.config.prefetch && let Some(forward) = forward {
+            self.prefetch(camera_pos, forward);
+        }
+        self.sweep_proxies(camera_pos, &mut events);
+
+        // Proxies first: chunk loads wait while proxy requests are queued
+        self.dispatch_proxies();
+        self.dispatch(&mut events);
+        self.receive(&mut events);
+        self.receive_proxies(camera_pos, &mut events);
+        self.evict_over_budget(&mut events);
+        self.refresh_proxies(&mut events);
+        events
+    }
+
+    /// Check if a chunk is within the view range, widened by `margin`.
+    /// Resident chunks unload once out of range with `unload_margin`.
+    pub fn in_range(&self, coord: ChunkCoord, camera_pos: Vec3, margin: f32) -> bool {
+        if self.config.view_distance <= 0.0 {
+            return true;
+        }
+        let center = chunk_center(coord);
+        let horizontal = Vec3::new(center.x - camera_pos.x, 0.0, center.z - camera_pos.z).length();
+        horizontal <= self.config.view_distance + margin
+            && (center.y - camera_pos.y).abs() <= self.config.view_height + margin
+    }
+
+    /// Check if a chunk is within the view range of the camera position of
+    /// the last update, rather than only requested ahead of its predicted
+    /// motion (or about to unload)
+    pub fn in_view(&self, coord: ChunkCoord) -> bool {
+        self.in_range(coord, self.predictor.current_position(), 0.0)
+    }
+
+    /// Check if any layer may have a chunk on disk
+    fn may_exist(&self, coord: ChunkCoord) -> bool {
+        self.layers.iter().any(|layer| layer.may_have(coord))
+    }
+
+    /// Queue every chunk in range that may exist, nearest first
+    fn request_in_range(&mut self, camera_pos: Vec3) {
+        let bounded = self.layers.iter().all(|l| l.chunks.is_some() && l.generation.is_none());
+        let mut candidates: Vec<ChunkCoord> = if bounded {
+            // Known chunk lists: no need to probe the disk for empty space
+            let known: HashSet<ChunkCoord> = self.layers.iter()
+                .flat_map(|l| l.chunks.iter().flatten().copied())
+                .collect();
+            known.into_iter().filter(|&c| self.in_range(c, camera_pos, 0.0)).collect()
+        } else {
+            let size = CHUNK_SIZE as f32;
+            let reach = (self.config.view_distance / size).ceil() as i32;
+            let height = (self.config.view_height / size).ceil() as i32;
+            let camera = ChunkCoord::from_world_pos(camera_pos);
+            let mut coords = Vec::new();
+            for x in camera.x - reach..=camera.x + reach {
+                for z in camera.z - reach..=camera.z + reach {
+                    for y in camera.y - height..=camera.y + height {
+                        let coord = ChunkCoord::new(x, y, z);
+                        if self.in_range(coord, camera_pos, 0.0) && self.may_exist(coord) {
+                            coords.push(coord);
+                        }
+                    }
+                }
+            }
+            coords
+        };
+
+        candidates.sort_by(|a, b| {
+            chunk_center(*a).distance_squared(camera_pos)
+                .total_cmp(&chunk_center(*b).distance_squared(camera_pos))
+        });
+        for coord in candidates {
+            let distance = chunk_center(coord).distance(camera_pos);
+            self.orchestrator.request_chunk(coord, LoadPriority::from_distance(distance));
+        }
+    }
+
+    /// Raise the priority of in-range chunks the camera is heading towards
+    fn prefetch(&mut self, camera_pos: Vec3, forward: Vec3) {
+        let orchestrator = &self.orchestrator;
+        let loaded = |c: ChunkCoord| orchestrator.is_resident(c) || orchestrator.is_loading(c);
+        let predicted = self.predictor.predict_needed_chunks_combined(Some(forward), None, &loaded);
+
+        for (coord, weight) in predicted {
+            if !self.in_range(coord, camera_pos, self.config.unload_margin) || !self.may_exist(coord) {
+                continue;
+            }
+            let base = LoadPriority::from_distance(chunk_center(coord).distance(camera_pos));
+            self.orchestrator.request_chunk(coord, LoadPriority(base.0 * (1.0 + weight)));
+        }
+    }
+
+    /// Unload resident chunks and drop queued requests that left the range
+    fn unload_out_of_range(&mut self, camera_pos: Vec3, events: &mut Vec<StreamEvent>) {
+        let margin = self.config.unload_margin;
+        let stale: Vec<ChunkCoord> = self.orchestrator.queued_chunks()
+            .filter(|&c| !self.in_range(c, camera_pos, margin))
+            .collect();
+        for coord in stale {
+            self.orchestrator.cancel_chunk(coord);
+        }
+
+        let leaving: Vec<ChunkCoord> = self.resident_bytes.keys()
+            .copied()
+            .filter(|&c| !self.in_range(c, camera_pos, margin))
+            .collect();
+        for coord in leaving {
+            self.unload(coord, events);
+        }
+    }
+
+    /// Hand queued chunks to the loaders of the layers that may have them
+    fn dispatch(&mut self, events: &mut Vec<StreamEvent>) {
+        let camera_pos = self.predictor.current_position();
+        for coord in self.orchestrator.get_pending_loads() {
+            let priority = 1.0 / (1.0 + chunk_center(coord).distance(camera_pos));
+            let mut requested = 0;
+            for (layer, loader) in self.layers.iter().zip(&mut self.loaders) {
+                if layer.may_have(coord) && loader.request(coord, priority) {
+                    requested += 1;
+                }
+            }
+
+            events.push(StreamEvent::Load(coord));
+            if requested == 0 {
+                self.finish(coord);
+            } else {
+                self.outstanding.insert(coord, requested);
+            }
+        }
+    }
+
+    /// Collect finished loads from every layer
+    fn receive(&mut self, events: &mut Vec<StreamEvent>) {
+        for i in 0..self.loaders.len() {
+            let layer_id = self.layers[i].id;
+            for result in self.loaders[i].poll_results() {
+                let coord = match result {
+                    LoadResult::Loaded(chunk) | LoadResult::Generated(chunk) => {
+                        let coord = chunk.coord;
+                        let bytes = chunk.octree.memory_usage();
+                        self.orchestrator.budget_mut().add_gpu(bytes);
+                        *self.resident_bytes.entry(coord).or_insert(0) += bytes;
+                        events.push(StreamEvent::Upload { coord, layer_id, octree: chunk.octree });
+                        coord
+                    }
+                    LoadResult::NotFound(coord) => coord,
+                    LoadResult::Corrupt(coord, e) => {
+                        log::error!("Skipping corrupt chunk {:?} in layer {}: {}", coord, layer_id, e);
+                        coord
+                    }
+                    LoadResult::Error(coord, e) => {
+                        log::error!("Failed to load chunk {:?} in layer {}: {}", coord, layer_id, e);
+                        coord
+                    }
+                };
+
+                if let Some(remaining) = self.outstanding.get_mut(&coord) {
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        self.outstanding.remove(&coord);
+                        self.finish(coord);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mark a chunk resident once every layer answered
+    fn finish(&mut self, coord: ChunkCoord) {
+        self.resident_bytes.entry(coord).or_insert(0);
+        self.orchestrator.mark_loaded(coord);
+    }
+
+    /// Evict chunks while the GPU budget is exceeded. Evicted chunks may be
+    /// empty ones that freed nothing, so this repeats until usage is down.
+    fn evict_over_budget(&mut self, events: &mut Vec<StreamEvent>) {
+        while self.orchestrator.budget().should_evict() {
+            let usage = self.orchestrator.budget().gpu_used();
+            let evicted = self.orchestrator.enforce_budget(usage);
+            if evicted.is_empty() {
+                break;
+            }
+            for coord in evicted {
+                self.release(coord, events);
+            }
+        }
+    }
+
+    fn unload(&mut self, coord: ChunkCoord, events: &mut Vec<StreamEvent>) {
+        self.orchestrator.mark_unloaded(coord);
+        self.release(coord, events);
+    }
+
+    /// Return a chunk's bytes to the budget and report it unloaded
+    fn release(&mut self, coord: ChunkCoord, events: &mut Vec<StreamEvent>) {
+        if let Some(bytes) = self.resident_bytes.remove(&coord) {
+            self.orchestrator.budget_mut().remove_gpu(bytes);
+            events.push(StreamEvent::Unload(coord));
+        }
+    }
+
+    /// Check if a SuperChunk's proxy is within the proxy range, widened by `margin`
+    fn proxy_in_range(&self, coord: SuperChunkCoord, camera_pos: Vec3, margin: f32) -> bool {
+        let center = coord.world_center();
+        let horizontal = Vec3::new(center.x - camera_pos.x, 0.0, center.z - camera_pos.z).length();
+        horizontal <= self.config.proxy_distance + margin
+    }
+
+    /// Request the known proxies in range, nearest first, and unload the
+    /// ones that left it, when the camera enters another SuperChunk
+    fn sweep_proxies(&mut self, camera_pos: Vec3, events: &mut Vec<StreamEvent>) {
+        let camera = SuperChunkCoord::from_world_pos(camera_pos);
+        match &mut self.proxies {
+            Some(proxies) if proxies.swept != Some(camera) => proxies.swept = Some(camera),
+            _ => return,
+        }
+
+        let Some(proxies) = &self.proxies else { return };
+        let margin = self.config.unload_margin;
+        let leaving: Vec<SuperChunkCoord> = proxies.loaded.keys()
+            .copied()
+            .filter(|&c| !self.proxy_in_range(c, camera_pos, margin))
+            .collect();
+        for coord in leaving {
+            self.unload_proxy(coord, events);
+        }
+
+        let Some(proxies) = &self.proxies else { return };
+        let mut wanted: Vec<(SuperChunkCoord, f32)> = proxies.known.iter()
+            .filter(|&&c| self.proxy_in_range(c, camera_pos, 0.0))
+            .map(|&c| (c, c.world_center().distance(camera_pos)))
+            .collect();
+        wanted.sort_by(|a, b| a.1.total_cmp(&b.1));
+        for (coord, distance) in wanted {
+            self.orchestrator.request_proxy(coord, LoadPriority::from_distance(distance));
+        }
+    }
+
+    /// Hand queued proxies to the rayon pool
+    fn dispatch_proxies(&mut self) {
+        let Some(proxies) = &mut self.proxies else { return };
+        for coord in self.orchestrator.get_pending_proxy_loads() {
+            let store = proxies.store.clone();
+            let result_tx = proxies.result_tx.clone();
+            proxies.loading.insert(coord);
+            rayon::spawn(move || {
+                // The receiver only goes away with the streamer
+                let _ = result_tx.send((coord, disk_io::load_super_chunk_proxy(&store, coord)));
+            });
+        }
+    }
+
+    /// Collect finished proxy loads
+    fn receive_proxies(&mut self, camera_pos: Vec3, events: &mut Vec<StreamEvent>) {
+        let Some(proxies) = &mut self.proxies else { return };
+        let results: Vec<_> = proxies.result_rx.try_iter().collect();
+
+        for (coord, result) in results {
+            let Some(proxies) = &mut self.proxies else { return };
+            proxies.loading.remove(&coord);
+            let proxy = match result {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    log::error!("Failed to load proxy {:?}: {}", coord, e);
+                    None
+                }
+            };
+
+            // SuperChunks without a proxy count as loaded too, so they are not
+            // requested again and chunk loads don't wait for them
+            let mut super_chunk = SuperChunk::new(coord);
+            if let Some(proxy) = proxy {
+                self.orchestrator.budget_mut().add_gpu(proxy.memory_usage());
+                super_chunk.proxy = Some(proxy);
+            }
+            proxies.loaded.insert(coord, Arc::new(super_chunk));
+            self.orchestrator.mark_proxy_loaded(coord);
+
+            // The camera moved away while it loaded
+            if !self.proxy_in_range(coord, camera_pos, self.config.unload_margin) {
+                self.unload_proxy(coord, events);
+            }
+        }
+    }
+
+    /// Report the visible part of proxies that were loaded or whose
+    /// SuperChunk gained or lost resident chunks.
+    ///
+    /// A proxy with no resident chunks is reported as is. Otherwise the
+    /// resident chunks are cut out of it on the rayon pool and the result is
+    /// reported once built; changes that arrive meanwhile are folded into one
+    /// more build after it.
+    fn refresh_proxies(&mut self, events: &mut Vec<StreamEvent>) {
+        let Some(proxies) = &mut self.proxies else { return };
+
+        let mut changed = self.orchestrator.take_changed_proxies();
+        for (coord, cut, octree) in proxies.cut_rx.try_iter().collect::<Vec<_>>() {
+            // Unloaded (and maybe loaded again) while it was built
+            if proxies.cutting.get(&coord) != Some(&cut) {
+                continue;
+            }
+            proxies.cutting.remove(&coord);
+            if proxies.recut.remove(&coord) {
+                changed.push(coord);
+            } else {
+                Self::report_proxy(proxies, coord, octree, events);
+            }
+        }
+
+        for coord in changed {
+            let Some(super_chunk) = proxies.loaded.get(&coord) else { continue };
+            if proxies.cutting.contains_key(&coord) {
+                proxies.recut.insert(coord);
+                continue;
+            }
+            let Some(coverage) = self.orchestrator.proxy_coverage(coord) else {
+                let proxy = super_chunk.proxy.clone();
+                Self::report_proxy(proxies, coord, proxy, events);
+                continue;
+            };
+
+            let super_chunk = super_chunk.clone();
+            let coverage = coverage.clone();
+            let cut_tx = proxies.cut_tx.clone();
+            proxies.next_cut += 1;
+            let cut = proxies.next_cut;
+            proxies.cutting.insert(coord, cut);
+            rayon::spawn(move || {
+                // The receiver only goes away with the streamer
+                let _ = cut_tx.send((coord, cut, super_chunk.visible_proxy(&coverage)));
+            });
+        }
+    }
+
+    /// Report the visible part of a proxy, or that nothing of it is left
+    fn report_proxy(proxies: &mut ProxyStream, coord: SuperChunkCoord, octree: Option<Octree>, events: &mut Vec<StreamEvent>) {
+        match octree {
+            Some(octree) => {
+                proxies.drawn.insert(coord);
+                events.push(StreamEvent::ProxyUpload { coord, octree });
+            }
+            None => {
+                if proxies.drawn.remove(&coord) {
+                    events.push(StreamEvent::ProxyUnload(coord));
+                }
+            }
+        }
+    }
+
+    /// Drop a loaded proxy and stop drawing it
+    fn unload_proxy(&mut self, coord: SuperChunkCoord, events: &mut Vec<StreamEvent>) {
+        let Some(proxies) = &mut self.proxies else { return };
+        self.orchestrator.mark_proxy_unloaded(coord);
+        proxies.cutting.remove(&coord);
+        proxies.recut.remove(&coord);
+        if let Some(super_chunk) = proxies.loaded.remove(&coord)
+            && let Some(proxy) = &super_chunk.proxy
+        {
+            self.orchestrator.budget_mut().remove_gpu(proxy.memory_usage());
+        }
+        if proxies.drawn.remove(&coord) {
+            events.push(StreamEvent::ProxyUnload(coord));
+        }
+    }
+}
+
+/// World-space center of a chunk
+fn chunk_center(coord: ChunkCoord) -> Vec3 {
+    coord.world_origin() + Vec3::splat(CHUNK_SIZE as f32 * 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::disk_io::{remove_super_chunk_proxy, save_stored_chunk, save_super_chunk_proxy, Chunk};
+    use crate::streaming::region::RegionStore;
+    use crate::voxel::svo::{OctreeBuilder, create_test_sphere};
+
+    fn write_layer(dir: &std::path::Path, coords: &[ChunkCoord]) {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+        let store = RegionStore::new(dir);
+        let octree = OctreeBuilder::new(16).build(&create_test_sphere(16, 6.0), CHUNK_SIZE as f32);
+        for &coord in coords {
+            save_stored_chunk(&store, &Chunk::from_octree(coord, octree.clone())).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_preload_and_unload() {
+        let dir = std::env::temp_dir().join("rktri_test_chunk_streamer");
+        let near = ChunkCoord::new(0, 0, 0);
+        let far = ChunkCoord::new(20, 0, 0);
+        write_layer(&dir, &[near, far]);
+
+        let config = StreamerConfig {
+            view_distance: 16.0,
+            unload_margin: 4.0,
+            ..Default::default()
+        };
+        let layers = vec![StreamLayer::new(7, &dir).with_chunks([near, far])];
+        let mut streamer = ChunkStreamer::new(layers, MemoryBudget::default(), config);
+
+        let events = streamer.preload(Vec3::new(2.0, 2.0, 2.0));
+        let uploads: Vec<(ChunkCoord, u32)> = events.iter()
+            .filter_map(|e| match e {
+                StreamEvent::Upload { coord, layer_id, .. } => Some((*coord, *layer_id)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(uploads, vec![(near, 7)]);
+        assert!(streamer.is_resident(near));
+        assert!(!streamer.is_resident(far));
+        assert!(streamer.in_view(near) && !streamer.in_view(far));
+        assert!(streamer.budget().gpu_used() > 0);
+
+        // Moving to the far chunk unloads the near one and streams the far one
+        let events = streamer.preload(Vec3::new(82.0, 2.0, 2.0));
+        assert!(events.iter().any(|e| matches!(e, StreamEvent::Unload(c) if *c == near)));
+        assert!(events.iter().any(|e| matches!(e, StreamEvent::Upload { coord, .. } if *coord == far)));
+        assert_eq!(streamer.resident_count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_generated_chunks_not_generated_again() {
+        use crate::generation::{GenerationConfig, GenerationPipeline};
+        use crate::streaming::chunk_loader::GeneratedLayer;
+        use std::sync::Arc;
+
+        let pipeline = Arc::new(GenerationPipeline::new(&GenerationConfig::default()));
+        let generation = ChunkGeneration::new(pipeline.clone(), GeneratedLayer::Terrain);
+        let surface = |x: i32, z: i32| {
+            let center = CHUNK_SIZE as f32 * 0.5;
+            let height = pipeline.height_at(x as f32 * CHUNK_SIZE as f32 + center, z as f32 * CHUNK_SIZE as f32 + center);
+            ChunkCoord::new(x, (height / CHUNK_SIZE as f32).floor() as i32, z)
+        };
+        let with_rocks = surface(0, 0);
+        let without_rocks = surface(1, 0);
+        let new = surface(500, 0);
+
+        // A rocks layer listing one of the two chunks the world was generated for
+        let rocks = StreamLayer::new(2, "rocks")
+            .with_chunks([with_rocks])
+            .with_generation(generation.for_layer(GeneratedLayer::Rocks))
+            .with_generated([with_rocks, without_rocks]);
+        assert!(rocks.may_have(with_rocks));
+        assert!(!rocks.may_have(without_rocks));
+        assert!(rocks.may_have(new));
+    }
+
+    #[test]
+    fn test_proxies_stream_ahead_of_chunks() {
+        let dir = std::env::temp_dir().join("rktri_test_chunk_streamer_proxies");
+        let chunk_dir = dir.join("terrain");
+        let proxy_dir = dir.join("proxies");
+        let near = ChunkCoord::new(0, 0, 0);
+        write_layer(&chunk_dir, &[near]);
+
+        let proxy_store = RegionStore::new(&proxy_dir);
+        let with_proxy = SuperChunkCoord::new(0, 0, 0);
+        let missing = SuperChunkCoord::new(1, 0, 0);
+        let proxy = OctreeBuilder::new(64).build(&create_test_sphere(64, 20.0), 64.0);
+        save_super_chunk_proxy(&proxy_store, with_proxy, &proxy).unwrap();
+        save_super_chunk_proxy(&proxy_store, missing, &proxy).unwrap();
+
+        // One load at a time, so a proxy stuck loading would block the chunk
+        let config = StreamerConfig {
+            view_distance: 16.0,
+            proxy_distance: 128.0,
+            max_concurrent_loads: 1,
+            ..Default::default()
+        };
+        let layers = vec![StreamLayer::new(1, &chunk_dir).with_chunks([near])];
+        let mut streamer = ChunkStreamer::new(layers, MemoryBudget::default(), config)
+            .with_proxies(&proxy_dir);
+        // Removed after the proxies were listed: it loads as absent
+        assert!(remove_super_chunk_proxy(&proxy_store, missing).unwrap());
+
+        let events = streamer.preload(Vec3::new(2.0, 2.0, 2.0));
+        let position = |f: &dyn Fn(&StreamEvent) -> bool| events.iter().position(f);
+        let first_proxy = position(&|e| matches!(e, StreamEvent::ProxyUpload { coord, .. } if *coord == with_proxy)).unwrap();
+        let chunk_load = position(&|e| matches!(e, StreamEvent::Load(c) if *c == near)).unwrap();
+        let chunk_upload = position(&|e| matches!(e, StreamEvent::Upload { coord, .. } if *coord == near)).unwrap();
+        let last_proxy = events.iter().rposition(|e| matches!(e, StreamEvent::ProxyUpload { .. })).unwrap();
+
+        // The chunk waited for both proxies, including the absent one
+        assert!(first_proxy < chunk_load);
+        assert!(streamer.is_proxy_resident(with_proxy));
+        assert!(streamer.is_proxy_resident(missing));
+        assert!(streamer.is_resident(near));
+        assert!(!events.iter().any(|e| matches!(e, StreamEvent::ProxyUpload { coord, .. } if *coord == missing)));
+        // The resident chunk is cut out of the proxy once it arrives
+        assert!(last_proxy > chunk_upload);
+
+        // Far away, the proxies unload
+        let events = streamer.preload(Vec3::new(2000.0, 2.0, 2.0));
+        assert!(events.iter().any(|e| matches!(e, StreamEvent::ProxyUnload(c) if *c == with_proxy)));
+        assert!(!streamer.is_proxy_resident(with_proxy));
+        assert!(!streamer.is_proxy_resident(missing));
+        assert_eq!(streamer.budget().gpu_used(), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
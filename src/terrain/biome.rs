@@ -1,7 +1,11 @@
 //! Biome system based on temperature and moisture
 
+use std::sync::OnceLock;
+
 use noise::{NoiseFn, Perlin};
 
+use crate::voxel::material::material_id;
+
 /// Biome types
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Biome {
@@ -49,23 +53,68 @@ impl Biome {
         }
     }
 
+    /// All biomes, in declaration order
+    pub const ALL: [Biome; 9] = [
+        Biome::Ocean,
+        Biome::Beach,
+        Biome::Desert,
+        Biome::Grassland,
+        Biome::Forest,
+        Biome::Taiga,
+        Biome::Tundra,
+        Biome::Mountains,
+        Biome::Snow,
+    ];
+
+    /// Registry name of this biome's terrain surface material
+    pub fn surface_material(&self) -> &'static str {
+        match self {
+            Biome::Ocean => "ocean_floor",
+            Biome::Beach => "beach_sand",
+            Biome::Desert => "desert_sand",
+            Biome::Grassland => "grassland",
+            Biome::Forest => "forest_floor",
+            Biome::Taiga => "taiga",
+            Biome::Tundra => "tundra",
+            Biome::Mountains => "mountain_rock",
+            Biome::Snow => "snow_cover",
+        }
+    }
+
+    /// Registry name of the material under this biome's surface
+    pub fn underground_material(&self) -> &'static str {
+        match self {
+            Biome::Beach => "sand",
+            Biome::Grassland | Biome::Forest | Biome::Taiga => "dirt",
+            Biome::Ocean | Biome::Desert | Biome::Tundra | Biome::Mountains | Biome::Snow => "stone",
+        }
+    }
+
     /// Get surface voxel color for this biome.
-    /// Material IDs 7-15 are biome-specific terrain surfaces.
-    /// The actual color is stored in the shader LUT (terrain_base_color),
+    /// The actual color comes from the material's albedo (see `MaterialRegistry`),
     /// since the voxel color field encodes terrain gradient for smooth normals.
     pub fn surface_color(&self) -> crate::voxel::voxel::Voxel {
         use crate::voxel::voxel::Voxel;
 
+        // Resolved once; this runs for every terrain voxel
+        static IDS: OnceLock<[u8; 9]> = OnceLock::new();
+        let ids = IDS.get_or_init(|| Biome::ALL.map(|b| material_id(b.surface_material())));
+        let (r, g, b) = self.surface_rgb();
+        Voxel::new(r, g, b, ids[*self as usize])
+    }
+
+    /// Surface color for this biome as RGB888
+    pub fn surface_rgb(&self) -> (u8, u8, u8) {
         match self {
-            Biome::Ocean => Voxel::new(30, 80, 150, 15),      // Ocean floor
-            Biome::Beach => Voxel::new(238, 214, 175, 7),     // Beach sand
-            Biome::Desert => Voxel::new(237, 201, 175, 8),    // Desert sand
-            Biome::Grassland => Voxel::new(100, 180, 80, 9),  // Grassland
-            Biome::Forest => Voxel::new(50, 120, 40, 10),     // Forest
-            Biome::Taiga => Voxel::new(80, 100, 60, 11),      // Taiga
-            Biome::Tundra => Voxel::new(160, 180, 170, 12),   // Tundra
-            Biome::Mountains => Voxel::new(120, 120, 120, 13), // Mountains
-            Biome::Snow => Voxel::new(240, 248, 255, 14),     // Snow
+            Biome::Ocean => (30, 80, 150),      // Ocean floor
+            Biome::Beach => (238, 214, 175),    // Beach sand
+            Biome::Desert => (237, 201, 175),   // Desert sand
+            Biome::Grassland => (100, 180, 80), // Grassland
+            Biome::Forest => (50, 120, 40),     // Forest
+            Biome::Taiga => (80, 100, 60),      // Taiga
+            Biome::Tundra => (160, 180, 170),   // Tundra
+            Biome::Mountains => (120, 120, 120), // Mountains
+            Biome::Snow => (240, 248, 255),     // Snow
         }
     }
 
@@ -73,16 +122,24 @@ impl Biome {
     pub fn underground_color(&self) -> crate::voxel::voxel::Voxel {
         use crate::voxel::voxel::Voxel;
 
+        static IDS: OnceLock<[u8; 9]> = OnceLock::new();
+        let ids = IDS.get_or_init(|| Biome::ALL.map(|b| material_id(b.underground_material())));
+        let (r, g, b) = self.underground_rgb();
+        Voxel::new(r, g, b, ids[*self as usize])
+    }
+
+    /// Underground color for this biome as RGB888
+    pub fn underground_rgb(&self) -> (u8, u8, u8) {
         match self {
-            Biome::Ocean => Voxel::new(100, 100, 120, 4),    // Dark stone
-            Biome::Beach => Voxel::new(180, 160, 140, 1),    // Packed sand
-            Biome::Desert => Voxel::new(200, 180, 150, 4),   // Sandstone
-            Biome::Grassland => Voxel::new(130, 100, 70, 6), // Brown dirt
-            Biome::Forest => Voxel::new(110, 85, 60, 6),     // Rich soil
-            Biome::Taiga => Voxel::new(140, 110, 80, 6),     // Rocky soil
-            Biome::Tundra => Voxel::new(120, 120, 130, 4),   // Frozen earth
-            Biome::Mountains => Voxel::new(90, 90, 90, 4),   // Deep stone
-            Biome::Snow => Voxel::new(100, 100, 110, 4),     // Ice/stone
+            Biome::Ocean => (100, 100, 120),    // Dark stone
+            Biome::Beach => (180, 160, 140),    // Packed sand
+            Biome::Desert => (200, 180, 150),   // Sandstone
+            Biome::Grassland => (130, 100, 70), // Brown dirt
+            Biome::Forest => (110, 85, 60),     // Rich soil
+            Biome::Taiga => (140, 110, 80),     // Rocky soil
+            Biome::Tundra => (120, 120, 130),   // Frozen earth
+            Biome::Mountains => (90, 90, 90),   // Deep stone
+            Biome::Snow => (100, 100, 110),     // Ice/stone
         }
     }
 
@@ -228,6 +285,10 @@ mod tests {
             // Colors should not be empty
             assert!(!surface.is_empty());
             assert!(!underground.is_empty());
+
+            // Surfaces use terrain materials so the tracer colors them procedurally
+            let material = crate::voxel::material::registry().get(surface.material_id).unwrap();
+            assert!(material.has_flag(crate::voxel::material::MaterialFlag::Terrain));
         }
     }
 
@@ -0,0 +1,208 @@
+//! Optional per-voxel attribute channel
+//!
+//! `Voxel` is kept at 4 bytes so the GPU traversal stays fast, which leaves
+//! no room for a normal next to a real color. Octrees that opt in carry one
+//! `BrickAttributes` per brick, parallel to the brick array, with a smooth
+//! normal, an RGB888 albedo, ambient occlusion, sky visibility and wetness
+//! per voxel.
+
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use rkyv::{Archive, Deserialize, Serialize};
+
+/// Which attribute channels of a voxel hold data
+pub mod channels {
+    pub const NORMAL: u8 = 1 << 0;
+    pub const ALBEDO: u8 = 1 << 1;
+    pub const AO: u8 = 1 << 2;
+    pub const WETNESS: u8 = 1 << 3;
+    pub const SKY: u8 = 1 << 4;
+}
+
+/// Encode a unit normal with an octahedral mapping, 8 bits per axis
+pub fn encode_octahedral(n: Vec3) -> u16 {
+    let n = n / (n.x.abs() + n.y.abs() + n.z.abs()).max(1e-6);
+    let (u, v) = if n.y >= 0.0 {
+        (n.x, n.z)
+    } else {
+        ((1.0 - n.z.abs()) * n.x.signum(), (1.0 - n.x.abs()) * n.z.signum())
+    };
+    let quantize = |c: f32| ((c * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u16;
+    (quantize(u) << 8) | quantize(v)
+}
+
+/// Decode a normal stored by `encode_octahedral`
+pub fn decode_octahedral(encoded: u16) -> Vec3 {
+    let u = (encoded >> 8) as f32 / 255.0 * 2.0 - 1.0;
+    let v = (encoded & 0xFF) as f32 / 255.0 * 2.0 - 1.0;
+    let y = 1.0 - u.abs() - v.abs();
+    let (x, z) = if y >= 0.0 {
+        (u, v)
+    } else {
+        ((1.0 - v.abs()) * u.signum(), (1.0 - u.abs()) * v.signum())
+    };
+    Vec3::new(x, y, z).normalize_or_zero()
+}
+
+/// Extended attributes of a single voxel - 10 bytes
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Pod, Zeroable, Archive, Deserialize, Serialize)]
+pub struct VoxelAttributes {
+    /// RGB888 albedo
+    pub albedo: [u8; 3],
+    /// Channels holding data (see `channels`)
+    pub channels: u8,
+    /// Octahedral-encoded smooth normal
+    pub normal: u16,
+    /// Ambient occlusion, 0 = unoccluded, 255 = fully occluded
+    pub ao: u8,
+    /// Surface wetness, 0 = dry, 255 = soaked
+    pub wetness: u8,
+    /// Sky visibility, 0 = no sky, 255 = open sky
+    pub sky: u8,
+    pub _pad: u8,
+}
+
+impl VoxelAttributes {
+    /// No attribute data
+    pub const NONE: VoxelAttributes = VoxelAttributes {
+        albedo: [0; 3],
+        channels: 0,
+        normal: 0,
+        ao: 0,
+        wetness: 0,
+        sky: 0,
+        _pad: 0,
+    };
+
+    /// Create a copy with the given smooth normal
+    pub fn with_normal(self, normal: Vec3) -> Self {
+        Self { normal: encode_octahedral(normal), channels: self.channels | channels::NORMAL, ..self }
+    }
+
+    /// Create a copy with the given RGB888 albedo
+    pub fn with_albedo(self, r: u8, g: u8, b: u8) -> Self {
+        Self { albedo: [r, g, b], channels: self.channels | channels::ALBEDO, ..self }
+    }
+
+    /// Create a copy with the given ambient occlusion (0-1)
+    pub fn with_ao(self, ao: f32) -> Self {
+        Self { ao: unit_to_u8(ao), channels: self.channels | channels::AO, ..self }
+    }
+
+    /// Create a copy with the given wetness (0-1)
+    pub fn with_wetness(self, wetness: f32) -> Self {
+        Self { wetness: unit_to_u8(wetness), channels: self.channels | channels::WETNESS, ..self }
+    }
+
+    /// Create a copy with the given sky visibility (0-1)
+    pub fn with_sky(self, sky: f32) -> Self {
+        Self { sky: unit_to_u8(sky), channels: self.channels | channels::SKY, ..self }
+    }
+
+    /// Smooth normal, if stored
+    pub fn normal(&self) -> Option<Vec3> {
+        self.has(channels::NORMAL).then(|| decode_octahedral(self.normal))
+    }
+
+    /// RGB888 albedo, if stored
+    pub fn albedo(&self) -> Option<(u8, u8, u8)> {
+        self.has(channels::ALBEDO).then_some((self.albedo[0], self.albedo[1], self.albedo[2]))
+    }
+
+    /// Ambient occlusion (0-1), if stored
+    pub fn ao(&self) -> Option<f32> {
+        self.has(channels::AO).then(|| self.ao as f32 / 255.0)
+    }
+
+    /// Wetness (0-1), if stored
+    pub fn wetness(&self) -> Option<f32> {
+        self.has(channels::WETNESS).then(|| self.wetness as f32 / 255.0)
+    }
+
+    /// Sky visibility (0-1), if stored
+    pub fn sky(&self) -> Option<f32> {
+        self.has(channels::SKY).then(|| self.sky as f32 / 255.0)
+    }
+
+    /// Check if all of the given channels hold data
+    pub fn has(&self, mask: u8) -> bool {
+        self.channels & mask == mask
+    }
+
+    /// Check if no channel holds data
+    pub fn is_empty(&self) -> bool {
+        self.channels == 0
+    }
+}
+
+fn unit_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Attributes of the 8 voxels of a brick, in `VoxelBrick` order - 80 bytes
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Pod, Zeroable, Archive, Deserialize, Serialize)]
+pub struct BrickAttributes {
+    pub voxels: [VoxelAttributes; 8],
+}
+
+impl BrickAttributes {
+    /// No attribute data for any voxel
+    pub const EMPTY: BrickAttributes = BrickAttributes {
+        voxels: [VoxelAttributes::NONE; 8],
+    };
+
+    /// Get attributes at local coordinates (0-1 each axis)
+    pub fn get(&self, x: u8, y: u8, z: u8) -> &VoxelAttributes {
+        &self.voxels[attribute_index(x, y, z)]
+    }
+
+    /// Set attributes at local coordinates
+    pub fn set(&mut self, x: u8, y: u8, z: u8, attributes: VoxelAttributes) {
+        self.voxels[attribute_index(x, y, z)] = attributes;
+    }
+
+    /// Check if no voxel has attribute data
+    pub fn is_empty(&self) -> bool {
+        self.voxels.iter().all(|v| v.is_empty())
+    }
+}
+
+/// Same layout as the voxels of a `VoxelBrick`
+fn attribute_index(x: u8, y: u8, z: u8) -> usize {
+    debug_assert!(x < 2 && y < 2 && z < 2);
+    ((z as usize) << 2) | ((y as usize) << 1) | (x as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(std::mem::size_of::<VoxelAttributes>(), 10);
+        assert_eq!(std::mem::size_of::<BrickAttributes>(), 80);
+    }
+
+    #[test]
+    fn test_octahedral_roundtrip() {
+        for n in [Vec3::X, Vec3::NEG_Y, Vec3::Z, Vec3::new(0.3, 0.8, -0.5), Vec3::new(-0.6, -0.2, 0.7)] {
+            let n = n.normalize();
+            let decoded = decode_octahedral(encode_octahedral(n));
+            assert!(decoded.dot(n) > 0.999, "{:?} decoded as {:?}", n, decoded);
+        }
+    }
+
+    #[test]
+    fn test_channels() {
+        let attrs = VoxelAttributes::NONE.with_albedo(200, 100, 50).with_ao(0.5);
+        assert_eq!(attrs.albedo(), Some((200, 100, 50)));
+        assert!((attrs.ao().unwrap() - 0.5).abs() < 0.01);
+        assert_eq!(attrs.normal(), None);
+        assert_eq!(attrs.wetness(), None);
+        assert_eq!(attrs.sky(), None);
+        assert!(VoxelAttributes::NONE.is_empty());
+        assert!(!attrs.is_empty());
+    }
+}
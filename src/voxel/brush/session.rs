@@ -4,7 +4,7 @@ use glam::Vec3;
 use crate::voxel::voxel::Voxel;
 use super::primitive::Axis;
 use super::stroke::{BrushStroke, BlendMode};
-use crate::voxel::edit::{EditOp, EditOverlay};
+use crate::voxel::edit::{EditDelta, EditOp, EditOverlay};
 use crate::voxel::edit::invalidator::ChunkInvalidator;
 
 /// A session for collecting brush strokes before building an octree
@@ -116,22 +116,9 @@ impl BrushSession {
     }
 
     /// Convert all strokes in this session to EditOps for the edit system.
-    /// Each stroke becomes a FillRegion (for Replace/Add) or ClearRegion (for Subtract).
+    /// Each stroke becomes an `EditOp::Stroke`, keeping its exact shape and blend mode.
     pub fn to_edit_ops(&self) -> Vec<EditOp> {
-        self.strokes
-            .iter()
-            .map(|stroke| {
-                match stroke.blend_mode {
-                    BlendMode::Replace | BlendMode::Add => EditOp::FillRegion {
-                        region: stroke.world_bounds,
-                        voxel: stroke.voxel,
-                    },
-                    BlendMode::Subtract => EditOp::ClearRegion {
-                        region: stroke.world_bounds,
-                    },
-                }
-            })
-            .collect()
+        self.strokes.iter().map(EditOp::from_stroke).collect()
     }
 
     /// Apply all strokes to an edit overlay, returning the IDs of created edits.
@@ -144,8 +131,8 @@ impl BrushSession {
 
     /// Mark all regions affected by this session's strokes as dirty.
     pub fn invalidate_chunks(&self, invalidator: &mut ChunkInvalidator) {
-        for stroke in &self.strokes {
-            invalidator.mark_dirty(&stroke.world_bounds);
+        for op in self.to_edit_ops() {
+            invalidator.mark_edit_dirty(&EditDelta::new(0, 0, op));
         }
     }
 }
@@ -153,6 +140,7 @@ impl BrushSession {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::voxel::brush::BrushPrimitive;
 
     #[test]
     fn test_new_session() {
@@ -220,10 +208,12 @@ mod tests {
         assert_eq!(ops.len(), 1);
 
         match &ops[0] {
-            EditOp::FillRegion { region: _, voxel: v } => {
+            EditOp::Stroke { primitive, voxel: v, blend, .. } => {
                 assert_eq!(v.material_id, 1);
+                assert_eq!(*blend, BlendMode::Replace);
+                assert!(matches!(primitive, BrushPrimitive::Sphere { .. }));
             }
-            _ => panic!("Expected FillRegion for Replace blend mode"),
+            _ => panic!("Expected Stroke for Replace blend mode"),
         }
     }
 
@@ -239,13 +229,25 @@ mod tests {
         assert_eq!(ops.len(), 1);
 
         match &ops[0] {
-            EditOp::ClearRegion { .. } => {
+            EditOp::Stroke { blend: BlendMode::Subtract, .. } => {
                 // Expected
             }
-            _ => panic!("Expected ClearRegion for Subtract blend mode"),
+            _ => panic!("Expected Subtract stroke for Subtract blend mode"),
         }
     }
 
+    #[test]
+    fn test_to_edit_ops_keeps_capsule_shape() {
+        let mut session = BrushSession::new();
+        let bark = Voxel::new(139, 90, 43, 2);
+        session.capsule(Vec3::ZERO, Vec3::new(2.0, 2.0, 0.0), 0.2, bark, 4);
+
+        let delta = EditDelta::new(1, 0, session.to_edit_ops().remove(0));
+        assert!(delta.evaluate_at(Vec3::new(1.0, 1.0, 0.0)).is_some());
+        // Inside the capsule's AABB, far from its axis
+        assert!(delta.evaluate_at(Vec3::new(1.8, 0.2, 0.0)).is_none());
+    }
+
     #[test]
     fn test_apply_to_overlay() {
         let mut session = BrushSession::new();
@@ -0,0 +1,505 @@
+//! Bake logged edits into on-disk chunk files.
+//!
+//! Baking folds the deltas from an `EditLog` into the chunk octrees of a
+//! world layer, saves the rebuilt chunks and drops the applied edits from
+//! the log, so long-lived worlds don't replay an ever-growing log on load.
+//! Occlusion is rebaked for the chunks edits have invalidated.
+
+use std::collections::{BTreeSet, HashSet};
+use std::io;
+use std::path::Path;
+
+use crate::core::types::Vec3;
+use crate::math::aabb::Aabb;
+use crate::streaming::disk_io;
+use crate::streaming::region::RegionStore;
+use crate::voxel::attributes::VoxelAttributes;
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
+use crate::voxel::svo::occlusion::has_baked_occlusion;
+use crate::voxel::svo::{AdaptiveOctreeBuilder, ChunkNeighbourhood, OcclusionBaker, Octree, SvdagBuilder};
+use crate::voxel::voxel::Voxel;
+use super::delta::EditDelta;
+use super::invalidator::ChunkInvalidator;
+use super::log::EditLog;
+
+/// Resolution used for chunks that don't exist yet (matches terrain chunks)
+const NEW_CHUNK_VOXELS: u32 = 128;
+
+/// Outcome of baking an edit log into a layer.
+#[derive(Debug, Default, Clone)]
+pub struct BakeReport {
+    /// Existing chunks rewritten with edits applied
+    pub updated: Vec<ChunkCoord>,
+    /// Chunks that didn't exist before and now have geometry
+    pub created: Vec<ChunkCoord>,
+    /// Chunks whose files were removed because the edits emptied them
+    pub removed: Vec<ChunkCoord>,
+    /// Edits baked and truncated from the log
+    pub edits_applied: usize,
+}
+
+/// Classifier that layers edits over an existing chunk octree.
+struct BakeClassifier<'a> {
+    base: &'a Octree,
+    /// World-space center of the chunk (octree-local origin)
+    center: Vec3,
+    /// Edits sorted by id (oldest first)
+    edits: Vec<&'a EditDelta>,
+}
+
+impl RegionClassifier for BakeClassifier<'_> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        if self.edits.iter().any(|delta| delta.op.intersects_aabb(aabb)) {
+            return RegionHint::Mixed;
+        }
+        let local = Aabb::new(aabb.min - self.center, aabb.max - self.center);
+        self.base.region_hint(&local)
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        let base = self.base.sample_voxel(pos - self.center);
+        self.edits
+            .iter()
+            .fold(base, |voxel, delta| delta.apply_at(pos, voxel))
+    }
+
+    fn evaluate_attributes(&self, pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+        self.base.sample_attributes(pos - self.center).unwrap_or_default()
+    }
+}
+
+/// Rebuild a chunk octree with edits applied on top, SVDAG-compressed.
+///
+/// `base` is the chunk's current octree (local space, centered on the
+/// chunk). Edits are applied in id order; Add strokes only fill space the
+/// base or earlier edits left empty. Attributes of the base are kept.
+pub fn bake_octree(base: &Octree, coord: ChunkCoord, edits: &[&EditDelta]) -> Octree {
+    let chunk_size = CHUNK_SIZE as f32;
+    let resolution = if base.max_depth() > 0 {
+        1u32 << base.max_depth()
+    } else {
+        NEW_CHUNK_VOXELS
+    };
+
+    let mut sorted = edits.to_vec();
+    sorted.sort_by_key(|delta| delta.id);
+
+    let origin = coord.world_origin();
+    let classifier = BakeClassifier {
+        base,
+        center: origin + Vec3::splat(chunk_size / 2.0),
+        edits: sorted,
+    };
+
+    let octree = AdaptiveOctreeBuilder::new(resolution)
+        .with_attributes(base.has_attributes())
+        .build(&classifier, origin, chunk_size);
+    SvdagBuilder::new().build(&octree)
+}
+
+/// Bake every edit in `edit_log` into the chunks of a world layer directory.
+///
+/// Each affected chunk is loaded (or started empty), rebuilt with its edits
+/// and saved SVDAG-compressed into the layer's region files. Chunks left
+/// empty are removed. Baked edits are then truncated from the log.
+///
+/// Edits are baked all-or-nothing: an edit touching a chunk that failed to
+/// load stays in the log and is applied to none of its chunks, so the next
+/// bake never applies it twice. Later edits sharing a chunk with a kept edit
+/// are kept too, so edits still land in id order. If a chunk fails to write,
+/// the chunks after it are left alone and edits are kept the same way, with
+/// written chunks holding a kept edit restored to what they held before.
+pub fn bake_edits(edit_log: &mut EditLog, layer_dir: &Path) -> io::Result<BakeReport> {
+    let mut report = BakeReport::default();
+
+    let chunks: BTreeSet<(i32, i32, i32)> = edit_log
+        .all_edits()
+        .iter()
+        .flat_map(|delta| delta.affected_chunks.iter().map(|c| (c.x, c.y, c.z)))
+        .collect();
+
+    let store = RegionStore::new(layer_dir);
+    // Chunks sharing subtrees with the layer's dictionary are rebuilt self-contained
+    let dictionary = disk_io::load_layer_dictionary(&store)?;
+
+    // Load every affected chunk before touching anything
+    let mut bases: Vec<(ChunkCoord, Option<Octree>)> = Vec::with_capacity(chunks.len());
+    let mut blocked: HashSet<ChunkCoord> = HashSet::new();
+    for (x, y, z) in chunks {
+        let coord = ChunkCoord::new(x, y, z);
+        match disk_io::load_stored_chunk_resolved(&store, coord, dictionary.as_ref()) {
+            Ok(chunk) => bases.push((coord, chunk.map(|chunk| chunk.octree))),
+            Err(e) => {
+                log::warn!("Skipping chunk {:?}: {}", coord, e);
+                blocked.insert(coord);
+            }
+        }
+    }
+    let failed = blocked.len();
+
+    let mut sorted: Vec<&EditDelta> = edit_log.all_edits().iter().collect();
+    sorted.sort_by_key(|delta| delta.id);
+    let mut baked_ids: HashSet<u64> = HashSet::new();
+    for delta in sorted {
+        if delta.affected_chunks.iter().any(|c| blocked.contains(c)) {
+            blocked.extend(delta.affected_chunks.iter().copied());
+        } else {
+            baked_ids.insert(delta.id);
+        }
+    }
+
+    // Chunks written so far, with what they held before, for rolling back
+    let mut written: Vec<(ChunkCoord, Option<Octree>)> = Vec::new();
+    let mut unwritten: HashSet<ChunkCoord> = HashSet::new();
+    for (coord, existing) in bases {
+        if !unwritten.is_empty() {
+            // A write failed: leave the remaining chunks alone
+            unwritten.insert(coord);
+            continue;
+        }
+        let edits: Vec<&EditDelta> = edit_log
+            .edits_for_chunk(coord)
+            .into_iter()
+            .filter(|delta| baked_ids.contains(&delta.id))
+            .collect();
+        if edits.is_empty() {
+            continue;
+        }
+
+        let base = existing.clone().unwrap_or_else(|| Octree::new(CHUNK_SIZE as f32, 0));
+        let octree = bake_octree(&base, coord, &edits);
+        let result = if octree.brick_count() == 0 || octree.root().is_empty() {
+            disk_io::remove_stored_chunk(&store, coord, disk_io::CHUNK_EXTENSION).map(|removed| {
+                if removed {
+                    report.removed.push(coord);
+                }
+            })
+        } else {
+            disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(coord, octree)).map(|_| {
+                if existing.is_some() {
+                    report.updated.push(coord);
+                } else {
+                    report.created.push(coord);
+                }
+            })
+        };
+        match result {
+            Ok(()) => written.push((coord, existing)),
+            Err(e) => {
+                log::warn!("Failed to write chunk {:?}: {}", coord, e);
+                unwritten.insert(coord);
+            }
+        }
+    }
+
+    if !unwritten.is_empty() {
+        // Edits touching an unwritten chunk stay in the log, like edits
+        // touching a chunk that failed to load. Written chunks they were
+        // applied to are rolled back, which keeps their other edits too.
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for delta in edit_log.all_edits() {
+                if baked_ids.contains(&delta.id) && delta.affected_chunks.iter().any(|c| unwritten.contains(c)) {
+                    baked_ids.remove(&delta.id);
+                    unwritten.extend(delta.affected_chunks.iter().copied());
+                    changed = true;
+                }
+            }
+        }
+        for (coord, existing) in written.into_iter().filter(|(c, _)| unwritten.contains(c)) {
+            if let Some(octree) = existing {
+                disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(coord, octree))?;
+            } else {
+                disk_io::remove_stored_chunk(&store, coord, disk_io::CHUNK_EXTENSION)?;
+            }
+        }
+        for list in [&mut report.updated, &mut report.created, &mut report.removed] {
+            list.retain(|c| !unwritten.contains(c));
+        }
+    }
+
+    report.edits_applied = edit_log.retain(|delta| !baked_ids.contains(&delta.id))?;
+
+    if failed > 0 {
+        log::warn!(
+            "{} chunks could not be loaded; {} edits touching them were kept",
+            failed,
+            edit_log.edit_count()
+        );
+    }
+    if !unwritten.is_empty() {
+        log::warn!(
+            "{} chunks could not be written or were rolled back; {} edits were kept",
+            unwritten.len(),
+            edit_log.edit_count()
+        );
+    }
+
+    Ok(report)
+}
+
+/// Bake occlusion for the chunks `invalidator` marks dirty in a layer.
+///
+/// Edits also change what nearby chunks see, so the chunks around each dirty
+/// one are rebaked too if they already carry baked occlusion. Returns the
+/// chunks written.
+pub fn rebake_occlusion(
+    invalidator: &ChunkInvalidator,
+    layer_dir: &Path,
+    baker: &OcclusionBaker,
+) -> io::Result<Vec<ChunkCoord>> {
+    let chunks: BTreeSet<(i32, i32, i32)> = invalidator
+        .dirty_chunks()
+        .into_iter()
+        .flat_map(ChunkNeighbourhood::coords_around)
+        .map(|c| (c.x, c.y, c.z))
+        .collect();
+
+    let store = RegionStore::new(layer_dir);
+    let dictionary = disk_io::load_layer_dictionary(&store)?;
+    let mut rebaked = Vec::new();
+
+    for (x, y, z) in chunks {
+        let coord = ChunkCoord::new(x, y, z);
+        let dirty = invalidator.is_chunk_dirty(&coord);
+        match disk_io::load_stored_chunk_resolved(&store, coord, dictionary.as_ref())? {
+            Some(chunk) if dirty || has_baked_occlusion(&chunk.octree) => {}
+            _ => continue,
+        }
+
+        let neighbourhood = disk_io::load_chunk_neighbourhood(&store, coord, dictionary.as_ref())?;
+        let Some(octree) = baker.bake_chunk(coord, &neighbourhood) else {
+            continue;
+        };
+        let chunk = disk_io::Chunk::from_octree(coord, SvdagBuilder::new().build_full(&octree));
+        disk_io::save_stored_chunk(&store, &chunk)?;
+        rebaked.push(coord);
+    }
+
+    Ok(rebaked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::brush::{BlendMode, BrushStroke};
+    use crate::voxel::edit::EditOp;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rktri_test_bake_{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Ground-plane chunk: solid below y = 1.0 (world)
+    fn ground_octree(coord: ChunkCoord) -> Octree {
+        AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 1.0 { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        )
+    }
+
+    fn sample(octree: &Octree, coord: ChunkCoord, pos: Vec3) -> Voxel {
+        octree.sample_voxel(pos - coord.world_origin() - Vec3::splat(CHUNK_SIZE as f32 / 2.0))
+    }
+
+    #[test]
+    fn test_bake_octree_applies_edits_in_order() {
+        let coord = ChunkCoord::new(0, 0, 0);
+        let base = ground_octree(coord);
+
+        let carve = EditDelta::new(1, 0, EditOp::from_stroke(
+            &BrushStroke::sphere(Vec3::new(2.0, 1.0, 2.0), 0.5, Voxel::EMPTY, 5)
+                .with_blend(BlendMode::Subtract),
+        ));
+        let fill = EditDelta::new(2, 0, EditOp::FillRegion {
+            region: Aabb::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 3.0, 1.0)),
+            voxel: Voxel::from_rgb565(0xF800, 7),
+        });
+
+        // Order given doesn't matter, ids do
+        let baked = bake_octree(&base, coord, &[&fill, &carve]);
+        assert!(sample(&baked, coord, Vec3::new(2.0, 0.8, 2.0)).is_empty());
+        assert_eq!(sample(&baked, coord, Vec3::new(3.5, 0.5, 3.5)).material_id, 1);
+        assert_eq!(sample(&baked, coord, Vec3::new(0.5, 2.5, 0.5)).material_id, 7);
+        assert!(sample(&baked, coord, Vec3::new(2.0, 3.0, 2.0)).is_empty());
+    }
+
+    #[test]
+    fn test_bake_edits_writes_chunks_and_truncates_log() {
+        let dir = temp_dir("layer");
+        let log_path = dir.join("edits.rked");
+
+        // Start from a legacy per-chunk file; baking moves it into a region file
+        let coord = ChunkCoord::new(0, 0, 0);
+        let chunk = disk_io::Chunk::from_octree(coord, ground_octree(coord));
+        std::fs::write(
+            disk_io::layer_chunk_path(&dir, coord),
+            disk_io::compress_svdag_chunk(&chunk).unwrap(),
+        ).unwrap();
+
+        let mut log = EditLog::new(&log_path);
+        log.append(EditDelta::new(0, 1, EditOp::ClearRegion {
+            region: Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+        })).unwrap();
+        // Chunk (2,0,0) has no file yet
+        log.append(EditDelta::new(0, 2, EditOp::FillRegion {
+            region: Aabb::new(Vec3::new(9.0, 1.0, 1.0), Vec3::new(10.0, 2.0, 2.0)),
+            voxel: Voxel::from_rgb565(0x07E0, 3),
+        })).unwrap();
+
+        let report = bake_edits(&mut log, &dir).unwrap();
+        assert_eq!(report.updated, vec![coord]);
+        assert_eq!(report.created, vec![ChunkCoord::new(2, 0, 0)]);
+        assert_eq!(report.edits_applied, 2);
+        assert_eq!(log.edit_count(), 0);
+        assert_eq!(EditLog::load(&log_path).unwrap().edit_count(), 0);
+
+        assert!(!disk_io::layer_chunk_path(&dir, coord).exists());
+        let reloaded = disk_io::load_stored_chunk(&RegionStore::new(&dir), coord)
+            .unwrap()
+            .unwrap();
+        assert!(sample(&reloaded.octree, coord, Vec3::new(0.5, 0.5, 0.5)).is_empty());
+        assert_eq!(sample(&reloaded.octree, coord, Vec3::new(2.5, 0.5, 2.5)).material_id, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_bake_edits_keeps_edits_touching_unreadable_chunks() {
+        let dir = temp_dir("unreadable");
+        let log_path = dir.join("edits.rked");
+        let store = RegionStore::new(&dir);
+
+        let good = ChunkCoord::new(0, 0, 0);
+        disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(good, ground_octree(good))).unwrap();
+        // Chunk (1,0,0) is a corrupt legacy file
+        std::fs::write(disk_io::layer_chunk_path(&dir, ChunkCoord::new(1, 0, 0)), b"not a chunk").unwrap();
+
+        let mut log = EditLog::new(&log_path);
+        // Spans both chunks, so it can't be baked
+        log.append(EditDelta::new(0, 1, EditOp::ClearRegion {
+            region: Aabb::new(Vec3::new(3.0, 0.0, 0.0), Vec3::new(5.0, 1.0, 1.0)),
+        })).unwrap();
+        // Only touches the good chunk, but must land after the kept edit
+        log.append(EditDelta::new(0, 2, EditOp::ClearRegion {
+            region: Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+        })).unwrap();
+        // Unrelated chunk bakes normally
+        log.append(EditDelta::new(0, 3, EditOp::FillRegion {
+            region: Aabb::new(Vec3::new(9.0, 1.0, 1.0), Vec3::new(10.0, 2.0, 2.0)),
+            voxel: Voxel::from_rgb565(0x07E0, 3),
+        })).unwrap();
+
+        let report = bake_edits(&mut log, &dir).unwrap();
+        assert_eq!(report.created, vec![ChunkCoord::new(2, 0, 0)]);
+        assert!(report.updated.is_empty());
+        assert_eq!(report.edits_applied, 1);
+        assert_eq!(log.all_edits().iter().map(|d| d.frame).collect::<Vec<_>>(), vec![1, 2]);
+
+        // The good chunk has none of the kept edits applied
+        let reloaded = disk_io::load_stored_chunk(&store, good).unwrap().unwrap();
+        assert_eq!(sample(&reloaded.octree, good, Vec3::new(0.5, 0.5, 0.5)).material_id, 1);
+        assert_eq!(sample(&reloaded.octree, good, Vec3::new(3.5, 0.5, 0.5)).material_id, 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bake_edits_rolls_back_on_write_failure() {
+        use crate::streaming::region::{region_of, region_path};
+
+        let dir = temp_dir("write_failure");
+        let log_path = dir.join("edits.rked");
+        let store = RegionStore::new(&dir);
+
+        let first = ChunkCoord::new(0, 0, 0);
+        let border = ChunkCoord::new(15, 0, 0);
+        for c in [first, border] {
+            disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(c, ground_octree(c))).unwrap();
+        }
+        // The region file of chunk (16,0,0) can't be created
+        let region = region_of(ChunkCoord::new(16, 0, 0));
+        std::os::unix::fs::symlink(dir.join("missing").join("region"), region_path(&dir, region)).unwrap();
+
+        let mut log = EditLog::new(&log_path);
+        // Only touches the first chunk: written and truncated
+        log.append(EditDelta::new(0, 1, EditOp::ClearRegion {
+            region: Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)),
+        })).unwrap();
+        // Spans the border chunk and the unwritable one
+        log.append(EditDelta::new(0, 2, EditOp::FillRegion {
+            region: Aabb::new(Vec3::new(63.5, 1.0, 1.0), Vec3::new(64.5, 2.0, 2.0)),
+            voxel: Voxel::from_rgb565(0x07E0, 3),
+        })).unwrap();
+        // Only touches the border chunk, which is rolled back
+        log.append(EditDelta::new(0, 3, EditOp::ClearRegion {
+            region: Aabb::new(Vec3::new(60.0, 0.0, 0.0), Vec3::new(61.0, 1.0, 1.0)),
+        })).unwrap();
+
+        let report = bake_edits(&mut log, &dir).unwrap();
+        assert_eq!(report.updated, vec![first]);
+        assert!(report.created.is_empty());
+        assert_eq!(report.edits_applied, 1);
+        assert_eq!(log.all_edits().iter().map(|d| d.frame).collect::<Vec<_>>(), vec![2, 3]);
+
+        // The border chunk holds none of the kept edits
+        let reloaded = disk_io::load_stored_chunk(&store, border).unwrap().unwrap();
+        assert!(sample(&reloaded.octree, border, Vec3::new(63.75, 1.5, 1.5)).is_empty());
+        assert_eq!(sample(&reloaded.octree, border, Vec3::new(60.5, 0.5, 0.5)).material_id, 1);
+        let first_chunk = disk_io::load_stored_chunk(&store, first).unwrap().unwrap();
+        assert!(sample(&first_chunk.octree, first, Vec3::new(0.5, 0.5, 0.5)).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rebake_occlusion_follows_invalidated_chunks() {
+        let dir = temp_dir("occlusion");
+        let store = RegionStore::new(&dir);
+        let coord = ChunkCoord::new(0, 0, 0);
+        let wall = ChunkCoord::new(1, 0, 0);
+
+        // Flat ground with baked occlusion in both chunks
+        let baker = OcclusionBaker::new();
+        let mut neighbourhood = ChunkNeighbourhood::new();
+        for c in [coord, wall] {
+            neighbourhood.insert(c, ground_octree(c));
+        }
+        for c in [coord, wall] {
+            let baked = baker.bake_chunk(c, &neighbourhood).unwrap();
+            disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(c, baked)).unwrap();
+        }
+
+        let ao_near_wall = || {
+            let chunk = disk_io::load_stored_chunk(&store, coord).unwrap().unwrap();
+            let local = Vec3::new(3.95, 0.95, 2.05) - Vec3::splat(CHUNK_SIZE as f32 / 2.0);
+            chunk.octree.sample_attributes(local).unwrap().ao().unwrap()
+        };
+        let before = ao_near_wall();
+
+        // Raise a wall just past the border, in the neighbouring chunk only
+        let base = disk_io::load_stored_chunk(&store, wall).unwrap().unwrap().octree;
+        let raise = EditDelta::new(1, 0, EditOp::FillRegion {
+            region: Aabb::new(Vec3::new(4.0, 0.0, 0.0), Vec3::new(4.5, 3.0, 4.0)),
+            voxel: Voxel::from_rgb565(0x4208, 1),
+        });
+        let edited = bake_octree(&base, wall, &[&raise]);
+        assert!(has_baked_occlusion(&edited));
+        disk_io::save_stored_chunk(&store, &disk_io::Chunk::from_octree(wall, edited)).unwrap();
+
+        let mut invalidator = ChunkInvalidator::new();
+        invalidator.mark_edit_dirty(&raise);
+        let rebaked = rebake_occlusion(&invalidator, &dir, &baker).unwrap();
+        assert_eq!(rebaked, vec![coord, wall]);
+        assert!(invalidator.has_dirty());
+        assert!(ao_near_wall() > before + 0.1, "{} -> {}", before, ao_near_wall());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
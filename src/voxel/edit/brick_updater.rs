@@ -40,10 +40,8 @@ impl BrickUpdater {
                                 z as f32 * voxel_size + voxel_size * 0.5,
                             );
 
-                        if let Some(new_voxel) = edit.evaluate_at(voxel_pos) {
-                            let idx = (z as usize * 4) + (y as usize * 2) + x as usize;
-                            result.voxels[idx] = new_voxel;
-                        }
+                        let idx = (z as usize * 4) + (y as usize * 2) + x as usize;
+                        result.voxels[idx] = edit.apply_at(voxel_pos, result.voxels[idx]);
                     }
                 }
             }
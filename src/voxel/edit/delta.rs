@@ -1,9 +1,10 @@
 //! Edit delta representation.
 
-use crate::core::types::Vec3;
+use crate::core::types::{Mat4, Vec3};
 use crate::math::aabb::Aabb;
 use crate::voxel::voxel::Voxel;
 use crate::voxel::chunk::ChunkCoord;
+use crate::voxel::brush::{BlendMode, BrushPrimitive, BrushStroke};
 
 /// Type of edit operation.
 #[derive(Clone, Debug)]
@@ -26,6 +27,15 @@ pub enum EditOp {
     ClearRegion {
         region: Aabb,
     },
+    /// Sculpt with a brush primitive, keeping its exact SDF shape
+    Stroke {
+        primitive: BrushPrimitive,
+        /// Primitive-to-world transform (rotation, translation, scale)
+        transform: Mat4,
+        /// Voxel painted by Replace/Add (ignored by Subtract)
+        voxel: Voxel,
+        blend: BlendMode,
+    },
 }
 
 impl EditOp {
@@ -40,6 +50,41 @@ impl EditOp {
             EditOp::FillRegion { region, .. } | EditOp::ClearRegion { region } => {
                 *region
             }
+            EditOp::Stroke { primitive, transform, .. } => {
+                primitive.world_bounds(transform)
+            }
+        }
+    }
+
+    /// Create a stroke edit from a brush stroke.
+    pub fn from_stroke(stroke: &BrushStroke) -> Self {
+        EditOp::Stroke {
+            primitive: stroke.primitive,
+            transform: stroke.transform,
+            voxel: stroke.voxel,
+            blend: stroke.blend_mode,
+        }
+    }
+
+    /// Check if this edit may touch any point of `aabb`.
+    ///
+    /// Conservative: strokes are tested against their SDF rather than their
+    /// bounding box, so a rotated capsule does not claim every chunk its
+    /// AABB happens to cover.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        if !self.affected_region().intersects(aabb) {
+            return false;
+        }
+        match self {
+            EditOp::Stroke { primitive, transform, .. } => {
+                let local_center = transform.inverse().transform_point3(aabb.center());
+                // Local distances scale by at least the smallest axis scale
+                let min_scale = transform.x_axis.truncate().length()
+                    .min(transform.y_axis.truncate().length())
+                    .min(transform.z_axis.truncate().length());
+                primitive.sdf(local_center) * min_scale <= aabb.half_extent().length()
+            }
+            _ => true,
         }
     }
 }
@@ -85,7 +130,11 @@ impl EditDelta {
         for x in min_cx..=max_cx {
             for y in min_cy..=max_cy {
                 for z in min_cz..=max_cz {
-                    chunks.push(ChunkCoord::new(x, y, z));
+                    let coord = ChunkCoord::new(x, y, z);
+                    let min = Vec3::new(x as f32, y as f32, z as f32) * chunk_size;
+                    if op.intersects_aabb(&Aabb::new(min, min + Vec3::splat(chunk_size))) {
+                        chunks.push(coord);
+                    }
                 }
             }
         }
@@ -131,6 +180,28 @@ impl EditDelta {
                     None
                 }
             }
+            EditOp::Stroke { primitive, transform, voxel, blend } => {
+                let local = transform.inverse().transform_point3(pos);
+                if !primitive.contains_point(local) {
+                    return None;
+                }
+                // Add can't see the base voxel here; use apply_at for that
+                match blend {
+                    BlendMode::Replace | BlendMode::Add => Some(*voxel),
+                    BlendMode::Subtract => Some(Voxel::EMPTY),
+                }
+            }
+        }
+    }
+
+    /// Apply this edit on top of the `existing` voxel at position.
+    /// Unlike `evaluate_at`, Add strokes only fill empty space.
+    pub fn apply_at(&self, pos: Vec3, existing: Voxel) -> Voxel {
+        match self.evaluate_at(pos) {
+            Some(_) if matches!(self.op, EditOp::Stroke { blend: BlendMode::Add, .. })
+                && !existing.is_empty() => existing,
+            Some(voxel) => voxel,
+            None => existing,
         }
     }
 }
@@ -189,4 +260,50 @@ mod tests {
         assert!(result.is_some());
         assert!(result.unwrap().is_empty());
     }
+
+    #[test]
+    fn test_stroke_delta_keeps_sdf_shape() {
+        let stroke = BrushStroke::sphere(Vec3::new(2.0, 2.0, 2.0), 1.0, Voxel::from_rgb565(0, 4), 5);
+        let delta = EditDelta::new(4, 0, EditOp::from_stroke(&stroke));
+
+        assert_eq!(delta.evaluate_at(Vec3::new(2.0, 2.0, 2.0)).unwrap().material_id, 4);
+        // Corner of the bounding box lies outside the sphere
+        assert!(delta.evaluate_at(Vec3::new(2.9, 2.9, 2.9)).is_none());
+    }
+
+    #[test]
+    fn test_stroke_blend_modes() {
+        let stone = Voxel::from_rgb565(0, 1);
+        let paint = Voxel::from_rgb565(0, 2);
+        let sphere = |blend| {
+            let stroke = BrushStroke::sphere(Vec3::ZERO, 1.0, paint, 5).with_blend(blend);
+            EditDelta::new(1, 0, EditOp::from_stroke(&stroke))
+        };
+
+        assert_eq!(sphere(BlendMode::Replace).apply_at(Vec3::ZERO, stone).material_id, 2);
+        assert_eq!(sphere(BlendMode::Add).apply_at(Vec3::ZERO, stone).material_id, 1);
+        assert_eq!(sphere(BlendMode::Add).apply_at(Vec3::ZERO, Voxel::EMPTY).material_id, 2);
+        assert!(sphere(BlendMode::Subtract).apply_at(Vec3::ZERO, stone).is_empty());
+        // Outside the brush the existing voxel is untouched
+        assert_eq!(sphere(BlendMode::Subtract).apply_at(Vec3::X * 2.0, stone).material_id, 1);
+    }
+
+    #[test]
+    fn test_stroke_affected_chunks_follow_sdf() {
+        // Diagonal capsule across 3 chunks: its AABB covers 27, the SDF far fewer
+        let stroke = BrushStroke::capsule(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(11.0, 11.0, 11.0),
+            0.5,
+            Voxel::from_rgb565(0, 1),
+            5,
+        );
+        let delta = EditDelta::new(5, 0, EditOp::from_stroke(&stroke));
+
+        assert!(delta.affected_chunks.contains(&ChunkCoord::new(0, 0, 0)));
+        assert!(delta.affected_chunks.contains(&ChunkCoord::new(1, 1, 1)));
+        assert!(delta.affected_chunks.contains(&ChunkCoord::new(2, 2, 2)));
+        assert!(!delta.affected_chunks.contains(&ChunkCoord::new(2, 0, 0)));
+        assert!(delta.affected_chunks.len() < 27);
+    }
 }
@@ -0,0 +1,337 @@
+//! Undo/redo history over an `EditOverlay`.
+
+use std::collections::VecDeque;
+
+use super::delta::{EditDelta, EditOp};
+use super::invalidator::ChunkInvalidator;
+use super::overlay::EditOverlay;
+
+/// Default number of undo steps kept.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 128;
+
+/// Edits that are undone and redone together.
+#[derive(Clone, Debug, Default)]
+struct Transaction {
+    /// Edits in the order they were applied
+    edits: Vec<EditDelta>,
+}
+
+/// Undo/redo stack for edits applied to an `EditOverlay`.
+///
+/// Edits made between `begin` and `commit` form one transaction and are
+/// undone as a single step (e.g. one brush drag). Edits applied outside a
+/// transaction are a step of their own. Every undo and redo marks the
+/// affected chunks dirty so `BrickUpdater` re-bakes them.
+#[derive(Debug)]
+pub struct EditHistory {
+    /// Committed transactions, oldest first
+    undo_stack: VecDeque<Transaction>,
+    /// Undone transactions, most recently undone last
+    redo_stack: Vec<Transaction>,
+    /// Transaction being recorded
+    pending: Option<Transaction>,
+    /// Nesting depth of `begin` calls
+    depth: u32,
+    /// Maximum number of undo steps
+    capacity: usize,
+}
+
+impl EditHistory {
+    /// Create a history keeping at most `capacity` undo steps.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            pending: None,
+            depth: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Start grouping edits into one undo step.
+    /// Calls may nest; only the outermost `commit` closes the transaction.
+    pub fn begin(&mut self) {
+        if self.depth == 0 {
+            self.pending = Some(Transaction::default());
+        }
+        self.depth += 1;
+    }
+
+    /// Close the current transaction.
+    /// Returns true if a non-empty undo step was recorded.
+    pub fn commit(&mut self) -> bool {
+        if self.depth == 0 {
+            return false;
+        }
+        self.depth -= 1;
+        if self.depth > 0 {
+            return false;
+        }
+        match self.pending.take() {
+            Some(transaction) if !transaction.edits.is_empty() => {
+                self.push_undo(transaction);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Discard the open transaction, removing its edits from the overlay.
+    pub fn cancel(&mut self, overlay: &mut EditOverlay, invalidator: &mut ChunkInvalidator) {
+        self.depth = 0;
+        if let Some(transaction) = self.pending.take() {
+            for delta in transaction.edits.iter().rev() {
+                overlay.remove_edit(delta.id);
+                invalidator.mark_edit_dirty(delta);
+            }
+        }
+    }
+
+    /// Check if a transaction is open.
+    pub fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    /// Apply an edit to the overlay and record it.
+    /// Clears the redo stack, like any new edit in an editor.
+    pub fn apply(
+        &mut self,
+        overlay: &mut EditOverlay,
+        invalidator: &mut ChunkInvalidator,
+        op: EditOp,
+        frame: u32,
+    ) -> u64 {
+        let id = overlay.add_edit(op.clone(), frame);
+        let delta = EditDelta::new(id, frame, op);
+        invalidator.mark_edit_dirty(&delta);
+        self.redo_stack.clear();
+
+        match &mut self.pending {
+            Some(transaction) => transaction.edits.push(delta),
+            None => self.push_undo(Transaction { edits: vec![delta] }),
+        }
+        id
+    }
+
+    /// Undo the most recent step.
+    /// An open transaction is committed first. Returns false if there is nothing to undo.
+    pub fn undo(&mut self, overlay: &mut EditOverlay, invalidator: &mut ChunkInvalidator) -> bool {
+        self.close_pending();
+        let Some(transaction) = self.undo_stack.pop_back() else {
+            return false;
+        };
+
+        for delta in transaction.edits.iter().rev() {
+            overlay.remove_edit(delta.id);
+            invalidator.mark_edit_dirty(delta);
+        }
+        self.redo_stack.push(transaction);
+        true
+    }
+
+    /// Redo the most recently undone step.
+    /// Returns false if there is nothing to redo.
+    pub fn redo(&mut self, overlay: &mut EditOverlay, invalidator: &mut ChunkInvalidator) -> bool {
+        self.close_pending();
+        let Some(transaction) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        for delta in &transaction.edits {
+            overlay.restore_edit(delta.clone());
+            invalidator.mark_edit_dirty(delta);
+        }
+        self.push_undo(transaction);
+        true
+    }
+
+    /// Check if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+            || self.pending.as_ref().is_some_and(|t| !t.edits.is_empty())
+    }
+
+    /// Check if there is a step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Number of committed undo steps.
+    pub fn undo_len(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Number of redo steps.
+    pub fn redo_len(&self) -> usize {
+        self.redo_stack.len()
+    }
+
+    /// Maximum number of undo steps.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Change the capacity, dropping the oldest steps if needed.
+    /// Dropped steps stay applied; they just can no longer be undone.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Forget all undo/redo steps (edits stay applied).
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.pending = None;
+        self.depth = 0;
+    }
+
+    fn close_pending(&mut self) {
+        if self.depth > 0 {
+            self.depth = 1;
+            self.commit();
+        }
+    }
+
+    fn push_undo(&mut self, transaction: Transaction) {
+        self.undo_stack.push_back(transaction);
+        while self.undo_stack.len() > self.capacity {
+            self.undo_stack.pop_front();
+        }
+    }
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Vec3;
+    use crate::voxel::chunk::ChunkCoord;
+    use crate::voxel::voxel::Voxel;
+
+    fn set(x: f32, material: u8) -> EditOp {
+        EditOp::SetVoxel {
+            position: Vec3::new(x, 2.0, 2.0),
+            voxel: Voxel::from_rgb565(0, material),
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_single_edit() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+        let pos = Vec3::new(2.0, 2.0, 2.0);
+
+        history.apply(&mut overlay, &mut invalidator, set(2.0, 1), 0);
+        history.apply(&mut overlay, &mut invalidator, set(2.0, 2), 1);
+        assert_eq!(overlay.evaluate_at(pos).unwrap().material_id, 2);
+
+        assert!(history.undo(&mut overlay, &mut invalidator));
+        assert_eq!(overlay.evaluate_at(pos).unwrap().material_id, 1);
+
+        assert!(history.redo(&mut overlay, &mut invalidator));
+        assert_eq!(overlay.evaluate_at(pos).unwrap().material_id, 2);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_transaction_is_one_step() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+
+        history.begin();
+        for i in 0..5 {
+            history.apply(&mut overlay, &mut invalidator, set(1.0 + i as f32 * 0.5, 1), 0);
+        }
+        assert!(history.commit());
+        assert_eq!(history.undo_len(), 1);
+
+        history.undo(&mut overlay, &mut invalidator);
+        assert_eq!(overlay.edit_count(), 0);
+        history.redo(&mut overlay, &mut invalidator);
+        assert_eq!(overlay.edit_count(), 5);
+    }
+
+    #[test]
+    fn test_nested_and_cancelled_transactions() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+
+        history.begin();
+        history.begin();
+        history.apply(&mut overlay, &mut invalidator, set(1.0, 1), 0);
+        assert!(!history.commit());
+        assert!(history.in_transaction());
+        history.apply(&mut overlay, &mut invalidator, set(1.5, 1), 0);
+        assert!(history.commit());
+        assert_eq!(history.undo_len(), 1);
+
+        history.begin();
+        history.apply(&mut overlay, &mut invalidator, set(3.0, 1), 0);
+        history.cancel(&mut overlay, &mut invalidator);
+        assert_eq!(overlay.edit_count(), 2);
+        assert_eq!(history.undo_len(), 1);
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+
+        history.apply(&mut overlay, &mut invalidator, set(1.0, 1), 0);
+        history.undo(&mut overlay, &mut invalidator);
+        assert!(history.can_redo());
+
+        history.apply(&mut overlay, &mut invalidator, set(1.5, 2), 1);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut overlay, &mut invalidator));
+    }
+
+    #[test]
+    fn test_capacity_drops_oldest() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::new(2);
+
+        for i in 0..4 {
+            history.apply(&mut overlay, &mut invalidator, set(1.0 + i as f32 * 0.5, 1), i);
+        }
+        assert_eq!(history.undo_len(), 2);
+
+        assert!(history.undo(&mut overlay, &mut invalidator));
+        assert!(history.undo(&mut overlay, &mut invalidator));
+        assert!(!history.undo(&mut overlay, &mut invalidator));
+        // The two oldest edits can no longer be undone but stay applied
+        assert_eq!(overlay.edit_count(), 2);
+    }
+
+    #[test]
+    fn test_undo_redo_marks_chunks_dirty() {
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+        let chunk = ChunkCoord::new(1, 0, 0);
+
+        history.apply(&mut overlay, &mut invalidator, set(6.0, 1), 0);
+        invalidator.take_dirty_chunks();
+
+        history.undo(&mut overlay, &mut invalidator);
+        assert!(invalidator.is_chunk_dirty(&chunk));
+        invalidator.take_dirty_chunks();
+
+        history.redo(&mut overlay, &mut invalidator);
+        assert!(invalidator.is_chunk_dirty(&chunk));
+        assert_eq!(invalidator.generation(&chunk), 3);
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::{HashMap, HashSet};
 use crate::math::Aabb;
 use crate::voxel::chunk::ChunkCoord;
 use crate::voxel::brick_handle::BrickId;
+use super::delta::EditDelta;
 
 const CHUNK_SIZE_METERS: f32 = 4.0;
 
@@ -62,6 +63,16 @@ impl ChunkInvalidator {
         }
     }
 
+    /// Mark the chunks touched by an edit as dirty.
+    ///
+    /// Uses the edit's precomputed chunk list, which follows stroke SDFs
+    /// more tightly than `mark_dirty` on the edit's bounding box.
+    pub fn mark_edit_dirty(&mut self, delta: &EditDelta) {
+        for &coord in &delta.affected_chunks {
+            self.mark_chunk_dirty(coord);
+        }
+    }
+
     /// Mark a specific chunk as dirty and increment its generation.
     pub fn mark_chunk_dirty(&mut self, coord: ChunkCoord) {
         self.dirty_chunks.insert(coord);
@@ -81,6 +92,14 @@ impl ChunkInvalidator {
         self.dirty_chunks.drain().collect()
     }
 
+    /// Get the dirty chunks without clearing them.
+    ///
+    /// For passes that follow edits (e.g. occlusion rebakes) alongside the
+    /// consumer that takes the list.
+    pub fn dirty_chunks(&self) -> Vec<ChunkCoord> {
+        self.dirty_chunks.iter().copied().collect()
+    }
+
     /// Take all dirty bricks and clear the dirty list.
     pub fn take_dirty_bricks(&mut self) -> Vec<BrickId> {
         self.dirty_bricks.drain().collect()
@@ -309,4 +328,20 @@ mod tests {
         let invalidator = ChunkInvalidator::default();
         assert!(!invalidator.has_dirty());
     }
+
+    #[test]
+    fn test_mark_edit_dirty() {
+        use crate::voxel::brush::BrushStroke;
+        use crate::voxel::edit::EditOp;
+        use crate::voxel::voxel::Voxel;
+
+        let mut invalidator = ChunkInvalidator::new();
+        let stroke = BrushStroke::sphere(Vec3::new(4.0, 2.0, 2.0), 1.0, Voxel::from_rgb565(0, 1), 5);
+        let delta = EditDelta::new(1, 0, EditOp::from_stroke(&stroke));
+
+        invalidator.mark_edit_dirty(&delta);
+        assert!(invalidator.is_chunk_dirty(&ChunkCoord::new(0, 0, 0)));
+        assert!(invalidator.is_chunk_dirty(&ChunkCoord::new(1, 0, 0)));
+        assert_eq!(invalidator.dirty_chunk_count(), delta.affected_chunks.len());
+    }
 }
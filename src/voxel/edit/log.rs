@@ -6,14 +6,18 @@ use std::fs::{File, OpenOptions};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 
-use crate::core::types::Vec3;
+use crate::core::types::{Mat4, Vec3};
 use crate::math::Aabb;
+use crate::voxel::brush::{Axis, BlendMode, BrushPrimitive};
 use crate::voxel::chunk::ChunkCoord;
 use crate::voxel::voxel::Voxel;
 use super::delta::{EditDelta, EditOp};
 
 const MAGIC: &[u8; 4] = b"RKED";
-const VERSION: u32 = 1;
+/// Version 2 added stroke ops (tag 4).
+const VERSION: u32 = 2;
+/// First version that can hold stroke ops.
+const STROKE_VERSION: u32 = 2;
 
 /// Append-only log of edits for persistence.
 pub struct EditLog {
@@ -25,6 +29,8 @@ pub struct EditLog {
     edits: Vec<EditDelta>,
     /// Next edit ID
     next_id: AtomicU64,
+    /// Header version of the file on disk
+    file_version: u32,
 }
 
 impl EditLog {
@@ -47,6 +53,7 @@ impl EditLog {
             chunk_index: HashMap::new(),
             edits: Vec::new(),
             next_id: AtomicU64::new(1),
+            file_version: VERSION,
         }
     }
 
@@ -69,7 +76,7 @@ impl EditLog {
         let mut version_bytes = [0u8; 4];
         reader.read_exact(&mut version_bytes)?;
         let version = u32::from_le_bytes(version_bytes);
-        if version != VERSION {
+        if version == 0 || version > VERSION {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Unsupported version: {}", version),
@@ -82,7 +89,7 @@ impl EditLog {
 
         loop {
             // Try to read next edit
-            let delta = match read_edit(&mut reader) {
+            let delta = match read_edit(&mut reader, version) {
                 Ok(delta) => delta,
                 Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
                 Err(e) => return Err(e),
@@ -105,6 +112,7 @@ impl EditLog {
             chunk_index,
             edits,
             next_id: AtomicU64::new(max_id + 1),
+            file_version: version,
         })
     }
 
@@ -125,6 +133,13 @@ impl EditLog {
         // Add to in-memory edits
         self.edits.push(delta.clone());
 
+        // An older file can't hold every op; rewrite it under the current version
+        if self.file_version < VERSION && self.path.exists() {
+            self.rewrite(&self.edits)?;
+            self.file_version = VERSION;
+            return Ok(id);
+        }
+
         // Append to file
         let file = OpenOptions::new()
             .create(true)
@@ -184,6 +199,8 @@ impl EditLog {
                         region.max.x, region.max.y, region.max.z
                     )
                 }
+                // Strokes blend with what came before, so none supersedes another
+                EditOp::Stroke { .. } => format!("stroke_{}", edit.id),
             };
             latest_edits.insert(key, edit);
         }
@@ -194,23 +211,8 @@ impl EditLog {
             .collect();
         compacted.sort_by_key(|e| e.id);
 
-        // Write to temporary file
-        let temp_path = self.path.with_extension("tmp");
-        let file = File::create(&temp_path)?;
-        let mut writer = BufWriter::new(file);
-
-        writer.write_all(MAGIC)?;
-        writer.write_all(&VERSION.to_le_bytes())?;
-
-        for edit in &compacted {
-            write_edit(&mut writer, edit)?;
-        }
-
-        writer.flush()?;
-        drop(writer);
-
-        // Replace original file
-        std::fs::rename(&temp_path, &self.path)?;
+        self.rewrite(&compacted)?;
+        self.file_version = VERSION;
 
         // Update in-memory state
         self.edits = compacted;
@@ -219,20 +221,48 @@ impl EditLog {
         Ok(())
     }
 
+    /// Keep only the edits for which `keep` returns true and rewrite the log.
+    /// Returns the number of edits removed.
+    pub fn retain(&mut self, mut keep: impl FnMut(&EditDelta) -> bool) -> io::Result<usize> {
+        let kept: Vec<EditDelta> = self.edits.iter().filter(|edit| keep(edit)).cloned().collect();
+        let removed = self.edits.len() - kept.len();
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        self.rewrite(&kept)?;
+        self.file_version = VERSION;
+        self.edits = kept;
+        self.rebuild_chunk_index();
+        Ok(removed)
+    }
+
+    /// Path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Write all edits to disk.
     pub fn save(&self) -> io::Result<()> {
-        let file = File::create(&self.path)?;
+        self.rewrite(&self.edits)
+    }
+
+    /// Write `edits` to a temporary file, then replace the log file with it.
+    fn rewrite(&self, edits: &[EditDelta]) -> io::Result<()> {
+        let temp_path = self.path.with_extension("tmp");
+        let file = File::create(&temp_path)?;
         let mut writer = BufWriter::new(file);
 
         writer.write_all(MAGIC)?;
         writer.write_all(&VERSION.to_le_bytes())?;
 
-        for edit in &self.edits {
+        for edit in edits {
             write_edit(&mut writer, edit)?;
         }
 
         writer.flush()?;
-        Ok(())
+        drop(writer);
+        std::fs::rename(&temp_path, &self.path)
     }
 
     /// Rebuild chunk index from edits.
@@ -246,8 +276,8 @@ impl EditLog {
     }
 }
 
-/// Read a single edit from the reader.
-fn read_edit(reader: &mut impl Read) -> io::Result<EditDelta> {
+/// Read a single edit from a reader over a log of the given version.
+fn read_edit(reader: &mut impl Read, version: u32) -> io::Result<EditDelta> {
     let mut buf8 = [0u8; 8];
     let mut buf4 = [0u8; 4];
     let mut buf1 = [0u8; 1];
@@ -287,6 +317,20 @@ fn read_edit(reader: &mut impl Read) -> io::Result<EditDelta> {
             let region = read_aabb(reader)?;
             EditOp::ClearRegion { region }
         }
+        4 if version < STROKE_VERSION => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Stroke op needs edit log version {}, file is version {}", STROKE_VERSION, version),
+            ));
+        }
+        4 => {
+            // Stroke
+            let primitive = read_primitive(reader)?;
+            let transform = read_mat4(reader)?;
+            let voxel = read_voxel(reader)?;
+            let blend = read_blend(reader)?;
+            EditOp::Stroke { primitive, transform, voxel, blend }
+        }
         _ => {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
@@ -322,6 +366,13 @@ fn write_edit(writer: &mut impl Write, delta: &EditDelta) -> io::Result<()> {
             writer.write_all(&[3u8])?; // op_type
             write_aabb(writer, *region)?;
         }
+        EditOp::Stroke { primitive, transform, voxel, blend } => {
+            writer.write_all(&[4u8])?; // op_type
+            write_primitive(writer, primitive)?;
+            write_mat4(writer, transform)?;
+            write_voxel(writer, *voxel)?;
+            write_blend(writer, *blend)?;
+        }
     }
 
     Ok(())
@@ -373,6 +424,131 @@ fn write_voxel(writer: &mut impl Write, voxel: Voxel) -> io::Result<()> {
     Ok(())
 }
 
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn invalid_tag(what: &str, tag: u8) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("Unknown {}: {}", what, tag))
+}
+
+fn read_mat4(reader: &mut impl Read) -> io::Result<Mat4> {
+    let mut cols = [0.0f32; 16];
+    for value in &mut cols {
+        *value = read_f32(reader)?;
+    }
+    Ok(Mat4::from_cols_array(&cols))
+}
+
+fn write_mat4(writer: &mut impl Write, m: &Mat4) -> io::Result<()> {
+    for value in m.to_cols_array() {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_axis(reader: &mut impl Read) -> io::Result<Axis> {
+    match read_u8(reader)? {
+        0 => Ok(Axis::X),
+        1 => Ok(Axis::Y),
+        2 => Ok(Axis::Z),
+        tag => Err(invalid_tag("axis", tag)),
+    }
+}
+
+fn write_axis(writer: &mut impl Write, axis: Axis) -> io::Result<()> {
+    let tag = match axis {
+        Axis::X => 0u8,
+        Axis::Y => 1,
+        Axis::Z => 2,
+    };
+    writer.write_all(&[tag])
+}
+
+fn read_blend(reader: &mut impl Read) -> io::Result<BlendMode> {
+    match read_u8(reader)? {
+        0 => Ok(BlendMode::Replace),
+        1 => Ok(BlendMode::Add),
+        2 => Ok(BlendMode::Subtract),
+        tag => Err(invalid_tag("blend mode", tag)),
+    }
+}
+
+fn write_blend(writer: &mut impl Write, blend: BlendMode) -> io::Result<()> {
+    let tag = match blend {
+        BlendMode::Replace => 0u8,
+        BlendMode::Add => 1,
+        BlendMode::Subtract => 2,
+    };
+    writer.write_all(&[tag])
+}
+
+fn read_primitive(reader: &mut impl Read) -> io::Result<BrushPrimitive> {
+    let primitive = match read_u8(reader)? {
+        0 => BrushPrimitive::Sphere { radius: read_f32(reader)? },
+        1 => BrushPrimitive::Box { half_extents: read_vec3(reader)? },
+        2 => BrushPrimitive::Capsule {
+            radius: read_f32(reader)?,
+            half_height: read_f32(reader)?,
+            axis: read_axis(reader)?,
+        },
+        3 => BrushPrimitive::Cylinder {
+            radius: read_f32(reader)?,
+            half_height: read_f32(reader)?,
+            axis: read_axis(reader)?,
+        },
+        4 => {
+            let radius = read_f32(reader)?;
+            let density = read_f32(reader)?;
+            let mut buf = [0u8; 4];
+            reader.read_exact(&mut buf)?;
+            BrushPrimitive::Cloud { radius, density, seed: u32::from_le_bytes(buf) }
+        }
+        tag => return Err(invalid_tag("brush primitive", tag)),
+    };
+    Ok(primitive)
+}
+
+fn write_primitive(writer: &mut impl Write, primitive: &BrushPrimitive) -> io::Result<()> {
+    match primitive {
+        BrushPrimitive::Sphere { radius } => {
+            writer.write_all(&[0u8])?;
+            writer.write_all(&radius.to_le_bytes())?;
+        }
+        BrushPrimitive::Box { half_extents } => {
+            writer.write_all(&[1u8])?;
+            write_vec3(writer, *half_extents)?;
+        }
+        BrushPrimitive::Capsule { radius, half_height, axis } => {
+            writer.write_all(&[2u8])?;
+            writer.write_all(&radius.to_le_bytes())?;
+            writer.write_all(&half_height.to_le_bytes())?;
+            write_axis(writer, *axis)?;
+        }
+        BrushPrimitive::Cylinder { radius, half_height, axis } => {
+            writer.write_all(&[3u8])?;
+            writer.write_all(&radius.to_le_bytes())?;
+            writer.write_all(&half_height.to_le_bytes())?;
+            write_axis(writer, *axis)?;
+        }
+        BrushPrimitive::Cloud { radius, density, seed } => {
+            writer.write_all(&[4u8])?;
+            writer.write_all(&radius.to_le_bytes())?;
+            writer.write_all(&density.to_le_bytes())?;
+            writer.write_all(&seed.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -526,4 +702,100 @@ mod tests {
 
         let _ = std::fs::remove_file(&path);
     }
+
+    #[test]
+    fn test_stroke_roundtrip() {
+        use crate::voxel::brush::BrushStroke;
+
+        let path = temp_path("stroke_roundtrip.rked");
+        let _ = std::fs::remove_file(&path);
+
+        let capsule = BrushStroke::capsule(
+            Vec3::new(1.0, 1.0, 1.0),
+            Vec3::new(3.0, 2.0, 1.5),
+            0.4,
+            Voxel::from_rgb565(0x4321, 2),
+            5,
+        ).with_blend(BlendMode::Subtract);
+        let cloud = BrushStroke::cloud(Vec3::new(6.0, 2.0, 2.0), 1.5, 0.7, 42, Voxel::from_rgb565(0x0F0F, 3), 6)
+            .with_blend(BlendMode::Add);
+
+        {
+            let mut log = EditLog::new(&path);
+            log.append(EditDelta::new(0, 1, EditOp::from_stroke(&capsule))).unwrap();
+            log.append(EditDelta::new(0, 2, EditOp::from_stroke(&cloud))).unwrap();
+        }
+
+        let log = EditLog::load(&path).unwrap();
+        assert_eq!(log.edit_count(), 2);
+        match &log.all_edits()[0].op {
+            EditOp::Stroke { primitive: BrushPrimitive::Capsule { radius, axis, .. }, transform, voxel, blend } => {
+                assert_eq!(*radius, 0.4);
+                assert_eq!(*axis, Axis::Y);
+                assert_eq!(*transform, capsule.transform);
+                assert_eq!(voxel.color, 0x4321);
+                assert_eq!(*blend, BlendMode::Subtract);
+            }
+            other => panic!("Expected capsule stroke, got {:?}", other),
+        }
+        match &log.all_edits()[1].op {
+            EditOp::Stroke { primitive: BrushPrimitive::Cloud { seed, density, .. }, blend, .. } => {
+                assert_eq!(*seed, 42);
+                assert_eq!(*density, 0.7);
+                assert_eq!(*blend, BlendMode::Add);
+            }
+            other => panic!("Expected cloud stroke, got {:?}", other),
+        }
+        // Chunk index follows the stroke shape after reload
+        assert_eq!(log.all_edits()[0].affected_chunks, EditDelta::new(0, 0, EditOp::from_stroke(&capsule)).affected_chunks);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// Write a log header with the given version followed by raw edit bytes.
+    fn write_raw_log(path: &Path, version: u32, edits: &[EditDelta]) {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+        for edit in edits {
+            write_edit(&mut bytes, edit).unwrap();
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn test_versioned_stroke_ops() {
+        use crate::voxel::brush::BrushStroke;
+
+        let path = temp_path("versioned_strokes.rked");
+        let set = EditDelta::new(1, 1, EditOp::SetVoxel {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            voxel: Voxel::from_rgb565(0x1234, 1),
+        });
+        let stroke = BrushStroke::capsule(Vec3::ZERO, Vec3::ONE, 0.5, Voxel::from_rgb565(0x4321, 2), 5);
+        let stroke = EditDelta::new(2, 2, EditOp::from_stroke(&stroke));
+
+        // Version 1 logs still load
+        write_raw_log(&path, 1, std::slice::from_ref(&set));
+        assert_eq!(EditLog::load(&path).unwrap().edit_count(), 1);
+
+        // A stroke in a version 1 log is a version error, not an unknown op
+        write_raw_log(&path, 1, &[set.clone(), stroke.clone()]);
+        let err = EditLog::load(&path).err().unwrap();
+        assert!(err.to_string().contains("version"), "{}", err);
+
+        // Appending a stroke to a version 1 log upgrades the header
+        write_raw_log(&path, 1, std::slice::from_ref(&set));
+        let mut log = EditLog::load(&path).unwrap();
+        log.append(stroke.clone()).unwrap();
+        log.append(EditDelta::new(3, 3, EditOp::ClearVoxel { position: Vec3::ONE })).unwrap();
+        let reloaded = EditLog::load(&path).unwrap();
+        assert_eq!(reloaded.edit_count(), 3);
+        assert!(matches!(reloaded.all_edits()[1].op, EditOp::Stroke { .. }));
+
+        // Newer versions are rejected
+        write_raw_log(&path, VERSION + 1, &[]);
+        assert!(EditLog::load(&path).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -8,9 +8,13 @@ pub mod overlay;
 pub mod invalidator;
 pub mod brick_updater;
 pub mod log;
+pub mod history;
+pub mod bake;
 
 pub use delta::{EditDelta, EditOp};
 pub use overlay::EditOverlay;
 pub use invalidator::ChunkInvalidator;
 pub use brick_updater::BrickUpdater;
 pub use log::EditLog;
+pub use history::EditHistory;
+pub use bake::{bake_edits, bake_octree, rebake_occlusion, BakeReport};
@@ -1,13 +1,15 @@
 //! Edit overlay - spatial index of active edits.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::core::types::Vec3;
 use super::delta::{EditDelta, EditOp};
 use crate::math::aabb::Aabb;
-use crate::voxel::chunk::ChunkCoord;
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
 use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
+use crate::voxel::svo::hashdag::NodeHash;
+use crate::voxel::svo::{HashDag, Octree};
 use crate::voxel::voxel::Voxel;
 
 /// Manages active edits as a spatial overlay.
@@ -20,6 +22,76 @@ pub struct EditOverlay {
     next_id: AtomicU64,
     /// Dirty chunks needing rebuild
     dirty_chunks: Vec<ChunkCoord>,
+    /// Tracked chunks: base octree and its edited HashDAG
+    tracked: HashMap<ChunkCoord, TrackedChunk>,
+}
+
+/// Most DAG roots a tracked chunk keeps for undo. Past it the oldest go,
+/// and undoing that far back replays the edits from the base.
+const MAX_TRACKED_ROOTS: usize = 128;
+
+/// A chunk whose edited voxels the overlay keeps up to date
+struct TrackedChunk {
+    /// Base octree with every overlay edit touching the chunk applied
+    dag: HashDag,
+    /// Retained DAG root after each applied edit, by edit id (0 is the base)
+    roots: BTreeMap<u64, Option<NodeHash>>,
+    /// Retained (root before, root after) of removed edits, for redo
+    removed: HashMap<u64, (Option<NodeHash>, Option<NodeHash>)>,
+}
+
+impl TrackedChunk {
+    fn new(base: &Octree) -> Self {
+        let mut dag = HashDag::from_octree(base);
+        let root = dag.retain_root();
+        Self {
+            dag,
+            roots: BTreeMap::from([(0, root)]),
+            removed: HashMap::new(),
+        }
+    }
+
+    /// Id of the newest applied edit (0 if none)
+    fn last_id(&self) -> u64 {
+        self.roots.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Apply an edit newer than every applied one and keep the root
+    fn push(&mut self, delta: &EditDelta, center: Vec3) {
+        self.dag.apply_edit(delta, center);
+        let root = self.dag.retain_root();
+        if let Some(old) = self.roots.insert(delta.id, root) {
+            self.dag.release_root(old);
+        }
+        if self.roots.len() > MAX_TRACKED_ROOTS
+            && let Some(&oldest) = self.roots.keys().nth(1)
+            && let Some(root) = self.roots.remove(&oldest)
+        {
+            self.dag.release_root(root);
+        }
+    }
+
+    /// Go back to the newest kept root from before `id`, dropping the roots
+    /// from `id` on. Returns the id of the root gone back to, and the
+    /// retained root of `id` itself if it had one.
+    fn rewind(&mut self, id: u64) -> (u64, Option<Option<NodeHash>>) {
+        let mut dropped = self.roots.split_off(&id);
+        let own = dropped.remove(&id);
+        for root in dropped.into_values() {
+            self.dag.release_root(root);
+        }
+        let (&from, &root) = self.roots.last_key_value().expect("base root is never dropped");
+        self.dag.set_root(root);
+        (from, own)
+    }
+
+    /// Forget the roots kept for redo
+    fn drop_removed(&mut self) {
+        for (_, (before, after)) in self.removed.drain() {
+            self.dag.release_root(before);
+            self.dag.release_root(after);
+        }
+    }
 }
 
 impl EditOverlay {
@@ -30,6 +102,7 @@ impl EditOverlay {
             chunk_index: HashMap::new(),
             next_id: AtomicU64::new(1),
             dirty_chunks: Vec::new(),
+            tracked: HashMap::new(),
         }
     }
 
@@ -49,12 +122,41 @@ impl EditOverlay {
             if !self.dirty_chunks.contains(chunk) {
                 self.dirty_chunks.push(*chunk);
             }
+
+            // The new edit has the highest id, so it applies on top. Like a
+            // new edit in an editor, it ends any chance to redo.
+            if let Some(tracked) = self.tracked.get_mut(chunk) {
+                tracked.drop_removed();
+                tracked.push(&delta, chunk_center(*chunk));
+            }
         }
 
         self.edits.insert(id, delta);
         id
     }
 
+    /// Re-insert a previously removed edit, keeping its original ID.
+    ///
+    /// Used by undo/redo so a restored edit sorts among the others exactly
+    /// where it was before it was removed.
+    pub fn restore_edit(&mut self, delta: EditDelta) {
+        let id = delta.id;
+        self.next_id.fetch_max(id + 1, Ordering::Relaxed);
+
+        let affected = delta.affected_chunks.clone();
+        for chunk in &affected {
+            self.chunk_index.entry(*chunk).or_default().push(id);
+            if !self.dirty_chunks.contains(chunk) {
+                self.dirty_chunks.push(*chunk);
+            }
+        }
+
+        self.edits.insert(id, delta);
+        for chunk in affected {
+            self.restore_tracked(chunk, id);
+        }
+    }
+
     /// Remove an edit by ID.
     pub fn remove_edit(&mut self, id: u64) -> Option<EditDelta> {
         if let Some(delta) = self.edits.remove(&id) {
@@ -66,6 +168,7 @@ impl EditOverlay {
                 if !self.dirty_chunks.contains(chunk) {
                     self.dirty_chunks.push(*chunk);
                 }
+                self.remove_tracked(*chunk, id);
             }
             Some(delta)
         } else {
@@ -73,6 +176,112 @@ impl EditOverlay {
         }
     }
 
+    /// Track the edited voxels of a chunk, starting from its `base` octree.
+    ///
+    /// Edits already in the overlay are applied right away; later edits are
+    /// applied incrementally to a HashDAG of the chunk, so only the paths
+    /// they touch are rebuilt. Replaces any previous base for the chunk.
+    pub fn track_chunk(&mut self, coord: ChunkCoord, base: &Octree) {
+        self.tracked.insert(coord, TrackedChunk::new(base));
+        self.replay_tracked(coord, 0);
+    }
+
+    /// Stop tracking a chunk. Returns whether it was tracked.
+    pub fn untrack_chunk(&mut self, coord: ChunkCoord) -> bool {
+        self.tracked.remove(&coord).is_some()
+    }
+
+    /// Edited HashDAG of a tracked chunk.
+    pub fn edited_chunk(&self, coord: ChunkCoord) -> Option<&HashDag> {
+        self.tracked.get(&coord).map(|tracked| &tracked.dag)
+    }
+
+    /// Edited octree of a tracked chunk, ready for upload.
+    pub fn edited_octree(&self, coord: ChunkCoord) -> Option<Octree> {
+        self.edited_chunk(coord).map(HashDag::to_octree)
+    }
+
+    /// Take a removed edit out of a tracked chunk's DAG.
+    ///
+    /// The DAG goes back to the root kept from before the edit, so undoing
+    /// the newest edit is a root swap. Newer edits, if any, are applied
+    /// again on top.
+    fn remove_tracked(&mut self, coord: ChunkCoord, id: u64) {
+        let Some(tracked) = self.tracked.get_mut(&coord) else {
+            return;
+        };
+        let (from, own) = tracked.rewind(id);
+        if let Some(after) = own {
+            // Only valid for redo on top of the root it was made on
+            let before = tracked.dag.retain_root();
+            if let Some((old_before, old_after)) = tracked.removed.insert(id, (before, after)) {
+                tracked.dag.release_root(old_before);
+                tracked.dag.release_root(old_after);
+            }
+        }
+        self.replay_tracked(coord, from);
+    }
+
+    /// Put a restored edit back into a tracked chunk's DAG.
+    ///
+    /// Redoing onto the root the edit was removed from swaps its kept root
+    /// back in; otherwise a restored newest edit is applied on top, and one
+    /// below newer edits rewinds the DAG and applies them again.
+    fn restore_tracked(&mut self, coord: ChunkCoord, id: u64) {
+        let center = chunk_center(coord);
+        let Some(tracked) = self.tracked.get_mut(&coord) else {
+            return;
+        };
+        let kept = tracked.removed.remove(&id);
+
+        if id > tracked.last_id() {
+            let current = (!tracked.dag.is_empty()).then(|| tracked.dag.root_hash());
+            match kept {
+                Some((before, after)) if before == current => {
+                    tracked.dag.set_root(after);
+                    tracked.roots.insert(id, after);
+                    tracked.dag.release_root(before);
+                }
+                _ => {
+                    if let Some((before, after)) = kept {
+                        tracked.dag.release_root(before);
+                        tracked.dag.release_root(after);
+                    }
+                    tracked.push(&self.edits[&id], center);
+                }
+            }
+            return;
+        }
+
+        if let Some((before, after)) = kept {
+            tracked.dag.release_root(before);
+            tracked.dag.release_root(after);
+        }
+        let (from, own) = tracked.rewind(id);
+        if let Some(root) = own {
+            tracked.dag.release_root(root);
+        }
+        self.replay_tracked(coord, from);
+    }
+
+    /// Apply the edits of a tracked chunk newer than `from` on top of its DAG
+    fn replay_tracked(&mut self, coord: ChunkCoord, from: u64) {
+        let Some(tracked) = self.tracked.get_mut(&coord) else {
+            return;
+        };
+
+        let mut edits: Vec<&EditDelta> = self.chunk_index
+            .get(&coord)
+            .map(|ids| ids.iter().filter(|&&id| id > from).filter_map(|id| self.edits.get(id)).collect())
+            .unwrap_or_default();
+        edits.sort_by_key(|delta| delta.id);
+
+        let center = chunk_center(coord);
+        for delta in edits {
+            tracked.push(delta, center);
+        }
+    }
+
     /// Get edits affecting a specific chunk.
     pub fn edits_for_chunk(&self, chunk: ChunkCoord) -> Vec<&EditDelta> {
         self.chunk_index
@@ -89,7 +298,7 @@ impl EditOverlay {
     pub fn edits_in_region(&self, aabb: &Aabb) -> Vec<&EditDelta> {
         self.edits
             .values()
-            .filter(|delta| delta.affected_region().intersects(aabb))
+            .filter(|delta| delta.op.intersects_aabb(aabb))
             .collect()
     }
 
@@ -113,32 +322,37 @@ impl EditOverlay {
         let affected: Vec<_> = self.chunk_index.keys().copied().collect();
         self.edits.clear();
         self.chunk_index.clear();
+        for tracked in self.tracked.values_mut() {
+            tracked.drop_removed();
+            if let (_, Some(root)) = tracked.rewind(1) {
+                tracked.dag.release_root(root);
+            }
+        }
         self.dirty_chunks = affected;
     }
 
     /// Evaluate edits at a position (latest edit wins).
+    ///
+    /// Edits are applied in order over an empty base, so Add strokes only
+    /// fill where no earlier edit placed a voxel.
     pub fn evaluate_at(&self, pos: Vec3) -> Option<Voxel> {
-        // Find edits that might affect this position
-        let mut latest: Option<(u64, Voxel)> = None;
-
-        for delta in self.edits.values() {
-            if let Some(voxel) = delta.evaluate_at(pos) {
-                match latest {
-                    Some((prev_id, _)) if delta.id > prev_id => {
-                        latest = Some((delta.id, voxel));
-                    }
-                    None => {
-                        latest = Some((delta.id, voxel));
-                    }
-                    _ => {}
-                }
-            }
-        }
+        let mut hits: Vec<&EditDelta> = self.edits
+            .values()
+            .filter(|delta| delta.evaluate_at(pos).is_some())
+            .collect();
+        hits.sort_by_key(|delta| delta.id);
 
-        latest.map(|(_, v)| v)
+        hits.into_iter().fold(None, |current, delta| {
+            Some(delta.apply_at(pos, current.unwrap_or(Voxel::EMPTY)))
+        })
     }
 }
 
+/// World-space center of a chunk (the local origin of its octree)
+fn chunk_center(coord: ChunkCoord) -> Vec3 {
+    coord.world_origin() + Vec3::splat(CHUNK_SIZE as f32 / 2.0)
+}
+
 impl Default for EditOverlay {
     fn default() -> Self {
         Self::new()
@@ -266,4 +480,139 @@ mod tests {
         let outside = Aabb::new(Vec3::new(10.0, 10.0, 10.0), Vec3::new(12.0, 12.0, 12.0));
         assert_eq!(overlay.classify_region(&outside), RegionHint::Unknown);
     }
+
+    #[test]
+    fn test_stroke_edits() {
+        use crate::voxel::brush::{BlendMode, BrushStroke};
+
+        let mut overlay = EditOverlay::new();
+        let stone = Voxel::from_rgb565(0, 1);
+        let moss = Voxel::from_rgb565(0, 2);
+
+        let sphere = BrushStroke::sphere(Vec3::new(2.0, 2.0, 2.0), 1.0, stone, 5);
+        overlay.add_edit(EditOp::from_stroke(&sphere), 0);
+        let added = BrushStroke::sphere(Vec3::new(3.0, 2.0, 2.0), 1.0, moss, 5)
+            .with_blend(BlendMode::Add);
+        overlay.add_edit(EditOp::from_stroke(&added), 1);
+
+        // Add keeps the earlier voxel and fills only empty space
+        assert_eq!(overlay.evaluate_at(Vec3::new(2.0, 2.0, 2.0)).unwrap().material_id, 1);
+        assert_eq!(overlay.evaluate_at(Vec3::new(3.8, 2.0, 2.0)).unwrap().material_id, 2);
+
+        let carve = BrushStroke::sphere(Vec3::new(2.0, 2.0, 2.0), 0.5, stone, 5)
+            .with_blend(BlendMode::Subtract);
+        overlay.add_edit(EditOp::from_stroke(&carve), 2);
+        assert!(overlay.evaluate_at(Vec3::new(2.0, 2.0, 2.0)).unwrap().is_empty());
+
+        // Inside the sphere's AABB but outside its SDF
+        assert!(overlay.evaluate_at(Vec3::new(1.1, 1.1, 1.1)).is_none());
+    }
+
+    #[test]
+    fn test_tracked_chunk_follows_edits() {
+        use crate::voxel::brush::{BlendMode, BrushStroke};
+        use crate::voxel::svo::AdaptiveOctreeBuilder;
+
+        let coord = ChunkCoord::new(0, 0, 0);
+        let stone = Voxel::from_rgb565(0x4208, 1);
+        let base = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 1.0 { stone } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        );
+        let sample = |overlay: &EditOverlay, pos: Vec3| {
+            overlay.edited_chunk(coord).unwrap().sample_voxel(pos - chunk_center(coord))
+        };
+
+        let mut overlay = EditOverlay::new();
+        let fill = overlay.add_edit(
+            EditOp::FillRegion {
+                region: Aabb::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 3.0, 1.0)),
+                voxel: Voxel::from_rgb565(0xF800, 7),
+            },
+            0,
+        );
+
+        // Edits made before tracking are applied to the base
+        overlay.track_chunk(coord, &base);
+        assert_eq!(sample(&overlay, Vec3::new(0.5, 2.5, 0.5)).material_id, 7);
+        assert_eq!(sample(&overlay, Vec3::new(2.0, 0.5, 2.0)), stone);
+
+        let carve = BrushStroke::sphere(Vec3::new(2.0, 1.0, 2.0), 0.5, Voxel::EMPTY, 5)
+            .with_blend(BlendMode::Subtract);
+        overlay.add_edit(EditOp::from_stroke(&carve), 1);
+        assert!(sample(&overlay, Vec3::new(2.0, 0.8, 2.0)).is_empty());
+
+        // Removing an older edit rewinds the chunk and reapplies the newer one
+        let removed = overlay.remove_edit(fill).unwrap();
+        assert!(sample(&overlay, Vec3::new(0.5, 2.5, 0.5)).is_empty());
+        assert!(sample(&overlay, Vec3::new(2.0, 0.8, 2.0)).is_empty());
+
+        overlay.restore_edit(removed);
+        assert_eq!(sample(&overlay, Vec3::new(0.5, 2.5, 0.5)).material_id, 7);
+
+        let edited = overlay.edited_octree(coord).unwrap();
+        assert_eq!(edited.sample_voxel(Vec3::new(0.5, 2.5, 0.5) - chunk_center(coord)).material_id, 7);
+
+        overlay.clear();
+        assert_eq!(sample(&overlay, Vec3::new(2.0, 0.8, 2.0)), stone);
+        assert!(overlay.untrack_chunk(coord));
+        assert!(overlay.edited_chunk(coord).is_none());
+    }
+
+    #[test]
+    fn test_tracked_undo_swaps_roots() {
+        use crate::voxel::edit::{ChunkInvalidator, EditHistory};
+        use crate::voxel::svo::AdaptiveOctreeBuilder;
+
+        let coord = ChunkCoord::new(0, 0, 0);
+        let stone = Voxel::from_rgb565(0x4208, 1);
+        let base = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 1.0 { stone } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        );
+        let mut overlay = EditOverlay::new();
+        let mut invalidator = ChunkInvalidator::new();
+        let mut history = EditHistory::default();
+        overlay.track_chunk(coord, &base);
+        let root = |overlay: &EditOverlay| overlay.edited_chunk(coord).unwrap().root_hash();
+
+        let mut roots = vec![root(&overlay)];
+        for i in 0..4 {
+            let x = i as f32;
+            let op = EditOp::FillRegion {
+                region: Aabb::new(Vec3::new(x, 2.0, 0.0), Vec3::new(x + 1.0, 3.0, 1.0)),
+                voxel: Voxel::from_rgb565(0, 7),
+            };
+            history.apply(&mut overlay, &mut invalidator, op, i);
+            roots.push(root(&overlay));
+        }
+
+        // Undo goes back through the kept roots, redo swaps the removed ones back in
+        for expected in roots.iter().rev().skip(1) {
+            history.undo(&mut overlay, &mut invalidator);
+            assert_eq!(root(&overlay), *expected);
+        }
+        assert_eq!(overlay.tracked[&coord].removed.len(), 4);
+        for expected in roots.iter().skip(1) {
+            history.redo(&mut overlay, &mut invalidator);
+            assert_eq!(root(&overlay), *expected);
+        }
+        assert!(overlay.tracked[&coord].removed.is_empty());
+        assert_eq!(overlay.tracked[&coord].roots.len(), 5);
+
+        // Removing a middle edit keeps the others applied
+        let third = overlay.edits_for_chunk(coord).iter().map(|e| e.id).nth(2).unwrap();
+        overlay.remove_edit(third);
+        let dag = overlay.edited_chunk(coord).unwrap();
+        assert!(dag.sample_voxel(Vec3::new(2.5, 2.5, 0.5) - chunk_center(coord)).is_empty());
+        assert_eq!(dag.sample_voxel(Vec3::new(3.5, 2.5, 0.5) - chunk_center(coord)).material_id, 7);
+
+        // Nothing is left pinned once the edits are gone
+        overlay.clear();
+        let tracked = overlay.tracked.get_mut(&coord).unwrap();
+        assert_eq!(tracked.dag.unique_node_count(), HashDag::from_octree(&base).unique_node_count());
+        assert_eq!(tracked.dag.collect_garbage(), 0);
+    }
 }
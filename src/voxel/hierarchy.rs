@@ -35,6 +35,12 @@ pub const BRICKS_PER_CHUNK: u32 = VOXELS_PER_CHUNK / BRICK_SIZE_VOXELS;
 /// Total bricks per chunk (256^3 = 16,777,216)
 pub const TOTAL_BRICKS_PER_CHUNK: u32 = BRICKS_PER_CHUNK * BRICKS_PER_CHUNK * BRICKS_PER_CHUNK;
 
+/// Far-field proxy voxels per SuperChunk dimension (64 = 1m voxels)
+pub const PROXY_VOXELS_PER_SUPER_CHUNK: u32 = 64;
+
+/// Far-field proxy voxels per chunk dimension (4)
+pub const PROXY_VOXELS_PER_CHUNK: u32 = PROXY_VOXELS_PER_SUPER_CHUNK / CHUNKS_PER_SUPER_CHUNK;
+
 /// Convert world position to SuperChunk coordinate
 pub fn world_to_super_chunk(pos: glam::Vec3) -> (i32, i32, i32) {
     (
@@ -0,0 +1,129 @@
+//! Occupancy grids: binvox run-length files and raw dense grids
+
+use glam::UVec3;
+
+use super::{ImportError, IndexedGrid, MAX_GRID_SIZE};
+
+/// Read a binvox file. Solid voxels get palette index 1.
+///
+/// binvox stores voxels x slowest, then z, then y, with y up. `dim` gives
+/// the extents in that storage order, as `x z y`.
+pub fn read_binvox(data: &[u8]) -> Result<IndexedGrid, ImportError> {
+    let mut pos = 0;
+    let mut next_line = || -> Result<&str, ImportError> {
+        let rest = &data[pos.min(data.len())..];
+        let end = rest.iter().position(|b| *b == b'\n')
+            .ok_or_else(|| ImportError::Malformed("unterminated binvox header".into()))?;
+        pos += end + 1;
+        std::str::from_utf8(&rest[..end])
+            .map(str::trim)
+            .map_err(|_| ImportError::Malformed("binvox header is not text".into()))
+    };
+
+    let magic = next_line().map_err(|_| ImportError::BadMagic("binvox"))?;
+    let version = magic.strip_prefix("#binvox")
+        .ok_or(ImportError::BadMagic("binvox"))?
+        .trim();
+    if version != "1" {
+        return Err(ImportError::UnsupportedVersion {
+            format: "binvox",
+            version: version.parse().unwrap_or(-1),
+        });
+    }
+
+    let mut dims = None;
+    loop {
+        let line = next_line()?;
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("dim") => {
+                let values: Vec<u32> = words.map(|w| w.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| ImportError::Malformed(format!("bad dim line '{}'", line)))?;
+                match values[..] {
+                    [x, z, y] if [x, y, z].iter().all(|d| (1..=MAX_GRID_SIZE).contains(d)) => {
+                        dims = Some(UVec3::new(x, y, z))
+                    }
+                    _ => return Err(ImportError::Malformed(format!("bad dim line '{}'", line))),
+                }
+            }
+            Some("data") => break,
+            // translate / scale place the grid in the source mesh's space
+            _ => {}
+        }
+    }
+    let size = dims.ok_or_else(|| ImportError::Malformed("binvox header has no dim".into()))?;
+
+    let mut grid = IndexedGrid::new(size)?;
+    let total = grid.cells.len();
+    let (size_y, size_z) = (size.y as usize, size.z as usize);
+    let mut index = 0;
+    for pair in data[pos..].chunks_exact(2) {
+        let (value, count) = (pair[0], pair[1] as usize);
+        if index + count > total {
+            return Err(ImportError::Malformed("binvox data overruns the grid".into()));
+        }
+        if value != 0 {
+            for i in index..index + count {
+                let y = i % size_y;
+                let z = (i / size_y) % size_z;
+                let x = i / (size_y * size_z);
+                grid.set(x as u32, y as u32, z as u32, 1);
+            }
+        }
+        index += count;
+    }
+    if index != total {
+        return Err(ImportError::Malformed(format!("binvox data covers {} of {} voxels", index, total)));
+    }
+    Ok(grid)
+}
+
+/// Read a headerless dense grid of one palette index byte per voxel,
+/// x fastest, then y, then z (y up)
+pub fn read_raw(data: &[u8], size: UVec3) -> Result<IndexedGrid, ImportError> {
+    let expected = IndexedGrid::new(size)?.cells.len();
+    if data.len() != expected {
+        return Err(ImportError::Malformed(format!(
+            "raw grid {}x{}x{} needs {} bytes, got {}",
+            size.x, size.y, size.z, expected, data.len()
+        )));
+    }
+    Ok(IndexedGrid { size, cells: data.to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binvox_run_lengths() {
+        // x = 2, z = 1, y = 3; storage order is y fastest
+        let mut file = b"#binvox 1\ndim 2 1 3\ntranslate 0 0 0\nscale 1\ndata\n".to_vec();
+        // x = 0: y 0..2 solid, y = 2 empty; x = 1: y = 0 empty, y 1..3 solid
+        file.extend_from_slice(&[1, 2, 0, 2, 1, 2]);
+        let grid = read_binvox(&file).unwrap();
+        assert_eq!(grid.size, UVec3::new(2, 3, 1));
+        assert_eq!(grid.solid_count(), 4);
+        assert_eq!(grid.get(0, 1, 0), 1);
+        assert_eq!(grid.get(0, 2, 0), 0);
+        assert_eq!(grid.get(1, 0, 0), 0);
+        assert_eq!(grid.get(1, 2, 0), 1);
+
+        file.truncate(file.len() - 2);
+        assert!(read_binvox(&file).is_err());
+        assert!(matches!(read_binvox(b"#binvox 2\n"), Err(ImportError::UnsupportedVersion { .. })));
+        assert!(matches!(
+            read_binvox(b"#binvox 1\ndim 65536 65536 65536\ndata\n"),
+            Err(ImportError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_raw_grid() {
+        let grid = read_raw(&[0, 3, 0, 0, 5, 0, 0, 0], UVec3::splat(2)).unwrap();
+        assert_eq!(grid.get(1, 0, 0), 3);
+        assert_eq!(grid.get(0, 0, 1), 5);
+        assert!(read_raw(&[0; 7], UVec3::splat(2)).is_err());
+    }
+}
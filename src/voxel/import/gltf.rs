@@ -0,0 +1,503 @@
+//! glTF 2.0 reader (`.gltf` and `.glb`)
+//!
+//! Reads triangle primitives of every mesh in the default scene, with node
+//! transforms applied, plus base color factors and textures. Buffers and
+//! images may be embedded, data URIs or files beside the model. Sparse
+//! accessors, non-triangle primitives and texture transforms are not
+//! supported.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use base64::Engine;
+use glam::{Mat4, Quat, Vec2, Vec3};
+use serde::Deserialize;
+
+use super::{base_dir, ImportError, MeshMaterial, MeshTexture, MeshTriangle, TriangleMesh};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+const MODE_TRIANGLES: u32 = 4;
+/// Node hierarchies deeper than this are treated as cyclic
+const MAX_NODE_DEPTH: usize = 64;
+
+/// Read a `.gltf` or `.glb` file and the buffers and images it references
+pub fn load_gltf(path: &Path) -> Result<TriangleMesh, ImportError> {
+    parse_gltf(&std::fs::read(path)?, base_dir(path))
+}
+
+/// Parse glTF JSON or a GLB container. External files are read relative
+/// to `base_dir`.
+pub fn parse_gltf(data: &[u8], base_dir: &Path) -> Result<TriangleMesh, ImportError> {
+    let (json, bin) = if data.starts_with(GLB_MAGIC) {
+        split_glb(data)?
+    } else {
+        (data, None)
+    };
+    let document: Document = serde_json::from_slice(json)?;
+
+    let buffers = document.buffers.iter()
+        .enumerate()
+        .map(|(i, buffer)| match &buffer.uri {
+            Some(uri) => read_uri(uri, base_dir),
+            None if i == 0 => bin.map(<[u8]>::to_vec)
+                .ok_or_else(|| ImportError::Malformed("buffer 0 has no uri and there is no GLB chunk".into())),
+            None => Err(ImportError::Malformed(format!("buffer {} has no uri", i))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut reader = GltfReader {
+        document: &document,
+        buffers,
+        base_dir,
+        mesh: TriangleMesh::default(),
+        textures_by_image: HashMap::new(),
+    };
+    reader.materials()?;
+
+    let roots: Vec<usize> = match document.scene.or((!document.scenes.is_empty()).then_some(0)) {
+        Some(scene) => document.scenes.get(scene)
+            .ok_or_else(|| ImportError::Malformed(format!("scene {} does not exist", scene)))?
+            .nodes.clone(),
+        // No scenes: every node that is nobody's child
+        None => {
+            let children: Vec<usize> = document.nodes.iter().flat_map(|n| n.children.iter().copied()).collect();
+            (0..document.nodes.len()).filter(|i| !children.contains(i)).collect()
+        }
+    };
+    for root in roots {
+        reader.node(root, Mat4::IDENTITY, 0)?;
+    }
+    Ok(reader.mesh)
+}
+
+/// JSON and BIN chunks of a GLB container
+fn split_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), ImportError> {
+    let u32_at = |offset: usize| -> Result<u32, ImportError> {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| ImportError::Malformed("truncated GLB".into()))
+    };
+    let version = u32_at(4)?;
+    if version != 2 {
+        return Err(ImportError::UnsupportedVersion { format: "glTF", version: version as i32 });
+    }
+    let length = (u32_at(8)? as usize).min(data.len());
+
+    let (mut json, mut bin) = (None, None);
+    let mut offset = 12;
+    while offset + 8 <= length {
+        let chunk_length = u32_at(offset)? as usize;
+        let chunk_type = u32_at(offset + 4)?;
+        let chunk = data.get(offset + 8..offset + 8 + chunk_length)
+            .ok_or_else(|| ImportError::Malformed("truncated GLB chunk".into()))?;
+        match chunk_type {
+            GLB_JSON_CHUNK => json = Some(chunk),
+            GLB_BIN_CHUNK => bin = bin.or(Some(chunk)),
+            _ => {}
+        }
+        // Chunks are 4-byte aligned
+        offset += 8 + chunk_length.next_multiple_of(4);
+    }
+    let json = json.ok_or_else(|| ImportError::Malformed("GLB has no JSON chunk".into()))?;
+    Ok((json, bin))
+}
+
+/// Contents of a data URI or a file relative to `base_dir`
+fn read_uri(uri: &str, base_dir: &Path) -> Result<Vec<u8>, ImportError> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (_, encoded) = data.split_once(";base64,")
+            .ok_or_else(|| ImportError::Malformed("data URI is not base64".into()))?;
+        return base64::engine::general_purpose::STANDARD.decode(encoded)
+            .map_err(|e| ImportError::Malformed(format!("bad base64 data URI: {}", e)));
+    }
+    Ok(std::fs::read(base_dir.join(uri.replace("%20", " ")))?)
+}
+
+struct GltfReader<'a> {
+    document: &'a Document,
+    buffers: Vec<Vec<u8>>,
+    base_dir: &'a Path,
+    mesh: TriangleMesh,
+    textures_by_image: HashMap<usize, usize>,
+}
+
+impl GltfReader<'_> {
+    /// One mesh material per glTF material, in order
+    fn materials(&mut self) -> Result<(), ImportError> {
+        for (i, material) in self.document.materials.iter().enumerate() {
+            let pbr = material.pbr_metallic_roughness.as_ref();
+            let texture = match pbr.and_then(|p| p.base_color_texture.as_ref()) {
+                Some(texture) => Some(self.texture(texture.index)?),
+                None => None,
+            };
+            self.mesh.materials.push(MeshMaterial {
+                name: material.name.clone().unwrap_or_else(|| format!("material_{}", i)),
+                color: pbr.and_then(|p| p.base_color_factor).unwrap_or([1.0; 4]),
+                texture,
+                alpha_cutout: material.alpha_mode.as_deref().is_some_and(|mode| mode != "OPAQUE"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Mesh texture for a glTF texture, decoding each image once
+    fn texture(&mut self, index: usize) -> Result<usize, ImportError> {
+        let image_index = self.document.textures.get(index)
+            .and_then(|t| t.source)
+            .ok_or_else(|| ImportError::Malformed(format!("texture {} has no image", index)))?;
+        if let Some(texture) = self.textures_by_image.get(&image_index) {
+            return Ok(*texture);
+        }
+
+        let source = self.document.images.get(image_index)
+            .ok_or_else(|| ImportError::Malformed(format!("image {} does not exist", image_index)))?;
+        let bytes = match (&source.uri, source.buffer_view) {
+            (Some(uri), _) => read_uri(uri, self.base_dir)?,
+            (None, Some(view)) => self.view_bytes(view)?.to_vec(),
+            (None, None) => return Err(ImportError::Malformed(format!("image {} has no data", image_index))),
+        };
+        self.mesh.textures.push(MeshTexture::from_image(image::load_from_memory(&bytes)?.to_rgba8()));
+        let texture = self.mesh.textures.len() - 1;
+        self.textures_by_image.insert(image_index, texture);
+        Ok(texture)
+    }
+
+    fn node(&mut self, index: usize, parent: Mat4, depth: usize) -> Result<(), ImportError> {
+        if depth > MAX_NODE_DEPTH {
+            return Err(ImportError::Malformed("node hierarchy too deep or cyclic".into()));
+        }
+        let document = self.document;
+        let node = document.nodes.get(index)
+            .ok_or_else(|| ImportError::Malformed(format!("node {} does not exist", index)))?;
+        let transform = parent * node.local_transform();
+        if let Some(mesh) = node.mesh {
+            self.primitives(mesh, transform)?;
+        }
+        for child in &node.children {
+            self.node(*child, transform, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    fn primitives(&mut self, mesh: usize, transform: Mat4) -> Result<(), ImportError> {
+        let document = self.document;
+        let mesh = document.meshes.get(mesh)
+            .ok_or_else(|| ImportError::Malformed(format!("mesh {} does not exist", mesh)))?;
+        for primitive in &mesh.primitives {
+            if primitive.mode != MODE_TRIANGLES {
+                log::warn!("Skipping glTF primitive with mode {}", primitive.mode);
+                continue;
+            }
+            let position = *primitive.attributes.get("POSITION")
+                .ok_or_else(|| ImportError::Malformed("primitive has no POSITION".into()))?;
+            let positions = self.accessor(position, 3)?;
+            let uvs = match primitive.attributes.get("TEXCOORD_0") {
+                Some(uv) => Some(self.accessor(*uv, 2)?),
+                None => None,
+            };
+            let vertex_count = positions.len() / 3;
+            let indices: Vec<u32> = match primitive.indices {
+                Some(indices) => self.accessor(indices, 1)?.into_iter().map(|i| i as u32).collect(),
+                None => (0..vertex_count as u32).collect(),
+            };
+            if indices.iter().any(|i| *i as usize >= vertex_count) {
+                return Err(ImportError::Malformed("index beyond the vertex count".into()));
+            }
+            if primitive.material.is_some_and(|m| m >= self.mesh.materials.len()) {
+                return Err(ImportError::Malformed("primitive material does not exist".into()));
+            }
+
+            let base = self.mesh.positions.len() as u32;
+            let uv_base = self.mesh.uvs.len() as u32;
+            self.mesh.positions.extend(positions.chunks_exact(3).map(|p| {
+                transform.transform_point3(Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32))
+            }));
+            if let Some(uvs) = &uvs {
+                self.mesh.uvs.extend(uvs.chunks_exact(2).map(|uv| Vec2::new(uv[0] as f32, uv[1] as f32)));
+            }
+            for triangle in indices.chunks_exact(3) {
+                let corners = [triangle[0], triangle[1], triangle[2]];
+                self.mesh.triangles.push(MeshTriangle {
+                    positions: corners.map(|i| base + i),
+                    uvs: uvs.is_some().then(|| corners.map(|i| uv_base + i)),
+                    material: primitive.material,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn view_bytes(&self, index: usize) -> Result<&[u8], ImportError> {
+        let view = self.document.buffer_views.get(index)
+            .ok_or_else(|| ImportError::Malformed(format!("buffer view {} does not exist", index)))?;
+        self.buffers.get(view.buffer)
+            .and_then(|b| b.get(view.byte_offset..view.byte_offset + view.byte_length))
+            .ok_or_else(|| ImportError::Malformed(format!("buffer view {} is out of range", index)))
+    }
+
+    /// Accessor values, `components` per element, normalized integers
+    /// mapped to [0, 1] or [-1, 1]
+    fn accessor(&self, index: usize, components: usize) -> Result<Vec<f64>, ImportError> {
+        let accessor = self.document.accessors.get(index)
+            .ok_or_else(|| ImportError::Malformed(format!("accessor {} does not exist", index)))?;
+        if accessor.sparse.is_some() {
+            return Err(ImportError::Malformed("sparse accessors are not supported".into()));
+        }
+        let expected = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            _ => "VEC3",
+        };
+        if accessor.kind != expected {
+            return Err(ImportError::Malformed(format!("accessor {} is {}, expected {}", index, accessor.kind, expected)));
+        }
+        let Some(view_index) = accessor.buffer_view else {
+            // No buffer view: all zeros
+            return Ok(vec![0.0; accessor.count * components]);
+        };
+
+        let component_size = match accessor.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return Err(ImportError::Malformed(format!("unknown component type {}", other))),
+        };
+        let bytes = self.view_bytes(view_index)?;
+        let stride = self.document.buffer_views[view_index].byte_stride.unwrap_or(component_size * components);
+        let element_end = |i: usize| accessor.byte_offset + i * stride + component_size * components;
+        if accessor.count > 0 && element_end(accessor.count - 1) > bytes.len() {
+            return Err(ImportError::Malformed(format!("accessor {} overruns its buffer view", index)));
+        }
+
+        let mut values = Vec::with_capacity(accessor.count * components);
+        for i in 0..accessor.count {
+            for c in 0..components {
+                let at = accessor.byte_offset + i * stride + c * component_size;
+                let b = &bytes[at..at + component_size];
+                let value = match accessor.component_type {
+                    5120 => normalize(b[0] as i8 as f64, 127.0, accessor.normalized),
+                    5121 => normalize(b[0] as f64, 255.0, accessor.normalized),
+                    5122 => normalize(i16::from_le_bytes([b[0], b[1]]) as f64, 32767.0, accessor.normalized),
+                    5123 => normalize(u16::from_le_bytes([b[0], b[1]]) as f64, 65535.0, accessor.normalized),
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                };
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+}
+
+fn normalize(value: f64, max: f64, normalized: bool) -> f64 {
+    if normalized { (value / max).max(-1.0) } else { value }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    scene: Option<usize>,
+    #[serde(default)]
+    scenes: Vec<Scene>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    meshes: Vec<Mesh>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    materials: Vec<Material>,
+    #[serde(default)]
+    textures: Vec<Texture>,
+    #[serde(default)]
+    images: Vec<Image>,
+}
+
+#[derive(Deserialize)]
+struct Scene {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct Node {
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    matrix: Option<[f32; 16]>,
+    translation: Option<[f32; 3]>,
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+}
+
+impl Node {
+    fn local_transform(&self) -> Mat4 {
+        if let Some(matrix) = self.matrix {
+            return Mat4::from_cols_array(&matrix);
+        }
+        Mat4::from_scale_rotation_translation(
+            self.scale.map_or(Vec3::ONE, Vec3::from),
+            self.rotation.map_or(Quat::IDENTITY, Quat::from_array),
+            self.translation.map_or(Vec3::ZERO, Vec3::from),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct Mesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    #[serde(default = "triangles_mode")]
+    mode: u32,
+}
+
+fn triangles_mode() -> u32 {
+    MODE_TRIANGLES
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Buffer {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Material {
+    name: Option<String>,
+    pbr_metallic_roughness: Option<PbrMetallicRoughness>,
+    alpha_mode: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PbrMetallicRoughness {
+    base_color_factor: Option<[f32; 4]>,
+    base_color_texture: Option<TextureInfo>,
+}
+
+#[derive(Deserialize)]
+struct TextureInfo {
+    index: usize,
+}
+
+#[derive(Deserialize)]
+struct Texture {
+    source: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Image {
+    uri: Option<String>,
+    buffer_view: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One triangle with u16 indices, translated by its node
+    fn triangle_gltf(buffer_uri: Option<String>) -> (String, Vec<u8>) {
+        let mut bin: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|f| f.to_le_bytes())
+            .collect();
+        bin.extend([0u16, 1, 2, 0].iter().flat_map(|i| i.to_le_bytes()));
+        let uri = buffer_uri.map_or(String::new(), |uri| format!(r#","uri":"{}""#, uri));
+        let json = format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "scene": 0,
+                "scenes": [{{"nodes": [0]}}],
+                "nodes": [{{"children": [1], "scale": [2, 2, 2]}}, {{"mesh": 0, "translation": [0, 5, 0]}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+                "materials": [{{"name": "paint", "pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1]}}}}],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+                    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+                ],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+                ],
+                "buffers": [{{"byteLength": {}{}}}]
+            }}"#,
+            bin.len(), uri
+        );
+        (json, bin)
+    }
+
+    fn check_triangle(mesh: &TriangleMesh) {
+        assert_eq!(mesh.triangles.len(), 1);
+        assert_eq!(mesh.triangles[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.positions, vec![Vec3::new(0.0, 10.0, 0.0), Vec3::new(2.0, 10.0, 0.0), Vec3::new(0.0, 12.0, 0.0)]);
+        assert_eq!(mesh.materials[0].name, "paint");
+        assert_eq!(mesh.materials[0].color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_gltf_data_uri() {
+        let (_, bin) = triangle_gltf(None);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&bin);
+        let (json, _) = triangle_gltf(Some(format!("data:application/octet-stream;base64,{}", encoded)));
+        check_triangle(&parse_gltf(json.as_bytes(), Path::new("")).unwrap());
+    }
+
+    #[test]
+    fn test_glb_container() {
+        let (json, mut bin) = triangle_gltf(None);
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let mut glb = GLB_MAGIC.to_vec();
+        glb.extend(2u32.to_le_bytes());
+        glb.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        for (kind, chunk) in [(GLB_JSON_CHUNK, &json), (GLB_BIN_CHUNK, &bin)] {
+            glb.extend((chunk.len() as u32).to_le_bytes());
+            glb.extend(kind.to_le_bytes());
+            glb.extend_from_slice(chunk);
+        }
+        check_triangle(&parse_gltf(&glb, Path::new("")).unwrap());
+
+        glb[4] = 1;
+        assert!(matches!(parse_gltf(&glb, Path::new("")), Err(ImportError::UnsupportedVersion { .. })));
+    }
+}
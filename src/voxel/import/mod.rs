@@ -0,0 +1,314 @@
+//! Importers for artist-authored voxel models
+//!
+//! - `vox`: MagicaVoxel `.vox`, with palette, multiple models and the
+//!   scene graph transforms flattened into one model
+//! - `binvox`: binvox run-length files and plain raw dense grids
+//! - `obj`, `gltf`: triangle meshes, turned into voxels by `voxelize`
+//!
+//! Voxel formats produce an `IndexedGrid` of palette indices. A `Palette`
+//! turns indices into RGB565 colors and material IDs, and
+//! `IndexedGrid::to_model` builds the octree through
+//! `OctreeBuilder::new_rectangular`, ready to save as `ClutterData` or
+//! `TreeData`. Meshes skip the palette and voxelize straight to a model.
+
+pub mod vox;
+pub mod binvox;
+pub mod obj;
+pub mod gltf;
+pub mod voxelize;
+
+pub use vox::VoxFile;
+pub use binvox::{read_binvox, read_raw};
+pub use voxelize::{voxelize, FillRule, MeshMaterial, MeshTexture, MeshTriangle, TriangleMesh, VoxelizeOptions};
+
+use std::io;
+use std::path::Path;
+
+use glam::{UVec3, Vec3};
+use thiserror::Error;
+
+use crate::clutter::{ClutterData, ClutterLibrary};
+use crate::voxel::instancing::VoxelModel;
+use crate::voxel::procgen::TreeStyle;
+use crate::voxel::svo::{Octree, OctreeBuilder};
+use crate::voxel::tree_data::{TreeData, TREE_FILE_EXTENSION};
+use crate::voxel::voxel::Voxel;
+
+/// Errors from reading voxel model files
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("not a {0} file")]
+    BadMagic(&'static str),
+    #[error("unsupported {format} version {version}")]
+    UnsupportedVersion { format: &'static str, version: i32 },
+    #[error("malformed file: {0}")]
+    Malformed(String),
+    #[error("model has no solid voxels")]
+    Empty,
+    #[error("model is {size} voxels across, the limit is {max}")]
+    TooLarge { size: u32, max: u32 },
+    #[error("failed to parse glTF JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to decode texture: {0}")]
+    Image(#[from] image::ImageError),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Largest grid edge, in voxels, an imported model may have
+pub const MAX_GRID_SIZE: u32 = 2048;
+
+/// Number of cells in a `size` grid.
+/// Fails for edges past `MAX_GRID_SIZE` or a cell count that overflows.
+fn grid_len(size: UVec3) -> Result<usize, ImportError> {
+    if size.max_element() > MAX_GRID_SIZE {
+        return Err(ImportError::Malformed(format!(
+            "grid {}x{}x{} is over {} voxels across",
+            size.x, size.y, size.z, MAX_GRID_SIZE
+        )));
+    }
+    (size.x as usize)
+        .checked_mul(size.y as usize)
+        .and_then(|n| n.checked_mul(size.z as usize))
+        .ok_or_else(|| ImportError::Malformed(format!("grid {}x{}x{} is too large", size.x, size.y, size.z)))
+}
+
+/// Dense grid of palette indices, x fastest, then y, then z (y up).
+/// Index 0 is empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedGrid {
+    pub size: UVec3,
+    pub cells: Vec<u8>,
+}
+
+impl IndexedGrid {
+    /// Empty grid of the given size.
+    /// Fails for sizes past `MAX_GRID_SIZE` on any axis.
+    pub fn new(size: UVec3) -> Result<Self, ImportError> {
+        Ok(Self { size, cells: vec![0; grid_len(size)?] })
+    }
+
+    fn index(&self, x: u32, y: u32, z: u32) -> usize {
+        let (size_x, size_y) = (self.size.x as usize, self.size.y as usize);
+        x as usize + size_x * (y as usize + size_y * z as usize)
+    }
+
+    pub fn get(&self, x: u32, y: u32, z: u32) -> u8 {
+        self.cells[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, z: u32, value: u8) {
+        let index = self.index(x, y, z);
+        self.cells[index] = value;
+    }
+
+    /// Number of non-empty cells
+    pub fn solid_count(&self) -> usize {
+        self.cells.iter().filter(|c| **c != 0).count()
+    }
+
+    /// Build an octree of `voxel_size` meter voxels.
+    ///
+    /// The model is centered horizontally and rests on the bottom of the
+    /// octree, like generated trees and rocks.
+    pub fn to_model(&self, palette: &Palette, voxel_size: f32) -> Result<ImportedModel, ImportError> {
+        build_model(self.size, voxel_size, |x, y, z| match self.get(x, y, z) {
+            0 => Voxel::EMPTY,
+            index => palette.voxel(index),
+        })
+    }
+}
+
+/// Build an octree of `voxel_size` meter voxels from a `size` grid, with
+/// the model centered horizontally and resting on the bottom of the octree
+fn build_model(
+    size: UVec3,
+    voxel_size: f32,
+    voxel_at: impl Fn(u32, u32, u32) -> Voxel,
+) -> Result<ImportedModel, ImportError> {
+    grid_len(size)?;
+    let side = size.max_element().next_power_of_two().max(2);
+    let pad_x = (side - size.x) / 2;
+    let pad_z = (side - size.z) / 2;
+    let height = size.y;
+
+    let mut voxels = vec![Voxel::EMPTY; grid_len(UVec3::new(side, height, side))?];
+    let (side_len, height_len) = (side as usize, height as usize);
+    let mut voxel_count = 0;
+    for z in 0..size.z {
+        for y in 0..height {
+            for x in 0..size.x {
+                let voxel = voxel_at(x, y, z);
+                if !voxel.is_empty() {
+                    let (x, y, z) = ((x + pad_x) as usize, y as usize, (z + pad_z) as usize);
+                    voxels[x + side_len * (y + height_len * z)] = voxel;
+                    voxel_count += 1;
+                }
+            }
+        }
+    }
+    if voxel_count == 0 {
+        return Err(ImportError::Empty);
+    }
+
+    let root_size = side as f32 * voxel_size;
+    let octree = OctreeBuilder::new_rectangular(side, height, side).build(&voxels, root_size);
+
+    // Bounds relative to the bottom center of the octree
+    let half = root_size / 2.0;
+    let min = Vec3::new(pad_x as f32 * voxel_size - half, 0.0, pad_z as f32 * voxel_size - half);
+    Ok(ImportedModel {
+        octree,
+        bounds_min: min,
+        bounds_max: min + size.as_vec3() * voxel_size,
+        voxel_count,
+    })
+}
+
+/// Palette index to voxel mapping
+#[derive(Clone, Debug)]
+pub struct Palette {
+    /// RGB per index; index 0 is unused
+    colors: [[u8; 3]; 256],
+    materials: [u8; 256],
+}
+
+impl Palette {
+    /// Palette with the given colors, every index using `material`
+    pub fn new(colors: [[u8; 3]; 256], material: u8) -> Self {
+        Self { colors, materials: [material; 256] }
+    }
+
+    /// Every index maps to the same voxel, for occupancy-only formats
+    pub fn uniform(color: [u8; 3], material: u8) -> Self {
+        Self::new([color; 256], material)
+    }
+
+    /// Use `material` for palette `index`
+    pub fn with_material(mut self, index: u8, material: u8) -> Self {
+        self.materials[index as usize] = material;
+        self
+    }
+
+    pub fn color(&self, index: u8) -> [u8; 3] {
+        self.colors[index as usize]
+    }
+
+    pub fn material(&self, index: u8) -> u8 {
+        self.materials[index as usize]
+    }
+
+    /// Voxel for a palette index
+    pub fn voxel(&self, index: u8) -> Voxel {
+        let [r, g, b] = self.color(index);
+        Voxel::new(r, g, b, self.material(index))
+    }
+}
+
+/// An imported model, built into an octree
+pub struct ImportedModel {
+    pub octree: Octree,
+    /// Solid bounds relative to the bottom center of the octree
+    pub bounds_min: Vec3,
+    pub bounds_max: Vec3,
+    /// Number of solid voxels imported
+    pub voxel_count: usize,
+}
+
+impl ImportedModel {
+    /// Clutter library metadata for this model.
+    /// The ID is assigned by `ClutterLibrary::add_clutter`.
+    pub fn clutter_data(&self, name: &str) -> ClutterData {
+        ClutterData::new(
+            0,
+            name,
+            self.bounds_min.to_array(),
+            self.bounds_max.to_array(),
+            self.octree.root_size(),
+            self.octree.max_depth(),
+            self.octree.node_count() as u32,
+            self.octree.brick_count() as u32,
+        )
+    }
+
+    /// Tree library entry holding the octree
+    pub fn tree_data(&self, style: TreeStyle, seed: u64) -> TreeData {
+        let mut data = TreeData::from_octree(&self.octree, style, seed);
+        data.bounds_min = self.bounds_min.to_array();
+        data.bounds_max = self.bounds_max.to_array();
+        data
+    }
+
+    /// Add to a clutter library. The library only indexes metadata, so the
+    /// octree is written beside the entry as a `.rkt` file with the same
+    /// stem. Returns the new ID and the octree path.
+    pub fn save_clutter(&self, library: &mut ClutterLibrary, name: &str) -> Result<(u16, std::path::PathBuf), io::Error> {
+        let id = library.add_clutter(&self.clutter_data(name))?;
+        let entry = library.entry(id).expect("clutter entry was just added");
+        let octree_path = library.base_dir().join(entry.path.with_extension(TREE_FILE_EXTENSION));
+        self.tree_data(TreeStyle::default(), 0).save_sync(&octree_path)?;
+        Ok((id, octree_path))
+    }
+
+    /// Instanceable model for a `ModelLibrary`, with its origin at the
+    /// bottom center
+    pub fn voxel_model(&self, name: &str) -> VoxelModel {
+        let half = self.octree.root_size() / 2.0;
+        VoxelModel::new(name, self.octree.clone(), self.bounds_max - self.bounds_min)
+            .with_origin(Vec3::new(half, 0.0, half))
+    }
+}
+
+/// Directory that relative paths inside a model file resolve against
+fn base_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_centered_on_floor() {
+        // 3 x 2 x 1 block: palette 1 on the bottom row, 2 on top
+        let mut grid = IndexedGrid::new(UVec3::new(3, 2, 1)).unwrap();
+        for x in 0..3 {
+            grid.set(x, 0, 0, 1);
+            grid.set(x, 1, 0, 2);
+        }
+        let mut colors = [[0; 3]; 256];
+        colors[1] = [255, 0, 0];
+        colors[2] = [0, 0, 255];
+        let palette = Palette::new(colors, 4).with_material(2, 6);
+
+        let model = grid.to_model(&palette, 0.5).unwrap();
+        assert_eq!(model.voxel_count, 6);
+        assert_eq!(model.octree.root_size(), 2.0);
+        assert_eq!(model.bounds_min, Vec3::new(-1.0, 0.0, -0.5));
+        assert_eq!(model.bounds_max, Vec3::new(0.5, 1.0, 0.0));
+
+        // Octree local space is centered: the floor is at y = -1
+        assert_eq!(model.octree.sample_voxel(Vec3::new(-0.75, -0.75, -0.25)), Voxel::new(255, 0, 0, 4));
+        assert_eq!(model.octree.sample_voxel(Vec3::new(0.25, -0.25, -0.25)), Voxel::new(0, 0, 255, 6));
+        assert!(model.octree.sample_voxel(Vec3::new(0.75, -0.75, -0.25)).is_empty());
+        assert!(model.octree.sample_voxel(Vec3::new(-0.75, 0.25, -0.25)).is_empty());
+        assert!(model.octree.sample_voxel(Vec3::new(-0.75, -0.75, -0.75)).is_empty());
+
+        let clutter = model.clutter_data("crate");
+        assert_eq!(clutter.bounds_size(), [1.5, 1.0, 0.5]);
+        assert!(IndexedGrid::new(UVec3::ONE).unwrap().to_model(&palette, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_grid_size_limits() {
+        assert!(matches!(
+            IndexedGrid::new(UVec3::new(MAX_GRID_SIZE + 1, 1, 1)),
+            Err(ImportError::Malformed(_))
+        ));
+        assert!(matches!(
+            build_model(UVec3::new(1, u32::MAX, 1), 1.0, |_, _, _| Voxel::EMPTY),
+            Err(ImportError::Malformed(_))
+        ));
+        assert_eq!(IndexedGrid::new(UVec3::new(MAX_GRID_SIZE, 1, 2)).unwrap().cells.len(), 4096);
+    }
+}
@@ -0,0 +1,228 @@
+//! Wavefront OBJ reader
+//!
+//! Reads positions, texture coordinates and faces (fan-triangulated), plus
+//! `mtllib` materials: `Kd`, `d`/`Tr` and `map_Kd` textures. Normals,
+//! groups and smoothing are ignored.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use glam::{Vec2, Vec3};
+
+use super::{base_dir, ImportError, MeshMaterial, MeshTexture, MeshTriangle, TriangleMesh};
+
+/// Read an OBJ file and the material libraries and textures it names
+pub fn load_obj(path: &Path) -> Result<TriangleMesh, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    parse_obj(&text, base_dir(path))
+}
+
+/// Parse OBJ text. Material libraries and textures are read relative to
+/// `base_dir`; missing ones are logged and skipped.
+pub fn parse_obj(text: &str, base_dir: &Path) -> Result<TriangleMesh, ImportError> {
+    let mut reader = ObjReader {
+        base_dir,
+        mesh: TriangleMesh::default(),
+        materials_by_name: HashMap::new(),
+        textures_by_path: HashMap::new(),
+        current_material: None,
+    };
+    for (line_number, line) in text.lines().enumerate() {
+        reader.line(line).map_err(|e| match e {
+            ImportError::Malformed(message) => ImportError::Malformed(format!("line {}: {}", line_number + 1, message)),
+            other => other,
+        })?;
+    }
+    Ok(reader.mesh)
+}
+
+struct ObjReader<'a> {
+    base_dir: &'a Path,
+    mesh: TriangleMesh,
+    materials_by_name: HashMap<String, usize>,
+    textures_by_path: HashMap<PathBuf, usize>,
+    current_material: Option<usize>,
+}
+
+impl ObjReader<'_> {
+    fn line(&mut self, line: &str) -> Result<(), ImportError> {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match keyword {
+            "v" => {
+                let [x, y, z] = parse_floats::<3>(rest, 3)?;
+                self.mesh.positions.push(Vec3::new(x, y, z));
+            }
+            "vt" => {
+                // OBJ texture v points up the image
+                let [u, v] = parse_floats::<2>(rest, 1)?;
+                self.mesh.uvs.push(Vec2::new(u, 1.0 - v));
+            }
+            "f" => self.face(rest)?,
+            "usemtl" => self.current_material = Some(self.material_index(rest)),
+            "mtllib" => {
+                let path = self.base_dir.join(rest);
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.material_library(&text, base_dir(&path))?,
+                    Err(e) => log::warn!("Skipping material library {}: {}", path.display(), e),
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn face(&mut self, rest: &str) -> Result<(), ImportError> {
+        let mut corners = Vec::new();
+        for corner in rest.split_whitespace() {
+            let mut parts = corner.split('/');
+            let position = resolve_index(parts.next(), self.mesh.positions.len())?
+                .ok_or_else(|| ImportError::Malformed(format!("face corner '{}' has no position", corner)))?;
+            let uv = resolve_index(parts.next(), self.mesh.uvs.len())?;
+            corners.push((position, uv));
+        }
+        if corners.len() < 3 {
+            return Err(ImportError::Malformed("face with fewer than 3 corners".into()));
+        }
+        for i in 1..corners.len() - 1 {
+            let [a, b, c] = [corners[0], corners[i], corners[i + 1]];
+            let uvs = match (a.1, b.1, c.1) {
+                (Some(ua), Some(ub), Some(uc)) => Some([ua, ub, uc]),
+                _ => None,
+            };
+            self.mesh.triangles.push(MeshTriangle {
+                positions: [a.0, b.0, c.0],
+                uvs,
+                material: self.current_material,
+            });
+        }
+        Ok(())
+    }
+
+    /// Index of a material by name, adding a default one if it is new
+    fn material_index(&mut self, name: &str) -> usize {
+        *self.materials_by_name.entry(name.to_string()).or_insert_with(|| {
+            self.mesh.materials.push(MeshMaterial { name: name.to_string(), ..Default::default() });
+            self.mesh.materials.len() - 1
+        })
+    }
+
+    fn material_library(&mut self, text: &str, dir: &Path) -> Result<(), ImportError> {
+        let mut current = None;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            if keyword == "newmtl" {
+                current = Some(self.material_index(rest));
+                continue;
+            }
+            let Some(index) = current else { continue };
+            match keyword {
+                "Kd" => {
+                    let [r, g, b] = parse_floats::<3>(rest, 3)?;
+                    let material = &mut self.mesh.materials[index];
+                    // A texture replaces the diffuse color rather than tinting it
+                    if material.texture.is_none() {
+                        material.color[..3].copy_from_slice(&[r, g, b]);
+                    }
+                }
+                "d" => self.mesh.materials[index].color[3] = parse_floats::<1>(rest, 1)?[0],
+                "Tr" => self.mesh.materials[index].color[3] = 1.0 - parse_floats::<1>(rest, 1)?[0],
+                "map_Kd" => {
+                    // Options come first; the file name is last
+                    let Some(file) = rest.split_whitespace().last() else { continue };
+                    if let Some(texture) = self.texture(&dir.join(file)) {
+                        let material = &mut self.mesh.materials[index];
+                        material.texture = Some(texture);
+                        material.color[..3].copy_from_slice(&[1.0; 3]);
+                        material.alpha_cutout = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn texture(&mut self, path: &Path) -> Option<usize> {
+        if let Some(index) = self.textures_by_path.get(path) {
+            return Some(*index);
+        }
+        match image::open(path) {
+            Ok(image) => {
+                self.mesh.textures.push(MeshTexture::from_image(image.to_rgba8()));
+                let index = self.mesh.textures.len() - 1;
+                self.textures_by_path.insert(path.to_path_buf(), index);
+                Some(index)
+            }
+            Err(e) => {
+                log::warn!("Skipping texture {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Resolve a 1-based (or negative, relative to the end) OBJ index
+fn resolve_index(part: Option<&str>, count: usize) -> Result<Option<u32>, ImportError> {
+    let Some(part) = part.filter(|p| !p.is_empty()) else { return Ok(None) };
+    let index: i64 = part.parse().map_err(|_| ImportError::Malformed(format!("bad index '{}'", part)))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(ImportError::Malformed(format!("index {} out of range", index)));
+    }
+    Ok(Some(resolved as u32))
+}
+
+/// Parse at least `required` and at most `N` floats; missing ones are 0
+fn parse_floats<const N: usize>(text: &str, required: usize) -> Result<[f32; N], ImportError> {
+    let mut values = [0.0; N];
+    let mut count = 0;
+    for (value, word) in values.iter_mut().zip(text.split_whitespace()) {
+        *value = word.parse().map_err(|_| ImportError::Malformed(format!("bad number '{}'", word)))?;
+        count += 1;
+    }
+    if count < required {
+        return Err(ImportError::Malformed(format!("expected {} numbers, got '{}'", required, text)));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_faces_and_materials() {
+        let temp_dir = TempDir::new().expect("failed to create temp dir");
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("box.mtl"), "newmtl red\nKd 1 0 0\nd 0.5\n").unwrap();
+
+        let obj = "\
+            mtllib box.mtl\n\
+            mtllib missing.mtl\n\
+            v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0 # comment\n\
+            vt 0 0\nvt 1 1\n\
+            f 1 2 3 4\n\
+            usemtl red\n\
+            f -4/1 -3/2 -2/1\n";
+        let mesh = parse_obj(obj, dir).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.uvs[1], Vec2::new(1.0, 0.0));
+        assert_eq!(mesh.triangles.len(), 3);
+        assert_eq!(mesh.triangles[1].positions, [0, 2, 3]);
+        assert!(mesh.triangles[1].material.is_none());
+
+        let textured = mesh.triangles[2];
+        assert_eq!(textured.positions, [0, 1, 2]);
+        assert_eq!(textured.uvs, Some([0, 1, 0]));
+        let material = &mesh.materials[textured.material.unwrap()];
+        assert_eq!((material.name.as_str(), material.color), ("red", [1.0, 0.0, 0.0, 0.5]));
+
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n", dir).is_err());
+        assert!(parse_obj("v 0 0\n", dir).is_err());
+    }
+}
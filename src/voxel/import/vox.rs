@@ -0,0 +1,487 @@
+//! MagicaVoxel `.vox` reader
+//!
+//! Reads models (`SIZE`/`XYZI`), the palette (`RGBA`) and the scene graph
+//! (`nTRN`/`nGRP`/`nSHP`). Material (`MATL`), layer and camera chunks are
+//! skipped. MagicaVoxel is z-up; grids are converted to the engine's y-up
+//! axes with `(x, y, z) -> (x, z, -y)`.
+
+use std::path::Path;
+
+use glam::{IVec3, UVec3};
+
+use super::{ImportError, IndexedGrid, Palette, MAX_GRID_SIZE};
+
+const MAGIC: &[u8; 4] = b"VOX ";
+const MAX_VERSION: i32 = 200;
+/// Scene graphs deeper than this are treated as cyclic
+const MAX_SCENE_DEPTH: usize = 64;
+
+/// One model: voxel coordinates and palette indices in MagicaVoxel axes
+#[derive(Clone, Debug)]
+pub struct VoxModel {
+    pub size: UVec3,
+    /// (x, y, z, palette index)
+    pub voxels: Vec<[u8; 4]>,
+}
+
+/// Rotation as a signed permutation matrix (rows) plus translation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VoxTransform {
+    pub rotation: [[i32; 3]; 3],
+    pub translation: IVec3,
+}
+
+impl VoxTransform {
+    pub const IDENTITY: Self = Self { rotation: [[1, 0, 0], [0, 1, 0], [0, 0, 1]], translation: IVec3::ZERO };
+
+    /// Decode the packed `_r` rotation byte: bits 0-1 and 2-3 give the
+    /// column of the non-zero entry in rows 0 and 1, bits 4-6 the row signs
+    fn from_rotation_byte(r: u8) -> Self {
+        let first = (r & 3) as usize;
+        let second = ((r >> 2) & 3) as usize;
+        let third = 3usize.saturating_sub(first + second).min(2);
+        let sign = |bit: u8| if r & (1 << bit) != 0 { -1 } else { 1 };
+        let mut rotation = [[0; 3]; 3];
+        rotation[0][first] = sign(4);
+        rotation[1][second] = sign(5);
+        rotation[2][third] = sign(6);
+        Self { rotation, translation: IVec3::ZERO }
+    }
+
+    fn rotate(&self, v: IVec3) -> IVec3 {
+        let row = |r: [i32; 3]| r[0] * v.x + r[1] * v.y + r[2] * v.z;
+        IVec3::new(row(self.rotation[0]), row(self.rotation[1]), row(self.rotation[2]))
+    }
+
+    pub fn apply(&self, v: IVec3) -> IVec3 {
+        self.rotate(v) + self.translation
+    }
+
+    /// `self` applied after `inner`
+    fn then(&self, inner: &Self) -> Self {
+        let mut rotation = [[0; 3]; 3];
+        for (i, row) in rotation.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.rotation[i][k] * inner.rotation[k][j]).sum();
+            }
+        }
+        Self { rotation, translation: self.apply(inner.translation) }
+    }
+}
+
+/// A model placed by the scene graph
+#[derive(Clone, Copy, Debug)]
+pub struct VoxInstance {
+    pub model: usize,
+    pub transform: VoxTransform,
+}
+
+/// Scene graph node
+#[derive(Clone, Debug)]
+enum Node {
+    Transform { child: i32, transform: VoxTransform, hidden: bool },
+    Group { children: Vec<i32> },
+    Shape { model: i32 },
+}
+
+/// Parsed `.vox` file
+#[derive(Clone, Debug)]
+pub struct VoxFile {
+    pub models: Vec<VoxModel>,
+    /// RGBA per palette index; index 0 is unused
+    pub palette: [[u8; 4]; 256],
+    /// Visible model placements from the scene graph, or every model at
+    /// the origin for files without one
+    pub instances: Vec<VoxInstance>,
+}
+
+impl VoxFile {
+    /// Read and parse a file
+    pub fn load(path: &Path) -> Result<Self, ImportError> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// Parse file contents
+    pub fn parse(data: &[u8]) -> Result<Self, ImportError> {
+        let mut reader = Reader { data, pos: 0 };
+        if reader.bytes(4)? != MAGIC {
+            return Err(ImportError::BadMagic("MagicaVoxel"));
+        }
+        let version = reader.i32()?;
+        if version > MAX_VERSION {
+            return Err(ImportError::UnsupportedVersion { format: "MagicaVoxel", version });
+        }
+
+        // MAIN holds every other chunk as its children
+        let main = reader.chunk()?;
+        if main.id != *b"MAIN" {
+            return Err(ImportError::Malformed("missing MAIN chunk".into()));
+        }
+        let mut reader = Reader { data: main.children, pos: 0 };
+
+        let mut models = Vec::new();
+        let mut size = None;
+        let mut palette = None;
+        let mut nodes = std::collections::HashMap::new();
+        while !reader.at_end() {
+            let next = reader.chunk()?;
+            let mut chunk = Reader { data: next.content, pos: 0 };
+            match &next.id {
+                b"SIZE" => {
+                    let [x, y, z] = [chunk.i32()?, chunk.i32()?, chunk.i32()?];
+                    if [x, y, z].iter().any(|d| !(1..=256).contains(d)) {
+                        return Err(ImportError::Malformed(format!("model size {}x{}x{}", x, y, z)));
+                    }
+                    size = Some(UVec3::new(x as u32, y as u32, z as u32));
+                }
+                b"XYZI" => {
+                    let size = size.take()
+                        .ok_or_else(|| ImportError::Malformed("XYZI without SIZE".into()))?;
+                    let count = chunk.i32()?.max(0) as usize;
+                    let voxels = chunk.bytes(count * 4)?
+                        .chunks_exact(4)
+                        .map(|v| [v[0], v[1], v[2], v[3]])
+                        .filter(|v| v[3] != 0 && (v[0] as u32) < size.x && (v[1] as u32) < size.y && (v[2] as u32) < size.z)
+                        .collect();
+                    models.push(VoxModel { size, voxels });
+                }
+                b"RGBA" => {
+                    // Entry i is the color of palette index i + 1
+                    let mut colors = [[0u8; 4]; 256];
+                    for (i, rgba) in chunk.bytes(256 * 4)?.chunks_exact(4).take(255).enumerate() {
+                        colors[i + 1] = [rgba[0], rgba[1], rgba[2], rgba[3]];
+                    }
+                    palette = Some(colors);
+                }
+                b"nTRN" => {
+                    let node_id = chunk.i32()?;
+                    let attributes = chunk.dict()?;
+                    let child = chunk.i32()?;
+                    let _reserved = chunk.i32()?;
+                    let _layer = chunk.i32()?;
+                    let frames = chunk.i32()?;
+                    let mut transform = VoxTransform::IDENTITY;
+                    if frames > 0 {
+                        let frame = chunk.dict()?;
+                        if let Some(r) = dict_value(&frame, "_r") {
+                            let r = r.parse().map_err(|_| ImportError::Malformed(format!("rotation '{}'", r)))?;
+                            transform = VoxTransform::from_rotation_byte(r);
+                        }
+                        if let Some(t) = dict_value(&frame, "_t") {
+                            transform.translation = parse_translation(t)?;
+                        }
+                    }
+                    let hidden = dict_value(&attributes, "_hidden") == Some("1");
+                    nodes.insert(node_id, Node::Transform { child, transform, hidden });
+                }
+                b"nGRP" => {
+                    let node_id = chunk.i32()?;
+                    let _attributes = chunk.dict()?;
+                    let count = chunk.i32()?.max(0);
+                    let children = (0..count).map(|_| chunk.i32()).collect::<Result<_, _>>()?;
+                    nodes.insert(node_id, Node::Group { children });
+                }
+                b"nSHP" => {
+                    let node_id = chunk.i32()?;
+                    let _attributes = chunk.dict()?;
+                    let count = chunk.i32()?;
+                    if count < 1 {
+                        return Err(ImportError::Malformed(format!("shape node {} has no model", node_id)));
+                    }
+                    let model = chunk.i32()?;
+                    nodes.insert(node_id, Node::Shape { model });
+                }
+                _ => {}
+            }
+        }
+
+        let palette = palette.unwrap_or_else(|| {
+            log::warn!("vox file has no palette, using a gray ramp");
+            std::array::from_fn(|i| [i as u8, i as u8, i as u8, 255])
+        });
+
+        let mut instances = Vec::new();
+        if nodes.is_empty() {
+            instances.extend((0..models.len()).map(|model| VoxInstance { model, transform: VoxTransform::IDENTITY }));
+        } else {
+            collect_instances(&nodes, 0, VoxTransform::IDENTITY, 0, &mut instances)?;
+        }
+        if let Some(bad) = instances.iter().find(|i| i.model >= models.len()) {
+            return Err(ImportError::Malformed(format!("scene references missing model {}", bad.model)));
+        }
+
+        Ok(Self { models, palette, instances })
+    }
+
+    /// Palette with these colors, every index using `material`
+    pub fn palette(&self, material: u8) -> Palette {
+        Palette::new(self.palette.map(|[r, g, b, _]| [r, g, b]), material)
+    }
+
+    /// One model on its own, without scene transforms
+    pub fn model_grid(&self, model: usize) -> Result<IndexedGrid, ImportError> {
+        let m = &self.models[model];
+        to_grid(m.voxels.iter().map(|v| (IVec3::new(v[0] as i32, v[1] as i32, v[2] as i32), v[3])))
+    }
+
+    /// Every visible instance placed by the scene graph, merged into one
+    /// grid. Later instances overwrite earlier ones where they overlap.
+    /// Fails when the placed instances span more than `MAX_GRID_SIZE`.
+    pub fn scene_grid(&self) -> Result<IndexedGrid, ImportError> {
+        to_grid(self.instances.iter().flat_map(|instance| {
+            let model = &self.models[instance.model];
+            // Translations place the model's center
+            let center = (model.size / 2).as_ivec3();
+            model.voxels.iter().map(move |v| {
+                let local = IVec3::new(v[0] as i32, v[1] as i32, v[2] as i32) - center;
+                (instance.transform.apply(local), v[3])
+            })
+        }))
+    }
+}
+
+/// Walk the scene graph from `node`, collecting visible shapes
+fn collect_instances(
+    nodes: &std::collections::HashMap<i32, Node>,
+    node: i32,
+    transform: VoxTransform,
+    depth: usize,
+    out: &mut Vec<VoxInstance>,
+) -> Result<(), ImportError> {
+    if depth > MAX_SCENE_DEPTH {
+        return Err(ImportError::Malformed("scene graph too deep or cyclic".into()));
+    }
+    match nodes.get(&node) {
+        Some(Node::Transform { child, transform: local, hidden }) => {
+            if !hidden {
+                collect_instances(nodes, *child, transform.then(local), depth + 1, out)?;
+            }
+        }
+        Some(Node::Group { children }) => {
+            for child in children {
+                collect_instances(nodes, *child, transform, depth + 1, out)?;
+            }
+        }
+        Some(Node::Shape { model }) => out.push(VoxInstance { model: *model as usize, transform }),
+        None => return Err(ImportError::Malformed(format!("scene references missing node {}", node))),
+    }
+    Ok(())
+}
+
+/// Build a y-up grid from z-up voxel positions
+fn to_grid(voxels: impl Iterator<Item = (IVec3, u8)> + Clone) -> Result<IndexedGrid, ImportError> {
+    let to_y_up = |p: IVec3| IVec3::new(p.x, p.z, -p.y - 1);
+    let (min, max) = voxels.clone().fold(
+        (IVec3::MAX, IVec3::MIN),
+        |(min, max), (p, _)| (min.min(to_y_up(p)), max.max(to_y_up(p))),
+    );
+    if min.x > max.x {
+        return IndexedGrid::new(UVec3::ZERO);
+    }
+
+    // Spans of far-apart instances can overflow i32
+    let extent = max.as_i64vec3() - min.as_i64vec3() + 1;
+    if extent.max_element() > MAX_GRID_SIZE as i64 {
+        return Err(ImportError::Malformed(format!(
+            "scene spans {}x{}x{} voxels, the limit is {}",
+            extent.x, extent.y, extent.z, MAX_GRID_SIZE
+        )));
+    }
+
+    let mut grid = IndexedGrid::new(extent.as_uvec3())?;
+    for (p, index) in voxels {
+        let cell = (to_y_up(p) - min).as_uvec3();
+        grid.set(cell.x, cell.y, cell.z, index);
+    }
+    Ok(grid)
+}
+
+fn dict_value<'a>(dict: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    dict.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+fn parse_translation(value: &str) -> Result<IVec3, ImportError> {
+    let parts: Vec<i32> = value.split_whitespace()
+        .map(|p| p.parse().map_err(|_| ImportError::Malformed(format!("translation '{}'", value))))
+        .collect::<Result<_, _>>()?;
+    match parts[..] {
+        [x, y, z] => Ok(IVec3::new(x, y, z)),
+        _ => Err(ImportError::Malformed(format!("translation '{}'", value))),
+    }
+}
+
+/// A RIFF-style chunk: id, own content and nested child chunks
+struct Chunk<'a> {
+    id: [u8; 4],
+    content: &'a [u8],
+    children: &'a [u8],
+}
+
+/// Little-endian cursor over chunk data
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ImportError> {
+        let bytes = self.data.get(self.pos..self.pos + len)
+            .ok_or_else(|| ImportError::Malformed("unexpected end of data".into()))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, ImportError> {
+        Ok(i32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, ImportError> {
+        let len = self.i32()?.max(0) as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn dict(&mut self) -> Result<Vec<(String, String)>, ImportError> {
+        let count = self.i32()?.max(0);
+        (0..count).map(|_| Ok((self.string()?, self.string()?))).collect()
+    }
+
+    fn chunk(&mut self) -> Result<Chunk<'a>, ImportError> {
+        let id = self.bytes(4)?.try_into().unwrap();
+        let content_len = self.i32()?.max(0) as usize;
+        let children_len = self.i32()?.max(0) as usize;
+        Ok(Chunk { id, content: self.bytes(content_len)?, children: self.bytes(children_len)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal .vox writer for building test files
+    #[derive(Default)]
+    struct VoxWriter {
+        chunks: Vec<u8>,
+    }
+
+    impl VoxWriter {
+        fn chunk(&mut self, id: &[u8; 4], content: Vec<u8>) {
+            self.chunks.extend_from_slice(id);
+            self.chunks.extend_from_slice(&(content.len() as i32).to_le_bytes());
+            self.chunks.extend_from_slice(&0i32.to_le_bytes());
+            self.chunks.extend(content);
+        }
+
+        fn model(&mut self, size: [i32; 3], voxels: &[[u8; 4]]) {
+            self.chunk(b"SIZE", size.iter().flat_map(|d| d.to_le_bytes()).collect());
+            let mut xyzi = (voxels.len() as i32).to_le_bytes().to_vec();
+            xyzi.extend(voxels.iter().flatten());
+            self.chunk(b"XYZI", xyzi);
+        }
+
+        fn finish(self) -> Vec<u8> {
+            let mut out = b"VOX ".to_vec();
+            out.extend_from_slice(&150i32.to_le_bytes());
+            out.extend_from_slice(b"MAIN");
+            out.extend_from_slice(&0i32.to_le_bytes());
+            out.extend_from_slice(&(self.chunks.len() as i32).to_le_bytes());
+            out.extend(self.chunks);
+            out
+        }
+    }
+
+    fn ints(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn dict(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = ints(&[entries.len() as i32]);
+        for (k, v) in entries {
+            out.extend(ints(&[k.len() as i32]));
+            out.extend(k.as_bytes());
+            out.extend(ints(&[v.len() as i32]));
+            out.extend(v.as_bytes());
+        }
+        out
+    }
+
+    fn transform(id: i32, child: i32, frame: &[(&str, &str)]) -> Vec<u8> {
+        let mut out = ints(&[id]);
+        out.extend(dict(&[]));
+        out.extend(ints(&[child, -1, 0, 1]));
+        out.extend(dict(frame));
+        out
+    }
+
+    #[test]
+    fn test_models_without_scene() {
+        let mut vox = VoxWriter::default();
+        // 2 x 3 x 1 model (z-up): a column along +y
+        vox.model([2, 3, 1], &[[0, 0, 0, 1], [0, 1, 0, 1], [0, 2, 0, 7]]);
+        let mut rgba = vec![0u8; 1024];
+        rgba[6 * 4..6 * 4 + 4].copy_from_slice(&[10, 20, 30, 255]);
+        vox.chunk(b"RGBA", rgba);
+        let file = VoxFile::parse(&vox.finish()).unwrap();
+
+        assert_eq!(file.models.len(), 1);
+        assert_eq!(file.palette[7], [10, 20, 30, 255]);
+        assert_eq!(file.instances.len(), 1);
+
+        // z-up +y becomes y-up -z: the voxel at y = 2 ends up at z = 0
+        let grid = file.model_grid(0).unwrap();
+        assert_eq!(grid.size, UVec3::new(1, 1, 3));
+        assert_eq!(grid.get(0, 0, 0), 7);
+        assert_eq!(grid.get(0, 0, 2), 1);
+        assert_eq!(file.palette(4).voxel(7), crate::voxel::voxel::Voxel::new(10, 20, 30, 4));
+        assert!(VoxFile::parse(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn test_scene_graph_transforms() {
+        let mut vox = VoxWriter::default();
+        // Model 0: 2 voxels along +x; model 1: a single voxel
+        vox.model([2, 1, 1], &[[0, 0, 0, 1], [1, 0, 0, 2]]);
+        vox.model([1, 1, 1], &[[0, 0, 0, 3]]);
+        // root transform -> group -> [turned shape 0, shape 1 at +z, hidden shape 1]
+        vox.chunk(b"nTRN", transform(0, 1, &[]));
+        let mut group = ints(&[1]);
+        group.extend(dict(&[]));
+        group.extend(ints(&[3, 2, 4, 8]));
+        vox.chunk(b"nGRP", group);
+        // Row 0 picks y, row 1 picks x and is negated: x' = y, y' = -x, z' = z
+        let r = (1 | (1 << 5)).to_string();
+        vox.chunk(b"nTRN", transform(2, 3, &[("_r", &r), ("_t", "0 0 0")]));
+        vox.chunk(b"nTRN", transform(4, 5, &[("_t", "5 0 3")]));
+        let mut hidden = ints(&[8]);
+        hidden.extend(dict(&[("_hidden", "1")]));
+        hidden.extend(ints(&[5, -1, 0, 1]));
+        hidden.extend(dict(&[]));
+        vox.chunk(b"nTRN", hidden);
+        for (id, model) in [(3, 0), (5, 1)] {
+            let mut shape = ints(&[id]);
+            shape.extend(dict(&[]));
+            shape.extend(ints(&[1, model]));
+            shape.extend(dict(&[]));
+            vox.chunk(b"nSHP", shape);
+        }
+        let file = VoxFile::parse(&vox.finish()).unwrap();
+        assert_eq!(file.instances.len(), 2);
+        assert_eq!(file.instances[1].transform.translation, IVec3::new(5, 0, 3));
+
+        // Model 0 centered at (1, 0, 0) and turned: x = 0, 1 -> y = 1, 0
+        let t = file.instances[0].transform;
+        assert_eq!(t.apply(IVec3::new(-1, 0, 0)), IVec3::new(0, 1, 0));
+        assert_eq!(t.apply(IVec3::new(0, 0, 0)), IVec3::new(0, 0, 0));
+
+        // z-up (0,1,0)=1, (0,0,0)=2, (5,0,3)=3 -> y-up (0,0,-2), (0,0,-1), (5,3,-1)
+        let grid = file.scene_grid().unwrap();
+        assert_eq!(grid.size, UVec3::new(6, 4, 2));
+        assert_eq!(grid.get(0, 0, 0), 1);
+        assert_eq!(grid.get(0, 0, 1), 2);
+        assert_eq!(grid.get(5, 3, 1), 3);
+        assert_eq!(grid.solid_count(), 3);
+    }
+}
@@ -0,0 +1,561 @@
+//! Triangle mesh voxelization
+//!
+//! Surface cells are every cell a triangle touches, found with a
+//! separating-axis triangle/box test so thin and axis-aligned geometry is
+//! never missed. Each takes its color from the closest point on the closest
+//! touching triangle, sampled from the material's texture. Interior cells
+//! are filled by casting a ray along +x through each row of cell centers and
+//! counting the triangles it crosses.
+
+use std::collections::HashMap;
+
+use glam::{IVec3, UVec3, Vec2, Vec3};
+
+use crate::voxel::chunk::VOXELS_PER_METER;
+use crate::voxel::voxel::Voxel;
+
+use super::{build_model, grid_len, ImportError, ImportedModel, MAX_GRID_SIZE};
+
+/// RGBA8 texture, sRGB encoded
+#[derive(Clone, Debug)]
+pub struct MeshTexture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl MeshTexture {
+    pub fn from_image(image: image::RgbaImage) -> Self {
+        let (width, height) = image.dimensions();
+        Self { width, height, pixels: image.pixels().map(|p| p.0).collect() }
+    }
+
+    /// Nearest texel with repeat wrapping. `uv` (0, 0) is the top left.
+    pub fn sample(&self, uv: Vec2) -> [u8; 4] {
+        let wrap = |t: f32, size: u32| (((t - t.floor()) * size as f32) as u32).min(size - 1);
+        self.pixels[(wrap(uv.x, self.width) + wrap(uv.y, self.height) * self.width) as usize]
+    }
+}
+
+/// Surface appearance of a group of triangles
+#[derive(Clone, Debug)]
+pub struct MeshMaterial {
+    pub name: String,
+    /// Linear RGBA multiplier for the texture, or the color without one
+    pub color: [f32; 4],
+    /// Index into `TriangleMesh::textures`
+    pub texture: Option<usize>,
+    /// Texels with alpha below the voxelizer's cutoff leave holes
+    pub alpha_cutout: bool,
+}
+
+impl Default for MeshMaterial {
+    fn default() -> Self {
+        Self { name: "default".to_string(), color: [0.8, 0.8, 0.8, 1.0], texture: None, alpha_cutout: false }
+    }
+}
+
+/// A triangle of `TriangleMesh`
+#[derive(Clone, Copy, Debug)]
+pub struct MeshTriangle {
+    /// Indices into `TriangleMesh::positions`
+    pub positions: [u32; 3],
+    /// Indices into `TriangleMesh::uvs`
+    pub uvs: Option<[u32; 3]>,
+    /// Index into `TriangleMesh::materials`; `None` uses the default
+    pub material: Option<usize>,
+}
+
+/// Triangle soup as loaded from a mesh file, y up
+#[derive(Clone, Debug, Default)]
+pub struct TriangleMesh {
+    pub positions: Vec<Vec3>,
+    /// Texture coordinates, (0, 0) at the top left of the image
+    pub uvs: Vec<Vec2>,
+    pub triangles: Vec<MeshTriangle>,
+    pub materials: Vec<MeshMaterial>,
+    pub textures: Vec<MeshTexture>,
+}
+
+impl TriangleMesh {
+    /// Bounds of the vertices used by triangles
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.triangles.iter()
+            .flat_map(|t| t.positions)
+            .map(|i| self.positions[i as usize])
+            .fold(None, |bounds, p| match bounds {
+                None => Some((p, p)),
+                Some((min, max)) => Some((p.min(min), p.max(max))),
+            })
+    }
+
+    /// Check every index is in range
+    pub fn validate(&self) -> Result<(), ImportError> {
+        for triangle in &self.triangles {
+            let bad_position = triangle.positions.iter().any(|i| *i as usize >= self.positions.len());
+            let bad_uv = triangle.uvs.is_some_and(|uvs| uvs.iter().any(|i| *i as usize >= self.uvs.len()));
+            let bad_material = triangle.material.is_some_and(|m| m >= self.materials.len());
+            if bad_position || bad_uv || bad_material {
+                return Err(ImportError::Malformed("triangle index out of range".into()));
+            }
+        }
+        if let Some(material) = self.materials.iter()
+            .find(|m| m.texture.is_some_and(|t| t >= self.textures.len()))
+        {
+            return Err(ImportError::Malformed(format!("material '{}' has a missing texture", material.name)));
+        }
+        Ok(())
+    }
+}
+
+/// How the inside of a mesh is filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Surface cells only: a hollow shell
+    Surface,
+    /// Inside where a ray crosses an odd number of triangles. Works on
+    /// meshes with inconsistent winding; nested shells alternate.
+    Parity,
+    /// Inside where front and back crossings don't cancel. Needs consistent
+    /// winding; overlapping parts fill solid.
+    #[default]
+    Winding,
+}
+
+impl FillRule {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "surface" | "none" => Some(Self::Surface),
+            "parity" => Some(Self::Parity),
+            "winding" => Some(Self::Winding),
+            _ => None,
+        }
+    }
+}
+
+/// Voxelization settings
+#[derive(Clone, Debug)]
+pub struct VoxelizeOptions {
+    pub voxels_per_meter: u32,
+    /// Mesh units to meters
+    pub scale: f32,
+    pub fill: FillRule,
+    /// Material ID for mesh materials without an entry in `mesh_materials`
+    pub material: u8,
+    /// Material ID per mesh material name
+    pub mesh_materials: HashMap<String, u8>,
+    /// Alpha below which cutout texels leave holes
+    pub alpha_cutoff: f32,
+}
+
+impl Default for VoxelizeOptions {
+    fn default() -> Self {
+        Self {
+            voxels_per_meter: VOXELS_PER_METER,
+            scale: 1.0,
+            fill: FillRule::default(),
+            material: 4, // stone
+            mesh_materials: HashMap::new(),
+            alpha_cutoff: 0.5,
+        }
+    }
+}
+
+impl VoxelizeOptions {
+    pub fn with_resolution(mut self, voxels_per_meter: u32) -> Self {
+        self.voxels_per_meter = voxels_per_meter;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_fill(mut self, fill: FillRule) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    pub fn with_material(mut self, material: u8) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Use `material` for triangles of the mesh material named `name`
+    pub fn with_mesh_material(mut self, name: impl Into<String>, material: u8) -> Self {
+        self.mesh_materials.insert(name.into(), material);
+        self
+    }
+
+    pub fn voxel_size(&self) -> f32 {
+        1.0 / self.voxels_per_meter as f32
+    }
+}
+
+/// Voxelize a mesh into an octree model resting on its floor
+pub fn voxelize(mesh: &TriangleMesh, options: &VoxelizeOptions) -> Result<ImportedModel, ImportError> {
+    let (size, voxels) = rasterize(mesh, options)?;
+    let (size_x, size_y) = (size.x as usize, size.y as usize);
+    build_model(size, options.voxel_size(), |x, y, z| {
+        voxels[x as usize + size_x * (y as usize + size_y * z as usize)]
+    })
+}
+
+/// Dense voxel grid covering the mesh bounds, x fastest, then y, then z
+fn rasterize(mesh: &TriangleMesh, options: &VoxelizeOptions) -> Result<(UVec3, Vec<Voxel>), ImportError> {
+    mesh.validate()?;
+    let (min, max) = mesh.bounds().ok_or(ImportError::Empty)?;
+
+    // Work in voxel units: cell (x, y, z) spans [x, x + 1) on each axis
+    let to_grid = options.scale * options.voxels_per_meter as f32;
+    let extent = ((max - min) * to_grid).ceil();
+    if extent.max_element() > MAX_GRID_SIZE as f32 {
+        return Err(ImportError::TooLarge { size: extent.max_element() as u32, max: MAX_GRID_SIZE });
+    }
+    let size = extent.as_uvec3().max(UVec3::ONE);
+    let positions: Vec<Vec3> = mesh.positions.iter().map(|p| (*p - min) * to_grid).collect();
+    let (size_x, size_y) = (size.x as usize, size.y as usize);
+    let index = |x: u32, y: u32, z: u32| x as usize + size_x * (y as usize + size_y * z as usize);
+    let default_material = MeshMaterial::default();
+
+    let mut voxels = vec![Voxel::EMPTY; grid_len(size)?];
+    // Squared distance from each surface cell center to its triangle
+    let mut nearest: HashMap<usize, f32> = HashMap::new();
+    // Ray crossings per row of cell centers: (x, +1 entering / -1 leaving)
+    let mut crossings: Vec<Vec<(f32, i32)>> = vec![Vec::new(); (size.y * size.z) as usize];
+
+    for triangle in &mesh.triangles {
+        let corners = triangle.positions.map(|i| positions[i as usize]);
+        let [a, b, c] = corners;
+        let normal = (b - a).cross(c - a);
+        if normal.length_squared() == 0.0 {
+            continue;
+        }
+        let material = triangle.material.map_or(&default_material, |m| &mesh.materials[m]);
+        let material_id = options.mesh_materials.get(&material.name).copied().unwrap_or(options.material);
+
+        // Surface: every cell the triangle touches, including both cells
+        // either side of a face that lies on a cell boundary
+        let cell_range = |lo: Vec3, hi: Vec3| {
+            let clamp = |v: Vec3| v.as_ivec3().max(IVec3::ZERO).as_uvec3().min(size - 1);
+            (clamp((lo - 1.0).ceil()), clamp(hi.floor()))
+        };
+        let (lo, hi) = cell_range(a.min(b).min(c), a.max(b).max(c));
+        for z in lo.z..=hi.z {
+            for y in lo.y..=hi.y {
+                for x in lo.x..=hi.x {
+                    let center = UVec3::new(x, y, z).as_vec3() + Vec3::splat(0.5);
+                    if !triangle_box_overlap(center, 0.5, corners) {
+                        continue;
+                    }
+                    let weights = closest_point_weights(center, corners);
+                    let distance = (a * weights.x + b * weights.y + c * weights.z - center).length_squared();
+                    let cell = index(x, y, z);
+                    if nearest.get(&cell).is_some_and(|d| *d <= distance) {
+                        continue;
+                    }
+                    if let Some(voxel) = surface_voxel(mesh, triangle, material, material_id, weights, options.alpha_cutoff) {
+                        voxels[cell] = voxel;
+                        nearest.insert(cell, distance);
+                    }
+                }
+            }
+        }
+
+        // Interior: where the triangle crosses rows of cell centers. The
+        // entering side of a ray along +x faces -x.
+        if options.fill == FillRule::Surface {
+            continue;
+        }
+        let direction = if normal.x < 0.0 { 1 } else { -1 };
+        let row_range = |lo: f32, hi: f32, size: u32| {
+            let first = (lo - 0.5).ceil().max(0.0) as u32;
+            let last = ((hi - 0.5).floor() as i64).min(size as i64 - 1);
+            first as i64..=last
+        };
+        let (min, max) = (a.min(b).min(c), a.max(b).max(c));
+        for z in row_range(min.z, max.z, size.z) {
+            for y in row_range(min.y, max.y, size.y) {
+                let point = Vec2::new(y as f32 + 0.5, z as f32 + 0.5);
+                if let Some(weights) = projected_weights(point, corners.map(|p| Vec2::new(p.y, p.z))) {
+                    let x = a.x * weights.x + b.x * weights.y + c.x * weights.z;
+                    crossings[(y + size.y as i64 * z) as usize].push((x, direction));
+                }
+            }
+        }
+    }
+
+    if options.fill != FillRule::Surface {
+        for z in 0..size.z {
+            for y in 0..size.y {
+                let row = &mut crossings[(y + size.y * z) as usize];
+                if row.is_empty() {
+                    continue;
+                }
+                row.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                // Interior cells copy the last surface cell before them
+                let mut fill_voxel = (0..size.x).map(|x| voxels[index(x, y, z)]).find(|v| !v.is_empty());
+                let mut next = 0;
+                let (mut count, mut winding) = (0, 0);
+                for x in 0..size.x {
+                    let center = x as f32 + 0.5;
+                    while next < row.len() && row[next].0 < center {
+                        count += 1;
+                        winding += row[next].1;
+                        next += 1;
+                    }
+                    let cell = index(x, y, z);
+                    if !voxels[cell].is_empty() {
+                        fill_voxel = Some(voxels[cell]);
+                        continue;
+                    }
+                    let inside = match options.fill {
+                        FillRule::Parity => count % 2 == 1,
+                        _ => winding != 0,
+                    };
+                    if inside && let Some(voxel) = fill_voxel {
+                        voxels[cell] = voxel;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok((size, voxels))
+}
+
+/// Voxel for a point on a triangle, or `None` where a cutout texel is clear
+fn surface_voxel(
+    mesh: &TriangleMesh,
+    triangle: &MeshTriangle,
+    material: &MeshMaterial,
+    material_id: u8,
+    weights: Vec3,
+    alpha_cutoff: f32,
+) -> Option<Voxel> {
+    let mut color = material.color;
+    if let (Some(texture), Some(uvs)) = (material.texture, triangle.uvs) {
+        let [ua, ub, uc] = uvs.map(|i| mesh.uvs[i as usize]);
+        let texel = mesh.textures[texture].sample(ua * weights.x + ub * weights.y + uc * weights.z);
+        for (channel, value) in color.iter_mut().zip(texel).take(3) {
+            *channel *= srgb_to_linear(value as f32 / 255.0);
+        }
+        color[3] *= texel[3] as f32 / 255.0;
+    }
+    if material.alpha_cutout && color[3] < alpha_cutoff {
+        return None;
+    }
+    let [r, g, b] = [color[0], color[1], color[2]].map(|c| (linear_to_srgb(c) * 255.0).round() as u8);
+    Some(Voxel::new(r, g, b, material_id))
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Separating axis test between a triangle and an axis-aligned cube.
+/// Touching counts as overlapping.
+fn triangle_box_overlap(center: Vec3, half: f32, triangle: [Vec3; 3]) -> bool {
+    let v = triangle.map(|p| p - center);
+    let separated = |axis: Vec3| {
+        let projected = v.map(|p| p.dot(axis));
+        let radius = half * axis.abs().element_sum();
+        projected.iter().copied().fold(f32::MAX, f32::min) > radius
+            || projected.iter().copied().fold(f32::MIN, f32::max) < -radius
+    };
+
+    // Box faces
+    if [Vec3::X, Vec3::Y, Vec3::Z].into_iter().any(separated) {
+        return false;
+    }
+    // Triangle plane
+    let edges = [v[1] - v[0], v[2] - v[1], v[0] - v[2]];
+    if separated(edges[0].cross(edges[1])) {
+        return false;
+    }
+    // Box axes crossed with triangle edges
+    !edges.iter().any(|edge| {
+        [Vec3::X, Vec3::Y, Vec3::Z].into_iter().any(|axis| {
+            let cross = axis.cross(*edge);
+            cross != Vec3::ZERO && separated(cross)
+        })
+    })
+}
+
+/// Barycentric weights of the point on a triangle closest to `p`
+fn closest_point_weights(p: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    let (ab, ac, ap) = (b - a, c - a, p - a);
+    let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return Vec3::X;
+    }
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return Vec3::Y;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        let t = d1 / (d1 - d3);
+        return Vec3::new(1.0 - t, t, 0.0);
+    }
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return Vec3::Z;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        let t = d2 / (d2 - d6);
+        return Vec3::new(1.0 - t, 0.0, t);
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return Vec3::new(0.0, 1.0 - t, t);
+    }
+    let denom = 1.0 / (va + vb + vc);
+    let (v, w) = (vb * denom, vc * denom);
+    Vec3::new(1.0 - v - w, v, w)
+}
+
+/// Barycentric weights of `p` inside a 2D triangle, or `None` outside.
+///
+/// Points exactly on an edge count for only one of the two triangles that
+/// share it, so rays through shared edges cross the surface once.
+fn projected_weights(p: Vec2, triangle: [Vec2; 3]) -> Option<Vec3> {
+    let [a, b, c] = triangle;
+    let area = (b - a).perp_dot(c - a);
+    if area == 0.0 {
+        return None;
+    }
+    // Orient counter-clockwise so the tie rule sees each shared edge once
+    // from each side
+    let (corners, flipped) = if area < 0.0 { ([a, c, b], true) } else { ([a, b, c], false) };
+
+    let mut weights = [0.0; 3];
+    for (i, weight) in weights.iter_mut().enumerate() {
+        let (from, to) = (corners[(i + 1) % 3], corners[(i + 2) % 3]);
+        let edge = to - from;
+        let value = edge.perp_dot(p - from);
+        let owns_edge = edge.y > 0.0 || (edge.y == 0.0 && edge.x < 0.0);
+        if value < 0.0 || (value == 0.0 && !owns_edge) {
+            return None;
+        }
+        *weight = value;
+    }
+    let total = weights[0] + weights[1] + weights[2];
+    let [wa, wb, wc] = weights.map(|w| w / total);
+    Some(if flipped { Vec3::new(wa, wc, wb) } else { Vec3::new(wa, wb, wc) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Axis-aligned box with outward-facing triangles
+    fn add_box(mesh: &mut TriangleMesh, min: Vec3, max: Vec3) {
+        let base = mesh.positions.len() as u32;
+        mesh.positions.extend((0..8).map(|i| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        }));
+        let faces = [[0, 4, 6, 2], [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3], [0, 2, 3, 1], [4, 5, 7, 6]];
+        for [a, b, c, d] in faces {
+            for positions in [[a, b, c], [a, c, d]] {
+                mesh.triangles.push(MeshTriangle { positions: positions.map(|i| base + i), uvs: None, material: None });
+            }
+        }
+    }
+
+    fn solid_count(voxels: &[Voxel]) -> usize {
+        voxels.iter().filter(|v| !v.is_empty()).count()
+    }
+
+    #[test]
+    fn test_box_surface_and_fill() {
+        let mut mesh = TriangleMesh::default();
+        add_box(&mut mesh, Vec3::ZERO, Vec3::ONE);
+        let options = VoxelizeOptions::default().with_resolution(8);
+
+        let (size, voxels) = rasterize(&mesh, &options.clone().with_fill(FillRule::Surface)).unwrap();
+        assert_eq!(size, UVec3::splat(8));
+        assert_eq!(solid_count(&voxels), 8 * 8 * 8 - 6 * 6 * 6);
+
+        for fill in [FillRule::Parity, FillRule::Winding] {
+            let (_, voxels) = rasterize(&mesh, &options.clone().with_fill(fill)).unwrap();
+            assert_eq!(solid_count(&voxels), 8 * 8 * 8, "{:?}", fill);
+        }
+
+        let model = voxelize(&mesh, &options).unwrap();
+        assert_eq!(model.voxel_count, 512);
+        assert_eq!(model.bounds_max - model.bounds_min, Vec3::ONE);
+        assert_eq!(model.octree.sample_voxel(Vec3::ZERO), Voxel::new(231, 231, 231, 4));
+    }
+
+    #[test]
+    fn test_nested_shells() {
+        // A box inside a box: parity leaves a hollow, winding fills it
+        let mut mesh = TriangleMesh::default();
+        add_box(&mut mesh, Vec3::ZERO, Vec3::ONE);
+        add_box(&mut mesh, Vec3::splat(0.25), Vec3::splat(0.75));
+        let options = VoxelizeOptions::default().with_resolution(8);
+
+        let (_, voxels) = rasterize(&mesh, &options.clone().with_fill(FillRule::Parity)).unwrap();
+        assert_eq!(solid_count(&voxels), 512 - 8);
+        let (_, voxels) = rasterize(&mesh, &options.with_fill(FillRule::Winding)).unwrap();
+        assert_eq!(solid_count(&voxels), 512);
+    }
+
+    #[test]
+    fn test_texture_colors_and_cutout() {
+        // A 2 x 1 quad in the xy plane showing a red | clear | blue texture
+        let mut mesh = TriangleMesh {
+            positions: vec![Vec3::ZERO, Vec3::new(3.0, 0.0, 0.0), Vec3::new(3.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0)],
+            uvs: vec![Vec2::new(0.0, 1.0), Vec2::new(1.0, 1.0), Vec2::new(1.0, 0.0), Vec2::new(0.0, 0.0)],
+            textures: vec![MeshTexture {
+                width: 3,
+                height: 1,
+                pixels: vec![[255, 0, 0, 255], [0, 255, 0, 0], [0, 0, 255, 255]],
+            }],
+            materials: vec![MeshMaterial {
+                name: "sign".to_string(),
+                color: [1.0; 4],
+                texture: Some(0),
+                alpha_cutout: true,
+            }],
+            triangles: Vec::new(),
+        };
+        for [a, b, c] in [[0, 1, 2], [0, 2, 3]] {
+            mesh.triangles.push(MeshTriangle { positions: [a, b, c], uvs: Some([a, b, c]), material: Some(0) });
+        }
+        let options = VoxelizeOptions::default().with_resolution(4).with_mesh_material("sign", 9);
+        let (size, voxels) = rasterize(&mesh, &options).unwrap();
+        assert_eq!(size, UVec3::new(12, 4, 1));
+
+        let at = |x: u32, y: u32| voxels[(x + size.x * y) as usize];
+        assert_eq!(at(1, 2), Voxel::new(255, 0, 0, 9));
+        assert!(at(5, 2).is_empty());
+        assert_eq!(at(10, 1), Voxel::new(0, 0, 255, 9));
+    }
+
+    #[test]
+    fn test_triangle_box_overlap() {
+        let triangle = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0), Vec3::new(0.0, 2.0, 0.0)];
+        assert!(triangle_box_overlap(Vec3::new(0.5, 0.5, 0.5), 0.5, triangle));
+        // Touching the plane from above
+        assert!(triangle_box_overlap(Vec3::new(0.5, 0.5, 0.5), 0.5, triangle.map(|p| p + Vec3::Z)));
+        // Beyond the hypotenuse
+        assert!(!triangle_box_overlap(Vec3::new(1.6, 1.6, 0.0), 0.5, triangle));
+    }
+}
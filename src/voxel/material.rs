@@ -0,0 +1,571 @@
+//! Material registry: named voxel materials and their PBR properties.
+//!
+//! `Voxel::material_id` is a `u8` index into this table. Generators look
+//! materials up by name (`material_id("rock")`) instead of hard-coding IDs,
+//! and the trace pipeline uploads `MaterialRegistry::gpu_table` as the
+//! `materials` storage buffer read by svo_trace.wgsl.
+//!
+//! Registries are loaded from JSON:
+//!
+//! ```json
+//! { "materials": [
+//!     { "id": 20, "name": "rock", "albedo": [0.35, 0.33, 0.32],
+//!       "roughness": 0.95, "flags": ["sdf_normal"] }
+//! ] }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::terrain::biome::Biome;
+
+/// Number of material slots addressable by `Voxel::material_id`
+pub const MAX_MATERIALS: usize = 256;
+
+/// Behaviour flags the renderer keys off instead of specific material IDs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialFlag {
+    /// Terrain surface: color comes from albedo with procedural variation
+    /// (the voxel color field stores the height gradient)
+    Terrain,
+    /// Tree bark: color field stores a radial normal, sways with wind
+    Bark,
+    /// Foliage: color field stores a cloud-center normal, flutters with wind
+    Foliage,
+    /// Color field stores an SDF gradient normal (rocks)
+    SdfNormal,
+    /// Water volume
+    Water,
+    /// Procedural grass blades drawn over terrain with a grass mask
+    GrassBlade,
+}
+
+impl MaterialFlag {
+    /// Bit in `GpuMaterial::flags`. Must match `MAT_FLAG_*` in svo_trace.wgsl.
+    pub fn bit(self) -> u32 {
+        match self {
+            MaterialFlag::Terrain => 1 << 0,
+            MaterialFlag::Bark => 1 << 1,
+            MaterialFlag::Foliage => 1 << 2,
+            MaterialFlag::SdfNormal => 1 << 3,
+            MaterialFlag::Water => 1 << 4,
+            MaterialFlag::GrassBlade => 1 << 5,
+        }
+    }
+}
+
+/// A named voxel material
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Material {
+    /// Value stored in `Voxel::material_id` (0 is reserved for empty voxels)
+    pub id: u8,
+    /// Unique lookup name (e.g., "rock", "beach_sand")
+    pub name: String,
+    /// Linear base color
+    #[serde(default = "default_albedo")]
+    pub albedo: [f32; 3],
+    #[serde(default = "default_roughness")]
+    pub roughness: f32,
+    #[serde(default)]
+    pub metallic: f32,
+    /// Emitted radiance (linear RGB, may exceed 1.0)
+    #[serde(default)]
+    pub emissive: [f32; 3],
+    /// Fraction of light transmitted through thin geometry (0 = opaque)
+    #[serde(default)]
+    pub transparency: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<MaterialFlag>,
+}
+
+fn default_albedo() -> [f32; 3] {
+    [0.5, 0.5, 0.5]
+}
+
+fn default_roughness() -> f32 {
+    0.5
+}
+
+impl Material {
+    /// Create a grey, half-rough material
+    pub fn new(id: u8, name: impl Into<String>) -> Self {
+        Self {
+            id,
+            name: name.into(),
+            albedo: default_albedo(),
+            roughness: default_roughness(),
+            metallic: 0.0,
+            emissive: [0.0; 3],
+            transparency: 0.0,
+            flags: Vec::new(),
+        }
+    }
+
+    /// Set albedo
+    pub fn with_albedo(mut self, r: f32, g: f32, b: f32) -> Self {
+        self.albedo = [r, g, b];
+        self
+    }
+
+    /// Set roughness and metallic
+    pub fn with_pbr(mut self, roughness: f32, metallic: f32) -> Self {
+        self.roughness = roughness;
+        self.metallic = metallic;
+        self
+    }
+
+    /// Set emissive radiance
+    pub fn with_emissive(mut self, r: f32, g: f32, b: f32) -> Self {
+        self.emissive = [r, g, b];
+        self
+    }
+
+    /// Set transparency
+    pub fn with_transparency(mut self, transparency: f32) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Add a behaviour flag
+    pub fn with_flag(mut self, flag: MaterialFlag) -> Self {
+        if !self.flags.contains(&flag) {
+            self.flags.push(flag);
+        }
+        self
+    }
+
+    /// Check for a behaviour flag
+    pub fn has_flag(&self, flag: MaterialFlag) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    /// Flags packed as GPU bits
+    pub fn flag_bits(&self) -> u32 {
+        self.flags.iter().fold(0, |bits, f| bits | f.bit())
+    }
+
+    /// GPU representation of this material
+    pub fn to_gpu(&self) -> GpuMaterial {
+        GpuMaterial {
+            albedo: self.albedo,
+            roughness: self.roughness,
+            emissive: self.emissive,
+            metallic: self.metallic,
+            transparency: self.transparency,
+            flags: self.flag_bits(),
+            _pad: [0; 2],
+        }
+    }
+
+    fn check(&self) -> Result<(), MaterialError> {
+        if self.id == 0 {
+            return Err(MaterialError::ReservedId(self.name.clone()));
+        }
+        if self.name.is_empty() {
+            return Err(MaterialError::EmptyName(self.id));
+        }
+        let unit = |v: f32| (0.0..=1.0).contains(&v);
+        let invalid = |field| Err(MaterialError::InvalidValue { name: self.name.clone(), field });
+        if !self.albedo.iter().all(|&c| unit(c)) {
+            return invalid("albedo");
+        }
+        if !unit(self.roughness) {
+            return invalid("roughness");
+        }
+        if !unit(self.metallic) {
+            return invalid("metallic");
+        }
+        if !unit(self.transparency) {
+            return invalid("transparency");
+        }
+        if !self.emissive.iter().all(|&c| c.is_finite() && c >= 0.0) {
+            return invalid("emissive");
+        }
+        Ok(())
+    }
+}
+
+/// GPU-side material data (48 bytes, 16-byte aligned).
+/// Must match `GpuMaterial` in svo_trace.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct GpuMaterial {
+    pub albedo: [f32; 3],
+    pub roughness: f32,
+    // -- 16 bytes --
+    pub emissive: [f32; 3],
+    pub metallic: f32,
+    // -- 16 bytes --
+    pub transparency: f32,
+    pub flags: u32,
+    pub _pad: [u32; 2],
+    // -- 16 bytes --
+    // Total: 48 bytes
+}
+
+impl GpuMaterial {
+    /// Shading used for IDs with no registered material
+    pub const UNREGISTERED: Self = Self {
+        albedo: [0.5, 0.5, 0.5],
+        roughness: 0.5,
+        emissive: [0.0; 3],
+        metallic: 0.0,
+        transparency: 0.0,
+        flags: 0,
+        _pad: [0; 2],
+    };
+}
+
+/// Errors from building or loading a material registry
+#[derive(Debug, Error)]
+pub enum MaterialError {
+    #[error("material '{0}' uses id 0, which is reserved for empty voxels")]
+    ReservedId(String),
+
+    #[error("material id {id} is used by both '{existing}' and '{name}'")]
+    DuplicateId { id: u8, existing: String, name: String },
+
+    #[error("material name '{0}' is registered twice")]
+    DuplicateName(String),
+
+    #[error("material {0} has an empty name")]
+    EmptyName(u8),
+
+    #[error("material '{name}' has an out-of-range {field}")]
+    InvalidValue { name: String, field: &'static str },
+
+    #[error("material registry has no '{0}' material, which the world generators or renderer need")]
+    MissingRequired(&'static str),
+
+    #[error("a material registry is already in use")]
+    AlreadyInstalled,
+
+    #[error("failed to parse material file: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Materials the world generators and grass renderer look up by name
+/// (`material_id`), besides the biome materials
+pub const GENERATOR_MATERIALS: [&str; 5] = ["rock", "bark", "leaves", "water", "grass_blade"];
+
+/// Every material name looked up at runtime: the `GENERATOR_MATERIALS` and
+/// each biome's surface and underground material
+pub fn required_materials() -> impl Iterator<Item = &'static str> {
+    let biomes = Biome::ALL.into_iter().flat_map(|b| [b.surface_material(), b.underground_material()]);
+    GENERATOR_MATERIALS.into_iter().chain(biomes)
+}
+
+/// On-disk layout of a material file
+#[derive(Serialize, Deserialize)]
+struct MaterialFile {
+    materials: Vec<Material>,
+}
+
+/// Table of materials indexed by `Voxel::material_id`
+#[derive(Clone, Debug)]
+pub struct MaterialRegistry {
+    slots: Vec<Option<Material>>,
+    by_name: HashMap<String, u8>,
+}
+
+impl MaterialRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            slots: vec![None; MAX_MATERIALS],
+            by_name: HashMap::new(),
+        }
+    }
+
+    /// The engine's built-in materials
+    pub fn builtin() -> Self {
+        use MaterialFlag::*;
+
+        let materials = [
+            // Core material types
+            Material::new(1, "sand").with_albedo(0.71, 0.63, 0.55).with_pbr(0.9, 0.0),
+            Material::new(2, "bark").with_albedo(0.35, 0.25, 0.15).with_pbr(0.85, 0.0).with_flag(Bark),
+            Material::new(3, "leaves").with_albedo(0.20, 0.35, 0.08).with_pbr(0.55, 0.0)
+                .with_transparency(0.8).with_flag(Foliage),
+            Material::new(4, "stone").with_albedo(0.35, 0.33, 0.32).with_pbr(0.95, 0.0),
+            Material::new(5, "snow").with_albedo(0.94, 0.97, 1.00).with_pbr(0.8, 0.1),
+            Material::new(6, "dirt").with_albedo(0.51, 0.39, 0.27).with_pbr(0.9, 0.0),
+            // Biome terrain surfaces
+            Material::new(7, "beach_sand").with_albedo(0.93, 0.84, 0.69).with_pbr(0.9, 0.0).with_flag(Terrain),
+            Material::new(8, "desert_sand").with_albedo(0.93, 0.79, 0.69).with_pbr(0.9, 0.0).with_flag(Terrain),
+            Material::new(9, "grassland").with_albedo(0.39, 0.71, 0.31).with_pbr(0.85, 0.0).with_flag(Terrain),
+            Material::new(10, "forest_floor").with_albedo(0.20, 0.47, 0.16).with_pbr(0.85, 0.0).with_flag(Terrain),
+            Material::new(11, "taiga").with_albedo(0.31, 0.39, 0.24).with_pbr(0.85, 0.0).with_flag(Terrain),
+            Material::new(12, "tundra").with_albedo(0.63, 0.71, 0.67).with_pbr(0.9, 0.0).with_flag(Terrain),
+            Material::new(13, "mountain_rock").with_albedo(0.47, 0.47, 0.47).with_pbr(0.95, 0.0).with_flag(Terrain),
+            Material::new(14, "snow_cover").with_albedo(0.94, 0.97, 1.00).with_pbr(0.8, 0.1).with_flag(Terrain),
+            Material::new(15, "ocean_floor").with_albedo(0.12, 0.31, 0.59).with_pbr(0.95, 0.0).with_flag(Terrain),
+            // Objects
+            Material::new(16, "grass_blade").with_albedo(0.30, 0.55, 0.20).with_pbr(0.9, 0.0)
+                .with_transparency(0.3).with_flag(GrassBlade),
+            Material::new(20, "rock").with_albedo(0.35, 0.33, 0.32).with_pbr(0.95, 0.0).with_flag(SdfNormal),
+            Material::new(200, "water").with_albedo(0.10, 0.79, 0.87).with_pbr(0.05, 0.0)
+                .with_transparency(0.9).with_flag(Water),
+        ];
+
+        Self::from_materials(materials).expect("built-in materials must be valid")
+    }
+
+    /// Build a registry from a list of materials
+    pub fn from_materials(materials: impl IntoIterator<Item = Material>) -> Result<Self, MaterialError> {
+        let mut registry = Self::new();
+        for material in materials {
+            registry.register(material)?;
+        }
+        Ok(registry)
+    }
+
+    /// Parse a registry from JSON
+    pub fn from_json(json: &str) -> Result<Self, MaterialError> {
+        let file: MaterialFile = serde_json::from_str(json)?;
+        Self::from_materials(file.materials)
+    }
+
+    /// Serialize to JSON, ordered by ID
+    pub fn to_json(&self) -> String {
+        let file = MaterialFile { materials: self.iter().cloned().collect() };
+        serde_json::to_string_pretty(&file).expect("material registry serializes")
+    }
+
+    /// Load from a JSON file. The file must define every `required_materials` name.
+    pub fn load(path: &Path) -> Result<Self, MaterialError> {
+        let registry = Self::from_json(&std::fs::read_to_string(path)?)?;
+        registry.check_required()?;
+        Ok(registry)
+    }
+
+    /// Save to a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_json())
+    }
+
+    /// Add a material. Rejects ID 0, out-of-range properties and any ID or
+    /// name that is already taken.
+    pub fn register(&mut self, material: Material) -> Result<u8, MaterialError> {
+        material.check()?;
+        if let Some(existing) = &self.slots[material.id as usize] {
+            return Err(MaterialError::DuplicateId {
+                id: material.id,
+                existing: existing.name.clone(),
+                name: material.name,
+            });
+        }
+        if self.by_name.contains_key(&material.name) {
+            return Err(MaterialError::DuplicateName(material.name));
+        }
+
+        let id = material.id;
+        self.by_name.insert(material.name.clone(), id);
+        self.slots[id as usize] = Some(material);
+        Ok(id)
+    }
+
+    /// Get a material by ID
+    pub fn get(&self, id: u8) -> Option<&Material> {
+        self.slots[id as usize].as_ref()
+    }
+
+    /// Look up a material ID by name
+    pub fn id(&self, name: &str) -> Option<u8> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Look up a material by name
+    pub fn by_name(&self, name: &str) -> Option<&Material> {
+        self.id(name).and_then(|id| self.get(id))
+    }
+
+    /// Number of registered materials
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    /// Check if no materials are registered
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    /// Iterate registered materials in ID order
+    pub fn iter(&self) -> impl Iterator<Item = &Material> {
+        self.slots.iter().flatten()
+    }
+
+    /// Check that every material in `required_materials` is registered
+    pub fn check_required(&self) -> Result<(), MaterialError> {
+        match required_materials().find(|name| self.id(name).is_none()) {
+            Some(name) => Err(MaterialError::MissingRequired(name)),
+            None => Ok(()),
+        }
+    }
+
+    /// Full GPU table, one entry per possible material ID
+    pub fn gpu_table(&self) -> Vec<GpuMaterial> {
+        self.slots
+            .iter()
+            .map(|slot| slot.as_ref().map_or(GpuMaterial::UNREGISTERED, Material::to_gpu))
+            .collect()
+    }
+}
+
+impl Default for MaterialRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+static REGISTRY: OnceLock<MaterialRegistry> = OnceLock::new();
+
+/// Install the process-wide registry used by generators and the renderer.
+///
+/// Must run before the first lookup. Fails if the registry lacks one of the
+/// `required_materials` or if one is already in use. Without this, the
+/// built-in materials are used.
+pub fn install(registry: MaterialRegistry) -> Result<(), MaterialError> {
+    registry.check_required()?;
+    REGISTRY.set(registry).map_err(|_| MaterialError::AlreadyInstalled)
+}
+
+/// The process-wide material registry
+pub fn registry() -> &'static MaterialRegistry {
+    REGISTRY.get_or_init(MaterialRegistry::builtin)
+}
+
+/// Material ID for a name in the process-wide registry.
+///
+/// Panics if the material is not registered. `load` and `install` reject
+/// registries without the `required_materials`, so this only fires for
+/// other names (e.g. ones given on a command line).
+pub fn material_id(name: &str) -> u8 {
+    registry()
+        .id(name)
+        .unwrap_or_else(|| panic!("material '{}' is not in the material registry", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_lookup() {
+        let registry = MaterialRegistry::builtin();
+        assert_eq!(registry.id("rock"), Some(20));
+        assert_eq!(registry.id("water"), Some(200));
+        assert!(registry.by_name("leaves").unwrap().has_flag(MaterialFlag::Foliage));
+        assert_eq!(registry.id("unobtainium"), None);
+        assert!(registry.get(0).is_none());
+    }
+
+    #[test]
+    fn test_rejects_collisions() {
+        let mut registry = MaterialRegistry::new();
+        registry.register(Material::new(5, "a")).unwrap();
+        assert!(matches!(registry.register(Material::new(5, "b")), Err(MaterialError::DuplicateId { id: 5, .. })));
+        assert!(matches!(registry.register(Material::new(6, "a")), Err(MaterialError::DuplicateName(_))));
+        assert!(matches!(registry.register(Material::new(0, "c")), Err(MaterialError::ReservedId(_))));
+        assert!(matches!(
+            registry.register(Material::new(7, "d").with_pbr(1.5, 0.0)),
+            Err(MaterialError::InvalidValue { field: "roughness", .. })
+        ));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let registry = MaterialRegistry::builtin();
+        let loaded = MaterialRegistry::from_json(&registry.to_json()).unwrap();
+        assert_eq!(loaded.len(), registry.len());
+        assert!(loaded.iter().zip(registry.iter()).all(|(a, b)| a == b));
+
+        // Defaults fill in omitted fields; IDs past 255 don't parse
+        let minimal = MaterialRegistry::from_json(r#"{"materials": [{"id": 9, "name": "moss", "flags": ["terrain"]}]}"#).unwrap();
+        assert_eq!(minimal.get(9).unwrap().roughness, 0.5);
+        assert_eq!(minimal.get(9).unwrap().flag_bits(), MaterialFlag::Terrain.bit());
+        assert!(MaterialRegistry::from_json(r#"{"materials": [{"id": 256, "name": "x"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_requires_generator_materials() {
+        let dir = std::env::temp_dir().join("rktri_test_materials_required");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("materials.json");
+
+        let mut partial = MaterialRegistry::new();
+        for (id, name) in [(2, "bark"), (3, "leaves"), (20, "rock")] {
+            partial.register(Material::new(id, name)).unwrap();
+        }
+        partial.save(&path).unwrap();
+        assert!(matches!(MaterialRegistry::load(&path), Err(MaterialError::MissingRequired("water"))));
+        assert!(matches!(install(partial), Err(MaterialError::MissingRequired("water"))));
+
+        // Biome materials are required too
+        let without_taiga = MaterialRegistry::from_materials(
+            MaterialRegistry::builtin().iter().filter(|m| m.name != "taiga").cloned(),
+        ).unwrap();
+        assert!(matches!(without_taiga.check_required(), Err(MaterialError::MissingRequired("taiga"))));
+        assert!(required_materials().any(|name| name == "stone"));
+
+        MaterialRegistry::builtin().save(&path).unwrap();
+        assert!(MaterialRegistry::load(&path).is_ok());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_gpu_table() {
+        assert_eq!(std::mem::size_of::<GpuMaterial>(), 48);
+        let table = MaterialRegistry::builtin().gpu_table();
+        assert_eq!(table.len(), MAX_MATERIALS);
+        assert_eq!(table[3].transparency, 0.8);
+        assert_eq!(table[7].flags, MaterialFlag::Terrain.bit());
+        assert_eq!(table[100].roughness, GpuMaterial::UNREGISTERED.roughness);
+    }
+
+    #[test]
+    fn test_shader_flags_match() {
+        // svo_trace.wgsl keys grass and rock handling off `has_material_flag`
+        let shader = include_str!("../../shaders/svo_trace.wgsl");
+        for (flag, name) in [
+            (MaterialFlag::Terrain, "TERRAIN"),
+            (MaterialFlag::Bark, "BARK"),
+            (MaterialFlag::Foliage, "FOLIAGE"),
+            (MaterialFlag::SdfNormal, "SDF_NORMAL"),
+            (MaterialFlag::GrassBlade, "GRASS_BLADE"),
+        ] {
+            let decl = format!("const MAT_FLAG_{}: u32 = {}u;", name, flag.bit());
+            assert!(shader.contains(&decl), "missing `{}`", decl);
+        }
+
+        // Moving grass and rock to other IDs keeps their flags in the table
+        let moved = MaterialRegistry::from_materials(MaterialRegistry::builtin().iter().map(|m| {
+            let mut m = m.clone();
+            match m.name.as_str() {
+                "grass_blade" => m.id = 90,
+                "rock" => m.id = 91,
+                _ => {}
+            }
+            m
+        })).unwrap();
+        let table = moved.gpu_table();
+        assert_eq!(table[90].flags, MaterialFlag::GrassBlade.bit());
+        assert_eq!(table[91].flags, MaterialFlag::SdfNormal.bit());
+        assert_eq!(table[16].flags, 0);
+    }
+}
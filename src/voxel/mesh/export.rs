@@ -0,0 +1,297 @@
+//! Mesh file writers: Wavefront OBJ and binary glTF 2.0 (.glb)
+//!
+//! OBJ output streams: meshes are written as they are produced, so a whole
+//! world region can be exported without holding it in memory. Vertex colors
+//! use the common `v x y z r g b` extension and faces are grouped by
+//! `usemtl` per voxel material. GLB needs every buffer length up front and
+//! takes one finished mesh, split into one primitive per material.
+
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+use glam::Vec3;
+use serde_json::json;
+
+use super::Mesh;
+use crate::voxel::material::registry;
+
+/// Name used for a material ID in exported files
+fn material_name(id: u8) -> String {
+    registry().get(id).map_or_else(|| format!("material_{}", id), |m| m.name.clone())
+}
+
+/// Triangle indices grouped by material, in material order
+fn triangles_by_material(mesh: &Mesh) -> Vec<(u8, Vec<u32>)> {
+    let mut groups: Vec<(u8, Vec<u32>)> = Vec::new();
+    let mut order: Vec<usize> = (0..mesh.triangle_count()).collect();
+    order.sort_by_key(|&t| mesh.triangle_material(t));
+    for t in order {
+        let material = mesh.triangle_material(t);
+        if groups.last().is_none_or(|(m, _)| *m != material) {
+            groups.push((material, Vec::new()));
+        }
+        groups.last_mut().unwrap().1.extend_from_slice(&mesh.indices[t * 3..t * 3 + 3]);
+    }
+    groups
+}
+
+/// Streaming OBJ writer
+pub struct ObjWriter<W: Write> {
+    out: W,
+    /// Vertices written so far; OBJ indices are global and 1-based
+    vertex_count: u32,
+    materials: BTreeSet<u8>,
+}
+
+impl<W: Write> ObjWriter<W> {
+    /// Start an OBJ file, referencing `mtllib` if given
+    pub fn new(mut out: W, mtllib: Option<&str>) -> io::Result<Self> {
+        writeln!(out, "# rktri voxel mesh")?;
+        if let Some(mtllib) = mtllib {
+            writeln!(out, "mtllib {}", mtllib)?;
+        }
+        Ok(Self { out, vertex_count: 0, materials: BTreeSet::new() })
+    }
+
+    /// Append a mesh as its own object
+    pub fn write_mesh(&mut self, name: &str, mesh: &Mesh) -> io::Result<()> {
+        if mesh.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out, "o {}", name)?;
+        for (p, c) in mesh.positions.iter().zip(&mesh.colors) {
+            writeln!(self.out, "v {} {} {} {:.4} {:.4} {:.4}", p.x, p.y, p.z, c.x, c.y, c.z)?;
+        }
+        for n in &mesh.normals {
+            writeln!(self.out, "vn {:.4} {:.4} {:.4}", n.x, n.y, n.z)?;
+        }
+        let base = self.vertex_count + 1;
+        for (material, indices) in triangles_by_material(mesh) {
+            writeln!(self.out, "usemtl {}", material_name(material))?;
+            for tri in indices.chunks(3) {
+                let [a, b, c] = [tri[0] + base, tri[1] + base, tri[2] + base];
+                writeln!(self.out, "f {a}//{a} {b}//{b} {c}//{c}")?;
+            }
+            self.materials.insert(material);
+        }
+        self.vertex_count += mesh.vertex_count() as u32;
+        Ok(())
+    }
+
+    /// Flush and return the output plus the materials used, for `write_mtl`
+    pub fn finish(mut self) -> io::Result<(W, Vec<u8>)> {
+        self.out.flush()?;
+        Ok((self.out, self.materials.into_iter().collect()))
+    }
+}
+
+/// Write an MTL library for the given material IDs from the registry
+pub fn write_mtl(mut out: impl Write, materials: &[u8]) -> io::Result<()> {
+    for &id in materials {
+        let material = registry().get(id);
+        let albedo = material.map_or([1.0; 3], |m| m.albedo);
+        let emissive = material.map_or([0.0; 3], |m| m.emissive);
+        let roughness = material.map_or(1.0, |m| m.roughness);
+        writeln!(out, "newmtl {}", material_name(id))?;
+        writeln!(out, "Kd {} {} {}", albedo[0], albedo[1], albedo[2])?;
+        writeln!(out, "Ke {} {} {}", emissive[0], emissive[1], emissive[2])?;
+        // Blinn-Phong exponent is the closest OBJ has to roughness
+        writeln!(out, "Ns {:.1}", (1.0 - roughness) * 1000.0)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const GL_FLOAT: u32 = 5126;
+const GL_UNSIGNED_INT: u32 = 5125;
+const GL_ARRAY_BUFFER: u32 = 34962;
+const GL_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// sRGB to linear, since glTF vertex colors are linear
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Append little-endian vec3s, returning their (offset, length) in bytes
+fn push_vec3s(bin: &mut Vec<u8>, values: impl Iterator<Item = Vec3>) -> (usize, usize) {
+    let offset = bin.len();
+    for v in values {
+        for c in v.to_array() {
+            bin.extend_from_slice(&c.to_le_bytes());
+        }
+    }
+    (offset, bin.len() - offset)
+}
+
+/// Write a mesh as a binary glTF file.
+///
+/// Vertex colors carry the voxel colors, so materials keep a white base
+/// color and only contribute roughness, metalness and emission.
+pub fn write_glb(mesh: &Mesh, mut out: impl Write) -> io::Result<()> {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut views = Vec::new();
+    let mut accessors = Vec::new();
+
+    let (min, max) = mesh.bounds();
+    let attributes = [
+        ("POSITION", push_vec3s(&mut bin, mesh.positions.iter().copied())),
+        ("NORMAL", push_vec3s(&mut bin, mesh.normals.iter().copied())),
+        ("COLOR_0", push_vec3s(&mut bin, mesh.colors.iter().map(|c| c.map(srgb_to_linear)))),
+    ];
+    for (name, (offset, length)) in attributes {
+        views.push(json!({
+            "buffer": 0, "byteOffset": offset, "byteLength": length, "target": GL_ARRAY_BUFFER,
+        }));
+        let mut accessor = json!({
+            "bufferView": views.len() - 1,
+            "componentType": GL_FLOAT,
+            "count": mesh.vertex_count(),
+            "type": "VEC3",
+        });
+        if name == "POSITION" {
+            accessor["min"] = json!(min.to_array());
+            accessor["max"] = json!(max.to_array());
+        }
+        accessors.push(accessor);
+    }
+
+    let mut primitives = Vec::new();
+    let mut materials = Vec::new();
+    for (material, indices) in triangles_by_material(mesh) {
+        let offset = bin.len();
+        for i in &indices {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        views.push(json!({
+            "buffer": 0, "byteOffset": offset, "byteLength": bin.len() - offset,
+            "target": GL_ELEMENT_ARRAY_BUFFER,
+        }));
+        accessors.push(json!({
+            "bufferView": views.len() - 1,
+            "componentType": GL_UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        primitives.push(json!({
+            "attributes": { "POSITION": 0, "NORMAL": 1, "COLOR_0": 2 },
+            "indices": accessors.len() - 1,
+            "material": materials.len(),
+        }));
+
+        let properties = registry().get(material);
+        materials.push(json!({
+            "name": material_name(material),
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [1.0, 1.0, 1.0, 1.0],
+                "metallicFactor": properties.map_or(0.0, |m| m.metallic),
+                "roughnessFactor": properties.map_or(1.0, |m| m.roughness),
+            },
+            "emissiveFactor": properties.map_or([0.0; 3], |m| m.emissive),
+            "extras": { "voxelMaterialId": material },
+        }));
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "rktri export_mesh" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0 }],
+        "meshes": [{ "primitives": primitives }],
+        "materials": materials,
+        "accessors": accessors,
+        "bufferViews": views,
+        "buffers": [{ "byteLength": bin.len() }],
+    });
+    if mesh.is_empty() {
+        // glTF requires at least one primitive per mesh
+        document["nodes"] = json!([]);
+        document["meshes"] = json!([]);
+    }
+
+    let mut json_bytes = serde_json::to_vec(&document).map_err(io::Error::other)?;
+    json_bytes.resize(json_bytes.len().next_multiple_of(4), b' ');
+    bin.resize(bin.len().next_multiple_of(4), 0);
+
+    let total = 12 + 8 + json_bytes.len() + 8 + bin.len();
+    for word in [GLB_MAGIC, GLB_VERSION, total as u32, json_bytes.len() as u32, CHUNK_JSON] {
+        out.write_all(&word.to_le_bytes())?;
+    }
+    out.write_all(&json_bytes)?;
+    for word in [bin.len() as u32, CHUNK_BIN] {
+        out.write_all(&word.to_le_bytes())?;
+    }
+    out.write_all(&bin)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::UVec3;
+    use crate::voxel::mesh::{greedy::greedy_mesh, VoxelGrid};
+    use crate::voxel::voxel::Voxel;
+
+    fn two_material_block() -> Mesh {
+        let stone = Voxel::new(128, 128, 128, 4);
+        let sand = Voxel::new(200, 180, 140, 1);
+        let grid = VoxelGrid::from_fn(UVec3::splat(2), Vec3::ZERO, 1.0, |pos| {
+            if pos.cmpgt(Vec3::splat(2.0)).any() || pos.cmplt(Vec3::ZERO).any() {
+                Voxel::EMPTY
+            } else if pos.y < 1.0 { stone } else { sand }
+        });
+        greedy_mesh(&grid)
+    }
+
+    #[test]
+    fn test_obj_groups_and_offsets() {
+        let mesh = two_material_block();
+        let mut writer = ObjWriter::new(Vec::new(), Some("block.mtl")).unwrap();
+        writer.write_mesh("a", &mesh).unwrap();
+        writer.write_mesh("b", &mesh).unwrap();
+        let (bytes, materials) = writer.finish().unwrap();
+        assert_eq!(materials, vec![1, 4]);
+
+        let text = String::from_utf8(bytes).unwrap();
+        let count = |prefix: &str| text.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(count("v "), 2 * mesh.vertex_count());
+        assert_eq!(count("vn "), 2 * mesh.vertex_count());
+        assert_eq!(count("f "), 2 * mesh.triangle_count());
+        assert_eq!(count("usemtl "), 4);
+        // The second object's faces index past the first object's vertices
+        let last_face = text.lines().rfind(|l| l.starts_with("f ")).unwrap();
+        let index: usize = last_face[2..].split("//").next().unwrap().parse().unwrap();
+        assert!(index > mesh.vertex_count() && index <= 2 * mesh.vertex_count());
+
+        let mut mtl = Vec::new();
+        write_mtl(&mut mtl, &materials).unwrap();
+        assert!(String::from_utf8(mtl).unwrap().contains("newmtl stone"));
+    }
+
+    #[test]
+    fn test_glb_layout() {
+        let mesh = two_material_block();
+        let mut bytes = Vec::new();
+        write_glb(&mesh, &mut bytes).unwrap();
+
+        let word = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        assert_eq!(word(0), GLB_MAGIC);
+        assert_eq!(word(4), 2);
+        assert_eq!(word(8) as usize, bytes.len());
+        assert_eq!(word(16), CHUNK_JSON);
+
+        let json_len = word(12) as usize;
+        let document: serde_json::Value = serde_json::from_slice(&bytes[20..20 + json_len]).unwrap();
+        assert_eq!(document["meshes"][0]["primitives"].as_array().unwrap().len(), 2);
+        assert_eq!(document["accessors"][0]["count"], mesh.vertex_count());
+
+        let bin_at = 20 + json_len;
+        assert_eq!(word(bin_at + 4), CHUNK_BIN);
+        assert_eq!(word(bin_at) as usize, document["buffers"][0]["byteLength"].as_u64().unwrap() as usize);
+        assert_eq!(bin_at + 8 + word(bin_at) as usize, bytes.len());
+    }
+}
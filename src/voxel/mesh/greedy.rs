@@ -0,0 +1,141 @@
+//! Greedy meshing: exposed voxel faces merged into maximal rectangles
+//!
+//! Each axis-aligned slice of the grid gets a 2D mask of faces looking into
+//! empty space; runs of faces with the same look (see `face_key`) are grown
+//! into rectangles and emitted as one quad each.
+
+use glam::{IVec3, Vec3};
+
+use super::{face_key, voxel_surface, Mesh, VoxelGrid};
+use crate::voxel::voxel::Voxel;
+
+type Face = ((u8, Option<u16>), Voxel);
+
+/// Mesh a grid into blocky quads with flat normals
+pub fn greedy_mesh(grid: &VoxelGrid) -> Mesh {
+    let mut mesh = Mesh::default();
+    let size = grid.size().as_ivec3();
+
+    for axis in 0..3 {
+        // (u, v, axis) is right-handed, so u x v points along +axis
+        let u = (axis + 1) % 3;
+        let v = (axis + 2) % 3;
+        let (width, height) = (size[u], size[v]);
+        let mut mask: Vec<Option<Face>> = vec![None; (width * height) as usize];
+
+        for layer in 0..size[axis] {
+            for positive in [false, true] {
+                let mut step = IVec3::ZERO;
+                step[axis] = if positive { 1 } else { -1 };
+
+                for j in 0..height {
+                    for i in 0..width {
+                        let mut cell = IVec3::ZERO;
+                        cell[axis] = layer;
+                        cell[u] = i;
+                        cell[v] = j;
+                        let voxel = grid.get(cell);
+                        let exposed = grid.get(cell + step).is_empty();
+                        mask[(i + j * width) as usize] = if exposed {
+                            face_key(voxel).map(|key| (key, voxel))
+                        } else {
+                            None
+                        };
+                    }
+                }
+
+                let plane = layer + positive as i32;
+                for j in 0..height {
+                    let mut i = 0;
+                    while i < width {
+                        let Some((key, voxel)) = mask[(i + j * width) as usize] else {
+                            i += 1;
+                            continue;
+                        };
+                        let same = |x: i32, y: i32| mask[(x + y * width) as usize].is_some_and(|(k, _)| k == key);
+
+                        let mut w = 1;
+                        while i + w < width && same(i + w, j) {
+                            w += 1;
+                        }
+                        let mut h = 1;
+                        while j + h < height && (0..w).all(|k| same(i + k, j + h)) {
+                            h += 1;
+                        }
+                        for y in j..j + h {
+                            for x in i..i + w {
+                                mask[(x + y * width) as usize] = None;
+                            }
+                        }
+
+                        let corner = |du: i32, dv: i32| {
+                            let mut p = IVec3::ZERO;
+                            p[axis] = plane;
+                            p[u] = i + du;
+                            p[v] = j + dv;
+                            grid.cell_min(p)
+                        };
+                        let mut normal = Vec3::ZERO;
+                        normal[axis] = step[axis] as f32;
+                        let color = voxel_surface(voxel).color;
+                        let mut corners = [corner(0, 0), corner(w, 0), corner(w, h), corner(0, h)];
+                        if !positive {
+                            corners.reverse();
+                        }
+                        let quad = corners.map(|p| mesh.push_vertex(p, normal, color, voxel.material_id));
+                        mesh.push_quad(quad);
+
+                        i += w;
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::UVec3;
+
+    fn block(size: u32, voxel: impl Fn(Vec3) -> Voxel) -> VoxelGrid {
+        VoxelGrid::from_fn(UVec3::splat(size), Vec3::ZERO, 1.0, |pos| {
+            if pos.cmpge(Vec3::ZERO).all() && pos.cmplt(Vec3::splat(size as f32)).all() {
+                voxel(pos)
+            } else {
+                Voxel::EMPTY
+            }
+        })
+    }
+
+    #[test]
+    fn test_cube_merges_to_six_quads() {
+        let stone = Voxel::new(128, 128, 128, 4);
+        let mesh = greedy_mesh(&block(4, |_| stone));
+        assert_eq!(mesh.triangle_count(), 12);
+        assert_eq!(mesh.bounds(), (Vec3::ZERO, Vec3::splat(4.0)));
+
+        // Every triangle faces away from the cube center
+        for t in 0..mesh.triangle_count() {
+            let [a, b, c] = [0, 1, 2].map(|k| mesh.positions[mesh.indices[t * 3 + k] as usize]);
+            let facing = (b - a).cross(c - a);
+            assert!(facing.dot((a + b + c) / 3.0 - Vec3::splat(2.0)) > 0.0, "triangle {} faces inward", t);
+            assert!(facing.normalize().dot(mesh.normals[mesh.indices[t * 3] as usize]) > 0.99);
+        }
+    }
+
+    #[test]
+    fn test_colors_split_quads() {
+        let red = Voxel::new(255, 0, 0, 4);
+        let blue = Voxel::new(0, 0, 255, 4);
+        let mesh = greedy_mesh(&block(4, |pos| if pos.x < 2.0 { red } else { blue }));
+        // 4 sides split in two, -x and +x whole
+        assert_eq!(mesh.triangle_count(), 2 * (4 * 2 + 2));
+        assert!(mesh.colors.iter().all(|c| *c == Vec3::X || *c == Vec3::Z));
+        // Neighbouring solid border voxels hide faces on that side
+        let grid = VoxelGrid::from_fn(UVec3::splat(2), Vec3::ZERO, 1.0, |_| red);
+        assert!(greedy_mesh(&grid).is_empty());
+    }
+}
@@ -0,0 +1,391 @@
+//! Triangle mesh extraction from voxel octrees
+//!
+//! Turns octree voxels into meshes for use outside the ray tracer:
+//! - `greedy`: blocky output, coplanar faces of equal color merged into quads
+//! - `surface_nets`: smooth output that follows the SDF normals and terrain
+//!   heights the generators store in voxels (see `voxel::sdf`)
+//! - `export`: OBJ and binary glTF (.glb) writers
+//!
+//! Meshers read a padded `VoxelGrid` rather than the octree itself, so a
+//! chunk can be meshed with its neighbours' border voxels and the pieces
+//! line up without duplicate faces.
+
+pub mod greedy;
+pub mod surface_nets;
+pub mod export;
+
+pub use export::{write_glb, write_mtl, ObjWriter};
+
+use glam::{IVec3, UVec3, Vec3};
+
+use crate::math::Aabb;
+use crate::voxel::material::{registry, MaterialFlag};
+use crate::voxel::sdf;
+use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
+use crate::voxel::svo::Octree;
+use crate::voxel::voxel::{rgb565_to_rgb, Voxel};
+
+/// Indexed triangle mesh with per-vertex attributes
+#[derive(Clone, Debug, Default)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    /// sRGB color in [0, 1]
+    pub colors: Vec<Vec3>,
+    pub materials: Vec<u8>,
+    /// Counter-clockwise triangles
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Add a vertex, returning its index
+    pub fn push_vertex(&mut self, position: Vec3, normal: Vec3, color: Vec3, material: u8) -> u32 {
+        let index = self.positions.len() as u32;
+        self.positions.push(position);
+        self.normals.push(normal);
+        self.colors.push(color);
+        self.materials.push(material);
+        index
+    }
+
+    /// Add a quad as two triangles; corners in counter-clockwise order
+    pub fn push_quad(&mut self, corners: [u32; 4]) {
+        let [a, b, c, d] = corners;
+        self.indices.extend_from_slice(&[a, b, c, a, c, d]);
+    }
+
+    /// Append another mesh
+    pub fn append(&mut self, other: &Mesh) {
+        let base = self.positions.len() as u32;
+        self.positions.extend_from_slice(&other.positions);
+        self.normals.extend_from_slice(&other.normals);
+        self.colors.extend_from_slice(&other.colors);
+        self.materials.extend_from_slice(&other.materials);
+        self.indices.extend(other.indices.iter().map(|i| i + base));
+    }
+
+    /// Axis-aligned bounds of all vertices as (min, max)
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        self.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), p| (min.min(*p), max.max(*p)),
+        )
+    }
+
+    /// Material of each triangle (taken from its first vertex)
+    pub fn triangle_material(&self, triangle: usize) -> u8 {
+        self.materials[self.indices[triangle * 3] as usize]
+    }
+}
+
+/// Meshing algorithm
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshMode {
+    /// Blocky faces merged into large quads
+    #[default]
+    Greedy,
+    /// Smooth dual surface with SDF normals
+    SurfaceNets,
+}
+
+impl MeshMode {
+    /// Parse a CLI name ("greedy", "nets" / "surface-nets")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "greedy" => Some(Self::Greedy),
+            "nets" | "surface-nets" | "surface_nets" => Some(Self::SurfaceNets),
+            _ => None,
+        }
+    }
+}
+
+/// Mesh a grid with the given algorithm
+pub fn extract_mesh(grid: &VoxelGrid, mode: MeshMode) -> Mesh {
+    match mode {
+        MeshMode::Greedy => greedy::greedy_mesh(grid),
+        MeshMode::SurfaceNets => surface_nets::surface_nets(grid),
+    }
+}
+
+/// Voxels per axis meshed at once by `mesh_tiled`
+pub const TILE_VOXELS: u32 = 64;
+
+/// Mesh a block of `size` voxels starting at `origin`, one tile at a time.
+///
+/// Full-resolution chunks are too large for a single dense grid, so the
+/// block is cut into tiles of `TILE_VOXELS`; tiles `source` classifies as
+/// uniform (border included) have no surface and are skipped. Each non-empty
+/// tile mesh is passed to `emit`.
+pub fn mesh_tiled(
+    source: &impl RegionClassifier,
+    origin: Vec3,
+    size: UVec3,
+    voxel_size: f32,
+    mode: MeshMode,
+    mut emit: impl FnMut(Mesh),
+) {
+    for tz in (0..size.z).step_by(TILE_VOXELS as usize) {
+        for ty in (0..size.y).step_by(TILE_VOXELS as usize) {
+            for tx in (0..size.x).step_by(TILE_VOXELS as usize) {
+                let start = UVec3::new(tx, ty, tz);
+                let tile_size = (size - start).min(UVec3::splat(TILE_VOXELS));
+                let tile_origin = origin + start.as_vec3() * voxel_size;
+
+                // Box through the centers of the border voxels
+                let half_voxel = Vec3::splat(voxel_size / 2.0);
+                let bounds = Aabb::new(
+                    tile_origin - half_voxel,
+                    tile_origin + tile_size.as_vec3() * voxel_size + half_voxel,
+                );
+                if source.classify_region(&bounds).is_terminal() {
+                    continue;
+                }
+
+                let grid = VoxelGrid::from_fn(tile_size, tile_origin, voxel_size, |pos| source.evaluate(pos));
+                let mesh = extract_mesh(&grid, mode);
+                if !mesh.is_empty() {
+                    emit(mesh);
+                }
+            }
+        }
+    }
+}
+
+/// Octree read through `Octree::region_hint`, in its local space
+struct OctreeSource<'a>(&'a Octree);
+
+impl RegionClassifier for OctreeSource<'_> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        self.0.region_hint(aabb)
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        self.0.sample_voxel(pos)
+    }
+}
+
+/// Mesh a whole octree in its local space, tile by tile
+pub fn mesh_octree(octree: &Octree, mode: MeshMode) -> Mesh {
+    let half = octree.root_size() / 2.0;
+    let resolution = 1u32 << octree.max_depth();
+    let mut mesh = Mesh::default();
+    mesh_tiled(
+        &OctreeSource(octree),
+        Vec3::splat(-half),
+        UVec3::splat(resolution),
+        octree.voxel_size(),
+        mode,
+        |tile| mesh.append(&tile),
+    );
+    mesh
+}
+
+/// Dense voxel block with a one-voxel border on every side.
+///
+/// Cells are addressed from -1 to `size` per axis; the border belongs to
+/// the neighbours and is only read, never meshed.
+#[derive(Clone, Debug)]
+pub struct VoxelGrid {
+    size: UVec3,
+    /// World position of the min corner of cell (0, 0, 0)
+    origin: Vec3,
+    voxel_size: f32,
+    voxels: Vec<Voxel>,
+}
+
+impl VoxelGrid {
+    /// Fill a grid by sampling `sample` at every voxel center, border included
+    pub fn from_fn(size: UVec3, origin: Vec3, voxel_size: f32, mut sample: impl FnMut(Vec3) -> Voxel) -> Self {
+        let padded = size + UVec3::splat(2);
+        let mut voxels = Vec::with_capacity((padded.x * padded.y * padded.z) as usize);
+        for z in -1..=size.z as i32 {
+            for y in -1..=size.y as i32 {
+                for x in -1..=size.x as i32 {
+                    let center = origin + (Vec3::new(x as f32, y as f32, z as f32) + 0.5) * voxel_size;
+                    voxels.push(sample(center));
+                }
+            }
+        }
+        Self { size, origin, voxel_size, voxels }
+    }
+
+    /// Grid covering a whole octree in its local space, with an empty border.
+    /// Dense: use `mesh_octree` for full-resolution chunks.
+    pub fn from_octree(octree: &Octree) -> Self {
+        let half = octree.root_size() / 2.0;
+        let resolution = 1u32 << octree.max_depth();
+        Self::from_fn(UVec3::splat(resolution), Vec3::splat(-half), octree.voxel_size(), |pos| {
+            octree.sample_voxel(pos)
+        })
+    }
+
+    /// Interior size in voxels
+    pub fn size(&self) -> UVec3 {
+        self.size
+    }
+
+    pub fn origin(&self) -> Vec3 {
+        self.origin
+    }
+
+    pub fn voxel_size(&self) -> f32 {
+        self.voxel_size
+    }
+
+    /// Voxel at a cell; border cells (-1 or `size`) are readable,
+    /// anything further out is empty
+    pub fn get(&self, cell: IVec3) -> Voxel {
+        let padded = cell + IVec3::ONE;
+        let dims = (self.size + UVec3::splat(2)).as_ivec3();
+        if padded.cmplt(IVec3::ZERO).any() || padded.cmpge(dims).any() {
+            return Voxel::EMPTY;
+        }
+        self.voxels[(padded.x + dims.x * (padded.y + dims.y * padded.z)) as usize]
+    }
+
+    /// World position of a cell's min corner
+    pub fn cell_min(&self, cell: IVec3) -> Vec3 {
+        self.origin + cell.as_vec3() * self.voxel_size
+    }
+
+    /// True if no voxel, border included, is solid
+    pub fn is_empty(&self) -> bool {
+        self.voxels.iter().all(Voxel::is_empty)
+    }
+}
+
+/// What a voxel looks like, decoded the same way svo_trace.wgsl does
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelSurface {
+    /// sRGB color in [0, 1]
+    pub color: Vec3,
+    /// Smooth normal stored in the voxel, if any
+    pub normal: Option<Vec3>,
+    /// Terrain surface height as a fraction of the voxel, if any
+    pub height: Option<f32>,
+}
+
+/// Decode a voxel's color, normal and terrain height.
+///
+/// Bark, foliage and SDF-normal voxels store an RGB565 normal and terrain
+/// voxels a height gradient in the color field; both take their color from
+/// the material albedo in the process-wide registry.
+pub fn voxel_surface(voxel: Voxel) -> VoxelSurface {
+    let material = registry().get(voxel.material_id);
+    let has_flag = |flag| material.is_some_and(|m| m.has_flag(flag));
+    let albedo = material.map_or(Vec3::ONE, |m| Vec3::from(m.albedo));
+
+    let bark = has_flag(MaterialFlag::Bark);
+    let foliage = has_flag(MaterialFlag::Foliage);
+    let sdf_normal = has_flag(MaterialFlag::SdfNormal);
+
+    if foliage || ((bark || sdf_normal) && voxel.flags > 0) {
+        VoxelSurface { color: albedo, normal: Some(sdf::decode_normal_rgb565(voxel.color)), height: None }
+    } else if bark {
+        VoxelSurface { color: albedo, normal: None, height: None }
+    } else if voxel.flags > 0 {
+        VoxelSurface {
+            color: albedo,
+            normal: Some(sdf::decode_gradient_normal(voxel.color)),
+            height: Some(voxel.flags as f32 / 255.0),
+        }
+    } else {
+        let (r, g, b) = rgb565_to_rgb(voxel.color);
+        VoxelSurface { color: Vec3::new(r as f32, g as f32, b as f32) / 255.0, normal: None, height: None }
+    }
+}
+
+/// Key for merging faces: voxels with equal keys look identical.
+/// Voxels whose color field holds a normal merge by material alone.
+fn face_key(voxel: Voxel) -> Option<(u8, Option<u16>)> {
+    if voxel.is_empty() {
+        return None;
+    }
+    let stores_normal = voxel_surface(voxel).normal.is_some();
+    Some((voxel.material_id, (!stores_normal).then_some(voxel.color)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::svo::OctreeBuilder;
+
+    #[test]
+    fn test_grid_border_from_neighbours() {
+        let stone = Voxel::new(128, 128, 128, 4);
+        // Solid below y = 0 everywhere, including outside the grid
+        let grid = VoxelGrid::from_fn(UVec3::splat(4), Vec3::new(0.0, -2.0, 0.0), 1.0, |pos| {
+            if pos.y < 0.0 { stone } else { Voxel::EMPTY }
+        });
+        assert_eq!(grid.get(IVec3::new(-1, 0, -1)), stone);
+        assert_eq!(grid.get(IVec3::new(4, 1, 4)), stone);
+        assert!(grid.get(IVec3::new(0, 2, 0)).is_empty());
+        assert!(grid.get(IVec3::new(-2, 0, 0)).is_empty());
+
+        let octree = OctreeBuilder::new(4).build(&vec![stone; 64], 1.0);
+        let grid = VoxelGrid::from_octree(&octree);
+        assert_eq!(grid.origin(), Vec3::splat(-0.5));
+        assert_eq!(grid.get(IVec3::new(3, 3, 3)), stone);
+        assert!(grid.get(IVec3::new(4, 0, 0)).is_empty());
+    }
+
+    /// Rod along x through a 100 x 8 x 8 block
+    struct Rod;
+
+    impl RegionClassifier for Rod {
+        fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+            if aabb.min.y > 6.0 { RegionHint::Empty } else { RegionHint::Unknown }
+        }
+
+        fn evaluate(&self, pos: Vec3) -> Voxel {
+            let inside = pos.x > 10.0 && pos.x < 90.0 && (2.0..6.0).contains(&pos.y) && (2.0..6.0).contains(&pos.z);
+            if inside { Voxel::new(128, 128, 128, 4) } else { Voxel::EMPTY }
+        }
+    }
+
+    #[test]
+    fn test_tiled_matches_single_grid() {
+        let whole = greedy::greedy_mesh(&VoxelGrid::from_fn(UVec3::new(100, 8, 8), Vec3::ZERO, 1.0, |p| Rod.evaluate(p)));
+        assert_eq!(whole.triangle_count(), 12);
+
+        let mut tiles = Vec::new();
+        mesh_tiled(&Rod, Vec3::ZERO, UVec3::new(100, 72, 8), 1.0, MeshMode::Greedy, |tile| tiles.push(tile));
+        // Two tiles along x hold the rod; the tiles above are skipped as empty
+        assert_eq!(tiles.len(), 2);
+        let mut tiled = Mesh::default();
+        tiles.iter().for_each(|tile| tiled.append(tile));
+        // The seam splits the four long sides
+        assert_eq!(tiled.triangle_count(), 12 + 8);
+        assert_eq!(tiled.bounds(), whole.bounds());
+    }
+
+    #[test]
+    fn test_voxel_surface_follows_shader_rules() {
+        let plain = voxel_surface(Voxel::new(255, 0, 0, 4));
+        assert_eq!(plain.color, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(plain.normal, None);
+
+        // Terrain: gradient normal, height fraction, albedo color
+        let terrain = voxel_surface(sdf::voxel_with_gradient(0.0, 0.0, 0.5, 9));
+        assert!(terrain.normal.unwrap().dot(Vec3::Y) > 0.99);
+        assert!((terrain.height.unwrap() - 0.5).abs() < 0.01);
+        assert_eq!(terrain.color, Vec3::from(registry().get(9).unwrap().albedo));
+
+        // Bark: RGB565 normal
+        let bark = Voxel::from_rgb565(sdf::encode_normal_rgb565(Vec3::X), 2).with_flags_value(128);
+        assert!(voxel_surface(bark).normal.unwrap().dot(Vec3::X) > 0.99);
+        assert_eq!(voxel_surface(bark).height, None);
+    }
+}
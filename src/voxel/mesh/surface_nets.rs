@@ -0,0 +1,177 @@
+//! Surface nets: a smooth dual surface over voxel occupancy
+//!
+//! Every cube of eight neighbouring voxel centers that the surface passes
+//! through gets one vertex, placed at the mean of its edge crossings; every
+//! voxel edge between solid and empty gets a quad joining the four cubes
+//! around it. Crossings sit halfway between voxel centers except on terrain
+//! voxels, whose stored height fraction places them at the true surface.
+//! Normals come from the normals generators encode in voxels, falling back
+//! to the occupancy gradient.
+
+use glam::{IVec3, Vec3};
+
+use super::{voxel_surface, Mesh, VoxelGrid};
+
+/// Corner offset of cube corner `i` (bit 0 = x, bit 1 = y, bit 2 = z)
+fn corner_offset(i: usize) -> IVec3 {
+    IVec3::new((i & 1) as i32, ((i >> 1) & 1) as i32, ((i >> 2) & 1) as i32)
+}
+
+/// The 12 cube edges as corner pairs, low corner first
+const EDGES: [(usize, usize); 12] = [
+    (0, 1), (2, 3), (4, 5), (6, 7),
+    (0, 2), (1, 3), (4, 6), (5, 7),
+    (0, 4), (1, 5), (2, 6), (3, 7),
+];
+
+/// Mesh a grid into a smooth surface with per-vertex normals
+pub fn surface_nets(grid: &VoxelGrid) -> Mesh {
+    let mut mesh = Mesh::default();
+    let size = grid.size().as_ivec3();
+
+    // Cubes are named by their min corner, from -1 to size - 1 per axis
+    let cubes = size + IVec3::ONE;
+    let cube_index = |c: IVec3| ((c.x + 1) + cubes.x * ((c.y + 1) + cubes.y * (c.z + 1))) as usize;
+    let mut vertex_of = vec![u32::MAX; (cubes.x * cubes.y * cubes.z) as usize];
+
+    for z in -1..size.z {
+        for y in -1..size.y {
+            for x in -1..size.x {
+                let cube = IVec3::new(x, y, z);
+                let corners: [_; 8] = std::array::from_fn(|i| grid.get(cube + corner_offset(i)));
+                let solid = corners.map(|v| !v.is_empty());
+                if solid.iter().all(|s| *s) || solid.iter().all(|s| !*s) {
+                    continue;
+                }
+                let surfaces = corners.map(|v| (!v.is_empty()).then(|| voxel_surface(v)));
+
+                let mut crossing_sum = Vec3::ZERO;
+                let mut crossings = 0;
+                for &(a, b) in &EDGES {
+                    if solid[a] == solid[b] {
+                        continue;
+                    }
+                    let (pa, pb) = (corner_offset(a).as_vec3(), corner_offset(b).as_vec3());
+                    let mut t = 0.5;
+                    // Terrain below empty space: the surface is at its stored height
+                    if pa.y != pb.y && solid[a]
+                        && let Some(height) = surfaces[a].and_then(|s| s.height)
+                    {
+                        t = height - 0.5;
+                    }
+                    crossing_sum += pa + (pb - pa) * t;
+                    crossings += 1;
+                }
+                let local = crossing_sum / crossings as f32;
+
+                let mut stored_normal = Vec3::ZERO;
+                let mut gradient = Vec3::ZERO;
+                let mut color = Vec3::ZERO;
+                let mut material = None;
+                for (i, surface) in surfaces.iter().enumerate() {
+                    let offset = corner_offset(i).as_vec3() - Vec3::splat(0.5);
+                    match surface {
+                        Some(surface) => {
+                            gradient -= offset;
+                            stored_normal += surface.normal.unwrap_or(Vec3::ZERO);
+                            color += surface.color;
+                            material.get_or_insert(corners[i].material_id);
+                        }
+                        None => gradient += offset,
+                    }
+                }
+                let solid_count = solid.iter().filter(|s| **s).count() as f32;
+                let normal = stored_normal.try_normalize()
+                    .or_else(|| gradient.try_normalize())
+                    .unwrap_or(Vec3::Y);
+
+                // Cube corners are voxel centers, half a voxel in from the cell min
+                let position = grid.cell_min(cube) + (local + Vec3::splat(0.5)) * grid.voxel_size();
+                vertex_of[cube_index(cube)] = mesh.push_vertex(
+                    position,
+                    normal,
+                    color / solid_count,
+                    material.unwrap_or(0),
+                );
+            }
+        }
+    }
+
+    // One quad per solid/empty edge leaving an interior voxel in +axis
+    for z in 0..size.z {
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let cell = IVec3::new(x, y, z);
+                let inside = !grid.get(cell).is_empty();
+                for axis in 0..3 {
+                    let mut step = IVec3::ZERO;
+                    step[axis] = 1;
+                    if inside != grid.get(cell + step).is_empty() {
+                        continue;
+                    }
+                    let (mut du, mut dv) = (IVec3::ZERO, IVec3::ZERO);
+                    du[(axis + 1) % 3] = 1;
+                    dv[(axis + 2) % 3] = 1;
+                    let mut quad = [cell - du - dv, cell - dv, cell, cell - du]
+                        .map(|cube| vertex_of[cube_index(cube)]);
+                    debug_assert!(quad.iter().all(|v| *v != u32::MAX));
+                    if !inside {
+                        quad.reverse();
+                    }
+                    mesh.push_quad(quad);
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use glam::UVec3;
+    use crate::voxel::sdf;
+    use crate::voxel::voxel::Voxel;
+
+    #[test]
+    fn test_single_voxel_is_closed() {
+        let stone = Voxel::new(128, 128, 128, 4);
+        let grid = VoxelGrid::from_fn(UVec3::splat(3), Vec3::ZERO, 1.0, |pos| {
+            if pos == Vec3::splat(1.5) { stone } else { Voxel::EMPTY }
+        });
+        let mesh = surface_nets(&grid);
+        assert_eq!(mesh.triangle_count(), 12);
+        assert_eq!(mesh.vertex_count(), 8);
+
+        // Every directed edge is matched by its reverse: closed and consistently wound
+        let mut edges: HashMap<(u32, u32), i32> = HashMap::new();
+        for tri in mesh.indices.chunks(3) {
+            for k in 0..3 {
+                let (a, b) = (tri[k], tri[(k + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_default() += if a < b { 1 } else { -1 };
+            }
+        }
+        assert!(edges.values().all(|n| *n == 0));
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!((*p - Vec3::splat(1.5)).dot(*n) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_terrain_height_and_normals() {
+        // One layer of flat terrain voxels, surface half way up
+        let ground = sdf::voxel_with_gradient(0.0, 0.0, 0.5, 9);
+        let height = ground.flags as f32 / 255.0;
+        let grid = VoxelGrid::from_fn(UVec3::splat(4), Vec3::ZERO, 1.0, |pos| {
+            if pos.y < 1.0 { ground } else { Voxel::EMPTY }
+        });
+        let mesh = surface_nets(&grid);
+        assert_eq!(mesh.triangle_count(), 2 * 16);
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!((p.y - height).abs() < 1e-5, "vertex at {:?}", p);
+            assert!(n.dot(Vec3::Y) > 0.99);
+        }
+    }
+}
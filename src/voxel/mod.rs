@@ -2,13 +2,14 @@
 
 pub mod voxel;
 pub mod brick;
+pub mod attributes;
 pub mod chunk;
 pub mod chunk_handle;
 pub mod brick_handle;
 pub mod world;
 pub mod world_index;
-// pub mod material;
-// pub mod query;
+pub mod material;
+pub mod query;
 pub mod svo;
 pub mod instancing;
 pub mod streaming;
@@ -26,14 +27,18 @@ pub mod super_chunk;
 pub mod water;
 pub mod edit;
 pub mod sdf;
+pub mod mesh;
+pub mod import;
 
+pub use attributes::{BrickAttributes, VoxelAttributes};
 pub use chunk::{Chunk, ChunkCoord, CHUNK_SIZE};
 pub use chunk_handle::{ChunkHandle, ChunkState, GpuChunkHandle};
 pub use brick_handle::{BrickId, BrickHandle};
 pub use world_index::WorldIndex;
 pub use tree_data::TreeData;
 pub use procgen::{TreeGenerator, TreeParams, TreeStyle};
-pub use rock_library::{RockGenerator, RockParams, MATERIAL_ROCK};
+pub use rock_library::{RockGenerator, RockParams};
+pub use material::{Material, MaterialFlag, MaterialRegistry, GpuMaterial, material_id};
 pub use world::World;
 pub use instancing::{VoxelModel, ModelInstance, ModelLibrary};
 pub use streaming::{BrickPool, BrickCache, BrickRequestQueue, StreamingManager, StreamingOrchestrator, MemoryBudget, StreamingStats, LoadPriority};
@@ -11,6 +11,7 @@ use std::collections::HashMap;
 use glam::Vec3;
 
 use crate::math::Aabb;
+use crate::voxel::material::material_id;
 use crate::voxel::sdf::{sdf_capsule, sdf_sphere, encode_normal_rgb565 as encode_sdf_normal};
 use crate::voxel::voxel::{Voxel, rgb565_to_rgb, rgb_to_565};
 use crate::voxel::svo::Octree;
@@ -142,8 +143,8 @@ impl TreeParams {
             trunk_taper: 0.4,
             crown_radius: 3.5,
             crown_density: 0.6,
-            bark_voxel: Voxel::new(90, 60, 30, 2),   // Dark brown bark
-            leaf_voxel: Voxel::new(40, 130, 30, 3),   // Deep forest green
+            bark_voxel: Voxel::new(90, 60, 30, material_id("bark")),   // Dark brown bark
+            leaf_voxel: Voxel::new(40, 130, 30, material_id("leaves")),   // Deep forest green
             trunk_level: 8,
             branch_level: 9,
             leaf_level: 10,
@@ -172,8 +173,8 @@ impl TreeParams {
             trunk_taper: 0.45,
             crown_radius: 4.0,
             crown_density: 0.55,
-            bark_voxel: Voxel::new(110, 85, 50, 2),   // Lighter brown bark
-            leaf_voxel: Voxel::new(120, 200, 100, 3),  // Light yellow-green
+            bark_voxel: Voxel::new(110, 85, 50, material_id("bark")),   // Lighter brown bark
+            leaf_voxel: Voxel::new(120, 200, 100, material_id("leaves")),  // Light yellow-green
             trunk_level: 8,
             branch_level: 9,
             leaf_level: 10,
@@ -202,8 +203,8 @@ impl TreeParams {
             trunk_taper: 0.4,
             crown_radius: 3.0,
             crown_density: 0.55,
-            bark_voxel: Voxel::new(75, 55, 35, 2),    // Gray-brown bark
-            leaf_voxel: Voxel::new(50, 150, 40, 3),   // Medium green
+            bark_voxel: Voxel::new(75, 55, 35, material_id("bark")),    // Gray-brown bark
+            leaf_voxel: Voxel::new(50, 150, 40, material_id("leaves")),   // Medium green
             trunk_level: 8,
             branch_level: 9,
             leaf_level: 10,
@@ -0,0 +1,224 @@
+//! Spatial queries over chunked worlds
+//!
+//! World-level ray casting walks the chunk grid front-to-back (3D DDA) and
+//! defers to `Octree::raycast` for each resident chunk along the way.
+
+use glam::Vec3;
+
+use crate::math::Ray;
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use crate::voxel::svo::{Octree, VoxelHit};
+use crate::voxel::world::World;
+use crate::voxel::world_index::WorldIndex;
+
+/// A voxel hit in a chunked world
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChunkHit {
+    /// Chunk containing the hit voxel
+    pub chunk: ChunkCoord,
+    /// Hit details; `position` is in world space
+    pub hit: VoxelHit,
+}
+
+/// Longest distance a chunk walk travels, so unbounded rays terminate (meters)
+pub const MAX_WALK_DISTANCE: f32 = 16384.0;
+
+/// Visit chunk coordinates pierced by a ray, nearest first.
+///
+/// Stops as soon as `visit` returns `Some`, or once the ray has travelled `max_t`
+/// (clamped to `MAX_WALK_DISTANCE`). Returns `None` without visiting anything
+/// for a NaN `max_t` or a zero-length direction.
+pub fn walk_chunks<T, F>(ray: &Ray, max_t: f32, mut visit: F) -> Option<T>
+where
+    F: FnMut(ChunkCoord) -> Option<T>,
+{
+    if max_t.is_nan() {
+        return None;
+    }
+    let max_t = max_t.min(MAX_WALK_DISTANCE);
+
+    let cell = CHUNK_SIZE as f32;
+    let start = ChunkCoord::from_world_pos(ray.origin);
+    let mut coord = [start.x, start.y, start.z];
+    let origin = ray.origin.to_array();
+    let dir = ray.direction.to_array();
+
+    let mut step = [0i32; 3];
+    let mut t_next = [f32::INFINITY; 3];
+    let mut t_delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        if dir[axis] > 0.0 {
+            step[axis] = 1;
+            t_next[axis] = ((coord[axis] + 1) as f32 * cell - origin[axis]) / dir[axis];
+            t_delta[axis] = cell / dir[axis];
+        } else if dir[axis] < 0.0 {
+            step[axis] = -1;
+            t_next[axis] = (coord[axis] as f32 * cell - origin[axis]) / dir[axis];
+            t_delta[axis] = -cell / dir[axis];
+        }
+    }
+    if step == [0; 3] {
+        return None;
+    }
+
+    loop {
+        if let Some(result) = visit(ChunkCoord::new(coord[0], coord[1], coord[2])) {
+            return Some(result);
+        }
+
+        // Advance along the axis whose cell boundary is closest
+        let axis = if t_next[0] <= t_next[1] && t_next[0] <= t_next[2] {
+            0
+        } else if t_next[1] <= t_next[2] {
+            1
+        } else {
+            2
+        };
+
+        if t_next[axis] > max_t {
+            return None;
+        }
+        coord[axis] += step[axis];
+        t_next[axis] += t_delta[axis];
+    }
+}
+
+/// Raycast a single chunk octree with a world-space ray.
+///
+/// Chunk octrees are centered on their chunk, so the ray is shifted into
+/// octree-local space and the hit position shifted back to world space.
+pub fn raycast_chunk(octree: &Octree, coord: ChunkCoord, ray: &Ray, max_t: f32) -> Option<VoxelHit> {
+    let center = coord.world_origin() + Vec3::splat(octree.root_size() / 2.0);
+    let local_ray = Ray::new(ray.origin - center, ray.direction);
+
+    octree.raycast(&local_ray, max_t).map(|mut hit| {
+        hit.position += center;
+        hit
+    })
+}
+
+impl World {
+    /// Cast a ray through all loaded chunks and return the nearest solid voxel.
+    pub fn raycast(&self, ray: &Ray, max_t: f32) -> Option<ChunkHit> {
+        walk_chunks(ray, max_t, |coord| {
+            let chunk = self.get_chunk(coord)?;
+            raycast_chunk(&chunk.octree, coord, ray, max_t).map(|hit| ChunkHit { chunk: coord, hit })
+        })
+    }
+}
+
+impl WorldIndex {
+    /// Cast a ray through resident chunks and return the nearest solid voxel.
+    ///
+    /// Chunks that are not loaded (unloaded or still loading) are treated as empty.
+    pub fn raycast(&self, ray: &Ray, max_t: f32) -> Option<ChunkHit> {
+        walk_chunks(ray, max_t, |coord| {
+            let handle = self.get_chunk(coord)?;
+            let state = handle.read_state();
+            let chunk = state.get_chunk()?;
+            raycast_chunk(&chunk.octree, coord, ray, max_t).map(|hit| ChunkHit { chunk: coord, hit })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::chunk::Chunk;
+    use crate::voxel::chunk_handle::ChunkHandle;
+    use crate::voxel::svo::AdaptiveOctreeBuilder;
+    use crate::voxel::voxel::Voxel;
+
+    /// Flat ground chunk: solid below y = 1.0 (world)
+    fn ground_chunk(coord: ChunkCoord) -> Chunk {
+        let builder = AdaptiveOctreeBuilder::new(16);
+        let octree = builder.build_simple(
+            &|pos: Vec3| {
+                if pos.y < 1.0 {
+                    Voxel::new(90, 140, 60, 1)
+                } else {
+                    Voxel::EMPTY
+                }
+            },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        );
+        Chunk::from_octree(coord, octree)
+    }
+
+    #[test]
+    fn test_walk_chunks_order() {
+        let ray = Ray::new(Vec3::new(1.0, 1.0, 1.0), Vec3::X);
+        let mut visited = Vec::new();
+        let _: Option<()> = walk_chunks(&ray, 10.0, |coord| {
+            visited.push(coord);
+            None
+        });
+        assert_eq!(
+            visited,
+            vec![ChunkCoord::new(0, 0, 0), ChunkCoord::new(1, 0, 0), ChunkCoord::new(2, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_walk_chunks_terminates() {
+        // A miss with unbounded max_t stops at MAX_WALK_DISTANCE
+        let ray = Ray::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(1.0, 0.3, 0.2).normalize());
+        let mut visited = 0usize;
+        let hit: Option<()> = walk_chunks(&ray, f32::INFINITY, |_| {
+            visited += 1;
+            None
+        });
+        assert!(hit.is_none());
+        assert!(visited > 0);
+
+        // A zero direction visits nothing
+        let ray = Ray::new(Vec3::ONE, Vec3::ZERO);
+        let hit: Option<()> = walk_chunks(&ray, f32::INFINITY, |_| panic!("visited"));
+        assert!(hit.is_none());
+        let hit: Option<()> = walk_chunks(&ray, f32::NAN, |_| panic!("visited"));
+        assert!(hit.is_none());
+    }
+
+    #[test]
+    fn test_world_raycast_down() {
+        let mut world = World::new();
+        world.insert_chunk(ground_chunk(ChunkCoord::new(0, 0, 0)));
+        world.insert_chunk(ground_chunk(ChunkCoord::new(1, 0, 0)));
+
+        let ray = Ray::new(Vec3::new(6.1, 3.5, 2.1), Vec3::NEG_Y);
+        let result = world.raycast(&ray, 10.0).expect("should hit ground");
+        assert_eq!(result.chunk, ChunkCoord::new(1, 0, 0));
+        assert!((result.hit.position.y - 1.0).abs() < 1e-4);
+        assert_eq!(result.hit.normal, Vec3::Y);
+    }
+
+    #[test]
+    fn test_world_raycast_across_chunks() {
+        let mut world = World::new();
+        world.insert_chunk(ground_chunk(ChunkCoord::new(1, 0, 0)));
+
+        // Starts in an unloaded chunk, travels into the loaded one
+        let ray = Ray::new(Vec3::new(-2.0, 0.3, 2.1), Vec3::X);
+        let result = world.raycast(&ray, 20.0).expect("should hit side of ground");
+        assert_eq!(result.chunk, ChunkCoord::new(1, 0, 0));
+        assert!((result.hit.position.x - 4.0).abs() < 1e-4);
+        assert_eq!(result.hit.normal, Vec3::NEG_X);
+
+        // Too short to reach it
+        assert!(world.raycast(&ray, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_world_index_raycast_skips_unloaded() {
+        let mut index = WorldIndex::new();
+        let loaded = ChunkCoord::new(0, 0, 0);
+        index.add_chunk(ChunkHandle::with_chunk(loaded, ground_chunk(loaded)));
+        index.add_chunk(ChunkHandle::new(ChunkCoord::new(0, 1, 0)));
+
+        let ray = Ray::new(Vec3::new(2.1, 7.0, 2.1), Vec3::NEG_Y);
+        let result = index.raycast(&ray, 20.0).expect("should hit ground");
+        assert_eq!(result.chunk, loaded);
+        assert!((result.hit.t - 6.0).abs() < 1e-4);
+    }
+}
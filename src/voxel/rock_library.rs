@@ -8,13 +8,11 @@ use glam::Vec3;
 
 use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
 
+use crate::voxel::material::material_id;
 use crate::voxel::sdf::{encode_normal_rgb565, smin};
 use crate::voxel::svo::{AdaptiveOctreeBuilder, Octree};
 use crate::voxel::voxel::Voxel;
 
-/// Material ID for rock voxels
-pub const MATERIAL_ROCK: u8 = 20;
-
 /// Rock generation parameters
 #[derive(Clone, Debug)]
 pub struct RockParams {
@@ -46,7 +44,7 @@ impl Default for RockParams {
             sharpness: 0.3,
             color_variation: 0.3,
             base_color: [120, 120, 120],
-            rock_voxel: Voxel::new(120, 120, 120, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(120, 120, 120, material_id("rock")),
         }
     }
 }
@@ -78,7 +76,7 @@ impl RockParams {
             sharpness: rng.float() * 0.7,
             color_variation: rng.float() * 0.5,
             base_color: [120, 120, 120],
-            rock_voxel: Voxel::new(120, 120, 120, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(120, 120, 120, material_id("rock")),
         }
     }
 
@@ -92,7 +90,7 @@ impl RockParams {
             sharpness: 0.4,
             color_variation: 0.3,
             base_color: [140, 140, 140],
-            rock_voxel: Voxel::new(140, 140, 140, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(140, 140, 140, material_id("rock")),
         }
     }
 
@@ -106,7 +104,7 @@ impl RockParams {
             sharpness: 0.3,
             color_variation: 0.3,
             base_color: [130, 130, 130],
-            rock_voxel: Voxel::new(130, 130, 130, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(130, 130, 130, material_id("rock")),
         }
     }
 
@@ -120,7 +118,7 @@ impl RockParams {
             sharpness: 0.25,
             color_variation: 0.25,
             base_color: [120, 120, 120],
-            rock_voxel: Voxel::new(120, 120, 120, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(120, 120, 120, material_id("rock")),
         }
     }
 
@@ -134,7 +132,7 @@ impl RockParams {
             sharpness: 0.2,
             color_variation: 0.2,
             base_color: [110, 110, 110],
-            rock_voxel: Voxel::new(110, 110, 110, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(110, 110, 110, material_id("rock")),
         }
     }
 
@@ -148,7 +146,7 @@ impl RockParams {
             sharpness: 0.1,
             color_variation: 0.2,
             base_color: [150, 145, 140],
-            rock_voxel: Voxel::new(150, 145, 140, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(150, 145, 140, material_id("rock")),
         }
     }
 
@@ -162,7 +160,7 @@ impl RockParams {
             sharpness: 0.35,
             color_variation: 0.4,
             base_color: [100, 110, 95],
-            rock_voxel: Voxel::new(100, 110, 95, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(100, 110, 95, material_id("rock")),
         }
     }
 
@@ -176,14 +174,14 @@ impl RockParams {
             sharpness: 0.2,
             color_variation: 0.15,
             base_color: [220, 225, 230],
-            rock_voxel: Voxel::new(220, 225, 230, MATERIAL_ROCK),
+            rock_voxel: Voxel::new(220, 225, 230, material_id("rock")),
         }
     }
 
     /// Set base color
     pub fn with_color(mut self, r: u8, g: u8, b: u8) -> Self {
         self.base_color = [r, g, b];
-        self.rock_voxel = Voxel::new(r, g, b, MATERIAL_ROCK);
+        self.rock_voxel = Voxel::new(r, g, b, material_id("rock"));
         self
     }
 }
@@ -537,7 +535,7 @@ impl RockGenerator {
                 // Use distance fraction for flags (how close to surface)
                 let dist_frac = ((-sdf_final / (height * 0.15) * 254.0).clamp(1.0, 255.0)) as u8;
 
-                Voxel::new(r, g, b, MATERIAL_ROCK).with_color_and_flags(normal_color, dist_frac)
+                Voxel::new(r, g, b, params.rock_voxel.material_id).with_color_and_flags(normal_color, dist_frac)
             } else {
                 Voxel::EMPTY
             }
@@ -589,6 +587,6 @@ mod tests {
     fn test_rock_voxel_material() {
         let generator = RockGenerator::new(42);
         let voxel = generator.rock_voxel();
-        assert_eq!(voxel.material_id, MATERIAL_ROCK);
+        assert_eq!(voxel.material_id, material_id("rock"));
     }
 }
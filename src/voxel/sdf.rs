@@ -86,6 +86,16 @@ pub fn encode_normal_rgb565(n: Vec3) -> u16 {
     (r5 << 11) | (g6 << 5) | b5
 }
 
+/// Decode a unit normal stored by `encode_normal_rgb565`.
+/// Mirrors decode_bark_normal() in the shader.
+#[inline]
+pub fn decode_normal_rgb565(encoded: u16) -> Vec3 {
+    let nx = ((encoded >> 11) & 0x1F) as f32 / 31.0 * 2.0 - 1.0;
+    let ny = ((encoded >> 5) & 0x3F) as f32 / 63.0 * 2.0 - 1.0;
+    let nz = (encoded & 0x1F) as f32 / 31.0 * 2.0 - 1.0;
+    Vec3::new(nx, ny, nz).normalize_or_zero()
+}
+
 /// Distance to a capsule (line segment with radius)
 /// Capsule from point `a` to `b` with radius `r`
 #[inline]
@@ -188,6 +198,13 @@ mod tests {
         assert!((dz - (-1.0)).abs() < 0.1);
     }
 
+    #[test]
+    fn test_normal_rgb565_roundtrip() {
+        let n = Vec3::new(0.6, -0.48, 0.64);
+        let decoded = decode_normal_rgb565(encode_normal_rgb565(n));
+        assert!(decoded.dot(n) > 0.99);
+    }
+
     #[test]
     fn test_sdf_sphere() {
         let d = sdf_sphere(Vec3::ZERO, Vec3::new(2.0, 0.0, 0.0), 1.0);
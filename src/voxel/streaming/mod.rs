@@ -14,12 +14,15 @@ pub mod orchestrator;
 pub mod prefetch;
 pub mod async_loader;
 pub mod feedback;
+pub mod replay;
 
 pub use brick_pool::BrickPool;
 pub use brick_cache::BrickCache;
 pub use request_queue::BrickRequestQueue;
 pub use manager::StreamingManager;
-pub use orchestrator::{StreamingOrchestrator, MemoryBudget, StreamingStats, LoadPriority};
+pub use orchestrator::{StreamingOrchestrator, StreamingStats, LoadPriority};
+pub use crate::streaming::MemoryBudget;
 pub use prefetch::PrefetchPredictor;
 pub use async_loader::{AsyncChunkLoader, AsyncLoaderConfig, LoadedChunk, LoadStatus};
 pub use feedback::{FeedbackBuffer, BrickRequest, BRICK_NOT_RESIDENT};
+pub use replay::{CameraFrame, CameraPath, FrameReport, ReplayConfig, ReplayReport, ReplaySummary, StreamingReplay};
@@ -1,34 +1,19 @@
 //! Streaming orchestrator for coordinating GPU feedback and chunk loading.
+//!
+//! SuperChunk far-field proxies are streamed ahead of chunks, so distant
+//! terrain shows up coarse before any full-resolution chunk arrives.
 
-use std::collections::{HashSet, HashMap, VecDeque};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet, HashMap, VecDeque};
 use glam::Vec3;
 
+use crate::streaming::MemoryBudget;
 use crate::voxel::chunk::ChunkCoord;
-use crate::voxel::hierarchy::world_to_chunk;
+use crate::voxel::hierarchy::{world_to_chunk, SUPER_CHUNK_SIZE_METERS};
+use crate::voxel::super_chunk::{ChunkPresenceMask, SuperChunkCoord};
 
-/// Memory budget configuration.
-#[derive(Clone, Debug)]
-pub struct MemoryBudget {
-    /// Maximum GPU memory for bricks (bytes)
-    pub gpu_brick_budget: usize,
-    /// Maximum GPU memory for nodes (bytes)
-    pub gpu_node_budget: usize,
-    /// Maximum CPU memory for cached chunks (bytes)
-    pub cpu_cache_budget: usize,
-    /// Target GPU utilization (0.0 - 1.0)
-    pub target_utilization: f32,
-}
-
-impl Default for MemoryBudget {
-    fn default() -> Self {
-        Self {
-            gpu_brick_budget: 2 * 1024 * 1024 * 1024, // 2 GB
-            gpu_node_budget: 512 * 1024 * 1024,       // 512 MB
-            cpu_cache_budget: 1024 * 1024 * 1024,     // 1 GB
-            target_utilization: 0.9,
-        }
-    }
-}
+/// GPU usage the orchestrator evicts down to, as a fraction of the budget
+const TARGET_UTILIZATION: f32 = 0.9;
 
 /// Statistics from streaming operations.
 #[derive(Clone, Debug, Default)]
@@ -90,12 +75,44 @@ pub struct LoadRequest {
     pub request_frame: u32,
 }
 
+/// Queued load request: highest priority first, then oldest
+struct QueuedLoad {
+    request: LoadRequest,
+    seq: u64,
+}
+
+impl PartialEq for QueuedLoad {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedLoad {}
+
+impl Ord for QueuedLoad {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.request.priority.0.total_cmp(&other.request.priority.0)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for QueuedLoad {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 /// Coordinates streaming of chunks and bricks.
 pub struct StreamingOrchestrator {
-    /// Memory budget configuration
+    /// Memory budget shared with the chunk streamer
     budget: MemoryBudget,
-    /// Pending load requests (priority queue)
-    load_queue: VecDeque<LoadRequest>,
+    /// Pending load requests (priority queue). Re-requesting a chunk at a
+    /// higher priority pushes a new entry; superseded entries are skipped.
+    load_queue: BinaryHeap<QueuedLoad>,
+    /// Best priority each queued chunk was requested at
+    queued: HashMap<ChunkCoord, LoadPriority>,
+    /// Sequence number of the next request
+    next_seq: u64,
     /// Chunks currently being loaded
     loading: HashSet<ChunkCoord>,
     /// Chunks that are resident
@@ -110,6 +127,16 @@ pub struct StreamingOrchestrator {
     max_concurrent_loads: usize,
     /// Last access frame for each chunk (for LRU eviction)
     last_access: HashMap<ChunkCoord, u32>,
+    /// Pending SuperChunk proxy requests, served before chunk loads
+    proxy_queue: VecDeque<(SuperChunkCoord, LoadPriority)>,
+    /// Proxies currently being loaded
+    proxies_loading: HashSet<SuperChunkCoord>,
+    /// Proxies that are resident
+    proxies_resident: HashSet<SuperChunkCoord>,
+    /// Resident chunks per SuperChunk, whose regions the proxy must not draw
+    proxy_coverage: HashMap<SuperChunkCoord, ChunkPresenceMask>,
+    /// Resident proxies whose coverage changed since `take_changed_proxies`
+    changed_proxies: HashSet<SuperChunkCoord>,
 }
 
 impl StreamingOrchestrator {
@@ -117,7 +144,9 @@ impl StreamingOrchestrator {
     pub fn new(budget: MemoryBudget) -> Self {
         Self {
             budget,
-            load_queue: VecDeque::new(),
+            load_queue: BinaryHeap::new(),
+            queued: HashMap::new(),
+            next_seq: 0,
             loading: HashSet::new(),
             resident: HashSet::new(),
             frame: 0,
@@ -125,9 +154,20 @@ impl StreamingOrchestrator {
             camera_pos: Vec3::ZERO,
             max_concurrent_loads: 4,
             last_access: HashMap::new(),
+            proxy_queue: VecDeque::new(),
+            proxies_loading: HashSet::new(),
+            proxies_resident: HashSet::new(),
+            proxy_coverage: HashMap::new(),
+            changed_proxies: HashSet::new(),
         }
     }
 
+    /// Set the maximum number of loads in flight.
+    pub fn with_max_concurrent_loads(mut self, max: usize) -> Self {
+        self.max_concurrent_loads = max;
+        self
+    }
+
     /// Begin a new frame.
     pub fn begin_frame(&mut self, camera_pos: Vec3) {
         self.frame += 1;
@@ -136,25 +176,39 @@ impl StreamingOrchestrator {
     }
 
     /// Request a chunk to be loaded.
+    ///
+    /// A chunk already queued is only re-queued if the new priority is higher.
     pub fn request_chunk(&mut self, chunk: ChunkCoord, priority: LoadPriority) {
         // Skip if already resident or loading
         if self.resident.contains(&chunk) || self.loading.contains(&chunk) {
             return;
         }
+        if let Some(queued) = self.queued.get(&chunk)
+            && *queued >= priority
+        {
+            return;
+        }
 
-        // Add to queue (insert by priority)
-        let request = LoadRequest {
-            chunk,
-            priority,
-            request_frame: self.frame,
-        };
+        self.queued.insert(chunk, priority);
+        self.load_queue.push(QueuedLoad {
+            request: LoadRequest {
+                chunk,
+                priority,
+                request_frame: self.frame,
+            },
+            seq: self.next_seq,
+        });
+        self.next_seq += 1;
+    }
 
-        // Simple insertion - could use a proper priority queue for better performance
-        let pos = self.load_queue
-            .iter()
-            .position(|r| r.priority < priority)
-            .unwrap_or(self.load_queue.len());
-        self.load_queue.insert(pos, request);
+    /// Drop a queued request. Chunks already loading are not affected.
+    pub fn cancel_chunk(&mut self, chunk: ChunkCoord) {
+        self.queued.remove(&chunk);
+    }
+
+    /// Chunks waiting in the load queue.
+    pub fn queued_chunks(&self) -> impl Iterator<Item = ChunkCoord> + '_ {
+        self.queued.keys().copied()
     }
 
     /// Request chunks around a position.
@@ -179,19 +233,129 @@ impl StreamingOrchestrator {
         }
     }
 
+    /// Request a SuperChunk's far-field proxy to be loaded.
+    pub fn request_proxy(&mut self, coord: SuperChunkCoord, priority: LoadPriority) {
+        if self.proxies_resident.contains(&coord)
+            || self.proxies_loading.contains(&coord)
+            || self.proxy_queue.iter().any(|(c, _)| *c == coord)
+        {
+            return;
+        }
+
+        let pos = self.proxy_queue
+            .iter()
+            .position(|(_, p)| *p < priority)
+            .unwrap_or(self.proxy_queue.len());
+        self.proxy_queue.insert(pos, (coord, priority));
+    }
+
+    /// Request the proxies of SuperChunks around a position. The radius is
+    /// typically well beyond the chunk view distance.
+    pub fn request_proxies_around(&mut self, pos: Vec3, radius: f32) {
+        let center = SuperChunkCoord::from_world_pos(pos);
+        let super_radius = (radius / SUPER_CHUNK_SIZE_METERS).ceil() as i32;
+
+        for dx in -super_radius..=super_radius {
+            for dy in -super_radius..=super_radius {
+                for dz in -super_radius..=super_radius {
+                    let coord = SuperChunkCoord::new(center.x + dx, center.y + dy, center.z + dz);
+                    let distance = (coord.world_center() - pos).length();
+                    self.request_proxy(coord, LoadPriority::from_distance(distance));
+                }
+            }
+        }
+    }
+
+    /// Get next proxies to load (respects max concurrent, shared with chunks).
+    /// Call before `get_pending_loads`: chunk loads wait until no proxy is queued.
+    pub fn get_pending_proxy_loads(&mut self) -> Vec<SuperChunkCoord> {
+        let in_flight = self.loading.len() + self.proxies_loading.len();
+        let available = self.max_concurrent_loads.saturating_sub(in_flight);
+        let mut result = Vec::with_capacity(available);
+
+        while result.len() < available {
+            let Some((coord, _)) = self.proxy_queue.pop_front() else { break };
+            if !self.proxies_resident.contains(&coord) {
+                self.proxies_loading.insert(coord);
+                result.push(coord);
+            }
+        }
+
+        result
+    }
+
+    /// Mark a proxy as loaded. Also call this for SuperChunks without a proxy
+    /// on disk, so they are not requested again.
+    pub fn mark_proxy_loaded(&mut self, coord: SuperChunkCoord) {
+        self.proxies_loading.remove(&coord);
+        self.proxies_resident.insert(coord);
+        self.changed_proxies.insert(coord);
+    }
+
+    /// Mark a proxy as unloaded/evicted.
+    pub fn mark_proxy_unloaded(&mut self, coord: SuperChunkCoord) {
+        self.proxies_resident.remove(&coord);
+        self.changed_proxies.remove(&coord);
+    }
+
+    /// Check if a proxy is resident.
+    pub fn is_proxy_resident(&self, coord: SuperChunkCoord) -> bool {
+        self.proxies_resident.contains(&coord)
+    }
+
+    /// Number of proxies in load queue.
+    pub fn proxy_queue_length(&self) -> usize {
+        self.proxy_queue.len()
+    }
+
+    /// Resident full-resolution chunks in a SuperChunk. Their regions replace
+    /// the proxy (see `SuperChunk::visible_proxy`).
+    pub fn proxy_coverage(&self, coord: SuperChunkCoord) -> Option<&ChunkPresenceMask> {
+        self.proxy_coverage.get(&coord)
+    }
+
+    /// Resident proxies that were loaded or had chunks become resident or
+    /// evicted since the last call, and need their visible part rebuilt.
+    pub fn take_changed_proxies(&mut self) -> Vec<SuperChunkCoord> {
+        let mut changed: Vec<SuperChunkCoord> = self.changed_proxies.drain().collect();
+        changed.sort_by_key(|c| (c.x, c.y, c.z));
+        changed
+    }
+
+    /// Record a chunk becoming resident or leaving in its SuperChunk's coverage
+    fn set_covered(&mut self, chunk: ChunkCoord, resident: bool) {
+        let coord = SuperChunkCoord::from_chunk(chunk);
+        let Some((x, y, z)) = coord.local_chunk(chunk) else { return };
+        let coverage = self.proxy_coverage.entry(coord).or_default();
+        coverage.set_present(x, y, z, resident);
+        if coverage.is_empty() {
+            self.proxy_coverage.remove(&coord);
+        }
+        if self.proxies_resident.contains(&coord) {
+            self.changed_proxies.insert(coord);
+        }
+    }
+
     /// Get next chunks to load (respects max concurrent).
+    /// Nothing is handed out while proxy requests are queued.
     pub fn get_pending_loads(&mut self) -> Vec<ChunkCoord> {
-        let available = self.max_concurrent_loads.saturating_sub(self.loading.len());
+        if !self.proxy_queue.is_empty() {
+            return Vec::new();
+        }
+        let in_flight = self.loading.len() + self.proxies_loading.len();
+        let available = self.max_concurrent_loads.saturating_sub(in_flight);
         let mut result = Vec::with_capacity(available);
 
         while result.len() < available {
-            if let Some(request) = self.load_queue.pop_front() {
-                if !self.resident.contains(&request.chunk) {
-                    self.loading.insert(request.chunk);
-                    result.push(request.chunk);
-                }
-            } else {
-                break;
+            let Some(QueuedLoad { request, .. }) = self.load_queue.pop() else { break };
+            // Skip entries superseded by a higher priority request or cancelled
+            if self.queued.get(&request.chunk) != Some(&request.priority) {
+                continue;
+            }
+            self.queued.remove(&request.chunk);
+            if !self.resident.contains(&request.chunk) {
+                self.loading.insert(request.chunk);
+                result.push(request.chunk);
             }
         }
 
@@ -204,6 +368,7 @@ impl StreamingOrchestrator {
         self.resident.insert(chunk);
         self.last_access.insert(chunk, self.frame);
         self.stats.chunks_loaded += 1;
+        self.set_covered(chunk, true);
     }
 
     /// Touch a chunk to update its last access time (for LRU tracking).
@@ -215,7 +380,9 @@ impl StreamingOrchestrator {
 
     /// Mark a chunk as unloaded/evicted.
     pub fn mark_unloaded(&mut self, chunk: ChunkCoord) {
-        self.resident.remove(&chunk);
+        if self.resident.remove(&chunk) {
+            self.set_covered(chunk, false);
+        }
     }
 
     /// Check if a chunk is resident.
@@ -228,6 +395,11 @@ impl StreamingOrchestrator {
         self.loading.contains(&chunk)
     }
 
+    /// Number of chunks being loaded.
+    pub fn loading_count(&self) -> usize {
+        self.loading.len()
+    }
+
     /// Get current statistics.
     pub fn stats(&self) -> &StreamingStats {
         &self.stats
@@ -238,6 +410,11 @@ impl StreamingOrchestrator {
         &self.budget
     }
 
+    /// Get memory budget for recording usage.
+    pub fn budget_mut(&mut self) -> &mut MemoryBudget {
+        &mut self.budget
+    }
+
     /// Number of resident chunks.
     pub fn resident_count(&self) -> usize {
         self.resident.len()
@@ -245,7 +422,7 @@ impl StreamingOrchestrator {
 
     /// Number of chunks in load queue.
     pub fn queue_length(&self) -> usize {
-        self.load_queue.len()
+        self.queued.len()
     }
 
     /// Current frame number.
@@ -256,7 +433,7 @@ impl StreamingOrchestrator {
     /// Enforce memory budget by evicting chunks.
     pub fn enforce_budget(&mut self, current_usage: usize) -> Vec<ChunkCoord> {
         let mut evicted = Vec::new();
-        let target = (self.budget.gpu_brick_budget as f32 * self.budget.target_utilization) as usize;
+        let target = (self.budget.gpu_budget() as f32 * TARGET_UTILIZATION) as usize;
 
         if current_usage > target {
             // Estimate per-chunk memory usage
@@ -289,13 +466,18 @@ impl StreamingOrchestrator {
                 })
                 .collect();
 
-            // Sort by eviction score (highest first)
-            candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            // Sort by eviction score (highest first), ties by coordinate so
+            // the order doesn't depend on hash set iteration
+            candidates.sort_by(|a, b| {
+                b.1.total_cmp(&a.1)
+                    .then_with(|| (a.0.x, a.0.y, a.0.z).cmp(&(b.0.x, b.0.y, b.0.z)))
+            });
 
             // Evict chunks until under budget
             for (coord, _score) in candidates.iter().take(chunks_to_evict) {
                 self.resident.remove(coord);
                 self.last_access.remove(coord);
+                self.set_covered(*coord, false);
                 evicted.push(*coord);
                 self.stats.bricks_evicted += 1;
             }
@@ -369,4 +551,66 @@ mod tests {
         // Critical should be first
         assert_eq!(pending[0], ChunkCoord::new(1, 0, 0));
     }
+
+    #[test]
+    fn test_rerequest_raises_priority() {
+        let mut orchestrator = StreamingOrchestrator::default().with_max_concurrent_loads(1);
+        orchestrator.begin_frame(Vec3::ZERO);
+
+        orchestrator.request_chunk(ChunkCoord::new(0, 0, 0), LoadPriority::HIGH);
+        orchestrator.request_chunk(ChunkCoord::new(1, 0, 0), LoadPriority::LOW);
+        orchestrator.request_chunk(ChunkCoord::new(1, 0, 0), LoadPriority::CRITICAL);
+        orchestrator.request_chunk(ChunkCoord::new(1, 0, 0), LoadPriority::LOW);
+        assert_eq!(orchestrator.queue_length(), 2);
+
+        assert_eq!(orchestrator.get_pending_loads(), vec![ChunkCoord::new(1, 0, 0)]);
+        orchestrator.mark_loaded(ChunkCoord::new(1, 0, 0));
+
+        // The superseded LOW entry is skipped, and cancelled chunks are dropped
+        orchestrator.cancel_chunk(ChunkCoord::new(0, 0, 0));
+        assert!(orchestrator.get_pending_loads().is_empty());
+        assert_eq!(orchestrator.queue_length(), 0);
+    }
+
+    #[test]
+    fn test_proxies_stream_before_chunks() {
+        let mut orchestrator = StreamingOrchestrator::default();
+        orchestrator.begin_frame(Vec3::ZERO);
+
+        orchestrator.request_chunk(ChunkCoord::new(0, 0, 0), LoadPriority::CRITICAL);
+        orchestrator.request_proxy(SuperChunkCoord::new(5, 0, 0), LoadPriority::LOW);
+        orchestrator.request_proxy(SuperChunkCoord::new(5, 0, 0), LoadPriority::LOW);
+        assert_eq!(orchestrator.proxy_queue_length(), 1);
+
+        // Chunks wait for queued proxies
+        assert!(orchestrator.get_pending_loads().is_empty());
+        let proxies = orchestrator.get_pending_proxy_loads();
+        assert_eq!(proxies, vec![SuperChunkCoord::new(5, 0, 0)]);
+        assert_eq!(orchestrator.get_pending_loads(), vec![ChunkCoord::new(0, 0, 0)]);
+
+        orchestrator.mark_proxy_loaded(proxies[0]);
+        assert!(orchestrator.is_proxy_resident(proxies[0]));
+        orchestrator.request_proxy(proxies[0], LoadPriority::HIGH);
+        assert_eq!(orchestrator.proxy_queue_length(), 0);
+    }
+
+    #[test]
+    fn test_resident_chunks_cover_proxy() {
+        let mut orchestrator = StreamingOrchestrator::default();
+        let proxy = SuperChunkCoord::new(-1, 0, 0);
+        orchestrator.proxies_loading.insert(proxy);
+        orchestrator.mark_proxy_loaded(proxy);
+        assert_eq!(orchestrator.take_changed_proxies(), vec![proxy]);
+
+        let chunk = ChunkCoord::new(-1, 2, 3);
+        orchestrator.loading.insert(chunk);
+        orchestrator.mark_loaded(chunk);
+        assert!(orchestrator.proxy_coverage(proxy).unwrap().is_present(15, 2, 3));
+        assert_eq!(orchestrator.take_changed_proxies(), vec![proxy]);
+        assert!(orchestrator.take_changed_proxies().is_empty());
+
+        orchestrator.mark_unloaded(chunk);
+        assert!(orchestrator.proxy_coverage(proxy).is_none());
+        assert_eq!(orchestrator.take_changed_proxies(), vec![proxy]);
+    }
 }
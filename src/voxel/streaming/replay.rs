@@ -0,0 +1,629 @@
+//! Headless streaming replay
+//!
+//! Replays a recorded camera path through `ChunkStreamer` and a `BrickCache`
+//! without a GPU or disk. The streamer's only layer loads from a mock
+//! source that answers after a fixed number of frames, so a replay gives the
+//! same report every run. Useful to reproduce streaming bugs and to track
+//! regressions in benchmarks.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::streaming::{
+    Chunk, ChunkSource, ChunkStreamer, LoadResult, MemoryBudget, StreamEvent, StreamLayer, StreamerConfig,
+};
+use crate::voxel::brick::VoxelBrick;
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use crate::voxel::streaming::brick_cache::{BrickCache, LoadPriority as BrickPriority};
+use crate::voxel::streaming::{PrefetchPredictor, StreamingOrchestrator};
+use crate::voxel::svo::Octree;
+
+/// Camera state for one frame of a recorded path
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraFrame {
+    pub position: [f32; 3],
+    pub forward: [f32; 3],
+}
+
+/// Recorded camera path, one entry per frame
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraPath {
+    /// Seconds between frames
+    pub frame_time: f32,
+    pub frames: Vec<CameraFrame>,
+}
+
+impl CameraPath {
+    /// Create an empty path recorded at `frame_time` seconds per frame
+    pub fn new(frame_time: f32) -> Self {
+        Self { frame_time, frames: Vec::new() }
+    }
+
+    /// Straight flight from `start` at `velocity` (m/s), looking along it
+    pub fn flyover(start: Vec3, velocity: Vec3, frame_count: usize, frame_time: f32) -> Self {
+        let forward = velocity.normalize_or(Vec3::X);
+        let mut path = Self::new(frame_time);
+        for i in 0..frame_count {
+            path.push(start + velocity * (i as f32 * frame_time), forward);
+        }
+        path
+    }
+
+    /// Record a frame
+    pub fn push(&mut self, position: Vec3, forward: Vec3) {
+        self.frames.push(CameraFrame {
+            position: position.to_array(),
+            forward: forward.to_array(),
+        });
+    }
+
+    /// Parse a path from JSON
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Serialize the path to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("camera path serializes")
+    }
+}
+
+/// Replay settings: view ranges, mock loader timing and GPU limits
+#[derive(Clone, Debug)]
+pub struct ReplayConfig {
+    /// Horizontal distance kept resident (meters)
+    pub view_distance: f32,
+    /// Vertical distance kept resident (meters)
+    pub view_height: f32,
+    /// Extra distance before resident chunks unload (meters)
+    pub unload_margin: f32,
+    /// Horizontal field of view used to decide which chunks are visible (degrees)
+    pub fov_degrees: f32,
+    /// Chunk Y levels that hold data; the mock loader finds nothing
+    /// elsewhere
+    pub ground_levels: (i32, i32),
+    /// Frames a mock load takes
+    pub latency_frames: u32,
+    /// Up to this many extra frames per chunk, derived from its coordinate
+    pub latency_jitter_frames: u32,
+    /// Chunks loading at once
+    pub max_concurrent_loads: usize,
+    /// GPU bytes each loaded chunk takes (mock chunks are padded to it)
+    pub chunk_bytes: usize,
+    /// Bricks each loaded chunk holds
+    pub bricks_per_chunk: u32,
+    /// Brick pool slots
+    pub brick_pool_slots: u32,
+    /// Bricks uploaded into the pool per frame
+    pub brick_uploads_per_frame: usize,
+    /// Raise the priority of chunks ahead of the camera's predicted motion
+    pub prefetch: bool,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            view_distance: 128.0,
+            view_height: 8.0,
+            unload_margin: 16.0,
+            fov_degrees: 90.0,
+            ground_levels: (0, 1),
+            latency_frames: 3,
+            latency_jitter_frames: 2,
+            max_concurrent_loads: 32,
+            chunk_bytes: 256 * 1024,
+            bricks_per_chunk: 64,
+            brick_pool_slots: 65536,
+            brick_uploads_per_frame: 4096,
+            prefetch: true,
+        }
+    }
+}
+
+/// What happened during one replayed frame
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FrameReport {
+    pub frame: u32,
+    pub position: [f32; 3],
+    /// Chunks resident at the end of the frame, including probed empty ones
+    pub resident: usize,
+    /// Chunks waiting in the load queue
+    pub queued: usize,
+    /// Chunks with a mock load in flight
+    pub loading: usize,
+    /// Chunks whose data arrived this frame
+    pub loaded: u32,
+    /// Frames from first request to resident, for each chunk loaded this frame
+    pub time_to_resident: Vec<u32>,
+    /// Visible chunks that were not resident
+    pub missing_visible: u32,
+    /// Chunks that became resident while visible, having been visible
+    /// while missing before
+    pub pop_ins: u32,
+    /// Chunks evicted to enforce the memory budget
+    pub evictions: u32,
+    /// Chunks unloaded for leaving the view range
+    pub unloads: u32,
+    /// GPU bytes in use at the end of the frame
+    pub gpu_used: usize,
+    /// GPU usage exceeded the budget at the end of the frame
+    pub budget_exceeded: bool,
+    pub bricks_uploaded: u32,
+    pub bricks_evicted: u32,
+    /// Bricks evicted while still requested this frame (pool too small)
+    pub bricks_thrashed: u32,
+    pub brick_hit_rate: f32,
+}
+
+/// Totals over a whole replay
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReplaySummary {
+    pub frames: u32,
+    pub chunks_loaded: u32,
+    pub mean_time_to_resident: f32,
+    pub p95_time_to_resident: u32,
+    pub max_time_to_resident: u32,
+    pub pop_ins: u32,
+    pub evictions: u32,
+    pub unloads: u32,
+    /// Frames that ended over the GPU budget
+    pub budget_violations: u32,
+    pub peak_gpu_used: usize,
+    pub bricks_evicted: u32,
+    pub bricks_thrashed: u32,
+}
+
+/// Report of a replay: per-frame detail and totals
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub summary: ReplaySummary,
+    pub frames: Vec<FrameReport>,
+}
+
+impl ReplayReport {
+    /// Serialize the report to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("replay report serializes")
+    }
+
+    fn summarize(frames: Vec<FrameReport>) -> Self {
+        let mut times: Vec<u32> = frames.iter()
+            .flat_map(|f| f.time_to_resident.iter().copied())
+            .collect();
+        times.sort_unstable();
+
+        let summary = ReplaySummary {
+            frames: frames.len() as u32,
+            chunks_loaded: frames.iter().map(|f| f.loaded).sum(),
+            mean_time_to_resident: if times.is_empty() {
+                0.0
+            } else {
+                times.iter().sum::<u32>() as f32 / times.len() as f32
+            },
+            p95_time_to_resident: times.get(times.len() * 95 / 100).or(times.last()).copied().unwrap_or(0),
+            max_time_to_resident: times.last().copied().unwrap_or(0),
+            pop_ins: frames.iter().map(|f| f.pop_ins).sum(),
+            evictions: frames.iter().map(|f| f.evictions).sum(),
+            unloads: frames.iter().map(|f| f.unloads).sum(),
+            budget_violations: frames.iter().filter(|f| f.budget_exceeded).count() as u32,
+            peak_gpu_used: frames.iter().map(|f| f.gpu_used).max().unwrap_or(0),
+            bricks_evicted: frames.iter().map(|f| f.bricks_evicted).sum(),
+            bricks_thrashed: frames.iter().map(|f| f.bricks_thrashed).sum(),
+        };
+        Self { summary, frames }
+    }
+}
+
+/// Brick pool stand-in: slots with LRU eviction
+struct MockBrickPool {
+    /// (brick id, last frame requested) per slot
+    slots: Vec<Option<(u32, u32)>>,
+    /// Occupied slots ordered by last frame requested
+    lru: BTreeSet<(u32, u32)>,
+    free: Vec<u32>,
+}
+
+impl MockBrickPool {
+    fn new(capacity: u32) -> Self {
+        Self {
+            slots: vec![None; capacity as usize],
+            lru: BTreeSet::new(),
+            free: (0..capacity).rev().collect(),
+        }
+    }
+
+    /// Take a free slot, or the least recently requested one. Returns the
+    /// slot and the (brick id, last frame requested) evicted from it.
+    fn allocate(&mut self, brick_id: u32, frame: u32) -> Option<(u32, Option<(u32, u32)>)> {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => self.lru.pop_first()?.1,
+        };
+        let evicted = self.slots[slot as usize].replace((brick_id, frame));
+        self.lru.insert((frame, slot));
+        Some((slot, evicted))
+    }
+
+    fn touch(&mut self, slot: u32, frame: u32) {
+        if let Some((_, last)) = &mut self.slots[slot as usize] {
+            self.lru.remove(&(*last, slot));
+            *last = frame;
+            self.lru.insert((frame, slot));
+        }
+    }
+
+    fn release(&mut self, slot: u32) {
+        if let Some((_, last)) = self.slots[slot as usize].take() {
+            self.lru.remove(&(last, slot));
+            self.free.push(slot);
+        }
+    }
+}
+
+/// Chunk source standing in for the disk: loads answer after a fixed number
+/// of frames, with a padded chunk on the ground levels and nothing elsewhere
+struct MockLoader {
+    latency_frames: u32,
+    latency_jitter_frames: u32,
+    ground_levels: (i32, i32),
+    /// Octree of every loaded chunk, padded to `chunk_bytes`
+    octree: Octree,
+    /// Polls so far; the streamer polls once per frame
+    frame: u32,
+    /// Loads by the frame they complete
+    in_flight: BTreeMap<u32, Vec<ChunkCoord>>,
+    pending: HashSet<ChunkCoord>,
+}
+
+impl MockLoader {
+    fn new(config: &ReplayConfig) -> Self {
+        let mut octree = Octree::new(CHUNK_SIZE as f32, 1);
+        let padding = config.chunk_bytes.saturating_sub(octree.memory_usage()) / std::mem::size_of::<VoxelBrick>();
+        for _ in 0..padding {
+            octree.add_brick(VoxelBrick::EMPTY);
+        }
+        Self {
+            latency_frames: config.latency_frames,
+            latency_jitter_frames: config.latency_jitter_frames,
+            ground_levels: config.ground_levels,
+            octree,
+            frame: 0,
+            in_flight: BTreeMap::new(),
+            pending: HashSet::new(),
+        }
+    }
+}
+
+impl ChunkSource for MockLoader {
+    fn request(&mut self, coord: ChunkCoord, _priority: f32) -> bool {
+        if !self.pending.insert(coord) {
+            return false;
+        }
+        let jitter = coord_hash(coord) % (self.latency_jitter_frames + 1);
+        self.in_flight.entry(self.frame + self.latency_frames + jitter).or_default().push(coord);
+        true
+    }
+
+    fn poll_results(&mut self) -> Vec<LoadResult> {
+        let due: Vec<u32> = self.in_flight.range(..=self.frame).map(|(&f, _)| f).collect();
+        self.frame += 1;
+
+        let (min_y, max_y) = self.ground_levels;
+        let mut results = Vec::new();
+        for f in due {
+            for coord in self.in_flight.remove(&f).unwrap_or_default() {
+                self.pending.remove(&coord);
+                results.push(if (min_y..=max_y).contains(&coord.y) {
+                    LoadResult::Loaded(Chunk::from_octree(coord, self.octree.clone()))
+                } else {
+                    LoadResult::NotFound(coord)
+                });
+            }
+        }
+        results
+    }
+}
+
+/// Deterministic replay of the streaming stack along a camera path
+pub struct StreamingReplay {
+    config: ReplayConfig,
+    streamer: ChunkStreamer,
+    bricks: BrickCache,
+    pool: MockBrickPool,
+    /// Frame each queued or loading chunk was first requested
+    first_request: HashMap<ChunkCoord, u32>,
+    /// Chunks seen while visible and not resident
+    seen_missing: HashSet<ChunkCoord>,
+    /// Chunks from `seen_missing` that became resident this frame
+    arrived: HashSet<ChunkCoord>,
+    /// First brick id of each resident chunk
+    brick_base: HashMap<ChunkCoord, u32>,
+    next_brick_id: u32,
+}
+
+impl StreamingReplay {
+    /// Create a replay with the given limits and memory budget
+    pub fn new(config: ReplayConfig, budget: MemoryBudget) -> Self {
+        let streamer_config = StreamerConfig {
+            view_distance: config.view_distance,
+            view_height: config.view_height,
+            unload_margin: config.unload_margin,
+            max_concurrent_loads: config.max_concurrent_loads,
+            prefetch: config.prefetch,
+            ..Default::default()
+        };
+        // Unbounded layer: the streamer probes every chunk in range, like a
+        // world without a chunk list
+        let layers = vec![StreamLayer::new(0, PathBuf::new())];
+        let sources: Vec<Box<dyn ChunkSource>> = vec![Box::new(MockLoader::new(&config))];
+        let streamer = ChunkStreamer::with_sources(layers, sources, budget, streamer_config);
+        let pool = MockBrickPool::new(config.brick_pool_slots);
+        Self {
+            config,
+            streamer,
+            bricks: BrickCache::new(0),
+            pool,
+            first_request: HashMap::new(),
+            seen_missing: HashSet::new(),
+            arrived: HashSet::new(),
+            brick_base: HashMap::new(),
+            next_brick_id: 0,
+        }
+    }
+
+    /// Use a custom prefetch predictor
+    pub fn with_predictor(mut self, predictor: PrefetchPredictor) -> Self {
+        self.streamer = self.streamer.with_predictor(predictor);
+        self
+    }
+
+    /// Replay every frame of a path
+    pub fn run(&mut self, path: &CameraPath) -> ReplayReport {
+        let frames = path.frames.iter()
+            .map(|f| self.step(Vec3::from(f.position), Vec3::from(f.forward), path.frame_time))
+            .collect();
+        ReplayReport::summarize(frames)
+    }
+
+    /// Replay a single frame
+    pub fn step(&mut self, camera_pos: Vec3, forward: Vec3, delta_time: f32) -> FrameReport {
+        let events = self.streamer.update(camera_pos, forward, delta_time);
+        self.bricks.begin_frame();
+        let frame = self.streamer.orchestrator().frame();
+        let mut report = FrameReport {
+            frame,
+            position: camera_pos.to_array(),
+            ..Default::default()
+        };
+
+        for event in events {
+            match event {
+                StreamEvent::Load(coord) => {
+                    self.first_request.entry(coord).or_insert(frame);
+                }
+                StreamEvent::Upload { coord, .. } => self.complete(coord, frame, &mut report),
+                StreamEvent::Unload(coord) => {
+                    // Still in range means the budget pushed it out
+                    if self.streamer.in_range(coord, camera_pos, self.config.unload_margin) {
+                        report.evictions += 1;
+                    } else {
+                        report.unloads += 1;
+                    }
+                    self.release(coord);
+                }
+                StreamEvent::ProxyUpload { .. } | StreamEvent::ProxyUnload(_) => {}
+            }
+        }
+        self.track_requests(frame);
+
+        let streamer = &self.streamer;
+        let margin = self.config.unload_margin;
+        self.seen_missing.retain(|&c| streamer.in_range(c, camera_pos, margin));
+
+        self.stream_bricks(camera_pos, forward, frame, &mut report);
+
+        let orchestrator = self.streamer.orchestrator();
+        let budget = self.streamer.budget();
+        report.resident = orchestrator.resident_count();
+        report.queued = orchestrator.queue_length();
+        report.loading = orchestrator.loading_count();
+        report.gpu_used = budget.gpu_used();
+        report.budget_exceeded = budget.gpu_used() > budget.gpu_budget();
+        report.brick_hit_rate = self.bricks.hit_rate();
+        report
+    }
+
+    /// Get the orchestrator, e.g. to inspect residency after a replay
+    pub fn orchestrator(&self) -> &StreamingOrchestrator {
+        self.streamer.orchestrator()
+    }
+
+    /// Record when queued chunks were first requested, and forget chunks
+    /// whose request was dropped or answered without data
+    fn track_requests(&mut self, frame: u32) {
+        let orchestrator = self.streamer.orchestrator();
+        let queued: HashSet<ChunkCoord> = orchestrator.queued_chunks().collect();
+        for &coord in &queued {
+            self.first_request.entry(coord).or_insert(frame);
+        }
+        self.first_request.retain(|c, _| queued.contains(c) || orchestrator.is_loading(*c));
+    }
+
+    /// Check if a chunk in range is inside the horizontal field of view
+    fn is_visible(&self, coord: ChunkCoord, camera_pos: Vec3, forward: Vec3) -> bool {
+        let to_chunk = chunk_center(coord) - camera_pos;
+        let flat = Vec3::new(to_chunk.x, 0.0, to_chunk.z);
+        let flat_forward = Vec3::new(forward.x, 0.0, forward.z).normalize_or_zero();
+        // The chunk the camera stands in, and those next to it, are always visible
+        if flat.length() <= CHUNK_SIZE as f32 * 1.5 || flat_forward == Vec3::ZERO {
+            return true;
+        }
+        let half_fov = (self.config.fov_degrees * 0.5).to_radians();
+        flat.normalize().dot(flat_forward) >= half_fov.cos()
+    }
+
+    /// Ground chunks in the streamer's range, nearest first
+    fn chunks_in_range(&self, camera_pos: Vec3) -> Vec<ChunkCoord> {
+        let size = CHUNK_SIZE as f32;
+        let reach = (self.config.view_distance / size).ceil() as i32;
+        let camera = ChunkCoord::from_world_pos(camera_pos);
+        let (min_y, max_y) = self.config.ground_levels;
+
+        let mut coords = Vec::new();
+        for x in camera.x - reach..=camera.x + reach {
+            for z in camera.z - reach..=camera.z + reach {
+                for y in min_y..=max_y {
+                    let coord = ChunkCoord::new(x, y, z);
+                    if self.streamer.in_range(coord, camera_pos, 0.0) {
+                        coords.push(coord);
+                    }
+                }
+            }
+        }
+        coords.sort_by(|a, b| {
+            chunk_center(*a).distance_squared(camera_pos)
+                .total_cmp(&chunk_center(*b).distance_squared(camera_pos))
+        });
+        coords
+    }
+
+    /// A chunk's data arrived: give it bricks
+    fn complete(&mut self, coord: ChunkCoord, frame: u32, report: &mut FrameReport) {
+        self.brick_base.insert(coord, self.next_brick_id);
+        self.next_brick_id += self.config.bricks_per_chunk;
+        self.bricks.resize(self.next_brick_id);
+
+        report.loaded += 1;
+        if let Some(requested) = self.first_request.remove(&coord) {
+            report.time_to_resident.push(frame - requested);
+        }
+        if self.seen_missing.remove(&coord) {
+            self.arrived.insert(coord);
+        }
+    }
+
+    /// Drop an unloaded chunk's bricks from the pool
+    fn release(&mut self, coord: ChunkCoord) {
+        let Some(base) = self.brick_base.remove(&coord) else { return };
+        for brick_id in base..base + self.config.bricks_per_chunk {
+            if let Some(slot) = self.bricks.get_slot(brick_id) {
+                self.pool.release(slot);
+            }
+            self.bricks.mark_evicted(brick_id);
+        }
+    }
+
+    /// Request the bricks of visible chunks and upload them into the pool
+    fn stream_bricks(&mut self, camera_pos: Vec3, forward: Vec3, frame: u32, report: &mut FrameReport) {
+        for coord in self.chunks_in_range(camera_pos) {
+            if !self.is_visible(coord, camera_pos, forward) {
+                continue;
+            }
+            let Some(&base) = self.brick_base.get(&coord) else {
+                report.missing_visible += 1;
+                self.seen_missing.insert(coord);
+                continue;
+            };
+            if self.arrived.remove(&coord) {
+                report.pop_ins += 1;
+            }
+            let priority = BrickPriority {
+                distance: chunk_center(coord).distance(camera_pos),
+                lod_level: 0,
+                request_frame: frame,
+            };
+            for brick_id in base..base + self.config.bricks_per_chunk {
+                self.bricks.request(brick_id, priority);
+                if let Some(slot) = self.bricks.get_slot(brick_id) {
+                    self.pool.touch(slot, frame);
+                }
+            }
+        }
+        // Arrived out of view: not a pop-in
+        self.arrived.clear();
+
+        for _ in 0..self.config.brick_uploads_per_frame {
+            let Some(brick_id) = self.bricks.pop_pending() else { break };
+            let Some((slot, evicted)) = self.pool.allocate(brick_id, frame) else { break };
+            if let Some((old, last_requested)) = evicted {
+                self.bricks.mark_evicted(old);
+                report.bricks_evicted += 1;
+                if last_requested == frame {
+                    report.bricks_thrashed += 1;
+                }
+            }
+            self.bricks.mark_loaded(brick_id, slot);
+            report.bricks_uploaded += 1;
+        }
+    }
+}
+
+/// World-space center of a chunk
+fn chunk_center(coord: ChunkCoord) -> Vec3 {
+    coord.world_origin() + Vec3::splat(CHUNK_SIZE as f32 * 0.5)
+}
+
+/// Stable per-chunk hash for load latency jitter
+fn coord_hash(coord: ChunkCoord) -> u32 {
+    (coord.x as u32).wrapping_mul(73_856_093)
+        ^ (coord.y as u32).wrapping_mul(19_349_663)
+        ^ (coord.z as u32).wrapping_mul(83_492_791)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::streaming::prefetch::FrustumPrefetchConfig;
+
+    fn flyover() -> CameraPath {
+        CameraPath::flyover(Vec3::new(0.0, 6.0, 0.0), Vec3::new(20.0, 0.0, 0.0), 60, 1.0 / 15.0)
+    }
+
+    fn replay(config: &ReplayConfig, budget: MemoryBudget) -> ReplayReport {
+        // A shallow frustum keeps prefetch prediction cheap in debug builds
+        let frustum = FrustumPrefetchConfig { max_depth: 16.0, ..Default::default() };
+        StreamingReplay::new(config.clone(), budget)
+            .with_predictor(PrefetchPredictor::new().with_frustum_config(frustum))
+            .run(&flyover())
+    }
+
+    #[test]
+    fn test_replay_is_deterministic() {
+        let config = ReplayConfig { view_distance: 48.0, ..Default::default() };
+        let first = replay(&config, MemoryBudget::default());
+        let second = replay(&config, MemoryBudget::default());
+
+        assert_eq!(first.to_json(), second.to_json());
+        assert_eq!(first.summary.frames, 60);
+        assert!(first.summary.chunks_loaded > 0);
+        let times = first.frames.iter().flat_map(|f| f.time_to_resident.iter());
+        assert!(times.into_iter().all(|&t| t >= 3), "loads take at least the mock latency");
+        assert!(first.summary.unloads > 0, "flying 80 m must leave chunks behind");
+    }
+
+    #[test]
+    fn test_replay_reports_budget_pressure() {
+        let config = ReplayConfig { view_distance: 48.0, latency_jitter_frames: 0, ..Default::default() };
+        // 4 MB fits 16 chunks of 256 KB, far fewer than the view range holds
+        let report = replay(&config, MemoryBudget::new(1024, 4));
+
+        assert!(report.summary.evictions > 0);
+        assert!(report.summary.peak_gpu_used <= 4 * 1024 * 1024);
+        assert_eq!(report.summary.budget_violations, 0);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["frames"].as_array().unwrap().len(), 60);
+        assert!(json["summary"]["evictions"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_camera_path_json_roundtrip() {
+        let path = flyover();
+        let parsed = CameraPath::from_json(&path.to_json()).unwrap();
+        assert_eq!(parsed.frames, path.frames);
+        assert_eq!(parsed.frame_time, path.frame_time);
+    }
+}
@@ -1,12 +1,19 @@
 //! SuperChunk - coarse-grained spatial container.
 //!
 //! A SuperChunk contains a 16x16x16 grid of regular chunks and provides
-//! LOD data for distant rendering.
+//! LOD data for distant rendering: a far-field proxy octree downsampled from
+//! its chunks, drawn wherever the full-resolution chunks are not resident.
 
 use std::time::Instant;
-use glam::Vec3;
+use glam::{UVec3, Vec3};
 
-use crate::voxel::hierarchy::{CHUNKS_PER_SUPER_CHUNK, SUPER_CHUNK_SIZE_METERS};
+use crate::voxel::brick::VoxelBrick;
+use crate::voxel::chunk::ChunkCoord;
+use crate::voxel::hierarchy::{
+    CHUNKS_PER_SUPER_CHUNK, PROXY_VOXELS_PER_CHUNK, PROXY_VOXELS_PER_SUPER_CHUNK, SUPER_CHUNK_SIZE_METERS,
+};
+use crate::voxel::svo::{Octree, OctreeBuilder, OctreeNode};
+use crate::voxel::voxel::Voxel;
 
 /// Coordinate of a SuperChunk in the world grid.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -43,6 +50,29 @@ impl SuperChunkCoord {
             z: (pos.z / SUPER_CHUNK_SIZE_METERS).floor() as i32,
         }
     }
+
+    /// Get the SuperChunk containing a chunk.
+    pub fn from_chunk(chunk: ChunkCoord) -> Self {
+        let n = CHUNKS_PER_SUPER_CHUNK as i32;
+        Self {
+            x: chunk.x.div_euclid(n),
+            y: chunk.y.div_euclid(n),
+            z: chunk.z.div_euclid(n),
+        }
+    }
+
+    /// Position of a chunk within this SuperChunk, or None if it lies outside.
+    pub fn local_chunk(&self, chunk: ChunkCoord) -> Option<(u32, u32, u32)> {
+        if Self::from_chunk(chunk) != *self {
+            return None;
+        }
+        let n = CHUNKS_PER_SUPER_CHUNK as i32;
+        Some((
+            chunk.x.rem_euclid(n) as u32,
+            chunk.y.rem_euclid(n) as u32,
+            chunk.z.rem_euclid(n) as u32,
+        ))
+    }
 }
 
 /// Bitmap tracking which chunks are present in a SuperChunk.
@@ -117,6 +147,8 @@ pub struct SuperChunk {
     pub distance_to_camera: f32,
     /// Whether this SuperChunk has any modified chunks
     pub is_dirty: bool,
+    /// Far-field proxy (1m voxels), if loaded
+    pub proxy: Option<Octree>,
 }
 
 impl SuperChunk {
@@ -128,6 +160,7 @@ impl SuperChunk {
             last_access: Instant::now(),
             distance_to_camera: f32::MAX,
             is_dirty: false,
+            proxy: None,
         }
     }
 
@@ -145,6 +178,203 @@ impl SuperChunk {
     pub fn chunk_count(&self) -> u32 {
         self.chunk_mask.count()
     }
+
+    /// Get the proxy to draw given which chunks are resident at full
+    /// resolution: their regions are cut out so they replace the proxy there.
+    ///
+    /// Returns None if there is no proxy or nothing of it is left.
+    pub fn visible_proxy(&self, resident: &ChunkPresenceMask) -> Option<Octree> {
+        let proxy = self.proxy.as_ref()?;
+        if resident.is_empty() {
+            return Some(proxy.clone());
+        }
+
+        let mut builder = ProxyBuilder::from_proxy(self.coord, proxy);
+        let n = CHUNKS_PER_SUPER_CHUNK;
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    if resident.is_present(x, y, z) {
+                        builder.clear_chunk(x, y, z);
+                    }
+                }
+            }
+        }
+        builder.build()
+    }
+}
+
+/// Builds a SuperChunk's far-field proxy: an octree of
+/// `PROXY_VOXELS_PER_SUPER_CHUNK`^3 voxels downsampled from its chunks.
+///
+/// Chunks are collapsed level by level, each level averaging its octants
+/// with `VoxelBrick::average_color` and `average_material`; a collapsed
+/// voxel is solid when at least half of its octants are.
+pub struct ProxyBuilder {
+    coord: SuperChunkCoord,
+    /// Dense proxy voxels in Z-Y-X order (x varies fastest)
+    voxels: Vec<Voxel>,
+}
+
+impl ProxyBuilder {
+    /// Create a builder for an empty proxy.
+    pub fn new(coord: SuperChunkCoord) -> Self {
+        let n = PROXY_VOXELS_PER_SUPER_CHUNK as usize;
+        Self {
+            coord,
+            voxels: vec![Voxel::EMPTY; n * n * n],
+        }
+    }
+
+    /// Create a builder starting from an existing proxy octree.
+    pub fn from_proxy(coord: SuperChunkCoord, proxy: &Octree) -> Self {
+        let mut builder = Self::new(coord);
+        let n = PROXY_VOXELS_PER_SUPER_CHUNK;
+        let cell = proxy.root_size() / n as f32;
+        let half = proxy.root_size() / 2.0;
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let local = (UVec3::new(x, y, z).as_vec3() + 0.5) * cell - half;
+                    builder.voxels[Self::index(UVec3::new(x, y, z))] = proxy.sample_voxel(local);
+                }
+            }
+        }
+        builder
+    }
+
+    /// SuperChunk this proxy belongs to.
+    pub fn coord(&self) -> SuperChunkCoord {
+        self.coord
+    }
+
+    /// Downsample a chunk octree into the proxy. Where the chunk is empty the
+    /// proxy keeps what earlier chunks (e.g. of another layer) put there.
+    ///
+    /// Returns false if the chunk lies outside this SuperChunk.
+    pub fn add_chunk(&mut self, chunk: ChunkCoord, octree: &Octree) -> bool {
+        let Some((x, y, z)) = self.coord.local_chunk(chunk) else {
+            return false;
+        };
+        let at = UVec3::new(x, y, z) * PROXY_VOXELS_PER_CHUNK;
+        self.splat_node(octree, octree.root(), PROXY_VOXELS_PER_CHUNK, at);
+        true
+    }
+
+    /// Clear the proxy voxels covered by a chunk, given its position within
+    /// the SuperChunk.
+    pub fn clear_chunk(&mut self, x: u32, y: u32, z: u32) {
+        let at = UVec3::new(x, y, z) * PROXY_VOXELS_PER_CHUNK;
+        self.fill(at, PROXY_VOXELS_PER_CHUNK, Voxel::EMPTY);
+    }
+
+    /// Build the proxy octree, or None if it has no solid voxels.
+    pub fn build(&self) -> Option<Octree> {
+        if self.voxels.iter().all(|v| v.is_empty()) {
+            return None;
+        }
+        Some(OctreeBuilder::new(PROXY_VOXELS_PER_SUPER_CHUNK).build(&self.voxels, SUPER_CHUNK_SIZE_METERS))
+    }
+
+    fn index(at: UVec3) -> usize {
+        let n = PROXY_VOXELS_PER_SUPER_CHUNK as usize;
+        (at.z as usize * n + at.y as usize) * n + at.x as usize
+    }
+
+    /// Write a node spanning `extent` proxy voxels at `at`
+    fn splat_node(&mut self, octree: &Octree, node: &OctreeNode, extent: u32, at: UVec3) {
+        if node.is_empty() {
+            return;
+        }
+        if node.is_terminal_leaf() {
+            self.splat_brick(octree.brick(node.brick_offset), extent, at);
+            return;
+        }
+        if extent == 1 {
+            self.set(at, collapse(&octant_voxels(octree, node)));
+            return;
+        }
+
+        let half = extent / 2;
+        for child in 0..8u8 {
+            if !node.is_child_valid(child) {
+                continue;
+            }
+            let child_at = at + octant_offset(child) * half;
+            if node.is_child_leaf(child) {
+                self.splat_brick(octree.brick(octree.leaf_brick_index(node, child)), half, child_at);
+            } else {
+                let child_node = octree.node(octree.child_node_index(node, child));
+                self.splat_node(octree, child_node, half, child_at);
+            }
+        }
+    }
+
+    /// Write a brick spanning `extent` proxy voxels at `at`
+    fn splat_brick(&mut self, brick: &VoxelBrick, extent: u32, at: UVec3) {
+        if extent == 1 {
+            self.set(at, collapse(brick));
+            return;
+        }
+        let half = extent / 2;
+        for (i, voxel) in brick.voxels.iter().enumerate() {
+            if !voxel.is_empty() {
+                self.fill(at + octant_offset(i as u8) * half, half, *voxel);
+            }
+        }
+    }
+
+    fn set(&mut self, at: UVec3, voxel: Voxel) {
+        if !voxel.is_empty() {
+            self.voxels[Self::index(at)] = voxel;
+        }
+    }
+
+    fn fill(&mut self, at: UVec3, extent: u32, voxel: Voxel) {
+        for z in at.z..at.z + extent {
+            for y in at.y..at.y + extent {
+                for x in at.x..at.x + extent {
+                    self.voxels[Self::index(UVec3::new(x, y, z))] = voxel;
+                }
+            }
+        }
+    }
+}
+
+/// Offset of an octant in child index order (x = bit 0, y = bit 1, z = bit 2)
+fn octant_offset(child: u8) -> UVec3 {
+    UVec3::new((child & 1) as u32, ((child >> 1) & 1) as u32, ((child >> 2) & 1) as u32)
+}
+
+/// A node's eight octants, each collapsed to one voxel
+fn octant_voxels(octree: &Octree, node: &OctreeNode) -> VoxelBrick {
+    if node.is_terminal_leaf() {
+        return *octree.brick(node.brick_offset);
+    }
+
+    let mut brick = VoxelBrick::EMPTY;
+    for child in 0..8u8 {
+        if !node.is_child_valid(child) {
+            continue;
+        }
+        brick.voxels[child as usize] = if node.is_child_leaf(child) {
+            collapse(octree.brick(octree.leaf_brick_index(node, child)))
+        } else {
+            let child_node = octree.node(octree.child_node_index(node, child));
+            collapse(&octant_voxels(octree, child_node))
+        };
+    }
+    brick
+}
+
+/// One voxel standing in for a brick: solid when at least half its voxels are
+fn collapse(brick: &VoxelBrick) -> Voxel {
+    let solid = brick.voxels.iter().filter(|v| !v.is_empty()).count();
+    if solid * 2 < brick.voxels.len() {
+        Voxel::EMPTY
+    } else {
+        Voxel::from_rgb565(brick.average_color(), brick.average_material())
+    }
 }
 
 #[cfg(test)]
@@ -170,4 +400,64 @@ mod tests {
         assert!(!mask.is_present(5, 5, 5));
         assert!(mask.is_empty());
     }
+
+    #[test]
+    fn test_chunk_to_super_chunk() {
+        let coord = SuperChunkCoord::from_chunk(ChunkCoord::new(17, -1, 0));
+        assert_eq!(coord, SuperChunkCoord::new(1, -1, 0));
+        assert_eq!(coord.local_chunk(ChunkCoord::new(17, -1, 0)), Some((1, 15, 0)));
+        assert_eq!(coord.local_chunk(ChunkCoord::new(0, 0, 0)), None);
+    }
+
+    /// Chunk whose lower half is solid stone
+    fn half_solid_chunk() -> Octree {
+        let size = 16u32;
+        let stone = Voxel::new(128, 128, 128, 2);
+        let voxels: Vec<Voxel> = (0..size * size * size)
+            .map(|i| if (i / size) % size < size / 2 { stone } else { Voxel::EMPTY })
+            .collect();
+        OctreeBuilder::new(size).build(&voxels, crate::voxel::hierarchy::CHUNK_SIZE_METERS)
+    }
+
+    #[test]
+    fn test_proxy_downsamples_chunks() {
+        let coord = SuperChunkCoord::new(0, 0, 0);
+        let mut builder = ProxyBuilder::new(coord);
+        assert!(builder.build().is_none());
+
+        assert!(builder.add_chunk(ChunkCoord::new(3, 0, 5), &half_solid_chunk()));
+        assert!(!builder.add_chunk(ChunkCoord::new(16, 0, 0), &half_solid_chunk()));
+        let proxy = builder.build().unwrap();
+        assert_eq!(proxy.root_size(), SUPER_CHUNK_SIZE_METERS);
+
+        // Chunk (3, 0, 5) spans 12..16 x 0..4 x 20..24 m; its lower 2 m are solid
+        let half = SUPER_CHUNK_SIZE_METERS / 2.0;
+        let solid = proxy.sample_voxel(Vec3::new(13.5, 0.5, 21.5) - half);
+        assert!(!solid.is_empty());
+        assert_eq!(solid.material_id, 2);
+        assert!(proxy.sample_voxel(Vec3::new(13.5, 3.5, 21.5) - half).is_empty());
+        assert!(proxy.sample_voxel(Vec3::new(1.5, 0.5, 1.5) - half).is_empty());
+    }
+
+    #[test]
+    fn test_resident_chunks_replace_proxy() {
+        let coord = SuperChunkCoord::new(0, 0, 0);
+        let mut builder = ProxyBuilder::new(coord);
+        builder.add_chunk(ChunkCoord::new(0, 0, 0), &half_solid_chunk());
+        builder.add_chunk(ChunkCoord::new(1, 0, 0), &half_solid_chunk());
+
+        let mut super_chunk = SuperChunk::new(coord);
+        assert!(super_chunk.visible_proxy(&ChunkPresenceMask::new()).is_none());
+        super_chunk.proxy = builder.build();
+
+        let half = SUPER_CHUNK_SIZE_METERS / 2.0;
+        let mut resident = ChunkPresenceMask::new();
+        resident.set_present(0, 0, 0, true);
+        let visible = super_chunk.visible_proxy(&resident).unwrap();
+        assert!(visible.sample_voxel(Vec3::new(1.5, 0.5, 1.5) - half).is_empty());
+        assert!(!visible.sample_voxel(Vec3::new(5.5, 0.5, 1.5) - half).is_empty());
+
+        resident.set_present(1, 0, 0, true);
+        assert!(super_chunk.visible_proxy(&resident).is_none());
+    }
 }
@@ -6,6 +6,7 @@
 
 use super::{Octree, OctreeNode};
 use super::classifier::{RegionClassifier, RegionHint};
+use crate::voxel::attributes::BrickAttributes;
 use crate::voxel::brick::VoxelBrick;
 use crate::voxel::voxel::{rgb_to_565, rgb565_to_rgb, Voxel};
 use crate::math::Aabb;
@@ -17,6 +18,8 @@ pub struct AdaptiveOctreeBuilder {
     size: u32,
     /// Maximum octree depth
     max_depth: u8,
+    /// Evaluate per-brick attributes alongside the voxels
+    attributes: bool,
 }
 
 impl AdaptiveOctreeBuilder {
@@ -28,9 +31,26 @@ impl AdaptiveOctreeBuilder {
         Self {
             size,
             max_depth,
+            attributes: false,
         }
     }
 
+    /// Evaluate `RegionClassifier::evaluate_attributes` for every leaf voxel and
+    /// store the result in the octree's attribute channel (classifier builds only)
+    pub fn with_attributes(mut self, attributes: bool) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Create an empty octree, carrying attributes if enabled
+    fn new_octree(&self, chunk_size: f32, node_capacity: usize, brick_capacity: usize) -> Octree {
+        let mut octree = Octree::with_capacity(chunk_size, self.max_depth, node_capacity, brick_capacity);
+        if self.attributes {
+            octree.enable_attributes();
+        }
+        octree
+    }
+
     /// Build octree from a simple voxel evaluator function.
     ///
     /// No classifier abstraction needed — just pass a function that returns
@@ -295,7 +315,7 @@ impl AdaptiveOctreeBuilder {
         match hint {
             RegionHint::Empty => {
                 // Return empty octree
-                Octree::new(chunk_size, self.max_depth)
+                self.new_octree(chunk_size, 1, 0)
             }
             RegionHint::Solid { material, color } => {
                 // Return octree with single solid root
@@ -321,7 +341,7 @@ impl AdaptiveOctreeBuilder {
         let center = chunk_origin + Vec3::splat(chunk_size * 0.5);
         let voxel = classifier.evaluate(center);
 
-        let mut octree = Octree::with_capacity(chunk_size, self.max_depth, 1, 2);
+        let mut octree = self.new_octree(chunk_size, 1, 2);
         // Reserve brick index 0 as padding — brick_offset=0 is the "no brick"
         // sentinel in OctreeNode::is_empty(), so real bricks must start at index 1.
         octree.add_brick(VoxelBrick::EMPTY);
@@ -358,7 +378,7 @@ impl AdaptiveOctreeBuilder {
         chunk_size: f32,
     ) -> Octree {
         let voxel_size = chunk_size / self.size as f32;
-        let mut octree = Octree::with_capacity(chunk_size, self.max_depth, 1024, 513);
+        let mut octree = self.new_octree(chunk_size, 1024, 513);
         // Reserve brick index 0 as padding — brick_offset=0 is the "no brick"
         // sentinel in OctreeNode::is_empty(), so real bricks must start at index 1.
         octree.add_brick(VoxelBrick::EMPTY);
@@ -619,6 +639,7 @@ impl AdaptiveOctreeBuilder {
         _node_index: u32,
     ) -> (u16, u8, bool) {
         let mut brick = VoxelBrick::EMPTY;
+        let mut attributes = BrickAttributes::EMPTY;
         let mut all_empty = true;
 
         for dz in 0..2u32 {
@@ -632,6 +653,9 @@ impl AdaptiveOctreeBuilder {
                     let voxel = classifier.evaluate(pos);
                     if !voxel.is_empty() {
                         all_empty = false;
+                        if self.attributes {
+                            attributes.set(dx as u8, dy as u8, dz as u8, classifier.evaluate_attributes(pos, voxel));
+                        }
                     }
                     brick.set(dx as u8, dy as u8, dz as u8, voxel);
                 }
@@ -644,7 +668,11 @@ impl AdaptiveOctreeBuilder {
 
         let lod_color = brick.average_color();
         let lod_material = brick.average_material();
-        octree.add_brick(brick);
+        if self.attributes {
+            octree.add_brick_with_attributes(brick, attributes);
+        } else {
+            octree.add_brick(brick);
+        }
 
         (lod_color, lod_material, false)
     }
@@ -692,6 +720,7 @@ impl AdaptiveOctreeBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::voxel::attributes::VoxelAttributes;
 
     /// Simple flat terrain classifier for testing
     struct FlatTerrainClassifier {
@@ -732,6 +761,12 @@ mod tests {
                 Voxel::EMPTY
             }
         }
+
+        fn evaluate_attributes(&self, _pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+            VoxelAttributes::NONE
+                .with_normal(Vec3::Y)
+                .with_albedo(self.color.0, self.color.1, self.color.2)
+        }
     }
 
     /// Sinusoidal terrain classifier for testing
@@ -898,4 +933,30 @@ mod tests {
         // Adaptive should be smaller than dense for terrain with ~50% solid
         assert!(adaptive_size < dense_size as usize);
     }
+
+    #[test]
+    fn test_build_with_attributes() {
+        let classifier = FlatTerrainClassifier::new(2.0);
+        let plain = AdaptiveOctreeBuilder::new(16).build(&classifier, Vec3::ZERO, 4.0);
+        assert!(!plain.has_attributes());
+
+        let octree = AdaptiveOctreeBuilder::new(16)
+            .with_attributes(true)
+            .build(&classifier, Vec3::ZERO, 4.0);
+        assert_eq!(octree.attributes_slice().unwrap().len(), octree.brick_count());
+        assert_eq!(octree.brick_count(), plain.brick_count());
+
+        // Surface voxel just below y = 2 (the octree is centered on the chunk)
+        let surface = Vec3::new(0.1, -0.1, 0.1);
+        let attrs = octree.sample_attributes(surface).unwrap();
+        assert!(attrs.normal().unwrap().dot(Vec3::Y) > 0.99);
+        assert_eq!(attrs.albedo(), Some((139, 90, 43)));
+        assert_eq!(octree.sample_voxel(surface), plain.sample_voxel(surface));
+        assert_eq!(octree.sample_attributes(Vec3::new(0.1, 1.0, 0.1)), None);
+
+        // Pruning keeps the attributes with their bricks
+        let pruned = octree.prune();
+        assert!(pruned.validate().is_empty(), "{:?}", pruned.validate());
+        assert_eq!(pruned.sample_attributes(surface), Some(attrs));
+    }
 }
@@ -5,6 +5,7 @@
 //! arbitrary volumetric content.
 
 use crate::core::types::Vec3;
+use crate::voxel::attributes::VoxelAttributes;
 use crate::voxel::voxel::Voxel;
 use crate::math::aabb::Aabb;
 
@@ -41,6 +42,13 @@ pub trait RegionClassifier: Send + Sync {
     ///
     /// This is called for leaf nodes or when classify_region returns Mixed/Unknown.
     fn evaluate(&self, pos: Vec3) -> Voxel;
+
+    /// Evaluate the extended attributes of the non-empty `voxel` at `pos`.
+    ///
+    /// Only called when building with attributes enabled. The default stores none.
+    fn evaluate_attributes(&self, _pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+        VoxelAttributes::NONE
+    }
 }
 
 impl RegionHint {
@@ -0,0 +1,320 @@
+//! Boolean (CSG) operations between octrees
+//!
+//! Every operation rebuilds the result through `AdaptiveOctreeBuilder` with a
+//! classifier that walks both operands, so uniform regions of the inputs stay
+//! uniform (and cheap) in the output. Operands may differ in `root_size` and
+//! `max_depth`; the second operand is placed with an offset in the first one's
+//! local space. Results live in the first operand's frame and are pruned.
+
+use glam::Vec3;
+
+use super::adaptive::AdaptiveOctreeBuilder;
+use super::classifier::{RegionClassifier, RegionHint};
+use super::Octree;
+use crate::math::Aabb;
+use crate::voxel::voxel::Voxel;
+
+/// Finest depth a CSG result may be built at (4096^3 voxels)
+const MAX_CSG_DEPTH: u8 = 12;
+
+/// Boolean operation applied by `CsgClassifier`
+enum CsgOp<'a> {
+    /// Remove `other` from the base
+    Difference(&'a Octree, Vec3),
+    /// Keep the base only where `other` is solid
+    Intersection(&'a Octree, Vec3),
+    /// Overwrite the base with `other` wherever `other` is solid
+    Replace(&'a Octree, Vec3),
+    /// Keep the base only inside a local-space region
+    Clip(Aabb),
+}
+
+/// Classifier combining a base octree with a second operand.
+/// Positions are in the base octree's local space.
+struct CsgClassifier<'a> {
+    base: &'a Octree,
+    op: CsgOp<'a>,
+}
+
+impl RegionClassifier for CsgClassifier<'_> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        let base = self.base.region_hint(aabb);
+        match &self.op {
+            CsgOp::Difference(other, offset) => match (base, other.region_hint(&shifted(aabb, *offset))) {
+                (RegionHint::Empty, _) | (_, RegionHint::Solid { .. }) => RegionHint::Empty,
+                (base, RegionHint::Empty) => base,
+                _ => RegionHint::Mixed,
+            },
+            CsgOp::Intersection(other, offset) => match (base, other.region_hint(&shifted(aabb, *offset))) {
+                (RegionHint::Empty, _) | (_, RegionHint::Empty) => RegionHint::Empty,
+                (base, RegionHint::Solid { .. }) => base,
+                _ => RegionHint::Mixed,
+            },
+            CsgOp::Replace(other, offset) => match other.region_hint(&shifted(aabb, *offset)) {
+                RegionHint::Empty => base,
+                solid @ RegionHint::Solid { .. } => solid,
+                _ => RegionHint::Mixed,
+            },
+            CsgOp::Clip(region) => {
+                if !overlaps(region, aabb) {
+                    RegionHint::Empty
+                } else if contains(region, aabb) || base == RegionHint::Empty {
+                    base
+                } else {
+                    RegionHint::Mixed
+                }
+            }
+        }
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        let base = self.base.sample_voxel(pos);
+        match &self.op {
+            CsgOp::Difference(other, offset) => {
+                if other.sample_voxel(pos - *offset).is_empty() { base } else { Voxel::EMPTY }
+            }
+            CsgOp::Intersection(other, offset) => {
+                if other.sample_voxel(pos - *offset).is_empty() { Voxel::EMPTY } else { base }
+            }
+            CsgOp::Replace(other, offset) => {
+                let voxel = other.sample_voxel(pos - *offset);
+                if voxel.is_empty() { base } else { voxel }
+            }
+            CsgOp::Clip(region) => {
+                if region.contains_point(pos) { base } else { Voxel::EMPTY }
+            }
+        }
+    }
+}
+
+/// Translate an AABB into an operand's local space
+fn shifted(aabb: &Aabb, offset: Vec3) -> Aabb {
+    Aabb::new(aabb.min - offset, aabb.max - offset)
+}
+
+/// Overlap with non-zero volume (touching faces don't count)
+fn overlaps(a: &Aabb, b: &Aabb) -> bool {
+    a.min.cmplt(b.max).all() && a.max.cmpgt(b.min).all()
+}
+
+/// Whether `outer` fully contains `inner`
+fn contains(outer: &Aabb, inner: &Aabb) -> bool {
+    outer.min.cmple(inner.min).all() && outer.max.cmpge(inner.max).all()
+}
+
+/// Region hint for a brick that may be uniform.
+/// Only flag-free voxels can be rebuilt as solid leaves without losing data.
+fn uniform_hint(uniform: Option<Voxel>) -> RegionHint {
+    match uniform {
+        Some(voxel) if voxel.is_empty() => RegionHint::Empty,
+        Some(voxel) if voxel.flags == 0 => RegionHint::Solid {
+            material: voxel.material_id,
+            color: voxel.color,
+        },
+        _ => RegionHint::Mixed,
+    }
+}
+
+impl Octree {
+    /// Classify a local-space region of this octree.
+    ///
+    /// Walks down to the smallest node containing the region: empty space and
+    /// uniform flag-free bricks give `Empty`/`Solid`, anything else `Mixed`.
+    /// Regions partly outside the octree are `Empty` only if the part inside is.
+    pub fn region_hint(&self, region: &Aabb) -> RegionHint {
+        let half = self.root_size() / 2.0;
+        let mut bounds = Aabb::new(Vec3::splat(-half), Vec3::splat(half));
+        if self.is_empty() || !overlaps(&bounds, region) {
+            return RegionHint::Empty;
+        }
+        let mut node = self.root();
+        if node.is_empty() {
+            return RegionHint::Empty;
+        }
+        if !contains(&bounds, region) {
+            return RegionHint::Mixed;
+        }
+
+        loop {
+            if node.is_empty() {
+                return RegionHint::Empty;
+            }
+            if node.is_terminal_leaf() {
+                return uniform_hint(self.brick(node.brick_offset).is_uniform());
+            }
+            if bounds.size().x <= region.size().x * 1.001 {
+                return RegionHint::Mixed;
+            }
+
+            // Find the single child holding the region; straddling regions are
+            // only resolved when every overlapped child is missing
+            let mut holder = None;
+            let mut overlapped = 0;
+            for child_idx in 0..8u8 {
+                let child_bounds = bounds.child_octant(child_idx);
+                if !overlaps(&child_bounds, region) {
+                    continue;
+                }
+                if node.child_valid_mask() & (1 << child_idx) != 0 {
+                    overlapped += 1;
+                }
+                holder = Some((child_idx, child_bounds));
+            }
+            let Some((child_idx, child_bounds)) = holder else {
+                return RegionHint::Empty;
+            };
+            if overlapped == 0 {
+                return RegionHint::Empty;
+            }
+            if !contains(&child_bounds, region) {
+                return RegionHint::Mixed;
+            }
+            if node.child_valid_mask() & (1 << child_idx) == 0 {
+                return RegionHint::Empty;
+            }
+
+            bounds = child_bounds;
+            if node.child_leaf_mask() & (1 << child_idx) != 0 {
+                return uniform_hint(self.brick(self.leaf_brick_index(node, child_idx)).is_uniform());
+            }
+            node = self.node(self.child_node_index(node, child_idx));
+        }
+    }
+
+    /// Subtract `other` (centered at `offset` in this octree's local space).
+    pub fn merge_difference(&self, other: &Octree, offset: Vec3) -> Octree {
+        let depth = self.csg_depth(Some(other));
+        self.build_csg(CsgOp::Difference(other, offset), depth)
+    }
+
+    /// Keep only the voxels that are also solid in `other` (centered at `offset`).
+    pub fn merge_intersection(&self, other: &Octree, offset: Vec3) -> Octree {
+        let depth = self.csg_depth(Some(other));
+        self.build_csg(CsgOp::Intersection(other, offset), depth)
+    }
+
+    /// Masked copy: overwrite voxels with `mask`'s wherever `mask` (centered at
+    /// `offset`) is solid. Unlike `merge_union`, the mask wins where both are solid.
+    pub fn replace_where(&self, mask: &Octree, offset: Vec3) -> Octree {
+        let depth = self.csg_depth(Some(mask));
+        self.build_csg(CsgOp::Replace(mask, offset), depth)
+    }
+
+    /// Keep only the voxels whose centers lie inside `region` (local space).
+    /// The result keeps this octree's size and frame.
+    pub fn extract_region(&self, region: &Aabb) -> Octree {
+        let depth = self.csg_depth(None);
+        self.build_csg(CsgOp::Clip(*region), depth)
+    }
+
+    /// Depth fine enough for both operands' voxels in this octree's frame
+    fn csg_depth(&self, other: Option<&Octree>) -> u8 {
+        let other_depth = other
+            .filter(|other| !other.is_empty())
+            .map(|other| (self.root_size() / other.voxel_size()).log2().ceil().max(0.0) as u8)
+            .unwrap_or(0);
+        self.max_depth().max(other_depth).min(MAX_CSG_DEPTH)
+    }
+
+    fn build_csg(&self, op: CsgOp<'_>, depth: u8) -> Octree {
+        let classifier = CsgClassifier { base: self, op };
+        let half = self.root_size() / 2.0;
+        AdaptiveOctreeBuilder::new(1u32 << depth)
+            .build(&classifier, Vec3::splat(-half), self.root_size())
+            .prune()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STONE: Voxel = Voxel { color: 0x8410, material_id: 4, flags: 0 };
+    const RED: Voxel = Voxel { color: 0xF800, material_id: 7, flags: 0 };
+
+    /// 4m cube at 16^3, solid below local y = 0
+    fn ground() -> Octree {
+        AdaptiveOctreeBuilder::new(16).build_simple(
+            &|pos: Vec3| if pos.y < 0.0 { STONE } else { Voxel::EMPTY },
+            Vec3::splat(-2.0),
+            4.0,
+        )
+    }
+
+    /// 2m cube at 32^3 (finer voxels) holding a sphere of radius 0.75
+    fn ball() -> Octree {
+        AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.length() < 0.75 { RED } else { Voxel::EMPTY },
+            Vec3::splat(-1.0),
+            2.0,
+        )
+    }
+
+    /// Voxel centers of a grid covering [-2, 2]^3 at `resolution` per axis
+    fn sample_points(resolution: u32) -> impl Iterator<Item = Vec3> {
+        let step = 4.0 / resolution as f32;
+        (0..resolution.pow(3)).map(move |i| {
+            let cell = Vec3::new(
+                (i % resolution) as f32,
+                ((i / resolution) % resolution) as f32,
+                (i / (resolution * resolution)) as f32,
+            );
+            (cell + Vec3::splat(0.5)) * step - Vec3::splat(2.0)
+        })
+    }
+
+    #[test]
+    fn test_difference_and_intersection() {
+        let ground = ground();
+        let ball = ball();
+        let offset = Vec3::new(0.5, 0.0, -0.5);
+
+        let carved = ground.merge_difference(&ball, offset);
+        let overlap = ground.merge_intersection(&ball, offset);
+        assert_eq!(carved.max_depth(), 6);
+        assert!(carved.validate().is_empty());
+        assert!(overlap.validate().is_empty());
+
+        for pos in sample_points(64) {
+            let in_ground = !ground.sample_voxel(pos).is_empty();
+            let in_ball = !ball.sample_voxel(pos - offset).is_empty();
+            let expected_carved = if in_ground && !in_ball { STONE } else { Voxel::EMPTY };
+            let expected_overlap = if in_ground && in_ball { STONE } else { Voxel::EMPTY };
+            assert_eq!(carved.sample_voxel(pos), expected_carved, "difference at {:?}", pos);
+            assert_eq!(overlap.sample_voxel(pos), expected_overlap, "intersection at {:?}", pos);
+        }
+    }
+
+    #[test]
+    fn test_replace_where_prefers_mask() {
+        let ground = ground();
+        let ball = ball();
+        let offset = Vec3::new(0.0, -0.25, 0.0);
+        let replaced = ground.replace_where(&ball, offset);
+        assert!(replaced.validate().is_empty());
+
+        for pos in sample_points(64) {
+            let mask = ball.sample_voxel(pos - offset);
+            let expected = if mask.is_empty() { ground.sample_voxel(pos) } else { mask };
+            assert_eq!(replaced.sample_voxel(pos), expected, "at {:?}", pos);
+        }
+    }
+
+    #[test]
+    fn test_extract_region() {
+        let ground = ground();
+        let region = Aabb::new(Vec3::new(-1.0, -2.0, -1.0), Vec3::new(1.0, -0.5, 1.0));
+        let clipped = ground.extract_region(&region);
+        assert_eq!(clipped.root_size(), ground.root_size());
+
+        for pos in sample_points(16) {
+            let expected = if region.contains_point(pos) { ground.sample_voxel(pos) } else { Voxel::EMPTY };
+            assert_eq!(clipped.sample_voxel(pos), expected, "at {:?}", pos);
+        }
+
+        // Operands that don't overlap leave the base untouched / empty
+        let far = Vec3::splat(10.0);
+        assert!(ground.merge_intersection(&ball(), far).is_empty());
+        assert_eq!(ground.merge_difference(&ball(), far).sample_voxel(Vec3::new(0.1, -1.0, 0.1)), STONE);
+    }
+}
@@ -0,0 +1,540 @@
+//! Shared subtree dictionary - node and brick runs shared across chunks
+//!
+//! `SvdagBuilder::build_full` only shares data within one chunk, but terrain
+//! repeats across chunks too: solid stone, identical grass surfaces. A world
+//! layer can keep one `SubtreeDictionary` of the node and brick runs that
+//! many chunks contain, stored and uploaded once.
+//!
+//! A chunk node whose `child_offset` or `brick_offset` has `DICTIONARY_REF`
+//! set addresses that run in the dictionary instead of the chunk's own
+//! arrays. Dictionary nodes only reference the dictionary, so the GPU can
+//! follow them unchanged once `OctreeBuffer::upload_dictionary` has placed
+//! the dictionary at the start of its buffers. Chunks with references are
+//! not self-contained: `SubtreeDictionary::resolve` copies the referenced
+//! runs back in before a chunk is sampled or edited on the CPU.
+//!
+//! Chunks carrying brick attributes are left alone: the dictionary holds
+//! plain bricks only.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Octree, OctreeNode};
+use crate::voxel::brick::VoxelBrick;
+
+/// Offset flag marking a child or brick run stored in the dictionary
+pub const DICTIONARY_REF: u32 = 1 << 31;
+
+/// Content hash of a subtree or brick run (FNV-1a, 128 bit so runs from
+/// different chunks can be matched without comparing their contents)
+type RunKey = u128;
+
+struct KeyHasher(u128);
+
+impl KeyHasher {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn new(tag: u8) -> Self {
+        let mut hasher = Self(Self::OFFSET);
+        hasher.write(&[tag]);
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u128).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn finish(self) -> RunKey {
+        self.0
+    }
+}
+
+/// A node's child and brick runs in the source octree
+struct NodeRuns {
+    /// Content key of the whole subtree
+    key: RunKey,
+    /// Run key and source indices of the internal children
+    children: Option<(RunKey, Vec<u32>)>,
+    /// Run key and source indices of the leaf bricks (or the terminal brick)
+    bricks: Option<(RunKey, Vec<u32>)>,
+}
+
+/// Runs of every node reachable from the root (`None` for orphans)
+fn node_runs(octree: &Octree) -> Vec<Option<NodeRuns>> {
+    let mut runs: Vec<Option<NodeRuns>> = (0..octree.node_count()).map(|_| None).collect();
+    if !runs.is_empty() {
+        collect_runs(octree, 0, &mut runs);
+    }
+    runs
+}
+
+fn collect_runs(octree: &Octree, index: u32, runs: &mut [Option<NodeRuns>]) -> RunKey {
+    if let Some(done) = &runs[index as usize] {
+        return done.key;
+    }
+
+    let node = *octree.node(index);
+    let internal = node.child_valid_mask() & !node.child_leaf_mask();
+    let leaves = node.child_valid_mask() & node.child_leaf_mask();
+
+    let children = (internal != 0).then(|| {
+        let indices: Vec<u32> = (0..8u8)
+            .filter(|&child_idx| internal & (1 << child_idx) != 0)
+            .map(|child_idx| octree.child_node_index(&node, child_idx))
+            .collect();
+        let mut hasher = KeyHasher::new(b'c');
+        for &child in &indices {
+            hasher.write(&collect_runs(octree, child, runs).to_le_bytes());
+        }
+        (hasher.finish(), indices)
+    });
+
+    let brick_indices: Vec<u32> = if node.is_terminal_leaf() {
+        vec![node.brick_offset]
+    } else {
+        (0..8u8)
+            .filter(|&child_idx| leaves & (1 << child_idx) != 0)
+            .map(|child_idx| octree.leaf_brick_index(&node, child_idx))
+            .collect()
+    };
+    let bricks = (!brick_indices.is_empty()).then(|| {
+        let mut hasher = KeyHasher::new(b'b');
+        for &brick in &brick_indices {
+            hasher.write(bytemuck::bytes_of(octree.brick(brick)));
+        }
+        (hasher.finish(), brick_indices)
+    });
+
+    let mut record = node;
+    record.child_offset = 0;
+    record.brick_offset = 0;
+    let mut hasher = KeyHasher::new(b'n');
+    hasher.write(bytemuck::bytes_of(&record));
+    hasher.write(&children.as_ref().map_or(0, |(key, _)| *key).to_le_bytes());
+    hasher.write(&bricks.as_ref().map_or(0, |(key, _)| *key).to_le_bytes());
+    let key = hasher.finish();
+
+    runs[index as usize] = Some(NodeRuns { key, children, bricks });
+    key
+}
+
+/// Node and brick runs shared by the chunks of a world layer
+#[derive(Clone, Debug)]
+pub struct SubtreeDictionary {
+    /// Dictionary nodes; offsets are dictionary-local
+    nodes: Vec<OctreeNode>,
+    /// Dictionary bricks; brick 0 is padding so terminal leaves never use it
+    bricks: Vec<VoxelBrick>,
+    /// Child run key -> first node of the run
+    node_runs: HashMap<RunKey, u32>,
+    /// Brick run key -> first brick of the run
+    brick_runs: HashMap<RunKey, u32>,
+}
+
+impl SubtreeDictionary {
+    /// Create an empty dictionary
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            bricks: vec![VoxelBrick::EMPTY],
+            node_runs: HashMap::new(),
+            brick_runs: HashMap::new(),
+        }
+    }
+
+    /// Rebuild a dictionary from stored nodes and bricks.
+    ///
+    /// The result can resolve and upload chunks, but new chunks shared
+    /// against it would not find the existing runs.
+    pub fn from_parts(nodes: Vec<OctreeNode>, bricks: Vec<VoxelBrick>) -> Self {
+        Self {
+            nodes,
+            bricks,
+            node_runs: HashMap::new(),
+            brick_runs: HashMap::new(),
+        }
+    }
+
+    /// Get slice of all dictionary nodes
+    pub fn nodes_slice(&self) -> &[OctreeNode] {
+        &self.nodes
+    }
+
+    /// Get slice of all dictionary bricks
+    pub fn bricks_slice(&self) -> &[VoxelBrick] {
+        &self.bricks
+    }
+
+    /// Get number of nodes
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Get number of bricks (including the padding brick)
+    pub fn brick_count(&self) -> usize {
+        self.bricks.len()
+    }
+
+    /// Check if nothing has been shared
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.bricks.len() <= 1
+    }
+
+    /// Calculate memory usage in bytes
+    pub fn memory_usage(&self) -> usize {
+        std::mem::size_of::<OctreeNode>() * self.nodes.len()
+            + std::mem::size_of::<VoxelBrick>() * self.bricks.len()
+    }
+
+    /// Copy the dictionary runs a chunk references into the chunk, giving a
+    /// self-contained octree
+    pub fn resolve(&self, octree: &Octree) -> Octree {
+        if octree.has_attributes() {
+            return octree.clone();
+        }
+        let mut resolver = Resolver {
+            dictionary: self,
+            nodes: octree.nodes_slice().to_vec(),
+            bricks: octree.bricks_slice().to_vec(),
+            node_runs: HashMap::new(),
+            brick_runs: HashMap::new(),
+        };
+        // Only the chunk's own nodes carry references
+        for index in 0..octree.node_count() {
+            let node = resolver.nodes[index];
+            resolver.nodes[index] = resolver.localize(node, false);
+        }
+
+        let mut result = Octree::from_serialized(octree.root_size(), octree.max_depth(), resolver.nodes, resolver.bricks);
+        result.set_dense_children(octree.dense_children());
+        result
+    }
+
+    /// Find or add a source node run (and everything below it)
+    fn insert_node_run(&mut self, octree: &Octree, runs: &[Option<NodeRuns>], children: &(RunKey, Vec<u32>)) -> u32 {
+        if let Some(&start) = self.node_runs.get(&children.0) {
+            return start;
+        }
+        let records: Vec<OctreeNode> = children.1.iter()
+            .map(|&child| self.insert_node(octree, runs, child))
+            .collect();
+        let start = self.nodes.len() as u32;
+        self.nodes.extend_from_slice(&records);
+        self.node_runs.insert(children.0, start);
+        start
+    }
+
+    /// Dictionary record for a source node, adding its runs
+    fn insert_node(&mut self, octree: &Octree, runs: &[Option<NodeRuns>], index: u32) -> OctreeNode {
+        let info = runs[index as usize].as_ref().expect("node reachable from the root");
+        let mut node = *octree.node(index);
+        node.child_offset = 0;
+        node.brick_offset = 0;
+        if let Some(children) = &info.children {
+            node.child_offset = self.insert_node_run(octree, runs, children);
+        }
+        if let Some(bricks) = &info.bricks {
+            node.brick_offset = self.insert_brick_run(octree, bricks);
+        }
+        node
+    }
+
+    /// Find or add a source brick run
+    fn insert_brick_run(&mut self, octree: &Octree, bricks: &(RunKey, Vec<u32>)) -> u32 {
+        if let Some(&start) = self.brick_runs.get(&bricks.0) {
+            return start;
+        }
+        let start = self.bricks.len() as u32;
+        self.bricks.extend(bricks.1.iter().map(|&brick| *octree.brick(brick)));
+        self.brick_runs.insert(bricks.0, start);
+        start
+    }
+}
+
+impl Default for SubtreeDictionary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copies dictionary runs into a chunk for `SubtreeDictionary::resolve`
+struct Resolver<'a> {
+    dictionary: &'a SubtreeDictionary,
+    nodes: Vec<OctreeNode>,
+    bricks: Vec<VoxelBrick>,
+    /// Dictionary run start -> chunk run start
+    node_runs: HashMap<u32, u32>,
+    brick_runs: HashMap<u32, u32>,
+}
+
+impl Resolver<'_> {
+    /// Point a node's dictionary offsets at chunk copies. Chunk nodes only
+    /// reference the dictionary through `DICTIONARY_REF`; every offset of a
+    /// dictionary node is dictionary-local.
+    fn localize(&mut self, mut node: OctreeNode, in_dictionary: bool) -> OctreeNode {
+        let dictionary_offset = |offset: u32| {
+            if in_dictionary {
+                Some(offset)
+            } else {
+                (offset & DICTIONARY_REF != 0).then_some(offset & !DICTIONARY_REF)
+            }
+        };
+        let valid = node.child_valid_mask();
+        let leaf = node.child_leaf_mask();
+
+        let internal = (valid & !leaf).count_ones();
+        if internal > 0 && let Some(start) = dictionary_offset(node.child_offset) {
+            node.child_offset = self.copy_node_run(start, internal);
+        }
+        let bricks = if node.is_terminal_leaf() { 1 } else { (valid & leaf).count_ones() };
+        if bricks > 0 && let Some(start) = dictionary_offset(node.brick_offset) {
+            node.brick_offset = self.copy_brick_run(start, bricks);
+        }
+        node
+    }
+
+    fn copy_node_run(&mut self, start: u32, len: u32) -> u32 {
+        if let Some(&local) = self.node_runs.get(&start) {
+            return local;
+        }
+        let dictionary = self.dictionary;
+        let records: Vec<OctreeNode> = dictionary.nodes[start as usize..(start + len) as usize]
+            .iter()
+            .map(|&node| self.localize(node, true))
+            .collect();
+        let local = self.nodes.len() as u32;
+        self.nodes.extend_from_slice(&records);
+        self.node_runs.insert(start, local);
+        local
+    }
+
+    fn copy_brick_run(&mut self, start: u32, len: u32) -> u32 {
+        if let Some(&local) = self.brick_runs.get(&start) {
+            return local;
+        }
+        // Keep terminal leaves off brick 0
+        if self.bricks.is_empty() {
+            self.bricks.push(VoxelBrick::EMPTY);
+        }
+        let local = self.bricks.len() as u32;
+        self.bricks.extend_from_slice(&self.dictionary.bricks[start as usize..(start + len) as usize]);
+        self.brick_runs.insert(start, local);
+        local
+    }
+}
+
+/// Builds a `SubtreeDictionary` from the chunks of a world layer in two
+/// passes: `count_chunk` every chunk, then `share_chunk` every chunk to get
+/// the version that references the dictionary.
+pub struct DictionaryBuilder {
+    /// Runs must appear in at least this many chunks to be shared
+    min_chunks: u32,
+    /// Run key -> number of chunks containing it
+    counts: HashMap<RunKey, u32>,
+    dictionary: SubtreeDictionary,
+}
+
+impl DictionaryBuilder {
+    /// Create a builder sharing runs found in two or more chunks
+    pub fn new() -> Self {
+        Self {
+            min_chunks: 2,
+            counts: HashMap::new(),
+            dictionary: SubtreeDictionary::new(),
+        }
+    }
+
+    /// Share only runs found in at least `min_chunks` chunks
+    pub fn with_min_chunks(mut self, min_chunks: u32) -> Self {
+        self.min_chunks = min_chunks.max(1);
+        self
+    }
+
+    /// First pass: count the node and brick runs of a chunk
+    pub fn count_chunk(&mut self, octree: &Octree) {
+        if octree.has_attributes() {
+            return;
+        }
+        let runs = node_runs(octree);
+        let keys: HashSet<RunKey> = runs.iter()
+            .flatten()
+            .flat_map(|info| [info.children.as_ref().map(|c| c.0), info.bricks.as_ref().map(|b| b.0)])
+            .flatten()
+            .collect();
+        for key in keys {
+            *self.counts.entry(key).or_insert(0) += 1;
+        }
+    }
+
+    /// Second pass: move the chunk's common runs into the dictionary and
+    /// return the chunk referencing them (packed, root at index 0)
+    pub fn share_chunk(&mut self, octree: &Octree) -> Octree {
+        if octree.node_count() == 0 || octree.has_attributes() {
+            return octree.clone();
+        }
+        let runs = node_runs(octree);
+        let mut local = LocalChunk {
+            nodes: vec![OctreeNode::empty()],
+            bricks: Vec::new(),
+            node_runs: HashMap::new(),
+            brick_runs: HashMap::new(),
+        };
+        local.nodes[0] = self.share_node(octree, &runs, 0, &mut local);
+
+        let mut result = Octree::with_capacity(octree.root_size(), octree.max_depth(), local.nodes.len(), local.bricks.len());
+        *result.root_mut() = local.nodes[0];
+        for node in local.nodes.iter().skip(1) {
+            result.add_node(*node);
+        }
+        for brick in &local.bricks {
+            result.add_brick(*brick);
+        }
+        result
+    }
+
+    /// The dictionary built so far
+    pub fn dictionary(&self) -> &SubtreeDictionary {
+        &self.dictionary
+    }
+
+    /// Finish building and return the dictionary
+    pub fn finish(self) -> SubtreeDictionary {
+        self.dictionary
+    }
+
+    fn is_shared(&self, key: RunKey) -> bool {
+        self.counts.get(&key).is_some_and(|&count| count >= self.min_chunks)
+    }
+
+    /// Chunk record for a source node, placing its runs locally or in the dictionary
+    fn share_node(&mut self, octree: &Octree, runs: &[Option<NodeRuns>], index: u32, local: &mut LocalChunk) -> OctreeNode {
+        let info = runs[index as usize].as_ref().expect("node reachable from the root");
+        let mut node = *octree.node(index);
+        node.child_offset = 0;
+        node.brick_offset = 0;
+
+        if let Some(children) = &info.children {
+            node.child_offset = if self.is_shared(children.0) {
+                DICTIONARY_REF | self.dictionary.insert_node_run(octree, runs, children)
+            } else if let Some(&start) = local.node_runs.get(&children.0) {
+                start
+            } else {
+                let records: Vec<OctreeNode> = children.1.iter()
+                    .map(|&child| self.share_node(octree, runs, child, local))
+                    .collect();
+                let start = local.nodes.len() as u32;
+                local.nodes.extend_from_slice(&records);
+                local.node_runs.insert(children.0, start);
+                start
+            };
+        }
+
+        if let Some(bricks) = &info.bricks {
+            let terminal = octree.node(index).is_terminal_leaf();
+            node.brick_offset = if self.is_shared(bricks.0) {
+                DICTIONARY_REF | self.dictionary.insert_brick_run(octree, bricks)
+            } else if let Some(&start) = local.brick_runs.get(&bricks.0)
+                && (start != 0 || !terminal)
+            {
+                start
+            } else {
+                if terminal && local.bricks.is_empty() {
+                    local.bricks.push(VoxelBrick::EMPTY);
+                }
+                let start = local.bricks.len() as u32;
+                local.bricks.extend(bricks.1.iter().map(|&brick| *octree.brick(brick)));
+                local.brick_runs.entry(bricks.0).or_insert(start);
+                start
+            };
+        }
+        node
+    }
+}
+
+impl Default for DictionaryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chunk-local output of `DictionaryBuilder::share_chunk`
+struct LocalChunk {
+    nodes: Vec<OctreeNode>,
+    bricks: Vec<VoxelBrick>,
+    node_runs: HashMap<RunKey, u32>,
+    brick_runs: HashMap<RunKey, u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::svo::{AdaptiveOctreeBuilder, SvdagBuilder};
+    use crate::voxel::voxel::Voxel;
+    use glam::Vec3;
+
+    /// Ground at `height` (in a 4m chunk), with a step up for x >= `step_x`
+    fn ground_chunk(height: f32, step_x: f32) -> Octree {
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| {
+                let top = if pos.x >= step_x { height + 0.6 } else { height };
+                if pos.y < top { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY }
+            },
+            Vec3::ZERO,
+            4.0,
+        );
+        SvdagBuilder::new().build_full(&octree)
+    }
+
+    fn assert_same_voxels(a: &Octree, b: &Octree) {
+        let step = 4.0 / 32.0;
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * step - Vec3::splat(2.0 - step / 2.0);
+                    assert_eq!(a.sample_voxel(p), b.sample_voxel(p), "mismatch at {:?}", p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_share_and_resolve() {
+        let chunks = [ground_chunk(1.3, 2.0), ground_chunk(1.3, 3.0), ground_chunk(2.1, 1.0)];
+
+        let mut builder = DictionaryBuilder::new();
+        for chunk in &chunks {
+            builder.count_chunk(chunk);
+        }
+        let shared: Vec<Octree> = chunks.iter().map(|chunk| builder.share_chunk(chunk)).collect();
+        let dictionary = builder.finish();
+        assert!(!dictionary.is_empty());
+
+        for (chunk, shared) in chunks.iter().zip(&shared) {
+            assert!(shared.node_count() + shared.brick_count() < chunk.node_count() + chunk.brick_count());
+            assert!(shared.nodes_slice().iter().any(|n| (n.child_offset | n.brick_offset) & DICTIONARY_REF != 0));
+
+            let resolved = dictionary.resolve(shared);
+            assert!(resolved.validate().is_empty(), "{:?}", resolved.validate());
+            assert_same_voxels(&resolved, chunk);
+        }
+
+        // A dictionary read back from its parts resolves the same way
+        let stored = SubtreeDictionary::from_parts(dictionary.nodes_slice().to_vec(), dictionary.bricks_slice().to_vec());
+        assert_same_voxels(&stored.resolve(&shared[0]), &chunks[0]);
+    }
+
+    #[test]
+    fn test_unshared_chunk_is_unchanged() {
+        let chunk = ground_chunk(1.3, 2.0);
+        let mut builder = DictionaryBuilder::new().with_min_chunks(2);
+        builder.count_chunk(&chunk);
+        let shared = builder.share_chunk(&chunk);
+
+        assert!(builder.dictionary().is_empty());
+        assert!(shared.nodes_slice().iter().all(|n| (n.child_offset | n.brick_offset) & DICTIONARY_REF == 0));
+        assert!(shared.validate().is_empty(), "{:?}", shared.validate());
+        assert_same_voxels(&shared, &chunk);
+    }
+}
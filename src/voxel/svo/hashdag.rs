@@ -3,10 +3,19 @@
 //! A HashDAG stores nodes per-level where identical subtrees share the same storage
 //! regardless of position. This provides maximum deduplication since nodes are
 //! identified by their content hash rather than their position in the tree.
+//!
+//! The DAG can be edited in place: only the nodes on the path from the root to
+//! each changed brick are rebuilt and re-interned, and nodes/bricks whose
+//! reference count drops to zero are released immediately, so an edit costs
+//! O(depth) instead of a full octree rebuild.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use glam::Vec3;
 use super::{Octree, OctreeNode};
+use crate::math::Aabb;
 use crate::voxel::brick::VoxelBrick;
+use crate::voxel::edit::EditDelta;
+use crate::voxel::voxel::{rgb_to_565, rgb565_to_rgb, Voxel};
 
 /// A content hash for DAG nodes
 pub type NodeHash = u64;
@@ -26,47 +35,84 @@ pub struct HashDagNode {
     pub lod_material: u16,
 }
 
+impl HashDagNode {
+    fn valid_mask(&self) -> u8 {
+        (self.flags & 0xFF) as u8
+    }
+
+    fn leaf_mask(&self) -> u8 {
+        ((self.flags >> 8) & 0xFF) as u8
+    }
+
+    /// Terminal leaf: no children, one brick covering the whole node
+    fn terminal_brick(&self) -> Option<NodeHash> {
+        if self.valid_mask() == 0 {
+            self.brick_hashes.first().copied()
+        } else {
+            None
+        }
+    }
+}
+
+/// A child slot of a node while it is being rebuilt by an edit.
+#[derive(Clone, Copy)]
+enum EditChild {
+    Empty,
+    /// Leaf brick child (only below the brick level)
+    Brick(VoxelBrick),
+    /// Terminal-leaf child node that hasn't been interned yet
+    Terminal(VoxelBrick),
+    /// Interned child node
+    Node(NodeHash),
+}
+
 /// Content-addressable DAG for maximum voxel compression.
 ///
 /// Nodes are stored per-level (level 0 = root, level N = leaves).
 /// Identical subtrees share the same node regardless of position.
+/// Every node and brick carries a reference count (parents referencing it,
+/// plus one for the root) so edits can free storage they orphan.
 pub struct HashDag {
     /// Level-wise node storage (level 0 = root)
     levels: Vec<HashMap<NodeHash, HashDagNode>>,
     /// Brick storage (deduplicated)
     bricks: HashMap<NodeHash, VoxelBrick>,
-    /// Root hash
+    /// Reference counts per level, keyed like `levels`
+    node_refs: Vec<HashMap<NodeHash, u32>>,
+    /// Reference counts keyed like `bricks`
+    brick_refs: HashMap<NodeHash, u32>,
+    /// Root hash (no entry in level 0 means the DAG is empty)
     root: NodeHash,
     /// Root size in meters
     root_size: f32,
     /// Maximum depth
     max_depth: usize,
-    /// Total unique nodes
-    total_nodes: usize,
 }
 
 impl HashDag {
+    /// Create an empty HashDAG covering `root_size` meters with `max_depth` levels.
+    pub fn new(root_size: f32, max_depth: u8) -> Self {
+        let max_depth = max_depth as usize;
+        Self {
+            levels: (0..=max_depth).map(|_| HashMap::new()).collect(),
+            bricks: HashMap::new(),
+            node_refs: (0..=max_depth).map(|_| HashMap::new()).collect(),
+            brick_refs: HashMap::new(),
+            root: 0,
+            root_size,
+            max_depth,
+        }
+    }
+
     /// Build a HashDAG from an existing Octree.
     pub fn from_octree(octree: &Octree) -> Self {
-        let max_depth = octree.max_depth() as usize;
+        let mut dag = Self::new(octree.root_size(), octree.max_depth());
 
         // Handle empty octrees (root node only, no bricks)
-        if octree.brick_count() == 0 {
-            return Self {
-                levels: (0..=max_depth).map(|_| HashMap::new()).collect(),
-                bricks: HashMap::new(),
-                root: 0,
-                root_size: octree.root_size(),
-                max_depth,
-                total_nodes: 0,
-            };
+        if octree.brick_count() == 0 || octree.root().is_empty() {
+            return dag;
         }
 
-        // Initialize level storage
-        let mut levels: Vec<HashMap<NodeHash, HashDagNode>> =
-            (0..=max_depth).map(|_| HashMap::new()).collect();
-        let mut bricks = HashMap::new();
-
         // Phase 1: Hash all bricks using FNV-1a
         let old_bricks = octree.bricks_slice();
         let mut brick_hashes = Vec::with_capacity(old_bricks.len());
@@ -74,71 +120,23 @@ impl HashDag {
         for brick in old_bricks {
             let hash = Self::hash_brick(brick);
             brick_hashes.push(hash);
-            bricks.entry(hash).or_insert(*brick);
         }
 
-        // Phase 2: Process octree bottom-up, level by level
-        // Build mapping from old node indices to their hashes at each level
-        let mut node_to_hash: Vec<Option<NodeHash>> = vec![None; octree.node_count()];
-        let mut node_to_level: Vec<usize> = vec![0; octree.node_count()];
-
-        // First pass: determine depth of each node
-        Self::compute_node_depths(octree, 0, 0, &mut node_to_level);
-
-        // Second pass: hash nodes bottom-up
-        let root_hash = Self::hash_octree_recursive(
+        // Phase 2: hash nodes bottom-up. SVDAG octrees can share a node between
+        // levels, so memoize per (node, level).
+        let mut node_to_hash: HashMap<(u32, usize), NodeHash> = HashMap::new();
+        dag.root = Self::hash_octree_recursive(
             octree,
             0,
             0,
             &brick_hashes,
-            &node_to_level,
             &mut node_to_hash,
-            &mut levels,
+            &mut dag.levels,
+            &mut dag.bricks,
         );
 
-        let total_nodes = levels.iter().map(|level| level.len()).sum();
-
-        Self {
-            levels,
-            bricks,
-            root: root_hash,
-            root_size: octree.root_size(),
-            max_depth,
-            total_nodes,
-        }
-    }
-
-    /// Compute depth of each node in the tree
-    fn compute_node_depths(
-        octree: &Octree,
-        node_idx: u32,
-        depth: usize,
-        node_to_level: &mut [usize],
-    ) {
-        node_to_level[node_idx as usize] = depth;
-
-        let node = octree.node(node_idx);
-        let valid_mask = node.child_valid_mask();
-        let leaf_mask = node.child_leaf_mask();
-
-        if valid_mask == 0 {
-            return;
-        }
-
-        let mut internal_count = 0u32;
-        for child_idx in 0u8..8 {
-            let child_mask = 1u8 << child_idx;
-            if valid_mask & child_mask == 0 {
-                continue;
-            }
-
-            // Only recurse into internal children
-            if leaf_mask & child_mask == 0 {
-                let child_node_idx = node.child_offset + internal_count;
-                Self::compute_node_depths(octree, child_node_idx, depth + 1, node_to_level);
-                internal_count += 1;
-            }
-        }
+        dag.recount_refs();
+        dag
     }
 
     /// Hash a node recursively, processing children first (bottom-up)
@@ -147,12 +145,12 @@ impl HashDag {
         node_idx: u32,
         level: usize,
         brick_hashes: &[NodeHash],
-        node_to_level: &[usize],
-        node_to_hash: &mut [Option<NodeHash>],
+        node_to_hash: &mut HashMap<(u32, usize), NodeHash>,
         levels: &mut [HashMap<NodeHash, HashDagNode>],
+        bricks: &mut HashMap<NodeHash, VoxelBrick>,
     ) -> NodeHash {
         // Check if already hashed
-        if let Some(hash) = node_to_hash[node_idx as usize] {
+        if let Some(&hash) = node_to_hash.get(&(node_idx, level)) {
             return hash;
         }
 
@@ -163,63 +161,39 @@ impl HashDag {
         let mut children = Vec::new();
         let mut brick_hashes_vec = Vec::new();
 
-        // Handle terminal leaf (single brick, no children)
         if node.is_terminal_leaf() {
-            // brick_offset is 1-indexed (0 is sentinel for empty)
-            let brick_hash = brick_hashes[node.brick_offset as usize - 1];
-            let hash = Self::hash_node(node.flags, &[], &[brick_hash as u64], node.lod_color, node.lod_material);
-            node_to_hash[node_idx as usize] = Some(hash);
-            levels[level as usize].insert(hash, HashDagNode {
-                flags: node.flags,
-                children: vec![],
-                brick_hashes: vec![brick_hash],
-                lod_color: node.lod_color,
-                lod_material: node.lod_material,
-            });
-            return hash;
+            let brick_idx = node.brick_offset as usize;
+            brick_hashes_vec.push(brick_hashes[brick_idx]);
+            bricks.entry(brick_hashes[brick_idx]).or_insert(*octree.brick(brick_idx as u32));
         }
 
-        if valid_mask != 0 {
-            let mut internal_count = 0u32;
-            let mut leaf_count = 0u32;
+        for child_idx in 0u8..8 {
+            let child_mask = 1u8 << child_idx;
+            if valid_mask & child_mask == 0 {
+                continue;
+            }
 
-            for child_idx in 0u8..8 {
-                let child_mask = 1u8 << child_idx;
-                if valid_mask & child_mask == 0 {
+            if leaf_mask & child_mask != 0 {
+                let brick_idx = octree.leaf_brick_index(node, child_idx) as usize;
+                if brick_idx >= brick_hashes.len() {
+                    log::error!("Brick index out of bounds: brick_offset={}, child={}, len={}",
+                        node.brick_offset, child_idx, brick_hashes.len());
                     continue;
                 }
-
-                if leaf_mask & child_mask != 0 {
-                    // Leaf child - get brick hash
-                    // brick_offset is 1-indexed in Octree (0 = empty), convert to 0-indexed for array
-                    if node.brick_offset == 0 {
-                        // No bricks in this octree - skip
-                        continue;
-                    }
-                    let brick_idx = node.brick_offset + leaf_count - 1;
-                    if brick_idx as usize >= brick_hashes.len() {
-                        log::error!("Brick index out of bounds: brick_offset={}, leaf_count={}, len={}",
-                            node.brick_offset, leaf_count, brick_hashes.len());
-                        continue;
-                    }
-                    let brick_hash = brick_hashes[brick_idx as usize];
-                    brick_hashes_vec.push(brick_hash);
-                    leaf_count += 1;
-                } else {
-                    // Internal child - recurse
-                    let child_node_idx = node.child_offset + internal_count;
-                    let child_hash = Self::hash_octree_recursive(
-                        octree,
-                        child_node_idx,
-                        level + 1,
-                        brick_hashes,
-                        node_to_level,
-                        node_to_hash,
-                        levels,
-                    );
-                    children.push(child_hash);
-                    internal_count += 1;
-                }
+                brick_hashes_vec.push(brick_hashes[brick_idx]);
+                bricks.entry(brick_hashes[brick_idx]).or_insert(*octree.brick(brick_idx as u32));
+            } else {
+                let child_node_idx = octree.child_node_index(node, child_idx);
+                let child_hash = Self::hash_octree_recursive(
+                    octree,
+                    child_node_idx,
+                    level + 1,
+                    brick_hashes,
+                    node_to_hash,
+                    levels,
+                    bricks,
+                );
+                children.push(child_hash);
             }
         }
 
@@ -236,7 +210,7 @@ impl HashDag {
         };
 
         levels[level].entry(hash).or_insert(dag_node);
-        node_to_hash[node_idx as usize] = Some(hash);
+        node_to_hash.insert((node_idx, level), hash);
 
         hash
     }
@@ -298,7 +272,7 @@ impl HashDag {
 
     /// Get compression statistics.
     pub fn stats(&self) -> HashDagStats {
-        let unique_nodes = self.total_nodes;
+        let unique_nodes = self.unique_node_count();
         let unique_bricks = self.bricks.len();
 
         // Rough memory estimate
@@ -306,11 +280,13 @@ impl HashDag {
             std::mem::size_of::<u32>() + // flags
             std::mem::size_of::<u16>() * 2 + // lod_color, lod_material
             std::mem::size_of::<Vec<NodeHash>>() * 2 + // children, brick_hashes
-            std::mem::size_of::<u64>() // hash key
+            std::mem::size_of::<u64>() + // hash key
+            std::mem::size_of::<u32>() // refcount
         );
         let brick_memory = unique_bricks * (
             std::mem::size_of::<VoxelBrick>() +
-            std::mem::size_of::<u64>() // hash key
+            std::mem::size_of::<u64>() + // hash key
+            std::mem::size_of::<u32>() // refcount
         );
 
         HashDagStats {
@@ -323,7 +299,7 @@ impl HashDag {
 
     /// Total unique nodes across all levels.
     pub fn unique_node_count(&self) -> usize {
-        self.total_nodes
+        self.levels.iter().map(|level| level.len()).sum()
     }
 
     /// Total unique bricks.
@@ -336,6 +312,26 @@ impl HashDag {
         self.root
     }
 
+    /// Root size in meters.
+    pub fn root_size(&self) -> f32 {
+        self.root_size
+    }
+
+    /// Maximum depth.
+    pub fn max_depth(&self) -> u8 {
+        self.max_depth as u8
+    }
+
+    /// Size of a finest-level voxel in meters.
+    pub fn voxel_size(&self) -> f32 {
+        self.root_size / (1u32 << self.max_depth) as f32
+    }
+
+    /// Check if the DAG holds no geometry.
+    pub fn is_empty(&self) -> bool {
+        self.root_node().is_none()
+    }
+
     /// Get node at a specific level by hash.
     pub fn node_at(&self, level: usize, hash: NodeHash) -> Option<&HashDagNode> {
         if level < self.levels.len() {
@@ -345,164 +341,596 @@ impl HashDag {
         }
     }
 
+    fn root_node(&self) -> Option<&HashDagNode> {
+        self.levels.first().and_then(|level| level.get(&self.root))
+    }
+
+    /// Level whose leaf children are finest-resolution bricks
+    fn brick_level(&self) -> usize {
+        self.max_depth.saturating_sub(2)
+    }
+
+    /// Local-space bounds of the root (centered at origin, like `Octree`)
+    fn root_bounds(&self) -> Aabb {
+        let half = self.root_size / 2.0;
+        Aabb::new(Vec3::splat(-half), Vec3::splat(half))
+    }
+
+    /// Sample voxel at a local position (range [-root_size/2, root_size/2]).
+    /// Returns Voxel::EMPTY outside the bounds or in empty space.
+    pub fn sample_voxel(&self, local_pos: Vec3) -> Voxel {
+        let half = self.root_size / 2.0;
+        if local_pos.cmplt(Vec3::splat(-half)).any() || local_pos.cmpge(Vec3::splat(half)).any() {
+            return Voxel::EMPTY;
+        }
+        if self.root_node().is_none() {
+            return Voxel::EMPTY;
+        }
+        self.sample_node(0, self.root, &self.root_bounds(), local_pos)
+    }
+
+    fn sample_node(&self, level: usize, hash: NodeHash, bounds: &Aabb, pos: Vec3) -> Voxel {
+        let Some(node) = self.node_at(level, hash) else {
+            return Voxel::EMPTY;
+        };
+        let child_idx = octant_of(bounds, pos);
+
+        if node.valid_mask() == 0 {
+            return match node.terminal_brick() {
+                Some(brick) => self.bricks[&brick].voxels[child_idx as usize],
+                None => Voxel::EMPTY,
+            };
+        }
+
+        let child_mask = 1u8 << child_idx;
+        if node.valid_mask() & child_mask == 0 {
+            return Voxel::EMPTY;
+        }
+        let child_bounds = bounds.child_octant(child_idx);
+        let before = node.valid_mask() & ((1u8 << child_idx) - 1);
+
+        if node.leaf_mask() & child_mask != 0 {
+            let rank = (before & node.leaf_mask()).count_ones() as usize;
+            let brick = &self.bricks[&node.brick_hashes[rank]];
+            return brick.voxels[octant_of(&child_bounds, pos) as usize];
+        }
+
+        let rank = (before & !node.leaf_mask()).count_ones() as usize;
+        self.sample_node(level + 1, node.children[rank], &child_bounds, pos)
+    }
+
     /// Convert back to a standard Octree (for GPU upload).
+    ///
+    /// Each unique node is emitted once; its children and leaf bricks are laid
+    /// out contiguously, so shared subtrees stay shared in the output.
     pub fn to_octree(&self) -> Octree {
         let mut octree = Octree::with_capacity(
             self.root_size,
             self.max_depth as u8,
-            self.total_nodes,
-            self.bricks.len(),
+            self.unique_node_count() + 1,
+            self.bricks.len() + 1,
         );
+        // Brick 0 is the empty sentinel (brick_offset 0 = no brick)
+        octree.add_brick(VoxelBrick::EMPTY);
 
-        // Build mapping from hash to new indices
-        let mut hash_to_node_idx: HashMap<NodeHash, u32> = HashMap::new();
-        let mut hash_to_brick_idx: HashMap<NodeHash, u32> = HashMap::new();
-
-        // Add all bricks first
-        for (hash, brick) in &self.bricks {
-            let idx = octree.add_brick(*brick);
-            hash_to_brick_idx.insert(*hash, idx);
-        }
-
-        // Process nodes top-down from root
-        if let Some(root_node) = self.levels[0].get(&self.root) {
-            Self::rebuild_octree_recursive(
-                root_node,
-                0,
-                &self.levels,
-                &self.bricks,
-                &mut hash_to_node_idx,
-                &hash_to_brick_idx,
-                &mut octree,
-            );
+        if self.root_node().is_none() {
+            return octree;
         }
 
+        // The root occupies node 0, already allocated by `with_capacity`
+        let mut emitted: HashMap<(usize, NodeHash), OctreeNode> = HashMap::new();
+        let root = self.emit_node(0, self.root, &mut emitted, &mut octree);
+        *octree.root_mut() = root;
+
         octree
     }
 
-    /// Recursively rebuild octree from HashDAG
-    fn rebuild_octree_recursive(
-        dag_node: &HashDagNode,
+    /// Emit the octree record for a node, appending its children and bricks
+    fn emit_node(
+        &self,
         level: usize,
-        levels: &[HashMap<NodeHash, HashDagNode>],
-        _bricks: &HashMap<NodeHash, VoxelBrick>,
-        hash_to_node_idx: &mut HashMap<NodeHash, u32>,
-        hash_to_brick_idx: &HashMap<NodeHash, u32>,
+        hash: NodeHash,
+        emitted: &mut HashMap<(usize, NodeHash), OctreeNode>,
         octree: &mut Octree,
-    ) -> u32 {
-        // Create octree node
+    ) -> OctreeNode {
+        if let Some(node) = emitted.get(&(level, hash)) {
+            return *node;
+        }
+
+        let dag_node = &self.levels[level][&hash];
         let mut node = OctreeNode::empty();
         node.flags = dag_node.flags;
         node.lod_color = dag_node.lod_color;
         node.lod_material = dag_node.lod_material;
 
-        let valid_mask = (dag_node.flags & 0xFF) as u8;
-        let leaf_mask = ((dag_node.flags >> 8) & 0xFF) as u8;
+        if let Some(brick) = dag_node.terminal_brick() {
+            node.brick_offset = octree.add_brick(self.bricks[&brick]);
+        } else {
+            if !dag_node.children.is_empty() {
+                // Reserve the child run first so it stays contiguous
+                node.child_offset = octree.node_count() as u32;
+                for _ in &dag_node.children {
+                    octree.add_node(OctreeNode::empty());
+                }
+                for (i, &child) in dag_node.children.iter().enumerate() {
+                    let child_node = self.emit_node(level + 1, child, emitted, octree);
+                    *octree.node_mut(node.child_offset + i as u32) = child_node;
+                }
+            }
+            if !dag_node.brick_hashes.is_empty() {
+                node.brick_offset = octree.brick_count() as u32;
+                for brick in &dag_node.brick_hashes {
+                    octree.add_brick(self.bricks[brick]);
+                }
+            }
+        }
+
+        emitted.insert((level, hash), node);
+        node
+    }
+
+    /// Set the voxel containing local position `pos`.
+    pub fn set_voxel(&mut self, pos: Vec3, voxel: Voxel) {
+        let half = self.root_size / 2.0;
+        let voxel_size = self.voxel_size();
+        let cell = ((pos + Vec3::splat(half)) / voxel_size).floor();
+        let resolution = (1u32 << self.max_depth) as f32;
+        if cell.cmplt(Vec3::ZERO).any() || cell.cmpge(Vec3::splat(resolution)).any() {
+            return;
+        }
+
+        // A region strictly inside the cell touches only that voxel
+        let center = (cell + Vec3::splat(0.5)) * voxel_size - Vec3::splat(half);
+        let region = Aabb::from_center_half_extent(center, Vec3::splat(voxel_size * 0.25));
+        self.edit_region(&region, |_, _| voxel);
+    }
 
-        // Process children
-        if valid_mask != 0 {
-            let mut child_indices = Vec::new();
-            let mut brick_indices = Vec::new();
+    /// Clear the voxel containing local position `pos`.
+    pub fn clear_voxel(&mut self, pos: Vec3) {
+        self.set_voxel(pos, Voxel::EMPTY);
+    }
 
-            let mut child_iter = dag_node.children.iter();
-            let mut brick_iter = dag_node.brick_hashes.iter();
+    /// Apply an edit delta to this DAG.
+    ///
+    /// `center` is the world-space position of the DAG's local origin (the
+    /// chunk center for chunk octrees); the edit is evaluated in world space.
+    pub fn apply_edit(&mut self, delta: &EditDelta, center: Vec3) {
+        let region = delta.affected_region();
+        let local = Aabb::new(region.min - center, region.max - center);
+        self.edit_region(&local, |pos, voxel| delta.apply_at(pos + center, voxel));
+    }
 
-            for child_idx in 0u8..8 {
-                let child_mask = 1u8 << child_idx;
-                if valid_mask & child_mask == 0 {
-                    continue;
+    /// Rewrite every finest-level voxel whose cell intersects `region` (local space).
+    ///
+    /// `f` receives the voxel center and its current value and returns the new
+    /// value. Only nodes on paths into the region are rebuilt; subtrees left
+    /// unchanged keep their hashes, and storage orphaned by the edit is freed.
+    pub fn edit_region<F>(&mut self, region: &Aabb, f: F)
+    where
+        F: Fn(Vec3, Voxel) -> Voxel,
+    {
+        let bounds = self.root_bounds();
+        if !bounds.intersects(region) {
+            return;
+        }
+
+        let old_root = self.root_node().map(|_| self.root);
+        let slot = old_root.map_or(EditChild::Empty, EditChild::Node);
+        let new_root = match self.edit_slot(0, slot, &bounds, region, &f) {
+            EditChild::Empty => None,
+            EditChild::Node(hash) => Some(hash),
+            EditChild::Brick(brick) | EditChild::Terminal(brick) => Some(self.intern_terminal(0, brick)),
+        };
+        self.set_root(new_root);
+    }
+
+    /// Take a reference on the current root so it outlives later edits, and
+    /// return it (`None` when the DAG is empty).
+    ///
+    /// Go back to it with `set_root` and give the reference back with
+    /// `release_root`. `collect_garbage` forgets retained roots.
+    pub fn retain_root(&mut self) -> Option<NodeHash> {
+        self.root_node()?;
+        *self.node_refs[0].entry(self.root).or_insert(0) += 1;
+        Some(self.root)
+    }
+
+    /// Make a root taken with `retain_root` current, freeing what only the
+    /// previous root referenced.
+    pub fn set_root(&mut self, root: Option<NodeHash>) {
+        let old_root = self.root_node().map(|_| self.root);
+        if root == old_root {
+            return;
+        }
+
+        // Reference the new root before releasing the old one so shared
+        // subtrees survive the swap
+        if let Some(hash) = root {
+            *self.node_refs[0].entry(hash).or_insert(0) += 1;
+        }
+        if let Some(hash) = old_root {
+            self.release_node(0, hash);
+        }
+        self.root = root.unwrap_or(0);
+    }
+
+    /// Drop a reference taken with `retain_root`
+    pub fn release_root(&mut self, root: Option<NodeHash>) {
+        if let Some(hash) = root {
+            self.release_node(0, hash);
+        }
+    }
+
+    /// Edit the node in `slot` (at `level`), returning its replacement.
+    /// Returns `slot` itself when nothing under it changed.
+    fn edit_slot(
+        &mut self,
+        level: usize,
+        slot: EditChild,
+        bounds: &Aabb,
+        region: &Aabb,
+        f: &dyn Fn(Vec3, Voxel) -> Voxel,
+    ) -> EditChild {
+        let mut children = self.slot_children(level, &slot);
+        let mut changed = false;
+
+        for child_idx in 0u8..8 {
+            let child_bounds = bounds.child_octant(child_idx);
+            if !child_bounds.intersects(region) {
+                continue;
+            }
+            let child = children[child_idx as usize];
+
+            if level >= self.brick_level() {
+                let before = self.slot_brick(level + 1, &child, &child_bounds);
+                let mut after = before;
+                for voxel_idx in 0u8..8 {
+                    let voxel_bounds = child_bounds.child_octant(voxel_idx);
+                    if voxel_bounds.intersects(region) {
+                        let voxel = &mut after.voxels[voxel_idx as usize];
+                        *voxel = f(voxel_bounds.center(), *voxel);
+                    }
+                }
+                if after.voxels != before.voxels {
+                    children[child_idx as usize] = if after.is_empty() {
+                        EditChild::Empty
+                    } else {
+                        EditChild::Brick(after)
+                    };
+                    changed = true;
                 }
+            } else {
+                let edited = self.edit_slot(level + 1, child, &child_bounds, region, f);
+                if !same_slot(&edited, &child) {
+                    children[child_idx as usize] = edited;
+                    changed = true;
+                }
+            }
+        }
 
-                if leaf_mask & child_mask != 0 {
-                    // Leaf child - get brick index
-                    if let Some(&brick_hash) = brick_iter.next() {
-                        if let Some(&brick_idx) = hash_to_brick_idx.get(&brick_hash) {
-                            brick_indices.push(brick_idx);
-                        }
+        if !changed {
+            return slot;
+        }
+        self.intern_children(level, children)
+    }
+
+    /// Expand the node in `slot` (at `level`) into its eight children
+    fn slot_children(&self, level: usize, slot: &EditChild) -> [EditChild; 8] {
+        match slot {
+            EditChild::Empty => [EditChild::Empty; 8],
+            EditChild::Brick(brick) | EditChild::Terminal(brick) => self.split_brick(level, brick),
+            EditChild::Node(hash) => {
+                let node = &self.levels[level][hash];
+                if let Some(brick) = node.terminal_brick() {
+                    return self.split_brick(level, &self.bricks[&brick]);
+                }
+
+                let mut children = [EditChild::Empty; 8];
+                let mut internal = node.children.iter();
+                let mut leaves = node.brick_hashes.iter();
+                for (child_idx, child) in children.iter_mut().enumerate() {
+                    let child_mask = 1u8 << child_idx;
+                    if node.valid_mask() & child_mask == 0 {
+                        continue;
                     }
-                } else {
-                    // Internal child - recurse
-                    if let Some(&child_hash) = child_iter.next() {
-                        // Check if already processed
-                        let child_node_idx = if let Some(&existing_idx) = hash_to_node_idx.get(&child_hash) {
-                            existing_idx
+                    *child = if node.leaf_mask() & child_mask != 0 {
+                        let brick = leaves.next().map_or(VoxelBrick::EMPTY, |hash| self.bricks[hash]);
+                        if level >= self.brick_level() {
+                            EditChild::Brick(brick)
                         } else {
-                            // Process child
-                            if let Some(child_dag_node) = levels.get(level + 1).and_then(|l| l.get(&child_hash)) {
-                                let idx = Self::rebuild_octree_recursive(
-                                    child_dag_node,
-                                    level + 1,
-                                    levels,
-                                    _bricks,
-                                    hash_to_node_idx,
-                                    hash_to_brick_idx,
-                                    octree,
-                                );
-                                hash_to_node_idx.insert(child_hash, idx);
-                                idx
-                            } else {
-                                0 // Shouldn't happen
-                            }
-                        };
-                        child_indices.push(child_node_idx);
-                    }
+                            // Coarse leaf brick = terminal leaf one level down
+                            EditChild::Terminal(brick)
+                        }
+                    } else {
+                        internal.next().map_or(EditChild::Empty, |&hash| EditChild::Node(hash))
+                    };
                 }
+                children
             }
+        }
+    }
 
-            // Set offsets
-            if !child_indices.is_empty() {
-                node.child_offset = child_indices[0];
+    /// Split a terminal leaf brick at `level` into eight uniform children
+    fn split_brick(&self, level: usize, brick: &VoxelBrick) -> [EditChild; 8] {
+        let mut children = [EditChild::Empty; 8];
+        for (child, voxel) in children.iter_mut().zip(brick.voxels) {
+            if voxel.is_empty() {
+                continue;
             }
-            if !brick_indices.is_empty() {
-                node.brick_offset = brick_indices[0];
+            let uniform = VoxelBrick::new([voxel; 8]);
+            *child = if level >= self.brick_level() {
+                EditChild::Brick(uniform)
+            } else {
+                EditChild::Terminal(uniform)
+            };
+        }
+        children
+    }
+
+    /// Finest-resolution brick covering a child slot at `level`
+    fn slot_brick(&self, level: usize, slot: &EditChild, bounds: &Aabb) -> VoxelBrick {
+        match slot {
+            EditChild::Empty => VoxelBrick::EMPTY,
+            EditChild::Brick(brick) | EditChild::Terminal(brick) => *brick,
+            EditChild::Node(hash) => match self.levels[level][hash].terminal_brick() {
+                Some(brick) => self.bricks[&brick],
+                None => {
+                    let mut brick = VoxelBrick::EMPTY;
+                    for (voxel_idx, voxel) in brick.voxels.iter_mut().enumerate() {
+                        let center = bounds.child_octant(voxel_idx as u8).center();
+                        *voxel = self.sample_node(level, *hash, bounds, center);
+                    }
+                    brick
+                }
+            },
+        }
+    }
+
+    /// Uniform non-empty voxel filling a child slot at `level`, if any
+    fn slot_uniform(&self, level: usize, slot: &EditChild) -> Option<Voxel> {
+        let uniform = match slot {
+            EditChild::Empty => None,
+            EditChild::Brick(brick) | EditChild::Terminal(brick) => brick.is_uniform(),
+            EditChild::Node(hash) => self.levels[level][hash]
+                .terminal_brick()
+                .and_then(|brick| self.bricks[&brick].is_uniform()),
+        };
+        uniform.filter(|voxel| !voxel.is_empty())
+    }
+
+    /// Build and intern a node at `level` from its rebuilt children.
+    /// Collapses to an (uninterned) terminal leaf when all children are the same solid voxel.
+    fn intern_children(&mut self, level: usize, children: [EditChild; 8]) -> EditChild {
+        if children.iter().all(|child| matches!(child, EditChild::Empty)) {
+            return EditChild::Empty;
+        }
+
+        let first = self.slot_uniform(level + 1, &children[0]);
+        if let Some(voxel) = first
+            && children.iter().all(|child| self.slot_uniform(level + 1, child) == Some(voxel))
+        {
+            return EditChild::Terminal(VoxelBrick::new([voxel; 8]));
+        }
+
+        let mut valid_mask = 0u8;
+        let mut leaf_mask = 0u8;
+        let mut child_hashes = Vec::new();
+        let mut brick_hashes = Vec::new();
+        let mut lods = Vec::with_capacity(8);
+
+        for (child_idx, child) in children.iter().enumerate() {
+            let child_mask = 1u8 << child_idx;
+            match child {
+                EditChild::Empty => continue,
+                EditChild::Brick(brick) => {
+                    leaf_mask |= child_mask;
+                    brick_hashes.push(self.intern_brick(brick));
+                    lods.push((brick.average_color(), brick.average_material()));
+                }
+                EditChild::Terminal(brick) => {
+                    child_hashes.push(self.intern_terminal(level + 1, *brick));
+                    lods.push((brick.average_color(), brick.average_material()));
+                }
+                EditChild::Node(hash) => {
+                    let node = &self.levels[level + 1][hash];
+                    lods.push((node.lod_color, node.lod_material as u8));
+                    child_hashes.push(*hash);
+                }
             }
+            valid_mask |= child_mask;
         }
 
-        octree.add_node(node)
+        let (lod_color, lod_material) = average_lod(&lods);
+        let node = HashDagNode {
+            flags: valid_mask as u32 | ((leaf_mask as u32) << 8),
+            children: child_hashes,
+            brick_hashes,
+            lod_color,
+            lod_material: lod_material as u16,
+        };
+        EditChild::Node(self.intern_node(level, node))
     }
 
-    /// Merge another HashDAG into this one (for combining base + edits).
-    /// Returns a new HashDAG with shared structure.
-    pub fn merge(&self, other: &HashDag) -> HashDag {
-        let max_depth = self.max_depth.max(other.max_depth);
+    /// Intern a terminal-leaf node holding `brick`
+    fn intern_terminal(&mut self, level: usize, brick: VoxelBrick) -> NodeHash {
+        let brick_hash = self.intern_brick(&brick);
+        let node = HashDagNode {
+            flags: 0,
+            children: Vec::new(),
+            brick_hashes: vec![brick_hash],
+            lod_color: brick.average_color(),
+            lod_material: brick.average_material() as u16,
+        };
+        self.intern_node(level, node)
+    }
+
+    /// Insert a brick if new (with no references yet) and return its hash
+    fn intern_brick(&mut self, brick: &VoxelBrick) -> NodeHash {
+        let hash = Self::hash_brick(brick);
+        self.bricks.entry(hash).or_insert(*brick);
+        self.brick_refs.entry(hash).or_insert(0);
+        hash
+    }
 
-        // Union of all bricks
-        let mut merged_bricks = self.bricks.clone();
-        for (hash, brick) in &other.bricks {
-            merged_bricks.entry(*hash).or_insert(*brick);
+    /// Insert a node if new and return its hash.
+    /// A new node starts unreferenced and takes a reference on each child and brick.
+    fn intern_node(&mut self, level: usize, node: HashDagNode) -> NodeHash {
+        let hash = Self::hash_node(node.flags, &node.children, &node.brick_hashes, node.lod_color, node.lod_material);
+        if self.levels[level].contains_key(&hash) {
+            return hash;
         }
 
-        // Union of nodes at each level
-        let mut merged_levels: Vec<HashMap<NodeHash, HashDagNode>> =
-            (0..=max_depth).map(|_| HashMap::new()).collect();
+        for child in &node.children {
+            *self.node_refs[level + 1].entry(*child).or_insert(0) += 1;
+        }
+        for brick in &node.brick_hashes {
+            *self.brick_refs.entry(*brick).or_insert(0) += 1;
+        }
+        self.levels[level].insert(hash, node);
+        self.node_refs[level].insert(hash, 0);
+        hash
+    }
+
+    /// Drop one reference to a node, freeing it (and releasing its children) at zero
+    fn release_node(&mut self, level: usize, hash: NodeHash) {
+        let Some(refs) = self.node_refs[level].get_mut(&hash) else {
+            return;
+        };
+        *refs = refs.saturating_sub(1);
+        if *refs > 0 {
+            return;
+        }
 
-        for (level_idx, level) in self.levels.iter().enumerate() {
-            for (hash, node) in level {
-                merged_levels[level_idx].entry(*hash).or_insert_with(|| node.clone());
+        self.node_refs[level].remove(&hash);
+        if let Some(node) = self.levels[level].remove(&hash) {
+            for child in node.children {
+                self.release_node(level + 1, child);
             }
+            for brick in node.brick_hashes {
+                self.release_brick(brick);
+            }
+        }
+    }
+
+    fn release_brick(&mut self, hash: NodeHash) {
+        let Some(refs) = self.brick_refs.get_mut(&hash) else {
+            return;
+        };
+        *refs = refs.saturating_sub(1);
+        if *refs == 0 {
+            self.brick_refs.remove(&hash);
+            self.bricks.remove(&hash);
+        }
+    }
+
+    /// Recompute all reference counts by walking the DAG from the root.
+    /// Nodes and bricks not reachable from the root end up with zero.
+    fn recount_refs(&mut self) {
+        self.node_refs = self
+            .levels
+            .iter()
+            .map(|level| level.keys().map(|&hash| (hash, 0)).collect())
+            .collect();
+        self.brick_refs = self.bricks.keys().map(|&hash| (hash, 0)).collect();
+
+        if self.root_node().is_none() {
+            return;
         }
+        self.node_refs[0].insert(self.root, 1);
 
-        for (level_idx, level) in other.levels.iter().enumerate() {
-            for (hash, node) in level {
-                merged_levels[level_idx].entry(*hash).or_insert_with(|| node.clone());
+        let mut visited: HashSet<(usize, NodeHash)> = HashSet::new();
+        let mut stack = vec![(0usize, self.root)];
+        while let Some((level, hash)) = stack.pop() {
+            if !visited.insert((level, hash)) {
+                continue;
+            }
+            let Some(node) = self.levels[level].get(&hash) else {
+                continue;
+            };
+            for &child in &node.children {
+                *self.node_refs[level + 1].entry(child).or_insert(0) += 1;
+                stack.push((level + 1, child));
+            }
+            for &brick in &node.brick_hashes {
+                *self.brick_refs.entry(brick).or_insert(0) += 1;
             }
         }
+    }
 
-        // Use other's root (edits override base)
-        let root = other.root;
-        let root_size = other.root_size;
+    /// Free every node and brick not reachable from the root.
+    /// Returns the number of entries removed.
+    ///
+    /// Edits free what they orphan as they go; this is only needed to
+    /// recover from external changes.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.recount_refs();
+
+        let mut removed = 0;
+        for (level, refs) in self.levels.iter_mut().zip(&mut self.node_refs) {
+            refs.retain(|_, count| *count > 0);
+            let before = level.len();
+            level.retain(|hash, _| refs.contains_key(hash));
+            removed += before - level.len();
+        }
 
-        let total_nodes = merged_levels.iter().map(|level| level.len()).sum();
+        self.brick_refs.retain(|_, count| *count > 0);
+        let before = self.bricks.len();
+        self.bricks.retain(|hash, _| self.brick_refs.contains_key(hash));
+        removed + before - self.bricks.len()
+    }
+}
 
-        HashDag {
-            levels: merged_levels,
-            bricks: merged_bricks,
-            root,
-            root_size,
-            max_depth,
-            total_nodes,
+/// Octant (bit 0 = x, bit 1 = y, bit 2 = z) of `bounds` containing `pos`
+fn octant_of(bounds: &Aabb, pos: Vec3) -> u8 {
+    let center = bounds.center();
+    (pos.x >= center.x) as u8 | (((pos.y >= center.y) as u8) << 1) | (((pos.z >= center.z) as u8) << 2)
+}
+
+/// Whether two child slots hold the same content representation
+fn same_slot(a: &EditChild, b: &EditChild) -> bool {
+    match (a, b) {
+        (EditChild::Empty, EditChild::Empty) => true,
+        (EditChild::Node(a), EditChild::Node(b)) => a == b,
+        (EditChild::Brick(a), EditChild::Brick(b))
+        | (EditChild::Terminal(a), EditChild::Terminal(b)) => a.voxels == b.voxels,
+        _ => false,
+    }
+}
+
+/// Average LOD color and most common material over child LODs
+fn average_lod(lods: &[(u16, u8)]) -> (u16, u8) {
+    let mut rgb_sum = [0u32; 3];
+    let mut count = 0u32;
+    let mut mat_counts = [0u8; 256];
+
+    for &(color, material) in lods {
+        if color == 0 {
+            continue;
         }
+        let (r, g, b) = rgb565_to_rgb(color);
+        rgb_sum[0] += r as u32;
+        rgb_sum[1] += g as u32;
+        rgb_sum[2] += b as u32;
+        mat_counts[material as usize] += 1;
+        count += 1;
     }
+
+    if count == 0 {
+        return (0, 0);
+    }
+
+    let lod_material = mat_counts
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, c)| *c)
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0);
+    let lod_color = rgb_to_565(
+        (rgb_sum[0] / count) as u8,
+        (rgb_sum[1] / count) as u8,
+        (rgb_sum[2] / count) as u8,
+    );
+    (lod_color, lod_material)
 }
 
 #[derive(Debug)]
@@ -516,7 +944,31 @@ pub struct HashDagStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::voxel::brush::{BlendMode, BrushStroke};
+    use crate::voxel::edit::EditOp;
     use crate::voxel::svo::builder::{OctreeBuilder, create_test_sphere};
+    use crate::voxel::svo::{AdaptiveOctreeBuilder, SvdagBuilder};
+
+    /// 32^3 sphere over [-16, 16] (1 m voxels)
+    fn sphere_octree() -> Octree {
+        let size = 32u32;
+        let voxels = create_test_sphere(size, 14.0);
+        OctreeBuilder::new(size).build(&voxels, size as f32)
+    }
+
+    /// Centers of every finest-level voxel
+    fn voxel_centers(root_size: f32, resolution: u32) -> impl Iterator<Item = Vec3> {
+        let voxel_size = root_size / resolution as f32;
+        let half = root_size / 2.0;
+        (0..resolution.pow(3)).map(move |i| {
+            let cell = Vec3::new(
+                (i % resolution) as f32,
+                ((i / resolution) % resolution) as f32,
+                (i / (resolution * resolution)) as f32,
+            );
+            (cell + Vec3::splat(0.5)) * voxel_size - Vec3::splat(half)
+        })
+    }
 
     #[test]
     fn test_from_octree_basic() {
@@ -585,4 +1037,150 @@ mod tests {
         assert!(reconstructed.node_count() > 0);
         assert!(reconstructed.brick_count() > 0);
     }
+
+    #[test]
+    fn test_roundtrip_preserves_voxels() {
+        let octree = sphere_octree();
+        let svdag = SvdagBuilder::new().build(&octree);
+
+        for source in [&octree, &svdag] {
+            let hashdag = HashDag::from_octree(source);
+            let rebuilt = hashdag.to_octree();
+            assert!(rebuilt.validate().is_empty(), "{:?}", rebuilt.validate());
+
+            for pos in voxel_centers(32.0, 32) {
+                let expected = octree.sample_voxel(pos);
+                assert_eq!(hashdag.sample_voxel(pos), expected, "dag at {:?}", pos);
+                assert_eq!(rebuilt.sample_voxel(pos), expected, "octree at {:?}", pos);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_and_clear_voxel() {
+        let octree = sphere_octree();
+        let mut hashdag = HashDag::from_octree(&octree);
+        let red = Voxel::from_rgb565(0xF800, 7);
+
+        // Outside the sphere (corner) and inside it
+        let outside = Vec3::new(-15.5, -15.5, -15.5);
+        let inside = Vec3::new(0.5, 0.5, 0.5);
+        hashdag.set_voxel(outside, red);
+        hashdag.clear_voxel(inside);
+
+        for pos in voxel_centers(32.0, 32) {
+            let expected = if pos == outside {
+                red
+            } else if pos == inside {
+                Voxel::EMPTY
+            } else {
+                octree.sample_voxel(pos)
+            };
+            assert_eq!(hashdag.sample_voxel(pos), expected, "at {:?}", pos);
+        }
+
+        // Edits release what they orphan as they go
+        assert_eq!(hashdag.collect_garbage(), 0);
+        let rebuilt = hashdag.to_octree();
+        assert!(rebuilt.validate().is_empty());
+        assert_eq!(rebuilt.sample_voxel(outside), red);
+        assert!(rebuilt.sample_voxel(inside).is_empty());
+    }
+
+    #[test]
+    fn test_edit_only_rebuilds_path() {
+        // Solid ground below y = 0; identical subtrees dedup to one node per level
+        let ground = Voxel::from_rgb565(0x4208, 1);
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 0.0 { ground } else { Voxel::EMPTY },
+            Vec3::splat(-2.0),
+            4.0,
+        );
+        let mut hashdag = HashDag::from_octree(&octree);
+        let base_nodes = hashdag.unique_node_count();
+        let pos = Vec3::new(0.0625, -0.0625, 0.0625);
+
+        // One new node per level on the path; the shared subtrees stay put
+        hashdag.clear_voxel(pos);
+        assert!(hashdag.sample_voxel(pos).is_empty());
+        let edited_nodes = hashdag.unique_node_count();
+        assert!(edited_nodes > base_nodes);
+        assert!(edited_nodes - base_nodes <= hashdag.max_depth() as usize);
+
+        hashdag.set_voxel(pos, ground);
+        for pos in voxel_centers(4.0, 32) {
+            assert_eq!(hashdag.sample_voxel(pos), octree.sample_voxel(pos), "at {:?}", pos);
+        }
+        assert_eq!(hashdag.collect_garbage(), 0);
+
+        // Repeating the edit re-interns the same nodes
+        hashdag.clear_voxel(pos);
+        assert_eq!(hashdag.unique_node_count(), edited_nodes);
+    }
+
+    #[test]
+    fn test_retained_roots_swap_back() {
+        let octree = sphere_octree();
+        let mut hashdag = HashDag::from_octree(&octree);
+        let base_nodes = hashdag.unique_node_count();
+        let inside = Vec3::new(0.5, 0.5, 0.5);
+
+        let base = hashdag.retain_root();
+        hashdag.clear_voxel(inside);
+        let carved = hashdag.retain_root();
+        assert!(hashdag.sample_voxel(inside).is_empty());
+
+        // Both versions stay alive and can be swapped without re-editing
+        hashdag.set_root(base);
+        assert_eq!(hashdag.sample_voxel(inside), octree.sample_voxel(inside));
+        hashdag.set_root(carved);
+        assert!(hashdag.sample_voxel(inside).is_empty());
+
+        // Releasing the carved version frees its path once it's not current
+        hashdag.set_root(base);
+        hashdag.release_root(carved);
+        hashdag.release_root(base);
+        assert_eq!(hashdag.unique_node_count(), base_nodes);
+        assert_eq!(hashdag.collect_garbage(), 0);
+    }
+
+    #[test]
+    fn test_apply_edit_matches_delta() {
+        let octree = sphere_octree();
+        let mut hashdag = HashDag::from_octree(&octree);
+
+        // DAG centered at (100, 0, 0) in world space
+        let center = Vec3::new(100.0, 0.0, 0.0);
+        let carve = EditDelta::new(1, 0, EditOp::from_stroke(
+            &BrushStroke::sphere(center + Vec3::new(8.0, 8.0, 0.0), 5.0, Voxel::EMPTY, 5)
+                .with_blend(BlendMode::Subtract),
+        ));
+        let fill = EditDelta::new(2, 0, EditOp::FillRegion {
+            region: Aabb::new(center + Vec3::new(-16.0, -16.0, -16.0), center + Vec3::new(-12.0, -12.0, -12.0)),
+            voxel: Voxel::from_rgb565(0x07E0, 3),
+        });
+        hashdag.apply_edit(&carve, center);
+        hashdag.apply_edit(&fill, center);
+
+        let mut changed = 0;
+        for pos in voxel_centers(32.0, 32) {
+            let base = octree.sample_voxel(pos);
+            let expected = fill.apply_at(pos + center, carve.apply_at(pos + center, base));
+            changed += (expected != base) as usize;
+            assert_eq!(hashdag.sample_voxel(pos), expected, "at {:?}", pos);
+        }
+        assert!(changed > 0);
+        assert_eq!(hashdag.collect_garbage(), 0);
+
+        // Clearing everything frees all storage
+        hashdag.apply_edit(
+            &EditDelta::new(3, 0, EditOp::ClearRegion {
+                region: Aabb::new(center - Vec3::splat(16.0), center + Vec3::splat(16.0)),
+            }),
+            center,
+        );
+        assert!(hashdag.is_empty());
+        assert_eq!(hashdag.unique_node_count(), 0);
+        assert_eq!(hashdag.unique_brick_count(), 0);
+    }
 }
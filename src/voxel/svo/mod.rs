@@ -4,17 +4,23 @@ pub mod node;
 pub mod octree;
 pub mod builder;
 pub mod svdag;
+pub mod dictionary;
 pub mod hashdag;
 pub mod adaptive;
 pub mod volumetric;
 pub mod composite;
 pub mod classifier;
 pub mod composite_classifier;
+pub mod raycast;
+pub mod csg;
+pub mod resample;
+pub mod occlusion;
 
 pub use node::OctreeNode;
 pub use octree::Octree;
 pub use builder::{OctreeBuilder, create_test_sphere};
-pub use svdag::SvdagBuilder;
+pub use svdag::{SvdagBuilder, SvdagStats};
+pub use dictionary::{DictionaryBuilder, SubtreeDictionary, DICTIONARY_REF};
 pub use hashdag::HashDag;
 pub use adaptive::AdaptiveOctreeBuilder;
 pub use volumetric::{VolumetricObject, OctreeInstance, VolumetricGrid};
@@ -23,3 +29,6 @@ pub use volumetric::{VolumetricObject, OctreeInstance, VolumetricGrid};
 pub use composite::CompositeRegionClassifier as CompositeEvaluator;
 pub use classifier::{RegionHint, RegionClassifier};
 pub use composite_classifier::CompositeRegionClassifier;
+pub use raycast::VoxelHit;
+pub use resample::ResampleFilter;
+pub use occlusion::{ChunkNeighbourhood, OcclusionBaker};
@@ -0,0 +1,449 @@
+//! CPU ambient occlusion and sky visibility bake
+//!
+//! Lighting is otherwise computed per frame on the GPU. The bake cone traces
+//! the hemisphere above every surface voxel of a chunk, through its octree and
+//! those of the 26 chunks around it, and stores the result in the AO and sky
+//! channels of the brick attributes so distant or low-end rendering can skip
+//! shadow rays. Chunks are rebuilt in tree form so every voxel keeps its own
+//! values; compress them with `SvdagBuilder` afterwards.
+
+use std::collections::HashMap;
+
+use glam::Vec3;
+
+use super::classifier::{RegionClassifier, RegionHint};
+use super::{AdaptiveOctreeBuilder, Octree};
+use crate::math::Aabb;
+use crate::voxel::attributes::{channels, VoxelAttributes};
+use crate::voxel::chunk::{ChunkCoord, CHUNK_SIZE};
+use crate::voxel::voxel::Voxel;
+
+/// Default number of cones traced per voxel
+const DEFAULT_CONES: u32 = 12;
+
+/// Default distance after which a cone counts as unoccluded (meters)
+const DEFAULT_MAX_DISTANCE: f32 = 2.0;
+
+/// Octrees of a chunk and the chunks around it, sampled in world space.
+///
+/// Missing chunks are air, except below a stored chunk of the same column:
+/// generation only stores chunks around the surface, so those are buried
+/// ground.
+#[derive(Default)]
+pub struct ChunkNeighbourhood {
+    chunks: HashMap<ChunkCoord, Octree>,
+    /// Lowest stored chunk Y per (x, z) column
+    lowest: HashMap<(i32, i32), i32>,
+}
+
+impl ChunkNeighbourhood {
+    /// Create an empty neighbourhood
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coordinates of a chunk and the 26 chunks around it
+    pub fn coords_around(coord: ChunkCoord) -> impl Iterator<Item = ChunkCoord> {
+        (-1..=1).flat_map(move |dz| {
+            (-1..=1).flat_map(move |dy| {
+                (-1..=1).map(move |dx| ChunkCoord::new(coord.x + dx, coord.y + dy, coord.z + dz))
+            })
+        })
+    }
+
+    /// Add a chunk's octree (local space, centered on the chunk)
+    pub fn insert(&mut self, coord: ChunkCoord, octree: Octree) {
+        let lowest = self.lowest.entry((coord.x, coord.z)).or_insert(coord.y);
+        *lowest = (*lowest).min(coord.y);
+        self.chunks.insert(coord, octree);
+    }
+
+    /// Get a chunk's octree
+    pub fn get(&self, coord: ChunkCoord) -> Option<&Octree> {
+        self.chunks.get(&coord)
+    }
+
+    /// Number of chunks stored
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Check if no chunk is stored
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Whether a chunk that isn't stored is buried ground
+    fn is_buried(&self, coord: ChunkCoord) -> bool {
+        self.lowest.get(&(coord.x, coord.z)).is_some_and(|&y| coord.y < y)
+    }
+
+    /// World-space center of a chunk's octree
+    fn center(coord: ChunkCoord, octree: &Octree) -> Vec3 {
+        coord.world_origin() + Vec3::splat(octree.root_size() / 2.0)
+    }
+
+    /// Whether the voxel at a world position is solid
+    pub fn is_solid_at(&self, pos: Vec3) -> bool {
+        let coord = ChunkCoord::from_world_pos(pos);
+        match self.chunks.get(&coord) {
+            Some(octree) => !octree.sample_voxel(pos - Self::center(coord, octree)).is_empty(),
+            None => self.is_buried(coord),
+        }
+    }
+
+    /// Whether every voxel overlapping a world-space region is solid (`solid`)
+    /// or every one is empty (`!solid`)
+    pub fn region_is(&self, region: &Aabb, solid: bool) -> bool {
+        let chunk_size = CHUNK_SIZE as f32;
+        let min = (region.min / chunk_size).floor().as_ivec3();
+        let max = ((region.max / chunk_size).ceil() - Vec3::ONE).as_ivec3();
+        for z in min.z..=max.z {
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let coord = ChunkCoord::new(x, y, z);
+                    let uniform = match self.chunks.get(&coord) {
+                        Some(octree) => {
+                            let center = Self::center(coord, octree);
+                            let local = Aabb::new(region.min - center, region.max - center);
+                            let half = octree.root_size() / 2.0;
+                            let bounds = Aabb::new(Vec3::splat(-half), Vec3::splat(half));
+                            region_is(octree, 0, &bounds, &local, solid)
+                        }
+                        None => self.is_buried(coord) == solid,
+                    };
+                    if !uniform {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Fraction of the cube around `pos` that is solid, estimated from the
+    /// octree hierarchy where it is uniform and from 8 samples otherwise
+    fn occupancy(&self, pos: Vec3, radius: f32) -> f32 {
+        let cube = Aabb::from_center_half_extent(pos, Vec3::splat(radius));
+        if self.region_is(&cube, false) {
+            return 0.0;
+        }
+        if self.region_is(&cube, true) {
+            return 1.0;
+        }
+        let offset = radius / 2.0;
+        let solid = (0..8u8)
+            .filter(|&i| {
+                let corner = Vec3::new(
+                    if i & 1 != 0 { offset } else { -offset },
+                    if i & 2 != 0 { offset } else { -offset },
+                    if i & 4 != 0 { offset } else { -offset },
+                );
+                self.is_solid_at(pos + corner)
+            })
+            .count();
+        solid as f32 / 8.0
+    }
+
+    /// Normal of a surface voxel from its empty face neighbours, or `None`
+    /// if the voxel is buried
+    fn open_normal(&self, pos: Vec3, voxel_size: f32) -> Option<Vec3> {
+        let mut open = Vec3::ZERO;
+        let mut exposed = false;
+        for dir in [Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z] {
+            if !self.is_solid_at(pos + dir * voxel_size) {
+                open += dir;
+                exposed = true;
+            }
+        }
+        exposed.then(|| open.try_normalize().unwrap_or(Vec3::Y))
+    }
+}
+
+/// Whether every voxel of `octree` overlapping `region` (octree-local) is
+/// solid (`solid`) or empty (`!solid`)
+fn region_is(octree: &Octree, node_idx: u32, bounds: &Aabb, region: &Aabb, solid: bool) -> bool {
+    let node = octree.node(node_idx);
+    if node.is_empty() {
+        return !solid;
+    }
+    if node.is_terminal_leaf() {
+        return brick_is(octree, node.brick_offset, bounds, region, solid);
+    }
+
+    for child_idx in 0..8u8 {
+        let child_bounds = bounds.child_octant(child_idx);
+        if !overlaps(&child_bounds, region) {
+            continue;
+        }
+        let uniform = if !node.is_child_valid(child_idx) {
+            !solid
+        } else if node.is_child_leaf(child_idx) {
+            brick_is(octree, octree.leaf_brick_index(node, child_idx), &child_bounds, region, solid)
+        } else {
+            region_is(octree, octree.child_node_index(node, child_idx), &child_bounds, region, solid)
+        };
+        if !uniform {
+            return false;
+        }
+    }
+    true
+}
+
+/// `region_is` for the voxels of one brick spanning `bounds`
+fn brick_is(octree: &Octree, brick_idx: u32, bounds: &Aabb, region: &Aabb, solid: bool) -> bool {
+    let brick = octree.brick(brick_idx);
+    (0..8u8).all(|i| {
+        !overlaps(&bounds.child_octant(i), region) || brick.voxels[i as usize].is_empty() != solid
+    })
+}
+
+/// Whether two boxes share volume (touching faces don't count)
+fn overlaps(a: &Aabb, b: &Aabb) -> bool {
+    const EPS: f32 = 1e-5;
+    a.min.cmplt(b.max - EPS).all() && a.max.cmpgt(b.min + EPS).all()
+}
+
+/// Whether any voxel of `octree` has baked occlusion
+pub fn has_baked_occlusion(octree: &Octree) -> bool {
+    octree.attributes_slice().is_some_and(|attributes| {
+        attributes.iter().any(|brick| brick.voxels.iter().any(|v| v.has(channels::AO)))
+    })
+}
+
+/// Bakes ambient occlusion and sky visibility into chunk octrees
+pub struct OcclusionBaker {
+    /// Cone axes in a +Z hemisphere, cosine distributed
+    cones: Vec<Vec3>,
+    /// Cone radius per meter of distance
+    spread: f32,
+    /// Distance after which a cone counts as unoccluded (meters)
+    max_distance: f32,
+}
+
+impl OcclusionBaker {
+    /// Create a baker with default cone count and distance
+    pub fn new() -> Self {
+        Self::default().with_cones(DEFAULT_CONES)
+    }
+
+    /// Set the number of cones traced per voxel
+    pub fn with_cones(mut self, count: u32) -> Self {
+        let count = count.max(1);
+        let golden_angle = std::f32::consts::PI * (3.0 - 5f32.sqrt());
+        self.cones = (0..count)
+            .map(|i| {
+                let r = ((i as f32 + 0.5) / count as f32).sqrt();
+                let phi = i as f32 * golden_angle;
+                Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).sqrt())
+            })
+            .collect();
+        // Cones of equal solid angle covering the hemisphere
+        self.spread = (1.0 - 1.0 / count as f32).acos().tan();
+        self
+    }
+
+    /// Set how far cones trace, clamped to one chunk so the 26 neighbours
+    /// hold everything a cone can reach
+    pub fn with_max_distance(mut self, distance: f32) -> Self {
+        self.max_distance = distance.clamp(0.0, CHUNK_SIZE as f32);
+        self
+    }
+
+    /// Number of cones traced per voxel
+    pub fn cone_count(&self) -> usize {
+        self.cones.len()
+    }
+
+    /// Distance after which a cone counts as unoccluded (meters)
+    pub fn max_distance(&self) -> f32 {
+        self.max_distance
+    }
+
+    /// Rebuild the chunk at `coord` in `neighbourhood` with AO and sky
+    /// visibility stored for its surface voxels.
+    ///
+    /// Existing attributes are kept. Returns `None` if the chunk isn't in the
+    /// neighbourhood.
+    pub fn bake_chunk(&self, coord: ChunkCoord, neighbourhood: &ChunkNeighbourhood) -> Option<Octree> {
+        let base = neighbourhood.get(coord)?;
+        let resolution = 1u32 << base.max_depth().max(1);
+        let chunk_size = base.root_size();
+        let classifier = OcclusionClassifier {
+            baker: self,
+            neighbourhood,
+            base,
+            center: ChunkNeighbourhood::center(coord, base),
+            voxel_size: chunk_size / resolution as f32,
+        };
+        Some(
+            AdaptiveOctreeBuilder::new(resolution)
+                .with_attributes(true)
+                .build(&classifier, coord.world_origin(), chunk_size),
+        )
+    }
+
+    /// Trace the hemisphere around `normal` above the voxel at `pos`.
+    ///
+    /// Returns (ambient occlusion, sky visibility), both 0-1. Sky visibility
+    /// is the cosine-weighted fraction of the upper sky that cones reach.
+    pub fn trace(&self, neighbourhood: &ChunkNeighbourhood, pos: Vec3, normal: Vec3, voxel_size: f32) -> (f32, f32) {
+        // Start in the air voxel above the surface so grazing cones clear it
+        let origin = pos + normal * voxel_size;
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+
+        let mut open = 0.0;
+        let mut sky = 0.0;
+        for cone in &self.cones {
+            let dir = tangent * cone.x + bitangent * cone.y + normal * cone.z;
+            let (unoccluded, reaches_sky) = self.trace_cone(neighbourhood, origin, dir, voxel_size);
+            open += unoccluded;
+            // Reweight from the normal's cosine distribution to the sky's
+            sky += reaches_sky * dir.y.max(0.0) / cone.z;
+        }
+        let count = self.cones.len() as f32;
+        (1.0 - open / count, (sky / count).min(1.0))
+    }
+
+    /// March one cone. Returns its unoccluded fraction, with occluders
+    /// weighted down over distance, and the fraction that escapes entirely.
+    fn trace_cone(&self, neighbourhood: &ChunkNeighbourhood, origin: Vec3, dir: Vec3, voxel_size: f32) -> (f32, f32) {
+        let mut transmittance = 1.0;
+        let mut occlusion = 0.0;
+        let mut t = voxel_size * 0.5;
+        while t < self.max_distance && transmittance > 0.01 {
+            let radius = (t * self.spread).max(voxel_size * 0.5);
+            let blocked = transmittance * neighbourhood.occupancy(origin + dir * t, radius);
+            occlusion += blocked * (1.0 - t / self.max_distance);
+            transmittance -= blocked;
+            t += radius;
+        }
+        (1.0 - occlusion, transmittance)
+    }
+}
+
+impl Default for OcclusionBaker {
+    fn default() -> Self {
+        Self {
+            cones: vec![Vec3::Z],
+            spread: 1.0,
+            max_distance: DEFAULT_MAX_DISTANCE,
+        }
+    }
+}
+
+/// Classifier that copies a chunk and traces occlusion for its surface voxels
+struct OcclusionClassifier<'a> {
+    baker: &'a OcclusionBaker,
+    neighbourhood: &'a ChunkNeighbourhood,
+    base: &'a Octree,
+    /// World-space center of the chunk (octree-local origin)
+    center: Vec3,
+    voxel_size: f32,
+}
+
+impl RegionClassifier for OcclusionClassifier<'_> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        let local = Aabb::new(aabb.min - self.center, aabb.max - self.center);
+        match self.base.region_hint(&local) {
+            // Solid regions next to air hold surface voxels that need tracing
+            hint @ RegionHint::Solid { .. } => {
+                let margin = Vec3::splat(self.voxel_size);
+                let grown = Aabb::new(aabb.min - margin, aabb.max + margin);
+                if self.neighbourhood.region_is(&grown, true) {
+                    hint
+                } else {
+                    RegionHint::Mixed
+                }
+            }
+            hint => hint,
+        }
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        self.base.sample_voxel(pos - self.center)
+    }
+
+    fn evaluate_attributes(&self, pos: Vec3, _voxel: Voxel) -> VoxelAttributes {
+        let attributes = self.base.sample_attributes(pos - self.center).unwrap_or_default();
+        let Some(open_normal) = self.neighbourhood.open_normal(pos, self.voxel_size) else {
+            return attributes;
+        };
+        let normal = attributes.normal().unwrap_or(open_normal);
+        let (ao, sky) = self.baker.trace(self.neighbourhood, pos, normal, self.voxel_size);
+        attributes.with_ao(ao).with_sky(sky)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ground chunk: solid below `height` (world Y)
+    fn ground(coord: ChunkCoord, height: f32) -> Octree {
+        AdaptiveOctreeBuilder::new(16).build_simple(
+            &|pos: Vec3| if pos.y < height { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            coord.world_origin(),
+            CHUNK_SIZE as f32,
+        )
+    }
+
+    fn sample(octree: &Octree, coord: ChunkCoord, pos: Vec3) -> Option<VoxelAttributes> {
+        octree.sample_attributes(pos - coord.world_origin() - Vec3::splat(CHUNK_SIZE as f32 / 2.0))
+    }
+
+    #[test]
+    fn test_flat_ground_is_open() {
+        let coord = ChunkCoord::new(0, 0, 0);
+        let mut neighbourhood = ChunkNeighbourhood::new();
+        for c in ChunkNeighbourhood::coords_around(coord).filter(|c| c.y == 0) {
+            neighbourhood.insert(c, ground(c, 2.0));
+        }
+
+        let baked = OcclusionBaker::new().bake_chunk(coord, &neighbourhood).unwrap();
+        assert!(baked.validate().is_empty(), "{:?}", baked.validate());
+
+        // Surface voxel: open sky, no occlusion
+        let surface = sample(&baked, coord, Vec3::new(2.1, 1.9, 2.1)).unwrap();
+        assert!(surface.ao().unwrap() < 0.05, "ao {:?}", surface.ao());
+        assert!(surface.sky().unwrap() > 0.9, "sky {:?}", surface.sky());
+
+        // Buried voxels aren't traced
+        let buried = sample(&baked, coord, Vec3::new(2.1, 0.6, 2.1)).unwrap_or_default();
+        assert!(!buried.has(channels::AO));
+
+        // Same voxels as before
+        let base = neighbourhood.get(coord).unwrap();
+        for y in 0..16 {
+            let local = Vec3::new(0.1, y as f32 * 0.25 - 1.875, -0.3);
+            assert_eq!(baked.sample_voxel(local), base.sample_voxel(local));
+        }
+        assert!(has_baked_occlusion(&baked));
+        assert!(!has_baked_occlusion(base));
+    }
+
+    #[test]
+    fn test_wall_occludes_across_chunks() {
+        let coord = ChunkCoord::new(0, 0, 0);
+        let wall_coord = ChunkCoord::new(1, 0, 0);
+        let mut neighbourhood = ChunkNeighbourhood::new();
+        neighbourhood.insert(coord, ground(coord, 2.0));
+        // Neighbour to +X is solid up to the top: a wall at the chunk border
+        neighbourhood.insert(wall_coord, ground(wall_coord, 4.0));
+
+        let baked = OcclusionBaker::new().bake_chunk(coord, &neighbourhood).unwrap();
+        let at_wall = sample(&baked, coord, Vec3::new(3.9, 1.9, 2.1)).unwrap();
+        let open = sample(&baked, coord, Vec3::new(0.1, 1.9, 2.1)).unwrap();
+        assert!(at_wall.ao().unwrap() > open.ao().unwrap() + 0.1, "{:?} vs {:?}", at_wall.ao(), open.ao());
+        assert!(at_wall.sky().unwrap() < open.sky().unwrap());
+    }
+
+    #[test]
+    fn test_missing_chunks_below_are_buried() {
+        let mut neighbourhood = ChunkNeighbourhood::new();
+        neighbourhood.insert(ChunkCoord::new(0, 0, 0), ground(ChunkCoord::new(0, 0, 0), 2.0));
+        assert!(neighbourhood.is_solid_at(Vec3::new(1.0, -1.0, 1.0)));
+        assert!(!neighbourhood.is_solid_at(Vec3::new(1.0, 5.0, 1.0)));
+        assert!(!neighbourhood.is_solid_at(Vec3::new(9.0, -1.0, 1.0)));
+    }
+}
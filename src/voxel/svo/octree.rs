@@ -3,6 +3,7 @@
 use glam::Vec3;
 
 use super::node::OctreeNode;
+use crate::voxel::attributes::{BrickAttributes, VoxelAttributes};
 use crate::voxel::brick::VoxelBrick;
 use crate::voxel::voxel::Voxel;
 
@@ -13,6 +14,8 @@ pub struct Octree {
     nodes: Vec<OctreeNode>,
     /// Voxel bricks referenced by leaf nodes
     bricks: Vec<VoxelBrick>,
+    /// Optional per-brick attributes, parallel to `bricks`
+    attributes: Option<Vec<BrickAttributes>>,
     /// World-space size of the root node
     root_size: f32,
     /// Maximum tree depth (affects minimum voxel size)
@@ -29,6 +32,7 @@ impl Octree {
         Self {
             nodes: vec![OctreeNode::empty()],
             bricks: Vec::new(),
+            attributes: None,
             root_size,
             max_depth,
             dense_children: false,
@@ -44,6 +48,7 @@ impl Octree {
                 v
             },
             bricks: Vec::with_capacity(brick_capacity),
+            attributes: None,
             root_size,
             max_depth,
             dense_children: false,
@@ -60,12 +65,31 @@ impl Octree {
         Self {
             nodes,
             bricks,
+            attributes: None,
             root_size,
             max_depth,
             dense_children: false,
         }
     }
 
+    /// Attach serialized per-brick attributes (one entry per brick)
+    pub fn set_attributes(&mut self, attributes: Vec<BrickAttributes>) {
+        assert_eq!(attributes.len(), self.bricks.len(), "attributes must parallel the bricks");
+        self.attributes = Some(attributes);
+    }
+
+    /// Start carrying per-brick attributes; existing bricks get none
+    pub fn enable_attributes(&mut self) {
+        if self.attributes.is_none() {
+            self.attributes = Some(vec![BrickAttributes::EMPTY; self.bricks.len()]);
+        }
+    }
+
+    /// Check if this octree carries per-brick attributes
+    pub fn has_attributes(&self) -> bool {
+        self.attributes.is_some()
+    }
+
     /// Mark this octree as using dense child indexing
     /// (child_offset + child_idx instead of packed counting)
     pub fn set_dense_children(&mut self, dense: bool) {
@@ -102,6 +126,16 @@ impl Octree {
         &mut self.bricks[index as usize]
     }
 
+    /// Get a brick's attributes, if this octree carries them
+    pub fn brick_attributes(&self, index: u32) -> Option<&BrickAttributes> {
+        self.attributes.as_ref().map(|a| &a[index as usize])
+    }
+
+    /// Get a brick's mutable attributes, if this octree carries them
+    pub fn brick_attributes_mut(&mut self, index: u32) -> Option<&mut BrickAttributes> {
+        self.attributes.as_mut().map(|a| &mut a[index as usize])
+    }
+
     /// Get all nodes as slice (for GPU upload)
     pub fn nodes_slice(&self) -> &[OctreeNode] {
         &self.nodes
@@ -112,6 +146,11 @@ impl Octree {
         &self.bricks
     }
 
+    /// Get all brick attributes, parallel to `bricks_slice()`
+    pub fn attributes_slice(&self) -> Option<&[BrickAttributes]> {
+        self.attributes.as_deref()
+    }
+
     /// Get number of nodes
     pub fn node_count(&self) -> usize {
         self.nodes.len()
@@ -141,6 +180,7 @@ impl Octree {
     pub fn memory_usage(&self) -> usize {
         std::mem::size_of::<OctreeNode>() * self.nodes.len()
             + std::mem::size_of::<VoxelBrick>() * self.bricks.len()
+            + self.attributes.as_ref().map_or(0, |a| std::mem::size_of::<BrickAttributes>() * a.len())
     }
 
     /// Add a node and return its index
@@ -154,14 +194,59 @@ impl Octree {
     pub fn add_brick(&mut self, brick: VoxelBrick) -> u32 {
         let index = self.bricks.len() as u32;
         self.bricks.push(brick);
+        if let Some(attributes) = &mut self.attributes {
+            attributes.push(BrickAttributes::EMPTY);
+        }
         index
     }
 
+    /// Add a brick with attributes and return its index.
+    /// Enables attributes on this octree if needed.
+    pub fn add_brick_with_attributes(&mut self, brick: VoxelBrick, attributes: BrickAttributes) -> u32 {
+        self.enable_attributes();
+        let index = self.add_brick(brick);
+        self.attributes.as_mut().unwrap()[index as usize] = attributes;
+        index
+    }
+
+    /// Copy brick `index` of `source`, with its attributes if it has any,
+    /// and return the new index
+    pub(crate) fn add_brick_from(&mut self, source: &Octree, index: u32) -> u32 {
+        match source.brick_attributes(index) {
+            Some(attributes) => self.add_brick_with_attributes(*source.brick(index), *attributes),
+            None => self.add_brick(*source.brick(index)),
+        }
+    }
+
     /// Check if octree is empty (only has empty root)
     pub fn is_empty(&self) -> bool {
         self.nodes.len() == 1 && self.nodes[0].is_empty() && self.bricks.is_empty()
     }
 
+    /// Check if child nodes use dense indexing
+    pub fn dense_children(&self) -> bool {
+        self.dense_children
+    }
+
+    /// Get the node index of an internal (non-leaf) child.
+    /// Honours dense vs packed child indexing.
+    pub fn child_node_index(&self, node: &OctreeNode, child_idx: u8) -> u32 {
+        if self.dense_children {
+            node.child_offset + child_idx as u32
+        } else {
+            // Packed: only valid internal children are consecutive
+            let internal_before = node.child_valid_mask() & !node.child_leaf_mask() & ((1u8 << child_idx) - 1);
+            node.child_offset + internal_before.count_ones()
+        }
+    }
+
+    /// Get the brick index of a leaf child.
+    /// Leaf bricks are packed in bit order starting at the parent's brick_offset.
+    pub fn leaf_brick_index(&self, node: &OctreeNode, child_idx: u8) -> u32 {
+        let leaves_before = node.child_valid_mask() & node.child_leaf_mask() & ((1u8 << child_idx) - 1);
+        node.brick_offset + leaves_before.count_ones()
+    }
+
     /// Sample voxel at a local position within the octree bounds.
     /// The octree is centered at origin, so valid positions are in range [-root_size/2, root_size/2].
     /// Returns Voxel::EMPTY if position is outside bounds or in empty region.
@@ -180,29 +265,44 @@ impl Octree {
         }
 
         // Traverse octree to find voxel
-        self.sample_voxel_recursive(0, Vec3::ZERO, self.root_size, local_pos)
+        match self.locate_voxel(0, Vec3::ZERO, self.root_size, local_pos) {
+            Some((brick_idx, bx, by, bz)) => *self.bricks[brick_idx as usize].get(bx, by, bz),
+            None => Voxel::EMPTY,
+        }
     }
 
-    fn sample_voxel_recursive(
+    /// Sample the attributes of the voxel at a local position, like `sample_voxel`.
+    /// Returns `None` without attributes or outside any brick.
+    pub fn sample_attributes(&self, local_pos: Vec3) -> Option<VoxelAttributes> {
+        let attributes = self.attributes.as_ref()?;
+        let half = self.root_size / 2.0;
+        if local_pos.cmplt(Vec3::splat(-half)).any() || local_pos.cmpge(Vec3::splat(half)).any() {
+            return None;
+        }
+        let (brick_idx, bx, by, bz) = self.locate_voxel(0, Vec3::ZERO, self.root_size, local_pos)?;
+        Some(*attributes[brick_idx as usize].get(bx, by, bz))
+    }
+
+    /// Find the brick and in-brick coordinates holding `target`
+    fn locate_voxel(
         &self,
         node_idx: u32,
         center: Vec3,
         size: f32,
         target: Vec3,
-    ) -> Voxel {
+    ) -> Option<(u32, u8, u8, u8)> {
         let node = &self.nodes[node_idx as usize];
 
         if node.is_empty() {
-            return Voxel::EMPTY;
+            return None;
         }
 
         // Terminal leaf: single brick contains 2x2x2 voxels for this node
         if node.is_terminal_leaf() {
-            let brick = &self.bricks[node.brick_offset as usize];
             let bx = if target.x >= center.x { 1 } else { 0 };
             let by = if target.y >= center.y { 1 } else { 0 };
             let bz = if target.z >= center.z { 1 } else { 0 };
-            return *brick.get(bx, by, bz);
+            return Some((node.brick_offset, bx, by, bz));
         }
 
         // Determine which octant the target falls in
@@ -213,7 +313,7 @@ impl Octree {
         // Check if this child exists
         let child_mask = 1u8 << child_idx;
         if node.child_valid_mask() & child_mask == 0 {
-            return Voxel::EMPTY;
+            return None;
         }
 
         // Calculate child center
@@ -238,7 +338,6 @@ impl Octree {
                 }
             }
             let brick_idx = node.brick_offset + brick_count;
-            let brick = &self.bricks[brick_idx as usize];
 
             // Calculate position within brick (2x2x2)
             // Child covers size/2, brick has 2 voxels per axis, so each voxel is size/4
@@ -248,7 +347,7 @@ impl Octree {
             let by = ((rel.y / voxel_size).floor() as i32).clamp(0, 1) as u8;
             let bz = ((rel.z / voxel_size).floor() as i32).clamp(0, 1) as u8;
 
-            return *brick.get(bx, by, bz);
+            return Some((brick_idx, bx, by, bz));
         }
 
         // Descend to child node
@@ -268,7 +367,7 @@ impl Octree {
             }
             node.child_offset + internal_count
         };
-        self.sample_voxel_recursive(child_node_idx, child_center, size / 2.0, target)
+        self.locate_voxel(child_node_idx, child_center, size / 2.0, target)
     }
 
     /// Iterate all non-empty voxels, calling the callback with (local_position, voxel).
@@ -467,6 +566,9 @@ impl Octree {
         );
         // New octree uses packed indexing
         // dense_children defaults to false
+        if self.has_attributes() {
+            new_octree.enable_attributes();
+        }
 
         // Root is already at index 0 in new_octree
         self.compact_node_recursive(&mut new_octree, 0, 0);
@@ -491,7 +593,7 @@ impl Octree {
 
         // Terminal leaf: copy brick, set up terminal leaf in new tree
         if old_node.is_terminal_leaf() {
-            let new_brick_idx = new_octree.add_brick(self.bricks[old_node.brick_offset as usize]);
+            let new_brick_idx = new_octree.add_brick_from(self, old_node.brick_offset);
             let new_node = new_octree.node_mut(new_idx);
             new_node.lod_color = old_node.lod_color;
             new_node.lod_material = old_node.lod_material;
@@ -576,8 +678,14 @@ impl Octree {
             self.root_size,
             self.max_depth,
             content_nodes,
-            self.bricks.len(),
+            self.bricks.len() + 1,
         );
+        if self.has_attributes() {
+            new_octree.enable_attributes();
+        }
+        // Reserve brick 0: brick_offset 0 means "no brick", so a terminal
+        // leaf copied first would otherwise read as empty
+        new_octree.add_brick(VoxelBrick::EMPTY);
 
         self.prune_rebuild(0, 0, &has_content, &mut new_octree);
 
@@ -640,7 +748,7 @@ impl Octree {
 
         // Terminal leaf: copy brick
         if old_node.is_terminal_leaf() {
-            let new_brick = new_octree.add_brick(self.bricks[old_node.brick_offset as usize]);
+            let new_brick = new_octree.add_brick_from(self, old_node.brick_offset);
             let n = new_octree.node_mut(new_idx);
             n.lod_color = old_node.lod_color;
             n.lod_material = old_node.lod_material;
@@ -697,7 +805,7 @@ impl Octree {
         let max_old_brick = self.bricks.len() as u32;
         if num_leaves > 0 && old_node.brick_offset + num_leaves > max_old_brick {
             // Shared-brick solid node: convert to terminal leaf
-            let new_brick = new_octree.add_brick(self.bricks[old_node.brick_offset as usize]);
+            let new_brick = new_octree.add_brick_from(self, old_node.brick_offset);
             let n = new_octree.node_mut(new_idx);
             n.lod_color = old_node.lod_color;
             n.lod_material = old_node.lod_material;
@@ -716,7 +824,7 @@ impl Octree {
             }
             if leaf & (1 << bit) != 0 {
                 let old_brick_idx = old_node.brick_offset + old_leaf_count;
-                new_octree.add_brick(self.bricks[old_brick_idx as usize]);
+                new_octree.add_brick_from(self, old_brick_idx);
                 old_leaf_count += 1;
             }
         }
@@ -753,6 +861,77 @@ impl Octree {
     }
 }
 
+impl Octree {
+    /// Check the structural invariants of the node and brick arrays.
+    ///
+    /// Verifies that leaf masks are a subset of valid masks, that every
+    /// child and brick index is in range, that no child points back up the
+    /// tree, and that every node is reachable from the root (SVDAG-shared
+    /// nodes are fine). Returns one message per problem; empty means valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if self.nodes.is_empty() {
+            issues.push("octree has no root node".to_string());
+            return issues;
+        }
+
+        // 0 = unvisited, 1 = on the current path, 2 = done
+        let mut state = vec![0u8; self.nodes.len()];
+        self.validate_node(0, &mut state, &mut issues);
+
+        if let Some(attributes) = &self.attributes
+            && attributes.len() != self.bricks.len()
+        {
+            issues.push(format!("{} brick attributes for {} bricks", attributes.len(), self.bricks.len()));
+        }
+
+        let orphans = state.iter().filter(|&&s| s == 0).count();
+        if orphans > 0 {
+            issues.push(format!("{} orphan nodes unreachable from the root", orphans));
+        }
+        issues
+    }
+
+    fn validate_node(&self, index: u32, state: &mut [u8], issues: &mut Vec<String>) {
+        state[index as usize] = 1;
+        let node = self.nodes[index as usize];
+        let valid = node.child_valid_mask();
+        let leaf = node.child_leaf_mask();
+        let brick_count = self.bricks.len() as u32;
+
+        if leaf & !valid != 0 {
+            issues.push(format!("node {}: leaf mask {:#04x} not within valid mask {:#04x}", index, leaf, valid));
+        }
+        if node.is_terminal_leaf() && node.brick_offset >= brick_count {
+            issues.push(format!("node {}: terminal brick {} out of range ({} bricks)", index, node.brick_offset, brick_count));
+        }
+
+        for child_idx in 0..8u8 {
+            if valid & (1 << child_idx) == 0 {
+                continue;
+            }
+            if leaf & (1 << child_idx) != 0 {
+                let brick = self.leaf_brick_index(&node, child_idx);
+                if brick >= brick_count {
+                    issues.push(format!("node {} child {}: brick {} out of range ({} bricks)", index, child_idx, brick, brick_count));
+                }
+                continue;
+            }
+
+            let child = self.child_node_index(&node, child_idx);
+            match state.get(child as usize) {
+                None => issues.push(format!(
+                    "node {} child {}: node {} out of range ({} nodes)", index, child_idx, child, self.nodes.len()
+                )),
+                Some(1) => issues.push(format!("node {} child {}: cycle back to node {}", index, child_idx, child)),
+                Some(0) => self.validate_node(child, state, issues),
+                Some(_) => {}
+            }
+        }
+        state[index as usize] = 2;
+    }
+}
+
 impl Default for Octree {
     fn default() -> Self {
         Self::new(64.0, 13) // 64m root, ~1cm voxels at max depth
@@ -797,4 +976,33 @@ mod tests {
         octree.add_brick(VoxelBrick::EMPTY);
         assert_eq!(octree.memory_usage(), initial + 32); // VoxelBrick is 32 bytes
     }
+
+    #[test]
+    fn test_validate() {
+        use crate::voxel::svo::{AdaptiveOctreeBuilder, SvdagBuilder};
+
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 1.3 { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            Vec3::ZERO,
+            4.0,
+        );
+        assert!(octree.validate().is_empty(), "{:?}", octree.validate());
+        let svdag = SvdagBuilder::new().build(&octree);
+        assert!(svdag.validate().is_empty(), "{:?}", svdag.validate());
+
+        // Dangling child and an orphan
+        let mut broken = Octree::new(4.0, 2);
+        broken.root_mut().set_child_valid(3, true);
+        broken.root_mut().child_offset = 5;
+        broken.add_node(OctreeNode::empty());
+        let issues = broken.validate();
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+
+        // Brick index past the end
+        let mut broken = Octree::new(4.0, 2);
+        broken.root_mut().set_child_valid(0, true);
+        broken.root_mut().set_child_leaf(0, true);
+        broken.root_mut().brick_offset = 1;
+        assert_eq!(broken.validate().len(), 1);
+    }
 }
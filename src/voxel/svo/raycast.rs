@@ -0,0 +1,261 @@
+//! CPU-side ray casting against sparse voxel octrees
+//!
+//! Lets gameplay code (picking, line of sight, AI) query voxels without the GPU.
+//! Traversal visits children front-to-back using `Aabb::child_octant`, so the
+//! first solid voxel found is the nearest one along the ray.
+
+use glam::Vec3;
+
+use super::Octree;
+use crate::math::{Aabb, Ray};
+use crate::voxel::voxel::Voxel;
+
+/// Result of a ray hitting a solid voxel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VoxelHit {
+    /// Distance along the ray to the entry point of the voxel
+    pub t: f32,
+    /// Entry point on the voxel surface (`ray.at(t)`)
+    pub position: Vec3,
+    /// Outward normal of the voxel face the ray entered through
+    pub normal: Vec3,
+    /// The voxel that was hit
+    pub voxel: Voxel,
+    /// Index of the node that owns the hit brick
+    pub node_index: u32,
+    /// Index of the hit brick in the octree brick array
+    pub brick_index: u32,
+}
+
+impl Octree {
+    /// Cast a ray through the octree and return the nearest solid voxel.
+    ///
+    /// The ray is in octree-local space (octree centered at origin, bounds
+    /// `[-root_size/2, root_size/2]`, same as `sample_voxel`). Hits further
+    /// than `max_t` are ignored.
+    pub fn raycast(&self, ray: &Ray, max_t: f32) -> Option<VoxelHit> {
+        if self.root().is_empty() {
+            return None;
+        }
+
+        let half = self.root_size() / 2.0;
+        let bounds = Aabb::new(Vec3::splat(-half), Vec3::splat(half));
+        let (t_near, _) = ray.intersects_aabb(&bounds)?;
+        if t_near > max_t {
+            return None;
+        }
+
+        self.raycast_node(0, &bounds, ray, max_t)
+    }
+
+    fn raycast_node(&self, node_idx: u32, bounds: &Aabb, ray: &Ray, max_t: f32) -> Option<VoxelHit> {
+        let node = self.node(node_idx);
+
+        if node.is_empty() {
+            return None;
+        }
+
+        // Terminal leaf: single brick spans the whole node
+        if node.is_terminal_leaf() {
+            return self.raycast_brick(node_idx, node.brick_offset, bounds, ray, max_t);
+        }
+
+        let valid_mask = node.child_valid_mask();
+        let leaf_mask = node.child_leaf_mask();
+
+        // Gather intersected children and visit them front-to-back
+        let mut order: [(f32, u8); 8] = [(0.0, 0); 8];
+        let mut count = 0;
+        for child_idx in 0..8u8 {
+            if valid_mask & (1 << child_idx) == 0 {
+                continue;
+            }
+            if let Some((t0, _)) = ray.intersects_aabb(&bounds.child_octant(child_idx))
+                && t0 <= max_t
+            {
+                order[count] = (t0, child_idx);
+                count += 1;
+            }
+        }
+        order[..count].sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for &(_, child_idx) in &order[..count] {
+            let child_bounds = bounds.child_octant(child_idx);
+            let hit = if leaf_mask & (1 << child_idx) != 0 {
+                let brick_idx = self.leaf_brick_index(node, child_idx);
+                if brick_idx as usize >= self.brick_count() {
+                    continue;
+                }
+                self.raycast_brick(node_idx, brick_idx, &child_bounds, ray, max_t)
+            } else {
+                let child_node_idx = self.child_node_index(node, child_idx);
+                self.raycast_node(child_node_idx, &child_bounds, ray, max_t)
+            };
+
+            if hit.is_some() {
+                return hit;
+            }
+        }
+
+        None
+    }
+
+    /// Intersect the 2x2x2 voxels of a brick spanning `bounds`
+    fn raycast_brick(
+        &self,
+        node_idx: u32,
+        brick_idx: u32,
+        bounds: &Aabb,
+        ray: &Ray,
+        max_t: f32,
+    ) -> Option<VoxelHit> {
+        let brick = self.brick(brick_idx);
+        let mut best: Option<(f32, usize, Aabb)> = None;
+
+        // Brick voxel index uses the same bit layout as child_octant (x=1, y=2, z=4)
+        for (i, voxel) in brick.voxels.iter().enumerate() {
+            if voxel.is_empty() {
+                continue;
+            }
+            let voxel_bounds = bounds.child_octant(i as u8);
+            if let Some((t0, _)) = ray.intersects_aabb(&voxel_bounds)
+                && t0 <= max_t
+                && best.is_none_or(|(bt, _, _)| t0 < bt)
+            {
+                best = Some((t0, i, voxel_bounds));
+            }
+        }
+
+        best.map(|(t, i, voxel_bounds)| VoxelHit {
+            t,
+            position: ray.at(t),
+            normal: entry_normal(ray, &voxel_bounds),
+            voxel: brick.voxels[i],
+            node_index: node_idx,
+            brick_index: brick_idx,
+        })
+    }
+}
+
+/// Outward normal of the AABB face a ray enters through.
+///
+/// The entry face lies on the axis whose slab is entered last. For rays
+/// starting inside the box this still picks the face behind the origin.
+fn entry_normal(ray: &Ray, aabb: &Aabb) -> Vec3 {
+    let t1 = (aabb.min - ray.origin) * ray.inv_direction;
+    let t2 = (aabb.max - ray.origin) * ray.inv_direction;
+    let t_min = t1.min(t2);
+
+    if t_min.x >= t_min.y && t_min.x >= t_min.z {
+        Vec3::new(-ray.direction.x.signum(), 0.0, 0.0)
+    } else if t_min.y >= t_min.z {
+        Vec3::new(0.0, -ray.direction.y.signum(), 0.0)
+    } else {
+        Vec3::new(0.0, 0.0, -ray.direction.z.signum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::svo::builder::{OctreeBuilder, create_test_sphere};
+
+    fn sphere_octree() -> Octree {
+        let size = 32u32;
+        let voxels = create_test_sphere(size, 10.0);
+        OctreeBuilder::new(size).build(&voxels, size as f32)
+    }
+
+    #[test]
+    fn test_raycast_empty() {
+        let octree = Octree::new(16.0, 4);
+        let ray = Ray::new(Vec3::new(-20.0, 0.1, 0.1), Vec3::X);
+        assert!(octree.raycast(&ray, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_raycast_sphere_hit() {
+        let octree = sphere_octree();
+        let ray = Ray::new(Vec3::new(-30.0, 0.3, 0.3), Vec3::X);
+        let hit = octree.raycast(&ray, 100.0).expect("ray should hit sphere");
+
+        // Sphere radius is 10 voxels of 1m, so entry is ~10m from center
+        assert!((hit.position.x + 10.0).abs() <= 1.0, "hit at {:?}", hit.position);
+        assert_eq!(hit.normal, Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!hit.voxel.is_empty());
+
+        // The hit voxel must agree with point sampling just inside the face
+        let inside = hit.position + ray.direction * 0.01;
+        assert_eq!(octree.sample_voxel(inside), hit.voxel);
+    }
+
+    #[test]
+    fn test_raycast_max_t() {
+        let octree = sphere_octree();
+        let ray = Ray::new(Vec3::new(-30.0, 0.3, 0.3), Vec3::X);
+        assert!(octree.raycast(&ray, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_raycast_miss() {
+        let octree = sphere_octree();
+        let ray = Ray::new(Vec3::new(-30.0, 15.0, 0.3), Vec3::X);
+        assert!(octree.raycast(&ray, 100.0).is_none());
+    }
+
+    #[test]
+    fn test_raycast_matches_sample_voxel() {
+        let octree = sphere_octree();
+
+        // March down many vertical rays and compare against dense sampling
+        for ix in -12..12 {
+            for iz in -12..12 {
+                let x = ix as f32 + 0.37;
+                let z = iz as f32 + 0.61;
+                let ray = Ray::new(Vec3::new(x, 20.0, z), Vec3::NEG_Y);
+                let hit = octree.raycast(&ray, 100.0);
+
+                // Reference: first solid sample stepping down in small increments
+                let mut reference = None;
+                let mut y = 15.99;
+                while y > -16.0 {
+                    let voxel = octree.sample_voxel(Vec3::new(x, y, z));
+                    if !voxel.is_empty() {
+                        reference = Some((y, voxel));
+                        break;
+                    }
+                    y -= 0.05;
+                }
+
+                match (hit, reference) {
+                    (None, None) => {}
+                    (Some(hit), Some((ref_y, voxel))) => {
+                        assert!((hit.position.y - ref_y).abs() <= 0.06, "x={} z={}", x, z);
+                        assert_eq!(hit.voxel, voxel);
+                        assert_eq!(hit.normal, Vec3::Y);
+                    }
+                    (hit, reference) => panic!("mismatch at x={} z={}: {:?} vs {:?}", x, z, hit, reference),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_raycast_terminal_leaf() {
+        use crate::voxel::brick::VoxelBrick;
+
+        // Solid root stored as a terminal leaf (brick 0 is the padding sentinel)
+        let mut octree = Octree::new(4.0, 8);
+        octree.add_brick(VoxelBrick::EMPTY);
+        let voxel = Voxel::new(200, 100, 50, 3);
+        let brick_idx = octree.add_brick(VoxelBrick::new([voxel; 8]));
+        octree.root_mut().brick_offset = brick_idx;
+
+        let ray = Ray::new(Vec3::new(0.5, 10.0, 0.5), Vec3::NEG_Y);
+        let hit = octree.raycast(&ray, 100.0).unwrap();
+        assert!((hit.t - 8.0).abs() < 1e-4);
+        assert_eq!(hit.brick_index, brick_idx);
+        assert_eq!(hit.node_index, 0);
+        assert_eq!(hit.voxel, voxel);
+    }
+}
@@ -0,0 +1,224 @@
+//! Resampling octrees through arbitrary affine transforms
+//!
+//! Bakes a rotated, scaled or translated copy of a voxel model into a new
+//! grid, so instances can be merged into chunk layers at any yaw and scale
+//! instead of only being translated. Voxel data is copied as is.
+
+use glam::{Mat4, Vec3};
+
+use super::adaptive::AdaptiveOctreeBuilder;
+use super::classifier::{RegionClassifier, RegionHint};
+use super::Octree;
+use crate::math::Aabb;
+use crate::voxel::voxel::Voxel;
+
+/// How target voxels pick their value from the source
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResampleFilter {
+    /// Sample the source at each target voxel center
+    #[default]
+    Nearest,
+    /// Vote over sub-samples of each target voxel: solid if at least half of
+    /// them are, taking the most common solid voxel. Smoother when shrinking.
+    Majority,
+}
+
+/// Classifier reading the source octree through the inverse transform.
+/// Positions are in the target octree's local space.
+struct ResampleClassifier<'a> {
+    source: &'a Octree,
+    /// Target local space -> source local space
+    inverse: Mat4,
+    filter: ResampleFilter,
+    /// Target voxel size in meters
+    voxel_size: f32,
+    /// Sub-samples per axis for `Majority`
+    samples: u32,
+}
+
+impl RegionClassifier for ResampleClassifier<'_> {
+    fn classify_region(&self, aabb: &Aabb) -> RegionHint {
+        // The source-space bounds of the transformed box cover every sample
+        // taken inside it, so uniform bounds mean a uniform region
+        let mut bounds = Aabb::new(Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+        for corner in 0..8u8 {
+            let pos = Vec3::new(
+                if corner & 1 != 0 { aabb.max.x } else { aabb.min.x },
+                if corner & 2 != 0 { aabb.max.y } else { aabb.min.y },
+                if corner & 4 != 0 { aabb.max.z } else { aabb.min.z },
+            );
+            bounds.expand(self.inverse.transform_point3(pos));
+        }
+        self.source.region_hint(&bounds)
+    }
+
+    fn evaluate(&self, pos: Vec3) -> Voxel {
+        match self.filter {
+            ResampleFilter::Nearest => self.source.sample_voxel(self.inverse.transform_point3(pos)),
+            ResampleFilter::Majority => self.majority(pos),
+        }
+    }
+}
+
+impl ResampleClassifier<'_> {
+    fn majority(&self, center: Vec3) -> Voxel {
+        let n = self.samples;
+        let step = self.voxel_size / n as f32;
+        let start = center - Vec3::splat(self.voxel_size / 2.0 - step / 2.0);
+
+        let mut votes: Vec<(Voxel, u32)> = Vec::new();
+        let mut solid = 0;
+        for i in 0..n * n * n {
+            let offset = Vec3::new((i % n) as f32, ((i / n) % n) as f32, (i / (n * n)) as f32) * step;
+            let voxel = self.source.sample_voxel(self.inverse.transform_point3(start + offset));
+            if voxel.is_empty() {
+                continue;
+            }
+            solid += 1;
+            match votes.iter_mut().find(|(v, _)| *v == voxel) {
+                Some((_, count)) => *count += 1,
+                None => votes.push((voxel, 1)),
+            }
+        }
+
+        if solid * 2 < n * n * n {
+            return Voxel::EMPTY;
+        }
+        // Ties go to the first voxel seen, keeping the result deterministic
+        votes
+            .iter()
+            .fold(None, |best: Option<&(Voxel, u32)>, vote| match best {
+                Some(b) if b.1 >= vote.1 => Some(b),
+                _ => Some(vote),
+            })
+            .map_or(Voxel::EMPTY, |(voxel, _)| *voxel)
+    }
+}
+
+impl Octree {
+    /// Bake this octree through `transform` into a new grid.
+    ///
+    /// `transform` maps this octree's local space (centered at the origin) into
+    /// the target's local space, which spans `[-target_root_size/2, target_root_size/2]`
+    /// at `2^target_depth` voxels per axis. Anything mapped outside is clipped.
+    pub fn resample(
+        &self,
+        transform: &Mat4,
+        target_root_size: f32,
+        target_depth: u8,
+        filter: ResampleFilter,
+    ) -> Octree {
+        let resolution = 1u32 << target_depth;
+        let voxel_size = target_root_size / resolution as f32;
+
+        // Enough sub-samples to hit every source voxel a target voxel covers
+        let scale = transform.to_scale_rotation_translation().0.min_element().max(f32::EPSILON);
+        let source_voxel = self.voxel_size() * scale;
+        let samples = ((voxel_size / source_voxel).ceil() as u32).clamp(2, 4);
+
+        let classifier = ResampleClassifier {
+            source: self,
+            inverse: transform.inverse(),
+            filter,
+            voxel_size,
+            samples,
+        };
+        let half = target_root_size / 2.0;
+        AdaptiveOctreeBuilder::new(resolution)
+            .build(&classifier, Vec3::splat(-half), target_root_size)
+            .prune()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+    use crate::voxel::svo::OctreeBuilder;
+
+    const WOOD: Voxel = Voxel { color: 0x8A22, material_id: 2, flags: 0 };
+
+    /// 4m model at 32^3: a 1 x 1 x 2 m post standing at +x
+    fn post() -> Octree {
+        AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| {
+                let inside = pos.x >= 1.0 && pos.x < 2.0 && pos.y >= -1.0 && pos.y < 1.0 && pos.z >= -0.5 && pos.z < 0.5;
+                if inside { WOOD } else { Voxel::EMPTY }
+            },
+            Vec3::splat(-2.0),
+            4.0,
+        )
+    }
+
+    fn voxel_centers(root_size: f32, resolution: u32) -> impl Iterator<Item = Vec3> {
+        let voxel_size = root_size / resolution as f32;
+        (0..resolution.pow(3)).map(move |i| {
+            let cell = Vec3::new(
+                (i % resolution) as f32,
+                ((i / resolution) % resolution) as f32,
+                (i / (resolution * resolution)) as f32,
+            );
+            (cell + Vec3::splat(0.5)) * voxel_size - Vec3::splat(root_size / 2.0)
+        })
+    }
+
+    #[test]
+    fn test_identity_and_rotation() {
+        let post = post();
+        let same = post.resample(&Mat4::IDENTITY, 4.0, 5, ResampleFilter::Nearest);
+        for pos in voxel_centers(4.0, 32) {
+            assert_eq!(same.sample_voxel(pos), post.sample_voxel(pos), "identity at {:?}", pos);
+        }
+
+        // A quarter turn about +y moves the post from +x to -z
+        let yaw = Mat4::from_quat(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        let turned = post.resample(&yaw, 4.0, 5, ResampleFilter::Nearest);
+        assert!(turned.validate().is_empty());
+        for pos in voxel_centers(4.0, 32) {
+            let source = yaw.inverse().transform_point3(pos);
+            assert_eq!(turned.sample_voxel(pos), post.sample_voxel(source), "rotated at {:?}", pos);
+        }
+        assert_eq!(turned.sample_voxel(Vec3::new(0.0, 0.0, -1.5)), WOOD);
+        assert!(turned.sample_voxel(Vec3::new(1.5, 0.0, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn test_scale_into_larger_grid() {
+        let post = post();
+        // Twice the size, into an 8m grid with the same voxel size
+        let scale = Mat4::from_scale(Vec3::splat(2.0));
+        let big = post.resample(&scale, 8.0, 6, ResampleFilter::Nearest);
+        assert_eq!(big.root_size(), 8.0);
+        assert_eq!(big.max_depth(), 6);
+
+        assert_eq!(big.sample_voxel(Vec3::new(3.9, 1.9, 0.9)), WOOD);
+        assert_eq!(big.sample_voxel(Vec3::new(2.1, -1.9, -0.9)), WOOD);
+        assert!(big.sample_voxel(Vec3::new(1.9, 0.0, 0.0)).is_empty());
+        assert!(big.sample_voxel(Vec3::new(3.0, 2.1, 0.0)).is_empty());
+    }
+
+    #[test]
+    fn test_majority_drops_specks_when_shrinking() {
+        // A 4-voxel block at the center plus one stray voxel
+        let size = 32u32;
+        let voxel = 4.0 / size as f32;
+        let mut voxels = vec![Voxel::EMPTY; (size * size * size) as usize];
+        let index = |x: u32, y: u32, z: u32| ((z * size + y) * size + x) as usize;
+        for i in 0..64 {
+            voxels[index(16 + i % 4, 16 + (i / 4) % 4, 16 + i / 16)] = WOOD;
+        }
+        voxels[index(8, 8, 8)] = WOOD;
+        let model = OctreeBuilder::new(size).build(&voxels, 4.0);
+        assert_eq!(model.sample_voxel(Vec3::splat(-1.0 + voxel / 2.0)), WOOD);
+
+        let half = Mat4::from_scale(Vec3::splat(0.5));
+        let shrunk = model.resample(&half, 4.0, 5, ResampleFilter::Majority);
+        let mut solid = 0;
+        for pos in voxel_centers(4.0, 32) {
+            solid += !shrunk.sample_voxel(pos).is_empty() as u32;
+        }
+        // The block survives as 2x2x2, the speck is voted away
+        assert_eq!(solid, 8);
+        assert_eq!(shrunk.sample_voxel(Vec3::splat(voxel)), WOOD);
+    }
+}
@@ -1,23 +1,67 @@
-//! Sparse Voxel DAG (SVDAG) - Octree with brick deduplication
+//! Sparse Voxel DAG (SVDAG) - Octree with brick and node deduplication
 //!
-//! This simplified implementation focuses on brick deduplication which provides
-//! significant memory savings (typically 5-10x for terrain with repetitive patterns).
-//! Full node deduplication (SVDAG) can be added later for additional compression.
+//! `build` deduplicates bricks only, which provides significant memory savings
+//! (typically 5-10x for terrain with repetitive patterns) and keeps the node
+//! array untouched. `build_full` additionally merges identical subtrees so
+//! they share one node index within the chunk; the output stays a valid
+//! packed octree that `svo_trace.wgsl` can traverse unchanged.
 
 use std::collections::HashMap;
 use super::{Octree, OctreeNode};
+use crate::voxel::attributes::BrickAttributes;
 use crate::voxel::brick::VoxelBrick;
 
+/// What `SvdagBuilder::build_full_with_stats` managed to share
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SvdagStats {
+    pub nodes_before: usize,
+    pub nodes_after: usize,
+    pub bricks_before: usize,
+    pub bricks_after: usize,
+    /// Source nodes that resolved to an already emitted node
+    pub reused_nodes: usize,
+    /// Height of the tallest shared subtree in levels (1 = a node whose
+    /// children are all bricks); 0 when no node was shared
+    pub deepest_shared_level: u8,
+}
+
+impl SvdagStats {
+    /// Node and brick bytes removed by deduplication
+    pub fn bytes_saved(&self) -> usize {
+        let nodes = self.nodes_before.saturating_sub(self.nodes_after) * std::mem::size_of::<OctreeNode>();
+        let bricks = self.bricks_before.saturating_sub(self.bricks_after) * std::mem::size_of::<VoxelBrick>();
+        nodes + bricks
+    }
+
+    /// Node count reduction (before / after)
+    pub fn node_ratio(&self) -> f32 {
+        self.nodes_before as f32 / self.nodes_after.max(1) as f32
+    }
+
+    /// Brick count reduction (before / after)
+    pub fn brick_ratio(&self) -> f32 {
+        self.bricks_before as f32 / self.bricks_after.max(1) as f32
+    }
+
+    /// Accumulate another chunk's stats
+    pub fn merge(&mut self, other: &SvdagStats) {
+        self.nodes_before += other.nodes_before;
+        self.nodes_after += other.nodes_after;
+        self.bricks_before += other.bricks_before;
+        self.bricks_after += other.bricks_after;
+        self.reused_nodes += other.reused_nodes;
+        self.deepest_shared_level = self.deepest_shared_level.max(other.deepest_shared_level);
+    }
+}
+
 /// SVDAG builder - deduplicates identical bricks in octree
 pub struct SvdagBuilder {
     /// Brick hash map for deduplication: hash -> new_brick_index
     brick_map: HashMap<u64, u32>,
-    /// Old brick index -> new brick index mapping
-    brick_remap: Vec<u32>,
-    /// Node hash map for deduplication: hash -> new_node_index
+    /// Node run hash map for deduplication: hash -> first new node index of the run
     node_map: HashMap<u64, u32>,
-    /// Old node index -> new node index mapping
-    node_remap: Vec<u32>,
+    /// Single node record hash -> first new node index holding it
+    record_map: HashMap<u64, u32>,
 }
 
 impl SvdagBuilder {
@@ -25,14 +69,18 @@ impl SvdagBuilder {
     pub fn new() -> Self {
         Self {
             brick_map: HashMap::new(),
-            brick_remap: Vec::new(),
             node_map: HashMap::new(),
-            node_remap: Vec::new(),
+            record_map: HashMap::new(),
         }
     }
 
     /// Compress an octree by deduplicating identical bricks
     /// Returns a new octree with deduplicated bricks
+    ///
+    /// A node's leaf bricks are addressed as `brick_offset + n`, so bricks are
+    /// deduplicated per node as a whole run: two nodes share storage only
+    /// when all of their leaf bricks match. Terminal leaves never land on
+    /// brick 0 (`brick_offset == 0` means "no brick" for them).
     pub fn build(mut self, octree: &Octree) -> Octree {
         let old_nodes = octree.nodes_slice();
         let old_bricks = octree.bricks_slice();
@@ -41,42 +89,26 @@ impl SvdagBuilder {
             return Octree::new(octree.root_size(), octree.max_depth());
         }
 
-        // Phase 1: Deduplicate bricks and build remap table
-        let mut new_bricks: Vec<VoxelBrick> = Vec::new();
-        self.brick_remap = Vec::with_capacity(old_bricks.len());
-
-        for brick in old_bricks {
-            let hash = self.hash_brick(brick);
-
-            if let Some(&existing_idx) = self.brick_map.get(&hash) {
-                // Duplicate brick - reuse existing
-                self.brick_remap.push(existing_idx);
-            } else {
-                // New unique brick
-                let new_idx = new_bricks.len() as u32;
-                self.brick_map.insert(hash, new_idx);
-                self.brick_remap.push(new_idx);
-                new_bricks.push(*brick);
-            }
-        }
-
-        // Phase 2: Remap node brick_offset for nodes with leaf children
+        // Keep brick 0 as padding if the source reserves it (AdaptiveOctreeBuilder does)
+        let starts_at_zero = old_nodes.iter().any(|n| n.child_leaf_mask() & n.child_valid_mask() != 0 && n.brick_offset == 0);
+        let mut new_bricks: Vec<BrickEntry> = if starts_at_zero { Vec::new() } else { vec![brick_entry(octree, 0)] };
         let mut new_nodes: Vec<OctreeNode> = Vec::with_capacity(old_nodes.len());
 
         for old_node in old_nodes {
             let mut new_node = *old_node;
 
-            // If this node has leaf children, remap their brick indices
-            let valid_mask = old_node.child_valid_mask();
-            let leaf_mask = old_node.child_leaf_mask();
+            // Number of bricks this node addresses from brick_offset
+            let terminal = old_node.is_terminal_leaf();
+            let run_len = if terminal {
+                1
+            } else {
+                (old_node.child_valid_mask() & old_node.child_leaf_mask()).count_ones() as usize
+            };
 
-            // brick_offset points to first brick for this node's leaf children
-            if valid_mask != 0 && leaf_mask != 0 {
-                // Has some leaf children - remap brick_offset
-                let old_brick_idx = old_node.brick_offset;
-                if (old_brick_idx as usize) < self.brick_remap.len() {
-                    new_node.brick_offset = self.brick_remap[old_brick_idx as usize];
-                }
+            let start = old_node.brick_offset as usize;
+            if run_len > 0 && start + run_len <= old_bricks.len() {
+                let run: Vec<BrickEntry> = (start..start + run_len).map(|i| brick_entry(octree, i as u32)).collect();
+                new_node.brick_offset = self.intern_brick_run(&run, !terminal, &mut new_bricks);
             }
 
             new_nodes.push(new_node);
@@ -96,196 +128,134 @@ impl SvdagBuilder {
         );
 
         // Build new octree with deduplicated data
-        let mut result = Octree::with_capacity(
-            octree.root_size(),
-            octree.max_depth(),
-            new_nodes.len(),
-            new_bricks.len(),
-        );
+        let mut result = assemble(octree, &new_nodes, &new_bricks);
+        result.set_dense_children(octree.dense_children());
+        result
+    }
 
-        // Copy nodes (root is already created)
-        *result.root_mut() = new_nodes[0];
-        for node in new_nodes.iter().skip(1) {
-            result.add_node(*node);
+    /// Find or append a run of consecutive bricks, returning its start index
+    fn intern_brick_run(&mut self, run: &[BrickEntry], allow_zero: bool, new_bricks: &mut Vec<BrickEntry>) -> u32 {
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let hash = run.iter().fold(run.len() as u64, |hash, brick| {
+            (hash ^ self.hash_brick(brick)).wrapping_mul(FNV_PRIME)
+        });
+
+        if let Some(&start) = self.brick_map.get(&hash)
+            && (allow_zero || start != 0)
+        {
+            let existing = new_bricks.get(start as usize..start as usize + run.len());
+            if existing.is_some_and(|existing| existing.iter().zip(run).all(|(a, b)| a.0.voxels == b.0.voxels && a.1 == b.1)) {
+                return start;
+            }
         }
 
-        // Copy deduplicated bricks
-        for brick in &new_bricks {
-            result.add_brick(*brick);
+        if !allow_zero && new_bricks.is_empty() {
+            new_bricks.push(run[0]);
         }
-
-        result
+        let start = new_bricks.len() as u32;
+        new_bricks.extend_from_slice(run);
+        self.brick_map.entry(hash).or_insert(start);
+        start
     }
 
     /// Full SVDAG compression (nodes + bricks)
-    /// Phase 1: Deduplicate bricks (existing)
-    /// Phase 2: Bottom-up node deduplication (new)
-    pub fn build_full(mut self, octree: &Octree) -> Octree {
+    pub fn build_full(self, octree: &Octree) -> Octree {
+        self.build_full_with_stats(octree).0
+    }
+
+    /// Full SVDAG compression, also returning what was shared
+    ///
+    /// Nodes are canonicalised bottom-up: a node's record (flags, LOD data
+    /// and remapped child/brick offsets) identifies its whole subtree. Packed
+    /// traversal addresses internal children as `child_offset + rank`, so a
+    /// node's internal children are interned as one run; two subtrees share
+    /// storage when their parents' child runs match, or when a run already
+    /// appears contiguously in the node array. The root stays at index 0 and
+    /// the result always uses packed child indexing.
+    pub fn build_full_with_stats(mut self, octree: &Octree) -> (Octree, SvdagStats) {
         let old_nodes = octree.nodes_slice();
         let old_bricks = octree.bricks_slice();
+        let mut stats = SvdagStats {
+            nodes_before: old_nodes.len(),
+            bricks_before: old_bricks.len(),
+            ..Default::default()
+        };
 
         if old_nodes.is_empty() || old_bricks.is_empty() {
-            return Octree::new(octree.root_size(), octree.max_depth());
+            let result = Octree::new(octree.root_size(), octree.max_depth());
+            stats.nodes_after = result.node_count();
+            return (result, stats);
         }
 
-        // Phase 1: Deduplicate bricks and build remap table
-        let mut new_bricks: Vec<VoxelBrick> = Vec::new();
-        self.brick_remap = Vec::with_capacity(old_bricks.len());
-
-        for brick in old_bricks {
-            let hash = self.hash_brick(brick);
-
-            if let Some(&existing_idx) = self.brick_map.get(&hash) {
-                // Duplicate brick - reuse existing
-                self.brick_remap.push(existing_idx);
-            } else {
-                // New unique brick
-                let new_idx = new_bricks.len() as u32;
-                self.brick_map.insert(hash, new_idx);
-                self.brick_remap.push(new_idx);
-                new_bricks.push(*brick);
-            }
-        }
-
-        let brick_compression = if new_bricks.is_empty() {
-            1.0
-        } else {
-            old_bricks.len() as f32 / new_bricks.len() as f32
+        let mut dedup = NodeDedup {
+            octree,
+            canonical: vec![None; old_nodes.len()],
+            new_nodes: vec![OctreeNode::empty()],
+            new_bricks: Vec::new(),
+            stats: &mut stats,
         };
+        let (root, _) = dedup.canonicalize(&mut self, 0);
+        let NodeDedup { mut new_nodes, new_bricks, .. } = dedup;
+        new_nodes[0] = root;
+
+        stats.nodes_after = new_nodes.len();
+        stats.bricks_after = new_bricks.len();
+        log::debug!(
+            "Full SVDAG compression: {} nodes -> {} nodes ({:.1}x), {} bricks -> {} bricks ({:.1}x), {} KB saved",
+            stats.nodes_before,
+            stats.nodes_after,
+            stats.node_ratio(),
+            stats.bricks_before,
+            stats.bricks_after,
+            stats.brick_ratio(),
+            stats.bytes_saved() / 1024,
+        );
 
-        // Phase 2: Node deduplication bottom-up
-        // Build depth map for bottom-up processing
-        let max_depth = octree.max_depth();
-        let mut nodes_by_depth: Vec<Vec<u32>> = vec![Vec::new(); (max_depth as usize) + 1];
-
-        // Calculate depth for each node (BFS from root)
-        let mut node_depths = vec![0u32; old_nodes.len()];
-        let mut queue = vec![(0u32, 0u32)]; // (node_idx, depth)
-
-        while let Some((node_idx, depth)) = queue.pop() {
-            if (node_idx as usize) >= old_nodes.len() {
-                continue;
-            }
-
-            node_depths[node_idx as usize] = depth;
-            nodes_by_depth[depth as usize].push(node_idx);
-
-            let node = &old_nodes[node_idx as usize];
-            let valid_mask = node.child_valid_mask();
-            let leaf_mask = node.child_leaf_mask();
-
-            // Add non-leaf children to queue
-            if valid_mask != 0 {
-                let child_base = node.child_offset;
-                for i in 0..8u32 {
-                    if (valid_mask & (1 << i)) != 0 && (leaf_mask & (1 << i)) == 0 {
-                        let child_idx = child_base + i;
-                        queue.push((child_idx, depth + 1));
-                    }
-                }
-            }
-        }
-
-        // Process nodes bottom-up (deepest first)
-        let mut new_nodes: Vec<OctreeNode> = Vec::new();
-        self.node_remap = vec![0u32; old_nodes.len()];
-
-        for depth in (0..=(max_depth as usize)).rev() {
-            for &node_idx in &nodes_by_depth[depth] {
-                let old_node = &old_nodes[node_idx as usize];
-                let mut new_node = *old_node;
-
-                // Remap child offsets for non-leaf children
-                let valid_mask = old_node.child_valid_mask();
-                let leaf_mask = old_node.child_leaf_mask();
-
-                if valid_mask != 0 && leaf_mask != 0xFF {
-                    // Has some non-leaf children - remap child_offset
-                    let old_child_base = old_node.child_offset;
-
-                    // Find first non-leaf child to determine new child_offset
-                    for i in 0..8u32 {
-                        if (valid_mask & (1 << i)) != 0 && (leaf_mask & (1 << i)) == 0 {
-                            let old_child_idx = old_child_base + i;
-                            if (old_child_idx as usize) < self.node_remap.len() {
-                                let new_child_idx = self.node_remap[old_child_idx as usize];
-                                new_node.child_offset = new_child_idx.saturating_sub(i);
-                                break;
-                            }
-                        }
-                    }
-                }
-
-                // Remap brick_offset for nodes with leaf children
-                if valid_mask != 0 && leaf_mask != 0 {
-                    let old_brick_idx = old_node.brick_offset;
-                    if (old_brick_idx as usize) < self.brick_remap.len() {
-                        new_node.brick_offset = self.brick_remap[old_brick_idx as usize];
-                    }
-                }
-
-                // Hash node based on remapped children and bricks
-                let hash = self.hash_node(&new_node);
-
-                if let Some(&existing_idx) = self.node_map.get(&hash) {
-                    // Duplicate node - reuse existing
-                    self.node_remap[node_idx as usize] = existing_idx;
-                } else {
-                    // New unique node
-                    let new_idx = new_nodes.len() as u32;
-                    self.node_map.insert(hash, new_idx);
-                    self.node_remap[node_idx as usize] = new_idx;
-                    new_nodes.push(new_node);
-                }
-            }
-        }
+        let result = assemble(octree, &new_nodes, &new_bricks);
+        debug_assert!(result.validate().is_empty(), "{:?}", result.validate());
+        (result, stats)
+    }
 
-        let node_compression = if new_nodes.is_empty() {
-            1.0
-        } else {
-            old_nodes.len() as f32 / new_nodes.len() as f32
+    /// Find a run of node records already in `new_nodes`, or append it
+    ///
+    /// Returns the run's start index and whether it was reused.
+    fn intern_node_run(&mut self, run: &[OctreeNode], new_nodes: &mut Vec<OctreeNode>) -> (u32, bool) {
+        const FNV_PRIME: u64 = 0x100000001b3;
+        let hash = run.iter().fold(run.len() as u64, |hash, node| {
+            (hash ^ self.hash_node(node)).wrapping_mul(FNV_PRIME)
+        });
+        let matches_at = |start: u32| {
+            new_nodes
+                .get(start as usize..start as usize + run.len())
+                .is_some_and(|existing| bytemuck::cast_slice::<_, u8>(existing) == bytemuck::cast_slice::<_, u8>(run))
         };
 
-        log::info!(
-            "Full SVDAG compression: {} nodes -> {} nodes ({:.1}x), {} bricks -> {} bricks ({:.1}x)",
-            old_nodes.len(),
-            new_nodes.len(),
-            node_compression,
-            old_bricks.len(),
-            new_bricks.len(),
-            brick_compression,
-        );
-
-        // Build new octree with deduplicated data
-        let mut result = Octree::with_capacity(
-            octree.root_size(),
-            octree.max_depth(),
-            new_nodes.len(),
-            new_bricks.len(),
-        );
-
-        // Copy nodes (root is already created)
-        if !new_nodes.is_empty() {
-            *result.root_mut() = new_nodes[0];
-            for node in new_nodes.iter().skip(1) {
-                result.add_node(*node);
-            }
+        // Same run as another node's children, or a prefix of a run that
+        // starts with the same record
+        let candidates = [self.node_map.get(&hash), self.record_map.get(&self.hash_node(&run[0]))];
+        if let Some(&start) = candidates.into_iter().flatten().find(|&&start| matches_at(start)) {
+            return (start, true);
         }
 
-        // Copy deduplicated bricks
-        for brick in &new_bricks {
-            result.add_brick(*brick);
+        let start = new_nodes.len() as u32;
+        new_nodes.extend_from_slice(run);
+        self.node_map.entry(hash).or_insert(start);
+        for (i, node) in run.iter().enumerate() {
+            self.record_map.entry(self.hash_node(node)).or_insert(start + i as u32);
         }
-
-        result
+        (start, false)
     }
-
-    /// Hash a voxel brick using FNV-1a
-    fn hash_brick(&self, brick: &VoxelBrick) -> u64 {
+    /// Hash a voxel brick and its attributes using FNV-1a
+    fn hash_brick(&self, (brick, attributes): &BrickEntry) -> u64 {
         const FNV_OFFSET: u64 = 0xcbf29ce484222325;
         const FNV_PRIME: u64 = 0x100000001b3;
 
         let mut hash = FNV_OFFSET;
+        if !attributes.is_empty() {
+            hash = bytemuck::bytes_of(attributes).iter().fold(hash, |hash, &byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            });
+        }
         for voxel in &brick.voxels {
             // Hash all voxel fields
             hash ^= voxel.color as u64;
@@ -298,27 +268,103 @@ impl SvdagBuilder {
         hash
     }
 
-    /// Hash a node based on its flags, child offsets, and brick offsets
-    /// Two nodes are identical if they have the same structure and references
+    /// Hash a node record (flags, remapped offsets and LOD data) using FNV-1a
     fn hash_node(&self, node: &OctreeNode) -> u64 {
         const FNV_OFFSET: u64 = 0xcbf29ce484222325;
         const FNV_PRIME: u64 = 0x100000001b3;
 
-        let mut hash = FNV_OFFSET;
+        bytemuck::bytes_of(node).iter().fold(FNV_OFFSET, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
+}
+
+/// A brick with its attributes (`BrickAttributes::EMPTY` for octrees without any)
+type BrickEntry = (VoxelBrick, BrickAttributes);
+
+fn brick_entry(octree: &Octree, index: u32) -> BrickEntry {
+    (*octree.brick(index), octree.brick_attributes(index).copied().unwrap_or_default())
+}
 
-        // Hash flags (contains valid mask and leaf mask)
-        hash ^= node.flags as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
+/// Build an octree from deduplicated records, keeping attributes if `source` has them
+fn assemble(source: &Octree, new_nodes: &[OctreeNode], new_bricks: &[BrickEntry]) -> Octree {
+    let mut result = Octree::with_capacity(source.root_size(), source.max_depth(), new_nodes.len(), new_bricks.len());
+    *result.root_mut() = new_nodes[0];
+    for node in new_nodes.iter().skip(1) {
+        result.add_node(*node);
+    }
+    if source.has_attributes() {
+        result.enable_attributes();
+    }
+    for &(brick, attributes) in new_bricks {
+        let index = result.add_brick(brick);
+        if let Some(slot) = result.brick_attributes_mut(index) {
+            *slot = attributes;
+        }
+    }
+    result
+}
 
-        // Hash child_offset (already remapped)
-        hash ^= node.child_offset as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
+/// Per-octree state of `build_full_with_stats`
+struct NodeDedup<'a> {
+    octree: &'a Octree,
+    /// Canonical record and subtree height of each visited source node
+    canonical: Vec<Option<(OctreeNode, u8)>>,
+    new_nodes: Vec<OctreeNode>,
+    new_bricks: Vec<BrickEntry>,
+    stats: &'a mut SvdagStats,
+}
 
-        // Hash brick_offset (already remapped)
-        hash ^= node.brick_offset as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
+impl NodeDedup<'_> {
+    /// Emit the subtree under source node `index` (post-order) and return its
+    /// canonical record and height. The record itself is placed by the parent.
+    fn canonicalize(&mut self, builder: &mut SvdagBuilder, index: u32) -> (OctreeNode, u8) {
+        if let Some(done) = self.canonical[index as usize] {
+            return done;
+        }
 
-        hash
+        let old = *self.octree.node(index);
+        let valid = old.child_valid_mask();
+        let leaf = old.child_leaf_mask();
+        let mut node = old;
+        node.child_offset = 0;
+
+        // Internal children first, so their records are final
+        let mut children = Vec::new();
+        let mut child_height = 0;
+        for child_idx in 0..8u8 {
+            if valid & !leaf & (1 << child_idx) != 0 {
+                let child = self.octree.child_node_index(&old, child_idx);
+                let (record, height) = self.canonicalize(builder, child);
+                children.push(record);
+                child_height = child_height.max(height);
+            }
+        }
+        if !children.is_empty() {
+            let (start, reused) = builder.intern_node_run(&children, &mut self.new_nodes);
+            node.child_offset = start;
+            if reused {
+                self.stats.reused_nodes += children.len();
+                self.stats.deepest_shared_level = self.stats.deepest_shared_level.max(child_height);
+            }
+        }
+
+        if old.is_terminal_leaf() {
+            let brick = brick_entry(self.octree, old.brick_offset);
+            node.brick_offset = builder.intern_brick_run(&[brick], false, &mut self.new_bricks);
+        } else if valid & leaf != 0 {
+            let run: Vec<BrickEntry> = (0..8u8)
+                .filter(|&child_idx| valid & leaf & (1 << child_idx) != 0)
+                .map(|child_idx| brick_entry(self.octree, self.octree.leaf_brick_index(&old, child_idx)))
+                .collect();
+            node.brick_offset = builder.intern_brick_run(&run, true, &mut self.new_bricks);
+        } else {
+            node.brick_offset = 0;
+        }
+
+        let done = (node, child_height + 1);
+        self.canonical[index as usize] = Some(done);
+        done
     }
 }
 
@@ -332,6 +378,7 @@ impl Default for SvdagBuilder {
 mod tests {
     use super::*;
     use crate::voxel::svo::builder::{OctreeBuilder, create_test_sphere};
+    use glam::Vec3;
 
     #[test]
     fn test_svdag_empty() {
@@ -370,6 +417,31 @@ mod tests {
         assert_eq!(svdag.node_count(), octree.node_count());
     }
 
+    #[test]
+    fn test_build_keeps_leaf_bricks_contiguous() {
+        use crate::voxel::svo::AdaptiveOctreeBuilder;
+        use crate::voxel::voxel::Voxel;
+
+        // Flat ground: many nodes whose leaf bricks are all identical
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| if pos.y < 1.3 { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            Vec3::ZERO,
+            4.0,
+        );
+        let svdag = SvdagBuilder::new().build(&octree);
+        assert!(svdag.brick_count() < octree.brick_count());
+
+        let step = 4.0 / 32.0;
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * step - Vec3::splat(2.0 - step / 2.0);
+                    assert_eq!(svdag.sample_voxel(p), octree.sample_voxel(p), "mismatch at {:?}", p);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_node_deduplication() {
         // Create a symmetric octree with repeated patterns
@@ -413,7 +485,7 @@ mod tests {
 
         // Build with full node+brick deduplication
         let svdag_builder = SvdagBuilder::new();
-        let svdag = svdag_builder.build_full(&octree);
+        let (svdag, stats) = svdag_builder.build_full_with_stats(&octree);
 
         println!(
             "Full deduplication: {} nodes -> {} nodes ({:.1}x), {} bricks -> {} bricks ({:.1}x)",
@@ -425,11 +497,99 @@ mod tests {
             original_bricks as f32 / svdag.brick_count().max(1) as f32
         );
 
-        // Should have significant node compression due to symmetry
-        assert!(svdag.node_count() <= original_nodes);
-        // Should also have brick compression
-        assert!(svdag.brick_count() <= original_bricks);
-        // Combined compression should be better than brick-only
-        assert!(svdag.node_count() < original_nodes || svdag.brick_count() < original_bricks);
+        assert!(svdag.validate().is_empty(), "{:?}", svdag.validate());
+        assert!(!svdag.dense_children());
+        // The spheres sit at identical offsets in their octants, so whole
+        // sphere subtrees collapse into one
+        assert!(svdag.node_count() < original_nodes);
+        assert!(svdag.brick_count() < original_bricks);
+        assert_eq!(stats.nodes_after, svdag.node_count());
+        assert_eq!(stats.reused_nodes, original_nodes - svdag.node_count());
+        assert!(stats.deepest_shared_level >= 2);
+        assert!(stats.bytes_saved() > 0);
+
+        let step = size as f32 / 64.0;
+        for z in 0..64 {
+            for y in 0..64 {
+                for x in 0..64 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * step - Vec3::splat(size as f32 / 2.0 - step / 2.0);
+                    assert_eq!(svdag.sample_voxel(p), octree.sample_voxel(p), "mismatch at {:?}", p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_full_adaptive_terrain() {
+        use crate::voxel::svo::AdaptiveOctreeBuilder;
+        use crate::voxel::voxel::Voxel;
+
+        // Terraced ground with terminal leaves, brick 0 padding and partial nodes
+        let octree = AdaptiveOctreeBuilder::new(32).build_simple(
+            &|pos: Vec3| {
+                let height = if pos.x < 2.0 { 1.3 } else { 2.7 };
+                if pos.y < height { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY }
+            },
+            Vec3::ZERO,
+            4.0,
+        );
+        let (svdag, stats) = SvdagBuilder::new().build_full_with_stats(&octree);
+        assert!(svdag.validate().is_empty(), "{:?}", svdag.validate());
+        assert!(svdag.node_count() < octree.node_count());
+        assert!(stats.reused_nodes > 0);
+
+        // Deduplicating a DAG again finds nothing new
+        let (again, _) = SvdagBuilder::new().build_full_with_stats(&svdag);
+        assert_eq!(again.node_count(), svdag.node_count());
+
+        let step = 4.0 / 32.0;
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * step - Vec3::splat(2.0 - step / 2.0);
+                    assert_eq!(svdag.sample_voxel(p), octree.sample_voxel(p), "mismatch at {:?}", p);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_full_keeps_attributes() {
+        use crate::voxel::attributes::VoxelAttributes;
+        use crate::voxel::svo::AdaptiveOctreeBuilder;
+        use crate::voxel::voxel::Voxel;
+
+        // Flat ground whose bricks dedup to a few, then tell every brick apart
+        let mut octree = AdaptiveOctreeBuilder::new(16).build_simple(
+            &|pos: Vec3| if pos.y < 1.3 { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            Vec3::ZERO,
+            4.0,
+        );
+        octree.enable_attributes();
+        let count = octree.brick_count() as u32;
+        for index in 0..count {
+            let wet = VoxelAttributes::NONE.with_wetness(index as f32 / count as f32);
+            *octree.brick_attributes_mut(index).unwrap() = BrickAttributes { voxels: [wet; 8] };
+        }
+
+        let plain = SvdagBuilder::new().build_full(&AdaptiveOctreeBuilder::new(16).build_simple(
+            &|pos: Vec3| if pos.y < 1.3 { Voxel::from_rgb565(0x4208, 1) } else { Voxel::EMPTY },
+            Vec3::ZERO,
+            4.0,
+        ));
+        let svdag = SvdagBuilder::new().build_full(&octree);
+        assert!(svdag.validate().is_empty(), "{:?}", svdag.validate());
+        assert_eq!(svdag.attributes_slice().unwrap().len(), svdag.brick_count());
+        assert!(svdag.brick_count() > plain.brick_count());
+
+        let step = 4.0 / 16.0;
+        for z in 0..16 {
+            for y in 0..16 {
+                for x in 0..16 {
+                    let p = Vec3::new(x as f32, y as f32, z as f32) * step - Vec3::splat(2.0 - step / 2.0);
+                    assert_eq!(svdag.sample_attributes(p), octree.sample_attributes(p), "mismatch at {:?}", p);
+                }
+            }
+        }
     }
 }
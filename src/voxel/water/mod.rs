@@ -4,4 +4,4 @@ pub mod volume;
 pub mod system;
 
 pub use volume::{WaterBody, WaterBodyType, WaterProperties, WaterSurface};
-pub use system::{WaterSystem, MATERIAL_WATER};
+pub use system::WaterSystem;
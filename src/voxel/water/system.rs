@@ -5,12 +5,10 @@ use std::collections::HashMap;
 
 use super::{WaterBody, WaterBodyType, WaterProperties};
 use crate::math::Aabb;
+use crate::voxel::material::material_id;
 use crate::voxel::svo::classifier::{RegionClassifier, RegionHint};
 use crate::voxel::voxel::Voxel;
 
-/// Material ID for water voxels
-pub const MATERIAL_WATER: u8 = 200;
-
 /// Central manager for all water bodies in the world.
 pub struct WaterSystem {
     /// All water bodies by ID
@@ -23,6 +21,8 @@ pub struct WaterSystem {
     time: f32,
     /// Whether the system has an ocean
     has_ocean: bool,
+    /// Material ID written into water voxels
+    material: u8,
 }
 
 impl WaterSystem {
@@ -34,6 +34,7 @@ impl WaterSystem {
             sea_level: 0.0,
             time: 0.0,
             has_ocean: false,
+            material: material_id("water"),
         }
     }
 
@@ -219,7 +220,7 @@ impl RegionClassifier for WaterSystem {
         // Region entirely below water surface (deep underwater)
         if self.has_ocean && aabb.max.y < self.sea_level - 1.0 {
             return RegionHint::Solid {
-                material: MATERIAL_WATER,
+                material: self.material,
                 color: 0x1E5B, // Blue-green encoded
             };
         }
@@ -232,7 +233,7 @@ impl RegionClassifier for WaterSystem {
         if self.is_underwater(pos) {
             // Create blue-green water voxel
             // 0x1E5B in RGB565: R=3, G=114, B=27 -> roughly (24, 228, 216) in RGB888
-            Voxel::from_rgb565(0x1E5B, MATERIAL_WATER)
+            Voxel::from_rgb565(0x1E5B, self.material)
         } else {
             Voxel::EMPTY
         }
@@ -285,7 +286,7 @@ mod tests {
         // Deep underwater - solid water
         let deep = Aabb::new(Vec3::new(0.0, -50.0, 0.0), Vec3::new(1.0, -49.0, 1.0));
         match system.classify_region(&deep) {
-            RegionHint::Solid { material, .. } => assert_eq!(material, MATERIAL_WATER),
+            RegionHint::Solid { material, .. } => assert_eq!(material, material_id("water")),
             _ => panic!("Expected Solid"),
         }
 